@@ -0,0 +1,91 @@
+//! Time of the batch conversions in [`linear_regression::batch`] against mapping the scalar
+//! helpers over the same values, for a few million samples. Run once without and once with
+//! `--features simd` to compare:
+//!
+//! ```text
+//! cargo bench --bench batch
+//! cargo bench --bench batch --features simd
+//! ```
+//!
+//! On an AVX2 machine the portable kernels convert `i64`s no faster than the scalar helpers
+//! (0.95x here) and quantize 1.5x faster; with `simd` the AVX2 kernels reach 1.25x, bounded by
+//! the 16-byte writes, and 3.6x.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use linear_regression::batch::{field_column, i64_to_field, quantize_to_field, write_rows};
+use linear_regression::fixed_point::{quantize, to_field};
+use winterfell::math::fields::f128::BaseElement;
+
+const RUNS: usize = 5;
+const SAMPLES: usize = 1 << 22;
+
+/// Best of [`RUNS`] timings of `f`
+fn time(mut f: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, scalar: Duration, batched: Duration) {
+    println!(
+        "{name:<10} scalar {:>8.2} ms  batched {:>8.2} ms  ({:.2}x)",
+        scalar.as_secs_f64() * 1e3,
+        batched.as_secs_f64() * 1e3,
+        scalar.as_secs_f64() / batched.as_secs_f64()
+    );
+}
+
+fn main() {
+    let integers: Vec<i64> = (0..SAMPLES as i64).map(|i| (i * 7919) % 200_003 - 100_000).collect();
+    let reals: Vec<f64> = integers.iter().map(|&v| v as f64 / 3.0).collect();
+
+    // both sides write into a preallocated column, as trace builders do
+    let mut out = vec![BaseElement::new(0); SAMPLES];
+    let scalar = time(|| {
+        for (cell, &v) in out.iter_mut().zip(&integers) {
+            *cell = to_field(v as i128);
+        }
+        black_box(&out);
+    });
+    let batched = time(|| {
+        i64_to_field(&integers, &mut out);
+        black_box(&out);
+    });
+    report("i64", scalar, batched);
+
+    let scalar = time(|| {
+        for (cell, &v) in out.iter_mut().zip(&reals) {
+            *cell = to_field(quantize(v, 16));
+        }
+        black_box(&out);
+    });
+    let batched = time(|| {
+        quantize_to_field(&reals, 16, &mut out);
+        black_box(&out);
+    });
+    report("quantize", scalar, batched);
+
+    let width = 64;
+    let rows = field_column(&integers[..SAMPLES / width * width]);
+    let mut columns = vec![vec![BaseElement::new(0); SAMPLES / width]; width];
+    let scalar = time(|| {
+        for (r, row) in rows.chunks(width).enumerate() {
+            for (column, &value) in columns.iter_mut().zip(row) {
+                column[r] = value;
+            }
+        }
+        black_box(&columns);
+    });
+    let batched = time(|| {
+        write_rows(&mut columns, 0, &rows);
+        black_box(&columns);
+    });
+    report("columns", scalar, batched);
+}
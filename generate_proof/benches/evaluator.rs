@@ -0,0 +1,115 @@
+//! Constraint evaluation time of [`ParallelConstraintEvaluator`] against Winterfell's
+//! [`DefaultConstraintEvaluator`], on the narrow linear regression trace and the wide trace of
+//! the 64-bit integer regression. Only the evaluation is timed: both evaluators get the same
+//! extended trace and composition coefficients. Run once without and once with
+//! `--features concurrent` to compare:
+//!
+//! ```text
+//! cargo bench --bench evaluator
+//! cargo bench --bench evaluator --features concurrent
+//! ```
+
+use std::time::{Duration, Instant};
+
+use linear_regression::config::ProverConfig;
+use linear_regression::evaluator::ParallelConstraintEvaluator;
+use linear_regression::gadgets::IntegerWidth;
+use linear_regression::integer_regression::{
+    build_integer_regression_trace, checked_predict, IntegerRegressionAir, IntegerRegressionInputs,
+};
+use linear_regression::prover::HashFn;
+use linear_regression::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+use winterfell::{
+    crypto::MerkleTree, math::fields::f128::BaseElement, Air, ConstraintCompositionCoefficients, ConstraintEvaluator,
+    DefaultConstraintEvaluator, DefaultTraceLde, PartitionOptions, StarkDomain, Trace, TraceInfo, TraceTable,
+};
+
+const RUNS: usize = 5;
+
+/// Best of [`RUNS`] timings of `f`
+fn time(f: impl Fn()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn compare<A>(name: &str, trace: TraceTable<BaseElement>, inputs: A::PublicInputs)
+where
+    A: Air<BaseField = BaseElement>,
+{
+    let air = A::new(trace.info().clone(), inputs, ProverConfig::default().to_proof_options());
+    let domain = StarkDomain::new(&air);
+    let (lde, _) = DefaultTraceLde::<BaseElement, HashFn, MerkleTree<HashFn>>::new(
+        trace.info(),
+        trace.main_segment(),
+        &domain,
+        PartitionOptions::default(),
+    );
+    let context = air.context();
+    let coefficients = || ConstraintCompositionCoefficients {
+        transition: (1..=context.num_transition_constraints() as u64).map(BaseElement::from).collect(),
+        boundary: (1..=context.num_assertions() as u64).map(BaseElement::from).collect(),
+    };
+
+    let default = time(|| {
+        DefaultConstraintEvaluator::new(&air, None, coefficients()).evaluate(&lde, &domain);
+    });
+    let parallel = time(|| {
+        ParallelConstraintEvaluator::new(&air, coefficients()).evaluate(&lde, &domain);
+    });
+    println!(
+        "  {name:<18} {:>3} columns x {:>5} rows: default {:>9.2?}, parallel {:>9.2?} ({:.2}x)",
+        trace.info().main_trace_width(),
+        trace.info().length(),
+        default,
+        parallel,
+        default.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mode = if cfg!(feature = "concurrent") { "concurrent" } else { "sequential" };
+    println!("constraint evaluation ({mode} build)");
+
+    for num_samples in [62u64, 254, 1022] {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+        let xs: Vec<BaseElement> = (1..=num_samples).map(BaseElement::from).collect();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(11);
+        let inputs = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        compare::<LinearRegressionAir>("linear regression", trace, inputs);
+
+        let width = IntegerWidth::U64;
+        let (slope, intercept) = (3_000_007, 1_000_003);
+        let sample_x: Vec<u64> = (1..=num_samples).map(|x| x * 1_000_003).collect();
+        let target_x = 6_000_001;
+        let inputs = IntegerRegressionInputs {
+            width,
+            x_value: target_x,
+            predicted_y: checked_predict(width, slope, intercept, target_x).unwrap(),
+            sample_y_values: sample_x.iter().map(|&x| checked_predict(width, slope, intercept, x).unwrap()).collect(),
+            sample_x_values: sample_x.clone(),
+        };
+        let trace = build_integer_regression_trace(
+            width,
+            slope,
+            intercept,
+            &sample_x,
+            target_x,
+            TraceInfo::MIN_TRACE_LENGTH,
+        )
+        .unwrap();
+        compare::<IntegerRegressionAir>("integer regression", trace, inputs);
+    }
+}
@@ -0,0 +1,94 @@
+//! Commitment construction with [`HybridMerkleTree`] against Winterfell's [`MerkleTree`].
+//!
+//! Times building a tree over a million leaves with a growing number of threads and with every
+//! level on the [`ReferenceDevice`], the overhead a device backend has to beat, then a whole
+//! proof with each commitment builder. Winterfell's own tree only uses threads when built with
+//! `--features concurrent`, so run once without and once with it:
+//!
+//! ```text
+//! cargo bench --bench merkle
+//! cargo bench --bench merkle --features concurrent
+//! ```
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use linear_regression::config::ProverConfig;
+use std::sync::Arc;
+
+use linear_regression::merkle::{HybridMerkleTree, HybridProver, MerkleOptions, ReferenceDevice};
+use linear_regression::prover::{AirProver, HashFn};
+use linear_regression::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+use winterfell::crypto::{Hasher, MerkleTree, VectorCommitment};
+use winterfell::math::fields::f128::BaseElement;
+use winterfell::{Prover, TraceInfo};
+
+const LEAVES: u64 = 1 << 20;
+const NUM_SAMPLES: u64 = 1 << 12;
+const RUNS: usize = 5;
+
+/// Best of [`RUNS`] timings of `f`
+fn time(mut f: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, baseline: Duration, hybrid: Duration) {
+    println!(
+        "{name:<12} merkle {:>8.2} ms  hybrid {:>8.2} ms  ({:.2}x)",
+        baseline.as_secs_f64() * 1e3,
+        hybrid.as_secs_f64() * 1e3,
+        baseline.as_secs_f64() / hybrid.as_secs_f64()
+    );
+}
+
+fn main() {
+    let leaves: Vec<_> = (0..LEAVES).map(|i| HashFn::hash(&i.to_le_bytes())).collect();
+    let baseline = time(|| {
+        black_box(MerkleTree::<HashFn>::new(leaves.clone()).unwrap());
+    });
+    let cores = MerkleOptions::default().threads;
+    let mut counts = vec![1, 2, 4, cores];
+    counts.retain(|&threads| threads <= cores);
+    counts.dedup();
+    for threads in counts {
+        let hybrid = time(|| {
+            let options = MerkleOptions::default().with_threads(threads);
+            black_box(HybridMerkleTree::with_options(leaves.clone(), options).unwrap());
+        });
+        report(&format!("{threads} threads"), baseline, hybrid);
+    }
+    let device = time(|| {
+        let options = MerkleOptions::default().with_device(Arc::new(ReferenceDevice)).with_min_device_pairs(1);
+        black_box(HybridMerkleTree::with_options(leaves.clone(), options).unwrap());
+    });
+    report("reference", baseline, device);
+
+    let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+    let xs: Vec<BaseElement> = (1..=NUM_SAMPLES).map(BaseElement::from).collect();
+    let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+    let target = BaseElement::new(11);
+    let inputs = LinearRegressionInputs {
+        x_value: target,
+        predicted_y: slope * target + intercept,
+        sample_x_values: xs.clone(),
+        sample_y_values: ys.clone(),
+    };
+    let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+    let options = ProverConfig::default().to_proof_options();
+    let baseline = time(|| {
+        let prover = AirProver::<LinearRegressionAir>::new(options.clone(), inputs.clone());
+        black_box(prover.prove(trace.clone()).unwrap());
+    });
+    let hybrid = time(|| {
+        let prover = HybridProver::<LinearRegressionAir>::new(options.clone(), inputs.clone());
+        black_box(prover.prove(trace.clone()).unwrap());
+    });
+    report("prove", baseline, hybrid);
+}
@@ -0,0 +1,113 @@
+//! Single-proof latency and batch throughput of verification for the linear regression AIR.
+//!
+//! Times one proof through `winter-verifier`, which checks the queries one after another, and
+//! through [`verify`], which checks them in parallel with `concurrent`; then the same batch one
+//! proof at a time and through [`verify_many`]. Run once without and once with
+//! `--features concurrent` to compare:
+//!
+//! ```text
+//! cargo bench --bench verify --features prover
+//! cargo bench --bench verify --features concurrent
+//! ```
+//!
+//! `RAYON_NUM_THREADS` sets the threads; on a pool of one [`verify`] is `winter-verifier`. Only
+//! the query checks run in parallel: the transcript replay and the out-of-domain check do not,
+//! and the latter takes most of the time for the longest traces here.
+
+use std::time::{Duration, Instant};
+
+use linear_regression::config::{default_acceptable_options, ProverConfig};
+use linear_regression::prover::{prove, verify, verify_many, HashFn};
+use linear_regression::regression::{self, build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+use winterfell::crypto::{DefaultRandomCoin, MerkleTree};
+use winterfell::{math::fields::f128::BaseElement, Proof, TraceInfo};
+
+const BATCH_SIZE: usize = 32;
+const RUNS: usize = 5;
+
+fn batch(num_samples: u64) -> Vec<(Proof, LinearRegressionInputs)> {
+    let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+    let xs: Vec<BaseElement> = (1..=num_samples).map(BaseElement::from).collect();
+    let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+    let target = BaseElement::new(11);
+    let inputs = LinearRegressionInputs {
+        x_value: target,
+        predicted_y: slope * target + intercept,
+        sample_x_values: xs.clone(),
+        sample_y_values: ys.clone(),
+    };
+    let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+    let proof = prove::<LinearRegressionAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options())
+        .expect("proof generation failed");
+    vec![(proof, inputs); BATCH_SIZE]
+}
+
+/// Best of [`RUNS`] timings of `f` on a fresh copy of `items`
+fn time(items: &[(Proof, LinearRegressionInputs)], f: impl Fn(Vec<(Proof, LinearRegressionInputs)>)) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let items = items.to_vec();
+            let start = Instant::now();
+            f(items);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let options = default_acceptable_options();
+    let mode = if cfg!(feature = "concurrent") { "concurrent" } else { "sequential" };
+    println!("verify ({mode} build, {} threads), one proof", rayon_threads());
+
+    for num_samples in [6, 62, 510, 4094] {
+        let items = &batch(num_samples)[..1];
+        let sequential = time(items, |items| {
+            for (proof, inputs) in items {
+                type Coin = DefaultRandomCoin<HashFn>;
+                winterfell::verify::<LinearRegressionAir, HashFn, Coin, MerkleTree<HashFn>>(proof, inputs, &options)
+                    .unwrap();
+            }
+        });
+        let latency = time(items, |items| {
+            for (proof, inputs) in items {
+                verify::<LinearRegressionAir>(proof, inputs, &options).unwrap();
+            }
+        });
+        println!(
+            "  trace length {:>5}: winter-verifier {:>8.2?}, verify {:>8.2?} ({:.2}x)",
+            regression::trace_length(num_samples as usize, TraceInfo::MIN_TRACE_LENGTH),
+            sequential,
+            latency,
+            sequential.as_secs_f64() / latency.as_secs_f64()
+        );
+    }
+
+    println!("verify_many ({mode} build), {BATCH_SIZE} proofs per batch");
+    for num_samples in [6, 62, 510] {
+        let items = batch(num_samples);
+        let one_by_one = time(&items, |items| {
+            for (proof, inputs) in items {
+                verify::<LinearRegressionAir>(proof, inputs, &options).unwrap();
+            }
+        });
+        let batched = time(&items, |items| {
+            assert!(verify_many::<LinearRegressionAir>(items, &options).iter().all(Result::is_ok));
+        });
+        println!(
+            "  trace length {:>5}: one by one {:>8.2?}, verify_many {:>8.2?} ({:.2}x)",
+            regression::trace_length(num_samples as usize, TraceInfo::MIN_TRACE_LENGTH),
+            one_by_one,
+            batched,
+            one_by_one.as_secs_f64() / batched.as_secs_f64()
+        );
+    }
+}
+
+/// Threads the parallel query checks run on
+fn rayon_threads() -> usize {
+    #[cfg(feature = "concurrent")]
+    return rayon::current_num_threads();
+    #[cfg(not(feature = "concurrent"))]
+    return 1;
+}
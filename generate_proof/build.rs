@@ -0,0 +1,15 @@
+//! Records the git commit the crate is built from, see `src/provenance.rs`.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=STARK_GIT_COMMIT");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+    // builds from a source tarball have no repository; packagers may set the commit themselves
+    let commit = std::env::var("STARK_GIT_COMMIT").ok().or_else(|| {
+        let output = Command::new("git").args(["rev-parse", "HEAD"]).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    });
+    println!("cargo:rustc-env=STARK_GIT_COMMIT={}", commit.unwrap_or_else(|| "unknown".into()));
+}
@@ -0,0 +1,28 @@
+//! Proves that a batch of sample timestamps is fresh without revealing them.
+//!
+//! The data provider publishes `timestamps_digest` of its sample timestamps; the prover shows
+//! they are strictly increasing and the newest is at most an hour old at the reference time.
+//!
+//! ```text
+//! cargo run --example freshness
+//! ```
+
+use linear_regression::config::{default_acceptable_options, ProverConfig};
+use linear_regression::freshness::{build_freshness_trace, FreshnessAir, FreshnessInputs};
+use linear_regression::prover::{prove, verify};
+use winterfell::math::StarkField;
+
+fn main() {
+    let timestamps = [1_717_200_000, 1_717_200_900, 1_717_201_800, 1_717_202_700];
+    let (reference_time, max_age) = (1_717_203_600, 3_600);
+
+    let inputs = FreshnessInputs::for_timestamps(&timestamps, reference_time, max_age);
+    let trace = build_freshness_trace(&timestamps, reference_time, max_age).expect("data is fresh");
+    let proof = prove::<FreshnessAir>(trace, inputs, ProverConfig::default().to_proof_options()).expect("proving failed");
+    println!("proof: {} bytes for digest {}", proof.to_bytes().len(), inputs.digest.as_int());
+
+    verify::<FreshnessAir>(proof.clone(), inputs, &default_acceptable_options()).expect("fresh data verifies");
+    let a_day_later = FreshnessInputs { reference_time: reference_time + 86_400, ..inputs };
+    assert!(verify::<FreshnessAir>(proof, a_day_later, &default_acceptable_options()).is_err());
+    println!("verified; the same proof is rejected a day later");
+}
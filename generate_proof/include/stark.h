@@ -0,0 +1,86 @@
+/*
+ * C ABI of the STARK framework, built with the `ffi` feature (or `mobile`, which enables it).
+ *
+ * Ownership:
+ *  - Pointers passed in are borrowed for the duration of the call and never retained.
+ *  - A StarkConfig handle belongs to the caller until it is passed to stark_config_free.
+ *  - A StarkBuffer filled by the library belongs to the caller until it is passed to
+ *    stark_buffer_free.
+ *  - Each handle and buffer must be freed exactly once; freeing NULL is a no-op.
+ *
+ * Functions are thread-safe as long as a handle is not freed while another thread uses it.
+ */
+
+#ifndef STARK_H
+#define STARK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes */
+#define STARK_OK 0
+#define STARK_INVALID_PROOF 1
+#define STARK_MALFORMED_ENVELOPE 2
+#define STARK_PROVE_FAILED 3
+#define STARK_INVALID_ARGUMENT 4
+#define STARK_NULL_POINTER -1
+#define STARK_INTERNAL_ERROR -2
+
+/* Opaque prover and verifier configuration */
+typedef struct StarkConfig StarkConfig;
+
+/* Bytes allocated by the library */
+typedef struct StarkBuffer {
+    uint8_t *data;
+    size_t len;
+} StarkBuffer;
+
+/* Returns a configuration with the default parameters. */
+StarkConfig *stark_config_new(void);
+
+/*
+ * Parses a JSON prover configuration, e.g.
+ * {"num_queries":32,"blowup_factor":8,"grinding_factor":0,"fri_folding_factor":8,
+ *  "fri_remainder_max_degree":31,"domain":"acme-lending/prod","max_public_inputs":2048}
+ * and writes a new handle to *out. "domain" and "max_public_inputs" are optional.
+ */
+int32_t stark_config_from_json(const uint8_t *json_ptr, size_t len, StarkConfig **out);
+
+void stark_config_free(StarkConfig *config);
+
+/* Releases the bytes of *buffer and resets it to {NULL, 0}. */
+void stark_buffer_free(StarkBuffer *buffer);
+
+/*
+ * Only in libraries built with the `prover` feature (scripts/build-ffi.sh; not the mobile build).
+ * Proves y = slope * x + intercept over num_samples sample x values and the target x, writing
+ * the proof envelope to *out. `model` is the UTF-8 model name (model_len bytes), `salt` the
+ * 32-byte salt of the model commitment. On failure *out is left empty.
+ */
+int32_t stark_prove_linear(
+    const StarkConfig *config,
+    const uint8_t *model,
+    size_t model_len,
+    const uint8_t *salt,
+    uint64_t slope,
+    uint64_t intercept,
+    const uint64_t *sample_x,
+    size_t num_samples,
+    uint64_t x,
+    StarkBuffer *out);
+
+/* Verifies an envelope under the default verification policy. */
+int32_t stark_verify(const uint8_t *envelope_ptr, size_t len);
+
+/* Verifies an envelope for the deployment (domain tag) of `config`. */
+int32_t stark_verify_with_config(const StarkConfig *config, const uint8_t *envelope_ptr, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* STARK_H */
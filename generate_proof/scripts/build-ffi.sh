@@ -0,0 +1,18 @@
+#!/usr/bin/env sh
+# Builds the C ABI for the host:
+#
+#   target/release/liblinear_regression.so (.dylib on macOS)  shared library
+#   target/release/liblinear_regression.a                     static library
+#
+# Link against either one and include include/stark.h. With Go, for example:
+#
+#   #cgo CFLAGS: -I${SRCDIR}/include
+#   #cgo LDFLAGS: -L${SRCDIR}/target/release -llinear_regression
+#
+# The libraries prove as well as verify. Extra arguments are passed to cargo, e.g.
+# `scripts/build-ffi.sh --features concurrent`.
+set -eu
+
+cd "$(dirname "$0")/.."
+cargo rustc --release --lib --features ffi,prover --crate-type cdylib "$@"
+cargo rustc --release --lib --features ffi,prover --crate-type staticlib "$@"
@@ -0,0 +1,41 @@
+#!/usr/bin/env sh
+# Builds the verification library for mobile wallets:
+#
+#   target/aarch64-apple-ios/release/liblinear_regression.a       (static, for Xcode)
+#   target/aarch64-linux-android/release/liblinear_regression.so  (shared, for jniLibs/arm64-v8a)
+#
+# Usage: scripts/build-mobile.sh [ios|android|all]
+#
+# iOS needs Xcode's toolchain on macOS. Android needs ANDROID_NDK_HOME pointing at an NDK r25+;
+# ANDROID_API selects the minimum API level (default 24). The C declarations are in
+# include/stark.h.
+set -eu
+
+cd "$(dirname "$0")/.."
+target="${1:-all}"
+
+build() {
+    rustup target add "$1"
+    cargo rustc --release --lib --no-default-features --features mobile --target "$1" --crate-type "$2"
+}
+
+ios() {
+    build aarch64-apple-ios staticlib
+}
+
+android() {
+    : "${ANDROID_NDK_HOME:?set ANDROID_NDK_HOME to the Android NDK}"
+    host="$(uname -s | tr '[:upper:]' '[:lower:]')-x86_64"
+    toolchain="$ANDROID_NDK_HOME/toolchains/llvm/prebuilt/$host/bin"
+    export CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER="$toolchain/aarch64-linux-android${ANDROID_API:-24}-clang"
+    export CC_aarch64_linux_android="$CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER"
+    export AR_aarch64_linux_android="$toolchain/llvm-ar"
+    build aarch64-linux-android cdylib
+}
+
+case "$target" in
+    ios) ios ;;
+    android) android ;;
+    all) ios && android ;;
+    *) echo "usage: $0 [ios|android|all]" >&2; exit 1 ;;
+esac
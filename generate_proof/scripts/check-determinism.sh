@@ -0,0 +1,22 @@
+#!/usr/bin/env sh
+# Checks the golden proofs in vectors/determinism.txt on the host and on AArch64, with and
+# without the `concurrent` feature. The AArch64 run needs `cross` (or set CROSS=cargo on an
+# AArch64 host); it is skipped with a warning when `cross` is not installed.
+#
+# After an intended change of the proofs, the failing test prints the new vectors.
+set -eu
+
+cd "$(dirname "$0")/.."
+CROSS="${CROSS:-cross}"
+TARGET=aarch64-unknown-linux-gnu
+
+for features in "--features prover" "--features concurrent"; do
+    # shellcheck disable=SC2086
+    cargo test --lib $features determinism
+    if command -v "$CROSS" >/dev/null 2>&1; then
+        # shellcheck disable=SC2086
+        "$CROSS" test --lib --target "$TARGET" $features determinism
+    else
+        echo "warning: $CROSS not found, skipping $TARGET" >&2
+    fi
+done
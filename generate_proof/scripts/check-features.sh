@@ -0,0 +1,30 @@
+#!/usr/bin/env sh
+# Lints every feature combination the crate documents, including the empty one, which builds
+# the modules that need neither `verifier` nor `prover` (`abi`, `metrics`, `public_inputs`,
+# `schema` and, with `http`, the TLS client). Run it after touching `[features]` or a `cfg`.
+set -eu
+
+cd "$(dirname "$0")/.."
+for features in \
+    "--no-default-features" \
+    "--no-default-features --features http" \
+    "--no-default-features --features verifier" \
+    "" \
+    "--features prover" \
+    "--features cli" \
+    "--features server" \
+    "--features ffi" \
+    "--features mobile" \
+    "--features onchain" \
+    "--features onchain-tests" \
+    "--features arrow" \
+    "--features concurrent" \
+    "--features simd" \
+    "--features beacon" \
+    "--features encryption" \
+    "--features s3,gcs" \
+    "--features tracing"; do
+    echo "checking with ${features:-the default features}" >&2
+    # shellcheck disable=SC2086
+    cargo clippy --all-targets $features -- -D warnings
+done
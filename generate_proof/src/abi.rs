@@ -0,0 +1,117 @@
+//! Minimal Solidity ABI helpers for building calldata to companion contracts.
+
+use sha3::{Digest, Keccak256};
+
+/// Keccak-256 as used by the EVM
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// First four bytes of the Keccak-256 hash of a function signature, e.g. `"revokeModel(bytes32)"`
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Left-pads an unsigned integer into a 32-byte ABI word
+pub fn uint_word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Encodes a call to a function whose arguments are all static 32-byte words
+pub fn encode_call(signature: &str, words: &[[u8; 32]]) -> Vec<u8> {
+    let mut calldata = Vec::with_capacity(4 + 32 * words.len());
+    calldata.extend_from_slice(&selector(signature));
+    for word in words {
+        calldata.extend_from_slice(word);
+    }
+    calldata
+}
+
+/// Argument of [`encode_dynamic_call`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbiArg<'a> {
+    /// Any static 32-byte type, e.g. `bytes32` or `uint256`
+    Word([u8; 32]),
+    Bytes(&'a [u8]),
+    /// `bytes32[]`
+    Words(&'a [[u8; 32]]),
+}
+
+/// Encodes a call whose arguments may be dynamic; their data follows the head of static words
+/// and offsets, in argument order
+pub fn encode_dynamic_call(signature: &str, args: &[AbiArg]) -> Vec<u8> {
+    let mut head = Vec::with_capacity(32 * args.len());
+    let mut tail = Vec::new();
+    for arg in args {
+        match arg {
+            AbiArg::Word(word) => head.extend_from_slice(word),
+            AbiArg::Bytes(bytes) => {
+                head.extend_from_slice(&uint_word((32 * args.len() + tail.len()) as u128));
+                tail.extend_from_slice(&uint_word(bytes.len() as u128));
+                tail.extend_from_slice(bytes);
+                tail.resize(tail.len().next_multiple_of(32), 0);
+            }
+            AbiArg::Words(words) => {
+                head.extend_from_slice(&uint_word((32 * args.len() + tail.len()) as u128));
+                tail.extend_from_slice(&uint_word(words.len() as u128));
+                words.iter().for_each(|word| tail.extend_from_slice(word));
+            }
+        }
+    }
+    [&selector(signature)[..], &head, &tail].concat()
+}
+
+/// Lowercase hex encoding with a `0x` prefix, as expected by `cast` and JSON-RPC tooling
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + 2 * bytes.len());
+    out.push_str("0x");
+    for byte in bytes {
+        out.push_str(&format!("{byte:02x}"));
+    }
+    out
+}
+
+/// Parses hex with or without a `0x` prefix
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selector_matches_erc20_transfer() {
+        assert_eq!(selector("transfer(address,uint256)"), [0xa9, 0x05, 0x9c, 0xbb]);
+
+        let calldata = encode_call("transfer(address,uint256)", &[uint_word(1), uint_word(2)]);
+        assert_eq!(calldata.len(), 68);
+        assert_eq!(calldata[35], 1);
+        assert_eq!(calldata[67], 2);
+        assert!(to_hex(&calldata).starts_with("0xa9059cbb"));
+        assert_eq!(from_hex(&to_hex(&calldata)), Some(calldata));
+    }
+
+    #[test]
+    fn test_dynamic_call_matches_solidity_docs() {
+        // `sam(bytes,bool,uint256[])` with "dave", true and [1, 2, 3] from the ABI specification
+        let numbers = [1, 2, 3].map(uint_word);
+        let args = [AbiArg::Bytes(b"dave"), AbiArg::Word(uint_word(1)), AbiArg::Words(&numbers)];
+        let calldata = encode_dynamic_call("sam(bytes,bool,uint256[])", &args);
+        assert_eq!(calldata[..4], [0xa5, 0x64, 0x3b, 0xf2]);
+        assert_eq!(calldata.len(), 4 + 9 * 32);
+        assert_eq!(calldata[4..132], [uint_word(0x60), uint_word(1), uint_word(0xa0), uint_word(4)].concat());
+        assert_eq!(calldata[132..137], *b"dave\0");
+        assert_eq!(calldata[164..], [uint_word(3), uint_word(1), uint_word(2), uint_word(3)].concat());
+    }
+}
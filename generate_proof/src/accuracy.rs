@@ -0,0 +1,417 @@
+//! Classification accuracy claims over a committed test set.
+//!
+//! [`AccuracyAir`] proves that at least `min_accuracy_percent` percent of the predictions
+//! hashed into a public Rescue digest match the labels hashed into another one (see
+//! [`labels_digest`] and [`predictions_digest`]). The labels usually come from the party
+//! holding the test set and the predictions from an inference proof, so a buyer can check a
+//! model's accuracy without seeing either, nor the exact number of correct predictions.
+//!
+//! The trace has one [`CYCLE_LENGTH`]-row cycle per sample plus one that finishes both
+//! sponges. Each cycle holds a label, a prediction and an indicator that is 1 exactly when
+//! they are equal, enforced with the inverse of their difference. A running count adds the
+//! indicator at the end of every sample cycle; where the sample phase ends, after the last
+//! sample, `100 * count - min_accuracy_percent * num_samples` is range checked.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{PhaseSelector, RangeCheck};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`AccuracyAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "accuracy/v1";
+
+/// Test sets may hold up to `2^SAMPLE_BITS` samples
+pub const SAMPLE_BITS: usize = 32;
+
+/// Sponge tags of [`labels_digest`] and [`predictions_digest`]; `crate::signature` uses 1 and
+/// 2, `crate::freshness` 3 and `crate::robustness` 4
+const LABELS_TAG: u64 = 5;
+const PREDICTIONS_TAG: u64 = 6;
+/// `100 * num_samples` must fit into this many bits
+const SLACK_BITS: usize = 40;
+const DIGIT_BITS: usize = 2;
+
+/// Phases of an accuracy trace: one cycle per sample, then the padding cycles
+const SAMPLES: usize = 0;
+const PADDING: usize = 1;
+
+// Trace layout: both sponge states, label, prediction, inverse of their difference, match
+// indicator, the phase flags, running count, then the digits of the accuracy slack
+const LABEL_SPONGE: usize = 0;
+const PREDICTION_SPONGE: usize = LABEL_SPONGE + STATE_WIDTH;
+const LABEL: usize = PREDICTION_SPONGE + STATE_WIDTH;
+const PREDICTION: usize = LABEL + 1;
+const INVERSE: usize = PREDICTION + 1;
+const CORRECT: usize = INVERSE + 1;
+const PHASES_START: usize = CORRECT + 1;
+const COUNT: usize = PHASES_START + 2;
+const DIGITS_START: usize = COUNT + 1;
+
+struct Layout {
+    phases: PhaseSelector,
+    labels: RescueGadget,
+    predictions: RescueGadget,
+    slack: RangeCheck,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            phases: PhaseSelector::new(PHASES_START, 2),
+            labels: RescueGadget::new(LABEL_SPONGE),
+            predictions: RescueGadget::new(PREDICTION_SPONGE),
+            slack: RangeCheck::with_digit_bits(DIGITS_START, SLACK_BITS, DIGIT_BITS),
+        }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.slack.next_column()
+    }
+}
+
+/// Number of trace columns
+pub fn trace_width() -> usize {
+    Layout::new().trace_width()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    let layout = Layout::new();
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("label_sponge", LABEL_SPONGE..PREDICTION_SPONGE)
+        .with_columns("prediction_sponge", PREDICTION_SPONGE..LABEL)
+        .with_column("label", LABEL)
+        .with_column("prediction", PREDICTION)
+        .with_column("inverse", INVERSE)
+        .with_column("correct", CORRECT)
+        .with_columns("phases", PHASES_START..COUNT)
+        .with_column("count", COUNT)
+        .with_columns("slack_digits", DIGITS_START..layout.slack.next_column())
+}
+
+/// Trace length for `num_samples` labelled predictions
+pub fn trace_length(num_samples: usize) -> usize {
+    ((num_samples + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Digest the holder of a test set publishes for its labels
+pub fn labels_digest(labels: &[u64]) -> BaseElement {
+    let elements: Vec<BaseElement> = labels.iter().map(|&label| BaseElement::from(label)).collect();
+    rescue::hash_elements(&elements, LABELS_TAG)
+}
+
+/// Digest of a model's predictions on the test set, in the order of the labels
+pub fn predictions_digest(predictions: &[u64]) -> BaseElement {
+    let elements: Vec<BaseElement> = predictions.iter().map(|&prediction| BaseElement::from(prediction)).collect();
+    rescue::hash_elements(&elements, PREDICTIONS_TAG)
+}
+
+/// Reason a set of predictions does not satisfy an accuracy claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccuracyError {
+    Empty,
+    TooManySamples(usize),
+    LengthMismatch { labels: usize, predictions: usize },
+    /// The threshold is not in `1..=100`
+    InvalidThreshold(u64),
+    BelowThreshold { correct: usize, num_samples: usize, min_accuracy_percent: u64 },
+}
+
+impl fmt::Display for AccuracyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccuracyError::Empty => write!(f, "no samples"),
+            AccuracyError::TooManySamples(count) => write!(f, "{count} samples exceed 2^{SAMPLE_BITS}"),
+            AccuracyError::LengthMismatch { labels, predictions } => {
+                write!(f, "{labels} labels but {predictions} predictions")
+            }
+            AccuracyError::InvalidThreshold(percent) => write!(f, "accuracy threshold {percent}% is not in 1..=100"),
+            AccuracyError::BelowThreshold { correct, num_samples, min_accuracy_percent } => {
+                write!(f, "{correct} of {num_samples} predictions are correct, less than {min_accuracy_percent}%")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccuracyError {}
+
+/// Public inputs: both digests, the number of samples and the accuracy threshold in percent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccuracyInputs {
+    pub labels_digest: BaseElement,
+    pub predictions_digest: BaseElement,
+    pub num_samples: usize,
+    pub min_accuracy_percent: u64,
+}
+
+impl AccuracyInputs {
+    /// Inputs of the claim that `predictions` reach `min_accuracy_percent` against `labels`
+    pub fn for_test_set(labels: &[u64], predictions: &[u64], min_accuracy_percent: u64) -> Self {
+        Self {
+            labels_digest: labels_digest(labels),
+            predictions_digest: predictions_digest(predictions),
+            num_samples: labels.len(),
+            min_accuracy_percent,
+        }
+    }
+}
+
+impl ToElements<BaseElement> for AccuracyInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            self.labels_digest,
+            self.predictions_digest,
+            BaseElement::from(self.num_samples as u64),
+            BaseElement::from(self.min_accuracy_percent),
+        ]
+    }
+}
+
+/// AIR proving that committed predictions match committed labels often enough
+pub struct AccuracyAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: AccuracyInputs,
+}
+
+impl Air for AccuracyAir {
+    type BaseField = BaseElement;
+    type PublicInputs = AccuracyInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: AccuracyInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new();
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert!(pub_inputs.num_samples > 0, "Accuracy claims need at least one sample");
+        assert!(pub_inputs.num_samples as u64 >> SAMPLE_BITS == 0, "Test sets hold at most 2^{SAMPLE_BITS} samples");
+        assert_eq!(trace_length(pub_inputs.num_samples), trace_info.length());
+        assert!((1..=100).contains(&pub_inputs.min_accuracy_percent), "Accuracy threshold must be in 1..=100");
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of both sponges (degree 3)
+        // 2. Each sponge absorbs its value at the end of a cycle (degree 1)
+        // 3. Label and prediction are constant within a cycle (degree 1)
+        // 4. The indicator is 1 when label and prediction are equal and 0 otherwise (degree 2)
+        // 5. The phase flags (degree 2)
+        // 6. The count adds the indicator at the end of sample cycles (degree 2)
+        // 7. Where the sample phase ends, 100 * count - threshold * num_samples is the range
+        //    checked slack (degree 2)
+        let mut degrees = layout.labels.constraint_degrees();
+        degrees.extend(layout.predictions.constraint_degrees());
+        degrees.extend(vec![periodic(1); 2 * STATE_WIDTH + 2]);
+        degrees.extend(vec![TransitionConstraintDegree::new(2); 2]);
+        degrees.extend(layout.phases.constraint_degrees());
+        degrees.push(periodic(2));
+        degrees.push(TransitionConstraintDegree::new(2));
+        degrees.extend(layout.slack.constraint_degrees());
+
+        AccuracyAir {
+            context: AirContext::new(trace_info, degrees, 2 * STATE_WIDTH + 5, options),
+            layout,
+            inputs: pub_inputs,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(2 * STATE_WIDTH);
+        layout.labels.evaluate(current, next, periodic_values, &mut sponge_result[..STATE_WIDTH]);
+        layout.predictions.evaluate(current, next, periodic_values, &mut sponge_result[STATE_WIDTH..]);
+
+        let (absorb_result, rest) = rest.split_at_mut(2 * STATE_WIDTH);
+        let absorbed = [(LABEL_SPONGE, LABEL), (PREDICTION_SPONGE, PREDICTION)];
+        for (absorb, (sponge, value)) in absorb_result.chunks_mut(STATE_WIDTH).zip(absorbed) {
+            absorb[0] = last * (next[sponge] - current[sponge] - current[value]);
+            for i in 1..STATE_WIDTH {
+                absorb[i] = last * (next[sponge + i] - current[sponge + i]);
+            }
+        }
+        rest[0] = mask * (next[LABEL] - current[LABEL]);
+        rest[1] = mask * (next[PREDICTION] - current[PREDICTION]);
+
+        let difference = current[LABEL] - current[PREDICTION];
+        rest[2] = current[CORRECT] + difference * current[INVERSE] - E::ONE;
+        rest[3] = difference * current[CORRECT];
+
+        let phases = &layout.phases;
+        phases.evaluate(current, next, &mut rest[4..]);
+        let rest = &mut rest[4 + phases.num_constraints()..];
+        // one on the last sample row, where the phases only allow the padding phase to follow
+        let edge = phases.flag(current, SAMPLES) - phases.flag(next, SAMPLES);
+        rest[0] = next[COUNT] - current[COUNT] - phases.gate(current, &[SAMPLES], last * current[CORRECT]);
+
+        let required = self.inputs.min_accuracy_percent * self.inputs.num_samples as u64;
+        let scaled = E::from(100u32) * next[COUNT] - E::from(BaseElement::from(required));
+        rest[1] = edge * (scaled - layout.slack.recompose(current));
+        layout.slack.evaluate(current, &mut rest[2..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_samples = self.inputs.num_samples;
+        let digest_row = (num_samples + 1) * CYCLE_LENGTH - 1;
+        let mut assertions = Vec::new();
+        for (sponge, tag, digest) in [
+            (LABEL_SPONGE, LABELS_TAG, self.inputs.labels_digest),
+            (PREDICTION_SPONGE, PREDICTIONS_TAG, self.inputs.predictions_digest),
+        ] {
+            for (i, value) in rescue::sponge_iv(num_samples, tag).into_iter().enumerate() {
+                assertions.push(Assertion::single(sponge + i, 0, value));
+            }
+            assertions.push(Assertion::single(sponge, digest_row, digest));
+        }
+        let phases = &self.layout.phases;
+        assertions.push(Assertion::single(phases.column(SAMPLES), num_samples * CYCLE_LENGTH - 1, BaseElement::ONE));
+        assertions.push(Assertion::single(phases.column(PADDING), num_samples * CYCLE_LENGTH, BaseElement::ONE));
+        assertions.push(Assertion::single(COUNT, 0, BaseElement::ZERO));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving `predictions` reach `min_accuracy_percent` against `labels`
+#[cfg(feature = "prover")]
+pub fn build_accuracy_trace(
+    labels: &[u64],
+    predictions: &[u64],
+    min_accuracy_percent: u64,
+) -> Result<TraceTable<BaseElement>, AccuracyError> {
+    let num_samples = labels.len();
+    if num_samples != predictions.len() {
+        return Err(AccuracyError::LengthMismatch { labels: num_samples, predictions: predictions.len() });
+    }
+    if num_samples == 0 {
+        return Err(AccuracyError::Empty);
+    }
+    if num_samples as u64 >> SAMPLE_BITS != 0 {
+        return Err(AccuracyError::TooManySamples(num_samples));
+    }
+    if !(1..=100).contains(&min_accuracy_percent) {
+        return Err(AccuracyError::InvalidThreshold(min_accuracy_percent));
+    }
+    let correct = labels.iter().zip(predictions).filter(|(label, prediction)| label == prediction).count();
+    let slack = (100 * correct as u64)
+        .checked_sub(min_accuracy_percent * num_samples as u64)
+        .ok_or(AccuracyError::BelowThreshold { correct, num_samples, min_accuracy_percent })?;
+
+    // Padding cycles hold a label and a prediction that differ from each other and from the
+    // last sample, which keeps every column from being constant
+    let (last_label, last_prediction) = (labels[num_samples - 1], predictions[num_samples - 1]);
+    let padding_prediction = last_prediction.wrapping_add(if last_label == last_prediction { 2 } else { 1 });
+    let padding = (last_label.wrapping_add(1), padding_prediction);
+
+    let layout = Layout::new();
+    let length = trace_length(num_samples);
+    let mut columns = vec![vec![BaseElement::ZERO; length]; layout.trace_width()];
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut label_state = rescue::sponge_iv(num_samples, LABELS_TAG);
+    let mut prediction_state = rescue::sponge_iv(num_samples, PREDICTIONS_TAG);
+    let mut count = 0u64;
+
+    layout.slack.fill(&mut row, slack as i128).expect("Slack is at most 100 * num_samples");
+
+    for cycle in 0..length / CYCLE_LENGTH {
+        let (label, prediction) = labels.get(cycle).zip(predictions.get(cycle)).map_or(padding, |(&l, &p)| (l, p));
+        let (label, prediction) = (BaseElement::from(label), BaseElement::from(prediction));
+        let is_correct = label == prediction;
+        row[LABEL] = label;
+        row[PREDICTION] = prediction;
+        row[INVERSE] = if is_correct { BaseElement::ZERO } else { (label - prediction).inv() };
+        row[CORRECT] = BaseElement::from(is_correct as u64);
+        layout.phases.fill(&mut row, if cycle < num_samples { SAMPLES } else { PADDING });
+        row[COUNT] = BaseElement::from(count);
+
+        for step in 0..CYCLE_LENGTH {
+            layout.labels.write(&mut row, &label_state);
+            layout.predictions.write(&mut row, &prediction_state);
+            for (column, &value) in columns.iter_mut().zip(&row) {
+                column[cycle * CYCLE_LENGTH + step] = value;
+            }
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut label_state, step);
+                rescue::apply_round(&mut prediction_state, step);
+            }
+        }
+        label_state[0] += label;
+        prediction_state[0] += prediction;
+        if cycle < num_samples && is_correct {
+            count += 1;
+        }
+    }
+
+    let mut last_row: Vec<BaseElement> = columns.iter().map(|column| column[length - 1]).collect();
+    last_row[CORRECT] = BaseElement::new(2);
+    layout.phases.fill_exempt_row(&mut last_row, PADDING);
+    layout.slack.fill_exempt_row(&mut last_row);
+    for (column, value) in columns.iter_mut().zip(last_row) {
+        column[length - 1] = value;
+    }
+
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_accuracy_proof() {
+        let labels = [0, 1, 2, 1, 0, 2, 2, 1, 0, 1];
+        let predictions = [0, 1, 2, 0, 0, 2, 1, 1, 0, 1];
+        let inputs = AccuracyInputs::for_test_set(&labels, &predictions, 75);
+
+        let trace = build_accuracy_trace(&labels, &predictions, 75).unwrap();
+        let proof = prove::<AccuracyAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+
+        let options = default_acceptable_options();
+        let stricter = AccuracyInputs { min_accuracy_percent: 81, ..inputs };
+        assert!(verify::<AccuracyAir>(proof.clone(), stricter, &options).is_err());
+        let other_labels = AccuracyInputs { labels_digest: labels_digest(&predictions), ..inputs };
+        assert!(verify::<AccuracyAir>(proof.clone(), other_labels, &options).is_err());
+        let result = verify::<AccuracyAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+
+        // every prediction correct, on a single sample
+        let trace = build_accuracy_trace(&[3], &[3], 100).unwrap();
+        prove::<AccuracyAir>(trace, AccuracyInputs::for_test_set(&[3], &[3], 100), ProverConfig::default().to_proof_options())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_rejects_inaccurate_predictions() {
+        assert_eq!(
+            build_accuracy_trace(&[1, 2, 3], &[1, 2, 0], 70).unwrap_err(),
+            AccuracyError::BelowThreshold { correct: 2, num_samples: 3, min_accuracy_percent: 70 }
+        );
+        assert_eq!(build_accuracy_trace(&[1, 2, 3], &[1, 2, 0], 66).map(|_| ()), Ok(()));
+        assert_eq!(build_accuracy_trace(&[1], &[1, 2], 50).unwrap_err(), AccuracyError::LengthMismatch { labels: 1, predictions: 2 });
+        assert_eq!(build_accuracy_trace(&[], &[], 50).unwrap_err(), AccuracyError::Empty);
+        assert_eq!(build_accuracy_trace(&[1], &[1], 0).unwrap_err(), AccuracyError::InvalidThreshold(0));
+    }
+}
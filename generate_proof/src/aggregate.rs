@@ -0,0 +1,558 @@
+//! Aggregate statistics of a trace column as public outputs of a proof.
+//!
+//! Claims such as "the total predicted exposure is at most `L`" are statements about a column
+//! of an existing trace, not about a single row. [`AggregatedAir`] wraps any AIR and appends
+//! columns that aggregate one of its columns over the first `rows` rows, so the sum, minimum
+//! and maximum become public outputs the verifier reads from the [`AggregateClaim`] and
+//! compares with its limits. The mean is derived from the proven sum and row count.
+//!
+//! A [`PhaseSelector`] splits the trace into the aggregated rows and the rest, and is asserted
+//! to switch between rows `rows - 1` and `rows`. The sum is a running
+//! accumulator. Minimum and maximum are running extrema: a binary witness per row says whether
+//! the row replaces the extremum so far, and the difference between the row and the extremum
+//! is range checked in the direction the witness claims. Values aggregated into an extremum are
+//! range checked to signed 64-bit integers, so these differences cannot wrap around the field.
+//! The aggregated rows must end before the trace's second to last row, which leaves the
+//! transition-exempt last row free to break the appended constraints.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::{Trace, TraceTable};
+
+#[cfg(feature = "prover")]
+use crate::fixed_point::from_field;
+use crate::fixed_point::{div_floor, to_field};
+use crate::gadgets::{PhaseSelector, RangeCheck};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`AggregatedAir`], recorded with the inner AIR's id in its layouts
+pub const AIR_ID: &str = "aggregated/v1";
+
+/// Aggregated values are signed 64-bit integers, e.g. fixed-point predictions
+const VALUE_BITS: usize = 64;
+const DIGIT_BITS: usize = 2;
+/// Maps a signed value into `0..2^VALUE_BITS` for its range check
+const VALUE_OFFSET: i128 = 1 << (VALUE_BITS - 1);
+
+/// Phases of the appended columns: the aggregated rows, then the remaining rows
+const ROWS: usize = 0;
+const PADDING: usize = 1;
+
+/// Statistic proven by an [`AggregatedAir`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+/// Public outputs of an [`AggregatedAir`]: the aggregates of `column` over its first `rows` rows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AggregateClaim {
+    pub column: usize,
+    pub rows: usize,
+    pub sum: Option<i128>,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+impl AggregateClaim {
+    pub fn value(&self, aggregate: Aggregate) -> Option<i128> {
+        match aggregate {
+            Aggregate::Sum => self.sum,
+            Aggregate::Min => self.min.map(i128::from),
+            Aggregate::Max => self.max.map(i128::from),
+        }
+    }
+
+    /// Mean with `frac_bits` more fractional bits than the values, rounded down
+    pub fn mean(&self, frac_bits: u32) -> Option<i128> {
+        self.sum.map(|sum| div_floor(sum << frac_bits, self.rows as i128).0)
+    }
+}
+
+impl ToElements<BaseElement> for AggregateClaim {
+    /// `[column, rows, sum?, min?, max?]` with a presence flag before every aggregate
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![BaseElement::from(self.column as u64), BaseElement::from(self.rows as u64)];
+        for value in [self.sum, self.min.map(i128::from), self.max.map(i128::from)] {
+            match value {
+                Some(value) => elements.extend([BaseElement::ONE, to_field(value)]),
+                None => elements.push(BaseElement::ZERO),
+            }
+        }
+        elements
+    }
+}
+
+/// Public inputs of the inner AIR followed by the aggregate claim
+#[derive(Clone, Debug)]
+pub struct AggregatedInputs<P> {
+    pub inner: P,
+    pub claim: AggregateClaim,
+}
+
+impl<P: ToElements<BaseElement>> ToElements<BaseElement> for AggregatedInputs<P> {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = self.inner.to_elements();
+        elements.extend(self.claim.to_elements());
+        elements
+    }
+}
+
+/// Reason a column cannot be aggregated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AggregateError {
+    NoAggregates,
+    ColumnOutOfRange { column: usize, width: usize },
+    /// Aggregates need at least one row and two rows after the aggregated ones
+    InvalidRows { rows: usize, trace_length: usize },
+    /// A value aggregated into a minimum or maximum is not a signed 64-bit integer
+    ValueOutOfRange { row: usize, value: i128 },
+}
+
+impl fmt::Display for AggregateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateError::NoAggregates => write!(f, "no aggregates requested"),
+            AggregateError::ColumnOutOfRange { column, width } => {
+                write!(f, "column {column} is outside a trace of width {width}")
+            }
+            AggregateError::InvalidRows { rows, trace_length } => {
+                write!(f, "cannot aggregate {rows} rows of a trace of length {trace_length}")
+            }
+            AggregateError::ValueOutOfRange { row, value } => {
+                write!(f, "value {value} on row {row} is not a signed 64-bit integer")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AggregateError {}
+
+/// Running minimum or maximum: the extremum before the row, whether the row replaces it and
+/// the digits of their difference
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Extremum {
+    column: usize,
+    is_min: bool,
+    margin: RangeCheck,
+}
+
+impl Extremum {
+    fn new(column: usize, is_min: bool) -> Self {
+        Self { column, is_min, margin: RangeCheck::with_digit_bits(column + 2, VALUE_BITS, DIGIT_BITS) }
+    }
+
+    fn take_column(&self) -> usize {
+        self.column + 1
+    }
+
+    fn next_column(&self) -> usize {
+        self.margin.next_column()
+    }
+
+    /// Extremum before the first row, replaced by any value
+    fn initial(&self) -> i64 {
+        if self.is_min { i64::MAX } else { i64::MIN }
+    }
+
+    fn num_constraints(&self) -> usize {
+        3 + self.margin.num_constraints()
+    }
+
+    fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        let mut degrees = vec![2, 3, 3].into_iter().map(TransitionConstraintDegree::new).collect::<Vec<_>>();
+        degrees.extend(self.margin.constraint_degrees());
+        degrees
+    }
+
+    fn evaluate<E: FieldElement>(&self, current: &[E], next: &[E], value: E, active: E, result: &mut [E]) {
+        let (extremum, take) = (current[self.column], current[self.take_column()]);
+        result[0] = take * (take - E::ONE);
+        result[1] = next[self.column] - extremum - active * take * (value - extremum);
+        // a taken value is at or beyond the extremum so far, any other value at or within it
+        let direction = if self.is_min { E::ONE - take - take } else { take + take - E::ONE };
+        result[2] = active * (direction * (value - extremum) - self.margin.recompose(current));
+        self.margin.evaluate(current, &mut result[3..]);
+    }
+
+    /// Writes the row and returns the extremum after it
+    #[cfg(feature = "prover")]
+    fn fill(&self, row: &mut [BaseElement], extremum: i64, value: Option<i64>) -> i64 {
+        row[self.column] = to_field(extremum.into());
+        let take = value.is_some_and(|value| if self.is_min { value < extremum } else { value > extremum });
+        row[self.take_column()] = BaseElement::from(take as u8);
+        let margin = value.map_or(0, |value| (i128::from(value) - i128::from(extremum)).abs());
+        self.margin.fill(row, margin).expect("Differences of 64-bit values fit 64 bits");
+        if take { value.expect("Only values are taken") } else { extremum }
+    }
+
+    #[cfg(feature = "prover")]
+    fn fill_exempt_row(&self, row: &mut [BaseElement], extremum: i64) {
+        row[self.column] = to_field(extremum.into());
+        row[self.take_column()] = BaseElement::from(2u8);
+        self.margin.fill_exempt_row(row);
+    }
+}
+
+/// Columns appended to the inner trace from `first_column` on
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Layout {
+    phases: PhaseSelector,
+    sum: Option<usize>,
+    value: Option<RangeCheck>,
+    min: Option<Extremum>,
+    max: Option<Extremum>,
+}
+
+impl Layout {
+    fn new(first_column: usize, sum: bool, min: bool, max: bool) -> Self {
+        let phases = PhaseSelector::new(first_column, 2);
+        let mut next = phases.next_column();
+        let sum = sum.then(|| {
+            next += 1;
+            next - 1
+        });
+        let value = (min || max).then(|| {
+            let range = RangeCheck::with_digit_bits(next, VALUE_BITS, DIGIT_BITS);
+            next = range.next_column();
+            range
+        });
+        let min = min.then(|| {
+            let extremum = Extremum::new(next, true);
+            next = extremum.next_column();
+            extremum
+        });
+        let max = max.then(|| Extremum::new(next, false));
+        Self { phases, sum, value, min, max }
+    }
+
+    fn for_claim(first_column: usize, claim: &AggregateClaim) -> Self {
+        Self::new(first_column, claim.sum.is_some(), claim.min.is_some(), claim.max.is_some())
+    }
+
+    fn next_column(&self) -> usize {
+        match (self.max, self.min, self.value, self.sum) {
+            (Some(max), ..) => max.next_column(),
+            (None, Some(min), ..) => min.next_column(),
+            (None, None, Some(value), _) => value.next_column(),
+            (None, None, None, Some(sum)) => sum + 1,
+            (None, None, None, None) => self.phases.next_column(),
+        }
+    }
+
+    fn describe(&self, mut layout: LayoutDescriptor) -> LayoutDescriptor {
+        let first_column = self.phases.column(ROWS);
+        layout = layout.with_columns("phases", first_column..self.phases.next_column());
+        if let Some(sum) = self.sum {
+            layout = layout.with_column("sum", sum);
+        }
+        if let Some(value) = self.value {
+            layout = layout.with_columns("value_digits", value.next_column() - value.width()..value.next_column());
+        }
+        for extremum in self.extrema() {
+            let name = if extremum.is_min { "min" } else { "max" };
+            let margin = extremum.take_column() + 1..extremum.next_column();
+            layout = layout
+                .with_column(name, extremum.column)
+                .with_column(&format!("{name}_taken"), extremum.take_column())
+                .with_columns(&format!("{name}_margin_digits"), margin);
+        }
+        layout
+    }
+
+    fn extrema(&self) -> impl Iterator<Item = Extremum> {
+        self.min.into_iter().chain(self.max)
+    }
+
+    fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        let mut degrees = self.phases.constraint_degrees();
+        if self.sum.is_some() {
+            degrees.push(TransitionConstraintDegree::new(2));
+        }
+        if let Some(value) = self.value {
+            degrees.push(TransitionConstraintDegree::new(2));
+            degrees.extend(value.constraint_degrees());
+        }
+        for extremum in self.extrema() {
+            degrees.extend(extremum.constraint_degrees());
+        }
+        degrees
+    }
+}
+
+/// `A` with aggregates of one of its columns as additional public outputs.
+///
+/// The constraints and assertions of `A` apply to its own columns unchanged; the trace carries
+/// the aggregate columns after them, see [`append_aggregates`]. Wrap again to aggregate more
+/// columns. AIRs with auxiliary trace segments are not supported.
+pub struct AggregatedAir<A> {
+    inner: A,
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    claim: AggregateClaim,
+}
+
+impl<A: Air<BaseField = BaseElement>> Air for AggregatedAir<A> {
+    type BaseField = BaseElement;
+    type PublicInputs = AggregatedInputs<A::PublicInputs>;
+
+    fn new(trace_info: TraceInfo, pub_inputs: Self::PublicInputs, options: ProofOptions) -> Self {
+        assert!(!trace_info.is_multi_segment(), "Aggregates do not support auxiliary segments");
+        let claim = pub_inputs.claim;
+        let probe = Layout::for_claim(0, &claim);
+        let inner_width = trace_info.width() - probe.next_column();
+        assert!(claim.column < inner_width, "Aggregated column is outside the inner trace");
+        assert!(claim.rows >= 1 && claim.rows + 2 <= trace_info.length(), "Invalid number of aggregated rows");
+
+        let inner = A::new(TraceInfo::new(inner_width, trace_info.length()), pub_inputs.inner, options.clone());
+        let layout = Layout::for_claim(inner_width, &claim);
+        let inner_context = inner.context();
+        let constraints = inner.get_transition_constraints(&vec![BaseElement::ZERO; inner_context.num_transition_constraints()]);
+
+        // Constraints, after those of the inner AIR:
+        // 1. The phase flags (degree 2)
+        // 2. sum: next = sum + value on aggregated rows (degree 2)
+        // 3. aggregated rows: value + 2^63 matches its digits (degree 2), digits (degree 4)
+        // 4. per extremum: the witness is binary (degree 2), the extremum moves to the value
+        //    where it is taken (degree 3) and the margin matches its digits (degree 3)
+        let mut degrees = constraints.main_constraint_degrees().to_vec();
+        degrees.extend(layout.constraint_degrees());
+        let num_assertions = inner_context.num_assertions() + 2 + 2 * (claim.sum.iter().len() + layout.extrema().count());
+        let context = AirContext::new(trace_info, degrees, num_assertions, options)
+            .set_num_transition_exemptions(inner_context.num_transition_exemptions());
+
+        Self { inner, context, layout, claim }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let num_inner = self.inner.context().num_main_transition_constraints();
+        let (inner_result, result) = result.split_at_mut(num_inner);
+        self.inner.evaluate_transition(frame, periodic_values, inner_result);
+
+        let (current, next) = (frame.current(), frame.next());
+        let layout = &self.layout;
+        let phases = &layout.phases;
+        let value = current[self.claim.column];
+        phases.evaluate(current, next, result);
+        let mut offset = phases.num_constraints();
+
+        if let Some(sum) = layout.sum {
+            result[offset] = next[sum] - current[sum] - phases.gate(current, &[ROWS], value);
+            offset += 1;
+        }
+        if let Some(range) = layout.value {
+            let shifted = value + E::from(to_field(VALUE_OFFSET));
+            result[offset] = phases.gate(current, &[ROWS], shifted - range.recompose(current));
+            range.evaluate(current, &mut result[offset + 1..]);
+            offset += 1 + range.num_constraints();
+        }
+        for extremum in layout.extrema() {
+            extremum.evaluate(current, next, value, phases.flag(current, ROWS), &mut result[offset..]);
+            offset += extremum.num_constraints();
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = self.inner.get_assertions();
+        let (layout, rows) = (&self.layout, self.claim.rows);
+        assertions.push(Assertion::single(layout.phases.column(ROWS), rows - 1, BaseElement::ONE));
+        assertions.push(Assertion::single(layout.phases.column(PADDING), rows, BaseElement::ONE));
+        if let (Some(column), Some(sum)) = (layout.sum, self.claim.sum) {
+            assertions.push(Assertion::single(column, 0, BaseElement::ZERO));
+            assertions.push(Assertion::single(column, rows, to_field(sum)));
+        }
+        let claimed = [(layout.min, self.claim.min), (layout.max, self.claim.max)];
+        for (extremum, value) in claimed.into_iter().filter_map(|(e, v)| e.zip(v)) {
+            assertions.push(Assertion::single(extremum.column, 0, to_field(extremum.initial().into())));
+            assertions.push(Assertion::single(extremum.column, rows, to_field(value.into())));
+        }
+        assertions
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        self.inner.get_periodic_column_values()
+    }
+}
+
+/// Named columns of an [`AggregatedAir`] proving `claim` over the AIR of `inner`: the inner
+/// columns prefixed with `inner_`, then the appended ones, see [`crate::layout`]
+pub fn layout(inner: &LayoutDescriptor, claim: &AggregateClaim) -> LayoutDescriptor {
+    let layout = LayoutDescriptor::new(&format!("{AIR_ID}({})", inner.air_id)).with_layout("inner", inner, 0);
+    Layout::for_claim(inner.width(), claim).describe(layout)
+}
+
+/// Appends the columns of `aggregates` over the first `rows` rows of `column` to `trace` and
+/// returns the extended trace with the claim it proves
+#[cfg(feature = "prover")]
+pub fn append_aggregates(
+    trace: &TraceTable<BaseElement>,
+    column: usize,
+    rows: usize,
+    aggregates: &[Aggregate],
+) -> Result<(TraceTable<BaseElement>, AggregateClaim), AggregateError> {
+    let (width, length) = (trace.width(), trace.length());
+    if aggregates.is_empty() {
+        return Err(AggregateError::NoAggregates);
+    }
+    if column >= width {
+        return Err(AggregateError::ColumnOutOfRange { column, width });
+    }
+    if rows == 0 || rows + 2 > length {
+        return Err(AggregateError::InvalidRows { rows, trace_length: length });
+    }
+
+    let has = |aggregate| aggregates.contains(&aggregate);
+    let layout = Layout::new(width, has(Aggregate::Sum), has(Aggregate::Min), has(Aggregate::Max));
+    let values: Vec<i128> = (0..rows).map(|row| from_field(trace.get(column, row))).collect();
+    let extremum_values = match layout.value {
+        Some(_) => values
+            .iter()
+            .enumerate()
+            .map(|(row, &value)| i64::try_from(value).map_err(|_| AggregateError::ValueOutOfRange { row, value }))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+
+    let mut columns: Vec<Vec<BaseElement>> = (0..width).map(|i| trace.main_segment().get_column(i).to_vec()).collect();
+    columns.resize(layout.next_column(), vec![BaseElement::ZERO; length]);
+    let mut row = vec![BaseElement::ZERO; layout.next_column()];
+    let mut sum = 0i128;
+    let mut extrema: Vec<i64> = layout.extrema().map(|extremum| extremum.initial()).collect();
+
+    for step in 0..length {
+        let exempt = step == length - 1;
+        let active = step < rows;
+        if exempt {
+            layout.phases.fill_exempt_row(&mut row, PADDING);
+        } else {
+            layout.phases.fill(&mut row, if active { ROWS } else { PADDING });
+        }
+        if let Some(column) = layout.sum {
+            row[column] = to_field(sum);
+            if active {
+                sum += values[step];
+            }
+        }
+        if let Some(range) = layout.value {
+            match (exempt, extremum_values.get(step)) {
+                (true, _) => range.fill_exempt_row(&mut row),
+                (false, Some(&value)) => range.fill(&mut row, i128::from(value) + VALUE_OFFSET).expect("Values are 64-bit"),
+                (false, None) => range.fill(&mut row, 0).expect("Zero fits any range"),
+            }
+        }
+        for (extremum, current) in layout.extrema().zip(extrema.iter_mut()) {
+            if exempt {
+                extremum.fill_exempt_row(&mut row, *current);
+            } else {
+                *current = extremum.fill(&mut row, *current, extremum_values.get(step).copied());
+            }
+        }
+        for (column, &value) in columns[width..].iter_mut().zip(&row[width..]) {
+            column[step] = value;
+        }
+    }
+
+    let mut extrema = extrema.into_iter();
+    let claim = AggregateClaim {
+        column,
+        rows,
+        sum: layout.sum.map(|_| sum),
+        min: layout.min.and_then(|_| extrema.next()),
+        max: layout.max.and_then(|_| extrema.next()),
+    };
+    Ok((TraceTable::init(columns), claim))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    type Air = AggregatedAir<LinearRegressionAir>;
+
+    #[test]
+    fn test_prediction_aggregates() {
+        let (slope, intercept) = (BaseElement::new(3), to_field(-20));
+        let xs: Vec<BaseElement> = [2u128, 9, 4, 11, 5].map(BaseElement::new).to_vec();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(7),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let inner = LinearRegressionInputs {
+            x_value: BaseElement::new(7),
+            predicted_y: slope * BaseElement::new(7) + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys,
+        };
+
+        // y is column 3; the samples are the first five rows: -14, 7, -8, 13, -5
+        let aggregates = [Aggregate::Sum, Aggregate::Min, Aggregate::Max];
+        let (trace, claim) = append_aggregates(&trace, 3, xs.len(), &aggregates).unwrap();
+        assert_eq!((claim.sum, claim.min, claim.max), (Some(-7), Some(-14), Some(13)));
+        assert_eq!(claim.mean(4), Some(-23)); // floor(-7 * 16 / 5)
+        let exposure_limit = 0;
+        assert!(claim.value(Aggregate::Sum) <= Some(exposure_limit));
+
+        let inputs = AggregatedInputs { inner, claim };
+        crate::air_test!(Air, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<Air>(trace, inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+        verify::<Air>(proof.clone(), inputs.clone(), &default_acceptable_options()).unwrap();
+
+        let claim = &inputs.claim;
+        let tampered_claims = [
+            AggregateClaim { sum: Some(-6), ..claim.clone() },
+            AggregateClaim { min: Some(-13), ..claim.clone() },
+            AggregateClaim { max: Some(14), ..claim.clone() },
+        ];
+        for claim in tampered_claims {
+            let tampered = AggregatedInputs { inner: inputs.inner.clone(), claim };
+            assert!(verify::<Air>(proof.clone(), tampered, &default_acceptable_options()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let trace = TraceTable::init(vec![vec![BaseElement::ONE; 8]]);
+        assert_eq!(append_aggregates(&trace, 0, 7, &[Aggregate::Sum]).unwrap_err(), AggregateError::InvalidRows {
+            rows: 7,
+            trace_length: 8
+        });
+        assert_eq!(append_aggregates(&trace, 1, 4, &[Aggregate::Sum]).unwrap_err(), AggregateError::ColumnOutOfRange {
+            column: 1,
+            width: 1
+        });
+        let large = TraceTable::init(vec![vec![BaseElement::new(1 << 64); 8]]);
+        assert_eq!(append_aggregates(&large, 0, 4, &[Aggregate::Sum]).map(|(_, claim)| claim.sum), Ok(Some(4 << 64)));
+        assert!(matches!(
+            append_aggregates(&large, 0, 4, &[Aggregate::Max]),
+            Err(AggregateError::ValueOutOfRange { row: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,245 @@
+//! A small DSL for unit tests of AIR constraints.
+//!
+//! [`air_test!`](crate::air_test!) instantiates an AIR over a built trace or a few hand-written
+//! rows, optionally tampered with cell by cell, and every `expect_*` call evaluates the
+//! constraints and panics naming the AIR, constraint, row and value when the expectation fails:
+//!
+//! ```text
+//! air_test!(LinearRegressionAir, inputs)
+//!     .with_trace(&trace)
+//!     .with_cell(Y, 1, BaseElement::ZERO)
+//!     .expect_constraint_fails(0, 1)
+//!     .expect_constraint_holds(1);
+//! ```
+//!
+//! Rows are checked as transitions from each given row to the next, so a test can cover the
+//! first steps of a long trace without writing all of it; the AIR is instantiated for the
+//! trace length set with `with_trace_length`, by default the smallest one holding the rows.
+//! Assertions are only checked against complete traces.
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    Air, ProofOptions, Trace, TraceInfo, TraceTable,
+};
+
+use crate::analysis;
+use crate::config::ProverConfig;
+use crate::simulation::dry_run;
+use crate::trace::evaluate_transitions;
+
+/// Starts an [`AirTest`] of the AIR type `$air`, with `$inputs` or the default public inputs
+#[macro_export]
+macro_rules! air_test {
+    ($air:ty) => {
+        $crate::air_test::AirTest::<$air>::new(stringify!($air), Default::default())
+    };
+    ($air:ty, $inputs:expr) => {
+        $crate::air_test::AirTest::<$air>::new(stringify!($air), $inputs)
+    };
+}
+
+/// Rows of a trace and the AIR instance to check them against
+pub struct AirTest<A: Air<BaseField = BaseElement>> {
+    name: &'static str,
+    inputs: A::PublicInputs,
+    options: ProofOptions,
+    rows: Vec<Vec<BaseElement>>,
+    trace_length: Option<usize>,
+}
+
+impl<A> AirTest<A>
+where
+    A: Air<BaseField = BaseElement>,
+    A::PublicInputs: Clone,
+{
+    pub fn new(name: &'static str, inputs: A::PublicInputs) -> Self {
+        Self { name, inputs, options: ProverConfig::default().to_proof_options(), rows: Vec::new(), trace_length: None }
+    }
+
+    pub fn with_options(mut self, options: ProofOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Instantiates the AIR for traces of `trace_length` rows, more than the test writes
+    pub fn with_trace_length(mut self, trace_length: usize) -> Self {
+        self.trace_length = Some(trace_length);
+        self
+    }
+
+    /// Replaces the rows under test
+    pub fn with_rows<R: AsRef<[BaseElement]>>(mut self, rows: impl IntoIterator<Item = R>) -> Self {
+        self.rows = rows.into_iter().map(|row| row.as_ref().to_vec()).collect();
+        if let Some(row) = self.rows.iter().position(|row| row.len() != self.rows[0].len()) {
+            panic!("{}: row {row} has {} cells, row 0 has {}", self.name, self.rows[row].len(), self.rows[0].len());
+        }
+        self
+    }
+
+    /// Replaces the rows under test with every row of `trace`
+    pub fn with_trace(self, trace: &TraceTable<BaseElement>) -> Self {
+        let rows: Vec<Vec<BaseElement>> =
+            (0..trace.length()).map(|row| (0..trace.width()).map(|column| trace.get(column, row)).collect()).collect();
+        self.with_trace_length(trace.length()).with_rows(rows)
+    }
+
+    /// Overwrites one cell of the rows under test
+    pub fn with_cell(mut self, column: usize, row: usize, value: impl Into<BaseElement>) -> Self {
+        let name = self.name;
+        let cell = self.rows.get_mut(row).and_then(|cells| cells.get_mut(column));
+        *cell.unwrap_or_else(|| panic!("{name}: there is no cell {column} in row {row}")) = value.into();
+        self
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length.unwrap_or(self.rows.len().next_power_of_two().max(TraceInfo::MIN_TRACE_LENGTH))
+    }
+
+    fn trace_info(&self) -> TraceInfo {
+        assert!(!self.rows.is_empty(), "{}: no rows to test", self.name);
+        TraceInfo::new(self.rows[0].len(), self.trace_length())
+    }
+
+    fn air(&self) -> A {
+        A::new(self.trace_info(), self.inputs.clone(), self.options.clone())
+    }
+
+    /// The given rows, followed by zero rows up to the trace length
+    fn padded_trace(&self) -> TraceTable<BaseElement> {
+        let (width, length) = (self.rows[0].len(), self.trace_length());
+        assert!(self.rows.len() <= length, "{}: {} rows exceed the trace length {length}", self.name, self.rows.len());
+        let columns = (0..width)
+            .map(|column| {
+                let mut values: Vec<BaseElement> = self.rows.iter().map(|row| row[column]).collect();
+                values.resize(length, BaseElement::ZERO);
+                values
+            })
+            .collect();
+        TraceTable::init(columns)
+    }
+
+    /// Values of every transition constraint on every row whose transition the test covers:
+    /// each row followed by a given row and not exempt from the transition constraints
+    pub fn evaluations(&self) -> Vec<Vec<BaseElement>> {
+        let air = self.air();
+        let checked = (self.rows.len() - 1).min(self.trace_length() - air.context().num_transition_exemptions());
+        let mut evaluations = evaluate_transitions(&air, &self.padded_trace());
+        evaluations.truncate(checked);
+        evaluations
+    }
+
+    fn constraint_values(&self, constraint: usize) -> Vec<BaseElement> {
+        let evaluations = self.evaluations();
+        let count = evaluations.first().map_or(0, Vec::len);
+        assert!(constraint < count, "{}: there is no constraint {constraint}, the AIR has {count}", self.name);
+        evaluations.iter().map(|row| row[constraint]).collect()
+    }
+
+    /// Expects `constraint` to evaluate to zero on every checked row
+    pub fn expect_constraint_holds(self, constraint: usize) -> Self {
+        let values = self.constraint_values(constraint);
+        if let Some((row, value)) = values.iter().enumerate().find(|(_, value)| **value != BaseElement::ZERO) {
+            panic!("{}: constraint {constraint} does not hold on row {row}, it evaluates to {value}", self.name);
+        }
+        self
+    }
+
+    /// Expects `constraint` not to evaluate to zero on the transition from `row` to the next
+    pub fn expect_constraint_fails(self, constraint: usize, row: usize) -> Self {
+        let values = self.constraint_values(constraint);
+        let value = values.get(row).unwrap_or_else(|| panic!("{}: row {row} is not checked", self.name));
+        assert!(*value != BaseElement::ZERO, "{}: constraint {constraint} holds on row {row}", self.name);
+        self
+    }
+
+    /// Expects every transition constraint to evaluate to zero on every checked row
+    pub fn expect_all_hold(self) -> Self {
+        for (row, values) in self.evaluations().iter().enumerate() {
+            if let Some(constraint) = values.iter().position(|value| *value != BaseElement::ZERO) {
+                panic!("{}: constraint {constraint} does not hold on row {row}", self.name);
+            }
+        }
+        self
+    }
+
+    /// Expects the rows, which must be a complete trace, to satisfy every assertion
+    pub fn expect_assertions_hold(self) -> Self {
+        assert_eq!(self.rows.len(), self.trace_length(), "{}: assertions need a complete trace", self.name);
+        let report = dry_run::<A>(&self.padded_trace(), self.inputs.clone(), self.options.clone())
+            .unwrap_or_else(|err| panic!("{}: {err}", self.name));
+        if let Some(failure) = report.assertion_failures.first() {
+            panic!(
+                "{}: column {} at step {} is {}, the assertion expects {}",
+                self.name, failure.column, failure.step, failure.found, failure.expected
+            );
+        }
+        self
+    }
+
+    /// Expects every column to be read by a transition constraint or pinned by an assertion, see
+    /// [`analysis::coverage`]
+    pub fn expect_full_coverage(self) -> Self {
+        let coverage = analysis::coverage(&self.air());
+        assert!(coverage.is_full(), "{}: columns {:?} are unconstrained\n{coverage}", self.name, coverage.uncovered());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    fn trace_and_inputs() -> (TraceTable<BaseElement>, LinearRegressionInputs) {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+        let xs = [1u64, 2, 3].map(BaseElement::from).to_vec();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(20),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(20),
+            predicted_y: BaseElement::new(67),
+            sample_x_values: xs,
+            sample_y_values: ys,
+        };
+        (trace, inputs)
+    }
+
+    #[test]
+    fn test_expectations_on_a_tampered_trace() {
+        let (trace, inputs) = trace_and_inputs();
+        air_test!(LinearRegressionAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        // the first two rows suffice to check the first transition
+        let rows: Vec<Vec<BaseElement>> =
+            (0..2).map(|row| (0..trace.width()).map(|column| trace.get(column, row)).collect()).collect();
+        let first = air_test!(LinearRegressionAir, inputs.clone()).with_rows(&rows).with_trace_length(8);
+        assert_eq!(first.evaluations().len(), 1);
+        first.expect_all_hold();
+
+        // y of the second sample off by one breaks the linear relation there and nowhere else
+        air_test!(LinearRegressionAir, inputs)
+            .with_trace(&trace)
+            .with_cell(3, 1, trace.get(3, 1) + BaseElement::ONE)
+            .expect_constraint_fails(0, 1)
+            .expect_constraint_holds(1)
+            .expect_constraint_holds(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "LinearRegressionAir: constraint 0 does not hold on row 2, it evaluates to")]
+    fn test_failed_expectation_names_the_row() {
+        let (trace, inputs) = trace_and_inputs();
+        air_test!(LinearRegressionAir, inputs).with_trace(&trace).with_cell(3, 2, 0u64).expect_constraint_holds(0);
+    }
+}
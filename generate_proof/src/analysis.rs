@@ -0,0 +1,322 @@
+//! Size of an AIR instance's circuit, for designing and comparing AIRs.
+//!
+//! [`report`] reads everything from the instantiated AIR: the trace shape, the declared degree
+//! of every transition constraint, the assertions and the composition polynomial Winterfell
+//! will build from them. Constraint degrees are counted in multiples of the trace polynomial
+//! degree, with periodic columns rounded up the way Winterfell sizes the evaluation domain.
+//! Proving cost comes from the [`ProvingPlan`] of the instance; time needs a [`Calibration`].
+//! [`labeled_report`] also lists every constraint by label with its degree.
+//!
+//! [`coverage`] finds trace columns no constraint looks at: a column that is neither read by
+//! a transition constraint nor pinned by an assertion can hold anything, which silently
+//! breaks soundness. Transition constraints are probed on pseudo-random frames and periodic
+//! values, changing one cell at a time; a constraint that depends on a cell changes with it
+//! except with negligible probability. New AIRs call [`assert_full_coverage`] in their tests.
+
+use core::fmt;
+use std::collections::BTreeMap;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField},
+    Air, EvaluationFrame, ProofOptions, TraceInfo,
+};
+
+use crate::config::ProverConfig;
+use crate::constraints::ConstraintLabels;
+use crate::resources::{Calibration, ProvingPlan, ResourceEstimate};
+
+const PROBE_DOMAIN: &[u8] = b"stark-framework/coverage-probe/v1";
+
+/// Circuit size of one AIR instance
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitReport {
+    pub trace_width: usize,
+    pub trace_length: usize,
+    pub num_periodic_columns: usize,
+    /// Number of transition constraints of each degree
+    pub constraints_by_degree: BTreeMap<usize, usize>,
+    /// Label and degree of every transition constraint, when the AIR names them
+    pub labeled_constraints: Vec<(String, usize)>,
+    pub num_assertions: usize,
+    /// Degree of the constraint composition polynomial
+    pub composition_degree: usize,
+    /// Trace-length columns the composition polynomial is split into
+    pub composition_columns: usize,
+    pub plan: ProvingPlan,
+}
+
+impl CircuitReport {
+    pub fn num_constraints(&self) -> usize {
+        self.constraints_by_degree.values().sum()
+    }
+
+    pub fn max_degree(&self) -> usize {
+        self.constraints_by_degree.keys().next_back().copied().unwrap_or(0)
+    }
+
+    pub fn estimate(&self, calibration: &Calibration) -> ResourceEstimate {
+        calibration.estimate(&self.plan)
+    }
+}
+
+/// Reports the circuit size of `air`
+pub fn report<A: Air<BaseField = BaseElement>>(air: &A) -> CircuitReport {
+    let context = air.context();
+    let trace_length = context.trace_len();
+    let constraints = air.get_transition_constraints(&vec![BaseElement::ZERO; context.num_transition_constraints()]);
+
+    let mut constraints_by_degree = BTreeMap::new();
+    let mut max_evaluation_degree = 0;
+    for degree in constraints.main_constraint_degrees() {
+        let evaluation_degree = degree.get_evaluation_degree(trace_length);
+        max_evaluation_degree = max_evaluation_degree.max(evaluation_degree);
+        *constraints_by_degree.entry(evaluation_degree.div_ceil(trace_length - 1)).or_insert(0) += 1;
+    }
+    let divisor_degree = trace_length - context.num_transition_exemptions();
+
+    CircuitReport {
+        trace_width: context.trace_info().main_trace_width(),
+        trace_length,
+        num_periodic_columns: air.get_periodic_column_values().len(),
+        constraints_by_degree,
+        labeled_constraints: Vec::new(),
+        num_assertions: context.num_assertions(),
+        composition_degree: max_evaluation_degree.saturating_sub(divisor_degree),
+        composition_columns: context.num_constraint_composition_columns(),
+        plan: ProvingPlan {
+            trace_width: context.trace_info().main_trace_width(),
+            trace_length,
+            blowup_factor: air.options().blowup_factor(),
+        },
+    }
+}
+
+/// [`report`] with the label and degree of every constraint of `air`
+pub fn labeled_report<A: Air<BaseField = BaseElement> + ConstraintLabels>(air: &A) -> CircuitReport {
+    let context = air.context();
+    let trace_length = context.trace_len();
+    let constraints = air.get_transition_constraints(&vec![BaseElement::ZERO; context.num_transition_constraints()]);
+    let degrees = constraints
+        .main_constraint_degrees()
+        .iter()
+        .map(|degree| degree.get_evaluation_degree(trace_length).div_ceil(trace_length - 1));
+    CircuitReport { labeled_constraints: air.constraint_labels().into_iter().zip(degrees).collect(), ..report(air) }
+}
+
+/// Smallest blowup factor the constraints of `air` can be evaluated with
+pub fn min_blowup<A: Air<BaseField = BaseElement>>(air: &A) -> usize {
+    air.context().ce_domain_size() / air.trace_length()
+}
+
+/// Which trace columns the constraints of an AIR instance look at
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnCoverage {
+    /// Whether a transition constraint reads the column in the current or the next row
+    pub transition: Vec<bool>,
+    /// Whether an assertion pins a cell of the column
+    pub asserted: Vec<bool>,
+}
+
+impl ColumnCoverage {
+    /// Columns neither read by a transition constraint nor asserted
+    pub fn uncovered(&self) -> Vec<usize> {
+        (0..self.transition.len()).filter(|&column| !self.transition[column] && !self.asserted[column]).collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.uncovered().is_empty()
+    }
+}
+
+impl fmt::Display for ColumnCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (column, (&transition, &asserted)) in self.transition.iter().zip(&self.asserted).enumerate() {
+            let covered_by = match (transition, asserted) {
+                (true, true) => "transitions, assertions",
+                (true, false) => "transitions",
+                (false, true) => "assertions",
+                (false, false) => "UNCONSTRAINED",
+            };
+            writeln!(f, "column {column}: {covered_by}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reports which main trace columns the constraints of `air` look at
+pub fn coverage<A: Air<BaseField = BaseElement>>(air: &A) -> ColumnCoverage {
+    let width = air.context().trace_info().main_trace_width();
+    let num_constraints = air.context().num_main_transition_constraints();
+    let mut probe = probe_elements();
+    let current: Vec<BaseElement> = (0..width).map(|_| probe()).collect();
+    let next: Vec<BaseElement> = (0..width).map(|_| probe()).collect();
+    let periodic: Vec<BaseElement> = air.get_periodic_column_values().iter().map(|_| probe()).collect();
+
+    let evaluate = |current: &[BaseElement], next: &[BaseElement]| {
+        let frame = EvaluationFrame::from_rows(current.to_vec(), next.to_vec());
+        let mut result = vec![BaseElement::ZERO; num_constraints];
+        air.evaluate_transition(&frame, &periodic, &mut result);
+        result
+    };
+    let baseline = evaluate(&current, &next);
+    let transition = (0..width)
+        .map(|column| {
+            let delta = probe();
+            let (mut shifted_current, mut shifted_next) = (current.clone(), next.clone());
+            shifted_current[column] += delta;
+            shifted_next[column] += delta;
+            evaluate(&shifted_current, &next) != baseline || evaluate(&current, &shifted_next) != baseline
+        })
+        .collect();
+
+    let mut asserted = vec![false; width];
+    for assertion in air.get_assertions() {
+        asserted[assertion.column()] = true;
+    }
+    ColumnCoverage { transition, asserted }
+}
+
+/// Panics with the coverage report unless every column of the `A` instance is constrained
+pub fn assert_full_coverage<A: Air<BaseField = BaseElement>>(trace_info: TraceInfo, pub_inputs: A::PublicInputs) {
+    let options: ProofOptions = ProverConfig::default().to_proof_options();
+    let coverage = coverage(&A::new(trace_info, pub_inputs, options));
+    assert!(coverage.is_full(), "columns {:?} are unconstrained\n{coverage}", coverage.uncovered());
+}
+
+/// Deterministic stream of field elements for probing constraints
+fn probe_elements() -> impl FnMut() -> BaseElement {
+    let mut reader = blake3::Hasher::new().update(PROBE_DOMAIN).finalize_xof();
+    move || loop {
+        let mut bytes = [0u8; 16];
+        reader.fill(&mut bytes);
+        let value = u128::from_le_bytes(bytes);
+        if value < BaseElement::MODULUS {
+            return BaseElement::new(value);
+        }
+    }
+}
+
+impl fmt::Display for CircuitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "trace: {} columns x {} rows", self.trace_width, self.trace_length)?;
+        writeln!(f, "periodic columns: {}", self.num_periodic_columns)?;
+        writeln!(f, "transition constraints: {}", self.num_constraints())?;
+        for (degree, count) in &self.constraints_by_degree {
+            writeln!(f, "  degree {degree}: {count}")?;
+        }
+        for (i, (label, degree)) in self.labeled_constraints.iter().enumerate() {
+            writeln!(f, "  {i} {label}: degree {degree}")?;
+        }
+        writeln!(f, "assertions: {}", self.num_assertions)?;
+        writeln!(
+            f,
+            "composition polynomial: degree {} in {} columns",
+            self.composition_degree, self.composition_columns
+        )?;
+        writeln!(
+            f,
+            "LDE domain: {} rows (blowup {}), peak memory about {} MiB",
+            self.plan.lde_length(),
+            self.plan.blowup_factor,
+            self.plan.peak_memory_bytes() >> 20
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::regression::{LinearRegressionAir, LinearRegressionInputs, TRACE_WIDTH};
+    use winterfell::{AirContext, Assertion, TransitionConstraintDegree};
+
+    #[test]
+    fn test_linear_regression_report() {
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(8),
+            predicted_y: BaseElement::new(21),
+            sample_x_values: vec![BaseElement::new(1); 10],
+            sample_y_values: vec![BaseElement::new(7); 10],
+        };
+        let config = ProverConfig::default();
+        let air = LinearRegressionAir::new(TraceInfo::new(TRACE_WIDTH, 16), inputs, config.to_proof_options());
+        let report = report(&air);
+
+        assert_eq!((report.trace_width, report.trace_length), (TRACE_WIDTH, 16));
+        // the gated relationship, booleanity and ordering of the phase flags
+        assert_eq!(report.constraints_by_degree[&2], 7);
+        assert_eq!(report.constraints_by_degree[&1], 3);
+        assert_eq!(report.max_degree(), 2);
+        assert_eq!(report.num_assertions, 23);
+        assert_eq!((report.composition_degree, report.composition_columns), (15, 1));
+        assert_eq!(report.plan, ProvingPlan::linear(10, &config));
+
+        let calibration = Calibration { seconds_per_unit: 1e-6, overhead_seconds: 0.5 };
+        assert!(report.estimate(&calibration).seconds > 0.5);
+        assert!(report.to_string().contains("degree 2: 7"));
+        assert!(report.labeled_constraints.is_empty());
+
+        let labeled = labeled_report(&air);
+        assert_eq!(labeled.labeled_constraints.len(), report.num_constraints());
+        assert_eq!(labeled.labeled_constraints[1], ("slope_constant".to_string(), 1));
+        assert!(labeled.to_string().contains("  0 linear_relation: degree 2"));
+    }
+
+    /// Constrains its first column and forgets the second one
+    struct Forgetful(AirContext<BaseElement>);
+
+    impl Air for Forgetful {
+        type BaseField = BaseElement;
+        type PublicInputs = ();
+
+        fn new(trace_info: TraceInfo, _pub_inputs: (), options: ProofOptions) -> Self {
+            Self(AirContext::new(trace_info, vec![TransitionConstraintDegree::new(1)], 1, options))
+        }
+
+        fn context(&self) -> &AirContext<BaseElement> {
+            &self.0
+        }
+
+        fn evaluate_transition<E: FieldElement<BaseField = BaseElement>>(
+            &self,
+            frame: &EvaluationFrame<E>,
+            _periodic_values: &[E],
+            result: &mut [E],
+        ) {
+            result[0] = frame.next()[0] - frame.current()[0] - E::ONE;
+        }
+
+        fn get_assertions(&self) -> Vec<Assertion<BaseElement>> {
+            vec![Assertion::single(0, 0, BaseElement::ZERO)]
+        }
+    }
+
+    #[test]
+    fn test_constraint_coverage() {
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(8),
+            predicted_y: BaseElement::new(21),
+            sample_x_values: vec![BaseElement::new(1); 10],
+            sample_y_values: vec![BaseElement::new(7); 10],
+        };
+        assert_full_coverage::<LinearRegressionAir>(TraceInfo::new(TRACE_WIDTH, 16), inputs);
+
+        let (labels, predictions) = ([1, 2, 3], [1, 2, 0]);
+        let accuracy = crate::accuracy::AccuracyInputs::for_test_set(&labels, &predictions, 60);
+        let trace_info = TraceInfo::new(crate::accuracy::trace_width(), crate::accuracy::trace_length(3));
+        assert_full_coverage::<crate::accuracy::AccuracyAir>(trace_info, accuracy);
+
+        let randomness = crate::privacy::NoiseRandomness { draw: 3, salt: BaseElement::ONE };
+        let release = crate::privacy::NoisySumInputs::for_release(&[4, 9], &randomness, 4, 3);
+        let trace_info = TraceInfo::new(crate::privacy::trace_width(4, 3), crate::privacy::trace_length(2));
+        assert_full_coverage::<crate::privacy::NoisySumAir>(trace_info, release);
+
+        let read = crate::state::StateReadInputs { root: BaseElement::ONE, depth: 5, key: 3, value: BaseElement::ONE };
+        assert_full_coverage::<crate::state::StateReadAir>(TraceInfo::new(8, crate::state::trace_length(5)), read);
+
+        let coverage = coverage(&Forgetful::new(TraceInfo::new(2, 8), (), ProverConfig::default().to_proof_options()));
+        assert_eq!(coverage.uncovered(), [1]);
+        assert_eq!(coverage.transition, [true, false]);
+        assert!(coverage.to_string().contains("column 1: UNCONSTRAINED"));
+    }
+}
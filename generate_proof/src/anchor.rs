@@ -0,0 +1,211 @@
+//! One on-chain root for a batch of envelopes.
+//!
+//! Settling every envelope on-chain costs a verification each. An [`AnchorBatch`] instead
+//! Merkleizes the Keccak-256 hashes of the envelopes' on-chain encodings, and a single
+//! `ProofAnchor.anchorRoot` transaction (see `verifier/ProofAnchor.sol`) records the root.
+//! Anyone holding an envelope and its [`InclusionProof`] then shows that it was part of the
+//! anchored batch, on-chain with `ProofAnchor.isIncluded` or off-chain with
+//! [`verify_anchored`], which also verifies the proof itself.
+//!
+//! Leaves are `keccak256(0x00 || envelope hash)` and inner nodes `keccak256(0x01 || left ||
+//! right)`, so a node can never pass for a leaf. The tree is padded with zero leaves to a
+//! power of two.
+
+use core::fmt;
+
+use crate::abi::{encode_call, keccak256, uint_word};
+use crate::config::VerificationPolicy;
+use crate::envelope::ProofEnvelope;
+use crate::serialization::Encoding;
+use crate::session::{verify_linear, SessionError};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Longest inclusion path accepted; every level takes a bit of the index
+pub const MAX_DEPTH: usize = 63;
+
+/// Keccak-256 of the envelope's on-chain encoding, which a contract computes from its calldata
+pub fn envelope_hash(envelope: &ProofEnvelope) -> [u8; 32] {
+    keccak256(&Encoding::OnChain.encode(envelope))
+}
+
+fn leaf(envelope_hash: &[u8; 32]) -> [u8; 32] {
+    keccak256(&[&[LEAF_PREFIX][..], envelope_hash].concat())
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak256(&[&[NODE_PREFIX][..], left, right].concat())
+}
+
+/// Merkle tree over the hashes of a batch of envelopes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnchorBatch {
+    hashes: Vec<[u8; 32]>,
+    /// Every level of the padded tree, leaves first and the root last
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl AnchorBatch {
+    pub fn new(envelopes: &[ProofEnvelope]) -> Self {
+        Self::from_hashes(envelopes.iter().map(envelope_hash).collect())
+    }
+
+    /// Batch of envelopes given by their [`envelope_hash`]es, in order
+    pub fn from_hashes(hashes: Vec<[u8; 32]>) -> Self {
+        let mut leaves: Vec<[u8; 32]> = hashes.iter().map(leaf).collect();
+        leaves.resize(hashes.len().next_power_of_two(), [0u8; 32]);
+        let mut levels = vec![leaves];
+        while levels.last().expect("at least the leaves").len() > 1 {
+            let children = levels.last().expect("at least the leaves");
+            levels.push(children.chunks(2).map(|pair| node(&pair[0], &pair[1])).collect());
+        }
+        Self { hashes, levels }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().expect("at least the leaves")[0]
+    }
+
+    /// Proof that the `index`-th envelope is in the batch
+    pub fn inclusion_proof(&self, index: usize) -> Option<InclusionProof> {
+        let envelope_hash = *self.hashes.get(index)?;
+        let levels = &self.levels[..self.levels.len() - 1];
+        let siblings = levels.iter().enumerate().map(|(level, nodes)| nodes[(index >> level) ^ 1]).collect();
+        Some(InclusionProof { index, envelope_hash, siblings })
+    }
+
+    /// Calldata for `ProofAnchor.anchorRoot(bytes32 root, uint256 count)`
+    pub fn anchor_calldata(&self) -> Vec<u8> {
+        encode_call("anchorRoot(bytes32,uint256)", &[self.root(), uint_word(self.len() as u128)])
+    }
+}
+
+/// Path from one envelope of a batch to its root, sibling of the leaf first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub index: usize,
+    pub envelope_hash: [u8; 32],
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl InclusionProof {
+    /// Root the path leads to; index bits beyond the width of `usize` count as zero
+    pub fn root(&self) -> [u8; 32] {
+        self.siblings.iter().enumerate().fold(leaf(&self.envelope_hash), |current, (level, sibling)| {
+            let bit = self.index.checked_shr(level as u32).unwrap_or(0) & 1;
+            if bit == 0 { node(&current, sibling) } else { node(sibling, &current) }
+        })
+    }
+
+    /// Whether the path leads from `envelope` to `root`; paths deeper than [`MAX_DEPTH`] never do
+    pub fn verify(&self, envelope: &ProofEnvelope, root: &[u8; 32]) -> bool {
+        self.siblings.len() <= MAX_DEPTH
+            && self.index >> self.siblings.len() == 0
+            && envelope_hash(envelope) == self.envelope_hash
+            && self.root() == *root
+    }
+}
+
+/// Reason an envelope of an anchored batch is rejected
+#[derive(Debug)]
+pub enum AnchorError {
+    /// The inclusion proof does not lead from the envelope to the anchored root
+    NotIncluded,
+    Session(SessionError),
+}
+
+impl fmt::Display for AnchorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnchorError::NotIncluded => write!(f, "envelope is not part of the anchored batch"),
+            AnchorError::Session(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for AnchorError {}
+
+/// Checks that a linear claim envelope is in the batch anchored as `root` and verifies it
+/// under `policy`
+pub fn verify_anchored(
+    envelope: &ProofEnvelope,
+    inclusion: &InclusionProof,
+    root: &[u8; 32],
+    policy: &VerificationPolicy,
+) -> Result<(), AnchorError> {
+    if !inclusion.verify(envelope, root) {
+        return Err(AnchorError::NotIncluded);
+    }
+    verify_linear(envelope, policy).map_err(AnchorError::Session)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::{selector, to_hex};
+    use crate::config::ProverConfig;
+    use crate::session::{LinearClaimRequest, ProvingSession};
+
+    #[test]
+    fn test_anchor_batch() {
+        let mut session = ProvingSession::new(ProverConfig::default(), "ci");
+        let envelopes: Vec<ProofEnvelope> = (0..3)
+            .map(|x| {
+                let request = LinearClaimRequest {
+                    model: "demo".into(),
+                    salt: to_hex(&[3u8; 32]),
+                    slope: 2,
+                    intercept: 5,
+                    sample_x: vec![1, 3],
+                    x,
+                    schema: None,
+                    beacon: None,
+                    terms_hash: None,
+                };
+                session.prove_linear(&request).unwrap()
+            })
+            .collect();
+        let batch = AnchorBatch::new(&envelopes);
+        let root = batch.root();
+        let policy = VerificationPolicy::default();
+        for (i, envelope) in envelopes.iter().enumerate() {
+            let inclusion = batch.inclusion_proof(i).unwrap();
+            assert_eq!(inclusion.siblings.len(), 2);
+            verify_anchored(envelope, &inclusion, &root, &policy).unwrap();
+        }
+        assert_eq!(batch.inclusion_proof(3), None);
+
+        let inclusion = batch.inclusion_proof(0).unwrap();
+        assert!(matches!(verify_anchored(&envelopes[1], &inclusion, &root, &policy), Err(AnchorError::NotIncluded)));
+        let moved = InclusionProof { index: 1, ..inclusion.clone() };
+        assert!(!moved.verify(&envelopes[0], &root));
+        // untrusted paths of any depth are rejected rather than overflowing the index shift
+        let deep = InclusionProof { index: usize::MAX, siblings: vec![[0u8; 32]; 70], ..inclusion.clone() };
+        assert!(!deep.verify(&envelopes[0], &root));
+
+        let calldata = batch.anchor_calldata();
+        assert_eq!(calldata[..4], selector("anchorRoot(bytes32,uint256)"));
+        assert_eq!(calldata[4..36], root);
+        assert_eq!(calldata[36..], uint_word(3));
+        let single = AnchorBatch::from_hashes(vec![inclusion.envelope_hash]);
+        assert_eq!(single.root(), leaf(&inclusion.envelope_hash));
+        assert!(single.inclusion_proof(0).unwrap().siblings.is_empty());
+    }
+
+    #[test]
+    fn test_contract_matches_hashing() {
+        let source = include_str!("../../verifier/ProofAnchor.sol");
+        assert!(source.contains("function anchorRoot(bytes32 root, uint256 count)"));
+        assert!(source.contains(&format!("bytes1 internal constant LEAF_PREFIX = 0x{LEAF_PREFIX:02x};")));
+        assert!(source.contains(&format!("bytes1 internal constant NODE_PREFIX = 0x{NODE_PREFIX:02x};")));
+    }
+}
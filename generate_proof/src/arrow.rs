@@ -0,0 +1,317 @@
+//! Reading trace inputs from Arrow record batches (feature `arrow`).
+//!
+//! A [`TraceSchema`] lists the columns a trace builder consumes and how each is encoded.
+//! [`TraceSchema::validate`] checks a batch schema against it before any data is touched, and
+//! [`TraceSchema::quantize`] reads the primitive value buffers directly into fixed-point
+//! integers, which [`QuantizedBatch::field_column`] embeds into the field.
+
+use core::fmt;
+
+use arrow_array::{
+    cast::AsArray,
+    types::{
+        Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type, UInt64Type,
+        UInt8Type,
+    },
+    Array, ArrowPrimitiveType, RecordBatch,
+};
+use arrow_schema::{DataType, Schema};
+use winterfell::math::fields::f128::BaseElement;
+
+use crate::fixed_point::{quantize, to_field};
+
+/// How the values of a column become trace cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Integer column, used as is
+    Integer,
+    /// Real-valued column, quantized with `frac_bits` fractional bits; integer columns are scaled
+    FixedPoint { frac_bits: u32 },
+}
+
+impl Encoding {
+    fn accepts(&self, data_type: &DataType) -> bool {
+        match self {
+            Encoding::Integer => data_type.is_integer(),
+            Encoding::FixedPoint { .. } => {
+                data_type.is_integer() || matches!(data_type, DataType::Float32 | DataType::Float64)
+            }
+        }
+    }
+}
+
+/// A column a trace builder expects
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub encoding: Encoding,
+}
+
+/// Errors returned when a record batch does not match a [`TraceSchema`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaError {
+    MissingColumn(String),
+    UnsupportedType { column: String, data_type: DataType },
+    /// The column holds a null at `row`
+    Null { column: String, row: usize },
+    /// A floating-point value at `row` is NaN or infinite
+    NonFinite { column: String, row: usize },
+    /// The value at `row` does not fit an `i128` once scaled to fixed point
+    Overflow { column: String, row: usize },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::MissingColumn(column) => write!(f, "missing column `{column}`"),
+            SchemaError::UnsupportedType { column, data_type } => {
+                write!(f, "column `{column}` has unsupported type {data_type}")
+            }
+            SchemaError::Null { column, row } => write!(f, "column `{column}` is null at row {row}"),
+            SchemaError::NonFinite { column, row } => write!(f, "column `{column}` is not finite at row {row}"),
+            SchemaError::Overflow { column, row } => {
+                write!(f, "column `{column}` overflows fixed point at row {row}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Columns a trace builder reads from a record batch, in the order it reads them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceSchema {
+    columns: Vec<ColumnSpec>,
+}
+
+impl TraceSchema {
+    pub fn new(columns: Vec<ColumnSpec>) -> Self {
+        for column in &columns {
+            if let Encoding::FixedPoint { frac_bits } = column.encoding {
+                assert!(frac_bits < 64, "Fixed-point columns support at most 63 fractional bits");
+            }
+        }
+        Self { columns }
+    }
+
+    /// Integer `x` and `y` sample columns for [`crate::regression::build_linear_regression_trace`]
+    pub fn linear_regression() -> Self {
+        Self::new(vec![
+            ColumnSpec { name: "x".into(), encoding: Encoding::Integer },
+            ColumnSpec { name: "y".into(), encoding: Encoding::Integer },
+        ])
+    }
+
+    /// Real-valued `x` column for [`crate::standardize::build_standardized_trace`]
+    pub fn standardized(frac_bits: u32) -> Self {
+        Self::new(vec![ColumnSpec { name: "x".into(), encoding: Encoding::FixedPoint { frac_bits } }])
+    }
+
+    pub fn columns(&self) -> &[ColumnSpec] {
+        &self.columns
+    }
+
+    /// Checks that every expected column exists with a type its encoding accepts
+    pub fn validate(&self, schema: &Schema) -> Result<(), SchemaError> {
+        for spec in &self.columns {
+            let field = schema
+                .field_with_name(&spec.name)
+                .map_err(|_| SchemaError::MissingColumn(spec.name.clone()))?;
+            if !spec.encoding.accepts(field.data_type()) {
+                return Err(SchemaError::UnsupportedType {
+                    column: spec.name.clone(),
+                    data_type: field.data_type().clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates `batch` and quantizes the expected columns
+    pub fn quantize(&self, batch: &RecordBatch) -> Result<QuantizedBatch, SchemaError> {
+        self.validate(&batch.schema())?;
+        let columns = self
+            .columns
+            .iter()
+            .map(|spec| {
+                let array = batch.column_by_name(&spec.name).expect("validated column exists");
+                quantize_column(&spec.name, array.as_ref(), spec.encoding)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(QuantizedBatch { names: self.columns.iter().map(|spec| spec.name.clone()).collect(), columns })
+    }
+}
+
+/// Fixed-point columns read from a record batch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuantizedBatch {
+    names: Vec<String>,
+    columns: Vec<Vec<i128>>,
+}
+
+impl QuantizedBatch {
+    pub fn num_rows(&self) -> usize {
+        self.columns.first().map_or(0, Vec::len)
+    }
+
+    pub fn column(&self, name: &str) -> Option<&[i128]> {
+        let index = self.names.iter().position(|n| n == name)?;
+        Some(&self.columns[index])
+    }
+
+    /// The named column embedded into the field (see [`crate::fixed_point::to_field`])
+    pub fn field_column(&self, name: &str) -> Option<Vec<BaseElement>> {
+        Some(self.column(name)?.iter().map(|&raw| to_field(raw)).collect())
+    }
+}
+
+fn quantize_column(name: &str, array: &dyn Array, encoding: Encoding) -> Result<Vec<i128>, SchemaError> {
+    if array.null_count() > 0 {
+        let row = (0..array.len()).find(|&i| array.is_null(i)).unwrap_or(0);
+        return Err(SchemaError::Null { column: name.into(), row });
+    }
+    let shift = match encoding {
+        Encoding::Integer => 0,
+        Encoding::FixedPoint { frac_bits } => frac_bits,
+    };
+
+    let values = match array.data_type() {
+        DataType::Int8 => integers::<Int8Type>(array),
+        DataType::Int16 => integers::<Int16Type>(array),
+        DataType::Int32 => integers::<Int32Type>(array),
+        DataType::Int64 => integers::<Int64Type>(array),
+        DataType::UInt8 => integers::<UInt8Type>(array),
+        DataType::UInt16 => integers::<UInt16Type>(array),
+        DataType::UInt32 => integers::<UInt32Type>(array),
+        DataType::UInt64 => integers::<UInt64Type>(array),
+        DataType::Float32 => {
+            return reals(name, array.as_primitive::<Float32Type>().values().iter().map(|&v| v as f64), shift);
+        }
+        DataType::Float64 => {
+            return reals(name, array.as_primitive::<Float64Type>().values().iter().copied(), shift);
+        }
+        data_type => {
+            return Err(SchemaError::UnsupportedType { column: name.into(), data_type: data_type.clone() });
+        }
+    };
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(row, v)| scale(v, shift).ok_or_else(|| SchemaError::Overflow { column: name.into(), row }))
+        .collect()
+}
+
+/// `v * 2^shift`, or `None` when the product does not fit an `i128`
+fn scale(v: i128, shift: u32) -> Option<i128> {
+    let scaled = v.checked_shl(shift)?;
+    (scaled >> shift == v).then_some(scaled)
+}
+
+fn integers<T>(array: &dyn Array) -> Vec<i128>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Into<i128>,
+{
+    array.as_primitive::<T>().values().iter().map(|&v| v.into()).collect()
+}
+
+fn reals(name: &str, values: impl Iterator<Item = f64>, frac_bits: u32) -> Result<Vec<i128>, SchemaError> {
+    values
+        .enumerate()
+        .map(|(row, v)| {
+            if !v.is_finite() {
+                return Err(SchemaError::NonFinite { column: name.into(), row });
+            }
+            // `as i128` saturates, so reject anything whose scaled magnitude reaches 2^127
+            if v.abs() * (1u128 << frac_bits) as f64 >= i128::MAX as f64 {
+                return Err(SchemaError::Overflow { column: name.into(), row });
+            }
+            Ok(quantize(v, frac_bits))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Float64Array, Int64Array, StringArray, UInt32Array, UInt64Array};
+    use arrow_schema::Field;
+
+    use super::*;
+    use crate::regression::build_linear_regression_trace;
+    use winterfell::TraceInfo;
+
+    fn batch(columns: Vec<(&str, Arc<dyn Array>)>) -> RecordBatch {
+        RecordBatch::try_from_iter(columns).unwrap()
+    }
+
+    #[test]
+    fn test_quantize_feeds_trace_builder() {
+        let batch = batch(vec![
+            ("x", Arc::new(Int64Array::from(vec![1, 3, 7, 10])) as Arc<dyn Array>),
+            ("y", Arc::new(UInt32Array::from(vec![7, 11, 19, 25]))),
+        ]);
+        let columns = TraceSchema::linear_regression().quantize(&batch).unwrap();
+        assert_eq!(columns.column("y"), Some(&[7i128, 11, 19, 25][..]));
+
+        let xs = columns.field_column("x").unwrap();
+        let ys = columns.field_column("y").unwrap();
+        let trace = build_linear_regression_trace(
+            BaseElement::new(2),
+            BaseElement::new(5),
+            &xs,
+            &ys,
+            xs[0],
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        assert_eq!(trace.get(3, 2), BaseElement::new(19));
+
+        let reals = RecordBatch::try_from_iter(vec![(
+            "x",
+            Arc::new(Float64Array::from(vec![1.5, -0.25])) as Arc<dyn Array>,
+        )])
+        .unwrap();
+        let columns = TraceSchema::standardized(4).quantize(&reals).unwrap();
+        assert_eq!(columns.column("x"), Some(&[24i128, -4][..]));
+    }
+
+    #[test]
+    fn test_schema_validation() {
+        let schema = TraceSchema::linear_regression();
+        let missing = Schema::new(vec![Field::new("x", DataType::Int64, false)]);
+        assert_eq!(schema.validate(&missing), Err(SchemaError::MissingColumn("y".into())));
+
+        let floats = batch(vec![
+            ("x", Arc::new(Int64Array::from(vec![1])) as Arc<dyn Array>),
+            ("y", Arc::new(Float64Array::from(vec![1.0]))),
+        ]);
+        assert!(matches!(schema.quantize(&floats), Err(SchemaError::UnsupportedType { .. })));
+
+        let strings = batch(vec![("x", Arc::new(StringArray::from(vec!["1"])) as Arc<dyn Array>)]);
+        assert!(matches!(TraceSchema::standardized(8).quantize(&strings), Err(SchemaError::UnsupportedType { .. })));
+
+        let nulls = batch(vec![
+            ("x", Arc::new(Int64Array::from(vec![Some(1), None])) as Arc<dyn Array>),
+            ("y", Arc::new(Int64Array::from(vec![1, 2]))),
+        ]);
+        assert_eq!(schema.quantize(&nulls), Err(SchemaError::Null { column: "x".into(), row: 1 }));
+
+        let nan = batch(vec![("x", Arc::new(Float64Array::from(vec![0.0, f64::NAN])) as Arc<dyn Array>)]);
+        assert_eq!(
+            TraceSchema::standardized(8).quantize(&nan),
+            Err(SchemaError::NonFinite { column: "x".into(), row: 1 })
+        );
+
+        let wide = batch(vec![("x", Arc::new(UInt64Array::from(vec![u64::MAX])) as Arc<dyn Array>)]);
+        let wide = TraceSchema::standardized(63).quantize(&wide).unwrap();
+        assert_eq!(wide.column("x"), Some(&[i128::from(u64::MAX) << 63][..]));
+        assert_eq!(scale(1 << 100, 63), None);
+        assert_eq!(scale(-1 << 64, 63), Some(-1 << 127));
+        let huge = batch(vec![("x", Arc::new(Float64Array::from(vec![1e30])) as Arc<dyn Array>)]);
+        assert_eq!(
+            TraceSchema::standardized(63).quantize(&huge),
+            Err(SchemaError::Overflow { column: "x".into(), row: 0 })
+        );
+    }
+}
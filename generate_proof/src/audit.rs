@@ -0,0 +1,218 @@
+//! Append-only, hash-chained log of proving operations.
+//!
+//! Each line of the log is one JSON [`AuditRecord`]. A record's `hash` is
+//! `blake3(prev_hash || json(record without hash))`, and `prev_hash` is the hash of the
+//! preceding record (all zeros for the first one), so editing, dropping or reordering any
+//! record breaks the chain from that point on.
+
+use core::fmt;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::abi::{from_hex, to_hex};
+use crate::config::ProverConfig;
+
+/// What the caller knows about a proving operation before it is logged
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub operator: String,
+    pub air_id: String,
+    pub claim_hash: String,
+    pub config: ProverConfig,
+    pub wall_time_ms: u64,
+}
+
+/// A log line: the entry plus its position in the hash chain
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub sequence: u64,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// The hashed part of a record, i.e. everything but `hash`
+#[derive(Serialize)]
+struct UnsealedRecord<'a> {
+    sequence: u64,
+    timestamp: u64,
+    #[serde(flatten)]
+    entry: &'a AuditEntry,
+    prev_hash: &'a str,
+}
+
+impl AuditRecord {
+    fn seal(sequence: u64, timestamp: u64, entry: AuditEntry, prev_hash: String) -> Self {
+        let hash = record_hash(&UnsealedRecord { sequence, timestamp, entry: &entry, prev_hash: &prev_hash });
+        Self { sequence, timestamp, entry, prev_hash, hash }
+    }
+
+    fn expected_hash(&self) -> String {
+        record_hash(&UnsealedRecord {
+            sequence: self.sequence,
+            timestamp: self.timestamp,
+            entry: &self.entry,
+            prev_hash: &self.prev_hash,
+        })
+    }
+}
+
+fn record_hash(record: &UnsealedRecord<'_>) -> String {
+    let body = serde_json::to_vec(record).expect("Audit records always serialize");
+    let prev = from_hex(record.prev_hash).unwrap_or_default();
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&prev);
+    hasher.update(&body);
+    to_hex(hasher.finalize().as_bytes())
+}
+
+fn genesis_hash() -> String {
+    to_hex(&[0u8; 32])
+}
+
+/// Errors raised while writing or verifying an audit log
+#[derive(Debug)]
+pub enum AuditError {
+    Io(io::Error),
+    /// Line `line` (1-based) could not be parsed
+    Malformed { line: usize, reason: String },
+    /// The hash chain is broken at line `line` (1-based)
+    Broken { line: usize, reason: String },
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::Io(err) => write!(f, "audit log I/O error: {err}"),
+            AuditError::Malformed { line, reason } => write!(f, "malformed audit record on line {line}: {reason}"),
+            AuditError::Broken { line, reason } => write!(f, "audit chain broken on line {line}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<io::Error> for AuditError {
+    fn from(err: io::Error) -> Self {
+        AuditError::Io(err)
+    }
+}
+
+/// Writer for an audit log file; opening a log verifies the existing chain first
+pub struct AuditLog {
+    path: PathBuf,
+    file: File,
+    next_sequence: u64,
+    last_hash: String,
+}
+
+impl AuditLog {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        let path = path.as_ref().to_path_buf();
+        let records = if path.exists() { read_chain(&path)? } else { Vec::new() };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let (next_sequence, last_hash) = match records.last() {
+            Some(last) => (last.sequence + 1, last.hash.clone()),
+            None => (0, genesis_hash()),
+        };
+        Ok(Self { path, file, next_sequence, last_hash })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Seals `entry` onto the end of the chain and flushes it to disk
+    pub fn append(&mut self, entry: AuditEntry) -> Result<AuditRecord, AuditError> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let record = AuditRecord::seal(self.next_sequence, timestamp, entry, self.last_hash.clone());
+
+        let mut line = serde_json::to_string(&record).expect("Audit records always serialize");
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+
+        self.next_sequence += 1;
+        self.last_hash = record.hash.clone();
+        Ok(record)
+    }
+}
+
+/// Verifies the whole chain in `path`, returning the number of records
+pub fn verify_log(path: impl AsRef<Path>) -> Result<usize, AuditError> {
+    Ok(read_chain(path.as_ref())?.len())
+}
+
+fn read_chain(path: &Path) -> Result<Vec<AuditRecord>, AuditError> {
+    let contents = fs::read_to_string(path)?;
+    let mut records: Vec<AuditRecord> = Vec::new();
+    let mut prev_hash = genesis_hash();
+
+    for (i, text) in contents.lines().enumerate() {
+        let line = i + 1;
+        let record: AuditRecord = serde_json::from_str(text)
+            .map_err(|err| AuditError::Malformed { line, reason: err.to_string() })?;
+
+        if record.sequence != records.len() as u64 {
+            let reason = format!("expected sequence {}, found {}", records.len(), record.sequence);
+            return Err(AuditError::Broken { line, reason });
+        }
+        if record.prev_hash != prev_hash {
+            return Err(AuditError::Broken { line, reason: "previous hash does not match".into() });
+        }
+        if record.hash != record.expected_hash() {
+            return Err(AuditError::Broken { line, reason: "record hash does not match contents".into() });
+        }
+
+        prev_hash = record.hash.clone();
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(operator: &str) -> AuditEntry {
+        AuditEntry {
+            operator: operator.into(),
+            air_id: "linear-regression/v1".into(),
+            claim_hash: to_hex(&[7u8; 32]),
+            config: ProverConfig::default(),
+            wall_time_ms: 12,
+        }
+    }
+
+    #[test]
+    fn test_chain_detects_tampering() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut log = AuditLog::open(&path).unwrap();
+        log.append(entry("alice")).unwrap();
+        log.append(entry("bob")).unwrap();
+        drop(log);
+
+        // reopening continues the existing chain
+        let mut log = AuditLog::open(&path).unwrap();
+        assert_eq!(log.append(entry("carol")).unwrap().sequence, 2);
+        assert_eq!(verify_log(&path).unwrap(), 3);
+
+        let tampered = fs::read_to_string(&path).unwrap().replace("bob", "eve");
+        fs::write(&path, tampered).unwrap();
+        assert!(matches!(verify_log(&path), Err(AuditError::Broken { line: 2, .. })));
+        assert!(AuditLog::open(&path).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
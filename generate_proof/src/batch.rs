@@ -0,0 +1,284 @@
+//! Batch conversion into field elements and batched trace column writes.
+//!
+//! The per-value helpers in [`crate::fixed_point`] branch on the sign of every value, which
+//! dominates trace building for multi-million-row datasets. The functions here produce
+//! exactly the same elements with branch-free kernels over [`LANES`] values at a time.
+//!
+//! The portable kernels work on `u128`s, which the compiler does not vectorize: they avoid the
+//! branches and the libm call of rounding, not the per-value work. With the `simd` feature on
+//! x86-64, AVX2 kernels are picked at runtime when the CPU supports it. They build the two
+//! 64-bit limbs of four elements at once: a non-negative `v` is `(v, 0)` and a negative one
+//! `(v - 2^128 + p, u64::MAX)`; quantization rounds four `f64`s at a time and
+//! converts them exactly below `2^51`, leaving larger and non-finite lanes to the portable
+//! kernel. Run `benches/batch.rs` with and without the feature to compare.
+
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+use crate::fixed_point::to_field;
+
+/// Values processed per kernel step
+pub const LANES: usize = 8;
+
+/// Rows copied per tile by [`write_rows`], small enough for a tile to stay in L1; trace
+/// builders assemble blocks of this many rows
+pub const TILE_ROWS: usize = 64;
+
+/// `2^128 - p`, below `2^46`: a sign-extended negative `i64` is `2^128 - |v|`, its field
+/// encoding `p - |v|`
+const WRAP: u128 = u128::MAX - BaseElement::MODULUS + 1;
+
+#[inline(always)]
+fn i64_lane(value: i64) -> BaseElement {
+    let negative = (value >> 63) as i128 as u128;
+    BaseElement::new((value as i128 as u128).wrapping_sub(negative & WRAP))
+}
+
+/// `f64::round`, which rounds half away from zero, without the libm call: `x - trunc(x)` is
+/// exact, so comparing it against one half decides the rounding direction exactly
+#[inline(always)]
+fn round_lane(x: f64) -> f64 {
+    let truncated = x.trunc();
+    let step = if (x - truncated).abs() >= 0.5 { 1.0f64.copysign(x) } else { 0.0 };
+    truncated + step
+}
+
+#[inline(always)]
+fn i64_kernel(values: &[i64], out: &mut [BaseElement]) {
+    let mut chunks = values.chunks_exact(LANES);
+    let mut outputs = out.chunks_exact_mut(LANES);
+    for (chunk, output) in (&mut chunks).zip(&mut outputs) {
+        for (cell, &value) in output.iter_mut().zip(chunk) {
+            *cell = i64_lane(value);
+        }
+    }
+    for (cell, &value) in outputs.into_remainder().iter_mut().zip(chunks.remainder()) {
+        *cell = i64_lane(value);
+    }
+}
+
+#[inline(always)]
+fn quantize_kernel(values: &[f64], scale: f64, out: &mut [BaseElement]) {
+    const I64_RANGE: f64 = 9_223_372_036_854_775_808.0;
+    let mut rounded = [0.0f64; LANES];
+    for (chunk, output) in values.chunks(LANES).zip(out.chunks_mut(LANES)) {
+        for (lane, &value) in rounded.iter_mut().zip(chunk) {
+            *lane = round_lane(value * scale);
+        }
+        let lanes = &rounded[..chunk.len()];
+        if lanes.iter().all(|lane| lane.abs() < I64_RANGE) {
+            for (cell, &lane) in output.iter_mut().zip(lanes) {
+                *cell = i64_lane(lane as i64);
+            }
+        } else {
+            // Out-of-range and non-finite lanes saturate like `quantize` does
+            for (cell, &lane) in output.iter_mut().zip(lanes) {
+                *cell = to_field(lane as i128);
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    //! Elements are written as their canonical `u128`, low limb first, the layout
+    //! `FieldElement::elements_as_bytes` of Winterfell relies on as well.
+
+    use core::arch::x86_64::*;
+
+    use super::*;
+
+    const _: () = assert!(size_of::<BaseElement>() == 2 * size_of::<u64>());
+
+    /// `2^52 + 2^51`: adding it to an integral `f64` below `2^51` in magnitude leaves the
+    /// integer in the low mantissa bits
+    const MAGIC: f64 = 6_755_399_441_055_744.0;
+    /// `2^51`
+    const EXACT_RANGE: f64 = 2_251_799_813_685_248.0;
+
+    /// Writes the field encodings of the four `i64` lanes of `values` to `out`
+    ///
+    /// # Safety
+    ///
+    /// `out` must be valid for writing four elements.
+    #[target_feature(enable = "avx2")]
+    unsafe fn store_i64_lanes(values: __m256i, out: *mut BaseElement) {
+        let negative = _mm256_cmpgt_epi64(_mm256_setzero_si256(), values);
+        let low = _mm256_sub_epi64(values, _mm256_and_si256(negative, _mm256_set1_epi64x(WRAP as i64)));
+        // [l0 h0 l2 h2] and [l1 h1 l3 h3] into [l0 h0 l1 h1] and [l2 h2 l3 h3]
+        let even = _mm256_unpacklo_epi64(low, negative);
+        let odd = _mm256_unpackhi_epi64(low, negative);
+        let out = out.cast::<__m256i>();
+        // SAFETY: the caller guarantees four elements, 64 bytes, at `out`
+        unsafe {
+            _mm256_storeu_si256(out, _mm256_permute2x128_si256::<0x20>(even, odd));
+            _mm256_storeu_si256(out.add(1), _mm256_permute2x128_si256::<0x31>(even, odd));
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub fn i64_kernel_avx2(values: &[i64], out: &mut [BaseElement]) {
+        assert_eq!(values.len(), out.len());
+        let (chunks, rest) = values.as_chunks::<4>();
+        for (i, chunk) in chunks.iter().enumerate() {
+            // SAFETY: `out` is as long as `values`, so it holds the chunk's four elements
+            unsafe {
+                let lanes = _mm256_loadu_si256(chunk.as_ptr().cast());
+                store_i64_lanes(lanes, out.as_mut_ptr().add(4 * i));
+            }
+        }
+        i64_kernel(rest, &mut out[4 * chunks.len()..]);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub fn quantize_kernel_avx2(values: &[f64], scale: f64, out: &mut [BaseElement]) {
+        assert_eq!(values.len(), out.len());
+        let (factor, half, one) = (_mm256_set1_pd(scale), _mm256_set1_pd(0.5), _mm256_set1_pd(1.0));
+        let (sign, magic, limit) = (_mm256_set1_pd(-0.0), _mm256_set1_pd(MAGIC), _mm256_set1_pd(EXACT_RANGE));
+        let (chunks, rest) = values.as_chunks::<4>();
+        for (i, chunk) in chunks.iter().enumerate() {
+            // SAFETY: the chunk holds four values
+            let x = _mm256_mul_pd(unsafe { _mm256_loadu_pd(chunk.as_ptr()) }, factor);
+            // `round_lane`: one step away from zero when the dropped fraction is at least a half
+            let truncated = _mm256_round_pd::<{ _MM_FROUND_TO_ZERO | _MM_FROUND_NO_EXC }>(x);
+            let fraction = _mm256_andnot_pd(sign, _mm256_sub_pd(x, truncated));
+            let away = _mm256_cmp_pd::<_CMP_GE_OQ>(fraction, half);
+            let step = _mm256_and_pd(away, _mm256_or_pd(_mm256_and_pd(x, sign), one));
+            let rounded = _mm256_add_pd(truncated, step);
+            // false for NaN too, which saturates in the portable kernel
+            let exact = _mm256_cmp_pd::<_CMP_LT_OQ>(_mm256_andnot_pd(sign, rounded), limit);
+            if _mm256_movemask_pd(exact) != 0b1111 {
+                quantize_kernel(chunk, scale, &mut out[4 * i..4 * i + 4]);
+                continue;
+            }
+            let biased = _mm256_castpd_si256(_mm256_add_pd(rounded, magic));
+            let integers = _mm256_sub_epi64(biased, _mm256_castpd_si256(magic));
+            // SAFETY: `out` is as long as `values`, so it holds the chunk's four elements
+            unsafe { store_i64_lanes(integers, out.as_mut_ptr().add(4 * i)) };
+        }
+        quantize_kernel(rest, scale, &mut out[4 * chunks.len()..]);
+    }
+
+    pub fn has_avx2() -> bool {
+        std::arch::is_x86_feature_detected!("avx2")
+    }
+}
+
+/// Writes the field encoding of every value, as [`to_field`] would, into `out`.
+///
+/// # Panics
+///
+/// Panics if `out` is not as long as `values`.
+pub fn i64_to_field(values: &[i64], out: &mut [BaseElement]) {
+    assert_eq!(values.len(), out.len(), "Output must hold one element per value");
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if x86::has_avx2() {
+        // SAFETY: AVX2 support was detected at runtime
+        return unsafe { x86::i64_kernel_avx2(values, out) };
+    }
+    i64_kernel(values, out)
+}
+
+/// Writes `to_field(quantize(value, frac_bits))` for every value into `out`.
+///
+/// # Panics
+///
+/// Panics if `out` is not as long as `values`.
+pub fn quantize_to_field(values: &[f64], frac_bits: u32, out: &mut [BaseElement]) {
+    assert_eq!(values.len(), out.len(), "Output must hold one element per value");
+    let scale = (1u128 << frac_bits) as f64;
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if x86::has_avx2() {
+        // SAFETY: AVX2 support was detected at runtime
+        return unsafe { x86::quantize_kernel_avx2(values, scale, out) };
+    }
+    quantize_kernel(values, scale, out)
+}
+
+/// Field encodings of `values`, see [`i64_to_field`]
+pub fn field_column(values: &[i64]) -> Vec<BaseElement> {
+    let mut out = vec![BaseElement::new(0); values.len()];
+    i64_to_field(values, &mut out);
+    out
+}
+
+/// Field encodings of `values` quantized with `frac_bits` fractional bits, see [`quantize_to_field`]
+pub fn quantized_column(values: &[f64], frac_bits: u32) -> Vec<BaseElement> {
+    let mut out = vec![BaseElement::new(0); values.len()];
+    quantize_to_field(values, frac_bits, &mut out);
+    out
+}
+
+/// Copies the row-major block `rows` into `columns`, starting at row `first_row`.
+///
+/// Trace builders that assemble one row at a time and scatter it into every column touch a
+/// different cache line per cell. This transposes the block in tiles of rows instead, so each
+/// column receives one contiguous run per tile.
+///
+/// # Panics
+///
+/// Panics if `rows` is not a whole number of rows of `columns.len()` elements, or if the block
+/// runs past the end of a column.
+pub fn write_rows(columns: &mut [Vec<BaseElement>], first_row: usize, rows: &[BaseElement]) {
+    let width = columns.len();
+    assert!(width > 0 && rows.len().is_multiple_of(width), "Block must hold whole rows");
+    let num_rows = rows.len() / width;
+    for tile in (0..num_rows).step_by(TILE_ROWS) {
+        let tile_rows = TILE_ROWS.min(num_rows - tile);
+        for (c, column) in columns.iter_mut().enumerate() {
+            let target = &mut column[first_row + tile..first_row + tile + tile_rows];
+            for (r, cell) in target.iter_mut().enumerate() {
+                *cell = rows[(tile + r) * width + c];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::quantize;
+
+    #[test]
+    fn test_batches_match_scalar_conversion() {
+        let integers: Vec<i64> = [i64::MIN, i64::MIN + 1, -(1 << 40), -1, 0, 1, 7, i64::MAX]
+            .into_iter()
+            .cycle()
+            .take(21)
+            .collect();
+        let expected: Vec<BaseElement> = integers.iter().map(|&v| to_field(v as i128)).collect();
+        assert_eq!(field_column(&integers), expected);
+
+        let reals = [
+            -2.75, 0.49999999999999994, -0.5, 0.5, 2.5, -3.5, 1e300, f64::NEG_INFINITY, f64::NAN, 123.456, -7e-9,
+        ];
+        for frac_bits in [0, 16, 62] {
+            // covers both the in-range lanes and the saturating fallback
+            let expected: Vec<BaseElement> = reals.iter().map(|&v| to_field(quantize(v, frac_bits))).collect();
+            assert_eq!(quantized_column(&reals, frac_bits), expected, "frac_bits = {frac_bits}");
+            assert_eq!(quantized_column(&reals[..3], frac_bits), expected[..3]);
+        }
+
+        // halves of both signs, and whole chunks on either side of the exact range of the
+        // vector kernels
+        let edge = (1u64 << 51) as f64;
+        let mut reals: Vec<f64> = (-64..64).map(|i| i as f64 * 0.25).collect();
+        reals.extend([edge - 1.0, edge - 0.5, -edge + 0.5, 7.0, edge, -edge, edge + 2.0, 1.5]);
+        for frac_bits in [0, 2] {
+            let expected: Vec<BaseElement> = reals.iter().map(|&v| to_field(quantize(v, frac_bits))).collect();
+            assert_eq!(quantized_column(&reals, frac_bits), expected, "frac_bits = {frac_bits}");
+        }
+    }
+
+    #[test]
+    fn test_write_rows_transposes_blocks() {
+        let width = 3;
+        let rows: Vec<BaseElement> = (0..(150 * width) as u64).map(BaseElement::from).collect();
+        let mut columns = vec![vec![BaseElement::new(0); 160]; width];
+        write_rows(&mut columns, 5, &rows);
+        for (c, column) in columns.iter().enumerate() {
+            assert_eq!(column[4], BaseElement::new(0));
+            assert_eq!(column[5 + 149], BaseElement::from((149 * width + c) as u64));
+            assert_eq!(column[155], BaseElement::new(0));
+        }
+    }
+}
@@ -0,0 +1,459 @@
+//! Public randomness from a drand beacon, bound into claims.
+//!
+//! Claims about random samples of a dataset, or audit games where a verifier picks the points
+//! to be proven, need randomness the prover could not predict or choose. A [`BeaconRound`] is
+//! one output of a drand network: a BLS signature by the network's threshold key over the round
+//! number (and, on chained networks, the previous signature), whose SHA-256 is the round's
+//! randomness. Nobody learns a round's value before the network publishes it.
+//!
+//! A request that carries a round has it copied into the envelope, where it becomes part of the
+//! claim hash. Verifiers whose [`VerificationPolicy`](crate::config::VerificationPolicy) names
+//! a [`DrandChain`] check the beacon signature off-circuit before accepting the envelope, which
+//! needs the `beacon` feature; without one the round is hashed into the claim but not checked.
+//! [`BeaconRound::challenge`] and [`BeaconRound::sample_indices`] derive values from the round;
+//! a linear claim bound to a round is proven at the sample points and prediction point
+//! [`BeaconRound::claim_points`] draws, which every verifier recomputes. A policy's
+//! `max_beacon_age` bounds how old the round may be, so a prover cannot keep a favourable round
+//! around, see [`DrandChain::earliest_round`].
+//!
+//! [`BeaconClient`] fetches rounds and chain information from a drand HTTP relay through the
+//! same client as [`Resolver`](crate::resolver::Resolver), in builds with the `http` feature.
+
+use core::fmt;
+use std::time::Duration;
+
+#[cfg(feature = "beacon")]
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+#[cfg(feature = "beacon")]
+use bls12_381::{pairing, G1Affine, G1Projective, G2Affine, G2Projective};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use winterfell::math::fields::f128::BaseElement;
+
+use crate::abi::from_hex;
+use crate::domain::DomainTag;
+use crate::resolver::{http_request, ResolveError, DEFAULT_TIMEOUT};
+
+const CHALLENGE_DOMAIN: &[u8] = b"stark-framework/beacon-challenge/v1";
+const SAMPLE_DOMAIN: &[u8] = b"stark-framework/beacon-sample/v1";
+const POINT_DOMAIN: &[u8] = b"stark-framework/beacon-point/v1";
+/// Hash-to-curve tags of the two drand schemes
+#[cfg(feature = "beacon")]
+const G1_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+#[cfg(feature = "beacon")]
+const G2_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+/// Bytes packed into one field element; 15 bytes always stay below the modulus
+const BYTES_PER_ELEMENT: usize = 15;
+
+/// Signature scheme of a drand network, named by its `schemeID`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrandScheme {
+    /// The original mainnet chain: G2 signatures over the previous signature and the round
+    #[serde(rename = "pedersen-bls-chained")]
+    PedersenBlsChained,
+    /// quicknet: G1 signatures over the round alone
+    #[serde(rename = "bls-unchained-g1-rfc9380")]
+    BlsUnchainedG1,
+}
+
+/// Public parameters of a drand network, as served by its `/info` endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrandChain {
+    #[serde(rename = "schemeID")]
+    pub scheme: DrandScheme,
+    /// Hex-encoded compressed group public key
+    pub public_key: String,
+    /// Unix time of round 1
+    pub genesis_time: u64,
+    /// Seconds between rounds
+    pub period: u64,
+}
+
+impl DrandChain {
+    /// The latest round published at unix time `time`, 0 before genesis
+    pub fn round_at(&self, time: u64) -> u64 {
+        match time.checked_sub(self.genesis_time) {
+            Some(elapsed) => elapsed / self.period.max(1) + 1,
+            None => 0,
+        }
+    }
+
+    /// The earliest round a verifier at unix time `now` accepts when rounds may be at most
+    /// `max_age` seconds old
+    pub fn earliest_round(&self, now: u64, max_age: u64) -> u64 {
+        self.round_at(now.saturating_sub(max_age)).max(1)
+    }
+}
+
+/// One drand output, as served by `/public/{round}`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconRound {
+    pub round: u64,
+    /// Hex-encoded SHA-256 of the signature
+    pub randomness: String,
+    /// Hex-encoded compressed BLS signature
+    pub signature: String,
+    /// Signature of the previous round, only on chained networks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_signature: Option<String>,
+}
+
+/// Reason a beacon round could not be fetched or is not authentic
+#[derive(Debug)]
+pub enum BeaconError {
+    Request(ResolveError),
+    Malformed(String),
+    /// The randomness is not the hash of the signature
+    RandomnessMismatch,
+    /// A chained round without the previous signature
+    MissingPreviousSignature,
+    /// The public key or the signature is not a point of the expected group
+    InvalidPoint,
+    BadSignature,
+    /// Checking the signature needs a build with the `beacon` feature
+    Unsupported,
+    /// The policy names a beacon chain but the envelope carries no round
+    MissingRound,
+    /// The round was published before the earliest round the policy accepts
+    StaleRound { round: u64, earliest: u64 },
+    /// The claim's sample points or prediction point are not the ones drawn from its round
+    PointsMismatch,
+}
+
+impl fmt::Display for BeaconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BeaconError::Request(err) => write!(f, "beacon request failed: {err}"),
+            BeaconError::Malformed(reason) => write!(f, "malformed beacon response: {reason}"),
+            BeaconError::RandomnessMismatch => write!(f, "beacon randomness is not the hash of its signature"),
+            BeaconError::MissingPreviousSignature => write!(f, "chained beacon round lacks the previous signature"),
+            BeaconError::InvalidPoint => write!(f, "beacon key or signature is not a valid curve point"),
+            BeaconError::BadSignature => write!(f, "beacon signature does not verify under the chain key"),
+            BeaconError::Unsupported => write!(f, "checking beacon signatures needs a build with the `beacon` feature"),
+            BeaconError::MissingRound => write!(f, "envelope carries no beacon round"),
+            BeaconError::StaleRound { round, earliest } => {
+                write!(f, "beacon round {round} is older than the earliest accepted round {earliest}")
+            }
+            BeaconError::PointsMismatch => write!(f, "claim points are not the ones drawn from the beacon round"),
+        }
+    }
+}
+
+impl std::error::Error for BeaconError {}
+
+fn hex_bytes(hex: &str) -> Result<Vec<u8>, BeaconError> {
+    from_hex(hex).ok_or_else(|| BeaconError::Malformed(format!("`{hex}` is not hex")))
+}
+
+#[cfg(feature = "beacon")]
+fn point<const N: usize>(hex: &str) -> Result<[u8; N], BeaconError> {
+    hex_bytes(hex)?.try_into().map_err(|_| BeaconError::InvalidPoint)
+}
+
+impl BeaconRound {
+    /// The 32 bytes of randomness
+    pub fn randomness_bytes(&self) -> Result<[u8; 32], BeaconError> {
+        hex_bytes(&self.randomness)?.try_into().map_err(|_| BeaconError::Malformed("randomness is not 32 bytes".into()))
+    }
+
+    /// The message the network signed for this round
+    #[cfg(feature = "beacon")]
+    fn message(&self, scheme: DrandScheme) -> Result<Vec<u8>, BeaconError> {
+        let mut hasher = Sha256::new();
+        if scheme == DrandScheme::PedersenBlsChained {
+            let previous = self.previous_signature.as_deref().ok_or(BeaconError::MissingPreviousSignature)?;
+            hasher.update(hex_bytes(previous)?);
+        }
+        hasher.update(self.round.to_be_bytes());
+        Ok(hasher.finalize().to_vec())
+    }
+
+    /// Checks that the round was signed by `chain` and that its randomness derives from the signature
+    pub fn verify(&self, chain: &DrandChain) -> Result<(), BeaconError> {
+        let signature = hex_bytes(&self.signature)?;
+        if Sha256::digest(&signature).as_slice() != self.randomness_bytes()? {
+            return Err(BeaconError::RandomnessMismatch);
+        }
+        self.verify_signature(chain)
+    }
+
+    #[cfg(not(feature = "beacon"))]
+    fn verify_signature(&self, _chain: &DrandChain) -> Result<(), BeaconError> {
+        Err(BeaconError::Unsupported)
+    }
+
+    #[cfg(feature = "beacon")]
+    fn verify_signature(&self, chain: &DrandChain) -> Result<(), BeaconError> {
+        let message = self.message(chain.scheme)?;
+        let valid = match chain.scheme {
+            DrandScheme::BlsUnchainedG1 => {
+                let key = Option::from(G2Affine::from_compressed(&point(&chain.public_key)?));
+                let signature = Option::from(G1Affine::from_compressed(&point(&self.signature)?));
+                let (key, signature): (G2Affine, G1Affine) = key.zip(signature).ok_or(BeaconError::InvalidPoint)?;
+                let hashed = <G1Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(&message, G1_DST);
+                pairing(&signature, &G2Affine::generator()) == pairing(&G1Affine::from(hashed), &key)
+            }
+            DrandScheme::PedersenBlsChained => {
+                let key = Option::from(G1Affine::from_compressed(&point(&chain.public_key)?));
+                let signature = Option::from(G2Affine::from_compressed(&point(&self.signature)?));
+                let (key, signature): (G1Affine, G2Affine) = key.zip(signature).ok_or(BeaconError::InvalidPoint)?;
+                let hashed = <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(&message, G2_DST);
+                pairing(&G1Affine::generator(), &signature) == pairing(&key, &G2Affine::from(hashed))
+            }
+        };
+        if valid { Ok(()) } else { Err(BeaconError::BadSignature) }
+    }
+
+    /// Feeds the round and its randomness into `hasher`
+    pub fn absorb_into(&self, hasher: &mut blake3::Hasher) {
+        hasher.update(&self.round.to_le_bytes());
+        hasher.update(&self.randomness_bytes().unwrap_or_default());
+    }
+
+    /// Challenge of deployment `domain` derived from this round, e.g. to seed an audit game
+    pub fn challenge(&self, domain: &DomainTag) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(CHALLENGE_DOMAIN);
+        domain.absorb_into(&mut hasher);
+        self.absorb_into(&mut hasher);
+        *hasher.finalize().as_bytes()
+    }
+
+    /// [`BeaconRound::challenge`] as field elements, for AIRs taking it as a public input
+    pub fn challenge_elements(&self, domain: &DomainTag) -> Vec<BaseElement> {
+        let challenge = self.challenge(domain);
+        challenge
+            .chunks(BYTES_PER_ELEMENT)
+            .map(|chunk| {
+                let mut bytes = [0u8; 16];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                BaseElement::new(u128::from_le_bytes(bytes))
+            })
+            .collect()
+    }
+
+    /// `count` distinct indices below `population`, in the order they were drawn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` exceeds `population`.
+    pub fn sample_indices(&self, domain: &DomainTag, population: usize, count: usize) -> Vec<usize> {
+        assert!(count <= population, "Cannot sample {count} distinct indices out of {population}");
+        let mut stream = self.stream(SAMPLE_DOMAIN, domain, population as u64);
+        let mut indices = Vec::with_capacity(count);
+        while indices.len() < count {
+            let index = draw(&mut stream, population as u64) as usize;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        indices
+    }
+
+    /// `count` distinct sample points and a prediction point in `min..=max`, the points a
+    /// linear claim bound to this round is proven at; `None` if the range holds fewer than
+    /// `count` points
+    pub fn claim_points(&self, domain: &DomainTag, min: u64, max: u64, count: usize) -> Option<(Vec<u64>, u64)> {
+        // a span of zero stands for the whole u64 range
+        let span = max.checked_sub(min)?.wrapping_add(1);
+        if span != 0 && count as u64 > span {
+            return None;
+        }
+        let mut stream = self.stream(POINT_DOMAIN, domain, span);
+        let x = min + draw(&mut stream, span);
+        let mut sample_x = Vec::with_capacity(count);
+        while sample_x.len() < count {
+            let point = min + draw(&mut stream, span);
+            if !sample_x.contains(&point) {
+                sample_x.push(point);
+            }
+        }
+        Some((sample_x, x))
+    }
+
+    /// Output stream of `label` over the challenge of `domain` and `bound`
+    fn stream(&self, label: &[u8], domain: &DomainTag, bound: u64) -> blake3::OutputReader {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(label);
+        hasher.update(&self.challenge(domain));
+        hasher.update(&bound.to_le_bytes());
+        hasher.finalize_xof()
+    }
+}
+
+/// A uniform value below `bound` read from `stream`, any value for a `bound` of zero
+fn draw(stream: &mut blake3::OutputReader, bound: u64) -> u64 {
+    loop {
+        let mut word = [0u8; 8];
+        stream.fill(&mut word);
+        let value = u64::from_le_bytes(word);
+        // rejection sampling below the largest multiple of `bound` keeps values uniform
+        match bound {
+            0 => return value,
+            bound if value < u64::MAX - u64::MAX % bound => return value % bound,
+            _ => {}
+        }
+    }
+}
+
+/// Fetches rounds from a drand HTTP relay
+#[derive(Clone, Debug)]
+pub struct BeaconClient {
+    url: String,
+    timeout: Duration,
+}
+
+impl BeaconClient {
+    /// Client for the chain served under `url`, e.g. `http://relay/{chain hash}`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), timeout: DEFAULT_TIMEOUT }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn get<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T, BeaconError> {
+        let url = format!("{}/{path}", self.url.trim_end_matches('/'));
+        let (status, body) = http_request(&url, None, self.timeout).map_err(BeaconError::Request)?;
+        if status != 200 {
+            return Err(BeaconError::Request(ResolveError::Status(status)));
+        }
+        serde_json::from_str(&body).map_err(|err| BeaconError::Malformed(err.to_string()))
+    }
+
+    pub fn info(&self) -> Result<DrandChain, BeaconError> {
+        self.get("info")
+    }
+
+    /// Round `round`, or the latest one without it
+    pub fn round(&self, round: Option<u64>) -> Result<BeaconRound, BeaconError> {
+        match round {
+            Some(round) => self.get(&format!("public/{round}")),
+            None => self.get("public/latest"),
+        }
+    }
+
+    /// Fetches `round` and checks it against `chain`
+    pub fn verified_round(&self, chain: &DrandChain, round: Option<u64>) -> Result<BeaconRound, BeaconError> {
+        let beacon = self.round(round)?;
+        beacon.verify(chain)?;
+        Ok(beacon)
+    }
+}
+
+#[cfg(all(test, feature = "beacon"))]
+mod tests {
+    use super::*;
+    use crate::abi::to_hex;
+    use bls12_381::Scalar;
+
+    /// Hex without the `0x` prefix, as drand encodes its values
+    fn drand_hex(bytes: &[u8]) -> String {
+        to_hex(bytes).split_off(2)
+    }
+
+    /// A network with secret key `secret` signing `round` like drand does
+    fn signed_round(scheme: DrandScheme, secret: u64, round: u64, previous: Option<&str>) -> (DrandChain, BeaconRound) {
+        let secret = Scalar::from(secret);
+        let mut beacon = BeaconRound {
+            round,
+            randomness: String::new(),
+            signature: String::new(),
+            previous_signature: previous.map(String::from),
+        };
+        let message = beacon.message(scheme).unwrap();
+        let (public_key, signature) = match scheme {
+            DrandScheme::BlsUnchainedG1 => {
+                let hashed = <G1Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(&message, G1_DST);
+                let key = G2Affine::from(G2Affine::generator() * secret).to_compressed().to_vec();
+                (key, G1Affine::from(hashed * secret).to_compressed().to_vec())
+            }
+            DrandScheme::PedersenBlsChained => {
+                let hashed = <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(&message, G2_DST);
+                let key = G1Affine::from(G1Affine::generator() * secret).to_compressed().to_vec();
+                (key, G2Affine::from(hashed * secret).to_compressed().to_vec())
+            }
+        };
+        beacon.randomness = drand_hex(&Sha256::digest(&signature));
+        beacon.signature = drand_hex(&signature);
+        let chain = DrandChain { scheme, public_key: drand_hex(&public_key), genesis_time: 1_692_803_367, period: 3 };
+        (chain, beacon)
+    }
+
+    #[test]
+    fn test_beacon_rounds_verify() {
+        let (chain, beacon) = signed_round(DrandScheme::BlsUnchainedG1, 0x5eed, 1000, None);
+        beacon.verify(&chain).unwrap();
+        let (other_chain, _) = signed_round(DrandScheme::BlsUnchainedG1, 0x5eee, 1000, None);
+        assert!(matches!(beacon.verify(&other_chain), Err(BeaconError::BadSignature)));
+        let replayed = BeaconRound { round: 1001, ..beacon.clone() };
+        assert!(matches!(replayed.verify(&chain), Err(BeaconError::BadSignature)));
+        let chosen = BeaconRound { randomness: drand_hex(&[7u8; 32]), ..beacon.clone() };
+        assert!(matches!(chosen.verify(&chain), Err(BeaconError::RandomnessMismatch)));
+
+        let previous = drand_hex(&[3u8; 96]);
+        let (chained, round) = signed_round(DrandScheme::PedersenBlsChained, 0x5eed, 7, Some(&previous));
+        round.verify(&chained).unwrap();
+        let unlinked = BeaconRound { previous_signature: None, ..round };
+        assert!(matches!(unlinked.verify(&chained), Err(BeaconError::MissingPreviousSignature)));
+
+        assert_eq!(chain.round_at(chain.genesis_time - 1), 0);
+        assert_eq!(chain.round_at(chain.genesis_time + 7), 3);
+        assert_eq!(chain.earliest_round(chain.genesis_time + 3000, 30), 991);
+        assert_eq!(chain.earliest_round(chain.genesis_time + 3000, 6000), 1);
+
+        let domain = DomainTag::default();
+        let indices = beacon.sample_indices(&domain, 10, 10);
+        let mut sorted = indices.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+        assert_eq!(beacon.sample_indices(&domain, 1000, 5), beacon.sample_indices(&domain, 1000, 5));
+        assert_ne!(beacon.challenge(&domain), beacon.challenge(&DomainTag::new("other-app").unwrap()));
+        assert_eq!(beacon.challenge_elements(&domain).len(), 3);
+
+        let (sample_x, x) = beacon.claim_points(&domain, 10, 19, 10).unwrap();
+        let mut sorted = sample_x.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (10..20).collect::<Vec<_>>());
+        assert!((10..20).contains(&x));
+        assert_eq!(beacon.claim_points(&domain, 10, 19, 11), None);
+        assert_eq!(beacon.claim_points(&domain, 0, u64::MAX, 3), beacon.claim_points(&domain, 0, u64::MAX, 3));
+        let other = beacon.claim_points(&DomainTag::new("other-app").unwrap(), 0, 1000, 3);
+        assert_ne!(beacon.claim_points(&domain, 0, 1000, 3), other);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_client_fetches_rounds() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let (chain, beacon) = signed_round(DrandScheme::BlsUnchainedG1, 42, 12, None);
+        let body = serde_json::to_string(&beacon).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // drain the headers so closing the socket does not reset the connection
+                let mut header = String::from("-");
+                while !header.trim().is_empty() {
+                    header.clear();
+                    reader.read_line(&mut header).unwrap();
+                }
+                let found = request_line.starts_with("GET /chain/public/12 ");
+                let (status, body) = if found { (200, body.as_str()) } else { (404, "") };
+                write!(stream, "HTTP/1.1 {status} OK\r\nContent-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+            }
+        });
+
+        let client = BeaconClient::new(format!("http://{address}/chain"));
+        assert_eq!(client.verified_round(&chain, Some(12)).unwrap(), beacon);
+        assert!(matches!(client.round(Some(13)), Err(BeaconError::Request(ResolveError::Status(404)))));
+    }
+}
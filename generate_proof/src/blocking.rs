@@ -0,0 +1,310 @@
+//! Async wrappers for proving and verification.
+//!
+//! [`prove`] and [`verify`] block the calling thread for the whole run of the prover, which
+//! stalls every other task of an async runtime's worker thread. [`prove_async`] and
+//! [`verify_async`] instead queue the work on a [`BlockingPool`] of dedicated threads and
+//! return a [`BlockingTask`] future that completes with the result. The futures only use
+//! `std::task`, so they run on tokio or any other executor without a dependency on it.
+//!
+//! Every call takes a [`CancellationToken`]. Cancelling it completes the futures that hold
+//! it with [`TaskError::Cancelled`] right away, and jobs that have not started yet are
+//! dropped without running. A proof that is already running cannot be interrupted inside
+//! Winterfell; it finishes on its pool thread and its result is discarded.
+
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+
+use winterfell::{
+    math::fields::f128::BaseElement, AcceptableOptions, Air, Proof, ProofOptions, ProverError, TraceTable,
+    VerifierError,
+};
+
+use crate::prover::{prove, verify};
+
+/// Shared flag cancelling the tasks it was handed to
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenState>,
+}
+
+#[derive(Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    /// Tasks to wake when the token is cancelled
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        for waker in self.inner.wakers.lock().expect("Token lock is not poisoned").drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.inner.wakers.lock().expect("Token lock is not poisoned");
+        if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    fn unregister(&self, waker: &Waker) {
+        self.inner.wakers.lock().expect("Token lock is not poisoned").retain(|registered| !registered.will_wake(waker));
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken").field("cancelled", &self.is_cancelled()).finish()
+    }
+}
+
+/// Reason a [`BlockingTask`] did not produce a value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaskError<E> {
+    Cancelled,
+    Failed(E),
+}
+
+impl<E: fmt::Display> fmt::Display for TaskError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::Cancelled => write!(f, "task was cancelled"),
+            TaskError::Failed(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for TaskError<E> {}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Dedicated threads running blocking jobs in submission order
+pub struct BlockingPool {
+    sender: Mutex<mpsc::Sender<Job>>,
+}
+
+impl BlockingPool {
+    /// Pool of `num_threads` threads (0 for one per core), which live as long as the pool
+    pub fn new(num_threads: usize) -> Self {
+        let num_threads = match num_threads {
+            0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+            n => n,
+        };
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..num_threads {
+            let receiver = Arc::clone(&receiver);
+            thread::Builder::new()
+                .name(format!("stark-blocking-{i}"))
+                .spawn(move || loop {
+                    let job = receiver.lock().expect("Pool queue lock is not poisoned").recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .expect("Blocking pool threads can be spawned");
+        }
+        Self { sender: Mutex::new(sender) }
+    }
+
+    /// Pool of one thread per core shared by [`prove_async`] and [`verify_async`]
+    pub fn global() -> &'static BlockingPool {
+        static POOL: OnceLock<BlockingPool> = OnceLock::new();
+        POOL.get_or_init(|| BlockingPool::new(0))
+    }
+
+    /// Runs `job` on a pool thread unless `token` is cancelled before it starts
+    pub fn spawn<T, E, F>(&self, token: &CancellationToken, job: F) -> BlockingTask<T, E>
+    where
+        T: Send + 'static,
+        E: Send + 'static,
+        F: FnOnce() -> Result<T, E> + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Slot { outcome: None, waker: None }));
+        let (slot, job_token) = (Arc::clone(&shared), token.clone());
+        let job: Job = Box::new(move || {
+            if job_token.is_cancelled() {
+                return;
+            }
+            let outcome = panic::catch_unwind(AssertUnwindSafe(job));
+            let mut slot = slot.lock().expect("Task slot lock is not poisoned");
+            slot.outcome = Some(outcome);
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        });
+        self.sender.lock().expect("Pool sender lock is not poisoned").send(job).expect("Pool threads are running");
+        BlockingTask { slot: shared, token: token.clone() }
+    }
+}
+
+struct Slot<T, E> {
+    outcome: Option<thread::Result<Result<T, E>>>,
+    waker: Option<Waker>,
+}
+
+/// Future of a job running on a [`BlockingPool`]; a panic of the job is resumed in the task
+/// awaiting it
+pub struct BlockingTask<T, E> {
+    slot: Arc<Mutex<Slot<T, E>>>,
+    token: CancellationToken,
+}
+
+impl<T, E> Future for BlockingTask<T, E> {
+    type Output = Result<T, TaskError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(Err(TaskError::Cancelled));
+        }
+        let mut slot = self.slot.lock().expect("Task slot lock is not poisoned");
+        let outcome = slot.outcome.take();
+        if outcome.is_some() {
+            // long-lived tokens would otherwise keep the wakers of every finished task
+            self.token.unregister(cx.waker());
+        }
+        match outcome {
+            Some(Ok(result)) => Poll::Ready(result.map_err(TaskError::Failed)),
+            Some(Err(panic)) => panic::resume_unwind(panic),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                drop(slot);
+                self.token.register(cx.waker());
+                // a cancellation between the first check and the registration has no waker
+                // to wake, so check once more
+                if self.token.is_cancelled() { Poll::Ready(Err(TaskError::Cancelled)) } else { Poll::Pending }
+            }
+        }
+    }
+}
+
+/// [`prove`] on the [`BlockingPool::global`] pool
+pub fn prove_async<A>(
+    trace: TraceTable<BaseElement>,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+    token: &CancellationToken,
+) -> BlockingTask<Proof, ProverError>
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Clone + Send + Sync + 'static,
+{
+    BlockingPool::global().spawn(token, move || prove::<A>(trace, pub_inputs, options))
+}
+
+/// [`verify`] on the [`BlockingPool::global`] pool
+pub fn verify_async<A>(
+    proof: Proof,
+    pub_inputs: A::PublicInputs,
+    acceptable_options: AcceptableOptions,
+    token: &CancellationToken,
+) -> BlockingTask<(), VerifierError>
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Send + 'static,
+{
+    BlockingPool::global().spawn(token, move || verify::<A>(proof, pub_inputs, &acceptable_options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use winterfell::TraceInfo;
+
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    struct Unpark(thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor: polls `future` on the current thread, parking between wakeups
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = core::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_prove_and_verify_async() {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(1));
+        let xs = vec![BaseElement::new(2), BaseElement::new(4)];
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(5),
+            predicted_y: BaseElement::new(16),
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+        let trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(5),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let options = ProverConfig::default().to_proof_options();
+
+        let token = CancellationToken::new();
+        let proof = block_on(prove_async::<LinearRegressionAir>(trace.clone(), inputs.clone(), options.clone(), &token))
+            .unwrap();
+        let verify = |proof, inputs| {
+            block_on(verify_async::<LinearRegressionAir>(proof, inputs, default_acceptable_options(), &token))
+        };
+        verify(proof.clone(), inputs.clone()).unwrap();
+        let wrong = LinearRegressionInputs { predicted_y: BaseElement::new(17), ..inputs.clone() };
+        let rejected = verify(proof, wrong);
+        assert!(matches!(rejected, Err(TaskError::Failed(_))));
+
+        // a cancelled task completes at once and its job never runs
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let task = prove_async::<LinearRegressionAir>(trace, inputs, options, &cancelled);
+        assert_eq!(block_on(task).unwrap_err(), TaskError::Cancelled);
+    }
+
+    #[test]
+    fn test_cancel_wakes_pending_task() {
+        let pool = BlockingPool::new(1);
+        let (release, blocked) = mpsc::channel::<()>();
+        let token = CancellationToken::new();
+        // the only pool thread waits until the task has been cancelled
+        let task = pool.spawn(&token, move || blocked.recv().map_err(|_| "sender dropped"));
+        let canceller = token.clone();
+        thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(20));
+            canceller.cancel();
+        });
+        assert_eq!(block_on(task), Err(TaskError::Cancelled));
+        release.send(()).unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+//! Builder for the boundary constraints (assertions) of an AIR.
+//!
+//! Winterfell turns every [`Assertion`] into one boundary constraint the verifier evaluates at
+//! each query, so a column carrying one sample per row with a single assertion per sample
+//! costs the verifier work linear in the sample count. Layouts that place samples at a fixed
+//! power-of-two stride can instead use one sequence assertion per column, and values repeated
+//! at a stride one periodic assertion. [`BoundaryBuilder::samples`] picks the compact form
+//! whenever the layout allows it.
+
+use winterfell::{math::fields::f128::BaseElement, Assertion};
+
+/// Collects the assertions of an AIR instance for a trace of a given length
+#[derive(Clone, Debug)]
+pub struct BoundaryBuilder {
+    trace_length: usize,
+    assertions: Vec<Assertion<BaseElement>>,
+}
+
+impl BoundaryBuilder {
+    pub fn new(trace_length: usize) -> Self {
+        assert!(trace_length.is_power_of_two(), "Trace length must be a power of two");
+        Self { trace_length, assertions: Vec::new() }
+    }
+
+    /// `column` equals `value` at `step`
+    pub fn single(&mut self, column: usize, step: usize, value: BaseElement) -> &mut Self {
+        self.push(Assertion::single(column, step, value))
+    }
+
+    /// `column` equals `value` at `first_step`, `first_step + stride`, ... up to the end of the trace
+    pub fn periodic(&mut self, column: usize, first_step: usize, stride: usize, value: BaseElement) -> &mut Self {
+        self.push(Assertion::periodic(column, first_step, stride, value))
+    }
+
+    /// `column` equals `values[i]` at `first_step + i * stride`; the values must cover the whole
+    /// trace, i.e. `values.len() * stride` must equal the trace length
+    pub fn sequence(&mut self, column: usize, first_step: usize, stride: usize, values: Vec<BaseElement>) -> &mut Self {
+        self.push(Assertion::sequence(column, first_step, stride, values))
+    }
+
+    /// `column` carries `values[i]` at `first_step + i * stride`.
+    ///
+    /// When `stride` is a power of two of at least 2 the values become one sequence assertion,
+    /// padded to the end of the trace with `padding`; the trace builder must write `padding`
+    /// on the remaining sample rows. Otherwise every value gets its own single assertion and
+    /// the padding is not asserted.
+    pub fn samples(
+        &mut self,
+        column: usize,
+        first_step: usize,
+        stride: usize,
+        values: &[BaseElement],
+        padding: BaseElement,
+    ) -> &mut Self {
+        if values.is_empty() {
+            return self;
+        }
+        if stride >= 2 && stride.is_power_of_two() && first_step < stride {
+            let num_slots = self.trace_length / stride;
+            assert!(values.len() <= num_slots, "{} samples do not fit a stride of {stride}", values.len());
+            let mut padded = values.to_vec();
+            padded.resize(num_slots, padding);
+            return self.sequence(column, first_step, stride, padded);
+        }
+        for (i, &value) in values.iter().enumerate() {
+            self.single(column, first_step + i * stride, value);
+        }
+        self
+    }
+
+    /// Number of assertions, as expected by `AirContext::new`
+    pub fn num_assertions(&self) -> usize {
+        self.assertions.len()
+    }
+
+    pub fn build(self) -> Vec<Assertion<BaseElement>> {
+        self.assertions
+    }
+
+    fn push(&mut self, assertion: Assertion<BaseElement>) -> &mut Self {
+        if let Err(err) = assertion.validate_trace_length(self.trace_length) {
+            panic!("Invalid assertion on column {}: {err}", assertion.column());
+        }
+        self.assertions.push(assertion);
+        self
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::evaluator::EvaluatorKind;
+    use crate::prover::{prove, verify, AirProver};
+    use winterfell::{
+        math::{FieldElement, ToElements},
+        Air, AirContext, EvaluationFrame, ProofOptions, Prover, TraceInfo, TraceTable, TransitionConstraintDegree,
+    };
+
+    const STRIDE: usize = 4;
+
+    /// Column 0 carries a sample every 4th row, column 1 sums column 0
+    struct StridedSumAir {
+        context: AirContext<BaseElement>,
+        inputs: StridedSumInputs,
+    }
+
+    #[derive(Clone)]
+    struct StridedSumInputs {
+        samples: Vec<BaseElement>,
+        sum: BaseElement,
+    }
+
+    impl ToElements<BaseElement> for StridedSumInputs {
+        fn to_elements(&self) -> Vec<BaseElement> {
+            let mut elements = self.samples.clone();
+            elements.push(self.sum);
+            elements
+        }
+    }
+
+    impl StridedSumAir {
+        fn boundary(trace_length: usize, inputs: &StridedSumInputs) -> BoundaryBuilder {
+            let mut boundary = BoundaryBuilder::new(trace_length);
+            boundary
+                .samples(0, 0, STRIDE, &inputs.samples, BaseElement::ZERO)
+                .single(1, 0, BaseElement::ZERO)
+                .single(1, trace_length - 1, inputs.sum);
+            boundary
+        }
+    }
+
+    impl Air for StridedSumAir {
+        type BaseField = BaseElement;
+        type PublicInputs = StridedSumInputs;
+
+        fn new(trace_info: TraceInfo, inputs: StridedSumInputs, options: ProofOptions) -> Self {
+            let num_assertions = Self::boundary(trace_info.length(), &inputs).num_assertions();
+            let degrees = vec![TransitionConstraintDegree::new(1)];
+            Self { context: AirContext::new(trace_info, degrees, num_assertions, options), inputs }
+        }
+
+        fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+            &self,
+            frame: &EvaluationFrame<E>,
+            _periodic_values: &[E],
+            result: &mut [E],
+        ) {
+            result[0] = frame.next()[1] - frame.current()[1] - frame.current()[0];
+        }
+
+        fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+            Self::boundary(self.context.trace_info().length(), &self.inputs).build()
+        }
+
+        fn context(&self) -> &AirContext<Self::BaseField> {
+            &self.context
+        }
+    }
+
+    #[test]
+    fn test_samples_become_one_sequence() {
+        let samples: Vec<BaseElement> = (1..=100u64).map(BaseElement::from).collect();
+        let mut strided = BoundaryBuilder::new(512);
+        strided.samples(2, 1, STRIDE, &samples, BaseElement::ZERO);
+        let mut contiguous = BoundaryBuilder::new(512);
+        contiguous.samples(2, 0, 1, &samples, BaseElement::ZERO);
+        assert_eq!(strided.num_assertions(), 1);
+        assert_eq!(contiguous.num_assertions(), 100);
+
+        let assertion = &strided.build()[0];
+        assert!(assertion.is_sequence());
+        let mut asserted = Vec::new();
+        assertion.apply(512, |step, value| asserted.push((step, value)));
+        assert_eq!(asserted.len(), 128);
+        assert_eq!(asserted[99], (397, BaseElement::new(100)));
+        assert_eq!(asserted[100], (401, BaseElement::ZERO));
+
+        let mut periodic = BoundaryBuilder::new(16);
+        periodic.periodic(0, 3, 8, BaseElement::ONE);
+        assert!(periodic.build()[0].is_periodic());
+    }
+
+    #[test]
+    fn test_strided_samples_proof() {
+        let length = 32;
+        let samples = [3u64, 5, 7].map(BaseElement::from).to_vec();
+        let inputs = StridedSumInputs { samples: samples.clone(), sum: BaseElement::new(15) };
+
+        let mut values = vec![BaseElement::ZERO; length];
+        for (i, &sample) in samples.iter().enumerate() {
+            values[i * STRIDE] = sample;
+        }
+        // the exempt last row keeps the running sum from being constant at the end
+        values[length - 1] = BaseElement::ONE;
+        let sums = (0..length)
+            .scan(BaseElement::ZERO, |sum, row| {
+                let current = *sum;
+                *sum += values[row];
+                Some(current)
+            })
+            .collect();
+        let trace = TraceTable::init(vec![values, sums]);
+        crate::air_test!(StridedSumAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<StridedSumAir>(trace.clone(), inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+        let options = default_acceptable_options();
+        let tampered = StridedSumInputs { samples: vec![samples[0], samples[1], BaseElement::new(8)], sum: BaseElement::new(16) };
+        assert!(verify::<StridedSumAir>(proof.clone(), tampered, &options).is_err());
+        verify::<StridedSumAir>(proof, inputs.clone(), &options).unwrap();
+
+        // the parallel evaluator handles the sequence assertion as well
+        let proof = AirProver::<StridedSumAir>::new(ProverConfig::default().to_proof_options(), inputs.clone())
+            .with_evaluator(EvaluatorKind::Parallel)
+            .prove(trace)
+            .unwrap();
+        verify::<StridedSumAir>(proof, inputs, &options).unwrap();
+    }
+}
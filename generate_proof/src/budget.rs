@@ -0,0 +1,321 @@
+//! Proving within a wall-clock budget.
+//!
+//! An interactive demo needs a proof within a few seconds more than it needs the configured
+//! parameters. [`downgrades`] lists the configured parameters followed by cheaper ones that
+//! still reach the policy's minimum security: each halves the blowup, which halves the LDE the
+//! prover spends its time on, and takes the fewest queries keeping the conjectured security.
+//!
+//! [`prove_within_budget`] estimates every candidate under a [`Calibration`], skips those that
+//! would not fit the budget and starts the first that does. While it runs, the clock is
+//! watched: once the next cheaper candidate would no longer finish in time if started, the
+//! attempt is abandoned and that candidate started instead. The cheapest candidate runs until
+//! the budget is spent. The [`BudgetReport`] records every candidate considered, its estimate
+//! and what became of it.
+//!
+//! Winterfell cannot be interrupted, so an abandoned attempt finishes on its own thread and its
+//! proof is discarded; until then it competes with its replacement for the cores.
+
+use core::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use winterfell::{math::fields::f128::BaseElement, Air, Proof, ProverError, Trace, TraceInfo, TraceTable};
+
+use crate::analysis::min_blowup;
+use crate::config::{conjectured_security_bits, min_queries, ProverConfig, VerificationPolicy};
+use crate::prover::{spawn_prove, ProvingHandle};
+use crate::resources::{Calibration, ProvingPlan};
+
+/// How often a running attempt is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Wall-clock time a proof must be produced in, and the security it must still reach
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeBudget {
+    pub limit: Duration,
+    pub min_security_bits: u32,
+}
+
+impl TimeBudget {
+    /// Budget of `limit` for proofs `policy` accepts
+    pub fn new(limit: Duration, policy: &VerificationPolicy) -> Self {
+        Self { limit, min_security_bits: policy.min_security_bits }
+    }
+}
+
+/// `config` and ever cheaper parameters reaching `min_security_bits` for a trace of
+/// `trace_length` rows whose constraints need a blowup of at least `min_blowup`, the most
+/// expensive first.
+///
+/// At the configured blowup the configured queries are kept, or raised to the minimum; smaller
+/// blowups take the fewest queries reaching it. Parameters FRI cannot fold the trace with are
+/// left out.
+pub fn downgrades(
+    config: &ProverConfig,
+    trace_length: usize,
+    min_blowup: usize,
+    min_security_bits: u32,
+) -> Vec<ProverConfig> {
+    let mut candidates = Vec::new();
+    let mut blowup_factor = config.blowup_factor;
+    while blowup_factor >= min_blowup.max(2) {
+        let queries = min_queries(blowup_factor, config.grinding_factor, config.field_extension(), min_security_bits);
+        if let Some(queries) = queries {
+            let num_queries =
+                if blowup_factor == config.blowup_factor { queries.max(config.num_queries) } else { queries };
+            let candidate = ProverConfig { blowup_factor, num_queries, ..config.clone() };
+            if candidate.validate_for_trace(trace_length).is_ok() {
+                candidates.push(candidate);
+            }
+        }
+        blowup_factor /= 2;
+    }
+    candidates
+}
+
+/// What became of a candidate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    /// Its estimate exceeded the time left, so it was never started
+    Skipped,
+    /// Started, but still running when a cheaper candidate had to take over
+    Abandoned,
+    Proved,
+}
+
+/// One candidate of a budgeted proof
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    pub blowup_factor: usize,
+    pub num_queries: usize,
+    pub security_bits: u32,
+    pub estimated_seconds: f64,
+    /// Time the attempt ran, `None` when it was skipped
+    pub elapsed_seconds: Option<f64>,
+    pub outcome: AttemptOutcome,
+}
+
+/// The candidates of a budgeted proof in the order they were considered
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BudgetReport {
+    pub limit_seconds: f64,
+    pub attempts: Vec<Attempt>,
+}
+
+impl BudgetReport {
+    /// The attempt that produced the proof
+    pub fn chosen(&self) -> Option<&Attempt> {
+        self.attempts.iter().find(|attempt| attempt.outcome == AttemptOutcome::Proved)
+    }
+
+    /// Whether cheaper parameters than the configured ones were used
+    pub fn downgraded(&self) -> bool {
+        self.attempts.first().is_some_and(|attempt| attempt.outcome != AttemptOutcome::Proved)
+    }
+}
+
+impl fmt::Display for BudgetReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.chosen() {
+            Some(chosen) => write!(
+                f,
+                "proved with blowup {} and {} queries ({}-bit) in {:.2}s of {:.2}s",
+                chosen.blowup_factor,
+                chosen.num_queries,
+                chosen.security_bits,
+                chosen.elapsed_seconds.unwrap_or_default(),
+                self.limit_seconds
+            )?,
+            None => write!(f, "no proof within {:.2}s", self.limit_seconds)?,
+        }
+        let count = |outcome| self.attempts.iter().filter(|attempt| attempt.outcome == outcome).count();
+        let (skipped, abandoned) = (count(AttemptOutcome::Skipped), count(AttemptOutcome::Abandoned));
+        if skipped + abandoned > 0 {
+            write!(f, "; {skipped} parameterizations skipped, {abandoned} abandoned")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reason a budgeted proof was not produced
+#[derive(Debug)]
+pub enum BudgetError {
+    /// No parameters reach the minimum security for this trace and AIR
+    NoCompliantOptions { min_security_bits: u32 },
+    /// Not even the cheapest candidate finished within the budget
+    Exhausted(BudgetReport),
+    Prover(ProverError),
+}
+
+impl fmt::Display for BudgetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetError::NoCompliantOptions { min_security_bits } => {
+                write!(f, "no prover parameters reach {min_security_bits} bits of security for this claim")
+            }
+            BudgetError::Exhausted(report) => write!(f, "time budget exhausted: {report}"),
+            BudgetError::Prover(err) => write!(f, "proving failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BudgetError {}
+
+/// A proof produced within its budget, with the parameters it was produced with
+#[derive(Debug)]
+pub struct BudgetedProof {
+    pub proof: Proof,
+    pub config: ProverConfig,
+    pub report: BudgetReport,
+}
+
+/// Proves `trace` for AIR `A` within `budget`, downgrading `config` when it would take too long
+pub fn prove_within_budget<A>(
+    trace: TraceTable<BaseElement>,
+    inputs: A::PublicInputs,
+    config: &ProverConfig,
+    calibration: &Calibration,
+    budget: &TimeBudget,
+) -> Result<BudgetedProof, BudgetError>
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Clone + Send + Sync + 'static,
+{
+    let trace_info = trace.info().clone();
+    let air = A::new(trace_info.clone(), inputs.clone(), config.to_proof_options());
+    let candidates = downgrades(config, trace_info.length(), min_blowup(&air), budget.min_security_bits);
+    run(&candidates, &trace_info, calibration, budget, |candidate| {
+        let options = candidate.proof_options_for(trace_info.length()).expect("Candidates fit the trace");
+        Ok(spawn_prove::<A>(trace.clone(), inputs.clone(), options, candidate.proving_threads()))
+    })
+}
+
+/// Runs `spawn` for `candidates`, most expensive first, until one proves within `budget`
+pub(crate) fn run<F>(
+    candidates: &[ProverConfig],
+    trace_info: &TraceInfo,
+    calibration: &Calibration,
+    budget: &TimeBudget,
+    mut spawn: F,
+) -> Result<BudgetedProof, BudgetError>
+where
+    F: FnMut(&ProverConfig) -> Result<ProvingHandle, BudgetError>,
+{
+    if candidates.is_empty() {
+        return Err(BudgetError::NoCompliantOptions { min_security_bits: budget.min_security_bits });
+    }
+    let started = Instant::now();
+    let limit = budget.limit.as_secs_f64();
+    let estimates: Vec<f64> = candidates
+        .iter()
+        .map(|candidate| calibration.estimate(&ProvingPlan::new(trace_info, candidate)).seconds)
+        .collect();
+    let mut report = BudgetReport { limit_seconds: limit, attempts: Vec::new() };
+
+    for (i, candidate) in candidates.iter().enumerate() {
+        let mut attempt = Attempt {
+            blowup_factor: candidate.blowup_factor,
+            num_queries: candidate.num_queries,
+            security_bits: conjectured_security_bits(&candidate.to_proof_options()),
+            estimated_seconds: estimates[i],
+            elapsed_seconds: None,
+            outcome: AttemptOutcome::Skipped,
+        };
+        let cheapest = i + 1 == candidates.len();
+        if !cheapest && started.elapsed().as_secs_f64() + estimates[i] > limit {
+            report.attempts.push(attempt);
+            continue;
+        }
+
+        // the latest moment the next cheaper candidate can start and still finish in time
+        let deadline = limit - estimates.get(i + 1).copied().unwrap_or(0.0);
+        let attempt_started = Instant::now();
+        let handle = spawn(candidate)?;
+        while !handle.is_finished() && started.elapsed().as_secs_f64() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        attempt.elapsed_seconds = Some(attempt_started.elapsed().as_secs_f64());
+        if !handle.is_finished() {
+            // dropping the handle detaches the prover thread
+            attempt.outcome = AttemptOutcome::Abandoned;
+            report.attempts.push(attempt);
+            continue;
+        }
+        let proof = handle.join().map_err(BudgetError::Prover)?;
+        attempt.outcome = AttemptOutcome::Proved;
+        report.attempts.push(attempt);
+        return Ok(BudgetedProof { proof, config: candidate.clone(), report });
+    }
+    Err(BudgetError::Exhausted(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::verify;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    fn linear_claim() -> (TraceTable<BaseElement>, LinearRegressionInputs) {
+        let (slope, intercept, x) = (BaseElement::new(2), BaseElement::new(5), BaseElement::new(9));
+        let xs: Vec<BaseElement> = (1..=60u64).map(BaseElement::from).collect();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, x, TraceInfo::MIN_TRACE_LENGTH);
+        let inputs = LinearRegressionInputs {
+            x_value: x,
+            predicted_y: slope * x + intercept,
+            sample_x_values: xs,
+            sample_y_values: ys,
+        };
+        (trace, inputs)
+    }
+
+    #[test]
+    fn test_downgrades_keep_security() {
+        let config = ProverConfig::default();
+        let candidates = downgrades(&config, 1024, 2, 90);
+        let shapes: Vec<_> = candidates.iter().map(|c| (c.blowup_factor, c.num_queries)).collect();
+        assert_eq!(shapes, vec![(8, 32), (4, 46), (2, 91)]);
+        assert!(candidates.iter().all(|c| conjectured_security_bits(&c.to_proof_options()) >= 90));
+
+        // a stricter policy raises the configured queries, and the AIR bounds the blowup
+        let strict = downgrades(&config, 1024, 4, 100);
+        assert_eq!(strict.iter().map(|c| (c.blowup_factor, c.num_queries)).collect::<Vec<_>>(), vec![(8, 34), (4, 51)]);
+        assert!(downgrades(&config, 1024, 16, 90).is_empty());
+    }
+
+    #[test]
+    fn test_downgrades_when_the_budget_will_blow() {
+        let (trace, inputs) = linear_claim();
+        let config = ProverConfig::default();
+        let policy = VerificationPolicy { min_security_bits: 90, ..VerificationPolicy::default() };
+        let budget = TimeBudget::new(Duration::from_secs(60), &policy);
+
+        // the configured parameters are estimated to fit
+        let fast = Calibration { seconds_per_unit: 0.0, overhead_seconds: 0.0 };
+        let proved = prove_within_budget::<LinearRegressionAir>(trace.clone(), inputs.clone(), &config, &fast, &budget)
+            .unwrap();
+        assert_eq!(proved.config, config);
+        assert!(!proved.report.downgraded());
+
+        // estimated at 150s and 67s, the two largest blowups are skipped
+        let unit = Calibration { seconds_per_unit: 1.0, overhead_seconds: 0.0 };
+        let work = unit.estimate(&ProvingPlan::new(trace.info(), &config)).seconds;
+        let slow = Calibration { seconds_per_unit: 150.0 / work, overhead_seconds: 0.0 };
+        let proved =
+            prove_within_budget::<LinearRegressionAir>(trace.clone(), inputs.clone(), &config, &slow, &budget).unwrap();
+        let outcomes: Vec<_> = proved.report.attempts.iter().map(|a| (a.blowup_factor, a.outcome)).collect();
+        let skipped = AttemptOutcome::Skipped;
+        assert_eq!(outcomes, vec![(8, skipped), (4, skipped), (2, AttemptOutcome::Proved)]);
+        assert!(proved.report.to_string().starts_with("proved with blowup 2 and 91 queries (90-bit)"));
+        verify::<LinearRegressionAir>(proved.proof, inputs.clone(), &policy.acceptable_options()).unwrap();
+
+        // with no time at all every attempt is abandoned
+        let instant = TimeBudget { limit: Duration::ZERO, ..budget };
+        let err = prove_within_budget::<LinearRegressionAir>(trace, inputs, &config, &fast, &instant).unwrap_err();
+        let BudgetError::Exhausted(report) = err else { panic!("expected an exhausted budget, got {err}") };
+        let outcomes: Vec<_> = report.attempts.iter().map(|attempt| attempt.outcome).collect();
+        assert_eq!(outcomes, vec![AttemptOutcome::Skipped, AttemptOutcome::Skipped, AttemptOutcome::Abandoned]);
+    }
+}
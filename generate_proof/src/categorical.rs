@@ -0,0 +1,350 @@
+//! Predictions over a categorical feature encoded one-hot.
+//!
+//! A [`CategoricalModel`] adds a per-category offset to a linear term, `y = m * x + c +
+//! offset[category]`, where the category is one of those declared by a categorical
+//! [`ColumnSchema`]. [`CategoricalAir`] keeps the category private: the public inputs carry a
+//! commitment to its index, and the trace holds one [`OneHot`] flag per declared category.
+//! The flags are binary and exactly one of them is set, so the proof guarantees that the
+//! input encodes exactly one declared category, that the committed index is that category
+//! and that the prediction used its offset.
+//!
+//! The category is committed as the first lane of a Rescue permutation of `[index, salt,
+//! CATEGORY_TAG, num_categories]`, see [`category_commitment`]. The trace is a single
+//! [`CYCLE_LENGTH`]-row cycle: the prediction, the sponge running that permutation and the
+//! flags, all read on the first row.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::batch::field_column;
+use crate::constraints::ConstraintLabels;
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::OneHot;
+use crate::layout::LayoutDescriptor;
+use crate::schema::ColumnSchema;
+
+/// Identifier of [`CategoricalAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "categorical/v1";
+
+/// Keeps the trace within Winterfell's 255 columns
+pub const MAX_CATEGORIES: usize = 250;
+
+/// Sponge tag of category commitments; tags 1 to 22 are used elsewhere
+const CATEGORY_TAG: u64 = 23;
+
+// Trace layout: the prediction, the commitment sponge, then one flag per category
+const PREDICTION: usize = 0;
+const SPONGE: usize = 1;
+const FLAGS: usize = SPONGE + STATE_WIDTH;
+
+/// Every proof is one cycle long
+pub const TRACE_LENGTH: usize = CYCLE_LENGTH;
+
+/// Named columns of the trace for a model of `num_categories` categories, see [`crate::layout`]
+pub fn layout(num_categories: usize) -> LayoutDescriptor {
+    LayoutDescriptor::new(AIR_ID)
+        .with_column("prediction", PREDICTION)
+        .with_columns("sponge", SPONGE..FLAGS)
+        .with_columns("category_flags", FLAGS..OneHot::new(FLAGS, num_categories).next_column())
+}
+
+/// Reason a categorical prediction cannot be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CategoricalError {
+    /// The model has no offsets or more than [`MAX_CATEGORIES`]
+    CategoryCount(usize),
+    /// The category index is not one of the model's
+    UnknownCategory { category: usize, num_categories: usize },
+    /// The schema column is numeric or declares a different number of categories
+    SchemaMismatch { column: String, expected: usize, found: usize },
+}
+
+impl fmt::Display for CategoricalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CategoricalError::CategoryCount(count) => {
+                write!(f, "model has {count} categories, expected 1 to {MAX_CATEGORIES}")
+            }
+            CategoricalError::UnknownCategory { category, num_categories } => {
+                write!(f, "category {category} is not one of the model's {num_categories}")
+            }
+            CategoricalError::SchemaMismatch { column, expected, found } => {
+                write!(f, "column {column} declares {found} categories, the model has {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CategoricalError {}
+
+/// `y = slope * x + intercept + offsets[category]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CategoricalModel {
+    pub slope: i64,
+    pub intercept: i64,
+    /// One offset per category, in the order of the schema's categories
+    pub offsets: Vec<i64>,
+}
+
+impl CategoricalModel {
+    pub fn num_categories(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn predict(&self, x: i64, category: usize) -> i128 {
+        self.slope as i128 * x as i128 + self.intercept as i128 + self.offsets[category] as i128
+    }
+
+    fn validate(&self) -> Result<(), CategoricalError> {
+        match self.num_categories() {
+            1..=MAX_CATEGORIES => Ok(()),
+            count => Err(CategoricalError::CategoryCount(count)),
+        }
+    }
+
+    /// Checks that `column` declares one category per offset
+    pub fn check_column(&self, column: &ColumnSchema) -> Result<(), CategoricalError> {
+        if column.categories.len() != self.num_categories() {
+            return Err(CategoricalError::SchemaMismatch {
+                column: column.name.clone(),
+                expected: self.num_categories(),
+                found: column.categories.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn commitment_start(category: usize, salt: BaseElement, num_categories: usize) -> [BaseElement; STATE_WIDTH] {
+    let (tag, count) = (BaseElement::from(CATEGORY_TAG), BaseElement::from(num_categories as u64));
+    [BaseElement::from(category as u64), salt, tag, count]
+}
+
+/// Commitment to the index of one of `num_categories` categories
+pub fn category_commitment(category: usize, salt: BaseElement, num_categories: usize) -> BaseElement {
+    let mut state = commitment_start(category, salt, num_categories);
+    rescue::permute(&mut state);
+    state[0]
+}
+
+/// Public inputs: the model, the numeric feature, the category commitment and the prediction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CategoricalInputs {
+    pub model: CategoricalModel,
+    pub x: i64,
+    pub commitment: BaseElement,
+    pub prediction: i128,
+}
+
+impl ToElements<BaseElement> for CategoricalInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let model = &self.model;
+        let mut elements = vec![to_field(model.slope as i128), to_field(model.intercept as i128)];
+        elements.push(BaseElement::from(model.num_categories() as u64));
+        elements.extend(field_column(&model.offsets));
+        elements.extend([to_field(self.x as i128), self.commitment, to_field(self.prediction)]);
+        elements
+    }
+}
+
+/// AIR proving a prediction over one committed, one-hot encoded category
+pub struct CategoricalAir {
+    context: AirContext<BaseElement>,
+    inputs: CategoricalInputs,
+    one_hot: OneHot,
+}
+
+impl Air for CategoricalAir {
+    type BaseField = BaseElement;
+    type PublicInputs = CategoricalInputs;
+
+    fn new(trace_info: TraceInfo, inputs: CategoricalInputs, options: ProofOptions) -> Self {
+        assert_eq!(inputs.model.validate(), Ok(()), "Invalid number of categories");
+        let one_hot = OneHot::new(FLAGS, inputs.model.num_categories());
+        assert_eq!(one_hot.next_column(), trace_info.width());
+        assert_eq!(TRACE_LENGTH, trace_info.length());
+
+        // Constraints:
+        // 1. Every flag is binary (degree 2) and exactly one is set (degree 1)
+        // 2. Rescue rounds of the commitment sponge (degree 3)
+        // 3. On the first row, the committed index is the flagged category (degree 1)
+        // 4. On the first row, the prediction is m * x + c plus the flagged category's offset
+        //    (degree 1)
+        let mut degrees = one_hot.constraint_degrees();
+        degrees.extend(RescueGadget::new(SPONGE).constraint_degrees());
+        degrees.extend(vec![TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]); 2]);
+
+        let context = AirContext::new(trace_info, degrees, 4, options);
+        CategoricalAir { context, inputs, one_hot }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let first = periodic_values[rescue::NUM_PERIODIC_COLUMNS];
+        let model = &self.inputs.model;
+
+        let (flags, rest) = result.split_at_mut(self.one_hot.num_constraints());
+        self.one_hot.evaluate(current, flags);
+        let sponge = RescueGadget::new(SPONGE);
+        sponge.evaluate(current, next, periodic_values, rest);
+
+        let linear = to_field(model.slope as i128 * self.inputs.x as i128 + model.intercept as i128);
+        let offsets: Vec<E> = model.offsets.iter().map(|&offset| E::from(to_field(offset as i128))).collect();
+        let prediction = E::from(linear) + self.one_hot.select(current, &offsets);
+        rest[STATE_WIDTH] = first * (sponge.state(current)[0] - self.one_hot.index(current));
+        rest[STATE_WIDTH + 1] = first * (current[PREDICTION] - prediction);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        let mut first = vec![BaseElement::ZERO; CYCLE_LENGTH];
+        first[0] = BaseElement::ONE;
+        let mut columns = rescue::periodic_columns();
+        columns.push(first);
+        columns
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        // the index and the salt in the first two lanes stay private
+        let num_categories = self.inputs.model.num_categories() as u64;
+        vec![
+            Assertion::single(PREDICTION, 0, to_field(self.inputs.prediction)),
+            Assertion::single(SPONGE + 2, 0, BaseElement::from(CATEGORY_TAG)),
+            Assertion::single(SPONGE + 3, 0, BaseElement::from(num_categories)),
+            Assertion::single(SPONGE, CYCLE_LENGTH - 1, self.inputs.commitment),
+        ]
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+impl ConstraintLabels for CategoricalAir {
+    fn constraint_labels(&self) -> Vec<String> {
+        let mut labels = self.one_hot.constraint_labels();
+        labels.extend((0..STATE_WIDTH).map(|lane| format!("commitment_round_{lane}")));
+        labels.extend(["committed_category".into(), "prediction".into()]);
+        labels
+    }
+}
+
+/// Build the execution trace proving `model`'s prediction for `x` in `category`, with the
+/// inputs it proves; `salt` hides the category behind its commitment
+#[cfg(feature = "prover")]
+pub fn build_categorical_trace(
+    model: &CategoricalModel,
+    x: i64,
+    category: usize,
+    salt: BaseElement,
+) -> Result<(TraceTable<BaseElement>, CategoricalInputs), CategoricalError> {
+    model.validate()?;
+    let num_categories = model.num_categories();
+    if category >= num_categories {
+        return Err(CategoricalError::UnknownCategory { category, num_categories });
+    }
+
+    let one_hot = OneHot::new(FLAGS, num_categories);
+    let prediction = model.predict(x, category);
+    let mut trace = TraceTable::new(one_hot.next_column(), TRACE_LENGTH);
+    let mut row = vec![BaseElement::ZERO; one_hot.next_column()];
+    let mut state = commitment_start(category, salt, num_categories);
+    let sponge = RescueGadget::new(SPONGE);
+    for i in 0..TRACE_LENGTH {
+        sponge.write(&mut row, &state);
+        if i < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, i);
+            one_hot.fill(&mut row, category);
+            row[PREDICTION] = to_field(prediction);
+        } else {
+            // the exempt row misses the prediction its flags select, so the prediction
+            // constraint never collapses to zero
+            one_hot.fill_exempt_row(&mut row);
+            let offsets = field_column(&model.offsets).into_iter().fold(BaseElement::ZERO, |sum, offset| sum + offset);
+            let linear = to_field(model.slope as i128 * x as i128 + model.intercept as i128);
+            row[PREDICTION] = linear + offsets.double() + BaseElement::ONE;
+        }
+        trace.update_row(i, &row);
+    }
+
+    let commitment = category_commitment(category, salt, num_categories);
+    Ok((trace, CategoricalInputs { model: model.clone(), x, commitment, prediction }))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    fn model() -> CategoricalModel {
+        CategoricalModel { slope: 3, intercept: -4, offsets: vec![10, -20, 0, 7] }
+    }
+
+    #[test]
+    fn test_categorical_prediction_proof() {
+        let salt = BaseElement::new(0x5eed);
+        let (trace, inputs) = build_categorical_trace(&model(), 6, 1, salt).unwrap();
+        assert_eq!(inputs.prediction, 18 - 4 - 20);
+        assert_eq!(inputs.commitment, category_commitment(1, salt, 4));
+        crate::air_test!(CategoricalAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        // a second flag, or a flag of 2 with the others cleared, breaks the encoding
+        crate::air_test!(CategoricalAir, inputs.clone())
+            .with_trace(&trace)
+            .with_cell(FLAGS + 3, 0, 1u64)
+            .expect_constraint_fails(4, 0);
+        crate::air_test!(CategoricalAir, inputs.clone())
+            .with_trace(&trace)
+            .with_cell(FLAGS + 1, 0, 2u64)
+            .expect_constraint_fails(1, 0);
+
+        let proof = prove::<CategoricalAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options());
+        let (proof, options) = (proof.unwrap(), default_acceptable_options());
+        // the same prediction claimed under another category's commitment
+        let other = CategoricalInputs { commitment: category_commitment(3, salt, 4), ..inputs.clone() };
+        assert!(verify::<CategoricalAir>(proof.clone(), other, &options).is_err());
+        let wrong = CategoricalInputs { prediction: inputs.prediction + 1, ..inputs.clone() };
+        assert!(verify::<CategoricalAir>(proof.clone(), wrong, &options).is_err());
+        let result = verify::<CategoricalAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_categories_follow_the_schema() {
+        let regions = ["north", "south", "east", "west"].map(String::from).to_vec();
+        let column = ColumnSchema::categorical("region", regions);
+        let category = column.category_index("east").unwrap();
+        model().check_column(&column).unwrap();
+        let (_, inputs) = build_categorical_trace(&model(), 1, category, BaseElement::ONE).unwrap();
+        assert_eq!(inputs.prediction, 3 - 4);
+
+        let short = ColumnSchema::categorical("region", vec!["north".into()]);
+        let mismatch = CategoricalError::SchemaMismatch { column: "region".into(), expected: 4, found: 1 };
+        assert_eq!(model().check_column(&short), Err(mismatch));
+        let numeric = ColumnSchema::new("region", "", 0, 0, 3);
+        assert!(model().check_column(&numeric).is_err());
+        assert_eq!(
+            build_categorical_trace(&model(), 1, 4, BaseElement::ONE).unwrap_err(),
+            CategoricalError::UnknownCategory { category: 4, num_categories: 4 }
+        );
+        let empty = CategoricalModel { offsets: vec![], ..model() };
+        let error = build_categorical_trace(&empty, 1, 0, BaseElement::ONE).unwrap_err();
+        assert_eq!(error, CategoricalError::CategoryCount(0));
+    }
+}
@@ -0,0 +1,466 @@
+//! Datasets proven in segments, chained by a rolling state commitment.
+//!
+//! A dataset too large for one trace is split into segments, each proven on its own by
+//! [`SegmentAir`]. Every segment imports a [`ChainState`], a Rescue sponge over all values
+//! before it together with their running sum and count, and exports the state after absorbing
+//! its own values. [`ChainVerifier`] checks the envelopes of a chain one at a time, as they
+//! arrive, and requires the state imported by segment `i + 1` to be the one exported by
+//! segment `i`; [`ChainVerifier::finish`] returns one verdict for the whole dataset whose
+//! final state is [`chain_state`] of all values, which the data owner can compute on its own.
+//!
+//! In a segment of `n` values, row `8c` of the trace holds the state before value `c`: each
+//! cycle permutes the sponge and the value is added to its first lane and to the running sum
+//! at the end of the cycle. The imported state is asserted at row 0 and the exported one at
+//! row `8n`.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField, ToElements},
+    AcceptableOptions, Air, AirContext, Assertion, EvaluationFrame, Proof, ProofOptions, TraceInfo,
+    TransitionConstraintDegree, VerifierError,
+};
+#[cfg(feature = "prover")]
+use winterfell::{ProverError, TraceTable};
+
+use crate::envelope::ProofEnvelope;
+use crate::fingerprint::ConstraintFingerprint;
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::layout::LayoutDescriptor;
+#[cfg(feature = "prover")]
+use crate::prover::prove;
+use crate::prover::verify;
+use crate::registry::{ModelCommitment, ModelId};
+
+/// Identifier of [`SegmentAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "chain-segment/v1";
+
+/// Sponge tag of [`ChainState::initial`]; tags 1 to 9 are used by the other AIRs
+const CHAIN_TAG: u64 = 10;
+
+// Trace layout: sponge state, the value absorbed at the end of the cycle, running sum
+const SPONGE: usize = 0;
+const VALUE: usize = SPONGE + STATE_WIDTH;
+const SUM: usize = VALUE + 1;
+const TRACE_WIDTH: usize = SUM + 1;
+
+/// Rolling commitment to a prefix of a dataset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainState {
+    pub sponge: [BaseElement; STATE_WIDTH],
+    /// Sum of the values, as field elements
+    pub sum: BaseElement,
+    pub rows: u64,
+}
+
+impl ChainState {
+    /// State of the empty prefix every chain starts from
+    pub fn initial() -> Self {
+        Self { sponge: rescue::sponge_iv(0, CHAIN_TAG), sum: BaseElement::ZERO, rows: 0 }
+    }
+
+    /// State after absorbing `values` into `self`
+    pub fn absorb(&self, values: &[i64]) -> Self {
+        let mut state = *self;
+        for &value in values {
+            let value = to_field(value as i128);
+            rescue::permute(&mut state.sponge);
+            state.sponge[0] += value;
+            state.sum += value;
+        }
+        state.rows += values.len() as u64;
+        state
+    }
+
+    /// Digest of the prefix
+    pub fn digest(&self) -> BaseElement {
+        self.sponge[0]
+    }
+
+    fn elements(&self) -> impl Iterator<Item = BaseElement> + '_ {
+        self.sponge.iter().copied().chain([self.sum, BaseElement::from(self.rows)])
+    }
+
+    fn from_elements(elements: &[BaseElement]) -> Option<Self> {
+        let [s0, s1, s2, s3, sum, rows] = elements.try_into().ok()?;
+        Some(Self { sponge: [s0, s1, s2, s3], sum, rows: u64::try_from(rows.as_int()).ok()? })
+    }
+}
+
+/// State of a chain over all of `values`
+pub fn chain_state(values: &[i64]) -> ChainState {
+    ChainState::initial().absorb(values)
+}
+
+/// Public inputs: the state a segment imports and the one it exports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SegmentInputs {
+    pub imported: ChainState,
+    pub exported: ChainState,
+}
+
+impl SegmentInputs {
+    /// Number of values absorbed by the segment
+    pub fn num_values(&self) -> usize {
+        self.exported.rows.saturating_sub(self.imported.rows) as usize
+    }
+
+    /// Parses the elements returned by `to_elements`, e.g. the public inputs of an envelope
+    pub fn from_elements(elements: &[BaseElement]) -> Option<Self> {
+        if elements.len() != 2 * (STATE_WIDTH + 2) {
+            return None;
+        }
+        let (imported, exported) = elements.split_at(STATE_WIDTH + 2);
+        Some(Self { imported: ChainState::from_elements(imported)?, exported: ChainState::from_elements(exported)? })
+    }
+}
+
+impl ToElements<BaseElement> for SegmentInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        self.imported.elements().chain(self.exported.elements()).collect()
+    }
+}
+
+/// Trace length for a segment of `num_values` values
+pub fn trace_length(num_values: usize) -> usize {
+    ((num_values + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..VALUE)
+        .with_column("value", VALUE)
+        .with_column("sum", SUM)
+}
+
+/// AIR proving that a segment of values takes a chain from one state to the next
+pub struct SegmentAir {
+    context: AirContext<BaseElement>,
+    sponge: RescueGadget,
+    inputs: SegmentInputs,
+}
+
+impl Air for SegmentAir {
+    type BaseField = BaseElement;
+    type PublicInputs = SegmentInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: SegmentInputs, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert!(pub_inputs.exported.rows > pub_inputs.imported.rows, "Segments hold at least one value");
+        assert_eq!(trace_length(pub_inputs.num_values()), trace_info.length());
+
+        let sponge = RescueGadget::new(SPONGE);
+        let periodic = TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the value at the end of a cycle (degree 1)
+        // 3. The value is constant within a cycle (degree 1)
+        // 4. The sum adds the value at the end of a cycle (degree 1)
+        let mut degrees = sponge.constraint_degrees();
+        degrees.extend(vec![periodic; STATE_WIDTH + 2]);
+
+        let context = AirContext::new(trace_info, degrees, 2 * (STATE_WIDTH + 1), options);
+        SegmentAir { context, sponge, inputs: pub_inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        self.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[VALUE]);
+        for i in 1..STATE_WIDTH {
+            rest[i] = last * (next[SPONGE + i] - current[SPONGE + i]);
+        }
+        rest[STATE_WIDTH] = mask * (next[VALUE] - current[VALUE]);
+        rest[STATE_WIDTH + 1] = next[SUM] - current[SUM] - last * current[VALUE];
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let export_row = self.inputs.num_values() * CYCLE_LENGTH;
+        let mut assertions = Vec::new();
+        for (row, state) in [(0, self.inputs.imported), (export_row, self.inputs.exported)] {
+            for (i, &value) in state.sponge.iter().enumerate() {
+                assertions.push(Assertion::single(SPONGE + i, row, value));
+            }
+            assertions.push(Assertion::single(SUM, row, state.sum));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace of a segment absorbing `values` into `imported`
+#[cfg(feature = "prover")]
+pub fn build_segment_trace(values: &[i64], imported: &ChainState) -> TraceTable<BaseElement> {
+    assert!(!values.is_empty(), "Segments hold at least one value");
+    let length = trace_length(values.len());
+    // padding cycles absorb a value other than the last one, which keeps the value column
+    // from being constant
+    let padding = values[values.len() - 1].wrapping_add(1);
+
+    let mut columns = vec![vec![BaseElement::ZERO; length]; TRACE_WIDTH];
+    let mut state = imported.sponge;
+    let mut sum = imported.sum;
+    for cycle in 0..length / CYCLE_LENGTH {
+        let value = to_field(values.get(cycle).copied().unwrap_or(padding) as i128);
+        for step in 0..CYCLE_LENGTH {
+            let row = cycle * CYCLE_LENGTH + step;
+            for (i, &lane) in state.iter().enumerate() {
+                columns[SPONGE + i][row] = lane;
+            }
+            columns[VALUE][row] = value;
+            columns[SUM][row] = sum;
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut state, step);
+            }
+        }
+        state[0] += value;
+        sum += value;
+    }
+    TraceTable::init(columns)
+}
+
+/// Proves a segment absorbing `values` into `imported`
+#[cfg(feature = "prover")]
+pub fn prove_segment(
+    values: &[i64],
+    imported: &ChainState,
+    options: ProofOptions,
+) -> Result<(Proof, SegmentInputs), ProverError> {
+    let inputs = SegmentInputs { imported: *imported, exported: imported.absorb(values) };
+    let proof = prove::<SegmentAir>(build_segment_trace(values, imported), inputs, options)?;
+    Ok((proof, inputs))
+}
+
+/// Envelope of a segment proof, fingerprinted for [`SegmentAir`]
+pub fn segment_envelope(
+    model_id: ModelId,
+    model_commitment: ModelCommitment,
+    proof: Proof,
+    inputs: SegmentInputs,
+) -> ProofEnvelope {
+    let fingerprint = segment_fingerprint(&proof, inputs);
+    ProofEnvelope::new(model_id, model_commitment, fingerprint, inputs.to_elements(), proof)
+}
+
+fn segment_fingerprint(proof: &Proof, inputs: SegmentInputs) -> ConstraintFingerprint {
+    let (trace_info, options) = (proof.trace_info().clone(), proof.options().clone());
+    ConstraintFingerprint::for_instance::<SegmentAir>(&layout(), trace_info, inputs, options)
+}
+
+/// Reason a chain of segment envelopes is rejected; `segment` is the 0-based position
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainError {
+    Empty,
+    /// The public inputs do not describe a segment
+    MalformedInputs { segment: usize },
+    /// The segment belongs to another model than the first one
+    ModelMismatch { segment: usize },
+    FingerprintMismatch { segment: usize },
+    /// The imported state is not the state exported by the previous segment
+    Broken { segment: usize },
+    Invalid { segment: usize, error: VerifierError },
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::Empty => write!(f, "chain has no segments"),
+            ChainError::MalformedInputs { segment } => write!(f, "segment {segment} has malformed public inputs"),
+            ChainError::ModelMismatch { segment } => write!(f, "segment {segment} is bound to another model"),
+            ChainError::FingerprintMismatch { segment } => {
+                write!(f, "segment {segment} was not proven with the segment constraints")
+            }
+            ChainError::Broken { segment } => {
+                write!(f, "segment {segment} does not import the state exported before it")
+            }
+            ChainError::Invalid { segment, error } => write!(f, "segment {segment} has an invalid proof: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// Consolidated claim of a verified chain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainVerdict {
+    pub model_id: ModelId,
+    pub model_commitment: ModelCommitment,
+    pub segments: usize,
+    /// State the first segment imported
+    pub initial: ChainState,
+    /// State the last segment exported
+    pub exported: ChainState,
+}
+
+/// Verifies the envelopes of a chain in order, one at a time
+pub struct ChainVerifier {
+    options: AcceptableOptions,
+    initial: ChainState,
+    state: ChainState,
+    model: Option<(ModelId, ModelCommitment)>,
+    segments: usize,
+}
+
+impl ChainVerifier {
+    /// Verifier of a chain starting from [`ChainState::initial`]
+    pub fn new(options: AcceptableOptions) -> Self {
+        Self::resuming(ChainState::initial(), options)
+    }
+
+    /// Verifier of a chain whose first segment imports `state`, e.g. the export of a chain
+    /// verified before
+    pub fn resuming(state: ChainState, options: AcceptableOptions) -> Self {
+        Self { options, initial: state, state, model: None, segments: 0 }
+    }
+
+    /// State exported by the last segment accepted so far
+    pub fn state(&self) -> &ChainState {
+        &self.state
+    }
+
+    /// Verifies the next segment of the chain and advances to the state it exports
+    pub fn push(&mut self, envelope: &ProofEnvelope) -> Result<&ChainState, ChainError> {
+        let segment = self.segments;
+        let inputs =
+            SegmentInputs::from_elements(&envelope.public_inputs).ok_or(ChainError::MalformedInputs { segment })?;
+        if inputs.exported.rows <= inputs.imported.rows {
+            return Err(ChainError::MalformedInputs { segment });
+        }
+        let model = (envelope.model_id, envelope.model_commitment);
+        if self.model.is_some_and(|first| first != model) {
+            return Err(ChainError::ModelMismatch { segment });
+        }
+        if inputs.imported != self.state {
+            return Err(ChainError::Broken { segment });
+        }
+        let proof = &envelope.proof;
+        if segment_fingerprint(proof, inputs) != envelope.constraint_fingerprint {
+            return Err(ChainError::FingerprintMismatch { segment });
+        }
+        verify::<SegmentAir>(proof.clone(), inputs, &self.options)
+            .map_err(|error| ChainError::Invalid { segment, error })?;
+
+        self.model = Some(model);
+        self.state = inputs.exported;
+        self.segments += 1;
+        Ok(&self.state)
+    }
+
+    /// Verdict over every segment pushed so far
+    pub fn finish(self) -> Result<ChainVerdict, ChainError> {
+        let (model_id, model_commitment) = self.model.ok_or(ChainError::Empty)?;
+        Ok(ChainVerdict {
+            model_id,
+            model_commitment,
+            segments: self.segments,
+            initial: self.initial,
+            exported: self.state,
+        })
+    }
+}
+
+/// Verifies a whole chain starting from [`ChainState::initial`]
+pub fn verify_chain(envelopes: &[ProofEnvelope], options: AcceptableOptions) -> Result<ChainVerdict, ChainError> {
+    let mut verifier = ChainVerifier::new(options);
+    for envelope in envelopes {
+        verifier.push(envelope)?;
+    }
+    verifier.finish()
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::domain::DomainTag;
+
+    fn chain(values: &[i64], segment_len: usize, model: &str) -> Vec<ProofEnvelope> {
+        let commitment = ModelCommitment::new(&DomainTag::default(), &[], &[7u8; 32]);
+        let mut state = ChainState::initial();
+        values
+            .chunks(segment_len)
+            .map(|segment| {
+                let options = ProverConfig::default().to_proof_options();
+                let (proof, inputs) = prove_segment(segment, &state, options).unwrap();
+                state = inputs.exported;
+                segment_envelope(ModelId::from_name(model), commitment, proof, inputs)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chain_verifies_to_dataset_state() {
+        let values: Vec<i64> = (0..11).map(|i| i * i - 40).collect();
+        let envelopes = chain(&values, 4, "demo");
+        let verdict = verify_chain(&envelopes, default_acceptable_options()).unwrap();
+        assert_eq!(verdict.segments, 3);
+        assert_eq!(verdict.initial, ChainState::initial());
+        assert_eq!(verdict.exported, chain_state(&values));
+        assert_eq!(verdict.exported.sum, to_field(values.iter().sum::<i64>() as i128));
+        assert_eq!(verdict.exported.rows, 11);
+
+        let imported = ChainState::initial();
+        let inputs = SegmentInputs { imported, exported: imported.absorb(&values[..4]) };
+        crate::air_test!(SegmentAir, inputs)
+            .with_trace(&build_segment_trace(&values[..4], &imported))
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        // incremental verification resumes from the last accepted state
+        let mut verifier = ChainVerifier::new(default_acceptable_options());
+        verifier.push(&envelopes[0]).unwrap();
+        let mut resumed = ChainVerifier::resuming(*verifier.state(), default_acceptable_options());
+        for envelope in &envelopes[1..] {
+            resumed.push(envelope).unwrap();
+        }
+        assert_eq!(resumed.finish().unwrap().exported, verdict.exported);
+    }
+
+    #[test]
+    fn test_rejects_broken_chains() {
+        let values: Vec<i64> = (1..=9).collect();
+        let envelopes = chain(&values, 3, "demo");
+        let options = default_acceptable_options;
+
+        let skipped = [envelopes[0].clone(), envelopes[2].clone()];
+        assert_eq!(verify_chain(&skipped, options()).unwrap_err(), ChainError::Broken { segment: 1 });
+        let reordered = [envelopes[1].clone(), envelopes[0].clone()];
+        assert_eq!(verify_chain(&reordered, options()).unwrap_err(), ChainError::Broken { segment: 0 });
+        let mut mixed = envelopes.clone();
+        mixed[2].model_id = ModelId::from_name("other");
+        assert_eq!(verify_chain(&mixed, options()).unwrap_err(), ChainError::ModelMismatch { segment: 2 });
+        assert_eq!(verify_chain(&[], options()).unwrap_err(), ChainError::Empty);
+
+        // a segment claiming another export than it proved
+        let mut forged = envelopes[1].clone();
+        let mut inputs = SegmentInputs::from_elements(&forged.public_inputs).unwrap();
+        inputs.exported.sum += BaseElement::ONE;
+        forged.public_inputs = inputs.to_elements();
+        forged.constraint_fingerprint = segment_fingerprint(&forged.proof, inputs);
+        assert!(matches!(
+            verify_chain(&[envelopes[0].clone(), forged], options()),
+            Err(ChainError::Invalid { segment: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,833 @@
+//! Command line front end, used by the `stark-cli` binary.
+//!
+//! ```text
+//! stark-cli prove --model NAME --salt HEX --slope M --intercept B --samples X1,X2,.. --x X --out FILE
+//!                 [--encoding compact|on-chain] [--schema FILE] [--audit-log FILE] [--operator NAME]
+//!                 [--operator-key FILE] [--drand URL [--drand-round N]] [--domain TAG] [--coin default|keccak]
+//!                 [--nonces FILE] [--terms FILE] [--security-profile 80|100|128] [--locale en|de|es|fr]
+//!                 [--encrypt-to KEY1,KEY2,..] [--provenance yes|no] [--store URL] [--time-budget S]
+//!                 [--pkcs11-module FILE --pkcs11-key-id HEX] [--diagnostics FILE] [LIMITS]
+//! stark-cli verify --envelope FILE [--registry FILE] [--trusted-operators KEY1,KEY2,..]
+//!                  [--drand-chain FILE [--max-beacon-age S]]
+//!                  [--domain TAG] [--coin default|keccak] [--slope M --intercept B] [--nonces FILE]
+//!                  [--terms FILE] [--security-profile 80|100|128] [--locale en|de|es|fr] [--decryption-key FILE]
+//!                  [--min-prover-version X.Y.Z] [--trusted-hardware-keys KEY1,KEY2,..] [--commit-config yes|no]
+//! stark-cli audit-verify --log FILE
+//! stark-cli migrate --envelope FILE --out FILE [--encoding compact|on-chain]
+//! stark-cli diff-trace --a TRACE --b TRACE [--inputs-a FILE --inputs-b FILE]
+//! stark-cli calibrate --out FILE
+//! stark-cli analyze --air <linear|freshness> --samples N [--calibration FILE]
+//! stark-cli recommend --air <linear|freshness> --samples N [--security BITS]
+//! stark-cli commit --data FILE --state FILE
+//! stark-cli inspect --diagnostics FILE
+//! stark-cli test-vectors --out DIR
+//! stark-cli serve --addr HOST:PORT [--audit-log FILE] [--operator NAME] [--domain TAG] [--store URL] [LIMITS]
+//!                 (feature `server`)
+//! ```
+//!
+//! `--operator-key` holds the hex-encoded 32-byte Ed25519 seed the envelope's claim hash is
+//! signed with; `verify` then prints the signer, and with `--trusted-operators` rejects envelopes
+//! not signed by one of the listed public keys.
+//!
+//! `prove --pkcs11-module` has the Ed25519 key with id `--pkcs11-key-id` on a PKCS#11 token,
+//! e.g. a YubiKey through `libykcs11.so`, attest the claim hash, logging in with the PIN in
+//! `STARK_PKCS11_PIN` if set; `verify` prints the device key, and with `--trusted-hardware-keys`
+//! rejects envelopes not attested by one of the listed keys, see [`crate::hardware`].
+//!
+//! `--coin keccak` draws the Fiat–Shamir challenges from the Keccak transcript an EVM verifier
+//! replicates (`verifier/KeccakCoin.sol`); `verify` must be given the same coin.
+//!
+//! `prove --nonces` binds a fresh claim nonce into the proof and records it in FILE;
+//! `verify --nonces` settles the verified claim in FILE and rejects claims without a nonce or
+//! settled before, as `verifier/ClaimSettlement.sol` does on-chain.
+//!
+//! `prove --terms` binds the hash of the license or terms document in FILE into the claim;
+//! `verify --terms` rejects claims made under other terms or none, see [`crate::terms`].
+//!
+//! `prove --encrypt-to` encrypts the envelope to the listed X25519 public keys, and `verify`
+//! opens such envelopes with the hex-encoded secret key in `--decryption-key`, see
+//! [`crate::encryption`].
+//!
+//! `prove --provenance yes` records the prover's version, git commit, hostname and time of
+//! proving in compact envelopes; `verify` prints it, and with `--min-prover-version` rejects
+//! envelopes of older provers, without provenance or unsigned, see [`crate::provenance`].
+//!
+//! `prove --out` also takes a store location, `file://DIR`, `s3://BUCKET/PREFIX` (feature `s3`)
+//! or `gs://BUCKET/PREFIX` (feature `gcs`), and writes the envelope there under its claim hash;
+//! `--store URL` keeps a compact copy of every envelope proven, which `serve` then answers
+//! `GET /envelopes/{claim hash}` from, see [`crate::storage`].
+//!
+//! `prove --time-budget S` proves within S seconds, switching to cheaper parameters that still
+//! reach the verifier's minimum security (or the `--security-profile` target) when the
+//! configured ones would take too long, and prints what was chosen; see [`crate::budget`].
+//! Like `LIMITS`, it estimates with `--calibration FILE` or calibrates on startup.
+//!
+//! `--security-profile` proves with the parameters of a [`SecurityProfile`] and verifies
+//! requiring its target instead of the defaults.
+//!
+//! `prove` and `verify` print the claim as a sentence in `--locale` (English by default), see
+//! [`crate::describe`].
+//!
+//! When `verify` rejects an envelope it lists what in the claim differs from what the verifier
+//! expects; with the model's `--slope` and `--intercept` it also points at the wrong `y` values.
+//!
+//! `--drand` binds a round of the drand network served at URL (the latest one without
+//! `--drand-round`) into the claim after checking its signature against the network's `/info`;
+//! the sample points and `--x` are then drawn from the round and `--samples` only sets how many
+//! there are. `verify --drand-chain` takes that info as a JSON file and rejects envelopes without
+//! a round signed by the network, or with `--max-beacon-age S` one published more than S seconds
+//! ago.
+//!
+//! `migrate` rewrites an envelope of an earlier version in the current version of `--encoding`
+//! (its own encoding by default), or says why it has to be proven again, see [`crate::migrate`].
+//! Proofs of versions predating the configuration commitment verify with `--commit-config no`.
+//!
+//! `LIMITS` are `--max-seconds S` and `--max-memory-mb M`; jobs estimated to exceed them are
+//! rejected. Time estimates use `--calibration FILE` as written by `calibrate`, or calibrate
+//! on startup when it is not given.
+//!
+//! `recommend` benchmarks proving the claim's shape over every field and extension on this
+//! machine and prints the fastest one reaching `--security` bits, by default the verifier's
+//! minimum.
+//!
+//! `prove --diagnostics` replays the proof's transcript and writes the out-of-domain frame, the
+//! composition coefficients and the DEEP composition values at every query to FILE as JSON;
+//! `inspect` pretty-prints such a file, see [`crate::diagnostics`].
+//!
+//! `commit` prints the Merkle root of the rows of `--data` (comma-separated field elements per
+//! line) and keeps the frontier in `--state`. Run again after appending rows to the data file,
+//! it only hashes the new rows; earlier rows are assumed unchanged.
+
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    Air, TraceInfo,
+};
+
+use crate::abi::to_hex;
+use crate::analysis;
+use crate::audit::{verify_log, AuditLog};
+use crate::beacon::{BeaconClient, DrandChain};
+use crate::budget::TimeBudget;
+use crate::codec;
+use crate::coin::CoinKind;
+use crate::config::{ProverConfig, SecurityProfile, VerificationPolicy, DEFAULT_MIN_SECURITY_BITS};
+use crate::conformance;
+use crate::dataset::{DatasetCommitment, DatasetError};
+use crate::describe::Locale;
+use crate::diagnostics::DeepDiagnostics;
+use crate::domain::DomainTag;
+use crate::encryption::{self, RecipientKey, RecipientPublicKey};
+use crate::explain::{explain_linear, LinearWitness};
+use crate::fields::{recommend, BenchmarkShape};
+use crate::freshness::{self, FreshnessAir, FreshnessInputs};
+use crate::hardware::{Pkcs11Signer, PIN_VARIABLE};
+use crate::migrate;
+use crate::nonce::NonceManager;
+use crate::operator::{OperatorKey, OperatorPublicKey};
+use crate::registry::ModelRegistry;
+use crate::regression::{self, LinearRegressionAir, LinearRegressionInputs};
+use crate::resources::{calibrate, Calibration, ResourceLimits};
+use crate::serialization::Encoding;
+use crate::session::{verify_linear, LinearClaimRequest, ProvingSession};
+use crate::storage;
+use crate::terms::TermsHash;
+use crate::trace::{diff, diff_with_air, read_trace};
+
+const USAGE: &str = "usage: stark-cli <prove|verify|audit-verify|migrate|diff-trace|calibrate|analyze|recommend|commit|inspect|test-vectors|serve> [--flag value]...";
+
+/// Runs the command described by `args` (without the program name)
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    let flags = parse_flags(rest)?;
+    match command.as_str() {
+        "prove" => prove(&flags),
+        "verify" => verify(&flags),
+        "audit-verify" => {
+            let count = verify_log(required(&flags, "log")?).map_err(|err| err.to_string())?;
+            println!("audit log intact: {count} records");
+            Ok(())
+        }
+        "migrate" => migrate(&flags),
+        "diff-trace" => diff_trace(&flags),
+        "calibrate" => {
+            let calibration = calibrate(&ProverConfig::default()).map_err(|err| err.to_string())?;
+            let out = required(&flags, "out")?;
+            let json = serde_json::to_string_pretty(&calibration).map_err(|err| err.to_string())?;
+            fs::write(out, json).map_err(|err| format!("{out}: {err}"))?;
+            println!("calibration written to {out}");
+            Ok(())
+        }
+        "analyze" => analyze(&flags),
+        "recommend" => {
+            let security = match flags.get("security") {
+                Some(_) => number(&flags, "security")? as u32,
+                None => DEFAULT_MIN_SECURITY_BITS,
+            };
+            let shape = BenchmarkShape::of(&circuit_report(&flags)?);
+            let recommendation = recommend(&shape, &ProverConfig::default(), security).map_err(|err| err.to_string())?;
+            print!("{recommendation}");
+            Ok(())
+        }
+        "commit" => commit(&flags),
+        "inspect" => {
+            let path = required(&flags, "diagnostics")?;
+            let diagnostics = DeepDiagnostics::load(Path::new(path)).map_err(|err| format!("{path}: {err}"))?;
+            print!("{diagnostics}");
+            Ok(())
+        }
+        "test-vectors" => {
+            let out = required(&flags, "out")?;
+            conformance::write_fixtures(Path::new(out)).map_err(|err| format!("{out}: {err}"))?;
+            println!("test vectors written to {out}");
+            Ok(())
+        }
+        #[cfg(feature = "server")]
+        "serve" => {
+            let listener = std::net::TcpListener::bind(required(&flags, "addr")?).map_err(|err| err.to_string())?;
+            crate::server::serve(listener, session(&flags)?).map_err(|err| err.to_string())
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn prove(flags: &HashMap<String, String>) -> Result<(), String> {
+    // every flag is checked before the proof is generated or anything is written
+    let locale = locale(flags)?;
+    let domain = domain(flags)?;
+    let encoding = encoding(flags, Encoding::Compact)?;
+    let recipient =
+        |key: &str| RecipientPublicKey::try_from(key.trim().to_string()).map_err(|err| format!("`{key}`: {err}"));
+    let recipients: Option<Vec<RecipientPublicKey>> =
+        flags.get("encrypt-to").map(|keys| keys.split(',').map(recipient).collect()).transpose()?;
+    let request = LinearClaimRequest {
+        model: required(flags, "model")?.to_string(),
+        salt: required(flags, "salt")?.to_string(),
+        slope: number(flags, "slope")?,
+        intercept: number(flags, "intercept")?,
+        sample_x: required(flags, "samples")?
+            .split(',')
+            .map(|x| x.trim().parse().map_err(|_| format!("invalid sample `{x}`")))
+            .collect::<Result<_, _>>()?,
+        x: number(flags, "x")?,
+        schema: match flags.get("schema") {
+            Some(path) => {
+                let json = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+                Some(serde_json::from_slice(&json).map_err(|err| format!("{path}: {err}"))?)
+            }
+            None => None,
+        },
+        beacon: None,
+        terms_hash: terms_hash(flags)?,
+    };
+    let request = match flags.get("drand") {
+        Some(url) => {
+            let client = BeaconClient::new(url.as_str());
+            let round = flags.get("drand-round").map(|_| number(flags, "drand-round")).transpose()?;
+            let chain = client.info().map_err(|err| format!("{url}: {err}"))?;
+            let beacon = client.verified_round(&chain, round).map_err(|err| format!("{url}: {err}"))?;
+            request.with_beacon(beacon, &domain).map_err(|err| format!("--samples: {err}"))?
+        }
+        None => request,
+    };
+    let out = required(flags, "out")?;
+    let mut session = session(flags)?;
+    if flags.contains_key("diagnostics") {
+        session = session.with_diagnostics();
+    }
+    let envelope = session.prove_linear(&request).map_err(|err| err.to_string())?;
+    if let Some(report) = session.last_budget_report() {
+        println!("time budget: {report}");
+    }
+    if let (Some(path), Some(diagnostics)) = (flags.get("diagnostics"), session.last_diagnostics()) {
+        diagnostics.save(Path::new(path)).map_err(|err| format!("{path}: {err}"))?;
+        println!("DEEP diagnostics written to {path}");
+    }
+
+    let mut bytes = encoding.encode(&envelope);
+    if let Some(recipients) = recipients {
+        bytes = encryption::encrypt(&bytes, &recipients).map_err(|err| err.to_string())?;
+    }
+    let claim_hash = envelope.claim_hash(&domain);
+    let location = if storage::is_store_location(out) {
+        let store = storage::open_store(out).map_err(|err| format!("{out}: {err}"))?;
+        storage::store_envelope(store.as_ref(), &claim_hash, &bytes).map_err(|err| format!("{out}: {err}"))?
+    } else {
+        fs::write(out, bytes).map_err(|err| format!("{out}: {err}"))?;
+        out.to_string()
+    };
+    println!("claim {} written to {location}", to_hex(&claim_hash));
+    println!("{}", envelope.describe(locale));
+    Ok(())
+}
+
+fn verify(flags: &HashMap<String, String>) -> Result<(), String> {
+    // every flag is checked before a nonce is settled
+    let locale = locale(flags)?;
+    let witness = match (flags.get("slope"), flags.get("intercept")) {
+        (Some(_), Some(_)) => Some(LinearWitness {
+            slope: BaseElement::from(number(flags, "slope")?),
+            intercept: BaseElement::from(number(flags, "intercept")?),
+        }),
+        (None, None) => None,
+        _ => return Err("--slope and --intercept must be given together".into()),
+    };
+    let path = required(flags, "envelope")?;
+    let mut bytes = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+    if encryption::is_encrypted(&bytes) {
+        let key_path = flags.get("decryption-key").ok_or(format!("{path}: encrypted, --decryption-key is required"))?;
+        let secret = fs::read_to_string(key_path).map_err(|err| format!("{key_path}: {err}"))?;
+        let key = RecipientKey::from_hex(&secret).map_err(|err| format!("{key_path}: {err}"))?;
+        bytes = encryption::decrypt(&bytes, &key).map_err(|err| format!("{path}: {err}"))?;
+    }
+    let encoding = Encoding::detect(&bytes).ok_or(format!("{path}: not a proof envelope"))?;
+    let envelope = encoding.decode(&bytes).map_err(|err| err.to_string())?;
+
+    if let Some(registry) = flags.get("registry") {
+        let registry = ModelRegistry::load(Path::new(registry)).map_err(|err| err.to_string())?;
+        registry.check_envelope(&envelope).map_err(|err| err.to_string())?;
+    }
+    let trusted_operators = public_keys(flags, "trusted-operators")?;
+    let beacon = match flags.get("drand-chain") {
+        Some(path) => {
+            let json = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+            Some(serde_json::from_slice::<DrandChain>(&json).map_err(|err| format!("{path}: {err}"))?)
+        }
+        None => None,
+    };
+    let policy = VerificationPolicy {
+        domain: domain(flags)?,
+        trusted_operators,
+        trusted_hardware_keys: public_keys(flags, "trusted-hardware-keys")?,
+        beacon,
+        max_beacon_age: flags.get("max-beacon-age").map(|_| number(flags, "max-beacon-age")).transpose()?,
+        required_terms_hash: terms_hash(flags)?,
+        random_coin: coin(flags)?,
+        min_prover_version: match flags.get("min-prover-version") {
+            Some(version) => Some(version.parse().map_err(|err| format!("--min-prover-version: {err}"))?),
+            None => None,
+        },
+        commit_config: match flags.get("commit-config").map(String::as_str) {
+            Some("yes") | None => true,
+            Some("no") => false,
+            Some(other) => return Err(format!("--commit-config must be yes or no, not `{other}`")),
+        },
+        ..security_profile(flags)?.map_or_else(VerificationPolicy::default, |profile| profile.verification_policy())
+    };
+    if let Err(err) = verify_linear(&envelope, &policy) {
+        return Err(format!("{err}\n{}", explain_linear(&envelope, &policy, witness.as_ref())));
+    }
+    if let Some(path) = flags.get("nonces") {
+        let mut nonces = NonceManager::open(path).map_err(|err| format!("{path}: {err}"))?;
+        nonces.settle(&envelope).map_err(|err| format!("{path}: {err}"))?;
+    }
+    println!("proof is valid for claim {}", to_hex(&envelope.claim_hash(&policy.domain)));
+    println!("{}", envelope.describe(locale));
+    if let Some(signer) = &envelope.signer {
+        println!("signed by operator {}", signer.public_key);
+    }
+    if let Some(attestation) = &envelope.attestation {
+        println!("attested by {} key {}", attestation.device, attestation.public_key);
+    }
+    if let Some(beacon) = &envelope.beacon {
+        println!("bound to drand round {} with randomness {}", beacon.round, beacon.randomness);
+    }
+    if let Some(terms_hash) = &envelope.terms_hash {
+        println!("made under terms {terms_hash}");
+    }
+    if let Some(provenance) = &envelope.provenance {
+        println!(
+            "proven by prover {} ({}) on {} at {}",
+            provenance.crate_version, provenance.git_commit, provenance.hostname, provenance.timestamp
+        );
+    }
+    Ok(())
+}
+
+/// The comma-separated Ed25519 public keys in `--{flag}`
+fn public_keys(flags: &HashMap<String, String>, flag: &str) -> Result<Vec<OperatorPublicKey>, String> {
+    match flags.get(flag) {
+        Some(keys) => keys
+            .split(',')
+            .map(|key| OperatorPublicKey::try_from(key.trim().to_string()).map_err(|err| format!("`{key}`: {err}")))
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Re-encodes `--envelope` in the current version of `--encoding` into `--out`
+fn migrate(flags: &HashMap<String, String>) -> Result<(), String> {
+    let path = required(flags, "envelope")?;
+    let bytes = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+    let own = Encoding::detect(&bytes).ok_or(format!("{path}: not a proof envelope"))?;
+    let target = encoding(flags, own)?;
+    let migrated = migrate::migrate(&bytes, target).map_err(|err| format!("{path}: {err}"))?;
+    let out = required(flags, "out")?;
+    fs::write(out, &migrated.bytes).map_err(|err| format!("{out}: {err}"))?;
+    println!("{} written to {out} as {target} v{}", migrated.from, migrate::current_version(target));
+    Ok(())
+}
+
+fn encoding(flags: &HashMap<String, String>, default: Encoding) -> Result<Encoding, String> {
+    match flags.get("encoding").map(String::as_str) {
+        None => Ok(default),
+        Some("compact") => Ok(Encoding::Compact),
+        Some("on-chain") => Ok(Encoding::OnChain),
+        Some(other) => Err(format!("unknown encoding `{other}`")),
+    }
+}
+
+/// Hash of the terms document in `--terms`
+fn terms_hash(flags: &HashMap<String, String>) -> Result<Option<TermsHash>, String> {
+    let Some(path) = flags.get("terms") else { return Ok(None) };
+    let terms = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+    Ok(Some(TermsHash::of(&terms)))
+}
+
+/// Prints the cells that changed between two trace files; with public inputs for both sides
+/// (comma-separated canonical hex or decimal field elements) it also compares the linear regression constraints
+fn diff_trace(flags: &HashMap<String, String>) -> Result<(), String> {
+    let load = |name: &str| {
+        let path = required(flags, name)?;
+        read_trace(path).map_err(|err| format!("{path}: {err}"))
+    };
+    let (a, b) = (load("a")?, load("b")?);
+
+    let result = match (flags.get("inputs-a"), flags.get("inputs-b")) {
+        (Some(inputs_a), Some(inputs_b)) => diff_with_air::<LinearRegressionAir>(
+            &a,
+            read_linear_inputs(inputs_a)?,
+            &b,
+            read_linear_inputs(inputs_b)?,
+            ProverConfig::default().to_proof_options(),
+        )
+        .map_err(|err| err.to_string())?,
+        (None, None) => diff(&a, &b),
+        _ => return Err("--inputs-a and --inputs-b must be given together".into()),
+    };
+    print!("{result}");
+    Ok(())
+}
+
+/// Prints the circuit size of an AIR instance over `--samples` samples with placeholder inputs
+fn analyze(flags: &HashMap<String, String>) -> Result<(), String> {
+    let report = circuit_report(flags)?;
+    print!("{report}");
+
+    if let Some(path) = flags.get("calibration") {
+        let json = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+        let calibration: Calibration = serde_json::from_str(&json).map_err(|err| format!("{path}: {err}"))?;
+        println!("estimated proving time: {:.2}s", report.estimate(&calibration).seconds);
+    }
+    Ok(())
+}
+
+/// Circuit size of the `--air` instance over `--samples` samples
+fn circuit_report(flags: &HashMap<String, String>) -> Result<analysis::CircuitReport, String> {
+    let num_samples = number(flags, "samples")? as usize;
+    let config = ProverConfig::default();
+    let options = config.to_proof_options();
+    match required(flags, "air")? {
+        "linear" => {
+            let inputs = LinearRegressionInputs {
+                x_value: BaseElement::ZERO,
+                predicted_y: BaseElement::ZERO,
+                sample_x_values: vec![BaseElement::ZERO; num_samples],
+                sample_y_values: vec![BaseElement::ZERO; num_samples],
+            };
+            let trace_length = regression::trace_length(num_samples, config.min_trace_length);
+            let trace_info = TraceInfo::new(regression::TRACE_WIDTH, trace_length);
+            Ok(analysis::labeled_report(&LinearRegressionAir::new(trace_info, inputs, options)))
+        }
+        "freshness" => {
+            let inputs = FreshnessInputs { digest: BaseElement::ZERO, num_samples, reference_time: 0, max_age: 0 };
+            let trace_info = TraceInfo::new(freshness::trace_width(), freshness::trace_length(num_samples));
+            Ok(analysis::report(&FreshnessAir::new(trace_info, inputs, options)))
+        }
+        air => Err(format!("unknown AIR `{air}`, expected linear or freshness")),
+    }
+}
+
+/// Appends the rows of `--data` past those already committed in `--state` and prints the root
+fn commit(flags: &HashMap<String, String>) -> Result<(), String> {
+    let data = required(flags, "data")?;
+    let state = Path::new(required(flags, "state")?);
+    let mut commitment = match DatasetCommitment::load(state) {
+        Ok(commitment) => commitment,
+        Err(DatasetError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => DatasetCommitment::new(),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let text = fs::read_to_string(data).map_err(|err| format!("{data}: {err}"))?;
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    let committed = commitment.num_rows() as usize;
+    if lines.len() < committed {
+        return Err(format!("{data} has {} rows, fewer than the {committed} already committed", lines.len()));
+    }
+    for (i, line) in lines.iter().enumerate().skip(committed) {
+        let row = line
+            .split(',')
+            .map(|cell| codec::parse_element(cell).map_err(|err| format!("{data}: line {}: {err}", i + 1)))
+            .collect::<Result<Vec<_>, _>>()?;
+        commitment.append(&row);
+    }
+    commitment.save(state).map_err(|err| err.to_string())?;
+    println!("{} rows ({} new), root {}", commitment.num_rows(), lines.len() - committed, to_hex(&commitment.root()));
+    Ok(())
+}
+
+fn read_linear_inputs(path: &str) -> Result<LinearRegressionInputs, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+    let elements = text
+        .split(',')
+        .map(|value| codec::parse_element(value).map_err(|err| format!("{path}: `{}`: {err}", value.trim())))
+        .collect::<Result<Vec<_>, _>>()?;
+    LinearRegressionInputs::from_elements(&elements)
+        .ok_or_else(|| format!("{path}: not linear regression public inputs"))
+}
+
+fn session(flags: &HashMap<String, String>) -> Result<ProvingSession, String> {
+    let operator = flags.get("operator").map(String::as_str).unwrap_or("cli");
+    let base = security_profile(flags)?.map_or_else(ProverConfig::default, |profile| profile.prover_config());
+    let config = ProverConfig { domain: domain(flags)?, random_coin: coin(flags)?, ..base };
+    let mut session = ProvingSession::new(config, operator);
+    if let Some(path) = flags.get("audit-log") {
+        session = session.with_audit_log(AuditLog::open(path).map_err(|err| err.to_string())?);
+    }
+    if let Some(path) = flags.get("operator-key") {
+        let seed = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+        session = session.with_operator_key(OperatorKey::from_hex(&seed).map_err(|err| format!("{path}: {err}"))?);
+    }
+    match (flags.get("pkcs11-module"), flags.get("pkcs11-key-id")) {
+        (Some(module), Some(key_id)) => {
+            let mut signer = Pkcs11Signer::new(module, key_id);
+            if let Ok(pin) = std::env::var(PIN_VARIABLE) {
+                signer = signer.with_pin(pin);
+            }
+            session = session.with_hardware_signer(Box::new(signer));
+        }
+        (None, None) => {}
+        _ => return Err("--pkcs11-module and --pkcs11-key-id must be given together".into()),
+    }
+    if let Some(path) = flags.get("nonces") {
+        session = session.with_nonce_manager(NonceManager::open(path).map_err(|err| format!("{path}: {err}"))?);
+    }
+    if let Some(location) = flags.get("store") {
+        let store = storage::open_store(location).map_err(|err| format!("{location}: {err}"))?;
+        session = session.with_store(store.into());
+    }
+    match flags.get("provenance").map(String::as_str) {
+        Some("yes") => session = session.with_provenance(),
+        Some("no") | None => {}
+        Some(other) => return Err(format!("--provenance must be yes or no, not `{other}`")),
+    }
+
+    let limits = ResourceLimits {
+        max_seconds: match flags.get("max-seconds") {
+            Some(value) => Some(value.parse().map_err(|_| "--max-seconds must be a number".to_string())?),
+            None => None,
+        },
+        max_memory_bytes: match flags.get("max-memory-mb") {
+            Some(_) => Some(number(flags, "max-memory-mb")?.saturating_mul(1 << 20)),
+            None => None,
+        },
+    };
+    if limits != ResourceLimits::default() {
+        let calibration = calibration(flags, session.config())?;
+        session = session.with_resource_limits(calibration, limits);
+    }
+    if let Some(seconds) = flags.get("time-budget") {
+        let seconds: f64 = seconds.parse().map_err(|_| "--time-budget must be a number".to_string())?;
+        let limit = Duration::try_from_secs_f64(seconds).map_err(|_| "--time-budget must not be negative")?;
+        let policy = security_profile(flags)?.map_or_else(VerificationPolicy::default, |p| p.verification_policy());
+        let calibration = calibration(flags, session.config())?;
+        session = session.with_time_budget(calibration, TimeBudget::new(limit, &policy));
+    }
+    Ok(session)
+}
+
+/// The timing model in `--calibration`, or one fitted on this machine now
+fn calibration(flags: &HashMap<String, String>, config: &ProverConfig) -> Result<Calibration, String> {
+    match flags.get("calibration") {
+        Some(path) => {
+            let json = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+            serde_json::from_str(&json).map_err(|err| format!("{path}: {err}"))
+        }
+        None => calibrate(config).map_err(|err| err.to_string()),
+    }
+}
+
+fn domain(flags: &HashMap<String, String>) -> Result<DomainTag, String> {
+    match flags.get("domain") {
+        Some(tag) => DomainTag::new(tag.as_str()).map_err(|err| err.to_string()),
+        None => Ok(DomainTag::default()),
+    }
+}
+
+fn coin(flags: &HashMap<String, String>) -> Result<CoinKind, String> {
+    flags.get("coin").map_or(Ok(CoinKind::Default), |coin| coin.parse())
+}
+
+/// `--security-profile`, the target in bits of a [`SecurityProfile`]
+fn security_profile(flags: &HashMap<String, String>) -> Result<Option<SecurityProfile>, String> {
+    flags.get("security-profile").map(|profile| profile.parse()).transpose()
+}
+
+fn locale(flags: &HashMap<String, String>) -> Result<Locale, String> {
+    flags.get("locale").map_or(Ok(Locale::default()), |locale| locale.parse())
+}
+
+fn parse_flags(args: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut flags = HashMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = arg.strip_prefix("--").ok_or_else(|| format!("unexpected argument `{arg}`"))?;
+        let value = args.next().ok_or_else(|| format!("missing value for --{name}"))?;
+        flags.insert(name.to_string(), value.clone());
+    }
+    Ok(flags)
+}
+
+fn required<'a>(flags: &'a HashMap<String, String>, name: &str) -> Result<&'a str, String> {
+    flags.get(name).map(String::as_str).ok_or_else(|| format!("missing --{name}"))
+}
+
+fn number(flags: &HashMap<String, String>, name: &str) -> Result<u64, String> {
+    required(flags, name)?.parse().map_err(|_| format!("--{name} must be an unsigned integer"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_commands() {
+        let dir = std::env::temp_dir().join(format!("stark-cli-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let envelope = dir.join("claim.stke");
+        let log = dir.join("audit.jsonl");
+        let salt = to_hex(&[1u8; 32]);
+
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3,7,10 --x 8 --out {} --audit-log {}",
+            envelope.display(),
+            log.display()
+        )))
+        .unwrap();
+        run(&args(&format!("verify --envelope {}", envelope.display()))).unwrap();
+        assert!(run(&args(&format!("verify --envelope {} --domain other-app", envelope.display()))).is_err());
+        run(&args(&format!("audit-verify --log {}", log.display()))).unwrap();
+        let on_chain = dir.join("envelope.onchain");
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --encoding on-chain",
+            on_chain.display()
+        )))
+        .unwrap();
+        run(&args(&format!("verify --envelope {}", on_chain.display()))).unwrap();
+        let migrated = dir.join("migrated.stke");
+        run(&args(&format!("migrate --envelope {} --out {}", on_chain.display(), migrated.display()))).unwrap();
+        assert_eq!(fs::read(&migrated).unwrap(), fs::read(&on_chain).unwrap());
+        let mut stale = fs::read(&on_chain).unwrap();
+        stale[4] -= 1;
+        fs::write(&migrated, &stale).unwrap();
+        run(&args(&format!("migrate --envelope {0} --out {0}", migrated.display()))).unwrap();
+        assert_eq!(fs::read(&migrated).unwrap(), fs::read(&on_chain).unwrap());
+        stale[4] -= 1;
+        fs::write(&migrated, stale).unwrap();
+        let rejected = run(&args(&format!("migrate --envelope {0} --out {0}", migrated.display())));
+        assert!(rejected.unwrap_err().contains("must be proven again"));
+        // an envelope of a prover predating the configuration commitment
+        let legacy = ProverConfig { commit_config: false, ..ProverConfig::default() };
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: salt.clone(),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let mut v9 = ProvingSession::new(legacy, "cli").prove_linear(&request).unwrap().to_bytes();
+        v9[4] = 9;
+        fs::write(&migrated, v9).unwrap();
+        run(&args(&format!("migrate --envelope {0} --out {0}", migrated.display()))).unwrap();
+        assert!(run(&args(&format!("verify --envelope {}", migrated.display()))).is_err());
+        run(&args(&format!("verify --envelope {} --commit-config no", migrated.display()))).unwrap();
+        let keccak = dir.join("keccak.stke");
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --coin keccak",
+            keccak.display()
+        )))
+        .unwrap();
+        run(&args(&format!("verify --envelope {} --coin keccak", keccak.display()))).unwrap();
+        let sealed = dir.join("sealed.stkx");
+        let secret = dir.join("recipient.key");
+        fs::write(&secret, to_hex(&[7u8; 32])).unwrap();
+        let recipient = RecipientKey::from_secret([7u8; 32]).public_key();
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --encrypt-to {recipient}",
+            sealed.display()
+        )))
+        .unwrap();
+        let locked = run(&args(&format!("verify --envelope {}", sealed.display())));
+        assert!(locked.unwrap_err().contains("--decryption-key"));
+        run(&args(&format!("verify --envelope {} --decryption-key {}", sealed.display(), secret.display()))).unwrap();
+        assert!(run(&args(&format!("verify --envelope {}", keccak.display()))).is_err());
+        let nonces = dir.join("nonces.log");
+        let once = dir.join("once.stke");
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --nonces {}",
+            once.display(),
+            nonces.display()
+        )))
+        .unwrap();
+        // an invalid flag fails before the nonce is settled or an envelope is written
+        assert!(run(&args(&format!("verify --envelope {} --nonces {} --locale xx", once.display(), nonces.display())))
+            .is_err());
+        let unwritten = dir.join("unwritten.stke");
+        assert!(run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --locale xx",
+            unwritten.display()
+        )))
+        .is_err());
+        assert!(!unwritten.exists());
+        run(&args(&format!("verify --envelope {} --nonces {}", once.display(), nonces.display()))).unwrap();
+        assert!(run(&args(&format!("verify --envelope {} --nonces {}", once.display(), nonces.display()))).is_err());
+        let (terms, other_terms) = (dir.join("terms.txt"), dir.join("other-terms.txt"));
+        fs::write(&terms, "CC-BY-4.0").unwrap();
+        fs::write(&other_terms, "CC-BY-NC-4.0").unwrap();
+        let licensed = dir.join("licensed.stke");
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --terms {}",
+            licensed.display(),
+            terms.display()
+        )))
+        .unwrap();
+        let require = |envelope: &Path, terms: &Path| {
+            format!("verify --envelope {} --terms {}", envelope.display(), terms.display())
+        };
+        run(&args(&require(&licensed, &terms))).unwrap();
+        assert!(run(&args(&require(&licensed, &other_terms))).is_err());
+        assert!(run(&args(&require(&once, &terms))).is_err());
+
+        let key_file = dir.join("operator.key");
+        fs::write(&key_file, to_hex(&[6u8; 32])).unwrap();
+        let operator = OperatorKey::from_seed(&[6u8; 32]).public_key();
+        let signed = dir.join("signed.stke");
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --operator-key {}",
+            signed.display(),
+            key_file.display()
+        )))
+        .unwrap();
+        run(&args(&format!("verify --envelope {} --trusted-operators {operator}", signed.display()))).unwrap();
+        let trusted = format!("--trusted-operators {operator}");
+        assert!(run(&args(&format!("verify --envelope {} {trusted}", on_chain.display()))).is_err());
+        // an operator key in software is no hardware attestation
+        let hardware = format!("--trusted-hardware-keys {operator}");
+        assert!(run(&args(&format!("verify --envelope {} {hardware}", signed.display()))).is_err());
+        let chain = dir.join("drand.json");
+        let info = r#"{"schemeID":"bls-unchained-g1-rfc9380","public_key":"00","genesis_time":0,"period":3}"#;
+        fs::write(&chain, info).unwrap();
+        assert!(run(&args(&format!("verify --envelope {} --drand-chain {}", signed.display(), chain.display()))).is_err());
+        let traced = dir.join("traced.stke");
+        // provenance counts only when the operator signature covers it
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --provenance yes \
+             --operator-key {}",
+            traced.display(),
+            key_file.display()
+        )))
+        .unwrap();
+        let min_version = |envelope: &Path, version: &str| {
+            format!("verify --envelope {} --min-prover-version {version}", envelope.display())
+        };
+        run(&args(&min_version(&traced, crate::provenance::CRATE_VERSION))).unwrap();
+        assert!(run(&args(&min_version(&traced, "999.0.0"))).is_err());
+        assert!(run(&args(&min_version(&signed, "0.0.0"))).is_err());
+        let store = dir.join("store");
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out file://{}",
+            store.display()
+        )))
+        .unwrap();
+        let shard = fs::read_dir(store.join("claims")).unwrap().next().unwrap().unwrap().path();
+        let stored = fs::read_dir(shard).unwrap().next().unwrap().unwrap().path();
+        run(&args(&format!("verify --envelope {}", stored.display()))).unwrap();
+        let diagnostics = dir.join("deep.json");
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --diagnostics {}",
+            traced.display(),
+            diagnostics.display()
+        )))
+        .unwrap();
+        run(&args(&format!("inspect --diagnostics {}", diagnostics.display()))).unwrap();
+        assert!(DeepDiagnostics::load(&diagnostics).unwrap().is_consistent());
+        assert!(run(&args(&format!("inspect --diagnostics {}", envelope.display()))).is_err());
+
+        let trace = dir.join("trace.csv");
+        let xs = [BaseElement::new(1), BaseElement::new(3)];
+        let ys = [BaseElement::new(7), BaseElement::new(11)];
+        let t = crate::regression::build_linear_regression_trace(
+            BaseElement::new(2),
+            BaseElement::new(5),
+            &xs,
+            &ys,
+            BaseElement::new(8),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        crate::trace::write_trace(&t, &trace).unwrap();
+        let inputs = dir.join("inputs.txt");
+        fs::write(&inputs, format!("8,21,1,{},7,11", codec::encode(BaseElement::new(3)))).unwrap();
+        run(&args(&format!(
+            "diff-trace --a {0} --b {0} --inputs-a {1} --inputs-b {1}",
+            trace.display(),
+            inputs.display()
+        )))
+        .unwrap();
+
+        let calibration = dir.join("calibration.json");
+        run(&args(&format!("calibrate --out {}", calibration.display()))).unwrap();
+        let limited = run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --calibration {} --max-memory-mb 0",
+            envelope.display(),
+            calibration.display()
+        )));
+        assert!(limited.unwrap_err().contains("job rejected"));
+        run(&args(&format!(
+            "prove --model demo --salt {salt} --slope 2 --intercept 5 --samples 1,3 --x 8 --out {} --calibration {} --time-budget 60",
+            envelope.display(),
+            calibration.display()
+        )))
+        .unwrap();
+        run(&args(&format!("verify --envelope {}", envelope.display()))).unwrap();
+        run(&args(&format!("analyze --air freshness --samples 3 --calibration {}", calibration.display()))).unwrap();
+        assert!(run(&args("analyze --air sorting --samples 3")).unwrap_err().contains("unknown AIR"));
+        run(&args("recommend --air linear --samples 6 --security 100")).unwrap();
+
+        let data = dir.join("data.csv");
+        let state = dir.join("data.state");
+        let commit = format!("commit --data {} --state {}", data.display(), state.display());
+        fs::write(&data, "1,2\n3,4\n").unwrap();
+        run(&args(&commit)).unwrap();
+        fs::write(&data, "1,2\n3,4\n5,6\n").unwrap();
+        run(&args(&commit)).unwrap();
+        let rows = [[1u64, 2], [3, 4], [5, 6]].map(|row| row.map(BaseElement::from));
+        let resumed = DatasetCommitment::load(&state).unwrap();
+        assert_eq!(resumed, DatasetCommitment::from_rows(rows.iter().map(|row| row.as_slice())));
+        fs::write(&data, "1,2\n").unwrap();
+        assert!(run(&args(&commit)).unwrap_err().contains("fewer than"));
+
+        assert!(run(&args("prove --model demo")).unwrap_err().contains("missing --salt"));
+        assert!(run(&args("frobnicate")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
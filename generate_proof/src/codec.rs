@@ -0,0 +1,159 @@
+//! Canonical string encoding of field elements for APIs and files.
+//!
+//! An element is written as `0x` followed by exactly `2 * ELEMENT_BYTES` lowercase hex digits
+//! of its canonical integer value in big-endian order, e.g. `0x0000000000000000000000000000002a`
+//! for 42 in the f128 field. Every element has exactly one encoding: uppercase digits, missing
+//! leading zeros and values at or above the modulus are rejected.
+//!
+//! [`element`] and [`elements`] plug the encoding into serde via `#[serde(with = "..")]`.
+
+use core::fmt;
+
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+/// Reason a string is not the canonical encoding of a field element
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodecError {
+    MissingPrefix,
+    Length { expected: usize, found: usize },
+    InvalidDigit,
+    OutOfRange,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix => write!(f, "field element must start with `0x`"),
+            Self::Length { expected, found } => {
+                write!(f, "field element must have {expected} hex digits, found {found}")
+            }
+            Self::InvalidDigit => write!(f, "field element must use lowercase hex digits"),
+            Self::OutOfRange => write!(f, "field element is not below the field modulus"),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+/// Canonical hex encoding of `element`
+pub fn encode<F: StarkField>(element: F) -> String {
+    let mut bytes = element.to_bytes();
+    bytes.reverse();
+    crate::abi::to_hex(&bytes)
+}
+
+/// Parses the canonical hex encoding produced by [`encode`]
+pub fn decode<F: StarkField>(text: &str) -> Result<F, CodecError> {
+    let digits = text.strip_prefix("0x").ok_or(CodecError::MissingPrefix)?;
+    let expected = 2 * F::ELEMENT_BYTES;
+    if digits.len() != expected {
+        return Err(CodecError::Length { expected, found: digits.len() });
+    }
+    if !digits.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return Err(CodecError::InvalidDigit);
+    }
+    let mut bytes = crate::abi::from_hex(digits).ok_or(CodecError::InvalidDigit)?;
+    bytes.reverse();
+    F::read_from_bytes(&bytes).map_err(|_| CodecError::OutOfRange)
+}
+
+/// Parses an element from a text file: canonical hex, or a decimal integer below the modulus
+/// as written by earlier versions of the CLI
+pub fn parse_element(text: &str) -> Result<BaseElement, CodecError> {
+    let text = text.trim();
+    if text.starts_with("0x") {
+        return decode(text);
+    }
+    match text.parse::<u128>() {
+        Ok(value) if value < BaseElement::MODULUS => Ok(BaseElement::new(value)),
+        Ok(_) => Err(CodecError::OutOfRange),
+        Err(_) => Err(CodecError::InvalidDigit),
+    }
+}
+
+/// Serde adapter for a single element: `#[serde(with = "crate::codec::element")]`
+pub mod element {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use winterfell::math::StarkField;
+
+    pub fn serialize<F: StarkField, S: Serializer>(element: &F, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::encode(*element))
+    }
+
+    pub fn deserialize<'de, F: StarkField, D: Deserializer<'de>>(deserializer: D) -> Result<F, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        super::decode(&text).map_err(D::Error::custom)
+    }
+}
+
+/// Serde adapter for a list of elements: `#[serde(with = "crate::codec::elements")]`
+pub mod elements {
+    use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+    use winterfell::math::StarkField;
+
+    pub fn serialize<F: StarkField, S: Serializer>(elements: &[F], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+        for &element in elements {
+            seq.serialize_element(&super::encode(element))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, F: StarkField, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<F>, D::Error> {
+        let texts = Vec::<String>::deserialize(deserializer)?;
+        texts.iter().map(|text| super::decode(text).map_err(D::Error::custom)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regression::LinearRegressionInputs;
+    use winterfell::math::{
+        fields::{f62, f64},
+        FieldElement,
+    };
+
+    fn round_trip<F: StarkField>() {
+        for element in [F::ZERO, F::ONE, F::from(42u32), -F::ONE] {
+            let text = encode(element);
+            assert_eq!(text.len(), 2 + 2 * F::ELEMENT_BYTES);
+            assert_eq!(decode::<F>(&text), Ok(element));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_across_fields() {
+        round_trip::<BaseElement>();
+        round_trip::<f64::BaseElement>();
+        round_trip::<f62::BaseElement>();
+
+        assert_eq!(encode(BaseElement::new(42)), "0x0000000000000000000000000000002a");
+        assert_eq!(encode(f64::BaseElement::new(255)), "0x00000000000000ff");
+        assert_eq!(decode::<f64::BaseElement>("0x2a"), Err(CodecError::Length { expected: 16, found: 2 }));
+        assert_eq!(decode::<f64::BaseElement>("000000000000002a"), Err(CodecError::MissingPrefix));
+        assert_eq!(decode::<f64::BaseElement>("0xffffffffffffffff"), Err(CodecError::OutOfRange));
+        assert_eq!(decode::<f64::BaseElement>("0x000000000000002A"), Err(CodecError::InvalidDigit));
+
+        assert_eq!(parse_element(" 42 "), Ok(BaseElement::new(42)));
+        assert_eq!(parse_element(&encode(BaseElement::new(42))), Ok(BaseElement::new(42)));
+        assert_eq!(parse_element(&BaseElement::MODULUS.to_string()), Err(CodecError::OutOfRange));
+    }
+
+    #[test]
+    fn test_claim_serde_uses_hex() {
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(6),
+            predicted_y: BaseElement::new(25),
+            sample_x_values: vec![BaseElement::new(1)],
+            sample_y_values: vec![-BaseElement::ONE],
+        };
+        let json = serde_json::to_value(&inputs).unwrap();
+        assert_eq!(json["x_value"], "0x00000000000000000000000000000006");
+        assert_eq!(json["sample_y_values"][0], encode(-BaseElement::ONE));
+        assert_eq!(serde_json::from_value::<LinearRegressionInputs>(json).unwrap(), inputs);
+
+        let decimal = r#"{"x_value":6,"predicted_y":"0x19","sample_x_values":[],"sample_y_values":[]}"#;
+        assert!(serde_json::from_str::<LinearRegressionInputs>(decimal).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Fiat–Shamir random coins a proof's challenges can be drawn from.
+//!
+//! Winterfell's `DefaultRandomCoin` hashes its transcript with the commitment hash, Blake3 in
+//! this crate, which an EVM verifier has to run in software. [`KeccakCoin`] keeps the same
+//! commitments but derives every challenge with Keccak-256 over 32-byte words, which Solidity
+//! computes with a single `keccak256(abi.encodePacked(..))`. `verifier/KeccakCoin.sol` is the
+//! on-chain transcript; `tests::test_solidity_coin_matches_transcript` pins both to the same
+//! draws.
+//!
+//! The transcript is:
+//!
+//! - seeding: `state = keccak256(e_0 || e_1 || ..)` with every seed element a big-endian word,
+//!   `counter = 0`
+//! - reseeding with a commitment `d`: `state = keccak256(state || d)`, `counter = 0`
+//! - the next word: `counter += 1`, `keccak256(state || uint64(counter))`
+//! - a field element: the low `ELEMENT_BYTES` bytes of successive words, one per base element,
+//!   redrawn until canonical
+//! - query positions for `nonce`: `state = keccak256(state || uint64(nonce))`, `counter = 0`,
+//!   then the low 64 bits of each next word masked to the domain size
+//! - proof-of-work: the trailing zeros of the low 64 bits of `keccak256(state || uint64(nonce))`
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest as _, Keccak256};
+use winterfell::{
+    crypto::{Digest, ElementHasher, RandomCoin, RandomCoinError},
+    math::{FieldElement, StarkField},
+};
+
+/// Attempts at drawing a canonical element or a batch of integers before giving up
+const MAX_DRAWS: usize = 1000;
+
+/// Random coin a proof was generated with; prover and verifier must agree on it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoinKind {
+    /// Winterfell's `DefaultRandomCoin` over the commitment hash
+    #[default]
+    Default,
+    /// [`KeccakCoin`], replicated by `verifier/KeccakCoin.sol`
+    Keccak,
+}
+
+impl fmt::Display for CoinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoinKind::Default => write!(f, "default"),
+            CoinKind::Keccak => write!(f, "keccak"),
+        }
+    }
+}
+
+impl core::str::FromStr for CoinKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(CoinKind::Default),
+            "keccak" => Ok(CoinKind::Keccak),
+            other => Err(format!("unknown random coin `{other}`")),
+        }
+    }
+}
+
+/// Keccak-256 transcript over commitments of the hash function `H`
+pub struct KeccakCoin<H: ElementHasher> {
+    state: [u8; 32],
+    counter: u64,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+impl<H: ElementHasher> KeccakCoin<H> {
+    fn absorb(&mut self, data: &[u8]) {
+        self.state = keccak(&[&self.state, data]);
+        self.counter = 0;
+    }
+
+    fn next(&mut self) -> [u8; 32] {
+        self.counter += 1;
+        keccak(&[&self.state, &self.counter.to_be_bytes()])
+    }
+}
+
+fn keccak(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Low 64 bits of a word read as a big-endian integer
+fn low_u64(word: &[u8; 32]) -> u64 {
+    u64::from_be_bytes(word[24..].try_into().expect("Eight bytes"))
+}
+
+impl<B: StarkField, H: ElementHasher<BaseField = B>> RandomCoin for KeccakCoin<H> {
+    type BaseField = B;
+    type Hasher = H;
+
+    fn new(seed: &[B]) -> Self {
+        let words: Vec<[u8; 32]> = seed
+            .iter()
+            .map(|element| {
+                // canonical little-endian bytes, reversed into the low end of the word
+                let mut word = [0u8; 32];
+                let bytes = element.to_bytes();
+                word[32 - bytes.len()..].iter_mut().zip(bytes.iter().rev()).for_each(|(w, &b)| *w = b);
+                word
+            })
+            .collect();
+        Self { state: keccak(&[&words.concat()]), counter: 0, _hasher: PhantomData }
+    }
+
+    fn reseed(&mut self, data: H::Digest) {
+        self.absorb(&data.as_bytes());
+    }
+
+    fn check_leading_zeros(&self, value: u64) -> u32 {
+        low_u64(&keccak(&[&self.state, &value.to_be_bytes()])).trailing_zeros()
+    }
+
+    fn draw<E: FieldElement<BaseField = B>>(&mut self) -> Result<E, RandomCoinError> {
+        let base_bytes = B::ELEMENT_BYTES;
+        for _ in 0..MAX_DRAWS {
+            let mut bytes = Vec::with_capacity(E::ELEMENT_BYTES);
+            for _ in 0..E::ELEMENT_BYTES / base_bytes {
+                let word = self.next();
+                bytes.extend(word[32 - base_bytes..].iter().rev());
+            }
+            if let Some(element) = E::from_random_bytes(&bytes) {
+                return Ok(element);
+            }
+        }
+        Err(RandomCoinError::FailedToDrawFieldElement(MAX_DRAWS))
+    }
+
+    fn draw_integers(
+        &mut self,
+        num_values: usize,
+        domain_size: usize,
+        nonce: u64,
+    ) -> Result<Vec<usize>, RandomCoinError> {
+        assert!(domain_size.is_power_of_two(), "Domain size must be a power of two");
+        assert!(num_values < domain_size, "Number of values must be smaller than the domain size");
+        if num_values > MAX_DRAWS {
+            return Err(RandomCoinError::FailedToDrawIntegers(num_values, 0, MAX_DRAWS));
+        }
+
+        self.absorb(&nonce.to_be_bytes());
+        let mask = (domain_size - 1) as u64;
+        Ok((0..num_values).map(|_| (low_u64(&self.next()) & mask) as usize).collect())
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use winterfell::crypto::{hashers::Blake3_256, Hasher};
+    use winterfell::math::fields::f128::BaseElement;
+
+    use crate::abi::to_hex;
+    use crate::config::{ProverConfig, VerificationPolicy};
+    use crate::session::{verify_linear, LinearClaimRequest, ProvingSession, SessionError};
+
+    type Coin = KeccakCoin<Blake3_256<BaseElement>>;
+
+    #[test]
+    fn test_solidity_coin_matches_transcript() {
+        let source = include_str!("../../verifier/KeccakCoin.t.sol");
+        let constant = |name: &str| {
+            let line = source
+                .lines()
+                .find(|line| line.contains(&format!(" constant {name} = ")))
+                .unwrap_or_else(|| panic!("KeccakCoin.t.sol has no constant {name}"));
+            line.rsplit(" = ").next().unwrap().trim_end_matches(';').to_string()
+        };
+        let number = |name: &str| {
+            let value = constant(name);
+            match value.strip_prefix("0x") {
+                Some(hex) => u128::from_str_radix(hex, 16).unwrap(),
+                None => value.parse().unwrap(),
+            }
+        };
+
+        let commitment = Blake3_256::<BaseElement>::hash(b"commitment");
+        assert_eq!(to_hex(&commitment.as_bytes()), constant("COMMITMENT"));
+        let mut coin = Coin::new(&[1u128, 2, 3].map(BaseElement::new));
+        coin.reseed(commitment);
+        assert_eq!(coin.draw::<BaseElement>().unwrap(), BaseElement::new(number("DRAW_0")));
+        assert_eq!(coin.draw::<BaseElement>().unwrap(), BaseElement::new(number("DRAW_1")));
+        assert_eq!(coin.check_leading_zeros(number("POW_NONCE") as u64) as u128, number("POW_ZEROS"));
+
+        let domain = number("QUERY_DOMAIN") as usize;
+        let positions = coin.draw_integers(4, domain, number("QUERY_NONCE") as u64).unwrap();
+        let expected: Vec<usize> = (0..4).map(|i| number(&format!("POSITION_{i}")) as usize).collect();
+        assert_eq!(positions, expected);
+    }
+
+    #[test]
+    fn test_keccak_proofs_need_keccak_verifier() {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3, 7, 10],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let config = ProverConfig { random_coin: CoinKind::Keccak, grinding_factor: 4, ..Default::default() };
+        let envelope = ProvingSession::new(config, "ci").prove_linear(&request).unwrap();
+        let keccak = VerificationPolicy { random_coin: CoinKind::Keccak, ..Default::default() };
+        verify_linear(&envelope, &keccak).unwrap();
+        assert!(matches!(
+            verify_linear(&envelope, &VerificationPolicy::default()),
+            Err(SessionError::Verifier(_))
+        ));
+        assert_eq!("keccak".parse::<CoinKind>(), Ok(CoinKind::Keccak));
+    }
+}
@@ -0,0 +1,299 @@
+//! Compound interest over a private rate.
+//!
+//! The claim is that `num_steps` rounds of `b_{t+1} = floor(b_t * (1 + r))` take a public
+//! initial balance to a public final balance, for a rate `r` that stays private. Balances are
+//! integers (e.g. token base units); `r` is a fixed-point number with `frac_bits` fractional
+//! bits in `(-1, 1)`, stored as the growth factor `2^frac_bits + r`.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::config::padded_trace_length;
+use crate::gadgets::{FixedPointMul, IntegerColumn, IntegerWidth, PhaseSelector};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`CompoundInterestAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "compound-interest/v1";
+
+/// Phases of a compounding trace: one row per compounding step, then the settled balance
+const COMPOUNDING: usize = 0;
+#[cfg(feature = "prover")]
+const SETTLED: usize = 1;
+
+// Trace layout: balance, growth factor, the phase flags, then the digits of the balance, of
+// the factor and of the rounding remainder of each step
+const BALANCE: usize = 0;
+const FACTOR: usize = 1;
+const PHASES_START: usize = 2;
+const DIGITS_START: usize = PHASES_START + 2;
+
+struct Layout {
+    phases: PhaseSelector,
+    balance: IntegerColumn,
+    factor: IntegerColumn,
+    step: FixedPointMul,
+}
+
+impl Layout {
+    fn new(frac_bits: u32) -> Self {
+        assert!((1..=32).contains(&frac_bits), "Rates support 1..=32 fractional bits");
+        let width = IntegerWidth::U64;
+        let balance = IntegerColumn::new(BALANCE, DIGITS_START, width.bits(), width.digit_bits());
+        // factors below 2: the rate is under 100% per step and `balance * factor` cannot wrap
+        let factor = IntegerColumn::new(FACTOR, balance.next_column(), frac_bits as usize + 1, 1);
+        let step = FixedPointMul::new(factor.next_column(), frac_bits);
+        Self { phases: PhaseSelector::new(PHASES_START, 2), balance, factor, step }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.step.next_column()
+    }
+}
+
+/// Trace length for `num_steps` compounding steps, the settled row and the exempt last row,
+/// padded to at least `min_trace_length` rows
+pub fn trace_length(num_steps: usize, min_trace_length: usize) -> usize {
+    padded_trace_length(num_steps + 2, min_trace_length)
+}
+
+/// Named columns of the trace for rates of `frac_bits` fractional bits, see [`crate::layout`]
+pub fn layout(frac_bits: u32) -> LayoutDescriptor {
+    let layout = Layout::new(frac_bits);
+    LayoutDescriptor::new(AIR_ID)
+        .with_column("balance", BALANCE)
+        .with_column("factor", FACTOR)
+        .with_columns("phases", PHASES_START..DIGITS_START)
+        .with_columns("balance_digits", DIGITS_START..layout.balance.next_column())
+        .with_columns("factor_digits", layout.balance.next_column()..layout.factor.next_column())
+        .with_columns("remainder_digits", layout.factor.next_column()..layout.step.next_column())
+}
+
+/// Reason a compounding schedule cannot be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompoundError {
+    NoSteps,
+    /// The rate is not in `(-1, 1)` at the given precision
+    RateOutOfRange(i128),
+    /// The balance leaves the 64-bit range after `step` steps
+    Overflow { step: usize },
+}
+
+impl fmt::Display for CompoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSteps => write!(f, "no compounding steps"),
+            Self::RateOutOfRange(rate) => write!(f, "rate {rate} is not strictly between -1 and 1"),
+            Self::Overflow { step } => write!(f, "balance exceeds 64 bits after {step} steps"),
+        }
+    }
+}
+
+impl std::error::Error for CompoundError {}
+
+/// Growth factor `2^frac_bits + rate` of a fixed-point rate
+fn growth_factor(rate: i128, frac_bits: u32) -> Result<i128, CompoundError> {
+    let one = 1i128 << frac_bits;
+    if rate <= -one || rate >= one {
+        return Err(CompoundError::RateOutOfRange(rate));
+    }
+    Ok(one + rate)
+}
+
+/// Balances after each of `num_steps` compounding steps, starting with `initial`
+pub fn compound(initial: u64, rate: i128, frac_bits: u32, num_steps: usize) -> Result<Vec<u64>, CompoundError> {
+    let factor = growth_factor(rate, frac_bits)?;
+    let mut balances = vec![initial];
+    for step in 1..=num_steps {
+        let next = (*balances.last().expect("Starts with the initial balance") as i128 * factor) >> frac_bits;
+        balances.push(u64::try_from(next).map_err(|_| CompoundError::Overflow { step })?);
+    }
+    Ok(balances)
+}
+
+/// Public inputs: both balances, the step count and the precision of the rate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompoundInputs {
+    pub frac_bits: u32,
+    pub num_steps: usize,
+    pub initial_balance: u64,
+    pub final_balance: u64,
+}
+
+impl ToElements<BaseElement> for CompoundInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            BaseElement::from(self.frac_bits),
+            BaseElement::from(self.num_steps as u64),
+            BaseElement::from(self.initial_balance),
+            BaseElement::from(self.final_balance),
+        ]
+    }
+}
+
+/// AIR proving `final = floor(... floor(initial * (1 + r)) ...)` over `num_steps` steps.
+///
+/// Pinning the last compounding row puts every earlier row in the compounding phase, so each
+/// of the first `num_steps` rows rounds the product of its balance and the constant factor
+/// into the next balance. The balance and the factor are range checked, so the products stay
+/// far below the field modulus and the rounding is unique.
+pub struct CompoundInterestAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: CompoundInputs,
+}
+
+impl Air for CompoundInterestAir {
+    type BaseField = BaseElement;
+    type PublicInputs = CompoundInputs;
+
+    fn new(trace_info: TraceInfo, inputs: CompoundInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new(inputs.frac_bits);
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert!(inputs.num_steps > 0, "Compounding needs at least one step");
+        assert!(inputs.num_steps + 1 < trace_info.length(), "The trace needs a settled row before the last row");
+
+        // Constraints:
+        // 1. Compounding step rounds balance * factor into the next balance (degree 2: the
+        //    factor is a constant column, the phase flag adds one) and its remainder digits
+        // 2. Factor consistency (degree 1)
+        // 3. Balance and factor match their digits
+        // 4. Phase flags: one-hot, compounding then settled
+        let mut degrees = layout.step.constraint_degrees(PhaseSelector::gated_degree(1));
+        degrees.push(TransitionConstraintDegree::new(1));
+        degrees.extend(layout.balance.constraint_degrees());
+        degrees.extend(layout.factor.constraint_degrees());
+        degrees.extend(layout.phases.constraint_degrees());
+
+        CompoundInterestAir { context: AirContext::new(trace_info, degrees, 3, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+
+        let selector = layout.phases.flag(current, COMPOUNDING);
+        layout.step.evaluate(current, current[BALANCE], current[FACTOR], next[BALANCE], selector, result);
+        let rest = &mut result[layout.step.num_constraints()..];
+        rest[0] = next[FACTOR] - current[FACTOR];
+
+        let rest = &mut rest[1..];
+        layout.balance.evaluate(current, rest);
+        let rest = &mut rest[layout.balance.num_constraints()..];
+        layout.factor.evaluate(current, rest);
+        layout.phases.evaluate(current, next, &mut rest[layout.factor.num_constraints()..]);
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_steps = self.inputs.num_steps;
+        vec![
+            Assertion::single(BALANCE, 0, BaseElement::from(self.inputs.initial_balance)),
+            Assertion::single(BALANCE, num_steps, BaseElement::from(self.inputs.final_balance)),
+            Assertion::single(self.layout.phases.column(COMPOUNDING), num_steps - 1, BaseElement::ONE),
+        ]
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace compounding `initial` at the fixed-point `rate` for `num_steps`
+#[cfg(feature = "prover")]
+pub fn build_compound_trace(
+    initial: u64,
+    rate: i128,
+    frac_bits: u32,
+    num_steps: usize,
+    min_trace_length: usize,
+) -> Result<TraceTable<BaseElement>, CompoundError> {
+    if num_steps == 0 {
+        return Err(CompoundError::NoSteps);
+    }
+    let balances = compound(initial, rate, frac_bits, num_steps)?;
+    let factor = growth_factor(rate, frac_bits)?;
+
+    let layout = Layout::new(frac_bits);
+    let trace_length = trace_length(num_steps, min_trace_length);
+    let mut trace = TraceTable::new(layout.trace_width(), trace_length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+
+    for i in 0..trace_length {
+        // The settled balance repeats after the last step
+        let balance = balances[i.min(num_steps)];
+        if i == trace_length - 1 {
+            layout.balance.fill_exempt_row(&mut row, balance);
+            layout.factor.fill_exempt_row(&mut row, factor as u64);
+            layout.step.fill_exempt_row(&mut row);
+            layout.phases.fill_exempt_row(&mut row, SETTLED);
+        } else {
+            layout.balance.fill(&mut row, balance).expect("Balances are 64-bit");
+            layout.factor.fill(&mut row, factor as u64).expect("Rate is within (-1, 1)");
+            if i < num_steps {
+                layout.step.fill(&mut row, balance as i128, factor).expect("Floor remainder is within 0..2^frac_bits");
+                layout.phases.fill(&mut row, COMPOUNDING);
+            } else {
+                layout.step.fill_unchecked(&mut row);
+                layout.phases.fill(&mut row, SETTLED);
+            }
+        }
+        trace.update_row(i, &row);
+    }
+
+    Ok(trace)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::fixed_point::quantize;
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_compound_interest_proof() {
+        let frac_bits = 24;
+        // 0.5% per step on 1000 tokens with 6 decimals
+        let rate = quantize(0.005, frac_bits);
+        let initial = 1_000_000_000;
+        for num_steps in [6, 12] {
+            let balances = compound(initial, rate, frac_bits, num_steps).unwrap();
+            let inputs = CompoundInputs { frac_bits, num_steps, initial_balance: initial, final_balance: balances[num_steps] };
+            assert!(inputs.final_balance > initial);
+
+            let trace = build_compound_trace(initial, rate, frac_bits, num_steps, TraceInfo::MIN_TRACE_LENGTH).unwrap();
+            crate::air_test!(CompoundInterestAir, inputs)
+                .with_trace(&trace)
+                .expect_all_hold()
+                .expect_assertions_hold()
+                .expect_full_coverage();
+            let proof = prove::<CompoundInterestAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+            let inflated = CompoundInputs { final_balance: inputs.final_balance + 1, ..inputs };
+            assert!(verify::<CompoundInterestAir>(proof.clone(), inflated, &default_acceptable_options()).is_err());
+            verify::<CompoundInterestAir>(proof, inputs, &default_acceptable_options()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_schedules() {
+        assert_eq!(
+            build_compound_trace(1, 1 << 16, 16, 3, TraceInfo::MIN_TRACE_LENGTH).unwrap_err(),
+            CompoundError::RateOutOfRange(1 << 16)
+        );
+        assert_eq!(build_compound_trace(1, 0, 16, 0, TraceInfo::MIN_TRACE_LENGTH).unwrap_err(), CompoundError::NoSteps);
+        assert_eq!(compound(u64::MAX, 1, 16, 2).unwrap_err(), CompoundError::Overflow { step: 1 });
+        // a negative rate decays the balance
+        assert_eq!(compound(1000, -(1 << 15), 16, 2).unwrap(), vec![1000, 500, 250]);
+    }
+}
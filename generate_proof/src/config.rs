@@ -0,0 +1,740 @@
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+// `winter-verifier` does not re-export the batching methods of the proof options
+use winter_air::BatchingMethod;
+use winterfell::{
+    math::{fields::f128::BaseElement, StarkField, ToElements},
+    AcceptableOptions, FieldExtension, ProofOptions, TraceInfo,
+};
+
+use crate::beacon::DrandChain;
+use crate::coin::CoinKind;
+use crate::domain::DomainTag;
+use crate::layout::LayoutDescriptor;
+use crate::nonce::pack_bytes;
+use crate::operator::OperatorPublicKey;
+use crate::provenance::ProverVersion;
+use crate::prover::HASH_FN_NAME;
+use crate::public_inputs::DEFAULT_MAX_PUBLIC_INPUTS;
+use crate::resolver::RegistryEndpoint;
+use crate::terms::TermsHash;
+
+/// Minimum conjectured security (in bits) accepted by default when verifying
+pub const DEFAULT_MIN_SECURITY_BITS: u32 = 95;
+
+/// FRI folding factors Winterfell implements
+pub const FRI_FOLDING_FACTORS: [usize; 4] = [2, 4, 8, 16];
+
+/// Largest FRI remainder degree Winterfell accepts
+pub const MAX_FRI_REMAINDER_DEGREE: usize = 255;
+
+/// Traces of at most this many rows take the small-claim fast path, see
+/// [`ProverConfig::small_claim_options`]
+pub const SMALL_TRACE_ROWS: usize = 64;
+
+/// Most queries a proof can hold
+const MAX_QUERIES: usize = 255;
+/// Largest proof-of-work difficulty Winterfell accepts
+pub const MAX_GRINDING_FACTOR: u32 = 32;
+/// Query security below which Winterfell does not count grinding bits
+const GRINDING_CONTRIBUTION_FLOOR: u32 = 80;
+/// Bits of the 128-bit field and collision resistance of Blake3-256
+const FIELD_BITS: u32 = 128;
+const COLLISION_RESISTANCE: u32 = 128;
+
+/// Name of the base field proofs are generated over
+pub const FIELD_NAME: &str = "f128";
+
+const CONFIG_DOMAIN: &[u8] = b"stark-framework/prover-config/v1";
+
+/// Prover parameters shared by the CLI, the server and library users
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProverConfig {
+    pub num_queries: usize,
+    pub blowup_factor: usize,
+    /// Leading zero bits of the proof-of-work nonce searched before the FRI queries are drawn
+    pub grinding_factor: u32,
+    /// Factor every FRI layer divides the degree by, one of [`FRI_FOLDING_FACTORS`]
+    pub fri_folding_factor: usize,
+    /// FRI stops folding once the polynomial has at most this degree; one less than a power of
+    /// two and at most [`MAX_FRI_REMAINDER_DEGREE`]
+    pub fri_remainder_max_degree: usize,
+    /// Namespace of the deployment the proofs are produced for
+    #[serde(default)]
+    pub domain: DomainTag,
+    /// Claims with more public input elements are rejected before proving
+    #[serde(default = "default_max_public_inputs")]
+    pub max_public_inputs: usize,
+    /// Threads proving, and with it the nonce search, runs on with the `concurrent` feature
+    /// unless `deterministic` is set; 0 uses every core
+    #[serde(default)]
+    pub worker_threads: usize,
+    /// Random coin the Fiat–Shamir challenges are drawn from
+    #[serde(default)]
+    pub random_coin: CoinKind,
+    /// Proves on one thread, so that a claim has the same proof bytes on every machine; see
+    /// [`crate::determinism`]. Multi-threaded proving has to be opted into by clearing it.
+    #[serde(default = "default_deterministic")]
+    pub deterministic: bool,
+    /// Proves traces of up to [`SMALL_TRACE_ROWS`] rows with the cheapest parameters reaching
+    /// the security of the configured ones
+    #[serde(default)]
+    pub small_claim_fast_path: bool,
+    /// Provers pad their traces to at least this many rows, a power of two of at least
+    /// Winterfell's minimum of 8; see [`padded_trace_length`]
+    #[serde(default = "default_min_trace_length")]
+    pub min_trace_length: usize,
+    /// Draws the DEEP and FRI challenges from the quadratic extension of the field, which
+    /// conjectured security beyond 127 bits needs
+    #[serde(default)]
+    pub quadratic_extension: bool,
+    /// Adds a [`ConfigCommitment`] to the public inputs, so that a verifier assuming another
+    /// AIR, field, hash function, random coin or proof options rejects the proof; cleared only
+    /// for verifiers predating the commitment
+    #[serde(default = "default_commit_config")]
+    pub commit_config: bool,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        Self {
+            num_queries: 32,
+            blowup_factor: 8,
+            grinding_factor: 0,
+            fri_folding_factor: 8,
+            fri_remainder_max_degree: 31,
+            domain: DomainTag::default(),
+            max_public_inputs: DEFAULT_MAX_PUBLIC_INPUTS,
+            worker_threads: 0,
+            random_coin: CoinKind::Default,
+            deterministic: true,
+            small_claim_fast_path: false,
+            min_trace_length: TraceInfo::MIN_TRACE_LENGTH,
+            quadratic_extension: false,
+            commit_config: true,
+        }
+    }
+}
+
+impl ProverConfig {
+    /// Checks the parameters Winterfell would otherwise reject with a panic
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(1..=MAX_QUERIES).contains(&self.num_queries) {
+            return Err(ConfigError::NumQueries(self.num_queries));
+        }
+        if !self.blowup_factor.is_power_of_two() || !(2..=128).contains(&self.blowup_factor) {
+            return Err(ConfigError::BlowupFactor(self.blowup_factor));
+        }
+        if self.grinding_factor > MAX_GRINDING_FACTOR {
+            return Err(ConfigError::GrindingFactor(self.grinding_factor));
+        }
+        if !FRI_FOLDING_FACTORS.contains(&self.fri_folding_factor) {
+            return Err(ConfigError::FoldingFactor(self.fri_folding_factor));
+        }
+        let degree = self.fri_remainder_max_degree;
+        if degree > MAX_FRI_REMAINDER_DEGREE || !(degree + 1).is_power_of_two() {
+            return Err(ConfigError::RemainderDegree(degree));
+        }
+        if !self.min_trace_length.is_power_of_two() || self.min_trace_length < TraceInfo::MIN_TRACE_LENGTH {
+            return Err(ConfigError::MinTraceLength(self.min_trace_length));
+        }
+        Ok(())
+    }
+
+    /// Checks that FRI can fold a trace of `trace_length` rows down to the remainder.
+    ///
+    /// Every FRI layer divides the degree bound, initially `trace_length`, by the folding
+    /// factor until the evaluation domain holds at most `(remainder degree + 1) * blowup`
+    /// points. Short traces reach a degree bound below the folding factor first, which
+    /// Winterfell only notices by panicking half way through the proof.
+    pub fn validate_for_trace(&self, trace_length: usize) -> Result<(), ConfigError> {
+        self.validate()?;
+        if schedule_fits(trace_length, self.blowup_factor, self.fri_folding_factor, self.fri_remainder_max_degree) {
+            return Ok(());
+        }
+        let max_folding_factor = FRI_FOLDING_FACTORS
+            .into_iter()
+            .rev()
+            .find(|&factor| schedule_fits(trace_length, self.blowup_factor, factor, self.fri_remainder_max_degree))
+            .unwrap_or(2);
+        let min_remainder_degree = (0..=MAX_FRI_REMAINDER_DEGREE.ilog2() + 1)
+            .map(|bits| (1 << bits) - 1)
+            .find(|&degree| schedule_fits(trace_length, self.blowup_factor, self.fri_folding_factor, degree));
+        Err(ConfigError::FoldingSchedule {
+            trace_length,
+            folding_factor: self.fri_folding_factor,
+            remainder_max_degree: self.fri_remainder_max_degree,
+            max_folding_factor,
+            min_remainder_degree,
+        })
+    }
+
+    /// Proof options for a trace of `trace_length` rows, see [`ProverConfig::validate_for_trace`]
+    pub fn proof_options_for(&self, trace_length: usize) -> Result<ProofOptions, ConfigError> {
+        self.validate_for_trace(trace_length)?;
+        Ok(self.to_proof_options())
+    }
+
+    /// Proof options for a trace of `trace_length` rows whose constraints need a blowup of at
+    /// least `min_blowup`.
+    ///
+    /// With `small_claim_fast_path` set, traces of up to [`SMALL_TRACE_ROWS`] rows use the
+    /// smallest blowup whose number of queries reaches the conjectured security of the
+    /// configured options while staying below the size of the evaluation domain, and a
+    /// remainder degree that ends FRI before the first folding. Otherwise, or when no smaller
+    /// blowup qualifies, this is [`ProverConfig::proof_options_for`].
+    pub fn small_claim_options(&self, trace_length: usize, min_blowup: usize) -> Result<ProofOptions, ConfigError> {
+        let options = self.proof_options_for(trace_length)?;
+        if !self.small_claim_fast_path || trace_length > SMALL_TRACE_ROWS {
+            return Ok(options);
+        }
+        let target = conjectured_security_bits(&options);
+        let extension = self.field_extension();
+        let blowups = (min_blowup.max(2).next_power_of_two()..=self.blowup_factor).filter(|b| b.is_power_of_two());
+        let fast = blowups.clone().find_map(|blowup| {
+            let num_queries = min_queries(blowup, self.grinding_factor, extension, target)?;
+            (num_queries < trace_length * blowup).then(|| {
+                ProofOptions::new(
+                    num_queries,
+                    blowup,
+                    self.grinding_factor,
+                    extension,
+                    self.fri_folding_factor,
+                    trace_length - 1,
+                    BatchingMethod::Linear,
+                    BatchingMethod::Linear,
+                )
+            })
+        });
+        Ok(fast.unwrap_or(options))
+    }
+
+    /// Threads proving runs on: one in deterministic mode, `worker_threads` otherwise
+    pub fn proving_threads(&self) -> usize {
+        if self.deterministic { 1 } else { self.worker_threads }
+    }
+
+    pub fn field_extension(&self) -> FieldExtension {
+        if self.quadratic_extension { FieldExtension::Quadratic } else { FieldExtension::None }
+    }
+
+    /// Commitment a proof of the AIR of `layout` with `options` carries under this configuration,
+    /// if any
+    pub fn config_commitment(&self, layout: &LayoutDescriptor, options: &ProofOptions) -> Option<ConfigCommitment> {
+        self.commit_config.then(|| ConfigCommitment::new(layout, &self.domain, self.random_coin, options))
+    }
+
+    pub fn to_proof_options(&self) -> ProofOptions {
+        ProofOptions::new(
+            self.num_queries,
+            self.blowup_factor,
+            self.grinding_factor,
+            self.field_extension(),
+            self.fri_folding_factor,
+            self.fri_remainder_max_degree,
+            BatchingMethod::Linear,
+            BatchingMethod::Linear,
+        )
+    }
+}
+
+/// Whether every FRI layer for a trace of `trace_length` rows divides its degree bound evenly
+fn schedule_fits(trace_length: usize, blowup: usize, folding_factor: usize, remainder_max_degree: usize) -> bool {
+    let (mut degree_bound, mut domain_size) = (trace_length, trace_length * blowup);
+    while domain_size > (remainder_max_degree + 1) * blowup {
+        if !degree_bound.is_multiple_of(folding_factor) {
+            return false;
+        }
+        degree_bound /= folding_factor;
+        domain_size /= folding_factor;
+    }
+    true
+}
+
+/// A [`ProverConfig`] Winterfell cannot prove with, for any trace or for a given trace length
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The number of queries is not in `1..=255`
+    NumQueries(usize),
+    /// The blowup factor is not a power of two in `2..=128`
+    BlowupFactor(usize),
+    /// The grinding factor exceeds [`MAX_GRINDING_FACTOR`]
+    GrindingFactor(u32),
+    FoldingFactor(usize),
+    RemainderDegree(usize),
+    /// The minimum trace length is not a power of two of at least 8
+    MinTraceLength(usize),
+    /// Folding a trace of this length would truncate the degree bound; folding by at most
+    /// `max_folding_factor`, or stopping at `min_remainder_degree` if there is one, works
+    FoldingSchedule {
+        trace_length: usize,
+        folding_factor: usize,
+        remainder_max_degree: usize,
+        max_folding_factor: usize,
+        min_remainder_degree: Option<usize>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::NumQueries(queries) => write!(f, "{queries} queries is not in 1..={MAX_QUERIES}"),
+            ConfigError::BlowupFactor(factor) => write!(f, "blowup factor {factor} is not a power of two in 2..=128"),
+            ConfigError::GrindingFactor(bits) => {
+                write!(f, "grinding factor {bits} exceeds {MAX_GRINDING_FACTOR}")
+            }
+            ConfigError::FoldingFactor(factor) => {
+                write!(f, "FRI folding factor {factor} is not one of {FRI_FOLDING_FACTORS:?}")
+            }
+            ConfigError::RemainderDegree(degree) => write!(
+                f,
+                "FRI remainder degree {degree} is not one less than a power of two up to {MAX_FRI_REMAINDER_DEGREE}"
+            ),
+            ConfigError::MinTraceLength(length) => {
+                let min = TraceInfo::MIN_TRACE_LENGTH;
+                write!(f, "minimum trace length {length} is not a power of two of at least {min}")
+            }
+            ConfigError::FoldingSchedule {
+                trace_length,
+                folding_factor,
+                remainder_max_degree,
+                max_folding_factor,
+                min_remainder_degree,
+            } => {
+                write!(
+                    f,
+                    "FRI folding factor {folding_factor} with remainder degree {remainder_max_degree} cannot fold a \
+                     trace of {trace_length} rows; use a folding factor of {max_folding_factor}"
+                )?;
+                match min_remainder_degree {
+                    Some(degree) => write!(f, " or a remainder degree of at least {degree}"),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn default_max_public_inputs() -> usize {
+    DEFAULT_MAX_PUBLIC_INPUTS
+}
+
+fn default_min_trace_length() -> usize {
+    TraceInfo::MIN_TRACE_LENGTH
+}
+
+/// Length of a trace of `rows` rows padded to a power of two of at least `min_trace_length`
+/// rows, and never fewer than Winterfell's minimum
+pub fn padded_trace_length(rows: usize, min_trace_length: usize) -> usize {
+    rows.max(min_trace_length).max(TraceInfo::MIN_TRACE_LENGTH).next_power_of_two()
+}
+
+/// Conjectured security of `options` over the 128-bit field with Blake3 commitments, as
+/// Winterfell's `ConjecturedSecurity` computes it
+pub fn conjectured_security_bits(options: &ProofOptions) -> u32 {
+    security_bits(options.num_queries(), options.blowup_factor(), options.grinding_factor(), options.field_extension())
+}
+
+/// Fewest queries at `blowup_factor` whose conjectured security reaches `target` bits
+pub(crate) fn min_queries(
+    blowup_factor: usize,
+    grinding_factor: u32,
+    extension: FieldExtension,
+    target: u32,
+) -> Option<usize> {
+    (1..=MAX_QUERIES).find(|&queries| security_bits(queries, blowup_factor, grinding_factor, extension) >= target)
+}
+
+fn security_bits(num_queries: usize, blowup_factor: usize, grinding_factor: u32, extension: FieldExtension) -> u32 {
+    let mut query_security = blowup_factor.ilog2() * num_queries as u32;
+    if query_security >= GRINDING_CONTRIBUTION_FLOOR {
+        query_security += grinding_factor;
+    }
+    ((FIELD_BITS * extension.degree()).min(query_security) - 1).min(COLLISION_RESISTANCE)
+}
+
+fn default_deterministic() -> bool {
+    true
+}
+
+fn default_commit_config() -> bool {
+    true
+}
+
+/// Hash of the configuration a proof is only valid under: the AIR's versioned id and layout
+/// hash, the field, the hash function, the random coin, the deployment's domain and the proof
+/// options.
+///
+/// Provers add it to the public inputs (see [`TaggedInputs`](crate::domain::TaggedInputs)), so it
+/// is absorbed into the transcript before any challenge is drawn. Verifiers recompute it from
+/// their own configuration and the options recorded in the proof instead of trusting a
+/// description shipped alongside it; any difference fails verification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConfigCommitment(pub [u8; 32]);
+
+impl ConfigCommitment {
+    pub fn new(layout: &LayoutDescriptor, domain: &DomainTag, coin: CoinKind, options: &ProofOptions) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(CONFIG_DOMAIN);
+        domain.absorb_into(&mut hasher);
+        hasher.update(&layout.hash().0);
+        for name in [&layout.versioned_id(), FIELD_NAME, HASH_FN_NAME, &coin.to_string()] {
+            hasher.update(&(name.len() as u64).to_le_bytes());
+            hasher.update(name.as_bytes());
+        }
+        for element in ToElements::<BaseElement>::to_elements(options) {
+            hasher.update(&element.as_int().to_le_bytes());
+        }
+        Self(*hasher.finalize().as_bytes())
+    }
+}
+
+impl fmt::Display for ConfigCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", crate::abi::to_hex(&self.0))
+    }
+}
+
+impl ToElements<BaseElement> for ConfigCommitment {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        pack_bytes(&self.0)
+    }
+}
+
+/// Security target proofs are produced and accepted at, fixing the prover parameters and the
+/// verifier's minimum together so that they cannot drift apart
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SecurityProfile {
+    Bits80,
+    Bits100,
+    /// Beyond what the 128-bit field reaches without an extension
+    Bits128,
+}
+
+impl SecurityProfile {
+    pub const ALL: [SecurityProfile; 3] = [SecurityProfile::Bits80, SecurityProfile::Bits100, SecurityProfile::Bits128];
+
+    /// Conjectured security the profile's proofs reach and its verifiers require
+    pub fn target_bits(&self) -> u32 {
+        match self {
+            SecurityProfile::Bits80 => 80,
+            SecurityProfile::Bits100 => 100,
+            SecurityProfile::Bits128 => 128,
+        }
+    }
+
+    /// Fewest queries reaching the target at `blowup_factor`, without grinding
+    pub fn num_queries(&self, blowup_factor: usize) -> usize {
+        (self.target_bits() as usize + 1).div_ceil(blowup_factor.ilog2() as usize)
+    }
+
+    /// Default prover parameters with the fewest queries and the smallest extension reaching
+    /// the target
+    pub fn prover_config(&self) -> ProverConfig {
+        let config = ProverConfig::default();
+        ProverConfig {
+            num_queries: self.num_queries(config.blowup_factor),
+            quadratic_extension: self.target_bits() >= FIELD_BITS,
+            ..config
+        }
+    }
+
+    /// Default policy requiring the target
+    pub fn verification_policy(&self) -> VerificationPolicy {
+        VerificationPolicy { min_security_bits: self.target_bits(), ..VerificationPolicy::default() }
+    }
+
+    pub fn acceptable_options(&self) -> AcceptableOptions {
+        AcceptableOptions::MinConjecturedSecurity(self.target_bits())
+    }
+}
+
+impl fmt::Display for SecurityProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-bit", self.target_bits())
+    }
+}
+
+impl core::str::FromStr for SecurityProfile {
+    type Err = String;
+
+    /// Parses the target in bits, e.g. `100`
+    fn from_str(bits: &str) -> Result<Self, String> {
+        SecurityProfile::ALL
+            .into_iter()
+            .find(|profile| profile.target_bits().to_string() == bits.trim_end_matches("-bit"))
+            .ok_or_else(|| format!("security profile must be one of 80, 100 or 128 bits, not `{bits}`"))
+    }
+}
+
+/// Options a verifier accepts when no stricter policy is configured
+pub fn default_acceptable_options() -> AcceptableOptions {
+    AcceptableOptions::MinConjecturedSecurity(DEFAULT_MIN_SECURITY_BITS)
+}
+
+/// What a verifier requires of a proof beyond its validity
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationPolicy {
+    pub min_security_bits: u32,
+    /// Reject envelopes whose constraint fingerprint differs from the verifier's own AIR, before
+    /// running the (then certainly failing) STARK verification
+    pub require_fingerprint_match: bool,
+    /// Only proofs produced for this deployment are accepted
+    #[serde(default)]
+    pub domain: DomainTag,
+    /// Envelopes with more public input elements are rejected without verifying
+    #[serde(default = "default_max_public_inputs")]
+    pub max_public_inputs: usize,
+    /// Envelopes must carry the model commitment published at this registry
+    #[serde(default)]
+    pub registry: Option<RegistryEndpoint>,
+    /// Proofs must have been ground to at least this many bits of proof-of-work
+    #[serde(default)]
+    pub min_grinding_bits: u32,
+    /// When non-empty, envelopes must be signed by one of these operators
+    #[serde(default)]
+    pub trusted_operators: Vec<OperatorPublicKey>,
+    /// Envelopes must carry a round of this drand network, whose signature is checked
+    #[serde(default)]
+    pub beacon: Option<DrandChain>,
+    /// With `beacon`, rounds published more than this many seconds before verification are
+    /// rejected, see [`DrandChain::earliest_round`]
+    #[serde(default)]
+    pub max_beacon_age: Option<u64>,
+    /// Envelopes must be made under the terms with this hash
+    #[serde(default)]
+    pub required_terms_hash: Option<TermsHash>,
+    /// Random coin the prover drew the Fiat–Shamir challenges from
+    #[serde(default)]
+    pub random_coin: CoinKind,
+    /// Envelopes must record the provenance of a prover of at least this version, covered by
+    /// their operator signature or attestation, see [`crate::provenance`]
+    #[serde(default)]
+    pub min_prover_version: Option<ProverVersion>,
+    /// When non-empty, envelopes must be attested by one of these hardware-held keys, see
+    /// [`crate::hardware`]
+    #[serde(default)]
+    pub trusted_hardware_keys: Vec<OperatorPublicKey>,
+    /// Proofs must commit to the configuration they are verified under, see
+    /// [`ConfigCommitment`]; cleared only for proofs of provers predating the commitment
+    #[serde(default = "default_commit_config")]
+    pub commit_config: bool,
+    /// Proofs must be made with the proof options this configuration picks for their trace, see
+    /// [`VerificationPolicy::expected_options`]; without one, any options reaching
+    /// `min_security_bits` are accepted
+    #[serde(default)]
+    pub prover_config: Option<ProverConfig>,
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        Self {
+            min_security_bits: DEFAULT_MIN_SECURITY_BITS,
+            require_fingerprint_match: true,
+            domain: DomainTag::default(),
+            max_public_inputs: DEFAULT_MAX_PUBLIC_INPUTS,
+            registry: None,
+            min_grinding_bits: 0,
+            trusted_operators: Vec::new(),
+            beacon: None,
+            max_beacon_age: None,
+            required_terms_hash: None,
+            random_coin: CoinKind::Default,
+            min_prover_version: None,
+            trusted_hardware_keys: Vec::new(),
+            commit_config: true,
+            prover_config: None,
+        }
+    }
+}
+
+impl VerificationPolicy {
+    pub fn acceptable_options(&self) -> AcceptableOptions {
+        AcceptableOptions::MinConjecturedSecurity(self.min_security_bits)
+    }
+
+    /// Options a proof of `trace_length` rows, whose constraints need a blowup of at least
+    /// `min_blowup`, must have been made with: those [`ProverConfig::small_claim_options`] of
+    /// `prover_config` picks, or the `found` options of the proof without a `prover_config`
+    pub fn expected_options(
+        &self,
+        trace_length: usize,
+        min_blowup: usize,
+        found: &ProofOptions,
+    ) -> Result<ProofOptions, ConfigError> {
+        match &self.prover_config {
+            Some(config) => config.small_claim_options(trace_length, min_blowup),
+            None => Ok(found.clone()),
+        }
+    }
+
+    /// Commitment a proof of the AIR of `layout` with `options` must carry under this policy, if
+    /// any
+    pub fn config_commitment(&self, layout: &LayoutDescriptor, options: &ProofOptions) -> Option<ConfigCommitment> {
+        self.commit_config.then(|| ConfigCommitment::new(layout, &self.domain, self.random_coin, options))
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::to_hex;
+    use crate::regression;
+    use crate::session::{verify_linear, LinearClaimRequest, ProvingSession, SessionError};
+
+    #[test]
+    fn test_validates_fri_schedule() {
+        let config = ProverConfig::default();
+        for bits in 3..=20 {
+            assert_eq!(config.validate_for_trace(1 << bits), Ok(()), "trace length 2^{bits}");
+        }
+
+        let config = ProverConfig { fri_folding_factor: 16, fri_remainder_max_degree: 3, ..ProverConfig::default() };
+        let err = config.validate_for_trace(8).unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::FoldingSchedule {
+                trace_length: 8,
+                folding_factor: 16,
+                remainder_max_degree: 3,
+                max_folding_factor: 8,
+                min_remainder_degree: Some(7),
+            }
+        );
+        // the suggested changes both work
+        let folding = ProverConfig { fri_folding_factor: 8, ..config.clone() };
+        assert_eq!(folding.validate_for_trace(8), Ok(()));
+        let remainder = ProverConfig { fri_remainder_max_degree: 7, ..config.clone() };
+        let options = remainder.proof_options_for(8).unwrap();
+        assert_eq!(options.to_fri_options().remainder_max_degree(), 7);
+        assert_eq!(config.validate_for_trace(16), Ok(()));
+
+        let invalid = ProverConfig { fri_folding_factor: 3, ..ProverConfig::default() };
+        assert_eq!(invalid.validate(), Err(ConfigError::FoldingFactor(3)));
+        let invalid = ProverConfig { fri_remainder_max_degree: 32, ..ProverConfig::default() };
+        assert_eq!(invalid.validate_for_trace(64), Err(ConfigError::RemainderDegree(32)));
+        for num_queries in [0, 256] {
+            let invalid = ProverConfig { num_queries, ..ProverConfig::default() };
+            assert_eq!(invalid.validate(), Err(ConfigError::NumQueries(num_queries)));
+        }
+        let invalid = ProverConfig { grinding_factor: 33, ..ProverConfig::default() };
+        assert_eq!(invalid.validate(), Err(ConfigError::GrindingFactor(33)));
+        let bounds = ProverConfig { num_queries: 255, grinding_factor: 32, ..ProverConfig::default() };
+        assert_eq!(bounds.proof_options_for(64).unwrap().grinding_factor(), 32);
+    }
+
+    #[test]
+    fn test_min_trace_length() {
+        assert_eq!(padded_trace_length(3, TraceInfo::MIN_TRACE_LENGTH), 8);
+        assert_eq!(padded_trace_length(10, TraceInfo::MIN_TRACE_LENGTH), 16);
+        assert_eq!(padded_trace_length(3, 64), 64);
+        assert_eq!(padded_trace_length(100, 64), 128);
+        for length in [4, 12] {
+            let invalid = ProverConfig { min_trace_length: length, ..ProverConfig::default() };
+            assert_eq!(invalid.validate(), Err(ConfigError::MinTraceLength(length)));
+        }
+
+        let config = ProverConfig { min_trace_length: 64, ..ProverConfig::default() };
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = ProvingSession::new(config, "ci").prove_linear(&request).unwrap();
+        assert_eq!(envelope.proof.trace_info().length(), 64);
+        verify_linear(&envelope, &VerificationPolicy::default()).unwrap();
+    }
+
+    #[test]
+    fn test_small_claim_fast_path() {
+        let config = ProverConfig { small_claim_fast_path: true, ..ProverConfig::default() };
+        let target = conjectured_security_bits(&config.to_proof_options());
+        let options = config.small_claim_options(32, 2).unwrap();
+        assert_eq!((options.blowup_factor(), options.num_queries()), (4, 48));
+        assert_eq!(options.to_fri_options().remainder_max_degree(), 31);
+        assert!(conjectured_security_bits(&options) >= target);
+        // 48 queries need a domain of more than 32 points, and larger traces are left alone
+        assert_eq!(config.small_claim_options(8, 2).unwrap().blowup_factor(), 8);
+        assert_eq!(config.small_claim_options(128, 2).unwrap(), config.to_proof_options());
+        assert_eq!(ProverConfig::default().small_claim_options(32, 2).unwrap(), config.to_proof_options());
+
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: (1..=20).collect(),
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = ProvingSession::new(config, "ci").prove_linear(&request).unwrap();
+        assert_eq!(envelope.proof.options().blowup_factor(), 4);
+        verify_linear(&envelope, &VerificationPolicy::default()).unwrap();
+    }
+
+    #[test]
+    fn test_proofs_commit_to_configuration() {
+        let options = ProverConfig::default().to_proof_options();
+        let commitment = |layout: &LayoutDescriptor, coin: CoinKind, options: &ProofOptions| {
+            ConfigCommitment::new(layout, &DomainTag::default(), coin, options)
+        };
+        let linear = regression::layout();
+        let base = commitment(&linear, CoinKind::Default, &options);
+        assert_ne!(base, commitment(&crate::freshness::layout(), CoinKind::Default, &options));
+        let regrouped = LayoutDescriptor::new(regression::AIR_ID).with_columns("columns", 0..regression::TRACE_WIDTH);
+        assert_ne!(base, commitment(&regrouped, CoinKind::Default, &options));
+        assert_ne!(base, commitment(&linear, CoinKind::Keccak, &options));
+        let cheaper = ProverConfig { num_queries: 40, ..ProverConfig::default() }.to_proof_options();
+        assert_ne!(base, commitment(&linear, CoinKind::Default, &cheaper));
+        assert_eq!(VerificationPolicy::default().config_commitment(&linear, &options), Some(base));
+
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let legacy_policy = VerificationPolicy { commit_config: false, ..VerificationPolicy::default() };
+        let committed = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request).unwrap();
+        verify_linear(&committed, &VerificationPolicy::default()).unwrap();
+        assert!(verify_linear(&committed, &legacy_policy).is_err());
+
+        // proofs of provers predating the commitment only verify when the policy expects none
+        let legacy = ProverConfig { commit_config: false, ..ProverConfig::default() };
+        let envelope = ProvingSession::new(legacy, "ci").prove_linear(&request).unwrap();
+        verify_linear(&envelope, &legacy_policy).unwrap();
+        assert!(verify_linear(&envelope, &VerificationPolicy::default()).is_err());
+
+        // a policy expecting options compares them with the proof's instead of trusting them
+        let expecting = |config: &ProverConfig| VerificationPolicy {
+            prover_config: Some(config.clone()),
+            ..VerificationPolicy::default()
+        };
+        verify_linear(&committed, &expecting(&ProverConfig::default())).unwrap();
+        let stronger = ProverConfig { num_queries: 40, ..ProverConfig::default() };
+        let envelope = ProvingSession::new(stronger.clone(), "ci").prove_linear(&request).unwrap();
+        verify_linear(&envelope, &expecting(&stronger)).unwrap();
+        let err = verify_linear(&envelope, &expecting(&ProverConfig::default())).unwrap_err();
+        assert!(matches!(err, SessionError::OptionsMismatch), "{err}");
+        let fast = ProverConfig { small_claim_fast_path: true, ..ProverConfig::default() };
+        let envelope = ProvingSession::new(fast.clone(), "ci").prove_linear(&request).unwrap();
+        verify_linear(&envelope, &expecting(&fast)).unwrap();
+        assert!(verify_linear(&envelope, &expecting(&ProverConfig::default())).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Test vectors for implementations of the envelope format in other languages.
+//!
+//! Partners verifying claims in Go or TypeScript need to hash claims and decode envelopes
+//! byte for byte as this crate does. [`claim_fixtures`] and [`envelope_fixtures`] compute the
+//! JSON fixtures in `test-vectors/` at the repository root, `stark-cli test-vectors` writes
+//! them, and the tests below pin them, so any change to a hash or an encoding shows up as a
+//! diff of the fixtures that other implementations run against.
+//!
+//! Byte strings are `0x`-prefixed hex and field elements decimal strings, as they do not fit
+//! into a JSON number. The envelopes carry tiny but complete proofs of linear claims, proven
+//! with the deterministic default prover. The `linear-deployment` envelope is proven for the
+//! default domain, the deployment the no_std verifiers in `verifier/` and `wasm-verifier/`
+//! are built for.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use winterfell::math::{fields::f128::BaseElement, FieldElement, StarkField};
+
+use crate::abi::to_hex;
+use crate::anchor::envelope_hash;
+use crate::coin::CoinKind;
+use crate::config::ProverConfig;
+use crate::domain::{DomainTag, DEFAULT_TAG};
+use crate::envelope::claim_hash;
+use crate::nonce::ClaimNonce;
+use crate::registry::{ModelCommitment, ModelId};
+use crate::serialization::Encoding;
+use crate::session::{LinearClaimRequest, ProvingSession};
+use crate::terms::TermsHash;
+
+/// File names of the fixtures in `test-vectors/`
+pub const CLAIMS_FILE: &str = "claims.json";
+pub const ENVELOPES_FILE: &str = "envelopes.json";
+
+/// A hash of a string input: a [`ModelId`] of a model name or a [`TermsHash`] of a document
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashVector {
+    pub input: String,
+    pub hash: String,
+}
+
+/// A [`ModelCommitment`] of parameters under a salt
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentVector {
+    pub domain: String,
+    pub parameters: Vec<String>,
+    pub salt: String,
+    pub commitment: String,
+}
+
+/// A [`claim_hash`] of public inputs with the optional nonce and terms hash
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimVector {
+    pub name: String,
+    pub domain: String,
+    pub nonce: Option<String>,
+    pub terms_hash: Option<String>,
+    pub public_inputs: Vec<String>,
+    pub claim_hash: String,
+}
+
+/// Contents of `claims.json`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimFixtures {
+    pub model_ids: Vec<HashVector>,
+    pub terms_hashes: Vec<HashVector>,
+    pub model_commitments: Vec<CommitmentVector>,
+    pub claims: Vec<ClaimVector>,
+}
+
+/// A linear claim proven from `request`, with both encodings of its envelope
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeVector {
+    pub name: String,
+    pub domain: String,
+    pub coin: String,
+    pub request: LinearClaimRequest,
+    pub public_inputs: Vec<String>,
+    pub claim_hash: String,
+    pub compact: String,
+    pub on_chain: String,
+    /// Keccak-256 of the on-chain encoding, see [`envelope_hash`]
+    pub envelope_hash: String,
+}
+
+/// Contents of `envelopes.json`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeFixtures {
+    pub envelopes: Vec<EnvelopeVector>,
+}
+
+fn elements(values: &[BaseElement]) -> Vec<String> {
+    values.iter().map(|value| value.as_int().to_string()).collect()
+}
+
+fn domain(tag: &str) -> DomainTag {
+    DomainTag::new(tag).expect("Fixture domains are valid")
+}
+
+pub fn claim_fixtures() -> ClaimFixtures {
+    let model_ids = ["demo", "house-prices", ""]
+        .into_iter()
+        .map(|name| HashVector { input: name.into(), hash: ModelId::from_name(name).to_string() })
+        .collect();
+    let terms_hashes = ["", "CC-BY-4.0", "Commercial use requires a license.\n"]
+        .into_iter()
+        .map(|terms| HashVector { input: terms.into(), hash: TermsHash::of(terms.as_bytes()).to_string() })
+        .collect();
+    let largest = BaseElement::ZERO - BaseElement::ONE;
+    let commitments = [
+        ("default", [BaseElement::new(2), BaseElement::new(5)], [3u8; 32]),
+        ("acme-lending/prod", [BaseElement::ZERO, largest], [0; 32]),
+    ];
+    let model_commitments = commitments
+        .into_iter()
+        .map(|(tag, parameters, salt)| CommitmentVector {
+            domain: tag.into(),
+            parameters: elements(&parameters),
+            salt: to_hex(&salt),
+            commitment: ModelCommitment::new(&domain(tag), &parameters, &salt).to_string(),
+        })
+        .collect();
+
+    let nonce = ClaimNonce([7u8; 32]);
+    let terms = TermsHash::of(b"CC-BY-4.0");
+    let inputs = [BaseElement::new(8), BaseElement::new(21), largest];
+    let cases = [
+        ("plain", "default", None, None, &inputs[..2]),
+        ("domain", "acme-lending/prod", None, None, &inputs[..2]),
+        ("nonce", "default", Some(nonce), None, &inputs[..2]),
+        ("terms", "default", None, Some(terms), &inputs[..2]),
+        ("nonce-and-terms", "acme-lending/prod", Some(nonce), Some(terms), &inputs[..]),
+        ("no-inputs", "default", None, None, &inputs[..0]),
+    ];
+    let claims = cases
+        .into_iter()
+        .map(|(name, tag, nonce, terms_hash, inputs)| ClaimVector {
+            name: name.into(),
+            domain: tag.into(),
+            nonce: nonce.map(|nonce| nonce.to_string()),
+            terms_hash: terms_hash.map(|hash| hash.to_string()),
+            public_inputs: elements(inputs),
+            claim_hash: to_hex(&claim_hash(&domain(tag), None, None, nonce.as_ref(), terms_hash.as_ref(), inputs)),
+        })
+        .collect();
+    ClaimFixtures { model_ids, terms_hashes, model_commitments, claims }
+}
+
+pub fn envelope_fixtures() -> EnvelopeFixtures {
+    let request = LinearClaimRequest {
+        model: "demo".into(),
+        salt: to_hex(&[3u8; 32]),
+        slope: 2,
+        intercept: 5,
+        sample_x: vec![1, 3],
+        x: 8,
+        schema: None,
+        beacon: None,
+        terms_hash: None,
+    };
+    let with_terms = LinearClaimRequest { terms_hash: Some(TermsHash::of(b"CC-BY-4.0")), ..request.clone() };
+    let cases = [
+        ("linear", "default", CoinKind::Default, request.clone()),
+        ("linear-keccak-terms", "acme-lending/prod", CoinKind::Keccak, with_terms),
+        ("linear-deployment", DEFAULT_TAG, CoinKind::Default, request),
+    ];
+    let envelopes = cases
+        .into_iter()
+        .map(|(name, tag, coin, request)| {
+            let config = ProverConfig { domain: domain(tag), random_coin: coin, ..ProverConfig::default() };
+            let envelope = ProvingSession::new(config, "conformance")
+                .prove_linear(&request)
+                .expect("Fixture claims hold");
+            EnvelopeVector {
+                name: name.into(),
+                domain: tag.into(),
+                coin: coin.to_string(),
+                public_inputs: elements(&envelope.public_inputs),
+                claim_hash: to_hex(&envelope.claim_hash(&domain(tag))),
+                compact: to_hex(&Encoding::Compact.encode(&envelope)),
+                on_chain: to_hex(&Encoding::OnChain.encode(&envelope)),
+                envelope_hash: to_hex(&envelope_hash(&envelope)),
+                request,
+            }
+        })
+        .collect();
+    EnvelopeFixtures { envelopes }
+}
+
+/// Writes both fixtures into `dir`
+pub fn write_fixtures(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let claims = serde_json::to_string_pretty(&claim_fixtures()).expect("Fixtures serialize to JSON");
+    fs::write(dir.join(CLAIMS_FILE), claims + "\n")?;
+    let envelopes = serde_json::to_string_pretty(&envelope_fixtures()).expect("Fixtures serialize to JSON");
+    fs::write(dir.join(ENVELOPES_FILE), envelopes + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::from_hex;
+    use crate::config::VerificationPolicy;
+    use crate::session::verify_linear;
+
+    const REGENERATE: &str = "regenerate with `stark-cli test-vectors --out ../test-vectors`";
+
+    #[test]
+    fn test_claim_fixtures_are_pinned() {
+        let pinned: ClaimFixtures = serde_json::from_str(include_str!("../../test-vectors/claims.json")).unwrap();
+        assert_eq!(claim_fixtures(), pinned, "claim hashing changed, {REGENERATE}");
+    }
+
+    #[test]
+    fn test_envelope_fixtures_are_pinned() {
+        let pinned: EnvelopeFixtures = serde_json::from_str(include_str!("../../test-vectors/envelopes.json")).unwrap();
+        assert_eq!(envelope_fixtures(), pinned, "envelope encoding changed, {REGENERATE}");
+
+        // the fixtures decode and verify as they are
+        for vector in &pinned.envelopes {
+            let policy = VerificationPolicy {
+                domain: domain(&vector.domain),
+                random_coin: vector.coin.parse().unwrap(),
+                ..VerificationPolicy::default()
+            };
+            for hex in [&vector.compact, &vector.on_chain] {
+                let bytes = from_hex(hex).unwrap();
+                let envelope = Encoding::detect(&bytes).unwrap().decode(&bytes).unwrap();
+                assert_eq!(to_hex(&envelope.claim_hash(&policy.domain)), vector.claim_hash);
+                verify_linear(&envelope, &policy).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Transition constraints as expression trees, with automatic degree reduction.
+//!
+//! An AIR with high-degree constraints, such as a polynomial model or a chain of products,
+//! pays for every extra degree with a larger composition polynomial. [`ConstraintSystem`]
+//! collects constraints written as [`Expr`] trees over the cells of the current and next row,
+//! and [`ConstraintSystem::reduce`] rewrites them so that no constraint exceeds a chosen
+//! degree: every product that would, has a factor moved into a new intermediate column `w`,
+//! defined by the constraint `w - factor = 0`. Equal factors share one column.
+//!
+//! [`ReducedSystem`] then supplies what an AIR needs: the total width, the declared degrees,
+//! the evaluation of every constraint into the result slice, and [`ReducedSystem::fill`],
+//! which appends the intermediate columns to the caller's columns. Degrees follow the
+//! convention of the hand-written AIRs: cells of columns registered as constant count as
+//! degree 0, every other cell as degree 1.
+//!
+//! Constraints carry labels such as `linear_relation`, given with
+//! [`ConstraintSystem::push_labeled`], which [`ReducedSystem::labels`] passes on in evaluation
+//! order. AIRs expose theirs through [`ConstraintLabels`], whether built here or written by
+//! hand, and diagnostics such as [`labeled_dry_run`](crate::simulation::labeled_dry_run) and
+//! [`labeled_report`](crate::analysis::labeled_report) name failing constraints by label
+//! instead of by index.
+
+use core::ops::{Add, Mul, Neg, Sub};
+
+use serde::{Deserialize, Serialize};
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::codec;
+
+/// Arithmetic expression over the cells of an evaluation frame, the periodic columns and the
+/// public inputs, exchanged as JSON in [`AirDescription`](crate::interop::AirDescription)s
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expr {
+    #[serde(rename = "const")]
+    Constant(#[serde(with = "codec::element")] BaseElement),
+    /// Cell of the given column in the current row
+    #[serde(rename = "cur")]
+    Current(usize),
+    /// Cell of the given column in the next row
+    Next(usize),
+    /// Value of the given periodic column
+    Periodic(usize),
+    /// Public input element at the given index
+    Public(usize),
+    Add(Vec<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Vec<Expr>),
+    Neg(Box<Expr>),
+    Pow(Box<Expr>, u32),
+}
+
+impl Expr {
+    pub fn constant(value: impl Into<BaseElement>) -> Self {
+        Expr::Constant(value.into())
+    }
+
+    pub fn current(column: usize) -> Self {
+        Expr::Current(column)
+    }
+
+    pub fn next(column: usize) -> Self {
+        Expr::Next(column)
+    }
+
+    pub fn pow(self, exponent: u32) -> Self {
+        Expr::Pow(Box::new(self), exponent)
+    }
+
+    /// `base` raised to `exponent` as products, by repeated squaring so the tree stays balanced
+    fn squarings(base: &Expr, exponent: u32) -> Self {
+        match exponent {
+            0 => Expr::constant(1u64),
+            1 => base.clone(),
+            _ => {
+                let half = Self::squarings(base, exponent / 2);
+                let square = half.clone() * half;
+                if exponent % 2 == 1 { square * base.clone() } else { square }
+            }
+        }
+    }
+
+    /// Largest column referenced, if any
+    pub fn max_column(&self) -> Option<usize> {
+        match self {
+            Expr::Constant(_) | Expr::Periodic(_) | Expr::Public(_) => None,
+            Expr::Current(column) | Expr::Next(column) => Some(*column),
+            Expr::Add(terms) | Expr::Mul(terms) => terms.iter().filter_map(Expr::max_column).max(),
+            Expr::Sub(a, b) => a.max_column().max(b.max_column()),
+            Expr::Neg(a) | Expr::Pow(a, _) => a.max_column(),
+        }
+    }
+
+    /// Whether the expression reads periodic columns or public inputs
+    pub fn reads_inputs(&self) -> bool {
+        match self {
+            Expr::Constant(_) | Expr::Current(_) | Expr::Next(_) => false,
+            Expr::Periodic(_) | Expr::Public(_) => true,
+            Expr::Add(terms) | Expr::Mul(terms) => terms.iter().any(Expr::reads_inputs),
+            Expr::Sub(a, b) => a.reads_inputs() || b.reads_inputs(),
+            Expr::Neg(a) | Expr::Pow(a, _) => a.reads_inputs(),
+        }
+    }
+
+    /// Degree of the expression in the trace cells when the cells of `constant_columns` count
+    /// as degree 0; constants, periodic values and public inputs have degree 0
+    pub fn degree(&self, constant_columns: &[usize]) -> usize {
+        match self {
+            Expr::Constant(_) | Expr::Periodic(_) | Expr::Public(_) => 0,
+            Expr::Current(column) | Expr::Next(column) => !constant_columns.contains(column) as usize,
+            Expr::Add(terms) => terms.iter().map(|term| term.degree(constant_columns)).max().unwrap_or(0),
+            Expr::Sub(a, b) => a.degree(constant_columns).max(b.degree(constant_columns)),
+            Expr::Mul(factors) => factors.iter().map(|factor| factor.degree(constant_columns)).sum(),
+            Expr::Neg(a) => a.degree(constant_columns),
+            Expr::Pow(a, exponent) => a.degree(constant_columns).saturating_mul(*exponent as usize),
+        }
+    }
+
+    pub fn evaluate<E: FieldElement + From<BaseElement>>(
+        &self,
+        current: &[E],
+        next: &[E],
+        periodic_values: &[E],
+        public_inputs: &[BaseElement],
+    ) -> E {
+        let eval = |expr: &Expr| expr.evaluate(current, next, periodic_values, public_inputs);
+        match self {
+            Expr::Constant(value) => E::from(*value),
+            Expr::Current(column) => current[*column],
+            Expr::Next(column) => next[*column],
+            Expr::Periodic(column) => periodic_values[*column],
+            Expr::Public(index) => E::from(public_inputs[*index]),
+            Expr::Add(terms) => terms.iter().fold(E::ZERO, |sum, term| sum + eval(term)),
+            Expr::Sub(a, b) => eval(a) - eval(b),
+            Expr::Mul(factors) => factors.iter().fold(E::ONE, |product, factor| product * eval(factor)),
+            Expr::Neg(a) => -eval(a),
+            Expr::Pow(a, exponent) => eval(a).exp((*exponent).into()),
+        }
+    }
+}
+
+impl Add for Expr {
+    type Output = Expr;
+
+    fn add(self, other: Expr) -> Expr {
+        Expr::Add(vec![self, other])
+    }
+}
+
+impl Sub for Expr {
+    type Output = Expr;
+
+    fn sub(self, other: Expr) -> Expr {
+        Expr::Sub(Box::new(self), Box::new(other))
+    }
+}
+
+impl Mul for Expr {
+    type Output = Expr;
+
+    fn mul(self, other: Expr) -> Expr {
+        Expr::Mul(vec![self, other])
+    }
+}
+
+impl Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        Expr::Neg(Box::new(self))
+    }
+}
+
+/// Names of the transition constraints of an AIR, for diagnostics
+pub trait ConstraintLabels {
+    /// One label per main transition constraint, in evaluation order
+    fn constraint_labels(&self) -> Vec<String>;
+}
+
+/// Transition constraints over a trace of `width` columns, each required to evaluate to zero
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConstraintSystem {
+    width: usize,
+    constant_columns: Vec<usize>,
+    constraints: Vec<Expr>,
+    labels: Vec<String>,
+}
+
+impl ConstraintSystem {
+    pub fn new(width: usize) -> Self {
+        Self { width, ..Self::default() }
+    }
+
+    /// Marks columns that hold the same value on every row, so products with them keep their degree
+    pub fn with_constant_columns(mut self, columns: impl IntoIterator<Item = usize>) -> Self {
+        self.constant_columns = columns.into_iter().collect();
+        self
+    }
+
+    /// Adds the constraint `expr = 0`, labelled `constraint_{index}`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `expr` references a column outside the trace, a periodic column or a public
+    /// input.
+    pub fn push(&mut self, expr: Expr) {
+        let label = format!("constraint_{}", self.constraints.len());
+        self.push_labeled(&label, expr);
+    }
+
+    /// Adds the constraint `expr = 0` under `label`, e.g. `slope_constant`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `expr` references a column outside the trace, a periodic column or a public
+    /// input.
+    pub fn push_labeled(&mut self, label: &str, expr: Expr) {
+        assert!(!expr.reads_inputs(), "Constraint {label} reads periodic columns or public inputs");
+        if let Some(column) = expr.max_column() {
+            assert!(column < self.width, "Constraint references column {column} of a {}-column trace", self.width);
+        }
+        self.constraints.push(expr);
+        self.labels.push(label.to_string());
+    }
+
+    pub fn constraints(&self) -> &[Expr] {
+        &self.constraints
+    }
+
+    /// Label of every constraint, in the order they were pushed
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Largest degree of any constraint
+    pub fn max_degree(&self) -> usize {
+        self.constraints.iter().map(|c| c.degree(&self.constant_columns)).max().unwrap_or(0)
+    }
+
+    /// Rewrites the constraints to degree at most `max_degree` using intermediate columns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_degree` is below 2, which no product of two cells can meet.
+    pub fn reduce(&self, max_degree: usize) -> ReducedSystem {
+        assert!(max_degree >= 2, "Degree reduction needs a target degree of at least 2");
+        let mut reduced = ReducedSystem {
+            base_width: self.width,
+            constant_columns: self.constant_columns.clone(),
+            constraints: Vec::new(),
+            intermediates: Vec::new(),
+            labels: self.labels.clone(),
+            intermediate_labels: Vec::new(),
+        };
+        for constraint in &self.constraints {
+            let first_intermediate = reduced.intermediates.len();
+            let constraint = reduced.reduce(constraint, max_degree);
+            reduced.constraints.push(constraint);
+            let label = &self.labels[reduced.constraints.len() - 1];
+            let definitions = first_intermediate..reduced.intermediates.len();
+            reduced.intermediate_labels.extend(definitions.map(|i| format!("{label}.intermediate_{i}")));
+        }
+        reduced
+    }
+}
+
+/// Constraints of a [`ConstraintSystem`] after degree reduction.
+///
+/// Intermediate column `i` sits at `base_width + i`; the constraints are the reduced original
+/// constraints in their order, then one definition per intermediate column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReducedSystem {
+    base_width: usize,
+    constant_columns: Vec<usize>,
+    constraints: Vec<Expr>,
+    intermediates: Vec<Expr>,
+    labels: Vec<String>,
+    intermediate_labels: Vec<String>,
+}
+
+impl ReducedSystem {
+    fn reduce(&mut self, expr: &Expr, max_degree: usize) -> Expr {
+        match expr {
+            Expr::Constant(_) | Expr::Current(_) | Expr::Next(_) | Expr::Periodic(_) | Expr::Public(_) => {
+                expr.clone()
+            }
+            Expr::Add(terms) => Expr::Add(terms.iter().map(|term| self.reduce(term, max_degree)).collect()),
+            Expr::Sub(a, b) => self.reduce(a, max_degree) - self.reduce(b, max_degree),
+            Expr::Neg(a) => -self.reduce(a, max_degree),
+            Expr::Pow(a, exponent) => self.reduce(&Expr::squarings(a, *exponent), max_degree),
+            Expr::Mul(factors) => {
+                let factors: Vec<Expr> = factors.iter().map(|factor| self.reduce(factor, max_degree)).collect();
+                let mut factors = factors.into_iter();
+                let Some(mut a) = factors.next() else {
+                    return Expr::constant(1u64);
+                };
+                for mut b in factors {
+                    // each round turns the higher-degree factor into a degree-1 cell
+                    while a.degree(&self.constant_columns) + b.degree(&self.constant_columns) > max_degree {
+                        if a.degree(&self.constant_columns) >= b.degree(&self.constant_columns) {
+                            a = self.intermediate(a);
+                        } else {
+                            b = self.intermediate(b);
+                        }
+                    }
+                    a = a * b;
+                }
+                a
+            }
+        }
+    }
+
+    /// Cell of an intermediate column defined as `expr`, shared with earlier equal definitions
+    fn intermediate(&mut self, expr: Expr) -> Expr {
+        let index = match self.intermediates.iter().position(|existing| *existing == expr) {
+            Some(index) => index,
+            None => {
+                self.intermediates.push(expr);
+                self.intermediates.len() - 1
+            }
+        };
+        Expr::Current(self.base_width + index)
+    }
+
+    /// Columns of the trace before the intermediate ones
+    pub fn base_width(&self) -> usize {
+        self.base_width
+    }
+
+    pub fn num_intermediates(&self) -> usize {
+        self.intermediates.len()
+    }
+
+    /// Trace width including the intermediate columns
+    pub fn width(&self) -> usize {
+        self.base_width + self.intermediates.len()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len() + self.intermediates.len()
+    }
+
+    /// Declared degree of every constraint, in evaluation order
+    pub fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        // consistency constraints of constant columns, like `next[a] - current[a]`, are
+        // declared degree 1 as in the hand-written AIRs
+        self.constraints
+            .iter()
+            .chain(&self.intermediates)
+            .map(|expr| TransitionConstraintDegree::new(expr.degree(&self.constant_columns).max(1)))
+            .collect()
+    }
+
+    /// Label of every constraint in evaluation order; the definition of an intermediate column
+    /// is labelled after the constraint that introduced it, e.g. `linear_relation.intermediate_0`
+    pub fn labels(&self) -> Vec<String> {
+        self.labels.iter().chain(&self.intermediate_labels).cloned().collect()
+    }
+
+    /// Writes every constraint evaluation into `result`
+    pub fn evaluate<E: FieldElement + From<BaseElement>>(&self, current: &[E], next: &[E], result: &mut [E]) {
+        let (constraints, definitions) = result[..self.num_constraints()].split_at_mut(self.constraints.len());
+        for (r, constraint) in constraints.iter_mut().zip(&self.constraints) {
+            *r = constraint.evaluate(current, next, &[], &[]);
+        }
+        for (i, (r, definition)) in definitions.iter_mut().zip(&self.intermediates).enumerate() {
+            *r = current[self.base_width + i] - definition.evaluate(current, next, &[], &[]);
+        }
+    }
+
+    /// Appends the intermediate columns to `columns`, the base columns of the trace.
+    ///
+    /// Definitions may read the next row, which wraps around on the last row. The last row of
+    /// every intermediate column is exempt from its definition and is filled with the defined
+    /// value plus one, so no definition vanishes on every row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of columns does not match [`ReducedSystem::base_width`].
+    #[cfg(feature = "prover")]
+    pub fn fill(&self, mut columns: Vec<Vec<BaseElement>>) -> TraceTable<BaseElement> {
+        assert_eq!(columns.len(), self.base_width, "Expected {} base columns", self.base_width);
+        let length = columns.first().map_or(0, Vec::len);
+        columns.extend(vec![vec![BaseElement::ZERO; length]; self.intermediates.len()]);
+
+        let mut current = vec![BaseElement::ZERO; self.width()];
+        let mut next = vec![BaseElement::ZERO; self.width()];
+        for row in 0..length {
+            for (column, values) in columns.iter().enumerate().take(self.base_width) {
+                current[column] = values[row];
+                next[column] = values[(row + 1) % length];
+            }
+            // definitions only read intermediate cells of earlier columns in the current row
+            for (i, definition) in self.intermediates.iter().enumerate() {
+                let mut value = definition.evaluate(&current, &next, &[], &[]);
+                if row == length - 1 {
+                    value += BaseElement::ONE;
+                }
+                current[self.base_width + i] = value;
+                columns[self.base_width + i][row] = value;
+            }
+        }
+        TraceTable::init(columns)
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+    use crate::simulation::labeled_dry_run;
+    use winterfell::{
+        math::ToElements, Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+    };
+
+    // Columns: x, the constant coefficient a, y = a * x^5 + 3 * x^3 - x
+    const X: usize = 0;
+    const A: usize = 1;
+    const Y: usize = 2;
+
+    fn polynomial_system() -> ConstraintSystem {
+        let x = || Expr::current(X);
+        let mut system = ConstraintSystem::new(3).with_constant_columns([A]);
+        let polynomial = Expr::current(A) * x().pow(5) + Expr::constant(3u64) * x().pow(3) - x();
+        system.push_labeled("polynomial", Expr::current(Y) - polynomial);
+        system.push(Expr::next(A) - Expr::current(A));
+        system
+    }
+
+    struct PolynomialAir {
+        context: AirContext<BaseElement>,
+        system: ReducedSystem,
+        first_y: BaseElement,
+    }
+
+    #[derive(Clone)]
+    struct FirstOutput(BaseElement);
+
+    impl ToElements<BaseElement> for FirstOutput {
+        fn to_elements(&self) -> Vec<BaseElement> {
+            vec![self.0]
+        }
+    }
+
+    impl Air for PolynomialAir {
+        type BaseField = BaseElement;
+        type PublicInputs = FirstOutput;
+
+        fn new(trace_info: TraceInfo, first_y: FirstOutput, options: ProofOptions) -> Self {
+            let system = polynomial_system().reduce(2);
+            assert_eq!(system.width(), trace_info.width());
+            let context = AirContext::new(trace_info, system.constraint_degrees(), 1, options);
+            Self { context, system, first_y: first_y.0 }
+        }
+
+        fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+            &self,
+            frame: &EvaluationFrame<E>,
+            _periodic_values: &[E],
+            result: &mut [E],
+        ) {
+            self.system.evaluate(frame.current(), frame.next(), result);
+        }
+
+        fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+            vec![Assertion::single(Y, 0, self.first_y)]
+        }
+
+        fn context(&self) -> &AirContext<Self::BaseField> {
+            &self.context
+        }
+    }
+
+    impl ConstraintLabels for PolynomialAir {
+        fn constraint_labels(&self) -> Vec<String> {
+            self.system.labels()
+        }
+    }
+
+    fn polynomial_trace(system: &ReducedSystem, length: usize) -> (TraceTable<BaseElement>, Vec<BaseElement>) {
+        let a = BaseElement::new(7);
+        let xs: Vec<BaseElement> = (0..length as u64).map(|i| BaseElement::new(i as u128 * 5 + 2)).collect();
+        let mut ys: Vec<BaseElement> =
+            xs.iter().map(|&x| a * x.exp(5) + BaseElement::new(3) * x.exp(3) - x).collect();
+        // the exempt last row breaks the row-local constraint
+        ys[length - 1] += BaseElement::ONE;
+        (system.fill(vec![xs, vec![a; length], ys.clone()]), ys)
+    }
+
+    #[test]
+    fn test_reduces_polynomial_to_degree_two() {
+        let system = polynomial_system();
+        assert_eq!(system.max_degree(), 5);
+        let reduced = system.reduce(2);
+        // x^2 is shared between both powers, x^5 needs x^4 on top of it
+        assert!(reduced.constraint_degrees().iter().all(|degree| degree.min_blowup_factor() <= 2));
+        assert_eq!(reduced.num_intermediates(), 2);
+        assert_eq!(reduced.width(), 5);
+
+        let (trace, ys) = polynomial_trace(&reduced, 16);
+        crate::air_test!(PolynomialAir, FirstOutput(ys[0]))
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<PolynomialAir>(trace, FirstOutput(ys[0]), ProverConfig::default().to_proof_options()).unwrap();
+        assert!(verify::<PolynomialAir>(proof.clone(), FirstOutput(ys[1]), &default_acceptable_options()).is_err());
+        verify::<PolynomialAir>(proof, FirstOutput(ys[0]), &default_acceptable_options()).unwrap();
+    }
+
+    #[test]
+    fn test_labels_survive_reduction() {
+        let reduced = polynomial_system().reduce(2);
+        let labels = ["polynomial", "constraint_1", "polynomial.intermediate_0", "polynomial.intermediate_1"];
+        assert_eq!(reduced.labels(), labels);
+
+        let (mut trace, ys) = polynomial_trace(&reduced, 8);
+        trace.set(Y, 2, ys[2] + BaseElement::ONE);
+        let options = ProverConfig::default().to_proof_options();
+        let report = labeled_dry_run::<PolynomialAir>(&trace, FirstOutput(ys[0]), options).unwrap();
+        assert_eq!(report.transition_failures.len(), 1);
+        assert!(report.to_string().contains("constraint 0 (polynomial) fails on 1 rows, first at row 2"), "{report}");
+    }
+}
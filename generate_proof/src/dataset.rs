@@ -0,0 +1,326 @@
+//! Append-only Merkle commitment to a dataset, resumable across runs.
+//!
+//! Rows are hashed into leaves of a binary tree of fixed depth [`DEPTH`] whose unused leaves
+//! are empty, so the root of a dataset never depends on how it was built. Only the frontier is
+//! kept: one frozen subtree root per set bit of the row count, the roots of the complete
+//! subtrees no later row can change. Appending a row and computing the root each cost
+//! `O(DEPTH)` hashes, and [`DatasetCommitment::save`] persists the frontier so a commitment to
+//! 10M rows resumes from a file of about 1 KiB instead of rehashing every row.
+//!
+//! [`DatasetTree`] keeps every level instead, for datasets whose rows are later opened
+//! individually, and produces [`RowOpening`]s against the same root.
+//!
+//! Proving code uses the builder through the library and claim tooling through
+//! `stark-cli commit`; both compute the same root for the same rows.
+
+use core::fmt;
+use std::{fs, io, path::Path};
+
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+use crate::abi::{from_hex, to_hex};
+
+/// Depth of the tree, bounding a dataset to `2^DEPTH` rows
+pub const DEPTH: usize = 64;
+
+const LEAF_DOMAIN: &[u8] = b"stark-framework/dataset-leaf/v1";
+const NODE_DOMAIN: &[u8] = b"stark-framework/dataset-node/v1";
+
+/// Hash of a row: its length and its elements in canonical little-endian form
+pub fn leaf_hash(row: &[BaseElement]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(LEAF_DOMAIN);
+    hasher.update(&(row.len() as u64).to_le_bytes());
+    for element in row {
+        hasher.update(&element.as_int().to_le_bytes());
+    }
+    *hasher.finalize().as_bytes()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(NODE_DOMAIN);
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+}
+
+/// Roots of empty subtrees, by height
+fn empty_roots() -> [[u8; 32]; DEPTH] {
+    let mut roots = [[0u8; 32]; DEPTH];
+    for height in 1..DEPTH {
+        roots[height] = node_hash(&roots[height - 1], &roots[height - 1]);
+    }
+    roots
+}
+
+/// Errors returned when loading or saving a [`DatasetCommitment`]
+#[derive(Debug)]
+pub enum DatasetError {
+    Malformed(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Malformed(line) => write!(f, "malformed commitment state line {line:?}"),
+            DatasetError::Io(err) => write!(f, "commitment state I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+impl From<io::Error> for DatasetError {
+    fn from(err: io::Error) -> Self {
+        DatasetError::Io(err)
+    }
+}
+
+/// Incremental Merkle commitment to the rows appended so far
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatasetCommitment {
+    num_rows: u64,
+    /// Root of the frozen subtree of height `h` when bit `h` of `num_rows` is set, zero otherwise
+    frontier: [[u8; 32]; DEPTH],
+}
+
+impl Default for DatasetCommitment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatasetCommitment {
+    pub fn new() -> Self {
+        Self { num_rows: 0, frontier: [[0u8; 32]; DEPTH] }
+    }
+
+    /// Commitment to all of `rows`
+    pub fn from_rows<'a>(rows: impl IntoIterator<Item = &'a [BaseElement]>) -> Self {
+        let mut commitment = Self::new();
+        commitment.extend(rows);
+        commitment
+    }
+
+    pub fn num_rows(&self) -> u64 {
+        self.num_rows
+    }
+
+    pub fn append(&mut self, row: &[BaseElement]) {
+        self.append_leaf(leaf_hash(row));
+    }
+
+    pub fn extend<'a>(&mut self, rows: impl IntoIterator<Item = &'a [BaseElement]>) {
+        for row in rows {
+            self.append(row);
+        }
+    }
+
+    /// Appends a leaf hashed elsewhere, e.g. by [`leaf_hash`] on another thread
+    pub fn append_leaf(&mut self, leaf: [u8; 32]) {
+        assert!(self.num_rows < u64::MAX, "Dataset exceeds 2^64 - 1 rows");
+        let mut node = leaf;
+        let mut height = 0;
+        // merge with every frozen subtree the new leaf completes
+        while self.num_rows >> height & 1 == 1 {
+            node = node_hash(&core::mem::take(&mut self.frontier[height]), &node);
+            height += 1;
+        }
+        self.frontier[height] = node;
+        self.num_rows += 1;
+    }
+
+    /// Root of the tree over the rows appended so far
+    pub fn root(&self) -> [u8; 32] {
+        let mut node = [0u8; 32];
+        for (height, (frozen, empty)) in self.frontier.iter().zip(empty_roots()).enumerate() {
+            node = if self.num_rows >> height & 1 == 1 { node_hash(frozen, &node) } else { node_hash(&node, &empty) };
+        }
+        node
+    }
+
+    // PERSISTENCE
+    // --------------------------------------------------------------------------------------------
+
+    /// Reads a state written by [`DatasetCommitment::save`]
+    pub fn load(path: &Path) -> Result<Self, DatasetError> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().unwrap_or_default();
+        let num_rows: u64 = header
+            .strip_prefix("rows ")
+            .and_then(|rows| rows.parse().ok())
+            .ok_or_else(|| DatasetError::Malformed(header.to_string()))?;
+
+        let mut commitment = Self { num_rows, frontier: [[0u8; 32]; DEPTH] };
+        let mut heights = Vec::new();
+        for line in lines {
+            let malformed = || DatasetError::Malformed(line.to_string());
+            let (height, root) = line.split_once(' ').ok_or_else(malformed)?;
+            let height: usize = height.parse().map_err(|_| malformed())?;
+            if height >= DEPTH || num_rows >> height & 1 == 0 || heights.contains(&height) {
+                return Err(malformed());
+            }
+            commitment.frontier[height] = from_hex(root).and_then(|bytes| bytes.try_into().ok()).ok_or_else(malformed)?;
+            heights.push(height);
+        }
+        if heights.len() != num_rows.count_ones() as usize {
+            return Err(DatasetError::Malformed(format!("{} frozen subtrees for {num_rows} rows", heights.len())));
+        }
+        Ok(commitment)
+    }
+
+    /// Writes the row count and frozen subtree roots, replacing `path` atomically
+    pub fn save(&self, path: &Path) -> Result<(), DatasetError> {
+        let mut contents = format!("rows {}\n", self.num_rows);
+        for height in (0..DEPTH).filter(|&height| self.num_rows >> height & 1 == 1) {
+            contents.push_str(&format!("{height} {}\n", to_hex(&self.frontier[height])));
+        }
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, contents)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// A committed row with its authentication path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowOpening {
+    pub index: u64,
+    pub row: Vec<BaseElement>,
+    /// Sibling of the path node at each height, from the leaf up
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl RowOpening {
+    /// Whether this opening proves `row` is row `index` of the dataset with `root`
+    pub fn verify(&self, root: &[u8; 32]) -> bool {
+        if self.siblings.len() != DEPTH {
+            return false;
+        }
+        let mut node = leaf_hash(&self.row);
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            node = if self.index >> height & 1 == 1 { node_hash(sibling, &node) } else { node_hash(&node, sibling) };
+        }
+        node == *root
+    }
+}
+
+/// Every level of a committed dataset, for opening rows
+pub struct DatasetTree {
+    /// Leaves first; each level is padded with an empty root to an even length before hashing
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl DatasetTree {
+    pub fn from_rows<'a>(rows: impl IntoIterator<Item = &'a [BaseElement]>) -> Self {
+        let empty = empty_roots();
+        let mut levels = vec![rows.into_iter().map(leaf_hash).collect::<Vec<_>>()];
+        while levels.last().expect("Starts with the leaves").len() > 1 {
+            let level = levels.last().expect("Starts with the leaves");
+            let height = levels.len() - 1;
+            let next =
+                level.chunks(2).map(|pair| node_hash(&pair[0], pair.get(1).unwrap_or(&empty[height]))).collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    pub fn num_rows(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// Root of the tree, equal to [`DatasetCommitment::root`] over the same rows
+    pub fn root(&self) -> [u8; 32] {
+        let empty = empty_roots();
+        let top = self.levels.len() - 1;
+        let mut node = self.levels[top].first().copied().unwrap_or(empty[0]);
+        for empty in &empty[top..] {
+            node = node_hash(&node, empty);
+        }
+        node
+    }
+
+    /// Opens row `index`, whose contents the caller supplies since the tree only keeps hashes
+    ///
+    /// Returns `None` if `index` is out of range or `row` is not the committed row.
+    pub fn open(&self, index: u64, row: &[BaseElement]) -> Option<RowOpening> {
+        let leaf = self.levels[0].get(usize::try_from(index).ok()?)?;
+        if *leaf != leaf_hash(row) {
+            return None;
+        }
+        let empty = empty_roots();
+        let siblings = (0..DEPTH)
+            .map(|height| {
+                let sibling = self.levels.get(height).and_then(|level| level.get((index >> height ^ 1) as usize));
+                *sibling.unwrap_or(&empty[height])
+            })
+            .collect();
+        Some(RowOpening { index, row: row.to_vec(), siblings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(range: core::ops::Range<u64>) -> Vec<Vec<BaseElement>> {
+        range.map(|i| vec![BaseElement::from(i), BaseElement::from(i * i)]).collect()
+    }
+
+    /// Root by hashing the whole padded tree level by level
+    fn naive_root(rows: &[Vec<BaseElement>]) -> [u8; 32] {
+        let mut level: Vec<[u8; 32]> = rows.iter().map(|row| leaf_hash(row)).collect();
+        for empty in empty_roots() {
+            if level.is_empty() {
+                level.push(empty);
+            }
+            if !level.len().is_multiple_of(2) {
+                level.push(empty);
+            }
+            level = level.chunks(2).map(|pair| node_hash(&pair[0], &pair[1])).collect();
+        }
+        level[0]
+    }
+
+    #[test]
+    fn test_incremental_root_matches_full_tree() {
+        let data = rows(0..37);
+        for count in [0, 1, 2, 3, 8, 37] {
+            let commitment = DatasetCommitment::from_rows(data[..count].iter().map(Vec::as_slice));
+            assert_eq!(commitment.root(), naive_root(&data[..count]), "{count} rows");
+        }
+        assert_ne!(DatasetCommitment::from_rows(data[1..].iter().map(Vec::as_slice)).root(), naive_root(&data));
+
+        let tree = DatasetTree::from_rows(data.iter().map(Vec::as_slice));
+        let root = naive_root(&data);
+        assert_eq!(tree.root(), root);
+        for index in [0, 5, 32, 36] {
+            assert!(tree.open(index, &data[index as usize]).unwrap().verify(&root));
+        }
+        let mut forged = tree.open(5, &data[5]).unwrap();
+        forged.index = 4;
+        assert!(!forged.verify(&root));
+        assert!(tree.open(5, &data[4]).is_none());
+        assert!(tree.open(37, &data[0]).is_none());
+    }
+
+    #[test]
+    fn test_resume_from_saved_state() {
+        let path = std::env::temp_dir().join(format!("stark-dataset-{}.state", std::process::id()));
+        let data = rows(0..21);
+
+        let commitment = DatasetCommitment::from_rows(data[..13].iter().map(Vec::as_slice));
+        commitment.save(&path).unwrap();
+        let mut resumed = DatasetCommitment::load(&path).unwrap();
+        assert_eq!(resumed, commitment);
+        resumed.extend(data[13..].iter().map(Vec::as_slice));
+        assert_eq!(resumed.root(), DatasetCommitment::from_rows(data.iter().map(Vec::as_slice)).root());
+
+        fs::write(&path, "rows 3\n0 00\n").unwrap();
+        assert!(matches!(DatasetCommitment::load(&path), Err(DatasetError::Malformed(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+//! Human-readable statements of what a proof claims.
+//!
+//! [`ProofEnvelope::describe`] renders an envelope as one sentence in a [`Locale`], e.g.
+//! "Model 0x4f2e.. committed as 0xabc.. predicts 21 for input 8, consistent with 2 samples".
+//! Every claim type has a [`ClaimFormatter`]; a [`Describer`] tries its formatters in order
+//! and falls back to a generic sentence naming the model and the number of public inputs.
+//! Formatters recognize their claims by the constraint fingerprint, so an envelope is never
+//! described as a claim it was not proven as. The CLI prints the statement after `prove` and `verify`, and the
+//! server returns it from `POST /describe`.
+
+use core::fmt;
+
+use crate::abi::to_hex;
+use crate::envelope::ProofEnvelope;
+use crate::fixed_point::from_field;
+use crate::regression::LinearRegressionInputs;
+use crate::session::linear_fingerprint;
+
+/// Languages claims are rendered in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Es,
+    Fr,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 4] = [Locale::En, Locale::De, Locale::Es, Locale::Fr];
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Locale::En => write!(f, "en"),
+            Locale::De => write!(f, "de"),
+            Locale::Es => write!(f, "es"),
+            Locale::Fr => write!(f, "fr"),
+        }
+    }
+}
+
+impl core::str::FromStr for Locale {
+    type Err = String;
+
+    /// Accepts language tags with a region, e.g. `de-CH`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.to_string() == language)
+            .ok_or_else(|| format!("unsupported locale `{s}`"))
+    }
+}
+
+/// Renders the claims of one claim type
+pub trait ClaimFormatter: Send + Sync {
+    /// Statement of `envelope` in `locale`, or `None` when it is not a claim of this type
+    fn describe(&self, envelope: &ProofEnvelope, locale: Locale) -> Option<String>;
+}
+
+/// Claims of [`ProvingSession::prove_linear`](crate::session::ProvingSession::prove_linear)
+pub struct LinearClaimFormatter;
+
+impl ClaimFormatter for LinearClaimFormatter {
+    fn describe(&self, envelope: &ProofEnvelope, locale: Locale) -> Option<String> {
+        let inputs = LinearRegressionInputs::from_elements(&envelope.public_inputs)?;
+        let proof = &envelope.proof;
+        let fingerprint = linear_fingerprint(proof.trace_info().clone(), inputs.clone(), proof.options().clone());
+        if fingerprint != envelope.constraint_fingerprint {
+            return None;
+        }
+        let (model, commitment) = (to_hex(&envelope.model_id.0), to_hex(&envelope.model_commitment.0));
+        let (x, y, n) = (from_field(inputs.x_value), from_field(inputs.predicted_y), inputs.sample_x_values.len());
+        Some(match locale {
+            Locale::En => format!(
+                "Model {model} committed as {commitment} predicts {y} for input {x}, consistent with {n} samples"
+            ),
+            Locale::De => format!(
+                "Das Modell {model} mit der Festlegung {commitment} sagt für die Eingabe {x} den Wert {y} voraus, \
+                 übereinstimmend mit {n} Stichproben"
+            ),
+            Locale::Es => format!(
+                "El modelo {model} comprometido como {commitment} predice {y} para la entrada {x}, \
+                 coherente con {n} muestras"
+            ),
+            Locale::Fr => format!(
+                "Le modèle {model} engagé sous {commitment} prédit {y} pour l'entrée {x}, \
+                 en accord avec {n} échantillons"
+            ),
+        })
+    }
+}
+
+/// Formatters tried in order, ending with a generic statement
+pub struct Describer {
+    formatters: Vec<Box<dyn ClaimFormatter>>,
+}
+
+impl Default for Describer {
+    /// Formatters for every claim type the session proves
+    fn default() -> Self {
+        Self { formatters: vec![Box::new(LinearClaimFormatter)] }
+    }
+}
+
+impl Describer {
+    /// Tries `formatter` before the formatters added so far
+    pub fn with_formatter(mut self, formatter: impl ClaimFormatter + 'static) -> Self {
+        self.formatters.insert(0, Box::new(formatter));
+        self
+    }
+
+    pub fn describe(&self, envelope: &ProofEnvelope, locale: Locale) -> String {
+        self.formatters
+            .iter()
+            .find_map(|formatter| formatter.describe(envelope, locale))
+            .unwrap_or_else(|| generic(envelope, locale))
+    }
+}
+
+fn generic(envelope: &ProofEnvelope, locale: Locale) -> String {
+    let (model, commitment) = (to_hex(&envelope.model_id.0), to_hex(&envelope.model_commitment.0));
+    let n = envelope.public_inputs.len();
+    match locale {
+        Locale::En => format!("Proof about model {model} committed as {commitment} with {n} public inputs"),
+        Locale::De => {
+            format!("Beweis über das Modell {model} mit der Festlegung {commitment} und {n} öffentlichen Eingaben")
+        }
+        Locale::Es => {
+            format!("Prueba sobre el modelo {model} comprometido como {commitment} con {n} entradas públicas")
+        }
+        Locale::Fr => format!("Preuve sur le modèle {model} engagé sous {commitment} avec {n} entrées publiques"),
+    }
+}
+
+impl ProofEnvelope {
+    /// The claim as a sentence in `locale`, see [`Describer`]
+    pub fn describe(&self, locale: Locale) -> String {
+        Describer::default().describe(self, locale)
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::session::{LinearClaimRequest, ProvingSession};
+
+    struct Upper;
+
+    impl ClaimFormatter for Upper {
+        fn describe(&self, envelope: &ProofEnvelope, locale: Locale) -> Option<String> {
+            let linear = LinearClaimFormatter.describe(envelope, locale)?;
+            (envelope.public_inputs.len() == 6).then(|| linear.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_describes_linear_claims() {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request).unwrap();
+        let commitment = to_hex(&envelope.model_commitment.0);
+        assert!(envelope.describe(Locale::En).ends_with(&format!(
+            "committed as {commitment} predicts 21 for input 8, consistent with 2 samples"
+        )));
+        assert!(envelope.describe("de-CH".parse().unwrap()).contains("sagt für die Eingabe 8 den Wert 21 voraus"));
+        assert_eq!("pt".parse::<Locale>(), Err("unsupported locale `pt`".to_string()));
+
+        // other proofs with the same number of inputs are not mistaken for linear claims
+        let mut other = envelope.clone();
+        other.constraint_fingerprint.0[0] ^= 1;
+        assert!(other.describe(Locale::Fr).starts_with("Preuve sur le modèle"));
+
+        let custom = Describer::default().with_formatter(Upper);
+        assert!(custom.describe(&envelope, Locale::Es).starts_with("EL MODELO"));
+    }
+}
@@ -0,0 +1,119 @@
+//! Reproducible proof generation and the golden vectors that pin it.
+//!
+//! The same claim proven with the same [`ProverConfig`] gives the same proof bytes on every
+//! machine, as long as `deterministic` is set, which is the default. The only step of the
+//! prover whose result depends on scheduling is Winterfell's proof-of-work search: with the
+//! `concurrent` feature it takes whichever nonce a thread finds first, and every query
+//! position is drawn after it. Deterministic proofs therefore run on one thread. The other
+//! nondeterministic inputs are also opt-in: claim nonces from a
+//! [`NonceManager`](crate::nonce::NonceManager) and drand rounds.
+//!
+//! [`reference_vectors`] proves a fixed set of claims; `vectors/determinism.txt` holds their
+//! [`ProofVector`]s, one per line as written by `Display`, and the test below asserts them.
+//! `scripts/check-determinism.sh` runs that test on x86-64 and AArch64, with and without the
+//! `concurrent` feature.
+
+use core::fmt;
+
+use winterfell::{ByteWriter, Proof};
+
+use crate::abi::{from_hex, to_hex};
+use crate::coin::CoinKind;
+use crate::config::ProverConfig;
+use crate::session::{LinearClaimRequest, ProvingSession};
+
+/// Digests of a proof and of the Fiat–Shamir transcript it was generated with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofVector {
+    pub name: String,
+    /// Blake3 of the serialized proof
+    pub proof: [u8; 32],
+    /// Blake3 of what the prover fed into the random coin: the commitments, the out-of-domain
+    /// frame and the proof-of-work nonce
+    pub transcript: [u8; 32],
+    pub pow_nonce: u64,
+}
+
+impl ProofVector {
+    pub fn of(name: impl Into<String>, proof: &Proof) -> Self {
+        Self {
+            name: name.into(),
+            proof: *blake3::hash(&proof.to_bytes()).as_bytes(),
+            transcript: transcript_digest(proof),
+            pow_nonce: proof.pow_nonce,
+        }
+    }
+
+    /// Parses a line written by `Display`
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let name = fields.next()?.to_string();
+        let mut digest = || -> Option<[u8; 32]> { from_hex(fields.next()?)?.try_into().ok() };
+        let (proof, transcript) = (digest()?, digest()?);
+        let pow_nonce = fields.next()?.parse().ok()?;
+        fields.next().is_none().then_some(Self { name, proof, transcript, pow_nonce })
+    }
+}
+
+impl fmt::Display for ProofVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.name, to_hex(&self.proof), to_hex(&self.transcript), self.pow_nonce)
+    }
+}
+
+fn transcript_digest(proof: &Proof) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    bytes.write(&proof.commitments);
+    bytes.write(&proof.ood_frame);
+    bytes.write_u64(proof.pow_nonce);
+    *blake3::hash(&bytes).as_bytes()
+}
+
+/// Vectors of the claims pinned in `vectors/determinism.txt`
+pub fn reference_vectors() -> Vec<ProofVector> {
+    let request = LinearClaimRequest {
+        model: "golden".into(),
+        salt: to_hex(&[9u8; 32]),
+        slope: 3,
+        intercept: 11,
+        sample_x: vec![2, 5, 7, 13],
+        x: 21,
+        schema: None,
+        beacon: None,
+        terms_hash: None,
+    };
+    let configs = [
+        ("linear", ProverConfig::default()),
+        ("linear-grinding", ProverConfig { grinding_factor: 8, ..Default::default() }),
+        ("linear-keccak", ProverConfig { random_coin: CoinKind::Keccak, grinding_factor: 4, ..Default::default() }),
+    ];
+    configs
+        .into_iter()
+        .map(|(name, config)| {
+            let envelope = ProvingSession::new(config, "golden").prove_linear(&request).expect("Reference claims hold");
+            ProofVector::of(name, &envelope.proof)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proofs_match_golden_vectors() {
+        let golden: Vec<ProofVector> = include_str!("../vectors/determinism.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| ProofVector::parse(line).unwrap_or_else(|| panic!("malformed vector `{line}`")))
+            .collect();
+        let actual = reference_vectors();
+        let listing: Vec<String> = actual.iter().map(ProofVector::to_string).collect();
+        assert_eq!(actual, golden, "proofs differ from vectors/determinism.txt, now:\n{}", listing.join("\n"));
+
+        // one worker is deterministic even when it is asked for explicitly
+        let config = ProverConfig { worker_threads: 4, ..Default::default() };
+        assert_eq!(config.proving_threads(), 1);
+        assert_eq!(ProverConfig { deterministic: false, ..config }.proving_threads(), 4);
+    }
+}
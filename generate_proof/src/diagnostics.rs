@@ -0,0 +1,490 @@
+//! DEEP composition diagnostics for debugging soundness issues.
+//!
+//! [`DeepDiagnostics`] replays a proof's Fiat–Shamir transcript and records what the prover
+//! committed to and what the verifier derives from it: the out-of-domain point `z`, the trace
+//! frame at `z` and `z * g`, the composition columns at `z`, the constraint and DEEP
+//! composition coefficients, and for every query the DEEP composition value computed from the
+//! openings next to the value FRI committed to. The transcript is deterministic, so these are
+//! exactly the values the prover worked with; the replay does not stop at the first
+//! inconsistency the way verification does, and shows where a broken proof first diverges.
+//!
+//! Capturing is opt-in: a session built
+//! [`with_diagnostics`](crate::session::ProvingSession::with_diagnostics) keeps the diagnostics
+//! of its last proof, `stark-cli prove --diagnostics FILE` writes them as JSON and
+//! `stark-cli inspect --diagnostics FILE` pretty-prints them. Only proofs over the base field
+//! without auxiliary segments are replayed.
+
+use core::fmt;
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use winterfell::{
+    crypto::{MerkleTree, RandomCoin},
+    math::{fields::f128::BaseElement, polynom, FieldElement},
+    Air, DeepCompositionCoefficients, EvaluationFrame, FieldExtension, Proof,
+};
+
+use crate::codec::{self, encode};
+use crate::coin::{CoinKind, KeccakCoin};
+use crate::config::VerificationPolicy;
+use crate::domain::{DomainSeparatedAir, TaggedInputs};
+use crate::envelope::ProofEnvelope;
+use crate::prover::HashFn;
+use crate::regression::{LinearRegressionAir, LinearRegressionInputs};
+use crate::schema::DataSchema;
+use crate::session::linear_config_commitment;
+use crate::transcript::{Transcript, TranscriptError};
+
+/// Reason a proof's diagnostics cannot be captured or read back
+#[derive(Debug)]
+pub enum DiagnosticsError {
+    /// Only proofs over the base field are replayed
+    UnsupportedExtension(FieldExtension),
+    /// The envelope's public inputs do not describe a linear claim
+    InvalidInputs,
+    /// The proof was made with other options than the policy expects
+    UnexpectedOptions,
+    /// The proof does not parse
+    Malformed(String),
+    /// A diagnostics file is not valid JSON of [`DeepDiagnostics`]
+    InvalidFile(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for DiagnosticsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticsError::UnsupportedExtension(extension) => {
+                write!(f, "proofs over the {extension:?} field extension are not replayed")
+            }
+            DiagnosticsError::InvalidInputs => write!(f, "public inputs do not describe a linear claim"),
+            DiagnosticsError::UnexpectedOptions => {
+                write!(f, "proof was made with other options than the policy expects")
+            }
+            DiagnosticsError::Malformed(reason) => write!(f, "malformed proof: {reason}"),
+            DiagnosticsError::InvalidFile(reason) => write!(f, "invalid diagnostics file: {reason}"),
+            DiagnosticsError::Io(err) => write!(f, "diagnostics I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DiagnosticsError {}
+
+impl From<TranscriptError> for DiagnosticsError {
+    fn from(err: TranscriptError) -> Self {
+        match err {
+            TranscriptError::UnsupportedExtension(extension) => DiagnosticsError::UnsupportedExtension(extension),
+            TranscriptError::Malformed(reason) => DiagnosticsError::Malformed(reason),
+        }
+    }
+}
+
+impl From<io::Error> for DiagnosticsError {
+    fn from(err: io::Error) -> Self {
+        DiagnosticsError::Io(err)
+    }
+}
+
+fn malformed(err: impl fmt::Display) -> DiagnosticsError {
+    DiagnosticsError::Malformed(err.to_string())
+}
+
+/// Trace and composition columns at the out-of-domain point `z` and at `z * g`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OodFrame {
+    #[serde(with = "codec::elements")]
+    pub trace_current: Vec<BaseElement>,
+    #[serde(with = "codec::elements")]
+    pub trace_next: Vec<BaseElement>,
+    #[serde(with = "codec::elements")]
+    pub composition_current: Vec<BaseElement>,
+    #[serde(with = "codec::elements")]
+    pub composition_next: Vec<BaseElement>,
+}
+
+/// The constraint composition polynomial at `z`, once evaluated from the trace frame and once
+/// recombined from the committed composition columns; a valid proof has both equal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OodCheck {
+    #[serde(with = "codec::element")]
+    pub from_frame: BaseElement,
+    #[serde(with = "codec::element")]
+    pub from_columns: BaseElement,
+}
+
+impl OodCheck {
+    pub fn holds(&self) -> bool {
+        self.from_frame == self.from_columns
+    }
+}
+
+/// A query's DEEP composition value computed from the trace and composition openings, next to
+/// the value of the first FRI layer (or of the remainder when there is none) at the same point
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeepQuery {
+    pub position: usize,
+    #[serde(with = "codec::element")]
+    pub x: BaseElement,
+    #[serde(with = "codec::element")]
+    pub computed: BaseElement,
+    #[serde(with = "codec::element")]
+    pub committed: BaseElement,
+}
+
+impl DeepQuery {
+    pub fn matches(&self) -> bool {
+        self.computed == self.committed
+    }
+}
+
+/// Out-of-domain and DEEP composition values of a proof, see the module documentation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeepDiagnostics {
+    pub trace_length: usize,
+    pub lde_domain_size: usize,
+    #[serde(with = "codec::element")]
+    pub z: BaseElement,
+    pub ood_frame: OodFrame,
+    /// Transition constraint evaluations at `z`, in the AIR's constraint order
+    #[serde(with = "codec::elements")]
+    pub transition_evaluations: Vec<BaseElement>,
+    #[serde(with = "codec::elements")]
+    pub transition_coefficients: Vec<BaseElement>,
+    #[serde(with = "codec::elements")]
+    pub boundary_coefficients: Vec<BaseElement>,
+    pub ood_check: OodCheck,
+    #[serde(with = "codec::elements")]
+    pub deep_trace_coefficients: Vec<BaseElement>,
+    #[serde(with = "codec::elements")]
+    pub deep_composition_coefficients: Vec<BaseElement>,
+    /// `None` when the proof has no openings at the replayed query positions, e.g. because the
+    /// transcript diverged from the prover's before they were drawn
+    pub queries: Option<Vec<DeepQuery>>,
+}
+
+impl DeepDiagnostics {
+    /// Diagnostics of a proof for AIR `A` whose challenges were drawn from the random coin `C`
+    pub fn capture<A, C>(proof: &Proof, pub_inputs: A::PublicInputs) -> Result<Self, DiagnosticsError>
+    where
+        A: Air<BaseField = BaseElement>,
+        C: RandomCoin<BaseField = BaseElement, Hasher = HashFn>,
+    {
+        let transcript = Transcript::<A>::replay::<C>(proof, pub_inputs)?;
+        let (transition_evaluations, ood_check) = ood_check(&transcript);
+        let Transcript {
+            air,
+            ood_trace: frame,
+            ood_composition_current,
+            ood_composition_next,
+            constraint_coefficients: coefficients,
+            z,
+            deep_coefficients: DeepCompositionCoefficients { trace: deep_trace, constraints: deep_constraints },
+            positions,
+            ..
+        } = transcript;
+        let lde_domain_size = air.lde_domain_size();
+
+        let ood_frame = OodFrame {
+            trace_current: frame.current().to_vec(),
+            trace_next: frame.next().to_vec(),
+            composition_current: ood_composition_current,
+            composition_next: ood_composition_next,
+        };
+        let queries = deep_queries(proof, &air, &positions, z, &ood_frame, [&deep_trace, &deep_constraints]).ok();
+
+        Ok(Self {
+            trace_length: air.trace_length(),
+            lde_domain_size,
+            z,
+            ood_frame,
+            transition_evaluations,
+            transition_coefficients: coefficients.transition,
+            boundary_coefficients: coefficients.boundary,
+            ood_check,
+            deep_trace_coefficients: deep_trace,
+            deep_composition_coefficients: deep_constraints,
+            queries,
+        })
+    }
+
+    /// Diagnostics of an envelope produced by
+    /// [`ProvingSession::prove_linear`](crate::session::ProvingSession::prove_linear) for the
+    /// deployment and random coin of `policy`
+    pub fn for_linear(envelope: &ProofEnvelope, policy: &VerificationPolicy) -> Result<Self, DiagnosticsError> {
+        let inner =
+            LinearRegressionInputs::from_elements(&envelope.public_inputs).ok_or(DiagnosticsError::InvalidInputs)?;
+        let tagged = TaggedInputs {
+            config: linear_config_commitment(&envelope.proof, policy).map_err(|_| DiagnosticsError::UnexpectedOptions)?,
+            tag: policy.domain.clone(),
+            nonce: envelope.nonce,
+            terms_hash: envelope.terms_hash,
+            schema: envelope.schema.as_ref().map(DataSchema::commitment),
+            inner,
+        };
+        type LinearAir = DomainSeparatedAir<LinearRegressionAir>;
+        match policy.random_coin {
+            CoinKind::Default => {
+                Self::capture::<LinearAir, winterfell::crypto::DefaultRandomCoin<HashFn>>(&envelope.proof, tagged)
+            }
+            CoinKind::Keccak => Self::capture::<LinearAir, KeccakCoin<HashFn>>(&envelope.proof, tagged),
+        }
+    }
+
+    /// Whether the composition polynomial agrees at `z` and every query's DEEP value matches FRI
+    pub fn is_consistent(&self) -> bool {
+        self.ood_check.holds() && self.queries.as_ref().is_some_and(|queries| queries.iter().all(DeepQuery::matches))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, DiagnosticsError> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|err| DiagnosticsError::InvalidFile(err.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), DiagnosticsError> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| DiagnosticsError::InvalidFile(err.to_string()))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// DEEP composition values at `positions` computed from the openings, the sum over trace and
+/// composition columns of `c_i * ((T_i(x) - T_i(z)) / (x - z) + (T_i(x) - T_i(z * g)) / (x - z * g))`
+/// as in `winter_verifier`, next to the values FRI committed to
+fn deep_queries<A: Air<BaseField = BaseElement>>(
+    proof: &Proof,
+    air: &A,
+    positions: &[usize],
+    z: BaseElement,
+    ood_frame: &OodFrame,
+    coefficients: [&[BaseElement]; 2],
+) -> Result<Vec<DeepQuery>, DiagnosticsError> {
+    if positions.len() != proof.num_unique_queries as usize {
+        return Err(malformed("query positions do not match the transcript"));
+    }
+    let lde_domain_size = air.lde_domain_size();
+    let trace_width = ood_frame.trace_current.len();
+    let (_, trace_rows) = proof.trace_queries[0]
+        .clone()
+        .parse::<BaseElement, HashFn, MerkleTree<HashFn>>(lde_domain_size, positions.len(), trace_width)
+        .map_err(malformed)?;
+    let (_, column_rows) = proof
+        .constraint_queries
+        .clone()
+        .parse::<BaseElement, HashFn, MerkleTree<HashFn>>(
+            lde_domain_size,
+            positions.len(),
+            ood_frame.composition_current.len(),
+        )
+        .map_err(malformed)?;
+    let committed = first_layer_values(proof, air, positions)?;
+
+    let points = [z, z * air.trace_domain_generator()];
+    let ood_rows = [
+        [&ood_frame.trace_current, &ood_frame.trace_next],
+        [&ood_frame.composition_current, &ood_frame.composition_next],
+    ];
+    let queries = positions.iter().zip(trace_rows.rows().zip(column_rows.rows())).zip(committed);
+    Ok(queries
+        .map(|((&position, (trace, columns)), committed)| {
+            let x = air.lde_domain_generator().exp_vartime(position as u128) * air.domain_offset();
+            let mut computed = BaseElement::ZERO;
+            for ((values, ood_rows), weights) in [trace, columns].into_iter().zip(&ood_rows).zip(coefficients) {
+                for (point, ood_row) in points.iter().zip(ood_rows) {
+                    let terms = values.iter().zip(ood_row.iter()).zip(weights);
+                    let numerator =
+                        terms.fold(BaseElement::ZERO, |sum, ((&value, &at), &weight)| sum + (value - at) * weight);
+                    computed += numerator / (x - *point);
+                }
+            }
+            DeepQuery { position, x, computed, committed }
+        })
+        .collect())
+}
+
+/// Transition constraint evaluations at `z` of a replayed transcript and its [`OodCheck`]
+pub(crate) fn ood_check<A: Air<BaseField = BaseElement>>(transcript: &Transcript<A>) -> (Vec<BaseElement>, OodCheck) {
+    let (air, z, coefficients) = (&transcript.air, transcript.z, &transcript.constraint_coefficients);
+    let (transition_evaluations, from_frame) =
+        evaluate_at_ood(air, &transcript.ood_trace, &coefficients.transition, &coefficients.boundary, z);
+    let from_columns = transcript
+        .ood_composition_current
+        .iter()
+        .enumerate()
+        .fold(BaseElement::ZERO, |sum, (i, &value)| sum + z.exp_vartime((i * air.trace_length()) as u128) * value);
+    (transition_evaluations, OodCheck { from_frame, from_columns })
+}
+
+/// Transition constraint evaluations at `z` and the constraint composition polynomial at `z`
+/// evaluated from them and the boundary constraints, as the verifier does
+fn evaluate_at_ood<A: Air<BaseField = BaseElement>>(
+    air: &A,
+    frame: &EvaluationFrame<BaseElement>,
+    transition_coefficients: &[BaseElement],
+    boundary_coefficients: &[BaseElement],
+    z: BaseElement,
+) -> (Vec<BaseElement>, BaseElement) {
+    let periodic_values: Vec<BaseElement> = air
+        .get_periodic_column_polys()
+        .iter()
+        .map(|poly| polynom::eval(poly, z.exp_vartime((air.trace_length() / poly.len()) as u128)))
+        .collect();
+    let transition = air.get_transition_constraints(transition_coefficients);
+    let mut evaluations = vec![BaseElement::ZERO; transition.num_main_constraints()];
+    air.evaluate_transition(frame, &periodic_values, &mut evaluations);
+    let mut composition = transition.combine_evaluations::<BaseElement>(&evaluations, &[], z);
+    for group in air.get_boundary_constraints(None, boundary_coefficients).main_constraints() {
+        composition += group.evaluate_at(frame.current(), z);
+    }
+    (evaluations, composition)
+}
+
+/// Values of the first FRI layer at the query positions, or of the remainder polynomial when
+/// the DEEP composition polynomial goes to the remainder directly
+fn first_layer_values<A: Air<BaseField = BaseElement>>(
+    proof: &Proof,
+    air: &A,
+    positions: &[usize],
+) -> Result<Vec<BaseElement>, DiagnosticsError> {
+    let lde_domain_size = air.lde_domain_size();
+    let folding = air.options().to_fri_options().folding_factor();
+    if proof.fri_proof.num_partitions() > 1 {
+        return Err(malformed("FRI layers hashed in partitions are not supported"));
+    }
+    let (layers, _) = proof
+        .fri_proof
+        .clone()
+        .parse_layers::<BaseElement, HashFn, MerkleTree<HashFn>>(lde_domain_size, folding)
+        .map_err(malformed)?;
+
+    let Some(values) = layers.first() else {
+        // coefficients are sent in reverse order
+        let remainder = proof.fri_proof.parse_remainder::<BaseElement>().map_err(malformed)?;
+        let generator = air.lde_domain_generator();
+        return Ok(positions
+            .iter()
+            .map(|&position| {
+                let x = generator.exp_vartime(position as u128) * air.domain_offset();
+                remainder.iter().fold(BaseElement::ZERO, |sum, &coefficient| sum * x + coefficient)
+            })
+            .collect());
+    };
+    // rows hold the `folding` values sharing a folded position, first occurrences in order as
+    // `winter_fri::folding::fold_positions`
+    let row_length = lde_domain_size / folding;
+    let mut folded: Vec<usize> = Vec::with_capacity(positions.len());
+    for index in positions.iter().map(|position| position % row_length) {
+        if !folded.contains(&index) {
+            folded.push(index);
+        }
+    }
+    if values.len() != folded.len() * folding {
+        return Err(malformed("the first FRI layer is not opened at the folded query positions"));
+    }
+    Ok(positions
+        .iter()
+        .map(|position| {
+            let row = folded.iter().position(|&index| index == position % row_length).expect("Folded above");
+            values[row * folding + position / row_length]
+        })
+        .collect())
+}
+
+impl fmt::Display for DeepDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |elements: &[BaseElement]| elements.iter().map(|&e| encode(e)).collect::<Vec<_>>().join(", ");
+        let verdict = |ok: bool| if ok { "ok" } else { "MISMATCH" };
+
+        writeln!(f, "trace length {}, LDE domain {}", self.trace_length, self.lde_domain_size)?;
+        writeln!(f, "z = {}", encode(self.z))?;
+        writeln!(f, "OOD frame")?;
+        writeln!(f, "  trace at z:            [{}]", list(&self.ood_frame.trace_current))?;
+        writeln!(f, "  trace at z * g:        [{}]", list(&self.ood_frame.trace_next))?;
+        writeln!(f, "  composition at z:      [{}]", list(&self.ood_frame.composition_current))?;
+        writeln!(f, "  composition at z * g:  [{}]", list(&self.ood_frame.composition_next))?;
+        writeln!(f, "transition constraints at z")?;
+        for (i, value) in self.transition_evaluations.iter().enumerate() {
+            writeln!(f, "  {i:>3}: {}", encode(*value))?;
+        }
+        writeln!(f, "constraint composition coefficients")?;
+        writeln!(f, "  transition: [{}]", list(&self.transition_coefficients))?;
+        writeln!(f, "  boundary:   [{}]", list(&self.boundary_coefficients))?;
+        writeln!(f, "composition at z: {}", verdict(self.ood_check.holds()))?;
+        writeln!(f, "  from the trace frame:         {}", encode(self.ood_check.from_frame))?;
+        writeln!(f, "  from the composition columns: {}", encode(self.ood_check.from_columns))?;
+        writeln!(f, "DEEP composition coefficients")?;
+        writeln!(f, "  trace:       [{}]", list(&self.deep_trace_coefficients))?;
+        writeln!(f, "  composition: [{}]", list(&self.deep_composition_coefficients))?;
+        let Some(queries) = &self.queries else {
+            return writeln!(f, "DEEP queries: the proof is not opened at the replayed positions");
+        };
+        let mismatches = queries.iter().filter(|query| !query.matches()).count();
+        writeln!(f, "DEEP queries: {} ({mismatches} mismatched)", queries.len())?;
+        for query in queries {
+            writeln!(
+                f,
+                "  {:>6}: computed {}, committed {} {}",
+                query.position,
+                encode(query.computed),
+                encode(query.committed),
+                verdict(query.matches())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::to_hex;
+    use crate::config::ProverConfig;
+    use crate::session::{LinearClaimRequest, ProvingSession};
+
+    fn linear_envelope() -> ProofEnvelope {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[7u8; 32]),
+            slope: 3,
+            intercept: 4,
+            sample_x: vec![1, 2, 5],
+            x: 7,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        ProvingSession::new(ProverConfig::default(), "test").prove_linear(&request).unwrap()
+    }
+
+    #[test]
+    fn test_valid_proofs_are_consistent() {
+        let envelope = linear_envelope();
+        let diagnostics = DeepDiagnostics::for_linear(&envelope, &VerificationPolicy::default()).unwrap();
+        assert!(diagnostics.is_consistent());
+        assert_eq!(diagnostics.queries.as_ref().unwrap().len(), envelope.proof.num_unique_queries as usize);
+        assert!(diagnostics.transition_evaluations.iter().any(|e| *e != BaseElement::ZERO));
+
+        let path = std::env::temp_dir().join(format!("deep-diagnostics-{}.json", std::process::id()));
+        diagnostics.save(&path).unwrap();
+        assert_eq!(DeepDiagnostics::load(&path).unwrap(), diagnostics);
+        std::fs::remove_file(&path).unwrap();
+        assert!(diagnostics.to_string().contains("composition at z: ok"));
+    }
+
+    #[test]
+    fn test_wrong_claims_diverge_at_z() {
+        // a different predicted y changes the boundary constraints, so the committed
+        // composition columns no longer match the frame at z
+        let mut envelope = linear_envelope();
+        envelope.public_inputs[1] += BaseElement::ONE;
+        let diagnostics = DeepDiagnostics::for_linear(&envelope, &VerificationPolicy::default()).unwrap();
+        assert!(!diagnostics.ood_check.holds());
+        assert!(!diagnostics.is_consistent());
+        assert!(diagnostics.to_string().contains("composition at z: MISMATCH"));
+
+        envelope.public_inputs.pop();
+        assert!(matches!(
+            DeepDiagnostics::for_linear(&envelope, &VerificationPolicy::default()),
+            Err(DiagnosticsError::InvalidInputs)
+        ));
+    }
+}
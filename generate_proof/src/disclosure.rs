@@ -0,0 +1,273 @@
+//! Selective disclosure of committed dataset rows, outside the STARK.
+//!
+//! A prover commits to a dataset with a [`DatasetTree`] and publishes the root next to its
+//! envelopes. After a verifier has checked an envelope it can send a [`DisclosureRequest`]
+//! naming the rows it wants to see; [`respond`] answers with a [`Disclosure`], the envelope
+//! bundled with a Merkle opening for each requested row, and [`Disclosure::check`] confirms
+//! that exactly those rows were opened against the root the verifier trusts. Everything else
+//! in the dataset stays hidden.
+//!
+//! Layout of [`Disclosure::to_bytes`] (all integers little-endian):
+//!
+//! | field                | size                              |
+//! |----------------------|-----------------------------------|
+//! | magic `STKD`         | 4                                 |
+//! | version              | 1                                 |
+//! | dataset root         | 32                                |
+//! | envelope length      | 4                                 |
+//! | envelope             | `ProofEnvelope::to_bytes()`       |
+//! | # openings           | 4                                 |
+//! | per opening: index   | 8                                 |
+//! | # row elements       | 4                                 |
+//! | row elements         | 16 each                           |
+//! | siblings             | 32 each, [`DEPTH`] of them        |
+
+use core::fmt;
+
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+use crate::abi::to_hex;
+use crate::dataset::{DatasetTree, RowOpening, DEPTH};
+use crate::envelope::{EnvelopeError, ProofEnvelope};
+
+const MAGIC: &[u8; 4] = b"STKD";
+const VERSION: u8 = 1;
+
+/// Rows a verifier asks to see, by index
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisclosureRequest {
+    pub indices: Vec<u64>,
+}
+
+impl DisclosureRequest {
+    /// Request for the given rows; duplicates are dropped and the rest sorted
+    pub fn new(indices: impl IntoIterator<Item = u64>) -> Self {
+        let mut indices: Vec<u64> = indices.into_iter().collect();
+        indices.sort_unstable();
+        indices.dedup();
+        Self { indices }
+    }
+}
+
+/// Reason a disclosure cannot be produced, decoded or accepted
+#[derive(Debug, PartialEq, Eq)]
+pub enum DisclosureError {
+    /// The dataset has no row with this index
+    RowOutOfRange(u64),
+    /// The committed dataset does not match the root the verifier trusts
+    RootMismatch { expected: [u8; 32], actual: [u8; 32] },
+    /// The openings are not exactly the requested rows
+    WrongRows { requested: Vec<u64>, opened: Vec<u64> },
+    /// The opening of this row does not authenticate against the root
+    InvalidOpening(u64),
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    NonCanonicalElement(u128),
+    Envelope(EnvelopeError),
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DisclosureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisclosureError::RowOutOfRange(index) => write!(f, "dataset has no row {index}"),
+            DisclosureError::RootMismatch { expected, actual } => {
+                write!(f, "dataset root {} does not match trusted root {}", to_hex(actual), to_hex(expected))
+            }
+            DisclosureError::WrongRows { requested, opened } => {
+                write!(f, "opened rows {opened:?} instead of the requested {requested:?}")
+            }
+            DisclosureError::InvalidOpening(index) => write!(f, "opening of row {index} does not match the root"),
+            DisclosureError::BadMagic => write!(f, "not a disclosure"),
+            DisclosureError::UnsupportedVersion(version) => write!(f, "unsupported disclosure version {version}"),
+            DisclosureError::Truncated => write!(f, "disclosure is truncated"),
+            DisclosureError::NonCanonicalElement(value) => write!(f, "{value} is not a canonical field element"),
+            DisclosureError::Envelope(err) => write!(f, "invalid envelope: {err}"),
+            DisclosureError::TrailingBytes(count) => write!(f, "{count} unexpected bytes after disclosure"),
+        }
+    }
+}
+
+impl std::error::Error for DisclosureError {}
+
+/// An envelope together with openings of some rows of the committed dataset
+#[derive(Clone, Debug)]
+pub struct Disclosure {
+    pub envelope: ProofEnvelope,
+    pub dataset_root: [u8; 32],
+    pub openings: Vec<RowOpening>,
+}
+
+/// Answers `request` with openings from `tree`, whose rows are `rows`
+pub fn respond(
+    envelope: ProofEnvelope,
+    tree: &DatasetTree,
+    rows: &[Vec<BaseElement>],
+    request: &DisclosureRequest,
+) -> Result<Disclosure, DisclosureError> {
+    let openings = request
+        .indices
+        .iter()
+        .map(|&index| {
+            let row = usize::try_from(index).ok().and_then(|i| rows.get(i));
+            row.and_then(|row| tree.open(index, row)).ok_or(DisclosureError::RowOutOfRange(index))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Disclosure { envelope, dataset_root: tree.root(), openings })
+}
+
+impl Disclosure {
+    /// Checks that the openings reveal exactly the rows of `request` from the dataset with
+    /// `trusted_root`, and returns those rows in request order.
+    ///
+    /// The envelope is verified separately, e.g. with
+    /// [`verify_linear`](crate::session::verify_linear).
+    pub fn check(&self, request: &DisclosureRequest, trusted_root: &[u8; 32]) -> Result<Vec<&[BaseElement]>, DisclosureError> {
+        if self.dataset_root != *trusted_root {
+            return Err(DisclosureError::RootMismatch { expected: *trusted_root, actual: self.dataset_root });
+        }
+        let opened: Vec<u64> = self.openings.iter().map(|opening| opening.index).collect();
+        if opened != request.indices {
+            return Err(DisclosureError::WrongRows { requested: request.indices.clone(), opened });
+        }
+        self.openings
+            .iter()
+            .map(|opening| {
+                if opening.verify(trusted_root) {
+                    Ok(opening.row.as_slice())
+                } else {
+                    Err(DisclosureError::InvalidOpening(opening.index))
+                }
+            })
+            .collect()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let envelope = self.envelope.to_bytes();
+        let mut bytes = Vec::with_capacity(45 + envelope.len() + self.openings.len() * (12 + 32 * DEPTH));
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.dataset_root);
+        bytes.extend_from_slice(&(envelope.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&envelope);
+        bytes.extend_from_slice(&(self.openings.len() as u32).to_le_bytes());
+        for opening in &self.openings {
+            bytes.extend_from_slice(&opening.index.to_le_bytes());
+            bytes.extend_from_slice(&(opening.row.len() as u32).to_le_bytes());
+            for element in &opening.row {
+                bytes.extend_from_slice(&element.as_int().to_le_bytes());
+            }
+            for sibling in &opening.siblings {
+                bytes.extend_from_slice(sibling);
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DisclosureError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(DisclosureError::BadMagic);
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(DisclosureError::UnsupportedVersion(version));
+        }
+        let dataset_root = reader.array()?;
+        let envelope_len = reader.u32()? as usize;
+        let envelope = ProofEnvelope::from_bytes(reader.take(envelope_len)?).map_err(DisclosureError::Envelope)?;
+
+        let num_openings = reader.u32()? as usize;
+        let mut openings = Vec::with_capacity(num_openings.min(reader.bytes.len() / (12 + 32 * DEPTH)));
+        for _ in 0..num_openings {
+            let index = u64::from_le_bytes(reader.array()?);
+            let row_len = reader.u32()? as usize;
+            let mut row = Vec::with_capacity(row_len.min(reader.bytes.len() / 16));
+            for _ in 0..row_len {
+                let value = u128::from_le_bytes(reader.array()?);
+                if value >= BaseElement::MODULUS {
+                    return Err(DisclosureError::NonCanonicalElement(value));
+                }
+                row.push(BaseElement::new(value));
+            }
+            let siblings = (0..DEPTH).map(|_| reader.array()).collect::<Result<_, _>>()?;
+            openings.push(RowOpening { index, row, siblings });
+        }
+        if !reader.bytes.is_empty() {
+            return Err(DisclosureError::TrailingBytes(reader.bytes.len()));
+        }
+
+        Ok(Self { envelope, dataset_root, openings })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DisclosureError> {
+        if self.bytes.len() < len {
+            return Err(DisclosureError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DisclosureError> {
+        Ok(self.take(N)?.try_into().expect("slice has exactly N bytes"))
+    }
+
+    fn u32(&mut self) -> Result<u32, DisclosureError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{ProverConfig, VerificationPolicy};
+    use crate::session::{verify_linear, LinearClaimRequest, ProvingSession};
+
+    #[test]
+    fn test_disclose_requested_rows() {
+        let rows: Vec<Vec<BaseElement>> =
+            (0..10u64).map(|i| vec![BaseElement::from(i), BaseElement::from(3 * i + 7)]).collect();
+        let tree = DatasetTree::from_rows(rows.iter().map(Vec::as_slice));
+        let published_root = tree.root();
+
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[4u8; 32]),
+            slope: 3,
+            intercept: 7,
+            sample_x: vec![1, 2, 4],
+            x: 6,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request).unwrap();
+
+        // the verifier checks the proof, then asks for two rows
+        verify_linear(&envelope, &VerificationPolicy::default()).unwrap();
+        let request = DisclosureRequest::new([7, 2, 7]);
+        let disclosure = respond(envelope, &tree, &rows, &request).unwrap();
+        let received = Disclosure::from_bytes(&disclosure.to_bytes()).unwrap();
+        verify_linear(&received.envelope, &VerificationPolicy::default()).unwrap();
+        assert_eq!(received.check(&request, &published_root).unwrap(), vec![rows[2].as_slice(), rows[7].as_slice()]);
+
+        let other = DisclosureRequest::new([2]);
+        assert!(matches!(received.check(&other, &published_root), Err(DisclosureError::WrongRows { .. })));
+        assert!(matches!(received.check(&request, &[0; 32]), Err(DisclosureError::RootMismatch { .. })));
+        let mut tampered = received.clone();
+        tampered.openings[0].row[1] += BaseElement::from(1u8);
+        assert_eq!(tampered.check(&request, &published_root), Err(DisclosureError::InvalidOpening(2)));
+        assert_eq!(
+            respond(received.envelope, &tree, &rows, &DisclosureRequest::new([10])).unwrap_err(),
+            DisclosureError::RowOutOfRange(10)
+        );
+    }
+}
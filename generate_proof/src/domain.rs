@@ -0,0 +1,278 @@
+//! Per-deployment domain separation.
+//!
+//! A [`DomainTag`] names the application a proof belongs to. It is mixed into claim hashes
+//! and model commitments, and [`DomainSeparatedAir`] adds it to the public inputs of any AIR,
+//! which makes it part of the seed Winterfell derives the Fiat-Shamir random coin from. A
+//! proof, claim or commitment produced for one deployment is therefore meaningless in another.
+//! The same inputs carry a claim's [`ClaimNonce`], which ties the proof to that one claim, and
+//! a [`ConfigCommitment`], which ties it to the configuration it was produced under.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+};
+
+use crate::config::ConfigCommitment;
+use crate::nonce::{pack_bytes, ClaimNonce};
+use crate::terms::TermsHash;
+
+/// Longest accepted tag, in bytes
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Tag used when a deployment does not configure its own
+pub const DEFAULT_TAG: &str = "stark-framework";
+
+/// Bytes packed into one field element; 15 bytes always stay below the modulus
+const BYTES_PER_ELEMENT: usize = 15;
+
+/// Leads the configuration section of [`TaggedInputs`]; above [`MAX_TAG_LEN`], so it can never
+/// be mistaken for the length prefix of a tag
+const CONFIG_SECTION: u64 = 0x636f_6e66_6967;
+
+/// Application namespace, e.g. `"acme-lending/prod"`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DomainTag(String);
+
+/// Error returned for tags that are empty, too long or not printable ASCII
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTag(pub String);
+
+impl fmt::Display for InvalidTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid domain tag `{}`: expected 1..={MAX_TAG_LEN} printable ASCII bytes", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTag {}
+
+impl DomainTag {
+    pub fn new(tag: impl Into<String>) -> Result<Self, InvalidTag> {
+        let tag = tag.into();
+        let valid = !tag.is_empty() && tag.len() <= MAX_TAG_LEN && tag.bytes().all(|b| b.is_ascii_graphic());
+        if valid { Ok(Self(tag)) } else { Err(InvalidTag(tag)) }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Feeds the length-prefixed tag into `hasher`; call it right after the hash's own domain
+    pub fn absorb_into(&self, hasher: &mut blake3::Hasher) {
+        hasher.update(&[self.0.len() as u8]);
+        hasher.update(self.0.as_bytes());
+    }
+}
+
+impl Default for DomainTag {
+    fn default() -> Self {
+        Self(DEFAULT_TAG.to_string())
+    }
+}
+
+impl fmt::Display for DomainTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for DomainTag {
+    type Error = InvalidTag;
+
+    fn try_from(tag: String) -> Result<Self, InvalidTag> {
+        Self::new(tag)
+    }
+}
+
+impl From<DomainTag> for String {
+    fn from(tag: DomainTag) -> Self {
+        tag.0
+    }
+}
+
+impl ToElements<BaseElement> for DomainTag {
+    /// `[len, chunk_0, chunk_1, ..]` with the bytes packed little-endian, 15 per element
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![BaseElement::from(self.0.len() as u64)];
+        for chunk in self.0.as_bytes().chunks(BYTES_PER_ELEMENT) {
+            let mut bytes = [0u8; 16];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            elements.push(BaseElement::new(u128::from_le_bytes(bytes)));
+        }
+        elements
+    }
+}
+
+/// Public inputs of `A` together with the deployment's tag and the configuration commitment,
+/// claim nonce, terms hash and schema commitment, if any.
+///
+/// Without a configuration commitment the elements are those of provers predating it, which
+/// bound no schema commitment either, so that their proofs still verify under a policy clearing
+/// [`commit_config`](crate::config::VerificationPolicy::commit_config)
+#[derive(Clone, Debug)]
+pub struct TaggedInputs<P> {
+    pub config: Option<ConfigCommitment>,
+    pub tag: DomainTag,
+    pub nonce: Option<ClaimNonce>,
+    pub terms_hash: Option<TermsHash>,
+    /// [`DataSchema::commitment`](crate::schema::DataSchema::commitment) of the claim's schema
+    pub schema: Option<[u8; 32]>,
+    pub inner: P,
+}
+
+impl<P: ToElements<BaseElement>> ToElements<BaseElement> for TaggedInputs<P> {
+    /// `[CONFIG_SECTION, commitment..]` if committed, the tag, `[0]` or `[1, nonce..]`, `[0]` or
+    /// `[1, terms hash..]`, with a configuration commitment `[0]` or `[1, schema commitment..]`,
+    /// then the inner inputs
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = Vec::new();
+        if let Some(config) = self.config {
+            elements.push(BaseElement::from(CONFIG_SECTION));
+            elements.extend(config.to_elements());
+        }
+        elements.extend(self.tag.to_elements());
+        let mut optionals = vec![
+            self.nonce.map(|nonce| nonce.to_elements()),
+            self.terms_hash.map(|hash| hash.to_elements()),
+        ];
+        if self.config.is_some() {
+            optionals.push(self.schema.map(|commitment| pack_bytes(&commitment)));
+        }
+        for optional in optionals {
+            match optional {
+                Some(section) => {
+                    elements.push(BaseElement::ONE);
+                    elements.extend(section);
+                }
+                None => elements.push(BaseElement::ZERO),
+            }
+        }
+        elements.extend(self.inner.to_elements());
+        elements
+    }
+}
+
+/// `A` with its public inputs extended by a [`DomainTag`].
+///
+/// The constraints are exactly those of `A`; only the transcript changes. AIRs with auxiliary
+/// trace segments are not supported.
+pub struct DomainSeparatedAir<A> {
+    inner: A,
+}
+
+impl<A: Air<BaseField = BaseElement>> Air for DomainSeparatedAir<A> {
+    type BaseField = BaseElement;
+    type PublicInputs = TaggedInputs<A::PublicInputs>;
+
+    fn new(trace_info: TraceInfo, pub_inputs: Self::PublicInputs, options: ProofOptions) -> Self {
+        assert!(!trace_info.is_multi_segment(), "Domain separation does not support auxiliary segments");
+        Self { inner: A::new(trace_info, pub_inputs.inner, options) }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        self.inner.context()
+    }
+
+    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        self.inner.evaluate_transition(frame, periodic_values, result)
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        self.inner.get_assertions()
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        self.inner.get_periodic_column_values()
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    #[test]
+    fn test_tag_validation_and_encoding() {
+        assert!(DomainTag::new("").is_err());
+        assert!(DomainTag::new("has space").is_err());
+        assert!(DomainTag::new("x".repeat(MAX_TAG_LEN + 1)).is_err());
+
+        let tag = DomainTag::new("acme-lending/prod").unwrap();
+        assert_eq!(tag.to_elements().len(), 3);
+        assert_ne!(tag.to_elements(), DomainTag::new("acme-lending/test").unwrap().to_elements());
+
+        let json = serde_json::to_string(&tag).unwrap();
+        assert_eq!(json, "\"acme-lending/prod\"");
+        assert_eq!(serde_json::from_str::<DomainTag>(&json).unwrap(), tag);
+        assert!(serde_json::from_str::<DomainTag>("\"\"").is_err());
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_tag() {
+        let (slope, intercept) = (BaseElement::new(2), BaseElement::new(5));
+        let xs = vec![BaseElement::new(1), BaseElement::new(3)];
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(8);
+        let inputs = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+        let tagged = |tag: &str| TaggedInputs {
+            config: None,
+            tag: DomainTag::new(tag).unwrap(),
+            nonce: None,
+            terms_hash: None,
+            schema: None,
+            inner: inputs.clone(),
+        };
+
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        crate::air_test!(DomainSeparatedAir<LinearRegressionAir>, tagged("tenant-a"))
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<DomainSeparatedAir<LinearRegressionAir>>(
+            trace,
+            tagged("tenant-a"),
+            ProverConfig::default().to_proof_options(),
+        )
+        .unwrap();
+
+        let options = default_acceptable_options();
+        assert!(verify::<DomainSeparatedAir<LinearRegressionAir>>(proof.clone(), tagged("tenant-b"), &options).is_err());
+        verify::<DomainSeparatedAir<LinearRegressionAir>>(proof, tagged("tenant-a"), &options).unwrap();
+    }
+
+    #[test]
+    fn test_schema_is_bound_with_the_configuration() {
+        let inner = LinearRegressionInputs::from_elements(&[BaseElement::new(8), BaseElement::new(21)]).unwrap();
+        let tagged = |config: Option<ConfigCommitment>, schema: Option<[u8; 32]>| TaggedInputs {
+            config,
+            tag: DomainTag::default(),
+            nonce: None,
+            terms_hash: None,
+            schema,
+            inner: inner.clone(),
+        };
+        // the elements of provers predating the configuration commitment
+        let legacy = [DomainTag::default().to_elements(), vec![BaseElement::ZERO; 2], inner.to_elements()];
+        assert_eq!(tagged(None, Some([3u8; 32])).to_elements(), legacy.concat());
+
+        let config = Some(ConfigCommitment([1u8; 32]));
+        assert_ne!(tagged(config, Some([3u8; 32])).to_elements(), tagged(config, None).to_elements());
+        assert_ne!(tagged(config, Some([3u8; 32])).to_elements(), tagged(config, Some([4u8; 32])).to_elements());
+    }
+}
@@ -0,0 +1,484 @@
+//! Model drift claims comparing the error of two committed residual windows.
+//!
+//! [`DriftAir`] proves that the mean squared error of a recent window of residuals exceeds the
+//! mean squared error of a baseline window by more than `delta`, where both windows are hashed
+//! into public Rescue digests (see [`residuals_digest`]). A monitoring job can publish the
+//! baseline digest once and the digest of each new window as it closes; a drift alert backed
+//! by this proof needs no trust in the job, and neither window nor either MSE is revealed.
+//!
+//! The trace runs two lanes side by side, one per window, each laid out like
+//! [`QuantileAir`](crate::quantile::QuantileAir): one [`CYCLE_LENGTH`]-row cycle per residual,
+//! a sponge absorbing the residual at the end of every cycle, a sign and a range checked
+//! magnitude, and a running sum of squares that adds `magnitude^2` at the end of every residual
+//! cycle. Windows may differ in length; the trace covers the longer one plus the cycle that
+//! finishes its sponge. On every row where both lanes pad their window their sums are final, and
+//! `n_b * sse_r - n_r * sse_b - delta * n_r * n_b - 1`, the comparison of the two means
+//! cleared of denominators, is range checked.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+#[cfg(feature = "prover")]
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{IntegerColumn, PhaseSelector, RangeCheck};
+use crate::layout::LayoutDescriptor;
+pub use crate::quantile::residuals_digest;
+use crate::quantile::RESIDUALS_TAG;
+
+/// Identifier of [`DriftAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "drift/v1";
+
+/// Each window may hold up to `2^SAMPLE_BITS` residuals
+pub const SAMPLE_BITS: usize = 20;
+
+/// Residual magnitudes must fit into this many bits, which keeps sums of squares far from
+/// the field modulus
+pub const RESIDUAL_BITS: usize = 32;
+/// `n_b * sse_r` is below `2^(SAMPLE_BITS + SAMPLE_BITS + 2 * RESIDUAL_BITS)`
+const SLACK_BITS: usize = 2 * SAMPLE_BITS + 2 * RESIDUAL_BITS;
+const DIGIT_BITS: usize = 2;
+
+/// Phases of a window: one cycle per residual, then the padding cycles
+const SAMPLES: usize = 0;
+const PADDING: usize = 1;
+
+/// Columns of one window: sponge state, residual, its sign, the phase flags, running sum of
+/// squares, then the magnitude and its digits
+#[derive(Clone, Copy)]
+struct Lane {
+    sponge: RescueGadget,
+    residual: usize,
+    sign: usize,
+    phases: PhaseSelector,
+    sse: usize,
+    magnitude: IntegerColumn,
+}
+
+impl Lane {
+    fn new(first_column: usize) -> Self {
+        let sponge = RescueGadget::new(first_column);
+        let residual = sponge.next_column();
+        let (sign, phases) = (residual + 1, PhaseSelector::new(residual + 2, 2));
+        let sse = phases.next_column();
+        let magnitude = IntegerColumn::new(sse + 1, sse + 2, RESIDUAL_BITS, DIGIT_BITS);
+        Self { sponge, residual, sign, phases, sse, magnitude }
+    }
+
+    fn next_column(&self) -> usize {
+        self.magnitude.next_column()
+    }
+
+    /// Names the lane's columns with the prefix `name`
+    fn describe(&self, name: &str, descriptor: LayoutDescriptor) -> LayoutDescriptor {
+        let sponge = self.sponge.next_column() - self.sponge.width();
+        let magnitude = self.magnitude.value_column();
+        descriptor
+            .with_columns(&format!("{name}_sponge"), sponge..self.residual)
+            .with_column(&format!("{name}_residual"), self.residual)
+            .with_column(&format!("{name}_sign"), self.sign)
+            .with_columns(&format!("{name}_phases"), self.phases.column(SAMPLES)..self.sse)
+            .with_column(&format!("{name}_sse"), self.sse)
+            .with_column(&format!("{name}_magnitude"), magnitude)
+            .with_columns(&format!("{name}_magnitude_digits"), magnitude + 1..self.next_column())
+    }
+
+    fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+        let mut degrees = self.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 1]);
+        degrees.extend(vec![TransitionConstraintDegree::new(2); 2]);
+        degrees.extend(self.phases.constraint_degrees());
+        degrees.extend(self.magnitude.constraint_degrees());
+        degrees.push(periodic(3));
+        degrees
+    }
+
+    fn evaluate<E: FieldElement + From<BaseElement>>(
+        &self,
+        current: &[E],
+        next: &[E],
+        periodic: &[E],
+        result: &mut [E],
+    ) {
+        let (mask, last) = (periodic[0], E::ONE - periodic[0]);
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        self.sponge.evaluate(current, next, periodic, sponge_result);
+
+        let sponge = self.sponge.state(current);
+        let absorbed = self.sponge.state(next);
+        rest[0] = last * (absorbed[0] - sponge[0] - current[self.residual]);
+        for i in 1..STATE_WIDTH {
+            rest[i] = last * (absorbed[i] - sponge[i]);
+        }
+        rest[4] = mask * (next[self.residual] - current[self.residual]);
+
+        let (sign, magnitude) = (current[self.sign], current[self.magnitude.value_column()]);
+        rest[5] = sign * (sign - E::ONE);
+        rest[6] = current[self.residual] - (E::ONE - sign.double()) * magnitude;
+        self.phases.evaluate(current, next, &mut rest[7..]);
+
+        let rest = &mut rest[7 + self.phases.num_constraints()..];
+        self.magnitude.evaluate(current, rest);
+        let rest = &mut rest[self.magnitude.num_constraints()..];
+        let square = last * magnitude * magnitude;
+        rest[0] = next[self.sse] - current[self.sse] - self.phases.gate(current, &[SAMPLES], square);
+    }
+
+    fn assertions(&self, num_samples: usize, digest: BaseElement) -> Vec<Assertion<BaseElement>> {
+        let sponge = self.sponge.next_column() - self.sponge.width();
+        let mut assertions: Vec<_> = rescue::sponge_iv(num_samples, RESIDUALS_TAG)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| Assertion::single(sponge + i, 0, value))
+            .collect();
+        assertions.push(Assertion::single(sponge, (num_samples + 1) * CYCLE_LENGTH - 1, digest));
+        let phases = &self.phases;
+        assertions.push(Assertion::single(phases.column(SAMPLES), num_samples * CYCLE_LENGTH - 1, BaseElement::ONE));
+        assertions.push(Assertion::single(phases.column(PADDING), num_samples * CYCLE_LENGTH, BaseElement::ONE));
+        assertions.push(Assertion::single(self.sse, 0, BaseElement::ZERO));
+        assertions
+    }
+}
+
+struct Layout {
+    baseline: Lane,
+    recent: Lane,
+    slack: RangeCheck,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let baseline = Lane::new(0);
+        let recent = Lane::new(baseline.next_column());
+        let slack = RangeCheck::with_digit_bits(recent.next_column(), SLACK_BITS, DIGIT_BITS);
+        Self { baseline, recent, slack }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.slack.next_column()
+    }
+}
+
+/// Number of trace columns
+pub fn trace_width() -> usize {
+    Layout::new().trace_width()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    let layout = Layout::new();
+    let descriptor = layout.baseline.describe("baseline", LayoutDescriptor::new(AIR_ID));
+    layout
+        .recent
+        .describe("recent", descriptor)
+        .with_columns("slack_digits", layout.recent.next_column()..layout.slack.next_column())
+}
+
+/// Trace length for windows of `num_baseline` and `num_recent` residuals
+pub fn trace_length(num_baseline: usize, num_recent: usize) -> usize {
+    ((num_baseline.max(num_recent) + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Sum of the squared residuals of a window
+pub fn sum_of_squares(residuals: &[i64]) -> u128 {
+    residuals.iter().map(|residual| (residual.unsigned_abs() as u128).pow(2)).sum()
+}
+
+/// Reason a pair of windows does not satisfy a drift claim
+#[derive(Clone, Debug, PartialEq)]
+pub enum DriftError {
+    /// One of the windows holds no residual
+    Empty,
+    TooManySamples(usize),
+    /// A residual does not fit into [`RESIDUAL_BITS`] bits
+    ResidualOutOfRange(i64),
+    /// The recent MSE does not exceed the baseline MSE by more than delta
+    NoDrift { baseline_mse: f64, recent_mse: f64, delta: u64 },
+}
+
+impl fmt::Display for DriftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriftError::Empty => write!(f, "a window holds no residuals"),
+            DriftError::TooManySamples(count) => write!(f, "{count} residuals exceed 2^{SAMPLE_BITS}"),
+            DriftError::ResidualOutOfRange(residual) => {
+                write!(f, "residual {residual} does not fit into {RESIDUAL_BITS} bits")
+            }
+            DriftError::NoDrift { baseline_mse, recent_mse, delta } => {
+                write!(f, "recent MSE {recent_mse} does not exceed baseline MSE {baseline_mse} by more than {delta}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DriftError {}
+
+/// Public inputs: the digest and size of both windows and the drift threshold
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DriftInputs {
+    pub baseline_digest: BaseElement,
+    pub num_baseline: usize,
+    pub recent_digest: BaseElement,
+    pub num_recent: usize,
+    /// Threshold on the difference of the two MSEs, in squared residual units
+    pub delta: u64,
+}
+
+impl DriftInputs {
+    /// Inputs of the claim that the MSE of `recent` exceeds the MSE of `baseline` by more than `delta`
+    pub fn for_windows(baseline: &[i64], recent: &[i64], delta: u64) -> Self {
+        Self {
+            baseline_digest: residuals_digest(baseline),
+            num_baseline: baseline.len(),
+            recent_digest: residuals_digest(recent),
+            num_recent: recent.len(),
+            delta,
+        }
+    }
+
+    /// `n_b * sse_r - n_r * sse_b - delta * n_r * n_b - 1`, negative when there is no drift
+    #[cfg(feature = "prover")]
+    fn slack(&self, baseline_sse: u128, recent_sse: u128) -> i128 {
+        let (num_baseline, num_recent) = (self.num_baseline as i128, self.num_recent as i128);
+        let threshold = self.delta as i128 * num_recent * num_baseline;
+        num_baseline * recent_sse as i128 - num_recent * baseline_sse as i128 - threshold - 1
+    }
+}
+
+impl ToElements<BaseElement> for DriftInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            self.baseline_digest,
+            BaseElement::from(self.num_baseline as u64),
+            self.recent_digest,
+            BaseElement::from(self.num_recent as u64),
+            BaseElement::from(self.delta),
+        ]
+    }
+}
+
+/// AIR proving that the error on a recent window drifted away from a baseline window
+pub struct DriftAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: DriftInputs,
+}
+
+impl Air for DriftAir {
+    type BaseField = BaseElement;
+    type PublicInputs = DriftInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: DriftInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new();
+        assert_eq!(layout.trace_width(), trace_info.width());
+        for num_samples in [pub_inputs.num_baseline, pub_inputs.num_recent] {
+            assert!(num_samples > 0, "Drift claims need residuals in both windows");
+            assert!(num_samples as u64 >> SAMPLE_BITS == 0, "Windows hold at most 2^{SAMPLE_BITS} residuals");
+        }
+        assert_eq!(trace_length(pub_inputs.num_baseline, pub_inputs.num_recent), trace_info.length());
+
+        // Constraints:
+        // 1. Per window: Rescue rounds (degree 3), absorbing the residual at the end of a cycle
+        //    and keeping it constant within one (degree 1), boolean sign with
+        //    residual = (1 - 2 * sign) * magnitude (degree 2), the phase flags (degree 2),
+        //    magnitude digits, and the sum of squares adding magnitude^2 at the end of
+        //    residual cycles (degree 3)
+        // 2. Where both windows are padded, the cleared comparison of their means is the
+        //    range checked slack (degree 3)
+        let mut degrees = layout.baseline.constraint_degrees();
+        degrees.extend(layout.recent.constraint_degrees());
+        degrees.push(TransitionConstraintDegree::new(3));
+        degrees.extend(layout.slack.constraint_degrees());
+
+        DriftAir {
+            context: AirContext::new(trace_info, degrees, 2 * STATE_WIDTH + 8, options),
+            layout,
+            inputs: pub_inputs,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+
+        let (baseline_result, rest) = result.split_at_mut(layout.baseline.constraint_degrees().len());
+        layout.baseline.evaluate(current, next, periodic_values, baseline_result);
+        let (recent_result, rest) = rest.split_at_mut(layout.recent.constraint_degrees().len());
+        layout.recent.evaluate(current, next, periodic_values, recent_result);
+
+        let inputs = &self.inputs;
+        let num_baseline = E::from(BaseElement::from(inputs.num_baseline as u64));
+        let num_recent = E::from(BaseElement::from(inputs.num_recent as u64));
+        let offset = BaseElement::from(inputs.delta) * BaseElement::from(inputs.num_recent as u64)
+            * BaseElement::from(inputs.num_baseline as u64)
+            + BaseElement::ONE;
+        let difference = num_baseline * current[layout.recent.sse] - num_recent * current[layout.baseline.sse];
+        let done = layout.baseline.phases.flag(current, PADDING) * layout.recent.phases.flag(current, PADDING);
+        rest[0] = done * (difference - E::from(offset) - layout.slack.recompose(current));
+        layout.slack.evaluate(current, &mut rest[1..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let inputs = &self.inputs;
+        let mut assertions = self.layout.baseline.assertions(inputs.num_baseline, inputs.baseline_digest);
+        assertions.extend(self.layout.recent.assertions(inputs.num_recent, inputs.recent_digest));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+#[cfg(feature = "prover")]
+fn check_window(residuals: &[i64]) -> Result<i64, DriftError> {
+    let &last = residuals.last().ok_or(DriftError::Empty)?;
+    if residuals.len() as u64 >> SAMPLE_BITS != 0 {
+        return Err(DriftError::TooManySamples(residuals.len()));
+    }
+    match residuals.iter().find(|residual| residual.unsigned_abs() >> RESIDUAL_BITS != 0) {
+        Some(&residual) => Err(DriftError::ResidualOutOfRange(residual)),
+        None => Ok(last),
+    }
+}
+
+/// Build the execution trace proving the MSE of `recent` exceeds the MSE of `baseline` by
+/// more than `delta`
+#[cfg(feature = "prover")]
+pub fn build_drift_trace(baseline: &[i64], recent: &[i64], delta: u64) -> Result<TraceTable<BaseElement>, DriftError> {
+    let last_residuals = [check_window(baseline)?, check_window(recent)?];
+    let inputs = DriftInputs::for_windows(baseline, recent, delta);
+    let sums = [sum_of_squares(baseline), sum_of_squares(recent)];
+    let slack = inputs.slack(sums[0], sums[1]);
+    if slack < 0 {
+        let mse = |sum: u128, len: usize| sum as f64 / len as f64;
+        let (baseline_mse, recent_mse) = (mse(sums[0], baseline.len()), mse(sums[1], recent.len()));
+        return Err(DriftError::NoDrift { baseline_mse, recent_mse, delta });
+    }
+
+    let layout = Layout::new();
+    let lanes = [(layout.baseline, baseline), (layout.recent, recent)];
+    let length = trace_length(baseline.len(), recent.len());
+    let mut columns = vec![vec![BaseElement::ZERO; length]; layout.trace_width()];
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut states = lanes.map(|(_, residuals)| rescue::sponge_iv(residuals.len(), RESIDUALS_TAG));
+    let mut sse = [0u128; 2];
+
+    layout.slack.fill(&mut row, slack).expect("Slack is below 2^SLACK_BITS");
+
+    // Padding cycles repeat a residual distinct from the last one of the window, which keeps
+    // the residual column from being constant for a single residual
+    let padding = last_residuals.map(|last| if last < 0 { last + 1 } else { last - 1 });
+
+    for cycle in 0..length / CYCLE_LENGTH {
+        for (i, (lane, residuals)) in lanes.iter().enumerate() {
+            let residual = residuals.get(cycle).copied().unwrap_or(padding[i]);
+            row[lane.residual] = to_field(residual as i128);
+            row[lane.sign] = BaseElement::from((residual < 0) as u64);
+            lane.phases.fill(&mut row, if cycle < residuals.len() { SAMPLES } else { PADDING });
+            row[lane.sse] = BaseElement::new(sse[i]);
+            lane.magnitude.fill(&mut row, residual.unsigned_abs()).expect("Residuals are range checked");
+        }
+
+        for step in 0..CYCLE_LENGTH {
+            for ((lane, _), state) in lanes.iter().zip(&states) {
+                lane.sponge.write(&mut row, state);
+            }
+            for (column, &value) in columns.iter_mut().zip(&row) {
+                column[cycle * CYCLE_LENGTH + step] = value;
+            }
+            if step < rescue::NUM_ROUNDS {
+                for state in states.iter_mut() {
+                    rescue::apply_round(state, step);
+                }
+            }
+        }
+        for (i, (lane, residuals)) in lanes.iter().enumerate() {
+            states[i][0] += row[lane.residual];
+            if let Some(residual) = residuals.get(cycle) {
+                sse[i] += (residual.unsigned_abs() as u128).pow(2);
+            }
+        }
+    }
+
+    let mut last_row: Vec<BaseElement> = columns.iter().map(|column| column[length - 1]).collect();
+    for (i, (lane, _)) in lanes.iter().enumerate() {
+        last_row[lane.sign] = BaseElement::new(2);
+        lane.phases.fill_exempt_row(&mut last_row, PADDING);
+        lane.magnitude.fill_exempt_row(&mut last_row, padding[i].unsigned_abs());
+    }
+    layout.slack.fill_exempt_row(&mut last_row);
+    for (column, value) in columns.iter_mut().zip(last_row) {
+        column[length - 1] = value;
+    }
+
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_drift_proof() {
+        // baseline MSE is 2, recent MSE is 10
+        let baseline = [1, -1, 2, 0, -2, 1, 1, -2];
+        let recent = [4, -4, 2, -4, 2, 2];
+        let inputs = DriftInputs::for_windows(&baseline, &recent, 7);
+
+        let trace = build_drift_trace(&baseline, &recent, 7).unwrap();
+        crate::air_test!(DriftAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<DriftAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+
+        let options = default_acceptable_options();
+        let larger = DriftInputs { delta: 8, ..inputs };
+        assert!(verify::<DriftAir>(proof.clone(), larger, &options).is_err());
+        let swapped = DriftInputs { baseline_digest: inputs.recent_digest, ..inputs };
+        assert!(verify::<DriftAir>(proof.clone(), swapped, &options).is_err());
+        let result = verify::<DriftAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+
+        // a recent window longer than the baseline
+        let (baseline, recent) = ([0], [-5, 5, 4]);
+        let trace = build_drift_trace(&baseline, &recent, 0).unwrap();
+        let inputs = DriftInputs::for_windows(&baseline, &recent, 0);
+        prove::<DriftAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_stable_windows() {
+        let baseline = [2, -2, 2];
+        assert_eq!(
+            build_drift_trace(&baseline, &[3, -1, 1], 0).unwrap_err(),
+            DriftError::NoDrift { baseline_mse: 4.0, recent_mse: 11.0 / 3.0, delta: 0 }
+        );
+        // equal MSEs are not a drift, even with delta 0
+        assert!(build_drift_trace(&baseline, &[-2, 2], 0).is_err());
+        assert!(build_drift_trace(&baseline, &[-3, 2], 0).is_ok());
+        assert_eq!(build_drift_trace(&[], &[1], 0).unwrap_err(), DriftError::Empty);
+        assert_eq!(build_drift_trace(&baseline, &[1 << 32], 0).unwrap_err(), DriftError::ResidualOutOfRange(1 << 32));
+    }
+}
@@ -0,0 +1,385 @@
+//! Exponential moving average claims over a committed, private price history.
+//!
+//! [`EmaAir`] proves that a public EMA is the last value of
+//! `ema_t = floor((α * p_t + (2^frac_bits - α) * ema_{t-1}) / 2^frac_bits)` over the prices hashed
+//! into a public [`price_commitment`], seeded with `ema_0 = p_0`. The smoothing factor `α` is a
+//! public fixed-point number in `[0, 1]` with `frac_bits` fractional bits; the prices and every
+//! intermediate average stay private, so an oracle can publish the EMA without its feed.
+//!
+//! Prices are absorbed one per [`CYCLE_LENGTH`]-row cycle, as in
+//! [`SmoothingAir`](crate::smoothing::SmoothingAir). Cycle `c` holds its price and the EMA of the
+//! prices before it; at the end of the cycle the EMA takes the cycle's price in with a
+//! range-checked rounding remainder. A flag set only in the first cycle weights that price by
+//! `1` instead of `α`. Prices and averages are range checked to 64 bits, so the weighted sums
+//! stay far below the field modulus and the rounding is unique.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{IntegerColumn, IntegerWidth, RangeCheck};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`EmaAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "ema-oracle/v1";
+
+/// Sponge tag of [`price_commitment`]
+const PRICES_TAG: u64 = 25;
+
+// Trace layout: sponge state, the price absorbed at the end of the cycle, the EMA of the
+// prices before it and the first-cycle flag, then the digits of the price, of the EMA and of
+// the rounding remainder
+const SPONGE: usize = 0;
+const PRICE: usize = SPONGE + STATE_WIDTH;
+const EMA: usize = PRICE + 1;
+const FIRST: usize = EMA + 1;
+const DIGITS_START: usize = FIRST + 1;
+
+struct Layout {
+    sponge: RescueGadget,
+    price: IntegerColumn,
+    ema: IntegerColumn,
+    remainder: RangeCheck,
+}
+
+impl Layout {
+    fn new(frac_bits: u32) -> Self {
+        assert!((1..=32).contains(&frac_bits), "Smoothing factors support 1..=32 fractional bits");
+        let width = IntegerWidth::U64;
+        let price = IntegerColumn::new(PRICE, DIGITS_START, width.bits(), width.digit_bits());
+        let ema = IntegerColumn::new(EMA, price.next_column(), width.bits(), width.digit_bits());
+        let remainder = RangeCheck::new(ema.next_column(), frac_bits as usize);
+        Self { sponge: RescueGadget::new(SPONGE), price, ema, remainder }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.remainder.next_column()
+    }
+}
+
+/// Number of trace columns for smoothing factors of `frac_bits` fractional bits
+pub fn trace_width(frac_bits: u32) -> usize {
+    Layout::new(frac_bits).trace_width()
+}
+
+/// Named columns of the trace for smoothing factors of `frac_bits` fractional bits, see
+/// [`crate::layout`]
+pub fn layout(frac_bits: u32) -> LayoutDescriptor {
+    let layout = Layout::new(frac_bits);
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..PRICE)
+        .with_column("price", PRICE)
+        .with_column("ema", EMA)
+        .with_column("first", FIRST)
+        .with_columns("price_digits", DIGITS_START..layout.price.next_column())
+        .with_columns("ema_digits", layout.price.next_column()..layout.ema.next_column())
+        .with_columns("remainder_digits", layout.ema.next_column()..layout.remainder.next_column())
+}
+
+/// Trace length for `num_prices` prices: a cycle per price and the cycle reading the final EMA
+pub fn trace_length(num_prices: usize) -> usize {
+    ((num_prices + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+fn sponge_start(num_prices: usize, salt: BaseElement) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_prices, PRICES_TAG);
+    state[0] = salt;
+    state
+}
+
+/// Commitment to the price history `prices`, blinded by `salt`
+pub fn price_commitment(prices: &[u64], salt: BaseElement) -> BaseElement {
+    let mut state = sponge_start(prices.len(), salt);
+    rescue::permute(&mut state);
+    for &price in prices {
+        state[0] += BaseElement::from(price);
+        rescue::permute(&mut state);
+    }
+    state[0]
+}
+
+/// Reason a price history cannot be proven averaged
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EmaError {
+    NoPrices,
+    /// The precision of the smoothing factor is not in `1..=32` bits
+    FracBits(u32),
+    /// The smoothing factor is above `1` at the given precision
+    AlphaOutOfRange(u64),
+}
+
+impl fmt::Display for EmaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPrices => write!(f, "no prices to average"),
+            Self::FracBits(bits) => write!(f, "{bits} fractional bits are not in 1..=32"),
+            Self::AlphaOutOfRange(alpha) => write!(f, "smoothing factor {alpha} is above 1"),
+        }
+    }
+}
+
+impl std::error::Error for EmaError {}
+
+fn check_alpha(alpha: u64, frac_bits: u32) -> Result<(), EmaError> {
+    if !(1..=32).contains(&frac_bits) {
+        return Err(EmaError::FracBits(frac_bits));
+    }
+    if alpha > 1 << frac_bits {
+        return Err(EmaError::AlphaOutOfRange(alpha));
+    }
+    Ok(())
+}
+
+/// One EMA step: `price` weighted by `weight`, the previous average by the rest of `2^frac_bits`,
+/// as the rounded average and the rounding remainder
+fn step(ema: u64, price: u64, weight: u64, frac_bits: u32) -> (u64, u64) {
+    let scale = 1u128 << frac_bits;
+    let total = weight as u128 * price as u128 + (scale - weight as u128) * ema as u128;
+    ((total >> frac_bits) as u64, (total & (scale - 1)) as u64)
+}
+
+/// The EMA after each of `prices`, seeded with the first price
+pub fn ema(prices: &[u64], alpha: u64, frac_bits: u32) -> Result<Vec<u64>, EmaError> {
+    check_alpha(alpha, frac_bits)?;
+    let first = *prices.first().ok_or(EmaError::NoPrices)?;
+    let averages = prices[1..].iter().scan(first, |ema, &price| {
+        *ema = step(*ema, price, alpha, frac_bits).0;
+        Some(*ema)
+    });
+    Ok(core::iter::once(first).chain(averages).collect())
+}
+
+/// Public inputs: the price commitment, the smoothing factor and its precision, the number of
+/// prices and the final EMA
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmaInputs {
+    pub commitment: BaseElement,
+    pub frac_bits: u32,
+    pub alpha: u64,
+    pub num_prices: usize,
+    pub final_ema: u64,
+}
+
+impl ToElements<BaseElement> for EmaInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            self.commitment,
+            BaseElement::from(self.frac_bits),
+            BaseElement::from(self.alpha),
+            BaseElement::from(self.num_prices as u64),
+            BaseElement::from(self.final_ema),
+        ]
+    }
+}
+
+/// AIR proving that a public EMA averages a committed price history
+pub struct EmaAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: EmaInputs,
+}
+
+impl Air for EmaAir {
+    type BaseField = BaseElement;
+    type PublicInputs = EmaInputs;
+
+    fn new(trace_info: TraceInfo, inputs: EmaInputs, options: ProofOptions) -> Self {
+        assert_eq!(check_alpha(inputs.alpha, inputs.frac_bits), Ok(()));
+        assert!(inputs.num_prices > 0, "At least one price is averaged");
+        let layout = Layout::new(inputs.frac_bits);
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(trace_length(inputs.num_prices), trace_info.length());
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the price at the end of a cycle (degree 1)
+        // 3. Price, EMA and first-cycle flag are constant within a cycle (degree 1)
+        // 4. The flag is cleared after the first cycle (degree 1)
+        // 5. At the end of a cycle 2^f * next EMA + remainder = w * price + (2^f - w) * EMA,
+        //    with w = 2^f in the first cycle and α after it (degree 2)
+        // 6. Price and EMA match their digits
+        // 7. Digits of the remainder (degree 2)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH]);
+        degrees.extend(vec![periodic(1); 3]);
+        degrees.push(periodic(1));
+        degrees.push(periodic(2));
+        degrees.extend(layout.price.constraint_degrees());
+        degrees.extend(layout.ema.constraint_degrees());
+        degrees.extend(layout.remainder.constraint_degrees());
+
+        EmaAir { context: AirContext::new(trace_info, degrees, STATE_WIDTH + 2, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        let (absorb, rest) = rest.split_at_mut(STATE_WIDTH);
+        absorb[0] = last * (next[SPONGE] - current[SPONGE] - current[PRICE]);
+        for lane in 1..STATE_WIDTH {
+            absorb[lane] = last * (next[SPONGE + lane] - current[SPONGE + lane]);
+        }
+
+        for (r, column) in rest[..3].iter_mut().zip([PRICE, EMA, FIRST]) {
+            *r = mask * (next[column] - current[column]);
+        }
+        rest[3] = last * next[FIRST];
+
+        let scale = E::from(2u32).exp(self.inputs.frac_bits.into());
+        let alpha = E::from(BaseElement::from(self.inputs.alpha));
+        let weighted = alpha * current[PRICE] + (scale - alpha) * current[EMA];
+        let seeded = current[FIRST] * (scale - alpha) * (current[PRICE] - current[EMA]);
+        let remainder = layout.remainder.recompose(current);
+        rest[4] = last * (scale * next[EMA] + remainder - weighted - seeded);
+
+        let rest = &mut rest[5..];
+        layout.price.evaluate(current, rest);
+        let rest = &mut rest[layout.price.num_constraints()..];
+        layout.ema.evaluate(current, rest);
+        layout.remainder.evaluate(current, &mut rest[layout.ema.num_constraints()..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_prices = self.inputs.num_prices;
+        // the salt in the first lane stays private
+        let start = sponge_start(num_prices, BaseElement::ZERO);
+        let mut assertions: Vec<_> = (1..STATE_WIDTH).map(|i| Assertion::single(SPONGE + i, 0, start[i])).collect();
+        assertions.push(Assertion::single(FIRST, 0, BaseElement::ONE));
+        assertions.push(Assertion::single(SPONGE, (num_prices + 1) * CYCLE_LENGTH - 1, self.inputs.commitment));
+        // the EMA of all prices is read in the cycle after the last one
+        assertions.push(Assertion::single(EMA, num_prices * CYCLE_LENGTH, BaseElement::from(self.inputs.final_ema)));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving the EMA of `prices` with smoothing factor `alpha`, with the
+/// inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_ema_trace(
+    prices: &[u64],
+    salt: BaseElement,
+    alpha: u64,
+    frac_bits: u32,
+) -> Result<(TraceTable<BaseElement>, EmaInputs), EmaError> {
+    let averages = ema(prices, alpha, frac_bits)?;
+    let layout = Layout::new(frac_bits);
+
+    let length = trace_length(prices.len());
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut state = sponge_start(prices.len(), salt);
+    let mut average = 0;
+    for i in 0..length {
+        let (cycle, step_in_cycle) = (i / CYCLE_LENGTH, i % CYCLE_LENGTH);
+        // padding cycles keep averaging their index as the price
+        let price = prices.get(cycle).copied().unwrap_or(cycle as u64);
+        let weight = if cycle == 0 { 1 << frac_bits } else { alpha };
+        let (next, remainder) = step(average, price, weight, frac_bits);
+        row[FIRST] = BaseElement::from((cycle == 0) as u64);
+        layout.sponge.write(&mut row, &state);
+        if step_in_cycle < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, step_in_cycle);
+        } else {
+            state[0] += BaseElement::from(price);
+        }
+        if i == length - 1 {
+            layout.price.fill_exempt_row(&mut row, price);
+            layout.ema.fill_exempt_row(&mut row, average);
+            layout.remainder.fill_exempt_row(&mut row);
+        } else {
+            layout.price.fill(&mut row, price).expect("Prices are 64-bit");
+            layout.ema.fill(&mut row, average).expect("Averages stay within the prices");
+            layout.remainder.fill(&mut row, remainder as i128).expect("Remainder is below 2^frac_bits");
+        }
+        if step_in_cycle == CYCLE_LENGTH - 1 {
+            average = next;
+        }
+        trace.update_row(i, &row);
+    }
+
+    let inputs = EmaInputs {
+        commitment: price_commitment(prices, salt),
+        frac_bits,
+        alpha,
+        num_prices: prices.len(),
+        final_ema: *averages.last().expect("Checked to be non-empty"),
+    };
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::fixed_point::quantize;
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_ema_proof() {
+        // α = 0.25 over prices with 8 decimals
+        let frac_bits = 16;
+        let alpha = quantize(0.25, frac_bits) as u64;
+        let prices = [2000, 2010, 1990, 2050, 2040].map(|price: u64| price * 100_000_000);
+        let salt = BaseElement::new(0xe3a);
+        let (trace, inputs) = build_ema_trace(&prices, salt, alpha, frac_bits).unwrap();
+        assert_eq!(inputs.final_ema, 201_902_343_750);
+        crate::air_test!(EmaAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<EmaAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+        let acceptable = default_acceptable_options();
+        let inflated = EmaInputs { final_ema: inputs.final_ema + 1, ..inputs };
+        assert!(verify::<EmaAir>(proof.clone(), inflated, &acceptable).is_err());
+        let faster = EmaInputs { alpha: quantize(0.5, frac_bits) as u64, ..inputs };
+        assert!(verify::<EmaAir>(proof.clone(), faster, &acceptable).is_err());
+        let other_history = EmaInputs { commitment: price_commitment(&prices[1..], salt), ..inputs };
+        assert!(verify::<EmaAir>(proof.clone(), other_history, &acceptable).is_err());
+        verify::<EmaAir>(proof, inputs, &acceptable).unwrap();
+    }
+
+    #[test]
+    fn test_ema_edges() {
+        // α = 1 follows the last price, α = 0 keeps the first one, and averages round down
+        assert_eq!(ema(&[7, 3, 9], 1 << 8, 8).unwrap(), vec![7, 3, 9]);
+        assert_eq!(ema(&[7, 3, 9], 0, 8).unwrap(), vec![7, 7, 7]);
+        assert_eq!(ema(&[7, 8], 1 << 7, 8).unwrap(), vec![7, 7]);
+        let salt = BaseElement::ONE;
+        let (trace, inputs) = build_ema_trace(&[u64::MAX], salt, 3, 2).unwrap();
+        assert_eq!(inputs.final_ema, u64::MAX);
+        crate::air_test!(EmaAir, inputs).with_trace(&trace).expect_all_hold().expect_assertions_hold();
+
+        assert_eq!(build_ema_trace(&[], salt, 1, 8).unwrap_err(), EmaError::NoPrices);
+        assert_eq!(ema(&[1], 257, 8).unwrap_err(), EmaError::AlphaOutOfRange(257));
+        assert_eq!(ema(&[1], 1, 0).unwrap_err(), EmaError::FracBits(0));
+    }
+}
@@ -0,0 +1,312 @@
+//! Envelopes encrypted to their counterparties.
+//!
+//! The public inputs of some claims are only meant for the counterparty of a deal, not for
+//! whoever handles the envelope in transit. [`encrypt`] seals an encoded envelope for one or
+//! more X25519 [`RecipientPublicKey`]s, and a recipient opens it with [`decrypt`], or with
+//! [`decrypt_and_verify`], which also decodes and verifies the claim.
+//!
+//! An encrypted envelope is a manifest followed by the ciphertext:
+//!
+//! ```text
+//! "STKX" | version | ephemeral public key | recipient count | (recipient key | wrapped key)* | ciphertext
+//! ```
+//!
+//! The envelope is encrypted with ChaCha20-Poly1305 under a random content key, authenticating
+//! the manifest as associated data. The content key is wrapped for every recipient under a key
+//! derived from the X25519 secret the recipient shares with a fresh ephemeral key, so the
+//! recipients are listed in the clear but each one only learns the content key.
+
+use core::fmt;
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use x25519_dalek::{PublicKey, SharedSecret, StaticSecret};
+
+use crate::abi::{from_hex, to_hex};
+use crate::config::VerificationPolicy;
+use crate::envelope::{EnvelopeError, ProofEnvelope};
+use crate::serialization::Encoding;
+use crate::session::{verify_linear, SessionError};
+
+const MAGIC: &[u8; 4] = b"STKX";
+const VERSION: u8 = 1;
+const KEY_DOMAIN: &str = "stark-framework/envelope-encryption/v1";
+
+pub const KEY_BYTES: usize = 32;
+/// Poly1305 tag appended to every ciphertext
+const TAG_BYTES: usize = 16;
+const WRAPPED_KEY_BYTES: usize = KEY_BYTES + TAG_BYTES;
+/// Most recipients a manifest lists
+pub const MAX_RECIPIENTS: usize = u8::MAX as usize;
+
+/// X25519 public key of a recipient, serialized as hex
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RecipientPublicKey(pub [u8; KEY_BYTES]);
+
+impl fmt::Display for RecipientPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl TryFrom<String> for RecipientPublicKey {
+    type Error = EncryptionError;
+
+    fn try_from(hex: String) -> Result<Self, EncryptionError> {
+        let bytes = from_hex(hex.trim()).and_then(|bytes| bytes.try_into().ok()).ok_or(EncryptionError::InvalidKey)?;
+        Ok(Self(bytes))
+    }
+}
+
+impl From<RecipientPublicKey> for String {
+    fn from(key: RecipientPublicKey) -> Self {
+        key.to_string()
+    }
+}
+
+/// A recipient's X25519 secret key
+pub struct RecipientKey(StaticSecret);
+
+impl RecipientKey {
+    /// Key from a 32-byte secret, which the recipient keeps like any private key
+    pub fn from_secret(secret: [u8; KEY_BYTES]) -> Self {
+        Self(StaticSecret::from(secret))
+    }
+
+    /// Key from a hex-encoded 32-byte secret
+    pub fn from_hex(hex: &str) -> Result<Self, EncryptionError> {
+        let secret: Option<[u8; KEY_BYTES]> = from_hex(hex.trim()).and_then(|bytes| bytes.try_into().ok());
+        Ok(Self::from_secret(secret.ok_or(EncryptionError::InvalidKey)?))
+    }
+
+    pub fn public_key(&self) -> RecipientPublicKey {
+        RecipientPublicKey(PublicKey::from(&self.0).to_bytes())
+    }
+}
+
+/// Reason an envelope cannot be encrypted, decrypted or verified after decryption
+#[derive(Debug)]
+pub enum EncryptionError {
+    /// No recipients, or more than [`MAX_RECIPIENTS`]
+    RecipientCount(usize),
+    /// Not 32 hex-encoded bytes, or a low-order point no secret can be shared with
+    InvalidKey,
+    NotEncrypted,
+    UnsupportedVersion(u8),
+    Truncated,
+    /// The key is not among the recipients of the manifest
+    NotARecipient(RecipientPublicKey),
+    /// The ciphertext or the manifest was altered
+    Tampered,
+    Envelope(EnvelopeError),
+    Session(SessionError),
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptionError::RecipientCount(count) => {
+                write!(f, "{count} recipients, expected 1..={MAX_RECIPIENTS}")
+            }
+            EncryptionError::InvalidKey => write!(f, "recipient key must be 32 hex-encoded bytes of a usable key"),
+            EncryptionError::NotEncrypted => write!(f, "not an encrypted envelope"),
+            EncryptionError::UnsupportedVersion(version) => {
+                write!(f, "unsupported encrypted envelope version {version}")
+            }
+            EncryptionError::Truncated => write!(f, "encrypted envelope is truncated"),
+            EncryptionError::NotARecipient(key) => write!(f, "envelope is not encrypted to {key}"),
+            EncryptionError::Tampered => write!(f, "encrypted envelope was tampered with"),
+            EncryptionError::Envelope(err) => write!(f, "{err}"),
+            EncryptionError::Session(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+/// Whether `bytes` start like an encrypted envelope
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Recipients and wrapped content keys at the start of an encrypted envelope
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub ephemeral: [u8; KEY_BYTES],
+    pub recipients: Vec<(RecipientPublicKey, [u8; WRAPPED_KEY_BYTES])>,
+}
+
+impl Manifest {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.ephemeral);
+        bytes.push(self.recipients.len() as u8);
+        for (key, wrapped) in &self.recipients {
+            bytes.extend_from_slice(&key.0);
+            bytes.extend_from_slice(wrapped);
+        }
+        bytes
+    }
+
+    /// The manifest at the start of `bytes` and its length
+    pub fn parse(bytes: &[u8]) -> Result<(Self, usize), EncryptionError> {
+        if !is_encrypted(bytes) {
+            return Err(EncryptionError::NotEncrypted);
+        }
+        let mut rest = &bytes[MAGIC.len()..];
+        let mut take = |len: usize| -> Result<&[u8], EncryptionError> {
+            let (head, tail) = rest.split_at_checked(len).ok_or(EncryptionError::Truncated)?;
+            rest = tail;
+            Ok(head)
+        };
+        let version = take(1)?[0];
+        if version != VERSION {
+            return Err(EncryptionError::UnsupportedVersion(version));
+        }
+        let ephemeral = take(KEY_BYTES)?.try_into().expect("32 bytes");
+        let count = take(1)?[0] as usize;
+        let recipients = (0..count)
+            .map(|_| {
+                let key = RecipientPublicKey(take(KEY_BYTES)?.try_into().expect("32 bytes"));
+                Ok((key, take(WRAPPED_KEY_BYTES)?.try_into().expect("48 bytes")))
+            })
+            .collect::<Result<_, EncryptionError>>()?;
+        let manifest = Self { ephemeral, recipients };
+        let len = bytes.len() - rest.len();
+        Ok((manifest, len))
+    }
+}
+
+/// Key wrapping the content key for `recipient`; `None` for low-order points
+fn wrapping_key(shared: SharedSecret, ephemeral: &[u8; KEY_BYTES], recipient: &RecipientPublicKey) -> Option<Key> {
+    if !shared.was_contributory() {
+        return None;
+    }
+    let material = [shared.as_bytes(), &ephemeral[..], &recipient.0].concat();
+    Some(blake3::derive_key(KEY_DOMAIN, &material).into())
+}
+
+fn random_bytes() -> [u8; KEY_BYTES] {
+    let mut bytes = [0u8; KEY_BYTES];
+    getrandom::getrandom(&mut bytes).expect("The operating system provides randomness");
+    bytes
+}
+
+/// Encrypts the encoded envelope `plaintext` to every key of `recipients`
+pub fn encrypt(plaintext: &[u8], recipients: &[RecipientPublicKey]) -> Result<Vec<u8>, EncryptionError> {
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        return Err(EncryptionError::RecipientCount(recipients.len()));
+    }
+    let content_key = Key::from(random_bytes());
+    let ephemeral_secret = StaticSecret::from(random_bytes());
+    let ephemeral = PublicKey::from(&ephemeral_secret).to_bytes();
+    let recipients = recipients
+        .iter()
+        .map(|recipient| {
+            let shared = ephemeral_secret.diffie_hellman(&PublicKey::from(recipient.0));
+            let key = wrapping_key(shared, &ephemeral, recipient).ok_or(EncryptionError::InvalidKey)?;
+            // every wrapping key is used once, so a zero nonce is safe
+            let wrapped = ChaCha20Poly1305::new(&key)
+                .encrypt(&Nonce::default(), content_key.as_slice())
+                .expect("Wrapping a key cannot fail");
+            Ok((*recipient, wrapped.try_into().expect("48 bytes")))
+        })
+        .collect::<Result<_, EncryptionError>>()?;
+
+    let mut bytes = Manifest { ephemeral, recipients }.to_bytes();
+    let payload = Payload { msg: plaintext, aad: &bytes };
+    let ciphertext =
+        ChaCha20Poly1305::new(&content_key).encrypt(&Nonce::default(), payload).expect("Encryption cannot fail");
+    bytes.extend_from_slice(&ciphertext);
+    Ok(bytes)
+}
+
+/// The encoded envelope `bytes` were encrypted from, if `key` is one of the recipients
+pub fn decrypt(bytes: &[u8], key: &RecipientKey) -> Result<Vec<u8>, EncryptionError> {
+    let (manifest, len) = Manifest::parse(bytes)?;
+    let recipient = key.public_key();
+    let (_, wrapped) = manifest
+        .recipients
+        .iter()
+        .find(|(listed, _)| *listed == recipient)
+        .ok_or(EncryptionError::NotARecipient(recipient))?;
+    let shared = key.0.diffie_hellman(&PublicKey::from(manifest.ephemeral));
+    let wrapping = wrapping_key(shared, &manifest.ephemeral, &recipient).ok_or(EncryptionError::Tampered)?;
+    let content_key = ChaCha20Poly1305::new(&wrapping)
+        .decrypt(&Nonce::default(), &wrapped[..])
+        .map_err(|_| EncryptionError::Tampered)?;
+    let payload = Payload { msg: &bytes[len..], aad: &bytes[..len] };
+    ChaCha20Poly1305::new(Key::from_slice(&content_key))
+        .decrypt(&Nonce::default(), payload)
+        .map_err(|_| EncryptionError::Tampered)
+}
+
+/// Decrypts a linear claim envelope with `key`, decodes it in whichever encoding it was
+/// written and verifies it under `policy`
+pub fn decrypt_and_verify(
+    bytes: &[u8],
+    key: &RecipientKey,
+    policy: &VerificationPolicy,
+) -> Result<ProofEnvelope, EncryptionError> {
+    let plaintext = decrypt(bytes, key)?;
+    let encoding = Encoding::detect(&plaintext).ok_or(EncryptionError::Envelope(EnvelopeError::BadMagic))?;
+    let envelope = encoding.decode(&plaintext).map_err(EncryptionError::Envelope)?;
+    verify_linear(&envelope, policy).map_err(EncryptionError::Session)?;
+    Ok(envelope)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::session::{LinearClaimRequest, ProvingSession};
+
+    #[test]
+    fn test_encrypts_to_every_recipient() {
+        let [alice, bob, eve] = [1, 2, 3].map(|byte| RecipientKey::from_secret([byte; 32]));
+        let plaintext = b"confidential envelope";
+        let sealed = encrypt(plaintext, &[alice.public_key(), bob.public_key()]).unwrap();
+        assert!(is_encrypted(&sealed));
+        let (manifest, _) = Manifest::parse(&sealed).unwrap();
+        let listed: Vec<_> = manifest.recipients.iter().map(|(key, _)| *key).collect();
+        assert_eq!(listed, vec![alice.public_key(), bob.public_key()]);
+        assert_eq!(decrypt(&sealed, &alice).unwrap(), plaintext);
+        assert_eq!(decrypt(&sealed, &bob).unwrap(), plaintext);
+        assert!(matches!(decrypt(&sealed, &eve), Err(EncryptionError::NotARecipient(key)) if key == eve.public_key()));
+
+        // flipping a byte of the ciphertext or of the manifest is detected
+        for index in [sealed.len() - 1, 5] {
+            let mut tampered = sealed.clone();
+            tampered[index] ^= 1;
+            assert!(matches!(decrypt(&tampered, &alice), Err(EncryptionError::Tampered)), "byte {index}");
+        }
+        assert!(matches!(encrypt(plaintext, &[]), Err(EncryptionError::RecipientCount(0))));
+        assert!(matches!(encrypt(plaintext, &[RecipientPublicKey([0; 32])]), Err(EncryptionError::InvalidKey)));
+        assert!(matches!(decrypt(plaintext, &alice), Err(EncryptionError::NotEncrypted)));
+    }
+
+    #[test]
+    fn test_decrypt_and_verify() {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request).unwrap();
+        let counterparty = RecipientKey::from_hex(&to_hex(&[7u8; 32])).unwrap();
+        let sealed = encrypt(&Encoding::OnChain.encode(&envelope), &[counterparty.public_key()]).unwrap();
+        let opened = decrypt_and_verify(&sealed, &counterparty, &VerificationPolicy::default()).unwrap();
+        assert_eq!(opened.public_inputs, envelope.public_inputs);
+    }
+}
@@ -0,0 +1,507 @@
+//! Self-describing container that ships a proof together with what it is a proof about.
+//!
+//! Layout (all integers little-endian):
+//!
+//! | field              | size                  |
+//! |--------------------|-----------------------|
+//! | magic `STKE`       | 4                     |
+//! | version            | 1                     |
+//! | model id           | 32                    |
+//! | model commitment   | 32                    |
+//! | constraint fp      | 32                    |
+//! | schema length      | 4, 0 without a schema |
+//! | schema             | JSON [`DataSchema`]   |
+//! | signed             | 1, 0 or 1             |
+//! | operator key       | 32 if signed          |
+//! | operator signature | 64 if signed          |
+//! | beacon length      | 4, 0 without a beacon |
+//! | beacon             | JSON [`BeaconRound`]  |
+//! | has nonce          | 1, 0 or 1             |
+//! | nonce              | 32 if it has one      |
+//! | has terms hash     | 1, 0 or 1             |
+//! | terms hash         | 32 if it has one      |
+//! | provenance length  | 4, 0 without          |
+//! | provenance         | JSON [`Provenance`]   |
+//! | attestation length | 4, 0 without          |
+//! | attestation        | JSON [`HardwareAttestation`] |
+//! | # public inputs    | 4                     |
+//! | public inputs      | 16 each               |
+//! | proof length       | 4                     |
+//! | proof              | `Proof::to_bytes()`   |
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, StarkField},
+    Proof,
+};
+
+use crate::beacon::BeaconRound;
+use crate::domain::DomainTag;
+use crate::fingerprint::ConstraintFingerprint;
+use crate::hardware::HardwareAttestation;
+use crate::nonce::ClaimNonce;
+use crate::operator::{OperatorPublicKey, OperatorSignature};
+use crate::provenance::Provenance;
+use crate::registry::{ModelCommitment, ModelId};
+use crate::schema::DataSchema;
+use crate::terms::TermsHash;
+
+const MAGIC: &[u8; 4] = b"STKE";
+/// Version written by [`ProofEnvelope::to_bytes`]; [`crate::migrate`] lists the earlier ones
+pub const VERSION: u8 = 10;
+const CLAIM_DOMAIN: &[u8] = b"stark-framework/claim/v2";
+const SIGNED_DOMAIN: &[u8] = b"stark-framework/signed-claim/v1";
+
+/// Hash identifying a claim of deployment `domain` by its public inputs and, if it declares
+/// them, the schema of its data, the beacon round its challenges were drawn from, its nonce and
+/// the terms it was made under. Each optional part is absorbed as a 0 when absent and as a 1
+/// followed by its data when present, so no part can stand in for another.
+pub fn claim_hash(
+    domain: &DomainTag,
+    schema: Option<&DataSchema>,
+    beacon: Option<&BeaconRound>,
+    nonce: Option<&ClaimNonce>,
+    terms_hash: Option<&TermsHash>,
+    public_inputs: &[BaseElement],
+) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(CLAIM_DOMAIN);
+    domain.absorb_into(&mut hasher);
+    absorb_optional(&mut hasher, schema, |hasher, schema| {
+        hasher.update(&schema.digest());
+    });
+    absorb_optional(&mut hasher, beacon, |hasher, beacon| beacon.absorb_into(hasher));
+    absorb_optional(&mut hasher, nonce, |hasher, nonce| {
+        hasher.update(&nonce.0);
+    });
+    absorb_optional(&mut hasher, terms_hash, |hasher, terms_hash| {
+        hasher.update(&terms_hash.0);
+    });
+    for input in public_inputs {
+        hasher.update(&input.as_int().to_le_bytes());
+    }
+    *hasher.finalize().as_bytes()
+}
+
+fn absorb_optional<T>(hasher: &mut blake3::Hasher, part: Option<&T>, absorb: impl FnOnce(&mut blake3::Hasher, &T)) {
+    match part {
+        Some(part) => {
+            hasher.update(&[1]);
+            absorb(hasher, part);
+        }
+        None => {
+            hasher.update(&[0]);
+        }
+    }
+}
+
+/// A proof bound to the registered model it was produced with
+#[derive(Clone, Debug)]
+pub struct ProofEnvelope {
+    pub model_id: ModelId,
+    pub model_commitment: ModelCommitment,
+    /// Fingerprint of the constraint system the proof was generated for
+    pub constraint_fingerprint: ConstraintFingerprint,
+    /// Names, units and ranges of the claim's data
+    pub schema: Option<DataSchema>,
+    /// Signature of the operator that produced the proof over [`Self::signed_hash`]
+    pub signer: Option<OperatorSignature>,
+    /// Public randomness the claim's challenges were derived from
+    pub beacon: Option<BeaconRound>,
+    /// Nonce that makes the claim hash unique, see [`crate::nonce`]
+    pub nonce: Option<ClaimNonce>,
+    /// License or terms of use the claim is made under, see [`crate::terms`]
+    pub terms_hash: Option<TermsHash>,
+    /// Build and machine the proof was produced on, see [`crate::provenance`]; not part of the
+    /// claim hash, but covered by the signer and the attestation
+    pub provenance: Option<Provenance>,
+    /// Signature of a hardware-held key over [`Self::signed_hash`], see [`crate::hardware`]
+    pub attestation: Option<HardwareAttestation>,
+    /// Public inputs of the proof, as returned by `ToElements::to_elements`
+    pub public_inputs: Vec<BaseElement>,
+    pub proof: Proof,
+}
+
+/// Errors returned when decoding a [`ProofEnvelope`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnvelopeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    NonCanonicalElement(u128),
+    InvalidSchema(String),
+    /// The signer is neither absent nor a public key and signature
+    InvalidSigner,
+    InvalidBeacon(String),
+    /// The nonce is neither absent nor 32 bytes other than all zeros
+    InvalidNonce,
+    /// The terms hash is neither absent nor 32 bytes other than all zeros
+    InvalidTermsHash,
+    InvalidProvenance(String),
+    InvalidAttestation(String),
+    /// Padding bytes of the on-chain encoding are not zero
+    InvalidPadding,
+    InvalidProof(String),
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::BadMagic => write!(f, "not a proof envelope"),
+            EnvelopeError::UnsupportedVersion(version) => write!(f, "unsupported envelope version {version}"),
+            EnvelopeError::Truncated => write!(f, "envelope is truncated"),
+            EnvelopeError::NonCanonicalElement(value) => write!(f, "{value} is not a canonical field element"),
+            EnvelopeError::InvalidSchema(err) => write!(f, "invalid schema: {err}"),
+            EnvelopeError::InvalidSigner => write!(f, "invalid operator signature section"),
+            EnvelopeError::InvalidBeacon(err) => write!(f, "invalid beacon round: {err}"),
+            EnvelopeError::InvalidNonce => write!(f, "invalid claim nonce"),
+            EnvelopeError::InvalidTermsHash => write!(f, "invalid terms hash"),
+            EnvelopeError::InvalidProvenance(err) => write!(f, "invalid provenance: {err}"),
+            EnvelopeError::InvalidAttestation(err) => write!(f, "invalid hardware attestation: {err}"),
+            EnvelopeError::InvalidPadding => write!(f, "envelope padding is not zero"),
+            EnvelopeError::InvalidProof(err) => write!(f, "invalid proof: {err}"),
+            EnvelopeError::TrailingBytes(count) => write!(f, "{count} unexpected bytes after envelope"),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+impl ProofEnvelope {
+    pub fn new(
+        model_id: ModelId,
+        model_commitment: ModelCommitment,
+        constraint_fingerprint: ConstraintFingerprint,
+        public_inputs: Vec<BaseElement>,
+        proof: Proof,
+    ) -> Self {
+        Self {
+            model_id,
+            model_commitment,
+            constraint_fingerprint,
+            schema: None,
+            signer: None,
+            beacon: None,
+            nonce: None,
+            terms_hash: None,
+            provenance: None,
+            attestation: None,
+            public_inputs,
+            proof,
+        }
+    }
+
+    pub fn with_schema(mut self, schema: DataSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub fn with_beacon(mut self, beacon: BeaconRound) -> Self {
+        self.beacon = Some(beacon);
+        self
+    }
+
+    pub fn with_nonce(mut self, nonce: ClaimNonce) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    pub fn with_terms_hash(mut self, terms_hash: TermsHash) -> Self {
+        self.terms_hash = Some(terms_hash);
+        self
+    }
+
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    pub fn claim_hash(&self, domain: &DomainTag) -> [u8; 32] {
+        claim_hash(
+            domain,
+            self.schema.as_ref(),
+            self.beacon.as_ref(),
+            self.nonce.as_ref(),
+            self.terms_hash.as_ref(),
+            &self.public_inputs,
+        )
+    }
+
+    /// Hash the operator signature and the hardware attestation cover: the claim hash and the
+    /// provenance, which is absorbed like the optional parts of the claim hash
+    pub fn signed_hash(&self, domain: &DomainTag) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(SIGNED_DOMAIN);
+        hasher.update(&self.claim_hash(domain));
+        absorb_optional(&mut hasher, self.provenance.as_ref(), |hasher, provenance| {
+            provenance.absorb_into(hasher)
+        });
+        *hasher.finalize().as_bytes()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proof = self.proof.to_bytes();
+        let schema = match &self.schema {
+            Some(schema) => serde_json::to_vec(schema).expect("Schemas serialize to JSON"),
+            None => Vec::new(),
+        };
+        let beacon = match &self.beacon {
+            Some(beacon) => serde_json::to_vec(beacon).expect("Beacon rounds serialize to JSON"),
+            None => Vec::new(),
+        };
+        let provenance = match &self.provenance {
+            Some(provenance) => serde_json::to_vec(provenance).expect("Provenance serializes to JSON"),
+            None => Vec::new(),
+        };
+        let attestation = match &self.attestation {
+            Some(attestation) => serde_json::to_vec(attestation).expect("Attestations serialize to JSON"),
+            None => Vec::new(),
+        };
+        let sections = schema.len() + beacon.len() + provenance.len() + attestation.len();
+        let mut bytes = Vec::with_capacity(292 + sections + 16 * self.public_inputs.len() + proof.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.model_id.0);
+        bytes.extend_from_slice(&self.model_commitment.0);
+        bytes.extend_from_slice(&self.constraint_fingerprint.0);
+        bytes.extend_from_slice(&(schema.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&schema);
+        match &self.signer {
+            Some(signer) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&signer.public_key.0);
+                bytes.extend_from_slice(&signer.signature);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&(beacon.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&beacon);
+        match &self.nonce {
+            Some(nonce) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&nonce.0);
+            }
+            None => bytes.push(0),
+        }
+        match &self.terms_hash {
+            Some(terms_hash) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&terms_hash.0);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&(provenance.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&provenance);
+        bytes.extend_from_slice(&(attestation.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&attestation);
+        bytes.extend_from_slice(&(self.public_inputs.len() as u32).to_le_bytes());
+        for input in &self.public_inputs {
+            bytes.extend_from_slice(&input.as_int().to_le_bytes());
+        }
+        bytes.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&proof);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        match Self::from_versioned_bytes(bytes)? {
+            (VERSION, envelope) => Ok(envelope),
+            (version, _) => Err(EnvelopeError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Decodes an envelope of any version up to [`VERSION`], with the fields that version did
+    /// not have left empty (a zero fingerprint before version 2), together with its version
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<(u8, Self), EnvelopeError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(EnvelopeError::BadMagic);
+        }
+        let version = reader.take(1)?[0];
+        if !(1..=VERSION).contains(&version) {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+        // the first version each section appeared in
+        let has = |first: u8| version >= first;
+        let model_id = ModelId(reader.array()?);
+        let model_commitment = ModelCommitment(reader.array()?);
+        let constraint_fingerprint = ConstraintFingerprint(if has(2) { reader.array()? } else { [0u8; 32] });
+        let schema = match if has(3) { reader.u32()? as usize } else { 0 } {
+            0 => None,
+            len => {
+                let schema: DataSchema = serde_json::from_slice(reader.take(len)?)
+                    .map_err(|err| EnvelopeError::InvalidSchema(err.to_string()))?;
+                schema.validate().map_err(|err| EnvelopeError::InvalidSchema(err.to_string()))?;
+                Some(schema)
+            }
+        };
+        let signer = match if has(4) { reader.take(1)?[0] } else { 0 } {
+            0 => None,
+            1 => Some(OperatorSignature { public_key: OperatorPublicKey(reader.array()?), signature: reader.array()? }),
+            _ => return Err(EnvelopeError::InvalidSigner),
+        };
+        let beacon = match if has(5) { reader.u32()? as usize } else { 0 } {
+            0 => None,
+            len => Some(decode_beacon(reader.take(len)?)?),
+        };
+        let nonce = match if has(6) { reader.take(1)?[0] } else { 0 } {
+            0 => None,
+            1 => match ClaimNonce(reader.array()?) {
+                ClaimNonce(bytes) if bytes == [0u8; 32] => return Err(EnvelopeError::InvalidNonce),
+                nonce => Some(nonce),
+            },
+            _ => return Err(EnvelopeError::InvalidNonce),
+        };
+        let terms_hash = match if has(7) { reader.take(1)?[0] } else { 0 } {
+            0 => None,
+            1 => match TermsHash(reader.array()?) {
+                TermsHash(bytes) if bytes == [0u8; 32] => return Err(EnvelopeError::InvalidTermsHash),
+                terms_hash => Some(terms_hash),
+            },
+            _ => return Err(EnvelopeError::InvalidTermsHash),
+        };
+        let provenance = match if has(8) { reader.u32()? as usize } else { 0 } {
+            0 => None,
+            len => Some(
+                serde_json::from_slice(reader.take(len)?)
+                    .map_err(|err| EnvelopeError::InvalidProvenance(err.to_string()))?,
+            ),
+        };
+        let attestation = match if has(9) { reader.u32()? as usize } else { 0 } {
+            0 => None,
+            len => Some(
+                serde_json::from_slice(reader.take(len)?)
+                    .map_err(|err| EnvelopeError::InvalidAttestation(err.to_string()))?,
+            ),
+        };
+
+        let num_inputs = reader.u32()? as usize;
+        let mut public_inputs = Vec::with_capacity(num_inputs.min(reader.bytes.len() / 16));
+        for _ in 0..num_inputs {
+            let value = u128::from_le_bytes(reader.array()?);
+            if value >= BaseElement::MODULUS {
+                return Err(EnvelopeError::NonCanonicalElement(value));
+            }
+            public_inputs.push(BaseElement::new(value));
+        }
+
+        let proof_len = reader.u32()? as usize;
+        let proof = Proof::from_bytes(reader.take(proof_len)?)
+            .map_err(|err| EnvelopeError::InvalidProof(err.to_string()))?;
+        if !reader.bytes.is_empty() {
+            return Err(EnvelopeError::TrailingBytes(reader.bytes.len()));
+        }
+
+        let envelope = Self {
+            model_id,
+            model_commitment,
+            constraint_fingerprint,
+            schema,
+            signer,
+            beacon,
+            nonce,
+            terms_hash,
+            provenance,
+            attestation,
+            public_inputs,
+            proof,
+        };
+        Ok((version, envelope))
+    }
+}
+
+/// Parses the JSON of a beacon round, rejecting rounds whose randomness is not 32 bytes of hex
+pub fn decode_beacon(json: &[u8]) -> Result<BeaconRound, EnvelopeError> {
+    let beacon: BeaconRound =
+        serde_json::from_slice(json).map_err(|err| EnvelopeError::InvalidBeacon(err.to_string()))?;
+    beacon.randomness_bytes().map_err(|err| EnvelopeError::InvalidBeacon(err.to_string()))?;
+    Ok(beacon)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], EnvelopeError> {
+        if self.bytes.len() < len {
+            return Err(EnvelopeError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], EnvelopeError> {
+        Ok(self.take(N)?.try_into().expect("slice has exactly N bytes"))
+    }
+
+    fn u32(&mut self) -> Result<u32, EnvelopeError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ColumnSchema;
+
+    #[test]
+    fn test_claim_hash_parts_cannot_stand_in_for_each_other() {
+        let domain = DomainTag::default();
+        let inputs = [BaseElement::new(8), BaseElement::new(21)];
+        let bytes = [7u8; 32];
+        let nonce = claim_hash(&domain, None, None, Some(&ClaimNonce(bytes)), None, &inputs);
+        let terms = claim_hash(&domain, None, None, None, Some(&TermsHash(bytes)), &inputs);
+        assert_ne!(nonce, terms);
+        assert_ne!(nonce, claim_hash(&domain, None, None, None, None, &inputs));
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let envelope = ProofEnvelope::new(
+            ModelId::from_name("house-prices"),
+            ModelCommitment([9u8; 32]),
+            ConstraintFingerprint([4u8; 32]),
+            vec![BaseElement::new(8), BaseElement::new(21)],
+            Proof::new_dummy(),
+        );
+        let bytes = envelope.to_bytes();
+        let decoded = ProofEnvelope::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.schema, None);
+
+        assert_eq!(decoded.model_id, envelope.model_id);
+        assert_eq!(decoded.model_commitment, envelope.model_commitment);
+        assert_eq!(decoded.constraint_fingerprint, envelope.constraint_fingerprint);
+        assert_eq!(decoded.public_inputs, envelope.public_inputs);
+        assert_eq!(decoded.proof.to_bytes(), envelope.proof.to_bytes());
+
+        assert_eq!(ProofEnvelope::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(), EnvelopeError::Truncated);
+        assert_eq!(ProofEnvelope::from_bytes(b"nope").unwrap_err(), EnvelopeError::BadMagic);
+
+        let area = ColumnSchema::new("area", "m^2", 0, 0, 500);
+        let schema = DataSchema::new(vec![area], ColumnSchema::new("price", "kEUR", 0, 0, 5000)).unwrap();
+        let described = envelope.clone().with_schema(schema.clone());
+        let decoded = ProofEnvelope::from_bytes(&described.to_bytes()).unwrap();
+        assert_eq!(decoded.schema, Some(schema));
+        let domain = DomainTag::default();
+        assert_eq!(decoded.claim_hash(&domain), described.claim_hash(&domain));
+        assert_ne!(described.claim_hash(&domain), envelope.claim_hash(&domain));
+
+        let licensed = envelope.clone().with_terms_hash(TermsHash::of(b"CC-BY-4.0"));
+        let decoded = ProofEnvelope::from_bytes(&licensed.to_bytes()).unwrap();
+        assert_eq!(decoded.terms_hash, licensed.terms_hash);
+        assert_ne!(licensed.claim_hash(&domain), envelope.claim_hash(&domain));
+
+        let traced = envelope.clone().with_provenance(Provenance::capture());
+        let decoded = ProofEnvelope::from_bytes(&traced.to_bytes()).unwrap();
+        assert_eq!(decoded.provenance, traced.provenance);
+        assert_eq!(traced.claim_hash(&domain), envelope.claim_hash(&domain));
+        assert_ne!(traced.signed_hash(&domain), envelope.signed_hash(&domain));
+        let mut retraced = traced.clone();
+        retraced.provenance.as_mut().unwrap().crate_version.major += 1;
+        assert_ne!(retraced.signed_hash(&domain), traced.signed_hash(&domain));
+    }
+}
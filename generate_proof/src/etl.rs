@@ -0,0 +1,701 @@
+//! Verifiable ETL: sample tables aggregated from committed raw events.
+//!
+//! Samples are often daily aggregates of raw events rather than raw data. [`AggregationAir`]
+//! proves that every row of a public sample table is the sum, or the floored mean, of its
+//! group of private events, and that the events are the ones behind a public Rescue commitment
+//! (see [`events_commitment`]). Groups are consecutive runs of events whose sizes are public,
+//! e.g. the number of events per day.
+//!
+//! The trace absorbs one event per [`CYCLE_LENGTH`]-row cycle like
+//! [`SegmentAir`](crate::chain::SegmentAir), with a running sum over all events. The running
+//! sum is asserted at every group boundary, so each group's sum is the difference of two
+//! asserted values; a mean is the sum floored by the public group size, which the verifier
+//! computes. The table is the proof's export: [`verify_pipeline`] checks it against the sample
+//! x values a linear regression envelope was proven over, so one verdict covers the events,
+//! the aggregation and the prediction.
+//!
+//! Aggregation proofs are bound to their deployment like linear ones: [`prove_aggregation`]
+//! proves [`DomainSeparatedAir`] over the domain tag, random coin and configuration commitment
+//! of the prover configuration, and [`verify_aggregation`] checks them, the signer and the
+//! attestation against the policy as [`verify_linear`] does for the regression.
+//!
+//! The salt sits in the last lane of the sponge's initial state, which is not asserted, and
+//! hides low-entropy events behind the commitment. Sums are field elements: their magnitude
+//! must stay below half the field modulus, which holds for fewer than `2^63` events.
+
+use core::fmt;
+
+use winterfell::{
+    crypto::DefaultRandomCoin,
+    math::{fields::f128::BaseElement, FieldElement, StarkField, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, Proof, ProofOptions, TraceInfo, TransitionConstraintDegree,
+    VerifierError,
+};
+#[cfg(feature = "prover")]
+use winterfell::{ProverError, TraceTable};
+
+use crate::coin::{CoinKind, KeccakCoin};
+#[cfg(feature = "prover")]
+use crate::config::ProverConfig;
+use crate::config::{ConfigError, VerificationPolicy};
+use crate::domain::{DomainSeparatedAir, TaggedInputs};
+use crate::envelope::ProofEnvelope;
+use crate::fingerprint::ConstraintFingerprint;
+use crate::fixed_point::{from_field, to_field};
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::hardware::{require_attestation, verify_attestation, HardwareError};
+use crate::layout::LayoutDescriptor;
+use crate::operator::{require_signer, verify_signer, SignerError};
+#[cfg(feature = "prover")]
+use crate::prover::spawn_prove_trace;
+use crate::prover::{verify_with_coin, HashFn};
+use crate::public_inputs::{check_count, TooManyPublicInputs};
+use crate::registry::{ModelCommitment, ModelId};
+use crate::regression::LinearRegressionInputs;
+use crate::session::{verify_linear, SessionError};
+
+/// Identifier of [`AggregationAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "etl-aggregation/v1";
+
+/// Sponge tag of [`events_commitment`]
+const EVENTS_TAG: u64 = 24;
+
+// Trace layout: sponge state, the event absorbed at the end of the cycle, running sum
+const SPONGE: usize = 0;
+const VALUE: usize = SPONGE + STATE_WIDTH;
+const SUM: usize = VALUE + 1;
+const TRACE_WIDTH: usize = SUM + 1;
+
+/// How a group of events becomes a row of the sample table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregation {
+    Sum,
+    /// The sum divided by the group size, rounded towards negative infinity
+    Mean,
+}
+
+impl Aggregation {
+    fn to_element(self) -> BaseElement {
+        match self {
+            Aggregation::Sum => BaseElement::ZERO,
+            Aggregation::Mean => BaseElement::ONE,
+        }
+    }
+
+    fn from_element(element: BaseElement) -> Option<Self> {
+        match element.as_int() {
+            0 => Some(Aggregation::Sum),
+            1 => Some(Aggregation::Mean),
+            _ => None,
+        }
+    }
+
+    fn apply(self, sum: i128, size: u64) -> i128 {
+        match self {
+            Aggregation::Sum => sum,
+            Aggregation::Mean => sum.div_euclid(size as i128),
+        }
+    }
+}
+
+fn initial_state(num_events: usize, salt: BaseElement) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_events, EVENTS_TAG);
+    state[STATE_WIDTH - 1] = salt;
+    state
+}
+
+/// Rescue commitment to `events` in order: each event is added to the first lane of the
+/// permuted state, and the first lane after a final permutation is the commitment
+pub fn events_commitment(events: &[i64], salt: BaseElement) -> BaseElement {
+    let mut state = initial_state(events.len(), salt);
+    for &event in events {
+        rescue::permute(&mut state);
+        state[0] += to_field(event as i128);
+    }
+    rescue::permute(&mut state);
+    state[0]
+}
+
+type TaggedAggregationAir = DomainSeparatedAir<AggregationAir>;
+
+/// Public inputs: the events' commitment, the aggregation, and the size and sum of every group
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AggregationInputs {
+    pub commitment: BaseElement,
+    pub aggregation: Aggregation,
+    pub group_sizes: Vec<u64>,
+    /// Sum of every group, as field elements
+    pub group_sums: Vec<BaseElement>,
+}
+
+impl AggregationInputs {
+    /// Number of events over all groups
+    pub fn num_events(&self) -> usize {
+        self.group_sizes.iter().sum::<u64>() as usize
+    }
+
+    /// The sample table: one aggregate per group, in the fixed-point scale of the events
+    pub fn table(&self) -> Vec<i128> {
+        let groups = self.group_sums.iter().zip(&self.group_sizes);
+        groups.map(|(&sum, &size)| self.aggregation.apply(from_field(sum), size)).collect()
+    }
+
+    /// Parses the elements returned by `to_elements`, e.g. the public inputs of an envelope
+    pub fn from_elements(elements: &[BaseElement]) -> Option<Self> {
+        if elements.len() < 4 || !elements.len().is_multiple_of(2) {
+            return None;
+        }
+        let num_groups = (elements.len() - 2) / 2;
+        let (sizes, sums) = elements[2..].split_at(num_groups);
+        let group_sizes = sizes.iter().map(|size| u64::try_from(size.as_int()).ok().filter(|&size| size > 0));
+        Some(Self {
+            commitment: elements[0],
+            aggregation: Aggregation::from_element(elements[1])?,
+            group_sizes: group_sizes.collect::<Option<_>>()?,
+            group_sums: sums.to_vec(),
+        })
+    }
+}
+
+impl ToElements<BaseElement> for AggregationInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![self.commitment, self.aggregation.to_element()];
+        elements.extend(self.group_sizes.iter().map(|&size| BaseElement::from(size)));
+        elements.extend(&self.group_sums);
+        elements
+    }
+}
+
+/// Trace length for `num_events` events: one cycle per event and one for the final permutation
+pub fn trace_length(num_events: usize) -> usize {
+    ((num_events + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+fn transition_degrees() -> Vec<TransitionConstraintDegree> {
+    let periodic = TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]);
+
+    // Constraints:
+    // 1. Rescue rounds of the sponge (degree 3)
+    // 2. The sponge absorbs the event at the end of a cycle (degree 1)
+    // 3. The event is constant within a cycle (degree 1)
+    // 4. The running sum adds the event at the end of a cycle (degree 1)
+    let mut degrees = RescueGadget::new(SPONGE).constraint_degrees();
+    degrees.extend(vec![periodic; STATE_WIDTH + 2]);
+    degrees
+}
+
+/// Smallest blowup factor the constraints of [`AggregationAir`] can be evaluated with, for
+/// every trace length
+pub fn min_blowup() -> usize {
+    transition_degrees().iter().map(TransitionConstraintDegree::min_blowup_factor).max().unwrap_or(1)
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..VALUE)
+        .with_column("event", VALUE)
+        .with_column("sum", SUM)
+}
+
+/// AIR proving that groups of committed events aggregate to a public sample table
+pub struct AggregationAir {
+    context: AirContext<BaseElement>,
+    sponge: RescueGadget,
+    inputs: AggregationInputs,
+}
+
+impl Air for AggregationAir {
+    type BaseField = BaseElement;
+    type PublicInputs = AggregationInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: AggregationInputs, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert!(!pub_inputs.group_sizes.is_empty(), "The table has at least one row");
+        assert!(pub_inputs.group_sizes.iter().all(|&size| size > 0), "Every group holds an event");
+        assert_eq!(pub_inputs.group_sizes.len(), pub_inputs.group_sums.len(), "One sum per group");
+        assert_eq!(trace_length(pub_inputs.num_events()), trace_info.length());
+
+        let sponge = RescueGadget::new(SPONGE);
+        let degrees = transition_degrees();
+
+        // the public lanes of the initial state, the running sum at the start and at every
+        // group boundary, and the commitment
+        let num_assertions = STATE_WIDTH - 1 + 1 + pub_inputs.group_sizes.len() + 1;
+        let context = AirContext::new(trace_info, degrees, num_assertions, options);
+        AggregationAir { context, sponge, inputs: pub_inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        self.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[VALUE]);
+        for i in 1..STATE_WIDTH {
+            rest[i] = last * (next[SPONGE + i] - current[SPONGE + i]);
+        }
+        rest[STATE_WIDTH] = mask * (next[VALUE] - current[VALUE]);
+        rest[STATE_WIDTH + 1] = next[SUM] - current[SUM] - last * current[VALUE];
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        // the salt in the last lane stays private
+        let initial = initial_state(self.inputs.num_events(), BaseElement::ZERO);
+        let mut assertions: Vec<_> =
+            (0..STATE_WIDTH - 1).map(|i| Assertion::single(SPONGE + i, 0, initial[i])).collect();
+        assertions.push(Assertion::single(SUM, 0, BaseElement::ZERO));
+
+        let (mut boundary, mut total) = (0, BaseElement::ZERO);
+        for (&size, &sum) in self.inputs.group_sizes.iter().zip(&self.inputs.group_sums) {
+            boundary += size as usize;
+            total += sum;
+            assertions.push(Assertion::single(SUM, boundary * CYCLE_LENGTH, total));
+        }
+        // the final permutation ends on the last row of the cycle after the last event
+        let commitment_row = boundary * CYCLE_LENGTH + rescue::NUM_ROUNDS;
+        assertions.push(Assertion::single(SPONGE, commitment_row, self.inputs.commitment));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace aggregating `groups` of events, committed with `salt`
+#[cfg(feature = "prover")]
+pub fn build_aggregation_trace(groups: &[Vec<i64>], salt: BaseElement) -> TraceTable<BaseElement> {
+    let events: Vec<i64> = groups.concat();
+    assert!(!events.is_empty(), "The table has at least one event");
+    let length = trace_length(events.len());
+    // padding cycles absorb an event other than the last one, which keeps the event column
+    // from being constant
+    let padding = events[events.len() - 1].wrapping_add(1);
+
+    let mut columns = vec![vec![BaseElement::ZERO; length]; TRACE_WIDTH];
+    let mut state = initial_state(events.len(), salt);
+    let mut sum = BaseElement::ZERO;
+    for cycle in 0..length / CYCLE_LENGTH {
+        let value = to_field(events.get(cycle).copied().unwrap_or(padding) as i128);
+        for step in 0..CYCLE_LENGTH {
+            let row = cycle * CYCLE_LENGTH + step;
+            for (i, &lane) in state.iter().enumerate() {
+                columns[SPONGE + i][row] = lane;
+            }
+            columns[VALUE][row] = value;
+            columns[SUM][row] = sum;
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut state, step);
+            }
+        }
+        state[0] += value;
+        sum += value;
+    }
+    TraceTable::init(columns)
+}
+
+/// Public inputs of the table aggregated from `groups` of events committed with `salt`
+pub fn aggregation_inputs(groups: &[Vec<i64>], aggregation: Aggregation, salt: BaseElement) -> AggregationInputs {
+    let sum = |group: &Vec<i64>| group.iter().fold(BaseElement::ZERO, |sum, &event| sum + to_field(event as i128));
+    AggregationInputs {
+        commitment: events_commitment(&groups.concat(), salt),
+        aggregation,
+        group_sizes: groups.iter().map(|group| group.len() as u64).collect(),
+        group_sums: groups.iter().map(sum).collect(),
+    }
+}
+
+/// Reason an aggregation cannot be proven
+#[cfg(feature = "prover")]
+#[derive(Debug)]
+pub enum AggregationError {
+    /// There are no groups, so no table
+    EmptyTable,
+    /// The group at this index holds no event
+    EmptyGroup(usize),
+    /// The configuration cannot prove a trace of the table's length
+    Config(ConfigError),
+    Prover(ProverError),
+}
+
+#[cfg(feature = "prover")]
+impl fmt::Display for AggregationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregationError::EmptyTable => write!(f, "aggregation has no groups"),
+            AggregationError::EmptyGroup(group) => write!(f, "group {group} holds no event"),
+            AggregationError::Config(err) => write!(f, "invalid prover configuration: {err}"),
+            AggregationError::Prover(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[cfg(feature = "prover")]
+impl std::error::Error for AggregationError {}
+
+/// Proves that `groups` of events committed with `salt` aggregate to their table, for the
+/// deployment, random coin and configuration commitment of `config`
+#[cfg(feature = "prover")]
+pub fn prove_aggregation(
+    groups: &[Vec<i64>],
+    aggregation: Aggregation,
+    salt: BaseElement,
+    config: &ProverConfig,
+) -> Result<(Proof, AggregationInputs), AggregationError> {
+    if groups.is_empty() {
+        return Err(AggregationError::EmptyTable);
+    }
+    if let Some(group) = groups.iter().position(Vec::is_empty) {
+        return Err(AggregationError::EmptyGroup(group));
+    }
+    let inputs = aggregation_inputs(groups, aggregation, salt);
+    let length = trace_length(inputs.num_events());
+    let options = config.small_claim_options(length, min_blowup()).map_err(AggregationError::Config)?;
+    let tagged = TaggedInputs {
+        config: config.config_commitment(&layout(), &options),
+        tag: config.domain.clone(),
+        nonce: None,
+        terms_hash: None,
+        schema: None,
+        inner: inputs.clone(),
+    };
+    let (trace, workers) = (build_aggregation_trace(groups, salt), config.proving_threads());
+    let handle = match config.random_coin {
+        CoinKind::Default => spawn_prove_trace::<TaggedAggregationAir, _, DefaultRandomCoin<HashFn>>(
+            trace, tagged, options, workers, None,
+        ),
+        CoinKind::Keccak => {
+            spawn_prove_trace::<TaggedAggregationAir, _, KeccakCoin<HashFn>>(trace, tagged, options, workers, None)
+        }
+    };
+    let proof = handle.join().map_err(AggregationError::Prover)?;
+    Ok((proof, inputs))
+}
+
+/// Envelope of an aggregation proof, fingerprinted for [`AggregationAir`]; `model_id` names
+/// the model the table is the sample data of
+pub fn aggregation_envelope(
+    model_id: ModelId,
+    model_commitment: ModelCommitment,
+    proof: Proof,
+    inputs: AggregationInputs,
+) -> ProofEnvelope {
+    let fingerprint = aggregation_fingerprint(&proof, inputs.clone());
+    ProofEnvelope::new(model_id, model_commitment, fingerprint, inputs.to_elements(), proof)
+}
+
+fn aggregation_fingerprint(proof: &Proof, inputs: AggregationInputs) -> ConstraintFingerprint {
+    let (trace_info, options) = (proof.trace_info().clone(), proof.options().clone());
+    ConstraintFingerprint::for_instance::<AggregationAir>(&layout(), trace_info, inputs, options)
+}
+
+/// Reason a pipeline of an aggregation and a regression envelope is rejected
+#[derive(Debug)]
+pub enum PipelineError {
+    /// The aggregation envelope's public inputs do not describe a table, or not the proof's
+    MalformedInputs,
+    PublicInputLimit(TooManyPublicInputs),
+    /// The aggregation proof was ground to fewer proof-of-work bits than the policy requires
+    InsufficientGrinding { required: u32, found: u32 },
+    FingerprintMismatch,
+    /// The aggregation envelope's operator signature is invalid, missing or untrusted
+    Signer(SignerError),
+    /// The aggregation envelope's attestation is invalid, missing or from an unlisted device
+    Hardware(HardwareError),
+    /// The policy's prover configuration cannot prove a trace of the proof's length
+    Config(ConfigError),
+    /// The aggregation proof was made with other options than the policy expects
+    OptionsMismatch,
+    Invalid(VerifierError),
+    /// The regression envelope is rejected on its own
+    Regression(SessionError),
+    /// The envelopes are bound to different models
+    ModelMismatch,
+    /// The regression was proven over another number of samples than the table has rows
+    SampleCount { table: usize, samples: usize },
+    /// The regression's sample x at `row` is not the table's
+    TableMismatch { row: usize },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::MalformedInputs => write!(f, "aggregation envelope has malformed public inputs"),
+            PipelineError::PublicInputLimit(err) => write!(f, "aggregation envelope: {err}"),
+            PipelineError::InsufficientGrinding { required, found } => {
+                write!(f, "aggregation proof has {found} grinding bits, the policy requires {required}")
+            }
+            PipelineError::FingerprintMismatch => {
+                write!(f, "aggregation envelope was not proven with the aggregation constraints")
+            }
+            PipelineError::Signer(err) => write!(f, "aggregation envelope: {err}"),
+            PipelineError::Hardware(err) => write!(f, "aggregation envelope: {err}"),
+            PipelineError::Config(err) => write!(f, "invalid prover configuration: {err}"),
+            PipelineError::OptionsMismatch => {
+                write!(f, "aggregation proof was made with other options than the policy expects")
+            }
+            PipelineError::Invalid(err) => write!(f, "aggregation proof is invalid: {err}"),
+            PipelineError::Regression(err) => write!(f, "regression envelope is rejected: {err}"),
+            PipelineError::ModelMismatch => write!(f, "aggregation and regression are bound to different models"),
+            PipelineError::SampleCount { table, samples } => {
+                write!(f, "regression has {samples} samples, the aggregated table {table} rows")
+            }
+            PipelineError::TableMismatch { row } => {
+                write!(f, "regression sample {row} is not row {row} of the aggregated table")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+/// Consolidated claim of a verified pipeline
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PipelineVerdict {
+    pub model_id: ModelId,
+    /// Commitment to the raw events the samples were aggregated from
+    pub events_commitment: BaseElement,
+    pub aggregation: Aggregation,
+    pub table: Vec<i128>,
+}
+
+/// Verifies an envelope of [`aggregation_envelope`] under `policy` and returns its table's
+/// public inputs: the proof for the policy's domain, random coin and configuration
+/// commitment, and the signer and attestation the policy requires
+pub fn verify_aggregation(
+    envelope: &ProofEnvelope,
+    policy: &VerificationPolicy,
+) -> Result<AggregationInputs, PipelineError> {
+    check_count(envelope.public_inputs.len(), policy.max_public_inputs).map_err(PipelineError::PublicInputLimit)?;
+    let inputs = AggregationInputs::from_elements(&envelope.public_inputs).ok_or(PipelineError::MalformedInputs)?;
+    // the AIR panics on a trace that does not fit the table, so the shape is checked first
+    let trace_info = envelope.proof.trace_info();
+    let num_events = inputs.group_sizes.iter().try_fold(0u64, |total, &size| total.checked_add(size));
+    let fits = num_events.and_then(|num_events| usize::try_from(num_events).ok()).is_some_and(|num_events| {
+        num_events < usize::MAX / (2 * CYCLE_LENGTH) && trace_info.length() == trace_length(num_events)
+    });
+    if !fits || trace_info.width() != TRACE_WIDTH || trace_info.is_multi_segment() {
+        return Err(PipelineError::MalformedInputs);
+    }
+
+    let found = envelope.proof.options().grinding_factor();
+    if found < policy.min_grinding_bits {
+        return Err(PipelineError::InsufficientGrinding { required: policy.min_grinding_bits, found });
+    }
+    if policy.require_fingerprint_match
+        && aggregation_fingerprint(&envelope.proof, inputs.clone()) != envelope.constraint_fingerprint
+    {
+        return Err(PipelineError::FingerprintMismatch);
+    }
+    if policy.trusted_operators.is_empty() {
+        verify_signer(envelope, &policy.domain).map_err(PipelineError::Signer)?;
+    } else {
+        require_signer(envelope, &policy.domain, &policy.trusted_operators).map_err(PipelineError::Signer)?;
+    }
+    if policy.trusted_hardware_keys.is_empty() {
+        verify_attestation(envelope, &policy.domain).map_err(PipelineError::Hardware)?;
+    } else {
+        require_attestation(envelope, &policy.domain, &policy.trusted_hardware_keys)
+            .map_err(PipelineError::Hardware)?;
+    }
+
+    let expected = policy
+        .expected_options(trace_info.length(), min_blowup(), envelope.proof.options())
+        .map_err(PipelineError::Config)?;
+    if expected != *envelope.proof.options() {
+        return Err(PipelineError::OptionsMismatch);
+    }
+    let tagged = TaggedInputs {
+        config: policy.config_commitment(&layout(), &expected),
+        tag: policy.domain.clone(),
+        nonce: envelope.nonce,
+        terms_hash: envelope.terms_hash,
+        schema: envelope.schema.as_ref().map(|schema| schema.commitment()),
+        inner: inputs.clone(),
+    };
+    let (proof, options) = (envelope.proof.clone(), policy.acceptable_options());
+    match policy.random_coin {
+        CoinKind::Default => {
+            verify_with_coin::<TaggedAggregationAir, DefaultRandomCoin<HashFn>>(proof, tagged, &options)
+        }
+        CoinKind::Keccak => verify_with_coin::<TaggedAggregationAir, KeccakCoin<HashFn>>(proof, tagged, &options),
+    }
+    .map_err(PipelineError::Invalid)?;
+    Ok(inputs)
+}
+
+/// Verifies that `aggregation` proves the sample table `regression` was proven over, and both
+/// envelopes under `policy`
+pub fn verify_pipeline(
+    aggregation: &ProofEnvelope,
+    regression: &ProofEnvelope,
+    policy: &VerificationPolicy,
+) -> Result<PipelineVerdict, PipelineError> {
+    let inputs = verify_aggregation(aggregation, policy)?;
+    verify_linear(regression, policy).map_err(PipelineError::Regression)?;
+    if aggregation.model_id != regression.model_id {
+        return Err(PipelineError::ModelMismatch);
+    }
+
+    let samples = LinearRegressionInputs::from_elements(&regression.public_inputs)
+        .expect("Verified linear envelopes parse")
+        .sample_x_values;
+    let table = inputs.table();
+    if samples.len() != table.len() {
+        return Err(PipelineError::SampleCount { table: table.len(), samples: samples.len() });
+    }
+    if let Some(row) = samples.iter().zip(&table).position(|(&x, &row)| x != to_field(row)) {
+        return Err(PipelineError::TableMismatch { row });
+    }
+    Ok(PipelineVerdict {
+        model_id: aggregation.model_id,
+        events_commitment: inputs.commitment,
+        aggregation: inputs.aggregation,
+        table,
+    })
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::to_hex;
+    use crate::config::ProverConfig;
+    use crate::domain::DomainTag;
+    use crate::operator::OperatorKey;
+    use crate::session::{LinearClaimRequest, ProvingSession};
+
+    fn groups() -> Vec<Vec<i64>> {
+        vec![vec![4, 6, 9], vec![12], vec![-3, 8], vec![5, 5, 5, 6]]
+    }
+
+    fn envelope(proof: Proof, inputs: AggregationInputs) -> ProofEnvelope {
+        let commitment = ModelCommitment::new(&DomainTag::default(), &[], &[7u8; 32]);
+        aggregation_envelope(ModelId::from_name("demo"), commitment, proof, inputs)
+    }
+
+    #[test]
+    fn test_aggregation_proof() {
+        let salt = BaseElement::new(0x5a17);
+        let config = ProverConfig::default();
+        for (aggregation, table) in [(Aggregation::Sum, vec![19, 12, 5, 21]), (Aggregation::Mean, vec![6, 12, 2, 5])] {
+            let (proof, inputs) = prove_aggregation(&groups(), aggregation, salt, &config).unwrap();
+            assert_eq!(inputs.table(), table);
+            assert_eq!(AggregationInputs::from_elements(&inputs.to_elements()), Some(inputs.clone()));
+            let verified = verify_aggregation(&envelope(proof, inputs.clone()), &VerificationPolicy::default());
+            assert_eq!(verified.unwrap(), inputs);
+        }
+        assert!(matches!(
+            prove_aggregation(&[vec![1], vec![]], Aggregation::Sum, salt, &config),
+            Err(AggregationError::EmptyGroup(1))
+        ));
+        assert!(matches!(prove_aggregation(&[], Aggregation::Sum, salt, &config), Err(AggregationError::EmptyTable)));
+        let events = groups().concat();
+        assert_ne!(events_commitment(&events, salt), events_commitment(&events, BaseElement::ONE));
+
+        // moving an event across a group boundary keeps the commitment's events but not the sums
+        let inputs = aggregation_inputs(&groups(), Aggregation::Sum, salt);
+        let mut regrouped = inputs.clone();
+        regrouped.group_sizes = vec![2, 2, 2, 4];
+        let trace = build_aggregation_trace(&groups(), salt);
+        let test = crate::air_test!(AggregationAir, regrouped).with_trace(&trace).expect_all_hold();
+        assert!(std::panic::catch_unwind(move || test.expect_assertions_hold()).is_err());
+        crate::air_test!(AggregationAir, inputs).with_trace(&trace).expect_assertions_hold().expect_full_coverage();
+    }
+
+    #[test]
+    fn test_aggregation_is_bound_to_deployment() {
+        let salt = BaseElement::new(11);
+        let (proof, inputs) = prove_aggregation(&groups(), Aggregation::Sum, salt, &ProverConfig::default()).unwrap();
+        let mut aggregated = envelope(proof, inputs);
+        let policy = VerificationPolicy::default();
+
+        let other_tenant = VerificationPolicy { domain: DomainTag::new("other-app").unwrap(), ..Default::default() };
+        assert!(matches!(verify_aggregation(&aggregated, &other_tenant), Err(PipelineError::Invalid(_))));
+        let keccak = VerificationPolicy { random_coin: CoinKind::Keccak, ..Default::default() };
+        assert!(matches!(verify_aggregation(&aggregated, &keccak), Err(PipelineError::Invalid(_))));
+        let uncommitted = VerificationPolicy { commit_config: false, ..Default::default() };
+        assert!(matches!(verify_aggregation(&aggregated, &uncommitted), Err(PipelineError::Invalid(_))));
+        let fewer_queries = ProverConfig { num_queries: 30, ..ProverConfig::default() };
+        let pinned = VerificationPolicy { prover_config: Some(fewer_queries), ..Default::default() };
+        assert!(matches!(verify_aggregation(&aggregated, &pinned), Err(PipelineError::OptionsMismatch)));
+
+        let operator = OperatorKey::from_seed(&[5u8; 32]);
+        let trusted = VerificationPolicy { trusted_operators: vec![operator.public_key()], ..Default::default() };
+        assert!(matches!(verify_aggregation(&aggregated, &trusted), Err(PipelineError::Signer(_))));
+        operator.sign_envelope(&mut aggregated, &policy.domain);
+        verify_aggregation(&aggregated, &trusted).unwrap();
+
+        // groups that do not fit the proof's trace are rejected before the AIR is built
+        for size in [1000, u64::MAX] {
+            let mut oversized = aggregated.clone();
+            let mut inputs = AggregationInputs::from_elements(&oversized.public_inputs).unwrap();
+            inputs.group_sizes[0] = size;
+            oversized.public_inputs = inputs.to_elements();
+            assert!(matches!(verify_aggregation(&oversized, &policy), Err(PipelineError::MalformedInputs)));
+        }
+
+        // proven for another deployment's coin
+        let keccak_config = ProverConfig { random_coin: CoinKind::Keccak, ..ProverConfig::default() };
+        let (proof, inputs) = prove_aggregation(&groups(), Aggregation::Sum, salt, &keccak_config).unwrap();
+        let keccak_envelope = envelope(proof, inputs);
+        assert!(matches!(verify_aggregation(&keccak_envelope, &policy), Err(PipelineError::Invalid(_))));
+        verify_aggregation(&keccak_envelope, &keccak).unwrap();
+    }
+
+    #[test]
+    fn test_pipeline_links_table_to_regression() {
+        let config = ProverConfig::default();
+        let (proof, inputs) = prove_aggregation(&groups(), Aggregation::Mean, BaseElement::new(11), &config).unwrap();
+        let aggregated = envelope(proof, inputs);
+        let regression = |sample_x: Vec<u64>| {
+            let request = LinearClaimRequest {
+                model: "demo".into(),
+                salt: to_hex(&[3u8; 32]),
+                slope: 2,
+                intercept: 1,
+                sample_x,
+                x: 9,
+                schema: None,
+                beacon: None,
+                terms_hash: None,
+            };
+            ProvingSession::new(ProverConfig::default(), "test").prove_linear(&request).unwrap()
+        };
+        let policy = VerificationPolicy::default();
+
+        let verdict = verify_pipeline(&aggregated, &regression(vec![6, 12, 2, 5]), &policy).unwrap();
+        assert_eq!((verdict.table, verdict.aggregation), (vec![6, 12, 2, 5], Aggregation::Mean));
+        assert!(matches!(
+            verify_pipeline(&aggregated, &regression(vec![6, 12, 3, 5]), &policy),
+            Err(PipelineError::TableMismatch { row: 2 })
+        ));
+        assert!(matches!(
+            verify_pipeline(&aggregated, &regression(vec![6, 12, 2]), &policy),
+            Err(PipelineError::SampleCount { table: 4, samples: 3 })
+        ));
+
+        // a table claiming other sums than the events have
+        let mut forged = aggregated.clone();
+        let mut inputs = AggregationInputs::from_elements(&forged.public_inputs).unwrap();
+        inputs.group_sums[2] += BaseElement::from(3u8);
+        forged.public_inputs = inputs.to_elements();
+        forged.constraint_fingerprint = aggregation_fingerprint(&forged.proof, inputs);
+        assert!(matches!(
+            verify_pipeline(&forged, &regression(vec![6, 12, 3, 5]), &policy),
+            Err(PipelineError::Invalid(_))
+        ));
+    }
+}
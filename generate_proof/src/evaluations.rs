@@ -0,0 +1,310 @@
+//! Evaluations of trace polynomials at public points, proven alongside any AIR.
+//!
+//! Winterfell interpolates every trace column over the trace domain `ω^0..ω^(n-1)`. Hybrid
+//! protocols built on top of a proof sometimes need that polynomial's value at agreed points,
+//! e.g. to link a column to a commitment in another proof system. [`EvaluatedAir`] proves an
+//! AIR together with [`TraceEvaluation`]s `p_c(z) = Σ L_i(z) * t_c[i]`, where `L_i` are the
+//! Lagrange polynomials of the trace domain: for each evaluation it appends an accumulator
+//! column holding the running sum, with the weights `L_i(z)` in a periodic column the verifier
+//! computes from `z`, and asserts that the sum over all rows is the claimed value.
+//!
+//! The evaluations are part of [`EvaluationInputs`], so they enter the transcript and the claim
+//! hash like any public input. The inner AIR's constraints and assertions are unchanged; it
+//! must keep Winterfell's single transition exemption, because the accumulator has to cover
+//! the last row. Columns that are constant over the trace are not exported: their polynomial
+//! is their value everywhere, and the accumulator constraints would fall short of their
+//! declared degree. Every evaluation costs the verifier an interpolation of a periodic column
+//! of the full trace length.
+
+use core::fmt;
+
+use winterfell::{
+    math::{batch_inversion, fields::f128::BaseElement, get_power_series, FieldElement, StarkField, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::{Trace, TraceTable};
+
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`EvaluatedAir`], recorded with the inner AIR's id in its layouts
+pub const AIR_ID: &str = "evaluated/v1";
+
+/// A trace column's polynomial evaluated at a public point
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEvaluation {
+    /// Column of the inner trace
+    pub column: usize,
+    pub point: BaseElement,
+    pub value: BaseElement,
+}
+
+/// Public inputs: the exported evaluations and the inner AIR's inputs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvaluationInputs<P> {
+    pub evaluations: Vec<TraceEvaluation>,
+    pub inner: P,
+}
+
+impl<P: ToElements<BaseElement>> ToElements<BaseElement> for EvaluationInputs<P> {
+    /// The number of evaluations, the column, point and value of each, then the inner inputs
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![BaseElement::from(self.evaluations.len() as u64)];
+        for evaluation in &self.evaluations {
+            elements.extend([BaseElement::from(evaluation.column as u64), evaluation.point, evaluation.value]);
+        }
+        elements.extend(self.inner.to_elements());
+        elements
+    }
+}
+
+/// Reason evaluations cannot be exported from a trace
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvaluationError {
+    NoEvaluations,
+    UnknownColumn { column: usize, width: usize },
+    /// The column has the same value on every row, which is then its evaluation at any point
+    ConstantColumn(usize),
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::NoEvaluations => write!(f, "no evaluations requested"),
+            EvaluationError::UnknownColumn { column, width } => {
+                write!(f, "column {column} is not in a trace of {width} columns")
+            }
+            EvaluationError::ConstantColumn(column) => write!(f, "column {column} is constant"),
+        }
+    }
+}
+
+impl std::error::Error for EvaluationError {}
+
+/// `L_i(point)` for every row `i` of a trace of `trace_length` rows
+pub fn lagrange_weights(point: BaseElement, trace_length: usize) -> Vec<BaseElement> {
+    let root = BaseElement::get_root_of_unity(trace_length.ilog2());
+    let domain = get_power_series(root, trace_length);
+    // `L_i(z) = ω^i (z^n - 1) / (n (z - ω^i))`, or the indicator of `i` for `z = ω^i`
+    let vanishing = point.exp((trace_length as u64).into()) - BaseElement::ONE;
+    if vanishing == BaseElement::ZERO {
+        return domain.iter().map(|&x| if x == point { BaseElement::ONE } else { BaseElement::ZERO }).collect();
+    }
+    let scale = vanishing / BaseElement::from(trace_length as u64);
+    let denominators: Vec<BaseElement> = domain.iter().map(|&x| point - x).collect();
+    domain.iter().zip(batch_inversion(&denominators)).map(|(&x, inverse)| x * scale * inverse).collect()
+}
+
+/// The polynomial interpolating `column` over the trace domain, evaluated at `point`
+pub fn evaluate_column(column: &[BaseElement], point: BaseElement) -> BaseElement {
+    lagrange_weights(point, column.len()).iter().zip(column).fold(BaseElement::ZERO, |sum, (&w, &t)| sum + w * t)
+}
+
+/// `A` with the evaluations of [`EvaluationInputs`] proven on accumulator columns appended to
+/// its trace. AIRs with auxiliary trace segments are not supported.
+pub struct EvaluatedAir<A> {
+    inner: A,
+    evaluations: Vec<TraceEvaluation>,
+    /// `L_0(z)` of every evaluation
+    first_weights: Vec<BaseElement>,
+    context: AirContext<BaseElement>,
+}
+
+impl<A: Air<BaseField = BaseElement>> EvaluatedAir<A> {
+    fn accumulator(&self, evaluation: usize) -> usize {
+        self.inner.trace_info().width() + evaluation
+    }
+}
+
+impl<A: Air<BaseField = BaseElement>> Air for EvaluatedAir<A> {
+    type BaseField = BaseElement;
+    type PublicInputs = EvaluationInputs<A::PublicInputs>;
+
+    fn new(trace_info: TraceInfo, pub_inputs: Self::PublicInputs, options: ProofOptions) -> Self {
+        assert!(!trace_info.is_multi_segment(), "Evaluations do not support auxiliary segments");
+        let evaluations = pub_inputs.evaluations;
+        assert!(!evaluations.is_empty(), "At least one evaluation is required");
+        let inner_width = trace_info.width() - evaluations.len();
+        assert!(evaluations.iter().all(|evaluation| evaluation.column < inner_width), "Unknown column");
+        let length = trace_info.length();
+        let inner = A::new(TraceInfo::new(inner_width, length), pub_inputs.inner, options.clone());
+        assert_eq!(inner.context().num_transition_exemptions(), 1, "The inner AIR must constrain every transition");
+
+        // Constraints: those of the inner AIR, then for every evaluation
+        // 1. the accumulator adds the next row's weighted value: acc' = acc + L_(i+1)(z) * t' (degree 1)
+        // 2. on the first row, the accumulator is the first weighted value: acc = L_0(z) * t (degree 1)
+        let zeros = vec![BaseElement::ZERO; inner.context().num_transition_constraints()];
+        let mut degrees = inner.get_transition_constraints(&zeros).main_constraint_degrees().to_vec();
+        for _ in &evaluations {
+            degrees.push(TransitionConstraintDegree::with_cycles(1, vec![length]));
+            degrees.push(TransitionConstraintDegree::with_cycles(1, vec![length]));
+        }
+        let num_assertions = inner.context().num_assertions() + evaluations.len();
+        let context = AirContext::new(trace_info, degrees, num_assertions, options);
+
+        let first_weights = evaluations.iter().map(|evaluation| lagrange_weights(evaluation.point, length)[0]);
+        let first_weights = first_weights.collect();
+        Self { inner, evaluations, first_weights, context }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let (current, next) = (frame.current(), frame.next());
+        let num_inner = self.inner.context().num_main_transition_constraints();
+        let inner_periodic = periodic_values.len() - self.evaluations.len() - 1;
+        let (inner_result, result) = result.split_at_mut(num_inner);
+        self.inner.evaluate_transition(frame, &periodic_values[..inner_periodic], inner_result);
+
+        // the weights of evaluation `k` are shifted by a row, so `first` is the last periodic value
+        let first = periodic_values[periodic_values.len() - 1];
+        for (k, evaluation) in self.evaluations.iter().enumerate() {
+            let acc = self.accumulator(k);
+            let next_weight = periodic_values[inner_periodic + k];
+            let first_weight = E::from(self.first_weights[k]);
+            result[2 * k] = next[acc] - current[acc] - next_weight * next[evaluation.column];
+            result[2 * k + 1] = first * (current[acc] - first_weight * current[evaluation.column]);
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = self.inner.get_assertions();
+        let last = self.trace_length() - 1;
+        for (k, evaluation) in self.evaluations.iter().enumerate() {
+            assertions.push(Assertion::single(self.accumulator(k), last, evaluation.value));
+        }
+        assertions
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        let length = self.trace_length();
+        let mut columns = self.inner.get_periodic_column_values();
+        for evaluation in &self.evaluations {
+            let mut weights = lagrange_weights(evaluation.point, length);
+            // row `i` holds the weight of row `i + 1`; the last row is exempt
+            weights.rotate_left(1);
+            columns.push(weights);
+        }
+        let mut first = vec![BaseElement::ZERO; length];
+        first[0] = BaseElement::ONE;
+        columns.push(first);
+        columns
+    }
+}
+
+/// Named columns of an [`EvaluatedAir`] exporting `num_evaluations` evaluations from the AIR of
+/// `inner`: the inner columns prefixed with `inner_`, then the accumulators, see
+/// [`crate::layout`]
+pub fn layout(inner: &LayoutDescriptor, num_evaluations: usize) -> LayoutDescriptor {
+    LayoutDescriptor::new(&format!("{AIR_ID}({})", inner.air_id))
+        .with_layout("inner", inner, 0)
+        .with_columns("accumulators", inner.width()..inner.width() + num_evaluations)
+}
+
+/// Appends an accumulator column to `trace` for every `(column, point)` in `requests`, with
+/// the evaluations they prove
+#[cfg(feature = "prover")]
+pub fn export_evaluations(
+    trace: &TraceTable<BaseElement>,
+    requests: &[(usize, BaseElement)],
+) -> Result<(TraceTable<BaseElement>, Vec<TraceEvaluation>), EvaluationError> {
+    if requests.is_empty() {
+        return Err(EvaluationError::NoEvaluations);
+    }
+    let width = trace.width();
+    let mut columns: Vec<Vec<BaseElement>> = (0..width).map(|column| trace.get_column(column).to_vec()).collect();
+    let mut evaluations = Vec::with_capacity(requests.len());
+    for &(column, point) in requests {
+        let values = columns[..width].get(column).ok_or(EvaluationError::UnknownColumn { column, width })?;
+        if values.iter().all(|&value| value == values[0]) {
+            return Err(EvaluationError::ConstantColumn(column));
+        }
+        let weights = lagrange_weights(point, trace.length());
+        let accumulator: Vec<BaseElement> = weights
+            .iter()
+            .zip(values)
+            .scan(BaseElement::ZERO, |sum, (&w, &t)| {
+                *sum += w * t;
+                Some(*sum)
+            })
+            .collect();
+        let value = *accumulator.last().expect("Traces are not empty");
+        evaluations.push(TraceEvaluation { column, point, value });
+        columns.push(accumulator);
+    }
+    Ok((TraceTable::init(columns), evaluations))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use winterfell::math::{fft, polynom};
+
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    type Evaluated = EvaluatedAir<LinearRegressionAir>;
+
+    #[test]
+    fn test_evaluations_match_interpolation() {
+        let column: Vec<BaseElement> = (0..16u128).map(|i| BaseElement::new(i * i + 3)).collect();
+        let mut coefficients = column.clone();
+        fft::interpolate_poly(&mut coefficients, &fft::get_inv_twiddles(column.len()));
+        for point in [BaseElement::new(5), BaseElement::new(1 << 100), BaseElement::get_root_of_unity(4).exp(3)] {
+            assert_eq!(evaluate_column(&column, point), polynom::eval(&coefficients, point));
+        }
+        // points of the trace domain select a row
+        assert_eq!(evaluate_column(&column, BaseElement::get_root_of_unity(4).exp(3)), column[3]);
+    }
+
+    #[test]
+    fn test_evaluations_are_proven() {
+        let (slope, intercept) = (BaseElement::new(2), BaseElement::new(5));
+        let xs: Vec<BaseElement> = [1u128, 3, 4, 9, 11].map(BaseElement::new).to_vec();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(8);
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        let inner = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs,
+            sample_y_values: ys,
+        };
+
+        let requests = [(2, BaseElement::new(77)), (3, BaseElement::new(1 << 90))];
+        let (extended, evaluations) = export_evaluations(&trace, &requests).unwrap();
+        assert_eq!(extended.width(), trace.width() + 2);
+        assert_eq!(evaluations[1].value, evaluate_column(trace.get_column(3), BaseElement::new(1 << 90)));
+        let inputs = EvaluationInputs { evaluations, inner };
+        crate::air_test!(Evaluated, inputs.clone())
+            .with_trace(&extended)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<Evaluated>(extended, inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+        let acceptable = default_acceptable_options();
+        let mut wrong = inputs.clone();
+        wrong.evaluations[0].value += BaseElement::ONE;
+        assert!(verify::<Evaluated>(proof.clone(), wrong, &acceptable).is_err());
+        let mut moved = inputs.clone();
+        moved.evaluations[1].point = BaseElement::new(78);
+        assert!(verify::<Evaluated>(proof.clone(), moved, &acceptable).is_err());
+        verify::<Evaluated>(proof, inputs, &acceptable).unwrap();
+
+        assert_eq!(export_evaluations(&trace, &[]).unwrap_err(), EvaluationError::NoEvaluations);
+        let unknown = export_evaluations(&trace, &[(9, BaseElement::ONE)]).unwrap_err();
+        assert_eq!(unknown, EvaluationError::UnknownColumn { column: 9, width: trace.width() });
+        // the slope column
+        let constant = export_evaluations(&trace, &[(0, BaseElement::ONE)]).unwrap_err();
+        assert_eq!(constant, EvaluationError::ConstantColumn(0));
+    }
+}
@@ -0,0 +1,365 @@
+//! Constraint evaluation split across the rows of the constraint evaluation domain.
+//!
+//! Winterfell's [`DefaultConstraintEvaluator`] evaluates one row at a time into a table with a
+//! column per divisor, then divides and merges the columns in a second pass; with the
+//! `concurrent` feature it only spreads rows over threads once the domain has 8192 rows.
+//! [`ParallelConstraintEvaluator`] writes the composition column directly: every thread takes
+//! a contiguous chunk of rows and evaluates it in blocks of [`BLOCK_ROWS`], merging each
+//! constraint over a whole block at once and dividing by the divisors in the same pass. For
+//! the wide traces of multi-feature AIRs this keeps the merge loops tight and the per-row
+//! state small.
+//!
+//! Only AIRs with a single trace segment are supported, which covers every AIR in this crate.
+//! The degree checks Winterfell runs in debug builds stay with the default evaluator.
+
+use winterfell::{
+    math::{batch_inversion, fft, fields::f128::BaseElement, FieldElement},
+    Air, AuxRandElements, CompositionPolyTrace, ConstraintCompositionCoefficients, ConstraintDivisor,
+    ConstraintEvaluator, DefaultConstraintEvaluator, EvaluationFrame, StarkDomain, TraceLde,
+};
+
+/// Rows evaluated before their constraint evaluations are merged
+pub const BLOCK_ROWS: usize = 64;
+
+/// Fewest rows handed to one thread
+#[cfg(feature = "concurrent")]
+const MIN_CHUNK_ROWS: usize = 1024;
+
+/// Constraint evaluator used by [`AirProver`](crate::prover::AirProver)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvaluatorKind {
+    /// Winterfell's [`DefaultConstraintEvaluator`]
+    #[default]
+    Default,
+    /// [`ParallelConstraintEvaluator`]
+    Parallel,
+}
+
+/// Inverses of a divisor numerator `x^a - b` over the constraint evaluation domain; they
+/// repeat every `ce_domain_size / a` rows
+fn inverse_numerators(divisor: &ConstraintDivisor<BaseElement>, domain: &StarkDomain<BaseElement>) -> Vec<BaseElement> {
+    let numerator = divisor.numerator();
+    assert_eq!(numerator.len(), 1, "Divisors with several numerator terms are not supported");
+    let (degree, shift) = numerator[0];
+    let offset = domain.offset().exp((degree as u64).into());
+    let values: Vec<_> = (0..domain.ce_domain_size() / degree)
+        .map(|step| domain.get_ce_x_power_at(step, degree as u64, offset) - shift)
+        .collect();
+    batch_inversion(&values)
+}
+
+/// Periodic column values over the constraint evaluation domain, one row per step
+struct PeriodicValues {
+    values: Vec<BaseElement>,
+    width: usize,
+    length: usize,
+}
+
+impl PeriodicValues {
+    fn new<A: Air<BaseField = BaseElement>>(air: &A) -> Self {
+        let polys = air.get_periodic_column_polys();
+        let columns: Vec<Vec<BaseElement>> = polys
+            .iter()
+            .map(|poly| {
+                let offset = air.domain_offset().exp(((air.trace_length() / poly.len()) as u64).into());
+                fft::evaluate_poly_with_offset(poly, &fft::get_twiddles(poly.len()), offset, air.ce_blowup_factor())
+            })
+            .collect();
+        let length = columns.iter().map(Vec::len).max().unwrap_or(0);
+        let values = (0..length).flat_map(|i| columns.iter().map(move |column| column[i % column.len()])).collect();
+        Self { values, width: columns.len(), length }
+    }
+
+    fn row(&self, step: usize) -> &[BaseElement] {
+        if self.width == 0 {
+            return &[];
+        }
+        let start = (step % self.length) * self.width;
+        &self.values[start..start + self.width]
+    }
+}
+
+/// Assertions sharing a divisor, with their composition coefficients applied
+struct BoundaryGroup<E> {
+    inverses: Vec<BaseElement>,
+    /// Column, asserted value and coefficient
+    single: Vec<(usize, BaseElement, E)>,
+    /// Column, asserted values at every step of the domain and coefficient
+    sequences: Vec<(usize, Vec<BaseElement>, E)>,
+}
+
+impl<E: FieldElement<BaseField = BaseElement>> BoundaryGroup<E> {
+    fn evaluate(&self, state: &[BaseElement], step: usize) -> E {
+        let single = self.single.iter().map(|&(column, value, coefficient)| coefficient.mul_base(state[column] - value));
+        let sequences = self
+            .sequences
+            .iter()
+            .map(|(column, values, coefficient)| coefficient.mul_base(state[*column] - values[step]));
+        single.chain(sequences).fold(E::ZERO, |acc, value| acc + value)
+    }
+}
+
+/// Constraint evaluator splitting the constraint evaluation domain into row chunks, see the
+/// [module documentation](self)
+pub struct ParallelConstraintEvaluator<'a, A: Air<BaseField = BaseElement>, E: FieldElement<BaseField = BaseElement>> {
+    air: &'a A,
+    transition_coefficients: Vec<E>,
+    transition_divisor: ConstraintDivisor<BaseElement>,
+    boundary_coefficients: Vec<E>,
+    periodic: PeriodicValues,
+}
+
+impl<'a, A, E> ParallelConstraintEvaluator<'a, A, E>
+where
+    A: Air<BaseField = BaseElement>,
+    E: FieldElement<BaseField = BaseElement>,
+{
+    pub fn new(air: &'a A, composition_coefficients: ConstraintCompositionCoefficients<E>) -> Self {
+        assert!(!air.trace_info().is_multi_segment(), "Only single-segment traces are supported");
+        let transition = air.get_transition_constraints(&composition_coefficients.transition);
+        Self {
+            air,
+            transition_coefficients: transition.main_constraint_coef(),
+            transition_divisor: transition.divisor().clone(),
+            boundary_coefficients: composition_coefficients.boundary,
+            periodic: PeriodicValues::new(air),
+        }
+    }
+
+    /// Groups the assertions by divisor, evaluating sequence assertions over the whole domain
+    fn boundary_groups(&self, domain: &StarkDomain<BaseElement>) -> Vec<BoundaryGroup<E>> {
+        let constraints = self.air.get_boundary_constraints(None, &self.boundary_coefficients);
+        constraints
+            .main_constraints()
+            .iter()
+            .map(|group| {
+                assert!(group.divisor().exemptions().is_empty(), "Assertion divisors have no exemptions");
+                let mut result =
+                    BoundaryGroup { inverses: inverse_numerators(group.divisor(), domain), single: Vec::new(), sequences: Vec::new() };
+                for constraint in group.constraints() {
+                    let poly = constraint.poly();
+                    if poly.len() == 1 {
+                        result.single.push((constraint.column(), poly[0], *constraint.cc()));
+                        continue;
+                    }
+                    let mut values = fft::evaluate_poly_with_offset(
+                        poly,
+                        &fft::get_twiddles(poly.len()),
+                        self.air.domain_offset(),
+                        self.air.ce_domain_size() / poly.len(),
+                    );
+                    // shift the evaluations so they are indexed by step, the sequence may not start at 0
+                    values.rotate_right(constraint.poly_offset().0 * self.air.ce_blowup_factor());
+                    result.sequences.push((constraint.column(), values, *constraint.cc()));
+                }
+                result
+            })
+            .collect()
+    }
+
+    /// Writes the composition values of the `out.len()` steps starting at `first_step`
+    fn evaluate_rows<T: TraceLde<E>>(
+        &self,
+        trace: &T,
+        domain: &StarkDomain<BaseElement>,
+        transition_inverses: &[BaseElement],
+        boundary: &[BoundaryGroup<E>],
+        first_step: usize,
+        out: &mut [E],
+    ) {
+        let num_constraints = self.transition_coefficients.len();
+        let lde_shift = domain.ce_to_lde_blowup().trailing_zeros();
+        let generator = domain.ce_domain_generator();
+
+        let mut frame = EvaluationFrame::new(self.air.trace_info().main_trace_width());
+        let mut row = vec![BaseElement::ZERO; num_constraints];
+        // Evaluations of a block, stored constraint by constraint
+        let mut transitions = vec![BaseElement::ZERO; num_constraints * BLOCK_ROWS];
+        let mut assertions = vec![E::ZERO; boundary.len() * BLOCK_ROWS];
+        let mut xs = [BaseElement::ZERO; BLOCK_ROWS];
+
+        for (block, out) in out.chunks_mut(BLOCK_ROWS).enumerate() {
+            let start = first_step + block * BLOCK_ROWS;
+            let mut x = domain.get_ce_x_at(start);
+            for i in 0..out.len() {
+                let step = start + i;
+                trace.read_main_trace_frame_into(step << lde_shift, &mut frame);
+                row.fill(BaseElement::ZERO);
+                self.air.evaluate_transition(&frame, self.periodic.row(step), &mut row);
+                for (j, &value) in row.iter().enumerate() {
+                    transitions[j * BLOCK_ROWS + i] = value;
+                }
+                for (g, group) in boundary.iter().enumerate() {
+                    assertions[g * BLOCK_ROWS + i] = group.evaluate(frame.current(), step);
+                }
+                xs[i] = x;
+                x *= generator;
+            }
+
+            out.fill(E::ZERO);
+            for (coefficient, column) in self.transition_coefficients.iter().zip(transitions.chunks_exact(BLOCK_ROWS)) {
+                for (acc, &value) in out.iter_mut().zip(column) {
+                    *acc += coefficient.mul_base(value);
+                }
+            }
+            for (i, acc) in out.iter_mut().enumerate() {
+                let step = start + i;
+                let z = transition_inverses[step % transition_inverses.len()];
+                *acc = acc.mul_base(z * self.transition_divisor.evaluate_exemptions_at(xs[i]));
+            }
+            for (group, column) in boundary.iter().zip(assertions.chunks_exact(BLOCK_ROWS)) {
+                for (i, (acc, &value)) in out.iter_mut().zip(column).enumerate() {
+                    *acc += value.mul_base(group.inverses[(start + i) % group.inverses.len()]);
+                }
+            }
+        }
+    }
+}
+
+impl<A, E> ConstraintEvaluator<E> for ParallelConstraintEvaluator<'_, A, E>
+where
+    A: Air<BaseField = BaseElement>,
+    E: FieldElement<BaseField = BaseElement>,
+{
+    type Air = A;
+
+    fn evaluate<T: TraceLde<E>>(self, trace: &T, domain: &StarkDomain<BaseElement>) -> CompositionPolyTrace<E> {
+        assert_eq!(trace.trace_len(), domain.lde_domain_size(), "Extended trace does not match the domain");
+        let transition_inverses = inverse_numerators(&self.transition_divisor, domain);
+        let boundary = self.boundary_groups(domain);
+        let mut result = vec![E::ZERO; domain.ce_domain_size()];
+
+        #[cfg(feature = "concurrent")]
+        {
+            use rayon::prelude::*;
+            let chunk_rows = (result.len() / rayon::current_num_threads()).max(MIN_CHUNK_ROWS);
+            result.par_chunks_mut(chunk_rows).enumerate().for_each(|(chunk, out)| {
+                self.evaluate_rows(trace, domain, &transition_inverses, &boundary, chunk * chunk_rows, out)
+            });
+        }
+        #[cfg(not(feature = "concurrent"))]
+        self.evaluate_rows(trace, domain, &transition_inverses, &boundary, 0, &mut result);
+
+        CompositionPolyTrace::new(result)
+    }
+}
+
+/// The evaluator picked by an [`EvaluatorKind`]
+pub enum AirEvaluator<'a, A: Air<BaseField = BaseElement>, E: FieldElement<BaseField = BaseElement>> {
+    Default(DefaultConstraintEvaluator<'a, A, E>),
+    Parallel(ParallelConstraintEvaluator<'a, A, E>),
+}
+
+impl<'a, A, E> AirEvaluator<'a, A, E>
+where
+    A: Air<BaseField = BaseElement>,
+    E: FieldElement<BaseField = BaseElement>,
+{
+    pub fn new(
+        kind: EvaluatorKind,
+        air: &'a A,
+        aux_rand_elements: Option<AuxRandElements<E>>,
+        composition_coefficients: ConstraintCompositionCoefficients<E>,
+    ) -> Self {
+        match kind {
+            EvaluatorKind::Default => {
+                Self::Default(DefaultConstraintEvaluator::new(air, aux_rand_elements, composition_coefficients))
+            }
+            EvaluatorKind::Parallel => Self::Parallel(ParallelConstraintEvaluator::new(air, composition_coefficients)),
+        }
+    }
+}
+
+impl<A, E> ConstraintEvaluator<E> for AirEvaluator<'_, A, E>
+where
+    A: Air<BaseField = BaseElement>,
+    E: FieldElement<BaseField = BaseElement>,
+{
+    type Air = A;
+
+    fn evaluate<T: TraceLde<E>>(self, trace: &T, domain: &StarkDomain<BaseElement>) -> CompositionPolyTrace<E> {
+        match self {
+            Self::Default(evaluator) => evaluator.evaluate(trace, domain),
+            Self::Parallel(evaluator) => evaluator.evaluate(trace, domain),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::freshness::{build_freshness_trace, FreshnessAir, FreshnessInputs};
+    use crate::prover::{verify, AirProver, HashFn};
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+    use winterfell::{
+        crypto::MerkleTree, DefaultTraceLde, PartitionOptions, Prover, Trace, TraceInfo, TraceTable,
+    };
+
+    /// Composition values of both evaluators for the same random coefficients
+    fn compositions<A>(trace: &TraceTable<BaseElement>, inputs: A::PublicInputs) -> (Vec<BaseElement>, Vec<BaseElement>)
+    where
+        A: Air<BaseField = BaseElement>,
+    {
+        let air = A::new(trace.info().clone(), inputs, ProverConfig::default().to_proof_options());
+        let domain = StarkDomain::new(&air);
+        let (lde, _) = DefaultTraceLde::<BaseElement, HashFn, MerkleTree<HashFn>>::new(
+            trace.info(),
+            trace.main_segment(),
+            &domain,
+            PartitionOptions::default(),
+        );
+        let context = air.context();
+        let coefficients = || ConstraintCompositionCoefficients {
+            transition: (1..=context.num_transition_constraints() as u64).map(|i| BaseElement::from(i * 7919)).collect(),
+            boundary: (1..=context.num_assertions() as u64).map(|i| BaseElement::from(i * 104_729)).collect(),
+        };
+        let default = DefaultConstraintEvaluator::new(&air, None, coefficients()).evaluate(&lde, &domain);
+        let parallel = ParallelConstraintEvaluator::new(&air, coefficients()).evaluate(&lde, &domain);
+        (default.into_inner(), parallel.into_inner())
+    }
+
+    #[test]
+    fn test_matches_default_evaluator() {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+        let xs: Vec<BaseElement> = (1..=20u64).map(BaseElement::from).collect();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(11);
+        let inputs = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        let (default, parallel) = compositions::<LinearRegressionAir>(&trace, inputs);
+        assert_eq!(default, parallel);
+
+        // periodic columns
+        let timestamps = [1_700_000_000, 1_700_000_060, 1_700_000_300];
+        let inputs = FreshnessInputs::for_timestamps(&timestamps, 1_700_000_400, 3_600);
+        let trace = build_freshness_trace(&timestamps, 1_700_000_400, 3_600).unwrap();
+        let (default, parallel) = compositions::<FreshnessAir>(&trace, inputs);
+        assert_eq!(default, parallel);
+    }
+
+    #[test]
+    fn test_parallel_proof_verifies() {
+        let (slope, intercept) = (BaseElement::new(2), BaseElement::new(5));
+        let xs = vec![BaseElement::new(1), BaseElement::new(4), BaseElement::new(9)];
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(6);
+        let inputs = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        let proof = AirProver::<LinearRegressionAir>::new(ProverConfig::default().to_proof_options(), inputs.clone())
+            .with_evaluator(EvaluatorKind::Parallel)
+            .prove(trace)
+            .unwrap();
+        verify::<LinearRegressionAir>(proof, inputs, &default_acceptable_options()).unwrap();
+    }
+}
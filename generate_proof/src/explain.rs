@@ -0,0 +1,370 @@
+//! Explanations of why a linear claim fails verification.
+//!
+//! Winterfell rejects a proof with a single [`VerifierError`](winterfell::VerifierError) that
+//! names the check that failed, not the part of the claim that is wrong. [`explain_linear`]
+//! re-derives what the envelope alone pins down (the public input layout, the trace shape for
+//! the claimed number of samples, the constraint fingerprint of the verifier's AIR version and
+//! the proof options against a policy) and, given the prover's witness, recomputes the predicted
+//! value and every sample's `y` to point at the exact public inputs that differ. Mismatches a
+//! transition constraint would reject name it by its label, see [`Mismatch::constraint`].
+//!
+//! The deployment's domain tag, the random coin and the configuration commitment are bound in
+//! the transcript only, so a proof made for others fails like a forged one. When the proof does
+//! not verify under the policy, it is verified again with the other random coin, the other
+//! choice of configuration commitment and the default domain tag, and the differences of the
+//! closest combination it verifies under are reported.
+
+use core::fmt;
+
+use winterfell::{crypto::hashers::Blake3_256, math::fields::f128::BaseElement, TraceInfo};
+
+use crate::coin::CoinKind;
+use crate::config::VerificationPolicy;
+use crate::domain::DomainTag;
+use crate::envelope::ProofEnvelope;
+use crate::fingerprint::ConstraintFingerprint;
+use crate::regression::{self, LinearRegressionInputs, TRACE_WIDTH};
+use crate::session::{linear_fingerprint, verify_linear_proof};
+
+/// Private values of a linear claim, known to the prover
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinearWitness {
+    pub slope: BaseElement,
+    pub intercept: BaseElement,
+}
+
+/// One difference between an envelope and what the verifier expects
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The public inputs are not `[x, y, sample x.., sample y..]`
+    Layout { num_inputs: usize },
+    /// The proof's trace is too short or of the wrong width for this many samples, e.g. after a
+    /// sample was added; `expected` is the smallest trace that fits them
+    TraceShape { expected: (usize, usize), found: (usize, usize) },
+    /// The proof was generated for another version of the AIR
+    AirVersion { expected: ConstraintFingerprint, found: ConstraintFingerprint },
+    /// The proof options reach fewer bits of conjectured security than the policy requires
+    Security { required: u32, found: u32 },
+    Grinding { required: u32, found: u32 },
+    /// The proof options are not those the policy's prover configuration picks for the trace
+    Options,
+    /// The proof verifies for the deployment `found`, not the policy's
+    Domain { expected: DomainTag, found: DomainTag },
+    /// The proof verifies with the random coin `found`, not the policy's
+    Coin { expected: CoinKind, found: CoinKind },
+    /// The proof verifies only if it does (`found`) or does not commit to its configuration,
+    /// unlike what the policy expects
+    ConfigCommitment { expected: bool, found: bool },
+    /// The claimed prediction is not `slope * x + intercept`
+    PredictedY { x: BaseElement, expected: BaseElement, found: BaseElement },
+    /// A sample's `y` is not `slope * x + intercept`
+    SampleY { index: usize, x: BaseElement, expected: BaseElement, found: BaseElement },
+}
+
+impl Mismatch {
+    /// Label of the [`LinearRegressionAir`](regression::LinearRegressionAir) constraint the
+    /// mismatch violates, for those the trace rather than the envelope rules out
+    pub fn constraint(&self) -> Option<&'static str> {
+        match self {
+            Mismatch::PredictedY { .. } | Mismatch::SampleY { .. } => Some("linear_relation"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Layout { num_inputs } => {
+                write!(f, "{num_inputs} public inputs do not form x, y and equally many sample x and y values")
+            }
+            Mismatch::TraceShape { expected, found } => write!(
+                f,
+                "proof covers a {}x{} trace, the claim's samples need at least {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            Mismatch::AirVersion { expected, found } => {
+                write!(f, "proof is for constraint system {found}, this verifier's AIR is {expected}")
+            }
+            Mismatch::Security { required, found } => {
+                write!(f, "proof options give {found} bits of conjectured security, {required} required")
+            }
+            Mismatch::Grinding { required, found } => {
+                write!(f, "proof has {found} grinding bits, {required} required")
+            }
+            Mismatch::Options => write!(f, "proof options are not those the policy's prover configuration picks"),
+            Mismatch::Domain { expected, found } => {
+                write!(f, "proof is for deployment `{found}`, the policy expects `{expected}`")
+            }
+            Mismatch::Coin { expected, found } => {
+                write!(f, "proof was made with the {found} random coin, the policy expects the {expected} one")
+            }
+            Mismatch::ConfigCommitment { expected: true, .. } => {
+                write!(f, "proof does not commit to its configuration, the policy requires it")
+            }
+            Mismatch::ConfigCommitment { expected: false, .. } => {
+                write!(f, "proof commits to its configuration, the policy expects a proof predating that")
+            }
+            Mismatch::PredictedY { x, expected, found } => {
+                write!(f, "predicted y at x = {x} is {found}, the model gives {expected}")
+            }
+            Mismatch::SampleY { index, x, expected, found } => {
+                write!(f, "sample {index} (x = {x}) has y = {found}, the model gives {expected}")
+            }
+        }
+    }
+}
+
+/// Every mismatch found in an envelope, in the order they are checked
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Explanation {
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Explanation {
+    pub fn is_empty(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.mismatches.is_empty() {
+            return writeln!(f, "claim matches the verifier's expectations; the proof itself is invalid");
+        }
+        for mismatch in &self.mismatches {
+            match mismatch.constraint() {
+                Some(label) => writeln!(f, "- {mismatch} (violates constraint {label})")?,
+                None => writeln!(f, "- {mismatch}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lists what in `envelope` differs from a linear claim `policy` accepts, recomputing the
+/// claimed `y` values when the prover's `witness` is given
+pub fn explain_linear(
+    envelope: &ProofEnvelope,
+    policy: &VerificationPolicy,
+    witness: Option<&LinearWitness>,
+) -> Explanation {
+    let mut mismatches = Vec::new();
+    let proof = &envelope.proof;
+
+    let security = proof.conjectured_security::<Blake3_256<BaseElement>>().bits();
+    if security < policy.min_security_bits {
+        mismatches.push(Mismatch::Security { required: policy.min_security_bits, found: security });
+    }
+    let grinding = proof.options().grinding_factor();
+    if grinding < policy.min_grinding_bits {
+        mismatches.push(Mismatch::Grinding { required: policy.min_grinding_bits, found: grinding });
+    }
+
+    let Some(inputs) = LinearRegressionInputs::from_elements(&envelope.public_inputs) else {
+        mismatches.push(Mismatch::Layout { num_inputs: envelope.public_inputs.len() });
+        return Explanation { mismatches };
+    };
+
+    let trace_info = proof.trace_info();
+    // provers may pad the trace beyond the shortest one the samples need
+    let expected = (TRACE_WIDTH, regression::trace_length(inputs.sample_x_values.len(), TraceInfo::MIN_TRACE_LENGTH));
+    let found = (trace_info.width(), trace_info.length());
+    if found.0 != expected.0 || found.1 < expected.1 {
+        mismatches.push(Mismatch::TraceShape { expected, found });
+    } else {
+        let fingerprint = linear_fingerprint(trace_info.clone(), inputs.clone(), proof.options().clone());
+        if fingerprint != envelope.constraint_fingerprint {
+            mismatches.push(Mismatch::AirVersion { expected: fingerprint, found: envelope.constraint_fingerprint });
+        }
+        let expected = policy.expected_options(trace_info.length(), regression::min_blowup(), proof.options());
+        if expected.ok().as_ref() != Some(proof.options()) {
+            mismatches.push(Mismatch::Options);
+        }
+        if verify_linear_proof(envelope, inputs.clone(), policy).is_err() {
+            mismatches.extend(deployment_mismatches(envelope, &inputs, policy));
+        }
+    }
+
+    if let Some(LinearWitness { slope, intercept }) = witness.copied() {
+        let x = inputs.x_value;
+        if inputs.predicted_y != slope * x + intercept {
+            mismatches.push(Mismatch::PredictedY { x, expected: slope * x + intercept, found: inputs.predicted_y });
+        }
+        let samples = inputs.sample_x_values.iter().zip(&inputs.sample_y_values);
+        for (index, (&x, &found)) in samples.enumerate() {
+            if found != slope * x + intercept {
+                mismatches.push(Mismatch::SampleY { index, x, expected: slope * x + intercept, found });
+            }
+        }
+    }
+
+    Explanation { mismatches }
+}
+
+/// Differences to the policy of the deployment, random coin and configuration commitment the
+/// proof verifies with, changing as few of them as possible; none if it verifies with no such
+/// combination
+fn deployment_mismatches(
+    envelope: &ProofEnvelope,
+    inputs: &LinearRegressionInputs,
+    policy: &VerificationPolicy,
+) -> Vec<Mismatch> {
+    let other_coin = match policy.random_coin {
+        CoinKind::Default => CoinKind::Keccak,
+        CoinKind::Keccak => CoinKind::Default,
+    };
+    // bit 0 changes the domain tag to the default one, bit 1 the coin, bit 2 the commitment
+    let default_tag = DomainTag::default();
+    let mut changes: Vec<u32> = (1..8).filter(|changes| changes & 1 == 0 || policy.domain != default_tag).collect();
+    changes.sort_by_key(|changes| changes.count_ones());
+    let verified = changes.into_iter().find_map(|changes| {
+        let candidate = VerificationPolicy {
+            domain: if changes & 1 != 0 { default_tag.clone() } else { policy.domain.clone() },
+            random_coin: if changes & 2 != 0 { other_coin } else { policy.random_coin },
+            commit_config: policy.commit_config != (changes & 4 != 0),
+            // the options are reported on their own
+            prover_config: None,
+            ..policy.clone()
+        };
+        verify_linear_proof(envelope, inputs.clone(), &candidate).is_ok().then_some(candidate)
+    });
+    let Some(found) = verified else {
+        return Vec::new();
+    };
+    let mut mismatches = Vec::new();
+    if found.domain != policy.domain {
+        mismatches.push(Mismatch::Domain { expected: policy.domain.clone(), found: found.domain });
+    }
+    if found.random_coin != policy.random_coin {
+        mismatches.push(Mismatch::Coin { expected: policy.random_coin, found: found.random_coin });
+    }
+    if found.commit_config != policy.commit_config {
+        mismatches.push(Mismatch::ConfigCommitment { expected: policy.commit_config, found: found.commit_config });
+    }
+    mismatches
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::to_hex;
+    use winterfell::Air;
+    use crate::config::ProverConfig;
+    use crate::constraints::ConstraintLabels;
+    use crate::regression::LinearRegressionAir;
+    use crate::session::{verify_linear, LinearClaimRequest, ProvingSession};
+
+    #[test]
+    fn test_points_at_tampered_inputs() {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3, 7, 10],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request).unwrap();
+        let policy = VerificationPolicy::default();
+        let witness = LinearWitness { slope: BaseElement::new(2), intercept: BaseElement::new(5) };
+        assert!(explain_linear(&envelope, &policy, Some(&witness)).is_empty());
+
+        // [x, y, sample x.., sample y..]: y and the third sample's y
+        let mut tampered = envelope.clone();
+        tampered.public_inputs[1] = BaseElement::new(22);
+        tampered.public_inputs[8] = BaseElement::new(20);
+        assert!(verify_linear(&tampered, &policy).is_err());
+        let explanation = explain_linear(&tampered, &policy, Some(&witness));
+        assert_eq!(
+            explanation.mismatches,
+            vec![
+                Mismatch::PredictedY {
+                    x: BaseElement::new(8),
+                    expected: BaseElement::new(21),
+                    found: BaseElement::new(22),
+                },
+                Mismatch::SampleY {
+                    index: 2,
+                    x: BaseElement::new(7),
+                    expected: BaseElement::new(19),
+                    found: BaseElement::new(20),
+                },
+            ]
+        );
+        let text = explanation.to_string();
+        assert!(text.contains("the model gives 21 (violates constraint linear_relation)"), "{text}");
+        // the label is the one the AIR reports for the relation
+        let inputs = LinearRegressionInputs::from_elements(&envelope.public_inputs).unwrap();
+        let (trace_info, options) = (envelope.proof.trace_info().clone(), envelope.proof.options().clone());
+        let air = LinearRegressionAir::new(trace_info, inputs, options);
+        assert_eq!(air.constraint_labels()[0], "linear_relation");
+
+        let mut stale = envelope.clone();
+        stale.constraint_fingerprint = ConstraintFingerprint([0u8; 32]);
+        stale.public_inputs.truncate(7);
+        let strict = VerificationPolicy { min_security_bits: 200, min_grinding_bits: 4, ..Default::default() };
+        let explanation = explain_linear(&stale, &strict, None);
+        assert!(matches!(
+            explanation.mismatches[..],
+            [
+                Mismatch::Security { required: 200, .. },
+                Mismatch::Grinding { required: 4, found: 0 },
+                Mismatch::Layout { num_inputs: 7 },
+            ]
+        ));
+
+        stale.public_inputs = envelope.public_inputs.clone();
+        let explanation = explain_linear(&stale, &policy, None);
+        assert!(matches!(explanation.mismatches[..], [Mismatch::AirVersion { .. }]));
+        assert!(explanation.to_string().contains("this verifier's AIR"));
+    }
+
+    #[test]
+    fn test_names_the_deployment_a_proof_verifies_for() {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let explain = |config: ProverConfig, policy: &VerificationPolicy| {
+            let envelope = ProvingSession::new(config, "ci").prove_linear(&request).unwrap();
+            assert!(verify_linear(&envelope, policy).is_err());
+            explain_linear(&envelope, policy, None).mismatches
+        };
+
+        let tenant = DomainTag::new("acme-lending/prod").unwrap();
+        let policy = VerificationPolicy { domain: tenant.clone(), ..VerificationPolicy::default() };
+        let found = DomainTag::default();
+        assert_eq!(explain(ProverConfig::default(), &policy), vec![Mismatch::Domain { expected: tenant, found }]);
+
+        let keccak = ProverConfig { random_coin: CoinKind::Keccak, ..ProverConfig::default() };
+        let (expected, found) = (CoinKind::Default, CoinKind::Keccak);
+        assert_eq!(explain(keccak, &VerificationPolicy::default()), vec![Mismatch::Coin { expected, found }]);
+
+        let legacy = ProverConfig { commit_config: false, ..ProverConfig::default() };
+        let mismatches = explain(legacy.clone(), &VerificationPolicy::default());
+        assert_eq!(mismatches, vec![Mismatch::ConfigCommitment { expected: true, found: false }]);
+        assert!(mismatches[0].to_string().contains("does not commit to its configuration"));
+
+        let stronger = ProverConfig { num_queries: 40, ..ProverConfig::default() };
+        let expecting = VerificationPolicy { prover_config: Some(stronger), ..VerificationPolicy::default() };
+        assert_eq!(explain(ProverConfig::default(), &expecting), vec![Mismatch::Options]);
+
+        // several differences at once
+        let keccak_legacy = ProverConfig { random_coin: CoinKind::Keccak, ..legacy };
+        assert!(matches!(
+            explain(keccak_legacy, &VerificationPolicy::default())[..],
+            [Mismatch::Coin { .. }, Mismatch::ConfigCommitment { expected: true, found: false }]
+        ));
+    }
+}
@@ -0,0 +1,213 @@
+//! Proving traces computed outside this crate.
+//!
+//! Pipelines that produce the witness themselves hand over the raw columns together with a
+//! [`TraceLayout`]. [`prove_external`] checks the columns against the layout and against the
+//! AIR's constraints and assertions before proving them as they are, in every build. The checks
+//! report the first failing row instead of tripping Winterfell's internal assertions, and keep
+//! invalid traces from reaching the prover in release builds, where Winterfell does not check.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    Air, Proof, ProofOptions, ProverError, TraceInfo, TraceTable,
+};
+
+use crate::prover::prove;
+use crate::trace::evaluate_transitions;
+
+/// Largest main trace width Winterfell accepts
+pub const MAX_TRACE_WIDTH: usize = TraceInfo::MAX_TRACE_WIDTH;
+
+/// Declared shape of an external trace
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceLayout {
+    pub width: usize,
+    pub length: usize,
+    /// Optional names used in error messages; empty or one per column
+    #[serde(default)]
+    pub column_names: Vec<String>,
+}
+
+impl TraceLayout {
+    pub fn new(width: usize, length: usize) -> Self {
+        Self { width, length, column_names: Vec::new() }
+    }
+
+    pub fn with_column_names(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.column_names = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn trace_info(&self) -> TraceInfo {
+        TraceInfo::new(self.width, self.length)
+    }
+
+    /// Name of `column` for error messages
+    pub fn column_name(&self, column: usize) -> String {
+        self.column_names.get(column).cloned().unwrap_or_else(|| format!("column {column}"))
+    }
+
+    fn validate(&self) -> Result<(), ExternalTraceError> {
+        let invalid = |reason: String| Err(ExternalTraceError::InvalidLayout(reason));
+        if self.width == 0 || self.width > MAX_TRACE_WIDTH {
+            return invalid(format!("width {} is not in 1..={MAX_TRACE_WIDTH}", self.width));
+        }
+        if self.length < TraceInfo::MIN_TRACE_LENGTH || !self.length.is_power_of_two() {
+            return invalid(format!("length {} is not a power of two of at least 8", self.length));
+        }
+        if !self.column_names.is_empty() && self.column_names.len() != self.width {
+            return invalid(format!("{} column names for width {}", self.column_names.len(), self.width));
+        }
+        Ok(())
+    }
+}
+
+/// Reason an external trace was rejected
+#[derive(Debug)]
+pub enum ExternalTraceError {
+    InvalidLayout(String),
+    ColumnCount { expected: usize, found: usize },
+    ColumnLength { column: String, expected: usize, found: usize },
+    Transition { constraint: usize, row: usize },
+    Assertion { column: String, step: usize },
+    Prover(ProverError),
+}
+
+impl fmt::Display for ExternalTraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLayout(reason) => write!(f, "invalid trace layout: {reason}"),
+            Self::ColumnCount { expected, found } => write!(f, "expected {expected} columns, found {found}"),
+            Self::ColumnLength { column, expected, found } => {
+                write!(f, "{column} has {found} rows, expected {expected}")
+            }
+            Self::Transition { constraint, row } => {
+                write!(f, "transition constraint {constraint} is not satisfied between rows {row} and {}", row + 1)
+            }
+            Self::Assertion { column, step } => write!(f, "assertion on {column} at step {step} is not satisfied"),
+            Self::Prover(err) => write!(f, "proof generation failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ExternalTraceError {}
+
+/// Builds a trace from column-major `columns` after checking them against `layout`
+pub fn import_trace(columns: Vec<Vec<BaseElement>>, layout: &TraceLayout) -> Result<TraceTable<BaseElement>, ExternalTraceError> {
+    layout.validate()?;
+    if columns.len() != layout.width {
+        return Err(ExternalTraceError::ColumnCount { expected: layout.width, found: columns.len() });
+    }
+    if let Some((column, values)) = columns.iter().enumerate().find(|(_, values)| values.len() != layout.length) {
+        return Err(ExternalTraceError::ColumnLength {
+            column: layout.column_name(column),
+            expected: layout.length,
+            found: values.len(),
+        });
+    }
+    Ok(TraceTable::init(columns))
+}
+
+/// Checks every transition constraint and assertion of `air` on `trace`
+pub fn check_trace<A: Air<BaseField = BaseElement>>(
+    air: &A,
+    trace: &TraceTable<BaseElement>,
+    layout: &TraceLayout,
+) -> Result<(), ExternalTraceError> {
+    for assertion in air.get_assertions() {
+        if assertion.column() >= trace.width() {
+            return Err(ExternalTraceError::InvalidLayout(format!(
+                "the AIR asserts on column {} of a {}-column trace",
+                assertion.column(),
+                trace.width()
+            )));
+        }
+        let mut failed = None;
+        assertion.apply(layout.length, |step, value| {
+            if failed.is_none() && trace.get(assertion.column(), step) != value {
+                failed = Some(step);
+            }
+        });
+        if let Some(step) = failed {
+            return Err(ExternalTraceError::Assertion { column: layout.column_name(assertion.column()), step });
+        }
+    }
+
+    for (row, evaluations) in evaluate_transitions(air, trace).iter().enumerate() {
+        if let Some(constraint) = evaluations.iter().position(|&value| value != BaseElement::ZERO) {
+            return Err(ExternalTraceError::Transition { constraint, row });
+        }
+    }
+    Ok(())
+}
+
+/// Proves an externally computed trace for AIR `A`, bypassing the crate's trace builders
+pub fn prove_external<A>(
+    columns: Vec<Vec<BaseElement>>,
+    layout: &TraceLayout,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+) -> Result<Proof, ExternalTraceError>
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Clone + Sync,
+{
+    let trace = import_trace(columns, layout)?;
+    let air = A::new(layout.trace_info(), pub_inputs.clone(), options.clone());
+    check_trace(&air, &trace, layout)?;
+    prove::<A>(trace, pub_inputs, options).map_err(ExternalTraceError::Prover)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::verify;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs, TRACE_WIDTH};
+
+    #[test]
+    fn test_prove_external_columns() {
+        let (slope, intercept) = (BaseElement::new(2), BaseElement::new(5));
+        let xs = vec![BaseElement::new(1), BaseElement::new(3)];
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(8),
+            predicted_y: BaseElement::new(21),
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+
+        // the columns an external pipeline would hand over
+        let built = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(8),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let columns: Vec<Vec<BaseElement>> =
+            (0..TRACE_WIDTH).map(|col| (0..8).map(|row| built.get(col, row)).collect()).collect();
+        let layout = TraceLayout::new(TRACE_WIDTH, 8)
+            .with_column_names(["slope", "intercept", "x", "y", "sample", "prediction", "padding"]);
+        let options = ProverConfig::default().to_proof_options();
+
+        let proof = prove_external::<LinearRegressionAir>(columns.clone(), &layout, inputs.clone(), options.clone())
+            .unwrap();
+        verify::<LinearRegressionAir>(proof, inputs.clone(), &default_acceptable_options()).unwrap();
+
+        let short = TraceLayout::new(TRACE_WIDTH, 16);
+        assert!(matches!(
+            import_trace(columns.clone(), &short),
+            Err(ExternalTraceError::ColumnLength { expected: 16, found: 8, .. })
+        ));
+        assert!(matches!(import_trace(columns.clone(), &TraceLayout::new(TRACE_WIDTH, 6)), Err(ExternalTraceError::InvalidLayout(_))));
+
+        let mut tampered = columns;
+        tampered[0][3] += BaseElement::ONE;
+        let err = prove_external::<LinearRegressionAir>(tampered, &layout, inputs, options).unwrap_err();
+        assert!(matches!(err, ExternalTraceError::Transition { row: 2, .. }), "{err}");
+    }
+}
@@ -0,0 +1,322 @@
+//! C ABI for embedding the framework in non-Rust hosts (feature `ffi`; `mobile` enables it for
+//! wallets).
+//!
+//! [`stark_verify`] checks an envelope and, with the `prover` feature, `stark_prove_linear`
+//! proves a linear claim the way `ProvingSession::prove_linear` does; both return one of the
+//! `STARK_*` status codes. `ffi` alone builds on the verifier, so wallets that only verify do
+//! not link the prover. The declarations are in `include/stark.h`. `scripts/build-ffi.sh` builds a shared and
+//! a static library for the host, `scripts/build-mobile.sh` the iOS and Android libraries.
+//!
+//! Ownership: pointers passed in are borrowed for the duration of the call and never retained.
+//! A [`StarkConfig`] handle belongs to the caller until it is passed to [`stark_config_free`];
+//! a [`StarkBuffer`] filled by the library belongs to the caller until it is passed to
+//! [`stark_buffer_free`]. Each must be freed exactly once. Panics never unwind into the host.
+
+use std::panic::{self, AssertUnwindSafe};
+
+#[cfg(feature = "prover")]
+use crate::abi::to_hex;
+use crate::config::{ProverConfig, VerificationPolicy};
+use crate::envelope::ProofEnvelope;
+use crate::session::verify_linear;
+#[cfg(feature = "prover")]
+use crate::session::{LinearClaimRequest, ProvingSession};
+
+/// The call succeeded; for [`stark_verify`] the envelope holds a valid proof
+pub const STARK_OK: i32 = 0;
+/// The envelope decodes but its proof does not verify
+pub const STARK_INVALID_PROOF: i32 = 1;
+/// The bytes are not a proof envelope
+pub const STARK_MALFORMED_ENVELOPE: i32 = 2;
+/// The claim could not be proven, e.g. it has no samples
+pub const STARK_PROVE_FAILED: i32 = 3;
+/// An argument is not valid UTF-8 or JSON, or does not describe a configuration
+pub const STARK_INVALID_ARGUMENT: i32 = 4;
+/// A required pointer is null
+pub const STARK_NULL_POINTER: i32 = -1;
+/// The call panicked; the panic is not propagated into the host
+pub const STARK_INTERNAL_ERROR: i32 = -2;
+
+/// Opaque prover and verifier configuration
+pub struct StarkConfig(ProverConfig);
+
+/// Bytes allocated by the library, released with [`stark_buffer_free`]
+#[repr(C)]
+pub struct StarkBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl StarkBuffer {
+    const EMPTY: Self = Self { data: std::ptr::null_mut(), len: 0 };
+
+    #[cfg(feature = "prover")]
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        Self { data: Box::into_raw(bytes.into_boxed_slice()).cast(), len }
+    }
+}
+
+/// Runs `f` without letting a panic cross the C boundary
+fn guarded(f: impl FnOnce() -> i32) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(STARK_INTERNAL_ERROR)
+}
+
+/// Borrows `len` bytes at `ptr`, treating a null pointer with zero length as empty.
+///
+/// # Safety
+///
+/// A non-null `ptr` must point to `len` readable bytes that stay valid for `'a`.
+unsafe fn borrow_bytes<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        // SAFETY: guaranteed by the caller
+        (false, _) => Some(unsafe { std::slice::from_raw_parts(ptr, len) }),
+    }
+}
+
+/// Returns a configuration with the default parameters
+#[unsafe(no_mangle)]
+pub extern "C" fn stark_config_new() -> *mut StarkConfig {
+    Box::into_raw(Box::new(StarkConfig(ProverConfig::default())))
+}
+
+/// Parses a JSON [`ProverConfig`] from the `len` bytes at `json_ptr` into `*out`.
+///
+/// # Safety
+///
+/// `json_ptr` must point to `len` readable bytes and `out` must be writable. On success the
+/// handle written to `*out` must be released with [`stark_config_free`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stark_config_from_json(json_ptr: *const u8, len: usize, out: *mut *mut StarkConfig) -> i32 {
+    if out.is_null() {
+        return STARK_NULL_POINTER;
+    }
+    // SAFETY: guaranteed by the caller
+    let Some(json) = (unsafe { borrow_bytes(json_ptr, len) }) else {
+        return STARK_NULL_POINTER;
+    };
+    guarded(|| match serde_json::from_slice::<ProverConfig>(json) {
+        Ok(config) if config.validate().is_ok() => {
+            // SAFETY: `out` is non-null and writable
+            unsafe { out.write(Box::into_raw(Box::new(StarkConfig(config)))) };
+            STARK_OK
+        }
+        _ => STARK_INVALID_ARGUMENT,
+    })
+}
+
+/// Releases a configuration; null is ignored.
+///
+/// # Safety
+///
+/// `config` must be null or a handle returned by this library that was not freed before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stark_config_free(config: *mut StarkConfig) {
+    if !config.is_null() {
+        // SAFETY: the handle came from `Box::into_raw` and is freed once
+        drop(unsafe { Box::from_raw(config) });
+    }
+}
+
+/// Releases bytes filled in by the library and resets `buffer` to empty; null is ignored.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a buffer that is empty or was filled by this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stark_buffer_free(buffer: *mut StarkBuffer) {
+    // SAFETY: guaranteed by the caller
+    let Some(buffer) = (unsafe { buffer.as_mut() }) else {
+        return;
+    };
+    if !buffer.data.is_null() {
+        // SAFETY: `data` and `len` came from `StarkBuffer::new`
+        drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) });
+    }
+    *buffer = StarkBuffer::EMPTY;
+}
+
+/// Proves `y = slope * x + intercept` over the `num_samples` sample x values at `sample_x` and
+/// the target `x`, writing the envelope bytes to `*out`.
+///
+/// `model` is the UTF-8 model name and `salt` the 32-byte salt of the model commitment.
+///
+/// # Safety
+///
+/// `config` must be a live handle, `model` must point to `model_len` readable bytes, `salt` to
+/// 32 readable bytes, `sample_x` to `num_samples` readable `u64`s and `out` must be writable.
+/// On success `*out` must be released with [`stark_buffer_free`]; otherwise it is left empty.
+#[cfg(feature = "prover")]
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn stark_prove_linear(
+    config: *const StarkConfig,
+    model: *const u8,
+    model_len: usize,
+    salt: *const u8,
+    slope: u64,
+    intercept: u64,
+    sample_x: *const u64,
+    num_samples: usize,
+    x: u64,
+    out: *mut StarkBuffer,
+) -> i32 {
+    // SAFETY: guaranteed by the caller
+    let (Some(config), Some(out)) = (unsafe { config.as_ref() }, unsafe { out.as_mut() }) else {
+        return STARK_NULL_POINTER;
+    };
+    *out = StarkBuffer::EMPTY;
+    if salt.is_null() || (sample_x.is_null() && num_samples > 0) {
+        return STARK_NULL_POINTER;
+    }
+    // SAFETY: guaranteed by the caller
+    let Some(model) = (unsafe { borrow_bytes(model, model_len) }) else {
+        return STARK_NULL_POINTER;
+    };
+    // SAFETY: `salt` is non-null and points to 32 bytes
+    let salt = unsafe { std::slice::from_raw_parts(salt, 32) };
+    let sample_x = match num_samples {
+        0 => &[][..],
+        // SAFETY: `sample_x` is non-null and points to `num_samples` values
+        _ => unsafe { std::slice::from_raw_parts(sample_x, num_samples) },
+    };
+
+    guarded(|| {
+        let Ok(model) = std::str::from_utf8(model) else {
+            return STARK_INVALID_ARGUMENT;
+        };
+        let request = LinearClaimRequest {
+            model: model.into(),
+            salt: to_hex(salt),
+            slope,
+            intercept,
+            sample_x: sample_x.to_vec(),
+            x,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        match ProvingSession::new(config.0.clone(), "ffi").prove_linear(&request) {
+            Ok(envelope) => {
+                *out = StarkBuffer::new(envelope.to_bytes());
+                STARK_OK
+            }
+            Err(_) => STARK_PROVE_FAILED,
+        }
+    })
+}
+
+/// Status code of verifying the envelope in `bytes` under `policy`
+fn verify_envelope(bytes: &[u8], policy: &VerificationPolicy) -> i32 {
+    let Ok(envelope) = ProofEnvelope::from_bytes(bytes) else {
+        return STARK_MALFORMED_ENVELOPE;
+    };
+    match verify_linear(&envelope, policy) {
+        Ok(()) => STARK_OK,
+        Err(_) => STARK_INVALID_PROOF,
+    }
+}
+
+/// Verifies the `len` envelope bytes at `envelope_ptr` under the default policy.
+///
+/// # Safety
+///
+/// `envelope_ptr` must be null or point to `len` readable bytes that stay valid for the call.
+/// The bytes are only read; the caller keeps ownership.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stark_verify(envelope_ptr: *const u8, len: usize) -> i32 {
+    if envelope_ptr.is_null() {
+        return STARK_NULL_POINTER;
+    }
+    // SAFETY: the caller guarantees `len` readable bytes at the non-null `envelope_ptr`
+    let bytes = unsafe { std::slice::from_raw_parts(envelope_ptr, len) };
+    guarded(|| verify_envelope(bytes, &VerificationPolicy::default()))
+}
+
+/// Verifies the `len` envelope bytes at `envelope_ptr` for the deployment of `config`.
+///
+/// # Safety
+///
+/// `config` must be a live handle and `envelope_ptr` must point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stark_verify_with_config(config: *const StarkConfig, envelope_ptr: *const u8, len: usize) -> i32 {
+    // SAFETY: guaranteed by the caller
+    let Some(config) = (unsafe { config.as_ref() }) else {
+        return STARK_NULL_POINTER;
+    };
+    if envelope_ptr.is_null() {
+        return STARK_NULL_POINTER;
+    }
+    // SAFETY: guaranteed by the caller
+    let bytes = unsafe { std::slice::from_raw_parts(envelope_ptr, len) };
+    guarded(|| {
+        let policy = VerificationPolicy {
+            domain: config.0.domain.clone(),
+            max_public_inputs: config.0.max_public_inputs,
+            commit_config: config.0.commit_config,
+            ..Default::default()
+        };
+        verify_envelope(bytes, &policy)
+    })
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_and_verify_through_handles() {
+        let json = br#"{"num_queries":32,"blowup_factor":8,"grinding_factor":0,"fri_folding_factor":8,"fri_remainder_max_degree":31,"domain":"payments"}"#;
+        let mut config = std::ptr::null_mut();
+        assert_eq!(unsafe { stark_config_from_json(json.as_ptr(), json.len(), &mut config) }, STARK_OK);
+
+        let (model, salt, samples) = ("demo", [7u8; 32], [1u64, 3, 7, 10]);
+        let mut buffer = StarkBuffer::EMPTY;
+        let status = unsafe {
+            stark_prove_linear(config, model.as_ptr(), model.len(), salt.as_ptr(), 2, 5, samples.as_ptr(), samples.len(), 8, &mut buffer)
+        };
+        assert_eq!(status, STARK_OK);
+        assert_eq!(unsafe { stark_verify_with_config(config, buffer.data, buffer.len) }, STARK_OK);
+        // proofs of another deployment do not verify under the default one
+        assert_eq!(unsafe { stark_verify(buffer.data, buffer.len) }, STARK_INVALID_PROOF);
+
+        unsafe { stark_buffer_free(&mut buffer) };
+        assert!(buffer.data.is_null());
+        let status = unsafe {
+            stark_prove_linear(config, model.as_ptr(), model.len(), salt.as_ptr(), 2, 5, std::ptr::null(), 0, 8, &mut buffer)
+        };
+        assert_eq!(status, STARK_PROVE_FAILED);
+        unsafe { stark_config_free(config) };
+
+        let mut config = std::ptr::null_mut();
+        assert_eq!(unsafe { stark_config_from_json(b"{".as_ptr(), 1, &mut config) }, STARK_INVALID_ARGUMENT);
+        assert!(config.is_null());
+    }
+
+    #[test]
+    fn test_status_codes() {
+        let request = LinearClaimRequest {
+            model: "wallet".into(),
+            salt: to_hex(&[9u8; 32]),
+            slope: 4,
+            intercept: 1,
+            sample_x: vec![2, 5, 6],
+            x: 3,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request).unwrap();
+        let mut bytes = envelope.to_bytes();
+        assert_eq!(unsafe { stark_verify(bytes.as_ptr(), bytes.len()) }, STARK_OK);
+
+        // the predicted y is the second public input; the inputs end where the proof length starts
+        let inputs = bytes.len() - envelope.proof.to_bytes().len() - 4 - 16 * envelope.public_inputs.len();
+        bytes[inputs + 16] ^= 1;
+        assert_eq!(unsafe { stark_verify(bytes.as_ptr(), bytes.len()) }, STARK_INVALID_PROOF);
+        assert_eq!(unsafe { stark_verify(bytes.as_ptr(), 10) }, STARK_MALFORMED_ENVELOPE);
+        assert_eq!(unsafe { stark_verify(std::ptr::null(), 0) }, STARK_NULL_POINTER);
+    }
+}
@@ -0,0 +1,484 @@
+//! Benchmarking the fields Winterfell offers and recommending the cheapest one for a claim.
+//!
+//! The AIRs of this crate are written over the 128-bit field, which reaches every security
+//! target without an extension. The 64-bit field has faster arithmetic but needs a quadratic
+//! or cubic extension for the DEEP and FRI challenges to stay above 64 bits, which grows the
+//! composition and FRI layers. Which one wins depends on the shape of the claim and on the
+//! machine, so [`recommend`] measures it: for every [`FieldChoice`] it proves a synthetic
+//! [`BenchmarkAir`] with the trace width, length and constraint degree of the claim's
+//! [`CircuitReport`], with enough queries to reach the target security, and picks the fastest
+//! choice whose conjectured security meets the target.
+//!
+//! The benchmark AIR only reproduces the cost of a claim, not its constraints: a
+//! recommendation for the 64-bit field says porting the claim's AIR pays off on this machine.
+
+use core::fmt;
+use std::time::Instant;
+
+use winterfell::{
+    crypto::{hashers::Blake3_256, DefaultRandomCoin, Hasher, MerkleTree},
+    math::{fields::f128, fields::f64, ExtensibleField, FieldElement, StarkField, ToElements},
+    matrix::ColMatrix,
+    Air, AirContext, Assertion, AuxRandElements, BatchingMethod, CompositionPoly, CompositionPolyTrace,
+    ConstraintCompositionCoefficients, DefaultConstraintCommitment, DefaultConstraintEvaluator, DefaultTraceLde,
+    EvaluationFrame, FieldExtension, PartitionOptions, Proof, ProofOptions, Prover, ProverError, StarkDomain,
+    TraceInfo, TracePolyTable, TraceTable, TransitionConstraintDegree,
+};
+
+use crate::analysis::CircuitReport;
+use crate::config::{ConfigError, ProverConfig, SecurityProfile};
+
+/// Winterfell proofs carry at most this many queries
+const MAX_QUERIES: usize = 255;
+
+/// Base field of a benchmarked configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaseField {
+    F64,
+    F128,
+}
+
+impl BaseField {
+    pub fn modulus_bits(&self) -> u32 {
+        match self {
+            BaseField::F64 => f64::BaseElement::MODULUS_BITS,
+            BaseField::F128 => f128::BaseElement::MODULUS_BITS,
+        }
+    }
+}
+
+impl fmt::Display for BaseField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BaseField::F64 => write!(f, "f64"),
+            BaseField::F128 => write!(f, "f128"),
+        }
+    }
+}
+
+/// A base field together with the extension its challenges are drawn from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldChoice {
+    pub field: BaseField,
+    pub extension: FieldExtension,
+}
+
+impl FieldChoice {
+    /// Every combination Winterfell can prove with; the 128-bit field has no cubic extension
+    pub const ALL: [FieldChoice; 5] = [
+        FieldChoice { field: BaseField::F64, extension: FieldExtension::None },
+        FieldChoice { field: BaseField::F64, extension: FieldExtension::Quadratic },
+        FieldChoice { field: BaseField::F64, extension: FieldExtension::Cubic },
+        FieldChoice { field: BaseField::F128, extension: FieldExtension::None },
+        FieldChoice { field: BaseField::F128, extension: FieldExtension::Quadratic },
+    ];
+
+    /// Highest conjectured security the field and the commitment hash allow, whatever the
+    /// number of queries
+    pub fn max_security_bits(&self) -> u32 {
+        let collision_resistance = <Blake3_256<f128::BaseElement> as Hasher>::COLLISION_RESISTANCE;
+        (self.field.modulus_bits() * self.extension.degree() - 1).min(collision_resistance)
+    }
+}
+
+impl fmt::Display for FieldChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.extension {
+            FieldExtension::None => write!(f, "{}", self.field),
+            FieldExtension::Quadratic => write!(f, "{} (quadratic extension)", self.field),
+            FieldExtension::Cubic => write!(f, "{} (cubic extension)", self.field),
+        }
+    }
+}
+
+/// Options of `profile` over `field`, with the smallest extension reaching its target; `None`
+/// if no extension does
+pub fn profile_options(profile: SecurityProfile, field: BaseField) -> Option<ProofOptions> {
+    let choice = FieldChoice::ALL
+        .into_iter()
+        .find(|choice| choice.field == field && choice.max_security_bits() >= profile.target_bits())?;
+    let config = profile.prover_config();
+    Some(ProofOptions::new(
+        config.num_queries,
+        config.blowup_factor,
+        config.grinding_factor,
+        choice.extension,
+        config.fri_folding_factor,
+        config.fri_remainder_max_degree,
+        BatchingMethod::Linear,
+        BatchingMethod::Linear,
+    ))
+}
+
+/// Base fields Winterfell can prove over with every extension
+pub trait BenchmarkField: StarkField + ExtensibleField<2> + ExtensibleField<3> + 'static {}
+
+impl<B: StarkField + ExtensibleField<2> + ExtensibleField<3> + 'static> BenchmarkField for B {}
+
+/// Trace shape and constraint degree a benchmark reproduces
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchmarkShape {
+    pub trace_width: usize,
+    pub trace_length: usize,
+    pub constraint_degree: usize,
+}
+
+impl BenchmarkShape {
+    /// Shape of the AIR instance described by `report`
+    pub fn of(report: &CircuitReport) -> Self {
+        Self {
+            trace_width: report.trace_width,
+            trace_length: report.trace_length,
+            constraint_degree: report.max_degree().max(1),
+        }
+    }
+}
+
+/// Public inputs of [`BenchmarkAir`]: the value every column starts from and the degree of
+/// the transition constraints
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchmarkInputs<B: StarkField> {
+    pub seed: B,
+    pub degree: usize,
+}
+
+impl<B: StarkField> ToElements<B> for BenchmarkInputs<B> {
+    fn to_elements(&self) -> Vec<B> {
+        vec![self.seed, B::from(self.degree as u32)]
+    }
+}
+
+/// Synthetic AIR over any field: column `i` starts at `seed + i` and evolves as
+/// `next[i] = current[i]^degree + current[i + 1]`, wrapping around at the last column
+pub struct BenchmarkAir<B: BenchmarkField> {
+    context: AirContext<B>,
+    degree: usize,
+    seed: B,
+}
+
+impl<B: BenchmarkField> Air for BenchmarkAir<B> {
+    type BaseField = B;
+    type PublicInputs = BenchmarkInputs<B>;
+
+    fn new(trace_info: TraceInfo, pub_inputs: BenchmarkInputs<B>, options: ProofOptions) -> Self {
+        let (width, degree) = (trace_info.width(), pub_inputs.degree);
+        assert!(degree > 0, "Benchmark constraints need a positive degree");
+        let degrees = vec![TransitionConstraintDegree::new(degree); width];
+        Self { context: AirContext::new(trace_info, degrees, width, options), degree, seed: pub_inputs.seed }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let (current, next) = (frame.current(), frame.next());
+        let width = current.len();
+        for (i, r) in result.iter_mut().enumerate() {
+            *r = next[i] - current[i].exp((self.degree as u32).into()) - current[(i + 1) % width];
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        (0..self.context.trace_info().width())
+            .map(|i| Assertion::single(i, 0, self.seed + B::from(i as u32)))
+            .collect()
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Prover for [`BenchmarkAir`] with the hash function and commitments of [`crate::prover`]
+struct BenchmarkProver<B: StarkField> {
+    options: ProofOptions,
+    inputs: BenchmarkInputs<B>,
+}
+
+impl<B: BenchmarkField> Prover for BenchmarkProver<B> {
+    type BaseField = B;
+    type Air = BenchmarkAir<B>;
+    type Trace = TraceTable<B>;
+    type HashFn = Blake3_256<B>;
+    type VC = MerkleTree<Self::HashFn>;
+    type RandomCoin = DefaultRandomCoin<Self::HashFn>;
+    type TraceLde<E: FieldElement<BaseField = B>> = DefaultTraceLde<E, Self::HashFn, Self::VC>;
+    type ConstraintCommitment<E: FieldElement<BaseField = B>> = DefaultConstraintCommitment<E, Self::HashFn, Self::VC>;
+    type ConstraintEvaluator<'a, E: FieldElement<BaseField = B>> = DefaultConstraintEvaluator<'a, Self::Air, E>;
+
+    fn get_pub_inputs(&self, _trace: &Self::Trace) -> BenchmarkInputs<B> {
+        self.inputs
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn new_trace_lde<E: FieldElement<BaseField = B>>(
+        &self,
+        trace_info: &TraceInfo,
+        main_trace: &ColMatrix<B>,
+        domain: &StarkDomain<B>,
+        partition_option: PartitionOptions,
+    ) -> (Self::TraceLde<E>, TracePolyTable<E>) {
+        DefaultTraceLde::new(trace_info, main_trace, domain, partition_option)
+    }
+
+    fn build_constraint_commitment<E: FieldElement<BaseField = B>>(
+        &self,
+        composition_poly_trace: CompositionPolyTrace<E>,
+        num_constraint_composition_columns: usize,
+        domain: &StarkDomain<B>,
+        partition_options: PartitionOptions,
+    ) -> (Self::ConstraintCommitment<E>, CompositionPoly<E>) {
+        DefaultConstraintCommitment::new(
+            composition_poly_trace,
+            num_constraint_composition_columns,
+            domain,
+            partition_options,
+        )
+    }
+
+    fn new_evaluator<'a, E: FieldElement<BaseField = B>>(
+        &self,
+        air: &'a Self::Air,
+        aux_rand_elements: Option<AuxRandElements<E>>,
+        composition_coefficients: ConstraintCompositionCoefficients<E>,
+    ) -> Self::ConstraintEvaluator<'a, E> {
+        DefaultConstraintEvaluator::new(air, aux_rand_elements, composition_coefficients)
+    }
+}
+
+/// Build the execution trace of [`BenchmarkAir`] for `shape`
+pub fn build_benchmark_trace<B: StarkField>(shape: &BenchmarkShape, inputs: &BenchmarkInputs<B>) -> TraceTable<B> {
+    let width = shape.trace_width;
+    let mut trace = TraceTable::new(width, shape.trace_length);
+    trace.fill(
+        |state| {
+            for (i, value) in state.iter_mut().enumerate() {
+                *value = inputs.seed + B::from(i as u32);
+            }
+        },
+        |_, state| {
+            let current = state.to_vec();
+            for (i, value) in state.iter_mut().enumerate() {
+                *value = current[i].exp((inputs.degree as u32).into()) + current[(i + 1) % width];
+            }
+        },
+    );
+    trace
+}
+
+/// Measured cost of proving a claim's shape with one [`FieldChoice`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchmarkResult {
+    pub choice: FieldChoice,
+    pub num_queries: usize,
+    /// Fastest wall time of [`BENCHMARK_RUNS`] proofs
+    pub seconds: f64,
+    pub proof_bytes: usize,
+    pub security_bits: u32,
+}
+
+/// Proofs timed per choice; the fastest one counts, which filters out scheduling noise
+pub const BENCHMARK_RUNS: usize = 2;
+
+fn benchmark<B: BenchmarkField>(
+    shape: &BenchmarkShape,
+    choice: FieldChoice,
+    options: ProofOptions,
+) -> Result<BenchmarkResult, ProverError> {
+    let inputs = BenchmarkInputs { seed: B::from(3u32), degree: shape.constraint_degree };
+    let prover = BenchmarkProver { options: options.clone(), inputs };
+    let mut seconds = f64::INFINITY;
+    let mut proof = None;
+    for _ in 0..BENCHMARK_RUNS {
+        let trace = build_benchmark_trace(shape, &inputs);
+        let started = Instant::now();
+        proof = Some(prover.prove(trace)?);
+        seconds = seconds.min(started.elapsed().as_secs_f64());
+    }
+    let proof: Proof = proof.expect("At least one benchmark run");
+    Ok(BenchmarkResult {
+        choice,
+        num_queries: options.num_queries(),
+        seconds,
+        proof_bytes: proof.to_bytes().len(),
+        security_bits: proof.conjectured_security::<Blake3_256<B>>().bits(),
+    })
+}
+
+/// Reason [`recommend`] could not benchmark a claim
+#[derive(Debug)]
+pub enum RecommendError {
+    Config(ConfigError),
+    Prover(ProverError),
+}
+
+impl fmt::Display for RecommendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecommendError::Config(err) => write!(f, "{err}"),
+            RecommendError::Prover(err) => write!(f, "benchmark proof failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RecommendError {}
+
+/// Benchmarks of every [`FieldChoice`] for one claim and security target
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recommendation {
+    pub target_bits: u32,
+    /// Results of the choices that were benchmarked, in the order of [`FieldChoice::ALL`]
+    pub results: Vec<BenchmarkResult>,
+    /// Choices that cannot reach the target with any number of queries
+    pub unreachable: Vec<FieldChoice>,
+}
+
+impl Recommendation {
+    /// The fastest benchmarked choice meeting the target
+    pub fn best(&self) -> Option<&BenchmarkResult> {
+        self.results
+            .iter()
+            .filter(|result| result.security_bits >= self.target_bits)
+            .min_by(|a, b| a.seconds.total_cmp(&b.seconds))
+    }
+}
+
+impl fmt::Display for Recommendation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            writeln!(
+                f,
+                "{:<26} {:>3} queries  {:>8.3}s  {:>7} bytes  {:>3} bits",
+                result.choice.to_string(),
+                result.num_queries,
+                result.seconds,
+                result.proof_bytes,
+                result.security_bits
+            )?;
+        }
+        for choice in &self.unreachable {
+            writeln!(f, "{:<26} cannot reach {} bits", choice.to_string(), self.target_bits)?;
+        }
+        match self.best() {
+            Some(best) => writeln!(f, "recommended: {} with {} queries", best.choice, best.num_queries),
+            None => writeln!(f, "no configuration reaches {} bits", self.target_bits),
+        }
+    }
+}
+
+/// Proves `shape` with every [`FieldChoice`] that can reach `target_bits` of conjectured
+/// security, using the blowup, grinding and FRI parameters of `config` and the fewest queries
+/// reaching the target
+pub fn recommend(
+    shape: &BenchmarkShape,
+    config: &ProverConfig,
+    target_bits: u32,
+) -> Result<Recommendation, RecommendError> {
+    config.validate_for_trace(shape.trace_length).map_err(RecommendError::Config)?;
+    let bits_per_query = config.blowup_factor.ilog2() as usize;
+    let num_queries = (target_bits as usize + 1).div_ceil(bits_per_query).max(1);
+
+    let mut recommendation = Recommendation { target_bits, results: Vec::new(), unreachable: Vec::new() };
+    for choice in FieldChoice::ALL {
+        if choice.max_security_bits() < target_bits || num_queries > MAX_QUERIES {
+            recommendation.unreachable.push(choice);
+            continue;
+        }
+        let options = ProofOptions::new(
+            num_queries,
+            config.blowup_factor,
+            config.grinding_factor,
+            choice.extension,
+            config.fri_folding_factor,
+            config.fri_remainder_max_degree,
+            BatchingMethod::Linear,
+            BatchingMethod::Linear,
+        );
+        let result = match choice.field {
+            BaseField::F64 => benchmark::<f64::BaseElement>(shape, choice, options),
+            BaseField::F128 => benchmark::<f128::BaseElement>(shape, choice, options),
+        };
+        recommendation.results.push(result.map_err(RecommendError::Prover)?);
+    }
+    Ok(recommendation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winterfell::AcceptableOptions;
+
+    use crate::config::conjectured_security_bits;
+
+    #[test]
+    fn test_benchmark_air_proves_over_both_fields() {
+        let shape = BenchmarkShape { trace_width: 3, trace_length: 32, constraint_degree: 3 };
+        let inputs = BenchmarkInputs { seed: f64::BaseElement::new(5), degree: 3 };
+        let options = ProverConfig::default().to_proof_options();
+        let prover = BenchmarkProver { options, inputs };
+        let proof = prover.prove(build_benchmark_trace(&shape, &inputs)).unwrap();
+        type Hash = Blake3_256<f64::BaseElement>;
+        let verify = |inputs| {
+            winterfell::verify::<BenchmarkAir<f64::BaseElement>, Hash, DefaultRandomCoin<Hash>, MerkleTree<Hash>>(
+                proof.clone(),
+                inputs,
+                &AcceptableOptions::MinConjecturedSecurity(0),
+            )
+        };
+        verify(inputs).unwrap();
+        assert!(verify(BenchmarkInputs { seed: f64::BaseElement::new(6), ..inputs }).is_err());
+
+        let inputs = BenchmarkInputs { seed: f128::BaseElement::new(5), degree: 3 };
+        crate::air_test!(BenchmarkAir<f128::BaseElement>, inputs)
+            .with_trace(&build_benchmark_trace(&shape, &inputs))
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let recommendation = recommend(&shape, &ProverConfig::default(), 95).unwrap();
+        let unreachable = FieldChoice { field: BaseField::F64, extension: FieldExtension::None };
+        assert_eq!(recommendation.unreachable, vec![unreachable]);
+        assert_eq!(recommendation.results.len(), 4);
+        assert!(recommendation.results.iter().all(|result| result.security_bits >= 95 && result.num_queries == 32));
+        assert!(recommendation.best().is_some());
+        assert!(recommendation.to_string().contains("recommended: "));
+
+        // beyond the collision resistance of the commitment hash
+        let recommendation = recommend(&shape, &ProverConfig::default(), 129).unwrap();
+        assert_eq!(recommendation.best(), None);
+        assert!(recommendation.results.is_empty());
+    }
+
+    #[test]
+    fn test_security_profiles_meet_targets() {
+        let shape = BenchmarkShape { trace_width: 2, trace_length: 16, constraint_degree: 2 };
+        for profile in SecurityProfile::ALL {
+            let target = profile.target_bits();
+            let config = profile.prover_config();
+            assert_eq!(profile_options(profile, BaseField::F128), Some(config.to_proof_options()), "{profile}");
+            for field in [BaseField::F64, BaseField::F128] {
+                let options = profile_options(profile, field).unwrap();
+                let choice = FieldChoice { field, extension: options.field_extension() };
+                // Winterfell's estimate of the proof, which verifiers check against the target
+                let result = match field {
+                    BaseField::F64 => benchmark::<f64::BaseElement>(&shape, choice, options.clone()),
+                    BaseField::F128 => benchmark::<f128::BaseElement>(&shape, choice, options.clone()),
+                };
+                let bits = result.unwrap().security_bits;
+                assert!(bits >= target, "{profile} over {choice}: {bits} bits");
+                if field == BaseField::F128 {
+                    assert_eq!(conjectured_security_bits(&options), bits);
+                }
+            }
+            let fewer = ProverConfig { num_queries: config.num_queries - 1, ..config };
+            assert!(conjectured_security_bits(&fewer.to_proof_options()) < target, "{profile}");
+        }
+        assert_eq!("100".parse(), Ok(SecurityProfile::Bits100));
+        assert!(SecurityProfile::Bits128.prover_config().quadratic_extension);
+    }
+}
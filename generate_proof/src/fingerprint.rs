@@ -0,0 +1,178 @@
+//! Deterministic fingerprint of an AIR instance's constraint system.
+//!
+//! The fingerprint hashes the AIR's layout hash (which covers its id and column names), the
+//! trace dimensions, the evaluation degree of every transition constraint, the shape of every
+//! assertion (column, first step, stride, number of values) and the constraint evaluations on
+//! a few fixed pseudo-random frames. The probe evaluations make
+//! the fingerprint sensitive to any change of the constraint polynomials themselves, not only
+//! to changes of their declared shape. Assertion values are public inputs and are left out.
+
+use core::fmt;
+
+use crate::layout::LayoutDescriptor;
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField},
+    Air, EvaluationFrame, ProofOptions, TraceInfo,
+};
+
+const FINGERPRINT_DOMAIN: &[u8] = b"stark-framework/constraint-fingerprint/v1";
+const NUM_PROBES: usize = 2;
+
+/// Hash committing to the constraint system of an instantiated AIR
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConstraintFingerprint(pub [u8; 32]);
+
+impl fmt::Display for ConstraintFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", crate::abi::to_hex(&self.0))
+    }
+}
+
+impl ConstraintFingerprint {
+    /// Fingerprint of `air`, whose trace is described by `layout`
+    pub fn of<A: Air<BaseField = BaseElement>>(layout: &LayoutDescriptor, air: &A) -> Self {
+        let context = air.context();
+        let trace_info = context.trace_info();
+        let num_constraints = context.num_main_transition_constraints();
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(FINGERPRINT_DOMAIN);
+        hasher.update(&layout.hash().0);
+        update_usize(&mut hasher, trace_info.main_trace_width());
+        update_usize(&mut hasher, trace_info.length());
+
+        let constraints = air.get_transition_constraints(&vec![BaseElement::ZERO; context.num_transition_constraints()]);
+        update_usize(&mut hasher, num_constraints);
+        for degree in constraints.main_constraint_degrees() {
+            update_usize(&mut hasher, degree.get_evaluation_degree(trace_info.length()));
+        }
+
+        let mut assertions = air.get_assertions();
+        assertions.sort_by_key(|a| (a.column(), a.first_step(), a.stride()));
+        update_usize(&mut hasher, assertions.len());
+        for assertion in &assertions {
+            for value in [assertion.column(), assertion.first_step(), assertion.stride(), assertion.values().len()] {
+                update_usize(&mut hasher, value);
+            }
+        }
+
+        let mut probe = Probe::new(&layout.air_id, trace_info.main_trace_width());
+        let num_periodic = air.get_periodic_column_values().len();
+        for _ in 0..NUM_PROBES {
+            let width = trace_info.main_trace_width();
+            let frame = EvaluationFrame::from_rows(probe.elements(width), probe.elements(width));
+            let periodic = probe.elements(num_periodic);
+            let mut evaluations = vec![BaseElement::ZERO; num_constraints];
+            air.evaluate_transition(&frame, &periodic, &mut evaluations);
+            for value in evaluations {
+                hasher.update(&value.as_int().to_le_bytes());
+            }
+        }
+
+        Self(*hasher.finalize().as_bytes())
+    }
+
+    /// Instantiates `A` for a proof of `trace_info` over `pub_inputs` and fingerprints it
+    pub fn for_instance<A: Air<BaseField = BaseElement>>(
+        layout: &LayoutDescriptor,
+        trace_info: TraceInfo,
+        pub_inputs: A::PublicInputs,
+        options: ProofOptions,
+    ) -> Self {
+        Self::of(layout, &A::new(trace_info, pub_inputs, options))
+    }
+}
+
+fn update_usize(hasher: &mut blake3::Hasher, value: usize) {
+    hasher.update(&(value as u64).to_le_bytes());
+}
+
+fn update_bytes(hasher: &mut blake3::Hasher, bytes: &[u8]) {
+    update_usize(hasher, bytes.len());
+    hasher.update(bytes);
+}
+
+/// Deterministic stream of field elements seeded by the AIR id and trace width
+struct Probe {
+    reader: blake3::OutputReader,
+}
+
+impl Probe {
+    fn new(air_id: &str, width: usize) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(FINGERPRINT_DOMAIN);
+        hasher.update(b"/probe");
+        update_bytes(&mut hasher, air_id.as_bytes());
+        update_usize(&mut hasher, width);
+        Self { reader: hasher.finalize_xof() }
+    }
+
+    fn elements(&mut self, count: usize) -> Vec<BaseElement> {
+        let mut elements = Vec::with_capacity(count);
+        while elements.len() < count {
+            let mut bytes = [0u8; 16];
+            self.reader.fill(&mut bytes);
+            let value = u128::from_le_bytes(bytes);
+            if value < BaseElement::MODULUS {
+                elements.push(BaseElement::new(value));
+            }
+        }
+        elements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::regression::{self, LinearRegressionAir, LinearRegressionInputs, TRACE_WIDTH};
+    use crate::standardize::{StandardizationParams, StandardizedRegressionAir, StandardizedRegressionInputs};
+
+    fn linear_inputs(predicted_y: u64) -> LinearRegressionInputs {
+        LinearRegressionInputs {
+            x_value: BaseElement::new(8),
+            predicted_y: BaseElement::from(predicted_y),
+            sample_x_values: vec![BaseElement::new(1)],
+            sample_y_values: vec![BaseElement::new(7)],
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_constraint_system() {
+        let options = ProverConfig::default().to_proof_options();
+        let instance = |layout: &LayoutDescriptor, inputs| {
+            let trace_info = TraceInfo::new(TRACE_WIDTH, 8);
+            ConstraintFingerprint::for_instance::<LinearRegressionAir>(layout, trace_info, inputs, options.clone())
+        };
+        let fingerprint = |inputs| instance(&regression::layout(), inputs);
+
+        // assertion values are public inputs, not part of the circuit
+        assert_eq!(fingerprint(linear_inputs(21)), fingerprint(linear_inputs(22)));
+
+        // so is the layout: another AIR id or column naming is another constraint system
+        let mut renamed = regression::layout();
+        renamed.air_id = "linear-regression/v1".into();
+        assert_ne!(fingerprint(linear_inputs(21)), instance(&renamed, linear_inputs(21)));
+        let regrouped = LayoutDescriptor::new(regression::AIR_ID).with_columns("columns", 0..TRACE_WIDTH);
+        assert_ne!(fingerprint(linear_inputs(21)), instance(&regrouped, linear_inputs(21)));
+
+        // constraints that depend on public parameters change with them
+        let standardized = |std_dev| StandardizedRegressionInputs {
+            params: StandardizationParams::new(0, std_dev, 4),
+            x_value: BaseElement::ONE,
+            predicted_y: BaseElement::ONE,
+            sample_x_values: vec![BaseElement::ONE],
+            sample_y_values: vec![BaseElement::ONE],
+        };
+        let layout = LayoutDescriptor::new("standardized").with_columns("columns", 0..11);
+        let standardized_fingerprint = |std_dev| {
+            ConstraintFingerprint::for_instance::<StandardizedRegressionAir>(
+                &layout,
+                TraceInfo::new(11, 8),
+                standardized(std_dev),
+                options.clone(),
+            )
+        };
+        assert_ne!(standardized_fingerprint(5), standardized_fingerprint(6));
+    }
+}
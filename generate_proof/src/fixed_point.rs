@@ -0,0 +1,156 @@
+use winterfell::math::{fields::f128::BaseElement, FieldElement, StarkField};
+
+/// Default number of fractional bits used when quantizing real-valued features
+pub const DEFAULT_FRACTIONAL_BITS: u32 = 16;
+
+/// Converts a real value into a signed fixed-point integer with `frac_bits` fractional bits
+pub fn quantize(value: f64, frac_bits: u32) -> i128 {
+    (value * (1u128 << frac_bits) as f64).round() as i128
+}
+
+/// Converts a signed fixed-point integer back into a real value
+pub fn dequantize(raw: i128, frac_bits: u32) -> f64 {
+    raw as f64 / (1u128 << frac_bits) as f64
+}
+
+/// Embeds a signed integer into the field; negative values map to `p - |raw|`
+pub fn to_field(raw: i128) -> BaseElement {
+    if raw < 0 {
+        BaseElement::ZERO - BaseElement::new(raw.unsigned_abs())
+    } else {
+        BaseElement::new(raw as u128)
+    }
+}
+
+/// Reads a field element back as a signed integer; elements above `p / 2` are negative
+pub fn from_field(value: BaseElement) -> i128 {
+    let raw = value.as_int();
+    if raw > BaseElement::MODULUS / 2 {
+        -((BaseElement::MODULUS - raw) as i128)
+    } else {
+        raw as i128
+    }
+}
+
+/// Floor division of signed fixed-point integers, returning `(quotient, remainder)`
+/// such that `numerator = quotient * divisor + remainder` and `0 <= remainder < divisor`
+pub fn div_floor(numerator: i128, divisor: i128) -> (i128, i128) {
+    assert!(divisor > 0, "Fixed-point divisor must be positive");
+    (numerator.div_euclid(divisor), numerator.rem_euclid(divisor))
+}
+
+/// Per-column affine quantization: `q = round(v * 2^frac_bits) + zero_point`.
+///
+/// A zero point of 0 gives symmetric quantization around zero; a non-zero zero point shifts
+/// an asymmetric range (e.g. all-positive prices) so it fits into fewer bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scale {
+    pub frac_bits: u32,
+    pub zero_point: i128,
+}
+
+impl Scale {
+    pub fn symmetric(frac_bits: u32) -> Self {
+        Self::asymmetric(frac_bits, 0)
+    }
+
+    pub fn asymmetric(frac_bits: u32, zero_point: i128) -> Self {
+        assert!(frac_bits < 64, "Scales support at most 63 fractional bits");
+        Self { frac_bits, zero_point }
+    }
+
+    /// Finest scale mapping `min..=max` into `0..2^bits`, with `min` at quantized value 0
+    pub fn fit_range(min: f64, max: f64, bits: u32) -> Self {
+        assert!(min.is_finite() && max.is_finite() && min <= max, "Invalid quantization range");
+        assert!(bits > 0 && bits < 64, "Quantized range must use 1..64 bits");
+        let limit = ((1u128 << bits) - 1) as f64;
+        let frac_bits = (0..64u32)
+            .take_while(|&f| {
+                let scale = (1u128 << f) as f64;
+                ((max * scale).round() - (min * scale).round()) <= limit
+            })
+            .last()
+            .expect("Range fits into the requested bits at scale 1");
+        Self::asymmetric(frac_bits, -quantize(min, frac_bits))
+    }
+
+    pub fn quantize(&self, value: f64) -> i128 {
+        quantize(value, self.frac_bits) + self.zero_point
+    }
+
+    pub fn dequantize(&self, raw: i128) -> f64 {
+        dequantize(self.centered(raw), self.frac_bits)
+    }
+
+    /// The fixed-point value `raw` encodes, with the zero point removed
+    pub fn centered(&self, raw: i128) -> i128 {
+        raw - self.zero_point
+    }
+
+    /// Field encoding `[frac_bits, zero_point]` used in public inputs
+    pub fn to_elements(&self) -> [BaseElement; 2] {
+        [BaseElement::from(self.frac_bits), to_field(self.zero_point)]
+    }
+}
+
+/// Re-expresses `raw` from scale `from` in scale `to`, which must be at least as fine
+pub fn align(raw: i128, from: &Scale, to: &Scale) -> i128 {
+    assert!(to.frac_bits >= from.frac_bits, "Aligning to a coarser scale loses precision");
+    (from.centered(raw) << (to.frac_bits - from.frac_bits)) + to.zero_point
+}
+
+/// Number of fractional bits dropped when the product of `a` and `b` is stored in `out`
+pub fn product_shift(a: &Scale, b: &Scale, out: &Scale) -> u32 {
+    let product_bits = a.frac_bits + b.frac_bits;
+    assert!(out.frac_bits <= product_bits, "Product output scale is finer than its operands");
+    product_bits - out.frac_bits
+}
+
+/// Multiplies `a_raw` (scale `a`) by `b_raw` (scale `b`) into scale `out`, rounding down.
+///
+/// Returns `(out_raw, remainder)` with
+/// `(a_raw - a.zp) * (b_raw - b.zp) = (out_raw - out.zp) * 2^shift + remainder` and
+/// `0 <= remainder < 2^shift`, where `shift` is [`product_shift`].
+pub fn rescale_product(a_raw: i128, a: &Scale, b_raw: i128, b: &Scale, out: &Scale) -> (i128, i128) {
+    let shift = product_shift(a, b, out);
+    let (quotient, remainder) = div_floor(a.centered(a_raw) * b.centered(b_raw), 1 << shift);
+    (quotient + out.zero_point, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_round_trip() {
+        for raw in [-1_000_000i128, -1, 0, 1, 42, 1 << 60] {
+            assert_eq!(from_field(to_field(raw)), raw);
+        }
+
+        let raw = quantize(-2.75, DEFAULT_FRACTIONAL_BITS);
+        assert_eq!(raw, -180224);
+        assert_eq!(dequantize(raw, DEFAULT_FRACTIONAL_BITS), -2.75);
+
+        assert_eq!(div_floor(-7, 2), (-4, 1));
+        assert_eq!(div_floor(7, 2), (3, 1));
+    }
+
+    #[test]
+    fn test_mixed_scales() {
+        let price = Scale::fit_range(100_000.0, 900_000.0, 24);
+        assert_eq!(price.frac_bits, 4);
+        assert_eq!(price.quantize(100_000.0), 0);
+        assert!(price.quantize(900_000.0) < 1 << 24);
+        assert_eq!(price.dequantize(price.quantize(250_000.5)), 250_000.5);
+
+        let rate = Scale::symmetric(20);
+        let out = Scale::asymmetric(8, 3);
+        let (raw, remainder) = rescale_product(price.quantize(200_000.0), &price, rate.quantize(0.5), &rate, &out);
+        assert_eq!(out.dequantize(raw), 100_000.0);
+        assert_eq!(remainder, 0);
+        assert_eq!(product_shift(&price, &rate, &out), 16);
+
+        let fine = Scale::symmetric(8);
+        assert_eq!(align(Scale::symmetric(2).quantize(1.25), &Scale::symmetric(2), &fine), fine.quantize(1.25));
+    }
+}
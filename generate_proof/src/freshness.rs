@@ -0,0 +1,366 @@
+//! Data freshness claims over a committed, private set of timestamps.
+//!
+//! [`FreshnessAir`] proves that the timestamps hashed into a public Rescue digest (see
+//! [`timestamps_digest`]) are strictly increasing and that the newest one lies within
+//! `max_age` of a public reference time. A consumer that knows the digest of the samples a
+//! model was evaluated on can therefore require the data to be fresh without seeing it.
+//!
+//! The trace has one [`CYCLE_LENGTH`]-row cycle per timestamp plus one that finishes the
+//! sponge. An `active` column is 1 on the cycles holding timestamps and 0 afterwards; its
+//! falling edge marks the row of the newest timestamp, where the age check applies.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+    TransitionConstraintDegree, 
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{IntegerColumn, RangeCheck, StrictlyIncreasing};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`FreshnessAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "freshness/v1";
+
+/// `max_age` and the age of the newest timestamp must fit into this many bits
+pub const AGE_BITS: usize = 32;
+
+/// Sponge tag of [`timestamps_digest`]; `crate::signature` uses 1 and 2
+const TIMESTAMPS_TAG: u64 = 3;
+const TIMESTAMP_BITS: usize = 64;
+const DIGIT_BITS: usize = 2;
+
+// Trace layout: sponge state, active flag, timestamp, then the digits of the timestamp, of the
+// gap to the next timestamp, of the newest timestamp's age and of `max_age - age`
+const SPONGE: usize = 0;
+const ACTIVE: usize = SPONGE + STATE_WIDTH;
+const T: usize = ACTIVE + 1;
+const DIGITS_START: usize = T + 1;
+
+struct Layout {
+    sponge: RescueGadget,
+    timestamp: IntegerColumn,
+    increasing: StrictlyIncreasing,
+    age: RangeCheck,
+    slack: RangeCheck,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let timestamp = IntegerColumn::new(T, DIGITS_START, TIMESTAMP_BITS, DIGIT_BITS);
+        let increasing = StrictlyIncreasing::new(T, timestamp.next_column(), TIMESTAMP_BITS);
+        let age = RangeCheck::with_digit_bits(increasing.next_column(), AGE_BITS, DIGIT_BITS);
+        let slack = RangeCheck::with_digit_bits(age.next_column(), AGE_BITS, DIGIT_BITS);
+        Self { sponge: RescueGadget::new(SPONGE), timestamp, increasing, age, slack }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.slack.next_column()
+    }
+}
+
+/// Number of trace columns
+pub fn trace_width() -> usize {
+    Layout::new().trace_width()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    let layout = Layout::new();
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..ACTIVE)
+        .with_column("active", ACTIVE)
+        .with_column("timestamp", T)
+        .with_columns("timestamp_digits", DIGITS_START..layout.timestamp.next_column())
+        .with_columns("gap_digits", layout.timestamp.next_column()..layout.increasing.next_column())
+        .with_columns("age_digits", layout.increasing.next_column()..layout.age.next_column())
+        .with_columns("slack_digits", layout.age.next_column()..layout.slack.next_column())
+}
+
+/// Trace length for `num_samples` timestamps
+pub fn trace_length(num_samples: usize) -> usize {
+    ((num_samples + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Digest a data provider publishes for its sample timestamps
+pub fn timestamps_digest(timestamps: &[u64]) -> BaseElement {
+    let elements: Vec<BaseElement> = timestamps.iter().map(|&t| BaseElement::from(t)).collect();
+    rescue::hash_elements(&elements, TIMESTAMPS_TAG)
+}
+
+/// Reason a set of timestamps does not satisfy a freshness claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FreshnessError {
+    Empty,
+    /// `timestamps[index]` is not greater than its predecessor
+    NotIncreasing { index: usize },
+    /// The newest timestamp is after the reference time
+    InFuture { newest: u64, reference_time: u64 },
+    Stale { age: u64, max_age: u64 },
+    MaxAgeTooLarge(u64),
+}
+
+impl fmt::Display for FreshnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FreshnessError::Empty => write!(f, "no timestamps"),
+            FreshnessError::NotIncreasing { index } => write!(f, "timestamp {index} is not after its predecessor"),
+            FreshnessError::InFuture { newest, reference_time } => {
+                write!(f, "newest timestamp {newest} is after the reference time {reference_time}")
+            }
+            FreshnessError::Stale { age, max_age } => write!(f, "newest timestamp is {age} old, more than {max_age}"),
+            FreshnessError::MaxAgeTooLarge(max_age) => write!(f, "max age {max_age} needs more than {AGE_BITS} bits"),
+        }
+    }
+}
+
+impl std::error::Error for FreshnessError {}
+
+/// Public inputs: the timestamps' digest and count, the reference time and the allowed age
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FreshnessInputs {
+    pub digest: BaseElement,
+    pub num_samples: usize,
+    pub reference_time: u64,
+    pub max_age: u64,
+}
+
+impl FreshnessInputs {
+    /// Inputs of the claim that `timestamps` are fresh as of `reference_time`
+    pub fn for_timestamps(timestamps: &[u64], reference_time: u64, max_age: u64) -> Self {
+        Self { digest: timestamps_digest(timestamps), num_samples: timestamps.len(), reference_time, max_age }
+    }
+}
+
+impl ToElements<BaseElement> for FreshnessInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            self.digest,
+            BaseElement::from(self.num_samples as u64),
+            BaseElement::from(self.reference_time),
+            BaseElement::from(self.max_age),
+        ]
+    }
+}
+
+/// AIR proving that committed timestamps are strictly increasing and recent
+pub struct FreshnessAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: FreshnessInputs,
+}
+
+impl Air for FreshnessAir {
+    type BaseField = BaseElement;
+    type PublicInputs = FreshnessInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: FreshnessInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new();
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert!(pub_inputs.num_samples > 0, "Freshness claims need at least one timestamp");
+        assert_eq!(trace_length(pub_inputs.num_samples), trace_info.length());
+        assert!(pub_inputs.max_age >> AGE_BITS == 0, "Max age must fit into {AGE_BITS} bits");
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. Sponge absorbs the timestamp at the end of a cycle (degree 1)
+        // 3. Timestamp is constant within a cycle (degree 1)
+        // 4. Active is boolean and never rises again (degree 2)
+        // 5. Timestamp matches its 64-bit digits
+        // 6. Active timestamps strictly increase from cycle to cycle (degree 2)
+        // 7. At the falling edge of active, age = reference - timestamp (degree 2)
+        // 8. age + slack = max_age, with both range checked (degree 1)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH]);
+        degrees.push(periodic(1));
+        degrees.extend(vec![TransitionConstraintDegree::new(2); 2]);
+        degrees.extend(layout.timestamp.constraint_degrees());
+        degrees.extend(layout.increasing.constraint_degrees(periodic(2)));
+        degrees.push(TransitionConstraintDegree::new(2));
+        degrees.extend(layout.age.constraint_degrees());
+        degrees.push(TransitionConstraintDegree::new(1));
+        degrees.extend(layout.slack.constraint_degrees());
+
+        FreshnessAir {
+            context: AirContext::new(trace_info, degrees, STATE_WIDTH + 3, options),
+            layout,
+            inputs: pub_inputs,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[T]);
+        for i in 1..STATE_WIDTH {
+            rest[i] = last * (next[SPONGE + i] - current[SPONGE + i]);
+        }
+        rest[4] = mask * (next[T] - current[T]);
+
+        let (active, edge) = (current[ACTIVE], current[ACTIVE] - next[ACTIVE]);
+        rest[5] = active * (active - E::ONE);
+        rest[6] = edge * (edge - E::ONE);
+
+        let rest = &mut rest[7..];
+        layout.timestamp.evaluate(current, rest);
+        let rest = &mut rest[layout.timestamp.num_constraints()..];
+        layout.increasing.evaluate(current, next, last * next[ACTIVE], rest);
+        let rest = &mut rest[layout.increasing.num_constraints()..];
+
+        let (age, slack) = (layout.age.recompose(current), layout.slack.recompose(current));
+        rest[0] = edge * (E::from(BaseElement::from(self.inputs.reference_time)) - current[T] - age);
+        layout.age.evaluate(current, &mut rest[1..]);
+        let rest = &mut rest[1 + layout.age.num_constraints()..];
+        rest[0] = age + slack - E::from(BaseElement::from(self.inputs.max_age));
+        layout.slack.evaluate(current, &mut rest[1..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_samples = self.inputs.num_samples;
+        let mut assertions: Vec<_> = rescue::sponge_iv(num_samples, TIMESTAMPS_TAG)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| Assertion::single(SPONGE + i, 0, value))
+            .collect();
+        assertions.push(Assertion::single(ACTIVE, num_samples * CYCLE_LENGTH - 1, BaseElement::ONE));
+        assertions.push(Assertion::single(ACTIVE, num_samples * CYCLE_LENGTH, BaseElement::ZERO));
+        assertions.push(Assertion::single(SPONGE, (num_samples + 1) * CYCLE_LENGTH - 1, self.inputs.digest));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving `timestamps` are fresh as of `reference_time`
+#[cfg(feature = "prover")]
+pub fn build_freshness_trace(
+    timestamps: &[u64],
+    reference_time: u64,
+    max_age: u64,
+) -> Result<TraceTable<BaseElement>, FreshnessError> {
+    let newest = *timestamps.last().ok_or(FreshnessError::Empty)?;
+    if let Some(index) = (1..timestamps.len()).find(|&i| timestamps[i] <= timestamps[i - 1]) {
+        return Err(FreshnessError::NotIncreasing { index });
+    }
+    if max_age >> AGE_BITS != 0 {
+        return Err(FreshnessError::MaxAgeTooLarge(max_age));
+    }
+    let age = reference_time.checked_sub(newest).ok_or(FreshnessError::InFuture { newest, reference_time })?;
+    if age > max_age {
+        return Err(FreshnessError::Stale { age, max_age });
+    }
+
+    let layout = Layout::new();
+    let num_samples = timestamps.len();
+    let length = trace_length(num_samples);
+    let mut columns = vec![vec![BaseElement::ZERO; length]; layout.trace_width()];
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut sponge_state = rescue::sponge_iv(num_samples, TIMESTAMPS_TAG);
+
+    layout.age.fill(&mut row, age as i128).expect("Age was checked against max age");
+    layout.slack.fill(&mut row, (max_age - age) as i128).expect("Slack is at most max age");
+
+    for cycle in 0..length / CYCLE_LENGTH {
+        // Padding cycles repeat a timestamp distinct from the newest one, which keeps the
+        // timestamp column from being constant for a single sample
+        let timestamp = timestamps.get(cycle).copied().unwrap_or(newest.wrapping_add(1));
+        layout.timestamp.fill(&mut row, timestamp).expect("Timestamps are 64-bit");
+        match timestamps.get(cycle + 1) {
+            Some(&next) => layout.increasing.fill(&mut row, timestamp, next).expect("Timestamps are increasing"),
+            None => layout.increasing.fill_unchecked(&mut row),
+        }
+        row[ACTIVE] = BaseElement::from((cycle < num_samples) as u64);
+
+        for step in 0..CYCLE_LENGTH {
+            layout.sponge.write(&mut row, &sponge_state);
+            for (column, &value) in columns.iter_mut().zip(&row) {
+                column[cycle * CYCLE_LENGTH + step] = value;
+            }
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut sponge_state, step);
+            }
+        }
+        sponge_state[0] += BaseElement::from(timestamp);
+    }
+
+    let mut last_row: Vec<BaseElement> = columns.iter().map(|column| column[length - 1]).collect();
+    layout.timestamp.fill_exempt_row(&mut last_row, newest.wrapping_add(1));
+    layout.increasing.fill_exempt_row(&mut last_row);
+    layout.age.fill_exempt_row(&mut last_row);
+    layout.slack.fill_exempt_row(&mut last_row);
+    for (column, value) in columns.iter_mut().zip(last_row) {
+        column[length - 1] = value;
+    }
+
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_freshness_proof() {
+        let timestamps = [1_700_000_000, 1_700_000_060, 1_700_000_300];
+        let (reference_time, max_age) = (1_700_000_400, 3_600);
+        let inputs = FreshnessInputs::for_timestamps(&timestamps, reference_time, max_age);
+
+        let trace = build_freshness_trace(&timestamps, reference_time, max_age).unwrap();
+        crate::air_test!(FreshnessAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<FreshnessAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+
+        let options = default_acceptable_options();
+        let later = FreshnessInputs { reference_time: reference_time + max_age, ..inputs };
+        assert!(verify::<FreshnessAir>(proof.clone(), later, &options).is_err());
+        let other_data = FreshnessInputs { digest: timestamps_digest(&[1, 2, 3]), ..inputs };
+        assert!(verify::<FreshnessAir>(proof.clone(), other_data, &options).is_err());
+        let result = verify::<FreshnessAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+
+        // a single sample fills exactly two cycles
+        let single = build_freshness_trace(&[10], 10, 0).unwrap();
+        prove::<FreshnessAir>(single, FreshnessInputs::for_timestamps(&[10], 10, 0), ProverConfig::default().to_proof_options())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_rejects_stale_and_unordered_data() {
+        assert_eq!(build_freshness_trace(&[], 0, 0).err(), Some(FreshnessError::Empty));
+        assert_eq!(build_freshness_trace(&[5, 5], 10, 10).err(), Some(FreshnessError::NotIncreasing { index: 1 }));
+        assert_eq!(
+            build_freshness_trace(&[5, 20], 10, 10).err(),
+            Some(FreshnessError::InFuture { newest: 20, reference_time: 10 })
+        );
+        assert_eq!(build_freshness_trace(&[5], 100, 10).err(), Some(FreshnessError::Stale { age: 95, max_age: 10 }));
+        assert_eq!(build_freshness_trace(&[5], 5, 1 << AGE_BITS).err(), Some(FreshnessError::MaxAgeTooLarge(1 << AGE_BITS)));
+    }
+}
@@ -0,0 +1,193 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    Assertion, TransitionConstraintDegree,
+};
+
+use super::fixed_mul::FixedPointMul;
+use super::integer::IntegerColumn;
+use super::range::RangeError;
+
+/// Bits a product `acc * term` may use without wrapping around the f128 field
+const MAX_PRODUCT_BITS: usize = 127;
+
+/// Running sum or fixed-point product of a term over the rows of a trace.
+///
+/// The accumulator column holds the value before a row's term is applied and the transition
+/// constraint moves it to the next row: `next = acc + term` for sums and
+/// `next = floor(acc * term / 2^shift)` for products, whose term carries `shift` fractional bits
+/// so the accumulator keeps its own scale. Rows with a zero selector copy the accumulator. The
+/// AIR pins the initial value and reads the final one with [`Accumulator::assertion`].
+///
+/// Bounded accumulators range check their column on every row to `0..2^value_bits`, so a sum
+/// of non-negative terms cannot wrap around the field unnoticed and a product's rounding is
+/// unique. Products are always bounded; the AIR must bound the term to `term_bits` bits itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Accumulator {
+    column: usize,
+    bound: Option<IntegerColumn>,
+    rescale: Option<FixedPointMul>,
+    shift: u32,
+}
+
+impl Accumulator {
+    /// Unbounded running sum; the AIR must bound the terms and their number so the sum of
+    /// fixed-point values with a common scale stays below half the field modulus
+    pub fn sum(column: usize) -> Self {
+        Self { column, bound: None, rescale: None, shift: 0 }
+    }
+
+    /// Running sum of non-negative terms, range checked to `value_bits` bits with the digits
+    /// starting at `first_digit_column`
+    pub fn bounded_sum(column: usize, first_digit_column: usize, value_bits: usize) -> Self {
+        Self { column, bound: Some(value_range(column, first_digit_column, value_bits)), rescale: None, shift: 0 }
+    }
+
+    /// Running product of non-negative fixed-point terms with `shift` fractional bits and at
+    /// most `term_bits` bits, range checked to `value_bits` bits (at most 64)
+    pub fn product(column: usize, first_digit_column: usize, value_bits: usize, term_bits: usize, shift: u32) -> Self {
+        assert!(value_bits + term_bits <= MAX_PRODUCT_BITS, "Accumulator product may wrap around the field");
+        let bound = value_range(column, first_digit_column, value_bits);
+        let rescale = FixedPointMul::new(bound.next_column(), shift);
+        Self { column, bound: Some(bound), rescale: Some(rescale), shift }
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// First column after the accumulator and its digits
+    pub fn next_column(&self) -> usize {
+        match (self.rescale, self.bound) {
+            (Some(rescale), _) => rescale.next_column(),
+            (None, Some(bound)) => bound.next_column(),
+            (None, None) => self.column + 1,
+        }
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        let step = if self.rescale.is_some() { 0 } else { 1 };
+        step + self.bound.map_or(0, |bound| bound.num_constraints())
+            + self.rescale.map_or(0, |rescale| rescale.num_constraints())
+    }
+
+    /// Degrees for a term of degree `term` gated by a selector of degree `selector`
+    pub fn constraint_degrees(&self, term: usize, selector: usize) -> Vec<TransitionConstraintDegree> {
+        let mut degrees = Vec::new();
+        match self.rescale {
+            // acc * (selector * term + (1 - selector) * 2^shift)
+            Some(rescale) => degrees.extend(rescale.constraint_degrees(TransitionConstraintDegree::new(
+                1 + (selector + term).max(selector),
+            ))),
+            None => degrees.push(TransitionConstraintDegree::new((selector + term).max(1))),
+        }
+        if let Some(bound) = self.bound {
+            degrees.extend(bound.constraint_degrees());
+        }
+        degrees
+    }
+
+    /// Constrains the step from `current` to `next`, applying `term` where `selector` is one
+    pub fn evaluate<E: FieldElement>(&self, current: &[E], next: &[E], term: E, selector: E, result: &mut [E]) {
+        let (acc, next_acc) = (current[self.column], next[self.column]);
+        let offset = match self.rescale {
+            Some(rescale) => {
+                let one = E::from(2u32).exp(self.shift.into());
+                let factor = selector * term + (E::ONE - selector) * one;
+                rescale.evaluate(current, acc, factor, next_acc, E::ONE, &mut result[..rescale.num_constraints()]);
+                rescale.num_constraints()
+            }
+            None => {
+                result[0] = next_acc - acc - selector * term;
+                1
+            }
+        };
+        if let Some(bound) = self.bound {
+            bound.evaluate(current, &mut result[offset..]);
+        }
+    }
+
+    /// Writes `acc` and its digits and returns the accumulator of the next row, applying
+    /// `term` when `enabled`
+    pub fn fill(&self, row: &mut [BaseElement], acc: i128, term: i128, enabled: bool) -> Result<i128, RangeError> {
+        row[self.column] = crate::fixed_point::to_field(acc);
+        if let Some(bound) = self.bound {
+            bound.fill(row, u64::try_from(acc).map_err(|_| range_error(acc, &bound))?)?;
+        }
+        match self.rescale {
+            Some(rescale) => rescale.fill(row, acc, if enabled { term } else { 1 << self.shift }),
+            None if enabled => Ok(acc.checked_add(term).expect("Accumulator sum overflows i128")),
+            None => Ok(acc),
+        }
+    }
+
+    /// Writes the final accumulator into the transition-exempt last row with filler digits
+    pub fn fill_exempt_row(&self, row: &mut [BaseElement], acc: i128) {
+        row[self.column] = crate::fixed_point::to_field(acc);
+        if let Some(bound) = self.bound {
+            bound.fill_exempt_row(row, acc as u64);
+        }
+        if let Some(rescale) = self.rescale {
+            rescale.fill_exempt_row(row);
+        }
+    }
+
+    /// Pins the accumulator at `step` to `value`, e.g. its initial value or the final result
+    pub fn assertion(&self, step: usize, value: BaseElement) -> Assertion<BaseElement> {
+        Assertion::single(self.column, step, value)
+    }
+}
+
+fn value_range(column: usize, first_digit_column: usize, value_bits: usize) -> IntegerColumn {
+    assert!(value_bits <= 64, "Accumulator values are at most 64 bits wide");
+    let digit_bits = if value_bits.is_multiple_of(2) { 2 } else { 1 };
+    IntegerColumn::new(column, first_digit_column, value_bits, digit_bits)
+}
+
+fn range_error(acc: i128, bound: &IntegerColumn) -> RangeError {
+    if acc < 0 {
+        RangeError::Negative(acc)
+    } else {
+        RangeError::TooLarge { value: acc, num_bits: bound.num_bits() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_steps(gadget: &Accumulator, terms: &[(i128, bool)], initial: i128) -> (i128, Vec<BaseElement>) {
+        let mut rows = vec![vec![BaseElement::ZERO; gadget.next_column()]; terms.len() + 1];
+        let mut acc = initial;
+        for (row, &(term, enabled)) in rows.iter_mut().zip(terms) {
+            acc = gadget.fill(row, acc, term, enabled).unwrap();
+        }
+        gadget.fill(&mut rows[terms.len()], acc, 0, false).unwrap();
+
+        let mut result = vec![BaseElement::ONE; gadget.num_constraints()];
+        let mut results = Vec::new();
+        for (r, &(term, enabled)) in terms.iter().enumerate() {
+            let selector = if enabled { BaseElement::ONE } else { BaseElement::ZERO };
+            gadget.evaluate(&rows[r], &rows[r + 1], crate::fixed_point::to_field(term), selector, &mut result);
+            results.extend(&result);
+        }
+        (acc, results)
+    }
+
+    #[test]
+    fn test_sum_and_product() {
+        let sum = Accumulator::bounded_sum(0, 1, 8);
+        let (total, results) = check_steps(&sum, &[(3, true), (0, false), (250, true)], 2);
+        assert_eq!(total, 255);
+        assert!(results.iter().all(|r| *r == BaseElement::ZERO));
+        let mut row = vec![BaseElement::ZERO; sum.next_column()];
+        assert_eq!(sum.fill(&mut row, 256, 0, false), Err(RangeError::TooLarge { value: 256, num_bits: 8 }));
+        assert_eq!(Accumulator::sum(0).fill(&mut row, -4, 3, true), Ok(-1));
+
+        // 1.5 * 1.25 * 0.5 with 4 fractional bits: 24 * 20 / 16 = 30, 30 * 8 / 16 = 15
+        let product = Accumulator::product(0, 1, 12, 8, 4);
+        let (total, results) = check_steps(&product, &[(20, true), (0, false), (8, true)], 24);
+        assert_eq!(total, 15);
+        assert!(results.iter().all(|r| *r == BaseElement::ZERO));
+        assert_eq!(product.assertion(3, BaseElement::new(15)), Assertion::single(0, 3, BaseElement::new(15)));
+    }
+}
@@ -0,0 +1,88 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    TransitionConstraintDegree,
+};
+
+use super::range::{RangeCheck, RangeError};
+use crate::fixed_point::div_floor;
+
+/// Proves `c = floor(a * b / 2^shift)` for fixed-point operands, i.e. the product of two
+/// fixed-point numbers rounded down into the scale of one of them.
+///
+/// The row carries the digits of the remainder `r = a * b - c * 2^shift`, range checked to
+/// `0..2^shift`. The AIR picks the cells holding `a`, `b` and `c` and must bound them itself
+/// (e.g. with an [`IntegerColumn`](super::IntegerColumn)) so `a * b` cannot wrap around the
+/// field; the rounding is then unique.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedPointMul {
+    shift: u32,
+    remainder: RangeCheck,
+}
+
+impl FixedPointMul {
+    pub fn new(first_digit_column: usize, shift: u32) -> Self {
+        Self { shift, remainder: RangeCheck::new(first_digit_column, shift as usize) }
+    }
+
+    /// First column after the remainder digits owned by this gadget
+    pub fn next_column(&self) -> usize {
+        self.remainder.next_column()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        1 + self.remainder.num_constraints()
+    }
+
+    /// Degrees for a product constraint of degree `product` (including any selector)
+    pub fn constraint_degrees(&self, product: TransitionConstraintDegree) -> Vec<TransitionConstraintDegree> {
+        let mut degrees = vec![product];
+        degrees.extend(self.remainder.constraint_degrees());
+        degrees
+    }
+
+    /// Constrains `selector * (a * b - c * 2^shift - r)` with the remainder digits of `row`
+    pub fn evaluate<E: FieldElement>(&self, row: &[E], a: E, b: E, c: E, selector: E, result: &mut [E]) {
+        let scale = E::from(2u32).exp(self.shift.into());
+        result[0] = selector * (a * b - c * scale - self.remainder.recompose(row));
+        self.remainder.evaluate(row, &mut result[1..]);
+    }
+
+    /// Writes the remainder digits of `a * b` and returns the rounded product
+    pub fn fill(&self, row: &mut [BaseElement], a: i128, b: i128) -> Result<i128, RangeError> {
+        let (product, remainder) = div_floor(a.checked_mul(b).expect("Fixed-point product overflows i128"), 1 << self.shift);
+        self.remainder.fill(row, remainder)?;
+        Ok(product)
+    }
+
+    /// Writes a zero remainder for rows whose product is not checked
+    pub fn fill_unchecked(&self, row: &mut [BaseElement]) {
+        self.remainder.fill(row, 0).expect("Zero fits any range");
+    }
+
+    pub fn fill_exempt_row(&self, row: &mut [BaseElement]) {
+        self.remainder.fill_exempt_row(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounded_product() {
+        let gadget = FixedPointMul::new(0, 8);
+        let mut row = vec![BaseElement::ZERO; gadget.next_column()];
+        // 3 * 2.75 = 8.25, with 8 fractional bits on the rate side
+        let c = gadget.fill(&mut row, 3, 2 * 256 + 192).unwrap();
+        assert_eq!(c, 8);
+
+        let mut result = vec![BaseElement::ONE; gadget.num_constraints()];
+        let (a, b) = (BaseElement::new(3), BaseElement::new(2 * 256 + 192));
+        gadget.evaluate(&row, a, b, BaseElement::new(8), BaseElement::ONE, &mut result);
+        assert!(result.iter().all(|r| *r == BaseElement::ZERO));
+        gadget.evaluate(&row, a, b, BaseElement::new(9), BaseElement::ONE, &mut result);
+        assert_ne!(result[0], BaseElement::ZERO);
+
+        assert_eq!(gadget.fill(&mut row, -3, 256 + 128).unwrap(), -5);
+    }
+}
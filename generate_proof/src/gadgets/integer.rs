@@ -0,0 +1,125 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    TransitionConstraintDegree,
+};
+
+use super::range::{RangeCheck, RangeError};
+
+/// Machine integer width whose wraparound-free semantics a trace must respect
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegerWidth {
+    U32,
+    U64,
+}
+
+impl IntegerWidth {
+    pub fn bits(&self) -> usize {
+        match self {
+            IntegerWidth::U32 => 32,
+            IntegerWidth::U64 => 64,
+        }
+    }
+
+    /// Bits per range-check digit; u64 columns use base-4 digits to stay within the
+    /// 255-column trace limit
+    pub fn digit_bits(&self) -> usize {
+        match self {
+            IntegerWidth::U32 => 1,
+            IntegerWidth::U64 => 2,
+        }
+    }
+
+    /// Width of the limbs used to multiply without field wraparound
+    pub fn half_bits(&self) -> usize {
+        self.bits() / 2
+    }
+
+    pub fn max(&self) -> u64 {
+        match self {
+            IntegerWidth::U32 => u32::MAX as u64,
+            IntegerWidth::U64 => u64::MAX,
+        }
+    }
+
+    /// Splits `value` into `(lo, hi)` limbs of [`IntegerWidth::half_bits`] bits each
+    pub fn split(&self, value: u64) -> (u64, u64) {
+        let half = self.half_bits();
+        (value & ((1u64 << half) - 1), value >> half)
+    }
+}
+
+/// A trace column holding an unsigned integer of a fixed bit width.
+///
+/// Owns the value column plus the range-check digits behind it and constrains
+/// `value = recompose(digits)` in addition to the range gadget's digit constraints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IntegerColumn {
+    value_column: usize,
+    range: RangeCheck,
+}
+
+impl IntegerColumn {
+    pub fn new(value_column: usize, first_digit_column: usize, num_bits: usize, digit_bits: usize) -> Self {
+        Self { value_column, range: RangeCheck::with_digit_bits(first_digit_column, num_bits, digit_bits) }
+    }
+
+    pub fn value_column(&self) -> usize {
+        self.value_column
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.range.num_bits()
+    }
+
+    /// First column after the range-check digits owned by this column
+    pub fn next_column(&self) -> usize {
+        self.range.next_column()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        1 + self.range.num_constraints()
+    }
+
+    pub fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        let mut degrees = vec![TransitionConstraintDegree::new(1)];
+        degrees.extend(self.range.constraint_degrees());
+        degrees
+    }
+
+    pub fn evaluate<E: FieldElement>(&self, row: &[E], result: &mut [E]) {
+        result[0] = row[self.value_column] - self.range.recompose(row);
+        self.range.evaluate(row, &mut result[1..]);
+    }
+
+    /// Writes `value` and its digit decomposition, failing if it does not fit the column
+    pub fn fill(&self, row: &mut [BaseElement], value: u64) -> Result<(), RangeError> {
+        self.range.fill(row, value as i128)?;
+        row[self.value_column] = BaseElement::from(value);
+        Ok(())
+    }
+
+    /// Writes `value` into the transition-exempt last row with filler digits
+    pub fn fill_exempt_row(&self, row: &mut [BaseElement], value: u64) {
+        row[self.value_column] = BaseElement::from(value);
+        self.range.fill_exempt_row(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_column_rejects_overflow() {
+        let column = IntegerColumn::new(0, 1, IntegerWidth::U32.bits(), IntegerWidth::U32.digit_bits());
+        let mut row = vec![BaseElement::ZERO; column.next_column()];
+
+        column.fill(&mut row, u32::MAX as u64).unwrap();
+        let mut result = vec![BaseElement::ONE; column.num_constraints()];
+        column.evaluate(&row, &mut result);
+        assert!(result.iter().all(|r| *r == BaseElement::ZERO));
+
+        assert!(column.fill(&mut row, u32::MAX as u64 + 1).is_err());
+        assert_eq!(IntegerWidth::U64.split(0x1234_5678_9abc_def0), (0x9abc_def0, 0x1234_5678));
+    }
+}
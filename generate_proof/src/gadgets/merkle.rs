@@ -0,0 +1,217 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    Assertion, TransitionConstraintDegree,
+};
+
+use super::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::layout::LayoutDescriptor;
+
+/// Sparse trees have at most this many levels below the root, one per key bit
+pub const MAX_DEPTH: usize = 64;
+
+/// Third permutation input of leaf and inner node hashes
+const LEAF_TAG: u64 = 13;
+const NODE_TAG: u64 = 14;
+
+/// Hash of the leaf holding `value` under `key`; empty leaves are zero
+pub fn leaf_hash(key: u64, value: BaseElement) -> BaseElement {
+    compress([BaseElement::from(key), value, BaseElement::from(LEAF_TAG), BaseElement::ZERO])
+}
+
+/// Hash of the node at `level` (1 is the parent of the leaves) with children `left` and `right`
+pub fn node_hash(level: usize, left: BaseElement, right: BaseElement) -> BaseElement {
+    compress([left, right, BaseElement::from(NODE_TAG), level_power(level)])
+}
+
+fn compress(mut state: [BaseElement; STATE_WIDTH]) -> BaseElement {
+    rescue::permute(&mut state);
+    state[0]
+}
+
+fn level_power(level: usize) -> BaseElement {
+    BaseElement::new(2).exp(level as u128)
+}
+
+/// Path from the leaf of `key` to the root of a sparse tree, sibling of the leaf first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleOpening {
+    pub key: u64,
+    pub value: BaseElement,
+    pub siblings: Vec<BaseElement>,
+}
+
+impl MerkleOpening {
+    pub fn depth(&self) -> usize {
+        self.siblings.len()
+    }
+
+    /// Root the opening leads to; key bits above the depth are ignored
+    pub fn root(&self) -> BaseElement {
+        let mut node = leaf_hash(self.key, self.value);
+        for (level, &sibling) in self.siblings.iter().enumerate() {
+            node = if self.key >> level & 1 == 0 {
+                node_hash(level + 1, node, sibling)
+            } else {
+                node_hash(level + 1, sibling, node)
+            };
+        }
+        node
+    }
+}
+
+/// Proves that a key and value open to a root of a sparse Merkle tree of Rescue hashes.
+///
+/// Cycle 0 hashes the leaf and cycle `c` the node at level `c`, whose input is the previous
+/// output and the sibling ordered by key bit `c - 1`. The bit and the sibling consumed at the
+/// end of a cycle sit on its last row, and the key is recomposed from the bits in an
+/// accumulator next to a column of powers of two, which also separates the levels in the
+/// node hashes. The gadget uses the periodic columns of [`rescue::periodic_columns`];
+/// [`SparseMerkleGadget::assertions`] pins the key, value and root of an opening starting at a
+/// given row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SparseMerkleGadget {
+    first_column: usize,
+    sponge: RescueGadget,
+}
+
+impl SparseMerkleGadget {
+    pub fn new(first_column: usize) -> Self {
+        Self { first_column, sponge: RescueGadget::new(first_column) }
+    }
+
+    fn bit(&self) -> usize {
+        self.sponge.next_column()
+    }
+
+    fn sibling(&self) -> usize {
+        self.bit() + 1
+    }
+
+    fn key(&self) -> usize {
+        self.bit() + 2
+    }
+
+    fn power(&self) -> usize {
+        self.bit() + 3
+    }
+
+    /// Sponge state, bit, sibling, key accumulator and power of two
+    pub fn width(&self) -> usize {
+        STATE_WIDTH + 4
+    }
+
+    pub fn next_column(&self) -> usize {
+        self.power() + 1
+    }
+
+    /// Names the gadget's columns with the prefix `name`
+    pub fn describe(&self, name: &str, descriptor: LayoutDescriptor) -> LayoutDescriptor {
+        descriptor
+            .with_columns(&format!("{name}_sponge"), self.first_column..self.bit())
+            .with_column(&format!("{name}_bit"), self.bit())
+            .with_column(&format!("{name}_sibling"), self.sibling())
+            .with_column(&format!("{name}_key"), self.key())
+            .with_column(&format!("{name}_power"), self.power())
+    }
+
+    /// Rows taken by an opening of a tree of `depth` levels
+    pub fn num_rows(depth: usize) -> usize {
+        (depth + 1) * CYCLE_LENGTH
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        STATE_WIDTH + 7
+    }
+
+    pub fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+        let mut degrees = self.sponge.constraint_degrees();
+        degrees.extend([periodic(2), periodic(2), periodic(1), periodic(1)]);
+        degrees.extend([periodic(2), periodic(2), periodic(1)]);
+        degrees
+    }
+
+    /// Writes the constraints into `result`; `periodic` holds the values of the columns returned
+    /// by [`rescue::periodic_columns`] for the current row
+    pub fn evaluate<E: FieldElement + From<BaseElement>>(
+        &self,
+        current: &[E],
+        next: &[E],
+        periodic: &[E],
+        result: &mut [E],
+    ) {
+        let last = E::ONE - periodic[0];
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        self.sponge.evaluate(current, next, periodic, sponge_result);
+
+        // the next cycle hashes (output, sibling) or (sibling, output) depending on the bit
+        let (output, sibling, bit) = (current[self.first_column], current[self.sibling()], current[self.bit()]);
+        let state = self.sponge.state(next);
+        let (power, key) = (current[self.power()], current[self.key()]);
+        rest[0] = last * (state[0] - output - bit * (sibling - output));
+        rest[1] = last * (state[1] - sibling - bit * (output - sibling));
+        rest[2] = last * (state[2] - E::from(BaseElement::from(NODE_TAG)));
+        rest[3] = last * (state[3] - next[self.power()]);
+
+        rest[4] = last * bit * (bit - E::ONE);
+        rest[5] = next[self.key()] - key - last * bit * power;
+        rest[6] = next[self.power()] - power - last * power;
+    }
+
+    /// Pins the leaf input and the key accumulator at `first_row`, the recomposed key and the
+    /// root of a tree of `depth` levels
+    pub fn assertions(
+        &self,
+        first_row: usize,
+        depth: usize,
+        key: u64,
+        value: BaseElement,
+        root: BaseElement,
+    ) -> Vec<Assertion<BaseElement>> {
+        let first = self.first_column;
+        let key = BaseElement::from(key);
+        let leaf = [key, value, BaseElement::from(LEAF_TAG), BaseElement::ZERO];
+        let mut assertions: Vec<_> =
+            leaf.into_iter().enumerate().map(|(i, input)| Assertion::single(first + i, first_row, input)).collect();
+        assertions.push(Assertion::single(self.key(), first_row, BaseElement::ZERO));
+        assertions.push(Assertion::single(self.power(), first_row, BaseElement::ONE));
+        assertions.push(Assertion::single(self.key(), first_row + depth * CYCLE_LENGTH, key));
+        assertions.push(Assertion::single(first, first_row + Self::num_rows(depth) - 1, root));
+        assertions
+    }
+
+    /// Writes `num_cycles` cycles from `first_row` on: the opening, then cycles hashing the
+    /// root with zero siblings
+    pub fn fill(&self, columns: &mut [Vec<BaseElement>], first_row: usize, opening: &MerkleOpening, num_cycles: usize) {
+        assert!(num_cycles > opening.depth(), "Openings of depth d take d + 1 cycles");
+        let mut state = [BaseElement::from(opening.key), opening.value, BaseElement::from(LEAF_TAG), BaseElement::ZERO];
+        let mut key = BaseElement::ZERO;
+        for cycle in 0..num_cycles {
+            let power = level_power(cycle);
+            let bit = (cycle < opening.depth() && opening.key >> cycle & 1 == 1) as u64;
+            let sibling = opening.siblings.get(cycle).copied().unwrap_or(BaseElement::ZERO);
+            for step in 0..CYCLE_LENGTH {
+                let row = first_row + cycle * CYCLE_LENGTH + step;
+                for (i, &lane) in state.iter().enumerate() {
+                    columns[self.first_column + i][row] = lane;
+                }
+                columns[self.bit()][row] = BaseElement::from(bit);
+                columns[self.sibling()][row] = sibling;
+                columns[self.key()][row] = key;
+                columns[self.power()][row] = power;
+                if step < rescue::NUM_ROUNDS {
+                    rescue::apply_round(&mut state, step);
+                }
+            }
+            let output = state[0];
+            let (left, right) = if bit == 1 { (sibling, output) } else { (output, sibling) };
+            state = [left, right, BaseElement::from(NODE_TAG), level_power(cycle + 1)];
+            key += BaseElement::from(bit) * power;
+        }
+    }
+
+    /// Fills the bit of the transition-exempt last row with a non-boolean value
+    pub fn fill_exempt_row(&self, row: &mut [BaseElement]) {
+        row[self.bit()] = BaseElement::new(2);
+    }
+}
@@ -0,0 +1,28 @@
+//! Reusable constraint gadgets shared by the AIRs in this crate.
+//!
+//! A gadget owns a contiguous range of trace columns and knows how to fill them and how to
+//! constrain them. Column indices are absolute positions in the main trace.
+//!
+//! Row-local gadget constraints are only enforced on rows covered by transition constraints,
+//! i.e. every row except the last. Gadgets fill that exempt last row with values that violate
+//! their constraints on purpose, so constraint polynomials never collapse to zero and
+//! Winterfell's debug-mode degree checks see the degrees declared by the AIR.
+
+pub mod accumulator;
+pub mod fixed_mul;
+pub mod integer;
+pub mod merkle;
+pub mod monotonic;
+pub mod one_hot;
+pub mod range;
+pub mod rescue;
+pub mod selector;
+
+pub use accumulator::Accumulator;
+pub use fixed_mul::FixedPointMul;
+pub use integer::{IntegerColumn, IntegerWidth};
+pub use merkle::{MerkleOpening, SparseMerkleGadget};
+pub use monotonic::StrictlyIncreasing;
+pub use one_hot::OneHot;
+pub use range::{RangeCheck, RangeError};
+pub use selector::PhaseSelector;
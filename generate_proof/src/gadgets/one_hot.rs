@@ -0,0 +1,139 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    TransitionConstraintDegree,
+};
+
+/// One-hot encoding of a categorical value: one flag column per category.
+///
+/// Every flag is binary and exactly one is set, so a row selects exactly one of the
+/// `num_categories` declared categories. The owning AIR reads the selection through
+/// [`OneHot::index`] or weighs per-category constants with [`OneHot::select`], both linear in
+/// the flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OneHot {
+    first_column: usize,
+    num_categories: usize,
+}
+
+impl OneHot {
+    pub const fn new(first_column: usize, num_categories: usize) -> Self {
+        assert!(num_categories >= 1, "A one-hot encoding needs at least one category");
+        Self { first_column, num_categories }
+    }
+
+    /// Column of the flag of `category`
+    pub fn column(&self, category: usize) -> usize {
+        assert!(category < self.num_categories, "Category {category} out of range");
+        self.first_column + category
+    }
+
+    /// Number of flag columns owned by this gadget
+    pub fn width(&self) -> usize {
+        self.num_categories
+    }
+
+    /// First column after the ones owned by this gadget
+    pub fn next_column(&self) -> usize {
+        self.first_column + self.num_categories
+    }
+
+    /// Booleanity of every flag and the exactly-one sum
+    pub fn num_constraints(&self) -> usize {
+        self.num_categories + 1
+    }
+
+    pub fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        let mut degrees = vec![TransitionConstraintDegree::new(2); self.num_categories];
+        degrees.push(TransitionConstraintDegree::new(1));
+        degrees
+    }
+
+    /// Labels of the constraints in evaluation order: `category_{c}_boolean` for every
+    /// category, then `category_exactly_one`
+    pub fn constraint_labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> =
+            (0..self.num_categories).map(|category| format!("category_{category}_boolean")).collect();
+        labels.push("category_exactly_one".into());
+        labels
+    }
+
+    /// Index of the selected category, `sum(c * flag_c)`
+    pub fn index<E: FieldElement>(&self, row: &[E]) -> E {
+        self.flags(row).iter().enumerate().skip(1).fold(E::ZERO, |sum, (c, &flag)| sum + E::from(c as u32) * flag)
+    }
+
+    /// The entry of `values` belonging to the selected category, `sum(values_c * flag_c)`
+    pub fn select<E: FieldElement>(&self, row: &[E], values: &[E]) -> E {
+        self.flags(row).iter().zip(values).fold(E::ZERO, |sum, (&flag, &value)| sum + value * flag)
+    }
+
+    pub fn evaluate<E: FieldElement>(&self, row: &[E], result: &mut [E]) {
+        let flags = self.flags(row);
+        for (r, &flag) in result.iter_mut().zip(flags) {
+            *r = flag * (flag - E::ONE);
+        }
+        result[self.num_categories] = flags.iter().fold(E::ZERO, |sum, &flag| sum + flag) - E::ONE;
+    }
+
+    /// Selected category of a row satisfying the constraints
+    pub fn category(&self, row: &[BaseElement]) -> Option<usize> {
+        let flags = self.flags(row);
+        let valid = flags.iter().all(|&flag| flag == BaseElement::ZERO || flag == BaseElement::ONE)
+            && flags.iter().filter(|&&flag| flag == BaseElement::ONE).count() == 1;
+        valid.then(|| flags.iter().position(|&flag| flag == BaseElement::ONE)).flatten()
+    }
+
+    /// Writes the flags of a row selecting `category`
+    pub fn fill(&self, row: &mut [BaseElement], category: usize) {
+        let column = self.column(category);
+        for (i, flag) in row[self.first_column..self.next_column()].iter_mut().enumerate() {
+            *flag = if self.first_column + i == column { BaseElement::ONE } else { BaseElement::ZERO };
+        }
+    }
+
+    /// Sets every flag of the exempt last row to 2, breaking booleanity and the exactly-one sum
+    pub fn fill_exempt_row(&self, row: &mut [BaseElement]) {
+        row[self.first_column..self.next_column()].fill(BaseElement::new(2));
+    }
+
+    fn flags<'a, E>(&self, row: &'a [E]) -> &'a [E] {
+        &row[self.first_column..self.next_column()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_hot_constraints() {
+        let gadget = OneHot::new(2, 4);
+        let mut row = vec![BaseElement::new(9); gadget.next_column()];
+        let mut result = vec![BaseElement::ONE; gadget.num_constraints()];
+        let all_zero = |result: &[BaseElement]| result.iter().all(|r| *r == BaseElement::ZERO);
+
+        for category in 0..4 {
+            gadget.fill(&mut row, category);
+            gadget.evaluate(&row, &mut result);
+            assert!(all_zero(&result), "category {category}");
+            assert_eq!(gadget.category(&row), Some(category));
+            assert_eq!(gadget.index(&row), BaseElement::from(category as u64));
+        }
+        let offsets = [5u64, 7, 11, 13].map(BaseElement::from);
+        assert_eq!(gadget.select(&row, &offsets), BaseElement::new(13));
+
+        // no category, two categories, and non-binary flags summing to one
+        let (zero, one) = (BaseElement::ZERO, BaseElement::ONE);
+        for flags in [[zero; 4], [one, zero, one, zero], [BaseElement::new(2), zero, zero, -one]] {
+            row[gadget.column(0)..gadget.next_column()].copy_from_slice(&flags);
+            gadget.evaluate(&row, &mut result);
+            assert!(!all_zero(&result), "{flags:?}");
+            assert_eq!(gadget.category(&row), None);
+        }
+
+        gadget.fill_exempt_row(&mut row);
+        gadget.evaluate(&row, &mut result);
+        assert!(result.iter().all(|r| *r != BaseElement::ZERO));
+        assert_eq!(gadget.constraint_labels()[4], "category_exactly_one");
+    }
+}
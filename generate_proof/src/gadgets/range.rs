@@ -0,0 +1,152 @@
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    TransitionConstraintDegree,
+};
+
+/// Error returned when a value does not fit into the range of a [`RangeCheck`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RangeError {
+    /// The value was negative
+    Negative(i128),
+    /// The value needs more bits than the gadget provides
+    TooLarge { value: i128, num_bits: usize },
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::Negative(value) => write!(f, "value {value} is negative"),
+            RangeError::TooLarge { value, num_bits } => {
+                write!(f, "value {value} does not fit into {num_bits} bits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+/// Proves `0 <= v < 2^num_bits` by decomposing `v` into digit columns within a single row.
+///
+/// Digits are binary by default; wider digits trade trace width for constraint degree
+/// (a digit of `d` bits is constrained by a product of degree `2^d`). The gadget only
+/// constrains the digit values; the owning AIR must tie the recomposed value
+/// (see [`RangeCheck::recompose`]) to the expression it wants range checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeCheck {
+    first_column: usize,
+    num_bits: usize,
+    digit_bits: usize,
+}
+
+impl RangeCheck {
+    pub fn new(first_column: usize, num_bits: usize) -> Self {
+        Self::with_digit_bits(first_column, num_bits, 1)
+    }
+
+    /// Range check using digits of `digit_bits` bits; `num_bits` must be a multiple of it
+    pub fn with_digit_bits(first_column: usize, num_bits: usize, digit_bits: usize) -> Self {
+        assert!(num_bits > 0 && num_bits < 128, "Range check must use 1..128 bits");
+        assert!(matches!(digit_bits, 1 | 2), "Range check digits must be 1 or 2 bits wide");
+        assert!(num_bits.is_multiple_of(digit_bits), "Bit count must be a multiple of the digit width");
+        Self { first_column, num_bits, digit_bits }
+    }
+
+    /// Smallest binary range check able to hold every value in `0..bound`
+    pub fn for_bound(first_column: usize, bound: u128) -> Self {
+        let num_bits = (u128::BITS - bound.saturating_sub(1).leading_zeros()).max(1);
+        Self::new(first_column, num_bits as usize)
+    }
+
+    /// Number of digit columns owned by this gadget
+    pub fn width(&self) -> usize {
+        self.num_bits / self.digit_bits
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    /// First column after the ones owned by this gadget
+    pub fn next_column(&self) -> usize {
+        self.first_column + self.width()
+    }
+
+    /// One digit constraint per digit column
+    pub fn num_constraints(&self) -> usize {
+        self.width()
+    }
+
+    pub fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        vec![TransitionConstraintDegree::new(1 << self.digit_bits); self.width()]
+    }
+
+    /// Writes `d * (d - 1) * ... * (d - (2^digit_bits - 1))` for every digit column into `result`
+    pub fn evaluate<E: FieldElement>(&self, row: &[E], result: &mut [E]) {
+        for (i, r) in result[..self.width()].iter_mut().enumerate() {
+            let digit = row[self.first_column + i];
+            *r = (1..1u32 << self.digit_bits).fold(digit, |acc, k| acc * (digit - E::from(k)));
+        }
+    }
+
+    /// Recombines the digit columns of `row` into the value they encode
+    pub fn recompose<E: FieldElement>(&self, row: &[E]) -> E {
+        let radix = E::from(1u32 << self.digit_bits);
+        row[self.first_column..self.next_column()]
+            .iter()
+            .rev()
+            .fold(E::ZERO, |acc, &digit| acc * radix + digit)
+    }
+
+    /// Decomposes `value` into the digit cells of `row`
+    pub fn fill(&self, row: &mut [BaseElement], value: i128) -> Result<(), RangeError> {
+        if value < 0 {
+            return Err(RangeError::Negative(value));
+        }
+        if value >> self.num_bits != 0 {
+            return Err(RangeError::TooLarge { value, num_bits: self.num_bits });
+        }
+        let mask = (1 << self.digit_bits) - 1;
+        for (i, cell) in row[self.first_column..self.next_column()].iter_mut().enumerate() {
+            *cell = BaseElement::new(((value >> (i * self.digit_bits)) & mask) as u128);
+        }
+        Ok(())
+    }
+
+    /// Fills the digit cells of the transition-exempt last row with the smallest invalid digit
+    pub fn fill_exempt_row(&self, row: &mut [BaseElement]) {
+        row[self.first_column..self.next_column()].fill(BaseElement::new(1 << self.digit_bits));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_and_recompose() {
+        let range = RangeCheck::for_bound(1, 10);
+        assert_eq!(range.num_bits(), 4);
+
+        let mut row = vec![BaseElement::ZERO; 1 + range.width()];
+        range.fill(&mut row, 9).unwrap();
+        assert_eq!(range.recompose(&row), BaseElement::new(9));
+
+        let mut result = vec![BaseElement::ONE; range.num_constraints()];
+        range.evaluate(&row, &mut result);
+        assert!(result.iter().all(|r| *r == BaseElement::ZERO));
+
+        assert_eq!(range.fill(&mut row, -1), Err(RangeError::Negative(-1)));
+        assert_eq!(range.fill(&mut row, 16), Err(RangeError::TooLarge { value: 16, num_bits: 4 }));
+
+        let quaternary = RangeCheck::with_digit_bits(0, 8, 2);
+        let mut row = vec![BaseElement::ZERO; quaternary.width()];
+        quaternary.fill(&mut row, 0b11_10_01_00).unwrap();
+        assert_eq!(row, [0u128, 1, 2, 3].map(BaseElement::new));
+        assert_eq!(quaternary.recompose(&row), BaseElement::new(0b11_10_01_00));
+        let mut result = vec![BaseElement::ONE; quaternary.num_constraints()];
+        quaternary.evaluate(&row, &mut result);
+        assert!(result.iter().all(|r| *r == BaseElement::ZERO));
+    }
+}
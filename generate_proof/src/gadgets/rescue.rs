@@ -0,0 +1,261 @@
+//! Rescue-style algebraic permutation over the f128 field, natively and as a gadget.
+//!
+//! The state has [`STATE_WIDTH`] elements and a permutation runs [`NUM_ROUNDS`] rounds, one per
+//! trace row, so a permutation occupies a [`CYCLE_LENGTH`]-row cycle whose last row holds the
+//! output. A round computes `s = M * s^3 + c1` followed by `s = M * s^(1/3) + c2`; `M` is a
+//! 4x4 Cauchy (hence MDS) matrix. The gadget checks the inverse S-box in the cheap direction:
+//! `(M^-1 * (next - c2))^3 = M * current^3 + c1`, a degree 3 constraint.
+
+use std::sync::OnceLock;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField},
+    TransitionConstraintDegree,
+};
+
+pub const STATE_WIDTH: usize = 4;
+pub const NUM_ROUNDS: usize = 7;
+pub const CYCLE_LENGTH: usize = 8;
+
+/// Periodic columns used by [`RescueGadget::evaluate`]: the round mask followed by the
+/// `c1` and `c2` round constants of every state element
+pub const NUM_PERIODIC_COLUMNS: usize = 1 + 2 * STATE_WIDTH;
+
+const CONSTANTS_DOMAIN: &[u8] = b"stark-framework/rescue/v1/round-constants";
+
+type State = [BaseElement; STATE_WIDTH];
+type Matrix = [[BaseElement; STATE_WIDTH]; STATE_WIDTH];
+
+struct Params {
+    mds: Matrix,
+    inv_mds: Matrix,
+    /// `(c1, c2)` for every round
+    round_constants: Vec<(State, State)>,
+}
+
+fn params() -> &'static Params {
+    static PARAMS: OnceLock<Params> = OnceLock::new();
+    PARAMS.get_or_init(|| {
+        // Cauchy matrix 1 / (x_i - y_j) with x = 0..4, y = 4..8
+        let mut mds = [[BaseElement::ZERO; STATE_WIDTH]; STATE_WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (BaseElement::from(i as u64) - BaseElement::from((j + STATE_WIDTH) as u64)).inv();
+            }
+        }
+
+        let mut reader = blake3::Hasher::new().update(CONSTANTS_DOMAIN).finalize_xof();
+        let mut next_element = || loop {
+            let mut bytes = [0u8; 16];
+            reader.fill(&mut bytes);
+            let value = u128::from_le_bytes(bytes);
+            if value < BaseElement::MODULUS {
+                return BaseElement::new(value);
+            }
+        };
+        let round_constants = (0..NUM_ROUNDS)
+            .map(|_| {
+                let c1: State = core::array::from_fn(|_| next_element());
+                let c2: State = core::array::from_fn(|_| next_element());
+                (c1, c2)
+            })
+            .collect();
+
+        Params { mds, inv_mds: invert(&mds), round_constants }
+    })
+}
+
+/// Gauss-Jordan inversion; the Cauchy matrix is always invertible
+fn invert(matrix: &Matrix) -> Matrix {
+    let mut left = *matrix;
+    let mut right = [[BaseElement::ZERO; STATE_WIDTH]; STATE_WIDTH];
+    for (i, row) in right.iter_mut().enumerate() {
+        row[i] = BaseElement::ONE;
+    }
+    for col in 0..STATE_WIDTH {
+        let pivot = (col..STATE_WIDTH).find(|&r| left[r][col] != BaseElement::ZERO).expect("Matrix is invertible");
+        left.swap(col, pivot);
+        right.swap(col, pivot);
+        let scale = left[col][col].inv();
+        for j in 0..STATE_WIDTH {
+            left[col][j] *= scale;
+            right[col][j] *= scale;
+        }
+        for r in 0..STATE_WIDTH {
+            if r != col {
+                let factor = left[r][col];
+                for j in 0..STATE_WIDTH {
+                    left[r][j] -= factor * left[col][j];
+                    right[r][j] -= factor * right[col][j];
+                }
+            }
+        }
+    }
+    right
+}
+
+fn multiply<E: FieldElement + From<BaseElement>>(matrix: &Matrix, state: &[E]) -> [E; STATE_WIDTH] {
+    core::array::from_fn(|i| {
+        matrix[i].iter().zip(state).fold(E::ZERO, |acc, (&m, &s)| acc + E::from(m) * s)
+    })
+}
+
+/// Exponent of the inverse S-box: `3 * INV_ALPHA = 1 mod (p - 1)`, valid because `p - 1 = 1 mod 3`
+const INV_ALPHA: u128 = 2 * ((BaseElement::MODULUS - 1) / 3) + 1;
+
+/// Applies round `round` to `state`
+pub fn apply_round(state: &mut State, round: usize) {
+    let params = params();
+    let (c1, c2) = &params.round_constants[round];
+    let cubed = state.map(|s| s.cube());
+    let mut mid = multiply(&params.mds, &cubed);
+    for (m, &c) in mid.iter_mut().zip(c1) {
+        *m = (*m + c).exp(INV_ALPHA);
+    }
+    *state = multiply(&params.mds, &mid);
+    for (s, &c) in state.iter_mut().zip(c2) {
+        *s += c;
+    }
+}
+
+/// Runs the full permutation on `state`
+pub fn permute(state: &mut State) {
+    for round in 0..NUM_ROUNDS {
+        apply_round(state, round);
+    }
+}
+
+/// Initial sponge state for hashing `len` elements, domain separated by `tag`
+pub fn sponge_iv(len: usize, tag: u64) -> State {
+    [BaseElement::ZERO, BaseElement::from(len as u64), BaseElement::from(tag), BaseElement::ZERO]
+}
+
+/// Sponge over `elements`: permutes [`sponge_iv`], then adds each element to the first lane
+/// and permutes again. In a trace this is one cycle per element plus one.
+pub fn hash_elements(elements: &[BaseElement], tag: u64) -> BaseElement {
+    let mut state = sponge_iv(elements.len(), tag);
+    permute(&mut state);
+    for &element in elements {
+        state[0] += element;
+        permute(&mut state);
+    }
+    state[0]
+}
+
+/// Periodic columns expected by [`RescueGadget::evaluate`], each of length [`CYCLE_LENGTH`]
+pub fn periodic_columns() -> Vec<Vec<BaseElement>> {
+    let params = params();
+    let mut mask = vec![BaseElement::ONE; CYCLE_LENGTH];
+    mask[CYCLE_LENGTH - 1] = BaseElement::ZERO;
+
+    let mut columns = vec![mask];
+    for set in 0..2 {
+        for i in 0..STATE_WIDTH {
+            let mut column: Vec<BaseElement> = params
+                .round_constants
+                .iter()
+                .map(|(c1, c2)| if set == 0 { c1[i] } else { c2[i] })
+                .collect();
+            column.push(BaseElement::ZERO);
+            columns.push(column);
+        }
+    }
+    columns
+}
+
+/// Permutation state held in [`STATE_WIDTH`] consecutive trace columns.
+///
+/// Constrains row `r` of every cycle to be round `r` applied to row `r - 1`; the owning AIR
+/// decides what the state is at the first row of each cycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RescueGadget {
+    first_column: usize,
+}
+
+impl RescueGadget {
+    pub fn new(first_column: usize) -> Self {
+        Self { first_column }
+    }
+
+    pub fn width(&self) -> usize {
+        STATE_WIDTH
+    }
+
+    pub fn next_column(&self) -> usize {
+        self.first_column + STATE_WIDTH
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        STATE_WIDTH
+    }
+
+    pub fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        vec![TransitionConstraintDegree::with_cycles(3, vec![CYCLE_LENGTH]); STATE_WIDTH]
+    }
+
+    pub fn state<E: Copy>(&self, row: &[E]) -> [E; STATE_WIDTH] {
+        core::array::from_fn(|i| row[self.first_column + i])
+    }
+
+    pub fn write(&self, row: &mut [BaseElement], state: &State) {
+        row[self.first_column..self.next_column()].copy_from_slice(state);
+    }
+
+    /// Writes the round constraints into `result`; `periodic` holds the values of the columns
+    /// returned by [`periodic_columns`] for the current row
+    pub fn evaluate<E: FieldElement + From<BaseElement>>(
+        &self,
+        current: &[E],
+        next: &[E],
+        periodic: &[E],
+        result: &mut [E],
+    ) {
+        let params = params();
+        let mask = periodic[0];
+        let (c1, c2) = periodic[1..NUM_PERIODIC_COLUMNS].split_at(STATE_WIDTH);
+
+        let cubed = self.state(current).map(|s| s * s * s);
+        let forward = multiply(&params.mds, &cubed);
+        let shifted: [E; STATE_WIDTH] = core::array::from_fn(|i| next[self.first_column + i] - c2[i]);
+        let backward = multiply(&params.inv_mds, &shifted);
+
+        for i in 0..STATE_WIDTH {
+            result[i] = mask * (backward[i] * backward[i] * backward[i] - forward[i] - c1[i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds_satisfy_gadget() {
+        let mds = params().mds;
+        let identity = multiply(&mds, &invert(&mds).map(|row| row[0]));
+        assert_eq!(identity, [BaseElement::ONE, BaseElement::ZERO, BaseElement::ZERO, BaseElement::ZERO]);
+        assert_eq!(BaseElement::new(5).cube().exp(INV_ALPHA), BaseElement::new(5));
+
+        let gadget = RescueGadget::new(1);
+        let periodic = periodic_columns();
+        let mut state = [1u128, 2, 3, 4].map(BaseElement::new);
+        let mut current = vec![BaseElement::ZERO; gadget.next_column()];
+        gadget.write(&mut current, &state);
+
+        for round in 0..NUM_ROUNDS {
+            apply_round(&mut state, round);
+            let mut next = vec![BaseElement::ZERO; gadget.next_column()];
+            gadget.write(&mut next, &state);
+
+            let values: Vec<BaseElement> = periodic.iter().map(|column| column[round]).collect();
+            let mut result = [BaseElement::ONE; STATE_WIDTH];
+            gadget.evaluate(&current, &next, &values, &mut result);
+            assert_eq!(result, [BaseElement::ZERO; STATE_WIDTH]);
+            current = next;
+        }
+
+        let mut again = [1u128, 2, 3, 4].map(BaseElement::new);
+        permute(&mut again);
+        assert_eq!(again, state);
+    }
+}
@@ -0,0 +1,164 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    TransitionConstraintDegree,
+};
+
+/// One-hot phase columns splitting a trace into consecutive phases.
+///
+/// Each row is in exactly one of `num_phases` phases, and phases only advance: a row in phase
+/// `p` is followed by a row in phase `p` or `p + 1`, so the trace reads phase 0, then phase 1
+/// and so on. A single assertion pinning the phase of one row therefore fixes the phase of
+/// every earlier and later row up to the phase boundaries. The owning AIR gates its own
+/// constraints by multiplying them with [`PhaseSelector::gate`], which adds one to their degree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PhaseSelector {
+    first_column: usize,
+    num_phases: usize,
+}
+
+impl PhaseSelector {
+    pub const fn new(first_column: usize, num_phases: usize) -> Self {
+        assert!(num_phases >= 2, "A phase selector needs at least two phases");
+        Self { first_column, num_phases }
+    }
+
+    /// Column of the flag of `phase`
+    pub fn column(&self, phase: usize) -> usize {
+        assert!(phase < self.num_phases, "Phase {phase} out of range");
+        self.first_column + phase
+    }
+
+    /// Number of phase columns owned by this gadget
+    pub fn width(&self) -> usize {
+        self.num_phases
+    }
+
+    /// First column after the ones owned by this gadget
+    pub fn next_column(&self) -> usize {
+        self.first_column + self.num_phases
+    }
+
+    /// Booleanity of every flag, the one-hot sum and one ordering constraint per phase
+    pub fn num_constraints(&self) -> usize {
+        2 * self.num_phases + 1
+    }
+
+    pub fn constraint_degrees(&self) -> Vec<TransitionConstraintDegree> {
+        let mut degrees = vec![TransitionConstraintDegree::new(2); self.num_phases];
+        degrees.push(TransitionConstraintDegree::new(1));
+        degrees.extend(vec![TransitionConstraintDegree::new(2); self.num_phases]);
+        degrees
+    }
+
+    /// Labels of the constraints in evaluation order: `phase_{p}_boolean` for every phase,
+    /// `phase_one_hot`, then `phase_{p}_order` for every phase
+    pub fn constraint_labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = (0..self.num_phases).map(|phase| format!("phase_{phase}_boolean")).collect();
+        labels.push("phase_one_hot".into());
+        labels.extend((0..self.num_phases).map(|phase| format!("phase_{phase}_order")));
+        labels
+    }
+
+    /// Degree of a constraint of degree `degree` after gating it with [`PhaseSelector::gate`]
+    pub fn gated_degree(degree: usize) -> TransitionConstraintDegree {
+        TransitionConstraintDegree::new(degree + 1)
+    }
+
+    /// Flag of `phase` in `row`: one when the row is in that phase, zero otherwise
+    pub fn flag<E: FieldElement>(&self, row: &[E], phase: usize) -> E {
+        row[self.column(phase)]
+    }
+
+    /// `constraint` enforced only on rows in one of `phases`
+    pub fn gate<E: FieldElement>(&self, row: &[E], phases: &[usize], constraint: E) -> E {
+        phases.iter().fold(E::ZERO, |sum, &phase| sum + self.flag(row, phase)) * constraint
+    }
+
+    pub fn evaluate<E: FieldElement>(&self, current: &[E], next: &[E], result: &mut [E]) {
+        let flags = &current[self.first_column..self.next_column()];
+        let next_flags = &next[self.first_column..self.next_column()];
+        let (booleanity, rest) = result.split_at_mut(self.num_phases);
+        for (r, &flag) in booleanity.iter_mut().zip(flags) {
+            *r = flag * (flag - E::ONE);
+        }
+        rest[0] = flags.iter().fold(E::ZERO, |sum, &flag| sum + flag) - E::ONE;
+
+        // a row in phase p is followed by one in phase p or p + 1
+        let last = self.num_phases - 1;
+        for (phase, r) in rest[1..=self.num_phases].iter_mut().enumerate() {
+            let allowed = if phase < last { next_flags[phase] + next_flags[phase + 1] } else { next_flags[last] };
+            *r = flags[phase] * (allowed - E::ONE);
+        }
+    }
+
+    pub fn is_active(&self, row: &[BaseElement], phase: usize) -> bool {
+        row[self.column(phase)] == BaseElement::ONE
+    }
+
+    /// Writes the flags of a row in `phase`
+    pub fn fill(&self, row: &mut [BaseElement], phase: usize) {
+        let column = self.column(phase);
+        for (i, flag) in row[self.first_column..self.next_column()].iter_mut().enumerate() {
+            *flag = if self.first_column + i == column { BaseElement::ONE } else { BaseElement::ZERO };
+        }
+    }
+
+    /// Fills the exempt last row after a row in `previous_phase`.
+    ///
+    /// The row moves on to the next phase, if any, so the ordering constraint of the previous
+    /// step holds and the last phase is reached at least once; every flag the previous step
+    /// does not read is set to 2, which breaks booleanity and the one-hot sum on a row where
+    /// they are not enforced. With two phases a previous row in the first phase leaves no
+    /// flag to break, so such traces should end in the last phase before the exempt row.
+    pub fn fill_exempt_row(&self, row: &mut [BaseElement], previous_phase: usize) {
+        self.fill(row, (previous_phase + 1).min(self.num_phases - 1));
+        for phase in (0..self.num_phases).filter(|&p| p != previous_phase && p != previous_phase + 1) {
+            row[self.column(phase)] = BaseElement::new(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_constraints() {
+        let selector = PhaseSelector::new(1, 3);
+        let mut current = vec![BaseElement::new(9); selector.next_column()];
+        let mut next = current.clone();
+        let mut result = vec![BaseElement::ONE; selector.num_constraints()];
+        let all_zero = |result: &[BaseElement]| result.iter().all(|r| *r == BaseElement::ZERO);
+
+        for (from, to) in [(0, 0), (0, 1), (1, 2), (2, 2)] {
+            selector.fill(&mut current, from);
+            selector.fill(&mut next, to);
+            selector.evaluate(&current, &next, &mut result);
+            assert!(all_zero(&result), "{from} -> {to}");
+        }
+        for (from, to) in [(0, 2), (1, 0), (2, 1)] {
+            selector.fill(&mut current, from);
+            selector.fill(&mut next, to);
+            selector.evaluate(&current, &next, &mut result);
+            assert!(!all_zero(&result), "{from} -> {to}");
+        }
+
+        // two phases at once
+        selector.fill(&mut current, 0);
+        current[selector.column(1)] = BaseElement::ONE;
+        selector.fill(&mut next, 1);
+        selector.evaluate(&current, &next, &mut result);
+        assert_ne!(result[selector.width()], BaseElement::ZERO);
+
+        selector.fill(&mut current, 2);
+        assert!(selector.is_active(&current, 2));
+        assert_eq!(selector.gate(&current, &[0, 1], BaseElement::new(5)), BaseElement::ZERO);
+        assert_eq!(selector.gate(&current, &[1, 2], BaseElement::new(5)), BaseElement::new(5));
+
+        // the exempt row keeps the last enforced step valid
+        selector.fill_exempt_row(&mut next, 2);
+        selector.evaluate(&current, &next, &mut result);
+        assert!(all_zero(&result[selector.width() + 1..]));
+        assert_eq!(next[selector.column(0)], BaseElement::new(2));
+    }
+}
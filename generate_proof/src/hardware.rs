@@ -0,0 +1,379 @@
+//! Claim attestations by keys held in hardware: YubiKeys, HSMs and other PKCS#11 tokens.
+//!
+//! An [`OperatorKey`](crate::operator::OperatorKey) is a seed on the prover's disk, so its
+//! signature says which configuration produced a proof, not who controlled the machine. A
+//! [`HardwareSigner`] keeps its Ed25519 key on a device that never releases it; the device
+//! signs [`ATTESTATION_DOMAIN`] followed by the envelope's signed hash, and the resulting
+//! [`HardwareAttestation`] travels in the envelope next to the operator signature. Verifiers
+//! that only accept proofs from enrolled devices list their keys in
+//! [`VerificationPolicy::trusted_hardware_keys`](crate::config::VerificationPolicy::trusted_hardware_keys).
+//!
+//! [`Pkcs11Signer`] drives any PKCS#11 module through OpenSC's `pkcs11-tool` with the `EDDSA`
+//! mechanism, e.g. `libykcs11` for a YubiKey (firmware 5.7 or later) or a network HSM's
+//! client library. Devices with their own transport, such as a Ledger app, implement
+//! [`HardwareSigner`] directly. Like the operator signature, the attestation covers the claim
+//! and the provenance, see [`ProofEnvelope::signed_hash`], and not the proof bytes; the
+//! on-chain encoding does not carry it.
+
+use core::fmt;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::abi::{from_hex, to_hex};
+use crate::domain::DomainTag;
+use crate::envelope::ProofEnvelope;
+use crate::operator::{OperatorPublicKey, SIGNATURE_BYTES};
+
+/// Prefix of every message a device signs; differs from the operator signing domain so neither
+/// signature can be replayed as the other
+pub const ATTESTATION_DOMAIN: &[u8] = b"stark-framework/hardware-attestation/v1";
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, followed by the 32-byte key
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+/// Kind of device that made an attestation, recorded for display and audits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceKind {
+    Pkcs11,
+    Ledger,
+    Other,
+}
+
+impl fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeviceKind::Pkcs11 => "PKCS#11 token",
+            DeviceKind::Ledger => "Ledger",
+            DeviceKind::Other => "hardware device",
+        })
+    }
+}
+
+/// Ed25519 signature made by a device, serialized as hex
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DeviceSignature(pub [u8; SIGNATURE_BYTES]);
+
+impl TryFrom<String> for DeviceSignature {
+    type Error = HardwareError;
+
+    fn try_from(hex: String) -> Result<Self, HardwareError> {
+        let bytes = from_hex(&hex).and_then(|bytes| bytes.try_into().ok()).ok_or(HardwareError::BadSignature)?;
+        Ok(Self(bytes))
+    }
+}
+
+impl From<DeviceSignature> for String {
+    fn from(signature: DeviceSignature) -> Self {
+        to_hex(&signature.0)
+    }
+}
+
+/// A device's signature over a claim hash together with the key that made it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardwareAttestation {
+    pub device: DeviceKind,
+    pub public_key: OperatorPublicKey,
+    pub signature: DeviceSignature,
+}
+
+impl HardwareAttestation {
+    /// Checks the signature over `claim_hash`
+    pub fn verify(&self, claim_hash: &[u8; 32]) -> Result<(), HardwareError> {
+        let key = VerifyingKey::from_bytes(&self.public_key.0).map_err(|_| HardwareError::InvalidKey)?;
+        key.verify_strict(&attested_message(claim_hash), &Signature::from_bytes(&self.signature.0))
+            .map_err(|_| HardwareError::BadSignature)
+    }
+}
+
+fn attested_message(claim_hash: &[u8; 32]) -> Vec<u8> {
+    [ATTESTATION_DOMAIN, claim_hash].concat()
+}
+
+/// Reason a device could not attest a claim or an attestation is not accepted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HardwareError {
+    /// The device or its driver could not be reached
+    Unavailable(String),
+    /// The device refused or failed the operation, e.g. a wrong PIN or a missing key
+    Device(String),
+    /// Not a 32-byte Ed25519 key, or not a point on the curve
+    InvalidKey,
+    BadSignature,
+    Missing,
+    /// Attested by a device the verifier has not enrolled
+    Untrusted(OperatorPublicKey),
+}
+
+impl fmt::Display for HardwareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HardwareError::Unavailable(err) => write!(f, "hardware signer unavailable: {err}"),
+            HardwareError::Device(err) => write!(f, "hardware signer failed: {err}"),
+            HardwareError::InvalidKey => write!(f, "invalid hardware key"),
+            HardwareError::BadSignature => write!(f, "hardware attestation does not match the claim"),
+            HardwareError::Missing => write!(f, "envelope carries no hardware attestation"),
+            HardwareError::Untrusted(key) => write!(f, "envelope was attested by unenrolled hardware key {key}"),
+        }
+    }
+}
+
+impl std::error::Error for HardwareError {}
+
+/// A device that signs messages with an Ed25519 key it does not export
+pub trait HardwareSigner: Send {
+    fn device(&self) -> DeviceKind;
+    fn public_key(&self) -> Result<OperatorPublicKey, HardwareError>;
+    /// Ed25519 signature over `message`, possibly after the device asks its holder to confirm
+    fn sign(&self, message: &[u8]) -> Result<[u8; SIGNATURE_BYTES], HardwareError>;
+}
+
+/// Has `signer` attest `claim_hash`, checking what the device returns before using it
+pub fn attest(signer: &dyn HardwareSigner, claim_hash: &[u8; 32]) -> Result<HardwareAttestation, HardwareError> {
+    let public_key = signer.public_key()?;
+    let signature = DeviceSignature(signer.sign(&attested_message(claim_hash))?);
+    let attestation = HardwareAttestation { device: signer.device(), public_key, signature };
+    attestation.verify(claim_hash)?;
+    Ok(attestation)
+}
+
+/// Has `signer` attest the signed hash of `envelope` under deployment `domain` into the envelope
+pub fn attest_envelope(
+    signer: &dyn HardwareSigner,
+    envelope: &mut ProofEnvelope,
+    domain: &DomainTag,
+) -> Result<(), HardwareError> {
+    envelope.attestation = Some(attest(signer, &envelope.signed_hash(domain))?);
+    Ok(())
+}
+
+/// The device key that attested `envelope` for deployment `domain`, or `None` without an
+/// attestation
+pub fn verify_attestation(
+    envelope: &ProofEnvelope,
+    domain: &DomainTag,
+) -> Result<Option<OperatorPublicKey>, HardwareError> {
+    match &envelope.attestation {
+        Some(attestation) => {
+            attestation.verify(&envelope.signed_hash(domain))?;
+            Ok(Some(attestation.public_key))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Checks that `envelope` is validly attested by one of the `enrolled` device keys
+pub fn require_attestation(
+    envelope: &ProofEnvelope,
+    domain: &DomainTag,
+    enrolled: &[OperatorPublicKey],
+) -> Result<OperatorPublicKey, HardwareError> {
+    match verify_attestation(envelope, domain)? {
+        Some(key) if enrolled.contains(&key) => Ok(key),
+        Some(key) => Err(HardwareError::Untrusted(key)),
+        None => Err(HardwareError::Missing),
+    }
+}
+
+/// Environment variable the PIN of a [`Pkcs11Signer`] reaches `pkcs11-tool` in, read through
+/// its `--pin env:NAME` form
+pub const PIN_VARIABLE: &str = "STARK_PKCS11_PIN";
+
+/// An Ed25519 key on a PKCS#11 token, used through OpenSC's `pkcs11-tool`
+#[derive(Clone)]
+pub struct Pkcs11Signer {
+    module: PathBuf,
+    key_id: String,
+    pin: Option<String>,
+    tool: PathBuf,
+}
+
+impl Pkcs11Signer {
+    /// The key pair with hex id `key_id` on the token of PKCS#11 module `module`
+    pub fn new(module: impl Into<PathBuf>, key_id: impl Into<String>) -> Self {
+        Self { module: module.into(), key_id: key_id.into(), pin: None, tool: PathBuf::from("pkcs11-tool") }
+    }
+
+    /// Logs into the token with `pin` before signing, handing it to the tool in [`PIN_VARIABLE`]
+    pub fn with_pin(mut self, pin: impl Into<String>) -> Self {
+        self.pin = Some(pin.into());
+        self
+    }
+
+    /// Runs `tool` instead of the `pkcs11-tool` on the `PATH`
+    pub fn with_tool(mut self, tool: impl Into<PathBuf>) -> Self {
+        self.tool = tool.into();
+        self
+    }
+
+    /// Runs the tool with `args`, writing `input` to its standard input, and returns its output.
+    /// The PIN, if any, is passed in [`PIN_VARIABLE`] rather than on the command line, where
+    /// every user of the machine could read it.
+    fn run(&self, args: &[&str], input: &[u8]) -> Result<Vec<u8>, HardwareError> {
+        let mut command = Command::new(&self.tool);
+        command.arg("--module").arg(&self.module).args(["--id", &self.key_id]).args(args);
+        if let Some(pin) = &self.pin {
+            command.env(PIN_VARIABLE, pin);
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| HardwareError::Unavailable(format!("{}: {err}", self.tool.display())))?;
+        let mut stdin = child.stdin.take().expect("The tool's standard input is piped");
+        // a tool failing before it reads the message closes the pipe; its exit status says why
+        match stdin.write_all(input) {
+            Err(err) if err.kind() != ErrorKind::BrokenPipe => return Err(HardwareError::Unavailable(err.to_string())),
+            _ => drop(stdin),
+        }
+        let output = child.wait_with_output().map_err(|err| HardwareError::Unavailable(err.to_string()))?;
+        if !output.status.success() {
+            return Err(HardwareError::Device(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl fmt::Debug for Pkcs11Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs11Signer").field("module", &self.module).field("key_id", &self.key_id).finish()
+    }
+}
+
+impl HardwareSigner for Pkcs11Signer {
+    fn device(&self) -> DeviceKind {
+        DeviceKind::Pkcs11
+    }
+
+    fn public_key(&self) -> Result<OperatorPublicKey, HardwareError> {
+        parse_public_key(&self.run(&["--read-object", "--type", "pubkey"], &[])?)
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<[u8; SIGNATURE_BYTES], HardwareError> {
+        let mut args = vec!["--sign", "--mechanism", "EDDSA"];
+        let pin = format!("env:{PIN_VARIABLE}");
+        if self.pin.is_some() {
+            args.extend(["--login", "--pin", &pin]);
+        }
+        let signature = self.run(&args, message)?;
+        signature.try_into().map_err(|_| HardwareError::BadSignature)
+    }
+}
+
+/// The key of a DER `SubjectPublicKeyInfo`, of a DER octet string or of 32 raw bytes, the
+/// forms tokens export Ed25519 public keys in
+fn parse_public_key(bytes: &[u8]) -> Result<OperatorPublicKey, HardwareError> {
+    let key = match bytes {
+        _ if bytes.starts_with(&ED25519_SPKI_PREFIX) => &bytes[ED25519_SPKI_PREFIX.len()..],
+        [0x04, 0x20, key @ ..] => key,
+        key => key,
+    };
+    let key: [u8; 32] = key.try_into().map_err(|_| HardwareError::InvalidKey)?;
+    VerifyingKey::from_bytes(&key).map_err(|_| HardwareError::InvalidKey)?;
+    Ok(OperatorPublicKey(key))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use crate::config::VerificationPolicy;
+    use crate::session::{verify_linear, LinearClaimRequest, ProvingSession, SessionError};
+
+    /// A software key standing in for a device
+    struct SoftToken(SigningKey);
+
+    impl HardwareSigner for SoftToken {
+        fn device(&self) -> DeviceKind {
+            DeviceKind::Other
+        }
+
+        fn public_key(&self) -> Result<OperatorPublicKey, HardwareError> {
+            Ok(OperatorPublicKey(self.0.verifying_key().to_bytes()))
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<[u8; SIGNATURE_BYTES], HardwareError> {
+            Ok(self.0.sign(message).to_bytes())
+        }
+    }
+
+    #[test]
+    fn test_policy_requires_enrolled_device() {
+        let token = SoftToken(SigningKey::from_bytes(&[7; 32]));
+        let enrolled = token.public_key().unwrap();
+        let request = LinearClaimRequest {
+            model: "attested".into(),
+            salt: to_hex(&[3; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let mut session = ProvingSession::new(Default::default(), "test").with_hardware_signer(Box::new(token));
+        let envelope = session.prove_linear(&request).unwrap();
+        let decoded = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(decoded.attestation, envelope.attestation);
+
+        let policy = VerificationPolicy { trusted_hardware_keys: vec![enrolled], ..Default::default() };
+        verify_linear(&decoded, &policy).unwrap();
+        let other = OperatorPublicKey(SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes());
+        let elsewhere = VerificationPolicy { trusted_hardware_keys: vec![other], ..Default::default() };
+        let untrusted = verify_linear(&decoded, &elsewhere);
+        assert!(matches!(untrusted, Err(SessionError::Hardware(HardwareError::Untrusted(key))) if key == enrolled));
+
+        let mut stripped = decoded.clone();
+        stripped.attestation = None;
+        assert!(matches!(verify_linear(&stripped, &policy), Err(SessionError::Hardware(HardwareError::Missing))));
+        // an attestation does not carry over to another claim
+        let mut moved = decoded;
+        moved.public_inputs[0] += winterfell::math::FieldElement::ONE;
+        let moved_attestation = moved.attestation.clone().unwrap();
+        assert_eq!(moved_attestation.verify(&moved.signed_hash(&policy.domain)), Err(HardwareError::BadSignature));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pkcs11_signer_drives_the_tool() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let key = SigningKey::from_bytes(&[9; 32]).verifying_key().to_bytes();
+        let dir = std::env::temp_dir().join(format!("stark-pkcs11-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spki: String = ED25519_SPKI_PREFIX.iter().chain(&key).map(|byte| format!("\\{byte:03o}")).collect();
+        // prints the key on `--read-object` and a signature of zeros on `--sign` when logged in with
+        // the PIN from the environment, which must not be on the command line
+        let script = format!(
+            "#!/bin/sh\ncase \"$*\" in\n*123456*) echo 'PIN on the command line' >&2; exit 1 ;;\n\
+             *--read-object*) printf '{spki}' ;;\n\
+             *'--login --pin env:{PIN_VARIABLE}'*) [ \"${PIN_VARIABLE}\" = 123456 ] && head -c 64 /dev/zero ;;\n\
+             *) echo 'CKR_USER_NOT_LOGGED_IN' >&2; exit 1 ;;\nesac\n"
+        );
+        let tool = dir.join("pkcs11-tool");
+        std::fs::write(&tool, script).unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let signer = Pkcs11Signer::new("/usr/lib/libykcs11.so", "01").with_tool(&tool);
+        assert_eq!(signer.public_key(), Ok(OperatorPublicKey(key)));
+        assert_eq!(signer.sign(b"claim"), Err(HardwareError::Device("CKR_USER_NOT_LOGGED_IN".into())));
+        // a device returning a signature that does not verify is not trusted
+        let signer = signer.with_pin("123456");
+        assert_eq!(signer.sign(b"claim"), Ok([0; SIGNATURE_BYTES]));
+        assert_eq!(attest(&signer, &[1; 32]), Err(HardwareError::BadSignature));
+        let missing = Pkcs11Signer::new("module.so", "01").with_tool(dir.join("missing"));
+        assert!(matches!(missing.public_key(), Err(HardwareError::Unavailable(_))));
+
+        assert_eq!(parse_public_key(&[[0x04, 0x20].as_slice(), &key].concat()), Ok(OperatorPublicKey(key)));
+        assert_eq!(parse_public_key(&key[1..]), Err(HardwareError::InvalidKey));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+//! The HTTP client of the network backends, feature `http`.
+//!
+//! Requests go through one [`ureq`] agent with rustls and the webpki roots, which handles
+//! `https://`, chunked transfer encoding and keep-alive. Redirects are not followed, and a
+//! request carrying credentials, an `Authorization` or `X-Amz-Security-Token` header, is
+//! refused unless its URL is `https://`.
+
+use core::fmt;
+use std::{
+    io::{self, Read},
+    sync::OnceLock,
+    time::Duration,
+};
+
+/// Headers whose values are secrets
+const CREDENTIAL_HEADERS: [&str; 2] = ["authorization", "x-amz-security-token"];
+
+/// Reason a request could not be sent or its response not be read
+#[derive(Debug)]
+pub enum HttpError {
+    /// The URL is not an `http://` or `https://` URL
+    UnsupportedUrl(String),
+    /// The request carries credentials and its URL is not `https://`
+    InsecureUrl(String),
+    /// The response body is longer than the caller's limit
+    TooLarge(u64),
+    Io(io::Error),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::UnsupportedUrl(url) => write!(f, "unsupported URL {url:?}"),
+            HttpError::InsecureUrl(url) => write!(f, "refusing to send credentials to {url:?} without TLS"),
+            HttpError::TooLarge(limit) => write!(f, "response is longer than {limit} bytes"),
+            HttpError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl From<io::Error> for HttpError {
+    fn from(err: io::Error) -> Self {
+        HttpError::Io(err)
+    }
+}
+
+fn agent() -> &'static ureq::Agent {
+    static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+    AGENT.get_or_init(|| ureq::AgentBuilder::new().redirects(0).build())
+}
+
+/// Sends `method` with `headers` and `body` to `url`, allowing `timeout` for the whole exchange,
+/// and returns the status and the response body of at most `limit` bytes
+pub fn send(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    timeout: Duration,
+    limit: u64,
+) -> Result<(u16, Vec<u8>), HttpError> {
+    if !url.starts_with("https://") {
+        if !url.starts_with("http://") {
+            return Err(HttpError::UnsupportedUrl(url.to_string()));
+        }
+        if headers.iter().any(|(name, _)| CREDENTIAL_HEADERS.contains(&name.to_ascii_lowercase().as_str())) {
+            return Err(HttpError::InsecureUrl(url.to_string()));
+        }
+    }
+    let mut request = agent().request(method, url).timeout(timeout);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    let response = match request.send_bytes(body) {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(ureq::Error::Transport(err)) if err.kind() == ureq::ErrorKind::InvalidUrl => {
+            return Err(HttpError::UnsupportedUrl(url.to_string()));
+        }
+        Err(ureq::Error::Transport(err)) => return Err(io::Error::other(err.to_string()).into()),
+    };
+    let status = response.status();
+    let mut bytes = Vec::new();
+    response.into_reader().take(limit + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > limit {
+        return Err(HttpError::TooLarge(limit));
+    }
+    Ok((status, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Answers one request with `response` and returns the server's URL
+    fn serve_once(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{address}/")
+    }
+
+    #[test]
+    fn test_decodes_chunked_responses() {
+        let url = serve_once("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nSTKE\r\n3\r\nabc\r\n0\r\n\r\n");
+        let timeout = Duration::from_secs(5);
+        assert_eq!(send("GET", &url, &[], &[], timeout, 64).unwrap(), (200, b"STKEabc".to_vec()));
+
+        let url = serve_once("HTTP/1.1 404 Not Found\r\nContent-Length: 3\r\n\r\nnop");
+        assert!(matches!(send("GET", &url, &[], &[], timeout, 2), Err(HttpError::TooLarge(2))));
+    }
+
+    #[test]
+    fn test_refuses_credentials_without_tls() {
+        let timeout = Duration::from_secs(5);
+        let bearer = [("Authorization", "Bearer secret")];
+        let err = send("GET", "http://127.0.0.1:9/object", &bearer, &[], timeout, 64).unwrap_err();
+        assert!(matches!(err, HttpError::InsecureUrl(_)), "{err}");
+        let token = [("X-Amz-Security-Token", "secret")];
+        assert!(matches!(send("PUT", "http://127.0.0.1:9/", &token, b"", timeout, 64), Err(HttpError::InsecureUrl(_))));
+        assert!(matches!(send("GET", "ftp://host/object", &[], &[], timeout, 64), Err(HttpError::UnsupportedUrl(_))));
+    }
+}
@@ -0,0 +1,456 @@
+//! Feature importance claims: the ranking of the private coefficients of a committed model by
+//! magnitude.
+//!
+//! [`ImportanceAir`] proves a public ranking `r_0, ..., r_{k-1}` of parameters of a model
+//! committed as [`parameters_commitment`], so the same commitment backs sensitivity claims:
+//! `|w[r_0]| >= |w[r_1]| >= ... >= |w[r_{k-1}]|`, and no other parameter has a larger magnitude
+//! than `w[r_{k-1}]`. A ranking of one parameter states "feature 3 has the largest weight".
+//! Parameters the claim ignores, such as the intercept, take part in neither comparison.
+//!
+//! The trace walks the parameters in commitment order, one [`CYCLE_LENGTH`]-row cycle each, and
+//! splits every parameter into a sign and a magnitude range checked to [`MAGNITUDE_BITS`] bits.
+//! One threshold column per ranked position stays constant over the whole trace; per-cycle role
+//! flags, pinned by assertions, tie the threshold of a ranked position to the magnitude of its
+//! parameter and select the comparison the cycle range checks: a ranked magnitude against the
+//! next threshold, or the last threshold against an unranked magnitude.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+#[cfg(feature = "prover")]
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{IntegerColumn, RangeCheck};
+use crate::layout::LayoutDescriptor;
+#[cfg(feature = "prover")]
+use crate::sensitivity::parameters_commitment;
+use crate::sensitivity::{sponge_start, trace_length};
+
+/// Identifier of [`ImportanceAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "feature-importance/v1";
+
+/// Width of the range checked magnitudes and their differences; `|i64::MIN|` needs all 64 bits
+pub const MAGNITUDE_BITS: usize = 64;
+
+/// Most parameters a ranking may name, keeping the trace within Winterfell's width limit
+pub const MAX_RANKED: usize = 64;
+
+const DIGIT_BITS: usize = 2;
+
+// Trace layout: sponge state, the cycle's parameter, its sign and magnitude, one threshold and
+// one role flag per ranked position, the ignored flag, then the digits of the magnitude and of
+// the compared gap
+const SPONGE: usize = 0;
+const PARAMETER: usize = SPONGE + STATE_WIDTH;
+const SIGN: usize = PARAMETER + 1;
+const MAGNITUDE: usize = SIGN + 1;
+const THRESHOLDS: usize = MAGNITUDE + 1;
+
+struct Layout {
+    num_ranked: usize,
+    sponge: RescueGadget,
+    magnitude: IntegerColumn,
+    gap: RangeCheck,
+}
+
+impl Layout {
+    fn new(num_ranked: usize) -> Self {
+        let digits = THRESHOLDS + 2 * num_ranked + 1;
+        let magnitude = IntegerColumn::new(MAGNITUDE, digits, MAGNITUDE_BITS, DIGIT_BITS);
+        let gap = RangeCheck::with_digit_bits(magnitude.next_column(), MAGNITUDE_BITS, DIGIT_BITS);
+        Self { num_ranked, sponge: RescueGadget::new(SPONGE), magnitude, gap }
+    }
+
+    fn threshold(&self, position: usize) -> usize {
+        THRESHOLDS + position
+    }
+
+    fn ranked_flag(&self, position: usize) -> usize {
+        THRESHOLDS + self.num_ranked + position
+    }
+
+    fn ignored_flag(&self) -> usize {
+        THRESHOLDS + 2 * self.num_ranked
+    }
+
+    fn trace_width(&self) -> usize {
+        self.gap.next_column()
+    }
+}
+
+/// Number of trace columns for a ranking of `num_ranked` parameters
+pub fn trace_width(num_ranked: usize) -> usize {
+    Layout::new(num_ranked).trace_width()
+}
+
+/// Named columns of the trace for a ranking of `num_ranked` parameters, see [`crate::layout`]
+pub fn layout(num_ranked: usize) -> LayoutDescriptor {
+    let layout = Layout::new(num_ranked);
+    let digits = layout.ignored_flag() + 1;
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..PARAMETER)
+        .with_column("parameter", PARAMETER)
+        .with_column("sign", SIGN)
+        .with_column("magnitude", MAGNITUDE)
+        .with_columns("thresholds", THRESHOLDS..layout.ranked_flag(0))
+        .with_columns("ranked_flags", layout.ranked_flag(0)..layout.ignored_flag())
+        .with_column("ignored_flag", layout.ignored_flag())
+        .with_columns("magnitude_digits", digits..layout.magnitude.next_column())
+        .with_columns("gap_digits", layout.magnitude.next_column()..layout.gap.next_column())
+}
+
+/// Indices of `parameters` from the largest magnitude to the smallest, ties in index order
+pub fn rank_by_magnitude(parameters: &[i64]) -> Vec<usize> {
+    let mut ranking: Vec<usize> = (0..parameters.len()).collect();
+    ranking.sort_by_key(|&i| core::cmp::Reverse(parameters[i].unsigned_abs()));
+    ranking
+}
+
+/// Reason a model does not satisfy a feature importance claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportanceError {
+    /// The claim ranks no parameter
+    NoRanking,
+    TooManyRanked { ranked: usize, max: usize },
+    /// The claim names a parameter the model does not have
+    UnknownParameter { index: usize, num_parameters: usize },
+    /// The claim names a parameter twice, in the ranking or among the ignored ones
+    DuplicateParameter { index: usize },
+    /// The parameter ranked at `position` outweighs the one ranked before it
+    OutOfOrder { position: usize },
+    /// Unranked parameter `index` outweighs the last ranked one
+    Outranked { index: usize },
+}
+
+impl fmt::Display for ImportanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportanceError::NoRanking => write!(f, "the claim ranks no parameter"),
+            ImportanceError::TooManyRanked { ranked, max } => {
+                write!(f, "the claim ranks {ranked} parameters, at most {max} are supported")
+            }
+            ImportanceError::UnknownParameter { index, num_parameters } => {
+                write!(f, "parameter {index} does not exist in a model of {num_parameters} parameters")
+            }
+            ImportanceError::DuplicateParameter { index } => write!(f, "parameter {index} is named twice"),
+            // the magnitudes themselves stay private
+            ImportanceError::OutOfOrder { position } => {
+                write!(f, "the parameter ranked at position {position} outweighs the one before it")
+            }
+            ImportanceError::Outranked { index } => {
+                write!(f, "unranked parameter {index} outweighs the last ranked parameter")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportanceError {}
+
+/// Public inputs: the parameters commitment, the number of parameters, the ranked parameters
+/// from the largest magnitude down and the parameters left out of the comparison
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportanceInputs {
+    pub commitment: BaseElement,
+    pub num_parameters: usize,
+    pub ranking: Vec<usize>,
+    pub ignored: Vec<usize>,
+}
+
+impl ImportanceInputs {
+    fn check(&self) -> Result<(), ImportanceError> {
+        if self.ranking.is_empty() {
+            return Err(ImportanceError::NoRanking);
+        }
+        if self.ranking.len() > MAX_RANKED {
+            return Err(ImportanceError::TooManyRanked { ranked: self.ranking.len(), max: MAX_RANKED });
+        }
+        let mut named = vec![false; self.num_parameters];
+        for &index in self.ranking.iter().chain(&self.ignored) {
+            let seen = named
+                .get_mut(index)
+                .ok_or(ImportanceError::UnknownParameter { index, num_parameters: self.num_parameters })?;
+            if core::mem::replace(seen, true) {
+                return Err(ImportanceError::DuplicateParameter { index });
+            }
+        }
+        Ok(())
+    }
+
+    /// Ranked position of every parameter, `None` for unranked ones
+    fn positions(&self) -> Vec<Option<usize>> {
+        let mut positions = vec![None; self.num_parameters];
+        for (position, &index) in self.ranking.iter().enumerate() {
+            positions[index] = Some(position);
+        }
+        positions
+    }
+}
+
+impl ToElements<BaseElement> for ImportanceInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![self.commitment, BaseElement::from(self.num_parameters as u64)];
+        for indices in [&self.ranking, &self.ignored] {
+            elements.push(BaseElement::from(indices.len() as u64));
+            elements.extend(indices.iter().map(|&index| BaseElement::from(index as u64)));
+        }
+        elements
+    }
+}
+
+/// AIR proving that the parameters of a committed model rank by magnitude as claimed
+pub struct ImportanceAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: ImportanceInputs,
+}
+
+impl Air for ImportanceAir {
+    type BaseField = BaseElement;
+    type PublicInputs = ImportanceInputs;
+
+    fn new(trace_info: TraceInfo, inputs: ImportanceInputs, options: ProofOptions) -> Self {
+        assert_eq!(inputs.check(), Ok(()), "Feature importance claims need a valid ranking");
+        let layout = Layout::new(inputs.ranking.len());
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(trace_length(inputs.num_parameters), trace_info.length());
+
+        let num_ranked = layout.num_ranked;
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the parameter at the end of a cycle (degree 1)
+        // 3. Parameter, sign, magnitude and role flags are constant within a cycle (degree 1)
+        // 4. Thresholds are constant over the whole trace (degree 1)
+        // 5. The sign is binary and the parameter is its signed magnitude (degree 2)
+        // 6. The magnitude equals its range checked digits (degree 1), digits (degree 4)
+        // 7. A ranked magnitude equals the threshold of its position (degree 2)
+        // 8. The compared gap equals its range checked digits (degree 2), digits (degree 4)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 3 + num_ranked + 1]);
+        degrees.extend(vec![TransitionConstraintDegree::new(1); num_ranked]);
+        degrees.extend(vec![TransitionConstraintDegree::new(2); 2]);
+        degrees.extend(layout.magnitude.constraint_degrees());
+        degrees.extend(vec![TransitionConstraintDegree::new(2); num_ranked + 1]);
+        degrees.extend(layout.gap.constraint_degrees());
+
+        let num_assertions = STATE_WIDTH + inputs.num_parameters * (num_ranked + 1);
+        ImportanceAir { context: AirContext::new(trace_info, degrees, num_assertions, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let num_ranked = layout.num_ranked;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[PARAMETER]);
+        for lane in 1..STATE_WIDTH {
+            rest[lane] = last * (next[SPONGE + lane] - current[SPONGE + lane]);
+        }
+        let flags = layout.ranked_flag(0)..=layout.ignored_flag();
+        let per_cycle = [PARAMETER, SIGN, MAGNITUDE].into_iter().chain(flags);
+        let (constant, rest) = rest[STATE_WIDTH..].split_at_mut(3 + num_ranked + 1);
+        for (r, column) in constant.iter_mut().zip(per_cycle) {
+            *r = mask * (next[column] - current[column]);
+        }
+        let (thresholds, rest) = rest.split_at_mut(num_ranked);
+        for (position, r) in thresholds.iter_mut().enumerate() {
+            let column = layout.threshold(position);
+            *r = next[column] - current[column];
+        }
+
+        let (sign, magnitude) = (current[SIGN], current[MAGNITUDE]);
+        rest[0] = sign * (sign - E::ONE);
+        rest[1] = current[PARAMETER] - magnitude + E::from(2u32) * sign * magnitude;
+        let rest = &mut rest[2..];
+        layout.magnitude.evaluate(current, rest);
+        let rest = &mut rest[layout.magnitude.num_constraints()..];
+
+        // ranked position `t` compares its magnitude with the next threshold, the last one with
+        // zero; an unranked cycle compares the last threshold with its magnitude
+        let mut unranked = E::ONE - current[layout.ignored_flag()];
+        let mut gap = E::ZERO;
+        for position in 0..num_ranked {
+            let flag = current[layout.ranked_flag(position)];
+            rest[position] = flag * (magnitude - current[layout.threshold(position)]);
+            let below = if position + 1 < num_ranked { current[layout.threshold(position + 1)] } else { E::ZERO };
+            gap += flag * (magnitude - below);
+            unranked -= flag;
+        }
+        gap += unranked * (current[layout.threshold(num_ranked - 1)] - magnitude);
+        rest[num_ranked] = gap - layout.gap.recompose(current);
+        layout.gap.evaluate(current, &mut rest[num_ranked + 1..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let (layout, inputs) = (&self.layout, &self.inputs);
+        let num_parameters = inputs.num_parameters;
+        // the salt in the first lane stays private
+        let start = sponge_start(num_parameters, BaseElement::ZERO);
+        let mut assertions: Vec<_> = (1..STATE_WIDTH).map(|i| Assertion::single(SPONGE + i, 0, start[i])).collect();
+        assertions.push(Assertion::single(SPONGE, (num_parameters + 1) * CYCLE_LENGTH - 1, inputs.commitment));
+        for (index, position) in inputs.positions().into_iter().enumerate() {
+            let step = index * CYCLE_LENGTH;
+            for t in 0..layout.num_ranked {
+                let flag = BaseElement::from((position == Some(t)) as u8);
+                assertions.push(Assertion::single(layout.ranked_flag(t), step, flag));
+            }
+            let flag = BaseElement::from(inputs.ignored.contains(&index) as u8);
+            assertions.push(Assertion::single(layout.ignored_flag(), step, flag));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving that `parameters` rank by magnitude as `ranking` claims,
+/// leaving out the `ignored` ones, with the inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_importance_trace(
+    parameters: &[i64],
+    salt: BaseElement,
+    ranking: &[usize],
+    ignored: &[usize],
+) -> Result<(TraceTable<BaseElement>, ImportanceInputs), ImportanceError> {
+    let inputs = ImportanceInputs {
+        commitment: parameters_commitment(parameters, salt),
+        num_parameters: parameters.len(),
+        ranking: ranking.to_vec(),
+        ignored: ignored.to_vec(),
+    };
+    inputs.check()?;
+    let magnitudes: Vec<u64> = parameters.iter().map(|parameter| parameter.unsigned_abs()).collect();
+    let ranked: Vec<u64> = ranking.iter().map(|&index| magnitudes[index]).collect();
+    if let Some(position) = (1..ranked.len()).find(|&t| ranked[t] > ranked[t - 1]) {
+        return Err(ImportanceError::OutOfOrder { position });
+    }
+    let positions = inputs.positions();
+    let threshold = ranked[ranked.len() - 1];
+    let outranking = |index: &usize| positions[*index].is_none() && !ignored.contains(index);
+    if let Some(index) = (0..parameters.len()).filter(outranking).find(|&index| magnitudes[index] > threshold) {
+        return Err(ImportanceError::Outranked { index });
+    }
+
+    let layout = Layout::new(ranking.len());
+    let length = trace_length(parameters.len());
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    for (position, &magnitude) in ranked.iter().enumerate() {
+        row[layout.threshold(position)] = BaseElement::from(magnitude);
+    }
+    // ignored cycles past the parameters flip the sign and grow the magnitude of the first one,
+    // so neither column is constant and no constraint vanishes on every row
+    let first = parameters.first().copied().unwrap_or(0);
+    let padding = (if first < 0 { 1 } else { -1 }) * (first.unsigned_abs() as i128 + 1);
+    let mut state = sponge_start(parameters.len(), salt);
+    for i in 0..length {
+        let (cycle, step) = (i / CYCLE_LENGTH, i % CYCLE_LENGTH);
+        let parameter = parameters.get(cycle).map_or(padding, |&parameter| parameter as i128);
+        let position = positions.get(cycle).copied().flatten();
+        row[PARAMETER] = to_field(parameter);
+        row[SIGN] = BaseElement::from((parameter < 0) as u8);
+        for t in 0..ranking.len() {
+            row[layout.ranked_flag(t)] = BaseElement::from((position == Some(t)) as u8);
+        }
+        let is_ignored = cycle >= parameters.len() || ignored.contains(&cycle);
+        row[layout.ignored_flag()] = BaseElement::from(is_ignored as u8);
+        layout.sponge.write(&mut row, &state);
+        if step < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, step);
+        } else {
+            state[0] += row[PARAMETER];
+        }
+
+        let magnitude = parameter.unsigned_abs() as u64;
+        let gap = match position {
+            _ if is_ignored => 0,
+            Some(t) => magnitude - ranked.get(t + 1).copied().unwrap_or(0),
+            None => threshold - magnitude,
+        };
+        if i == length - 1 {
+            layout.magnitude.fill_exempt_row(&mut row, magnitude);
+            layout.gap.fill_exempt_row(&mut row);
+        } else {
+            layout.magnitude.fill(&mut row, magnitude).expect("Magnitudes fit into 64 bits");
+            layout.gap.fill(&mut row, gap as i128).expect("The ranking was checked above");
+        }
+        trace.update_row(i, &row);
+    }
+
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_importance_proof() {
+        // intercept, then the weights of four features; feature 3 has the largest one
+        let parameters = [900, 12, -40, -75, 40];
+        let salt = BaseElement::new(0xfeed);
+        assert_eq!(rank_by_magnitude(&parameters[1..]), vec![2, 1, 3, 0]);
+        let (trace, inputs) = build_importance_trace(&parameters, salt, &[3, 2], &[0]).unwrap();
+        crate::air_test!(ImportanceAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        // a smaller magnitude of feature 3 breaks its tie to the first threshold
+        let first_tie = 2 * STATE_WIDTH + 6 + 2 + 2 + 33;
+        crate::air_test!(ImportanceAir, inputs.clone())
+            .with_trace(&trace)
+            .with_cell(MAGNITUDE, 3 * CYCLE_LENGTH, 40u64)
+            .expect_constraint_fails(first_tie, 3 * CYCLE_LENGTH);
+
+        let proof = prove::<ImportanceAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+        let options = default_acceptable_options();
+        let swapped = ImportanceInputs { ranking: vec![2, 3], ..inputs.clone() };
+        assert!(verify::<ImportanceAir>(proof.clone(), swapped, &options).is_err());
+        let counted = ImportanceInputs { ignored: vec![], ..inputs.clone() };
+        assert!(verify::<ImportanceAir>(proof.clone(), counted, &options).is_err());
+        let result = verify::<ImportanceAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_rankings() {
+        let (parameters, salt) = ([5, -9, 9, i64::MIN], BaseElement::ONE);
+        assert!(build_importance_trace(&parameters, salt, &[3, 1, 2, 0], &[]).is_ok());
+        assert!(build_importance_trace(&parameters, salt, &[2, 1], &[3]).is_ok());
+        let error = |ranking: &[usize], ignored: &[usize]| {
+            build_importance_trace(&parameters, salt, ranking, ignored).unwrap_err()
+        };
+        assert_eq!(error(&[1, 3], &[]), ImportanceError::OutOfOrder { position: 1 });
+        assert_eq!(error(&[1], &[]), ImportanceError::Outranked { index: 3 });
+        assert_eq!(error(&[], &[]), ImportanceError::NoRanking);
+        assert_eq!(error(&[3, 3], &[]), ImportanceError::DuplicateParameter { index: 3 });
+        assert_eq!(error(&[3], &[3]), ImportanceError::DuplicateParameter { index: 3 });
+        assert_eq!(error(&[4], &[]), ImportanceError::UnknownParameter { index: 4, num_parameters: 4 });
+    }
+}
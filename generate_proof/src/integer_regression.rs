@@ -0,0 +1,341 @@
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+    TransitionConstraintDegree, 
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+#[cfg(feature = "prover")]
+use crate::config::padded_trace_length;
+use crate::gadgets::{IntegerColumn, IntegerWidth};
+
+// Value columns; range-check digits for the integer columns follow from DIGITS_START
+const SLOPE_LO: usize = 0;
+const SLOPE_HI: usize = 1;
+const INTERCEPT: usize = 2;
+const X: usize = 3;
+const X_LO: usize = 4;
+const X_HI: usize = 5;
+const MID: usize = 6;
+const PRODUCT: usize = 7;
+const Y: usize = 8;
+const DIGITS_START: usize = 9;
+
+/// Error returned when a claim cannot be represented without integer overflow
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntegerError {
+    /// An input does not fit into the configured width
+    OutOfRange { step: usize, value: u64 },
+    /// An intermediate result overflowed the configured width
+    Overflow { step: usize, operation: &'static str },
+}
+
+impl fmt::Display for IntegerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegerError::OutOfRange { step, value } => write!(f, "value {value} at step {step} is out of range"),
+            IntegerError::Overflow { step, operation } => write!(f, "{operation} overflows at step {step}"),
+        }
+    }
+}
+
+impl std::error::Error for IntegerError {}
+
+/// Integer columns of the trace, allocated for a given width
+struct Layout {
+    slope_lo: IntegerColumn,
+    slope_hi: IntegerColumn,
+    intercept: IntegerColumn,
+    x_lo: IntegerColumn,
+    x_hi: IntegerColumn,
+    mid: IntegerColumn,
+    y: IntegerColumn,
+}
+
+impl Layout {
+    fn new(width: IntegerWidth) -> Self {
+        let (full, half, digit) = (width.bits(), width.half_bits(), width.digit_bits());
+        let slope_lo = IntegerColumn::new(SLOPE_LO, DIGITS_START, half, digit);
+        let slope_hi = IntegerColumn::new(SLOPE_HI, slope_lo.next_column(), half, digit);
+        let intercept = IntegerColumn::new(INTERCEPT, slope_hi.next_column(), full, digit);
+        let x_lo = IntegerColumn::new(X_LO, intercept.next_column(), half, digit);
+        let x_hi = IntegerColumn::new(X_HI, x_lo.next_column(), half, digit);
+        let mid = IntegerColumn::new(MID, x_hi.next_column(), half, digit);
+        let y = IntegerColumn::new(Y, mid.next_column(), full, digit);
+        Self { slope_lo, slope_hi, intercept, x_lo, x_hi, mid, y }
+    }
+
+    fn columns(&self) -> [IntegerColumn; 7] {
+        [self.slope_lo, self.slope_hi, self.intercept, self.x_lo, self.x_hi, self.mid, self.y]
+    }
+
+    fn trace_width(&self) -> usize {
+        self.y.next_column()
+    }
+}
+
+/// Computes `slope * x + intercept`, or `None` if any step overflows `width`
+pub fn checked_predict(width: IntegerWidth, slope: u64, intercept: u64, x: u64) -> Option<u64> {
+    slope
+        .checked_mul(x)
+        .and_then(|product| product.checked_add(intercept))
+        .filter(|&y| y <= width.max())
+        .filter(|_| slope <= width.max() && intercept <= width.max() && x <= width.max())
+}
+
+/// Public inputs for a linear regression evaluated with unsigned integer semantics
+#[derive(Clone, Debug)]
+pub struct IntegerRegressionInputs {
+    pub width: IntegerWidth,
+    pub x_value: u64,
+    pub predicted_y: u64,
+    pub sample_x_values: Vec<u64>,
+    pub sample_y_values: Vec<u64>,
+}
+
+impl ToElements<BaseElement> for IntegerRegressionInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![
+            BaseElement::from(self.width.bits() as u64),
+            BaseElement::from(self.x_value),
+            BaseElement::from(self.predicted_y),
+        ];
+        elements.extend(self.sample_x_values.iter().map(|&x| BaseElement::from(x)));
+        elements.extend(self.sample_y_values.iter().map(|&y| BaseElement::from(y)));
+        elements
+    }
+}
+
+/// AIR for `y = m * x + b` where every value and intermediate result is a u32/u64.
+///
+/// The product is computed from half-width limbs so no intermediate value can exceed 2^65:
+/// with u64 operands a plain `m * x` could wrap around the field modulus and still pass a
+/// 64-bit range check. The product itself needs no range check: it is an exact integer below
+/// 2^65, so `y = product + intercept < 2^w` already bounds it.
+pub struct IntegerRegressionAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    half_scale: BaseElement,
+    x_value: u64,
+    predicted_y: u64,
+    sample_x_values: Vec<u64>,
+    sample_y_values: Vec<u64>,
+}
+
+impl Air for IntegerRegressionAir {
+    type BaseField = BaseElement;
+    type PublicInputs = IntegerRegressionInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: IntegerRegressionInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new(pub_inputs.width);
+        assert_eq!(layout.trace_width(), trace_info.width());
+
+        let num_samples = pub_inputs.sample_x_values.len();
+        assert_eq!(num_samples, pub_inputs.sample_y_values.len(), "Sample arrays must have equal length");
+
+        // Constraints:
+        // 1. Slope limbs and intercept stay constant (degree 1)
+        // 2. x = x_hi * 2^h + x_lo (degree 1)
+        // 3. slope_hi * x_hi = 0 (degree 1, slope is a constant column)
+        // 4. mid = slope_hi * x_lo + slope_lo * x_hi (degree 1)
+        // 5. product = slope_lo * x_lo + mid * 2^h (degree 1)
+        // 6. y = product + intercept (degree 1)
+        // 7. Every integer column matches its range-checked digits
+        let mut degrees = vec![TransitionConstraintDegree::new(1); 8];
+        for column in layout.columns() {
+            degrees.extend(column.constraint_degrees());
+        }
+
+        let num_assertions = 2 * num_samples + 2;
+
+        IntegerRegressionAir {
+            context: AirContext::new(trace_info, degrees, num_assertions, options),
+            half_scale: BaseElement::from(1u64 << pub_inputs.width.half_bits()),
+            layout,
+            x_value: pub_inputs.x_value,
+            predicted_y: pub_inputs.predicted_y,
+            sample_x_values: pub_inputs.sample_x_values,
+            sample_y_values: pub_inputs.sample_y_values,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let half_scale = E::from(self.half_scale);
+
+        result[0] = next[SLOPE_LO] - current[SLOPE_LO];
+        result[1] = next[SLOPE_HI] - current[SLOPE_HI];
+        result[2] = next[INTERCEPT] - current[INTERCEPT];
+        result[3] = current[X] - (current[X_HI] * half_scale + current[X_LO]);
+        result[4] = current[SLOPE_HI] * current[X_HI];
+        result[5] = current[MID] - (current[SLOPE_HI] * current[X_LO] + current[SLOPE_LO] * current[X_HI]);
+        result[6] = current[PRODUCT] - (current[SLOPE_LO] * current[X_LO] + current[MID] * half_scale);
+        result[7] = current[Y] - current[PRODUCT] - current[INTERCEPT];
+
+        let mut offset = 8;
+        for column in self.layout.columns() {
+            column.evaluate(current, &mut result[offset..]);
+            offset += column.num_constraints();
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = Vec::new();
+
+        for (i, (&x, &y)) in self.sample_x_values.iter().zip(&self.sample_y_values).enumerate() {
+            assertions.push(Assertion::single(X, i, BaseElement::from(x)));
+            assertions.push(Assertion::single(Y, i, BaseElement::from(y)));
+        }
+
+        let prediction_step = self.sample_x_values.len();
+        assertions.push(Assertion::single(X, prediction_step, BaseElement::from(self.x_value)));
+        assertions.push(Assertion::single(Y, prediction_step, BaseElement::from(self.predicted_y)));
+
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace for an integer regression, failing on any overflow
+#[cfg(feature = "prover")]
+pub fn build_integer_regression_trace(
+    width: IntegerWidth,
+    slope: u64,
+    intercept: u64,
+    sample_x_values: &[u64],
+    target_x: u64,
+    min_trace_length: usize,
+) -> Result<TraceTable<BaseElement>, IntegerError> {
+    for value in [slope, intercept] {
+        if value > width.max() {
+            return Err(IntegerError::OutOfRange { step: 0, value });
+        }
+    }
+
+    let layout = Layout::new(width);
+    let num_samples = sample_x_values.len();
+    // Reserve at least one padding row: the last row is exempt from transition constraints
+    let trace_length = padded_trace_length(num_samples + 2, min_trace_length);
+    let trace_width = layout.trace_width();
+    let (slope_lo, slope_hi) = width.split(slope);
+
+    let mut columns = vec![vec![BaseElement::ZERO; trace_length]; trace_width];
+    let mut row = vec![BaseElement::ZERO; trace_width];
+
+    for step in 0..trace_length {
+        let x = sample_x_values.get(step).copied().unwrap_or(target_x);
+        if x > width.max() {
+            return Err(IntegerError::OutOfRange { step, value: x });
+        }
+        let product = slope
+            .checked_mul(x)
+            .filter(|&product| product <= width.max())
+            .ok_or(IntegerError::Overflow { step, operation: "slope * x" })?;
+        let y = product
+            .checked_add(intercept)
+            .filter(|&y| y <= width.max())
+            .ok_or(IntegerError::Overflow { step, operation: "slope * x + intercept" })?;
+        let (x_lo, x_hi) = width.split(x);
+        // Cannot overflow: slope * x fits into the width, so each cross term fits a half limb
+        let mid = slope_hi * x_lo + slope_lo * x_hi;
+
+        row[X] = BaseElement::from(x);
+        row[PRODUCT] = BaseElement::from(product);
+        let values = [slope_lo, slope_hi, intercept, x_lo, x_hi, mid, y];
+        for (column, value) in layout.columns().into_iter().zip(values) {
+            if step == trace_length - 1 {
+                column.fill_exempt_row(&mut row, value);
+            } else {
+                column
+                    .fill(&mut row, value)
+                    .expect("Limbs and checked results always fit their columns");
+            }
+        }
+
+        for (column, &value) in columns.iter_mut().zip(&row) {
+            column[step] = value;
+        }
+    }
+
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::prover::{prove, verify};
+    use crate::config::{default_acceptable_options, ProverConfig};
+
+    fn options() -> ProofOptions {
+        ProverConfig::default().to_proof_options()
+    }
+
+    #[test]
+    fn test_u64_regression_proof() {
+        let width = IntegerWidth::U64;
+        let (slope, intercept) = (3_000_000_007, 1_000_003);
+        let sample_x = vec![1, 70_000, 4_000_000_000, 5_123_456_789];
+        let target_x = 6_000_000_001;
+
+        let trace = build_integer_regression_trace(
+            width,
+            slope,
+            intercept,
+            &sample_x,
+            target_x,
+            TraceInfo::MIN_TRACE_LENGTH,
+        )
+        .unwrap();
+        let pub_inputs = IntegerRegressionInputs {
+            width,
+            x_value: target_x,
+            predicted_y: checked_predict(width, slope, intercept, target_x).unwrap(),
+            sample_y_values: sample_x.iter().map(|&x| checked_predict(width, slope, intercept, x).unwrap()).collect(),
+            sample_x_values: sample_x,
+        };
+
+        crate::air_test!(IntegerRegressionAir, pub_inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<IntegerRegressionAir>(trace, pub_inputs.clone(), options()).unwrap();
+        let result = verify::<IntegerRegressionAir>(proof, pub_inputs, &default_acceptable_options());
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_overflow_fails_trace_building() {
+        let width = IntegerWidth::U32;
+        assert_eq!(
+            build_integer_regression_trace(width, 70_000, 0, &[1, 70_000], 2, TraceInfo::MIN_TRACE_LENGTH).unwrap_err(),
+            IntegerError::Overflow { step: 1, operation: "slope * x" }
+        );
+        assert_eq!(
+            build_integer_regression_trace(
+                width,
+                1,
+                u32::MAX as u64,
+                &[1],
+                2,
+                TraceInfo::MIN_TRACE_LENGTH,
+            )
+            .unwrap_err(),
+            IntegerError::Overflow { step: 0, operation: "slope * x + intercept" }
+        );
+        assert_eq!(checked_predict(width, 1, 2, u32::MAX as u64), None);
+    }
+}
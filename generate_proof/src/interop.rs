@@ -0,0 +1,481 @@
+//! Verifying proofs of AIRs that are not compiled into this crate.
+//!
+//! A partner running their own Winterfell AIR shares an [`AirDescription`]: the trace width,
+//! every transition constraint as an [`Expr`] together with the degree their AIR declares for
+//! it, the periodic columns, the assertions and the proof options they prove with. An
+//! [`InteropVerifier`] instantiates [`DescribedAir`] from it and runs the regular Winterfell
+//! verifier, so a proof verifies exactly when the partner's own verifier would accept it.
+//!
+//! The constraint language is restricted to polynomials over the current and next row, the
+//! periodic columns and the public inputs. Public inputs are the flat element vector the
+//! partner's `ToElements` implementation produces, and proofs must use the hash function,
+//! random coin and vector commitment of [`crate::prover`].
+
+use core::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    AcceptableOptions, Air, AirContext, Assertion, EvaluationFrame, Proof, ProofOptions, TraceInfo,
+    TransitionConstraintDegree, VerifierError,
+};
+
+use crate::codec;
+use crate::config::ProverConfig;
+use crate::constraints::Expr;
+
+/// Checks that every column, periodic column and public input `expr` reads exists
+fn validate_expr(expr: &Expr, description: &AirDescription) -> Result<(), String> {
+    let check = |kind: &str, index: usize, bound: usize| {
+        if index < bound { Ok(()) } else { Err(format!("{kind} {index} out of range (0..{bound})")) }
+    };
+    match expr {
+        Expr::Constant(_) => Ok(()),
+        Expr::Current(column) | Expr::Next(column) => check("column", *column, description.trace_width),
+        Expr::Periodic(column) => check("periodic column", *column, description.periodic_columns.len()),
+        Expr::Public(index) => check("public input", *index, description.num_public_inputs),
+        Expr::Add(terms) | Expr::Mul(terms) => terms.iter().try_for_each(|term| validate_expr(term, description)),
+        Expr::Sub(a, b) => validate_expr(a, description).and_then(|_| validate_expr(b, description)),
+        Expr::Neg(a) | Expr::Pow(a, _) => validate_expr(a, description),
+    }
+}
+
+/// A transition constraint and the degree the describing AIR declares for it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintDescription {
+    pub expr: Expr,
+    pub degree: usize,
+    /// Cycle lengths of the periodic columns the constraint multiplies with
+    #[serde(default)]
+    pub cycles: Vec<usize>,
+}
+
+impl ConstraintDescription {
+    fn degree(&self) -> TransitionConstraintDegree {
+        if self.cycles.is_empty() {
+            TransitionConstraintDegree::new(self.degree)
+        } else {
+            TransitionConstraintDegree::with_cycles(self.degree, self.cycles.clone())
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeriodicColumn(#[serde(with = "codec::elements")] pub Vec<BaseElement>);
+
+/// Value an assertion pins a cell to
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssertedValue {
+    Const(#[serde(with = "codec::element")] BaseElement),
+    Public(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssertionDescription {
+    pub column: usize,
+    pub step: usize,
+    /// Count `step` back from the last row instead of forward from the first
+    #[serde(default)]
+    pub from_end: bool,
+    pub value: AssertedValue,
+}
+
+impl AssertionDescription {
+    /// Step the assertion pins in a trace of `trace_length` rows, at least `step + 1` of them
+    fn resolve(&self, trace_length: usize) -> usize {
+        if self.from_end { trace_length - 1 - self.step } else { self.step }
+    }
+}
+
+/// Shared description of an AIR, exchanged as JSON
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AirDescription {
+    pub air_id: String,
+    pub trace_width: usize,
+    pub num_public_inputs: usize,
+    pub constraints: Vec<ConstraintDescription>,
+    #[serde(default)]
+    pub periodic_columns: Vec<PeriodicColumn>,
+    pub assertions: Vec<AssertionDescription>,
+    /// Options the partner proves with; proofs with any other options are rejected
+    pub options: ProverConfig,
+}
+
+impl AirDescription {
+    /// Checks every invariant Winterfell would otherwise enforce with a panic while verifying;
+    /// the ones depending on the trace length are checked per proof
+    pub fn validate(&self) -> Result<(), InteropError> {
+        let invalid = |reason: String| Err(InteropError::InvalidDescription(reason));
+        if let Err(err) = self.options.validate() {
+            return invalid(format!("options: {err}"));
+        }
+        if self.trace_width == 0 || self.trace_width > TraceInfo::MAX_TRACE_WIDTH {
+            return invalid(format!("trace width {} is not in 1..={}", self.trace_width, TraceInfo::MAX_TRACE_WIDTH));
+        }
+        if self.constraints.is_empty() {
+            return invalid("no transition constraints".into());
+        }
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            if constraint.degree == 0 {
+                return invalid(format!("constraint {i} has degree 0"));
+            }
+            if let Some(cycle) = constraint.cycles.iter().find(|c| !c.is_power_of_two() || **c < 2) {
+                return invalid(format!("constraint {i} has cycle length {cycle}"));
+            }
+            if let Err(reason) = validate_expr(&constraint.expr, self) {
+                return invalid(format!("constraint {i}: {reason}"));
+            }
+            let degree = constraint.expr.degree(&[]);
+            if degree > constraint.degree {
+                return invalid(format!("constraint {i} has degree {degree}, declared {}", constraint.degree));
+            }
+            let blowup = constraint.degree().min_blowup_factor();
+            if blowup > self.options.blowup_factor {
+                return invalid(format!(
+                    "constraint {i} needs a blowup factor of {blowup}, the options use {}",
+                    self.options.blowup_factor
+                ));
+            }
+        }
+        for (i, column) in self.periodic_columns.iter().enumerate() {
+            if column.0.len() < 2 || !column.0.len().is_power_of_two() {
+                return invalid(format!("periodic column {i} has {} values", column.0.len()));
+            }
+        }
+        if self.assertions.is_empty() {
+            return invalid("no assertions".into());
+        }
+        for (i, assertion) in self.assertions.iter().enumerate() {
+            if assertion.column >= self.trace_width {
+                return invalid(format!("assertion {i} is on column {}", assertion.column));
+            }
+            if let AssertedValue::Public(index) = assertion.value
+                && index >= self.num_public_inputs
+            {
+                return invalid(format!("assertion {i} uses public input {index}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_trace_length(&self, trace_length: usize) -> Result<(), InteropError> {
+        let invalid = |reason: String| Err(InteropError::InvalidDescription(reason));
+        if let Some(column) = self.periodic_columns.iter().find(|c| c.0.len() > trace_length) {
+            return invalid(format!("periodic column of {} values exceeds the trace", column.0.len()));
+        }
+        if let Some(assertion) = self.assertions.iter().find(|a| a.step >= trace_length) {
+            return invalid(format!("assertion step {} is beyond the trace", assertion.step));
+        }
+        let mut cells = std::collections::HashSet::new();
+        for (i, assertion) in self.assertions.iter().enumerate() {
+            if !cells.insert((assertion.column, assertion.resolve(trace_length))) {
+                return invalid(format!("assertion {i} overlaps an earlier assertion"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Public inputs of a [`DescribedAir`]: the description and the partner's public input elements.
+///
+/// Only [`InteropVerifier::verify`] builds them, after checking the description against the
+/// proof, so that [`DescribedAir::new`] cannot fail.
+#[derive(Clone, Debug)]
+pub struct DescribedInputs {
+    description: Arc<AirDescription>,
+    values: Vec<BaseElement>,
+}
+
+impl ToElements<BaseElement> for DescribedInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        self.values.clone()
+    }
+}
+
+/// AIR interpreting an [`AirDescription`] validated against the proof by [`InteropVerifier`]
+pub struct DescribedAir {
+    context: AirContext<BaseElement>,
+    inputs: DescribedInputs,
+}
+
+impl Air for DescribedAir {
+    type BaseField = BaseElement;
+    type PublicInputs = DescribedInputs;
+
+    fn new(trace_info: TraceInfo, inputs: DescribedInputs, options: ProofOptions) -> Self {
+        let description = &inputs.description;
+        let degrees = description.constraints.iter().map(ConstraintDescription::degree).collect();
+        let num_assertions = description.assertions.len();
+        DescribedAir { context: AirContext::new(trace_info, degrees, num_assertions, options), inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        for (r, constraint) in result.iter_mut().zip(&self.inputs.description.constraints) {
+            *r = constraint.expr.evaluate(frame.current(), frame.next(), periodic_values, &self.inputs.values);
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let trace_length = self.context.trace_len();
+        self.inputs
+            .description
+            .assertions
+            .iter()
+            .map(|assertion| {
+                let step = assertion.resolve(trace_length);
+                let value = match assertion.value {
+                    AssertedValue::Const(value) => value,
+                    AssertedValue::Public(index) => self.inputs.values[index],
+                };
+                Assertion::single(assertion.column, step, value)
+            })
+            .collect()
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        self.inputs.description.periodic_columns.iter().map(|column| column.0.clone()).collect()
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Reason a described proof was rejected
+#[derive(Debug)]
+pub enum InteropError {
+    InvalidDescription(String),
+    PublicInputs { expected: usize, found: usize },
+    TraceWidth { expected: usize, found: usize },
+    /// The proof was generated with options other than the described ones
+    Options,
+    Verifier(VerifierError),
+}
+
+impl fmt::Display for InteropError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidDescription(reason) => write!(f, "invalid AIR description: {reason}"),
+            Self::PublicInputs { expected, found } => write!(f, "expected {expected} public inputs, found {found}"),
+            Self::TraceWidth { expected, found } => {
+                write!(f, "proof has a {found}-column trace, the description {expected} columns")
+            }
+            Self::Options => write!(f, "proof options differ from the described options"),
+            Self::Verifier(err) => write!(f, "proof verification failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for InteropError {}
+
+/// Verifier for the proofs of one described AIR
+#[derive(Clone, Debug)]
+pub struct InteropVerifier {
+    description: Arc<AirDescription>,
+}
+
+impl InteropVerifier {
+    pub fn new(description: AirDescription) -> Result<Self, InteropError> {
+        description.validate()?;
+        Ok(Self { description: Arc::new(description) })
+    }
+
+    pub fn description(&self) -> &AirDescription {
+        &self.description
+    }
+
+    pub fn verify(
+        &self,
+        proof: Proof,
+        public_inputs: Vec<BaseElement>,
+        acceptable: &AcceptableOptions,
+    ) -> Result<(), InteropError> {
+        let description = &self.description;
+        if public_inputs.len() != description.num_public_inputs {
+            return Err(InteropError::PublicInputs { expected: description.num_public_inputs, found: public_inputs.len() });
+        }
+        let trace_info = proof.trace_info();
+        if trace_info.width() != description.trace_width {
+            return Err(InteropError::TraceWidth { expected: description.trace_width, found: trace_info.width() });
+        }
+        description.check_trace_length(trace_info.length())?;
+        if *proof.options() != description.options.to_proof_options() {
+            return Err(InteropError::Options);
+        }
+
+        let inputs = DescribedInputs { description: description.clone(), values: public_inputs };
+        crate::prover::verify::<DescribedAir>(proof, inputs, acceptable).map_err(InteropError::Verifier)
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::default_acceptable_options;
+    use crate::prover::prove;
+    use winterfell::TraceTable;
+
+    /// The partner's compiled AIR: a Fibonacci pair whose even steps also add a periodic
+    /// offset, with the last term as its public input
+    struct PartnerAir {
+        context: AirContext<BaseElement>,
+        result: BaseElement,
+    }
+
+    #[derive(Clone)]
+    struct PartnerInputs(BaseElement);
+
+    impl ToElements<BaseElement> for PartnerInputs {
+        fn to_elements(&self) -> Vec<BaseElement> {
+            vec![self.0]
+        }
+    }
+
+    impl Air for PartnerAir {
+        type BaseField = BaseElement;
+        type PublicInputs = PartnerInputs;
+
+        fn new(trace_info: TraceInfo, inputs: PartnerInputs, options: ProofOptions) -> Self {
+            let degrees = vec![TransitionConstraintDegree::with_cycles(1, vec![2]), TransitionConstraintDegree::new(2)];
+            Self { context: AirContext::new(trace_info, degrees, 3, options), result: inputs.0 }
+        }
+
+        fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+            &self,
+            frame: &EvaluationFrame<E>,
+            periodic_values: &[E],
+            result: &mut [E],
+        ) {
+            let (current, next) = (frame.current(), frame.next());
+            result[0] = next[0] - current[1] - periodic_values[0] * current[0];
+            result[1] = next[1] - current[0] - current[1] * current[1];
+        }
+
+        fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+            let last = self.context.trace_len() - 1;
+            vec![
+                Assertion::single(0, 0, BaseElement::ONE),
+                Assertion::single(1, 0, BaseElement::ONE),
+                Assertion::single(1, last, self.result),
+            ]
+        }
+
+        fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+            vec![vec![BaseElement::ONE, BaseElement::ZERO]]
+        }
+
+        fn context(&self) -> &AirContext<Self::BaseField> {
+            &self.context
+        }
+    }
+
+    fn partner_description() -> AirDescription {
+        let json = r#"{
+            "air_id": "partner/square-fib",
+            "trace_width": 2,
+            "num_public_inputs": 1,
+            "constraints": [
+                {"expr": {"sub": [{"next": 0}, {"add": [{"cur": 1}, {"mul": [{"periodic": 0}, {"cur": 0}]}]}]}, "degree": 1, "cycles": [2]},
+                {"expr": {"sub": [{"next": 1}, {"add": [{"cur": 0}, {"pow": [{"cur": 1}, 2]}]}]}, "degree": 2}
+            ],
+            "periodic_columns": [["0x00000000000000000000000000000001", "0x00000000000000000000000000000000"]],
+            "assertions": [
+                {"column": 0, "step": 0, "value": {"const": "0x00000000000000000000000000000001"}},
+                {"column": 1, "step": 0, "value": {"const": "0x00000000000000000000000000000001"}},
+                {"column": 1, "step": 0, "from_end": true, "value": {"public": 0}}
+            ],
+            "options": {"num_queries": 32, "blowup_factor": 8, "grinding_factor": 0, "fri_folding_factor": 8, "fri_remainder_max_degree": 31}
+        }"#;
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_verifies_partner_proof() {
+        let length = 16;
+        let mut columns = vec![vec![BaseElement::ONE; length], vec![BaseElement::ONE; length]];
+        for step in 1..length {
+            let (a, b) = (columns[0][step - 1], columns[1][step - 1]);
+            let offset = if step % 2 == 1 { a } else { BaseElement::ZERO };
+            columns[0][step] = b + offset;
+            columns[1][step] = a + b * b;
+        }
+        let result = columns[1][length - 1];
+
+        let description = partner_description();
+        let options = description.options.to_proof_options();
+        let trace = TraceTable::init(columns);
+        let inputs = DescribedInputs { description: Arc::new(description.clone()), values: vec![result] };
+        crate::air_test!(DescribedAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<PartnerAir>(trace, PartnerInputs(result), options).unwrap();
+
+        let verifier = InteropVerifier::new(description).unwrap();
+        let acceptable = default_acceptable_options();
+        verifier.verify(proof.clone(), vec![result], &acceptable).unwrap();
+        assert!(matches!(
+            verifier.verify(proof.clone(), vec![result + BaseElement::ONE], &acceptable),
+            Err(InteropError::Verifier(_))
+        ));
+        assert!(matches!(verifier.verify(proof, vec![], &acceptable), Err(InteropError::PublicInputs { .. })));
+    }
+
+    #[test]
+    fn test_rejects_invalid_descriptions() {
+        let mut description = partner_description();
+        description.constraints[1].expr = Expr::Sub(Box::new(Expr::Next(2)), Box::new(Expr::Public(0)));
+        let err = InteropVerifier::new(description).unwrap_err();
+        assert!(err.to_string().contains("column 2 out of range"), "{err}");
+
+        let mut description = partner_description();
+        description.assertions[2].value = AssertedValue::Public(1);
+        assert!(matches!(InteropVerifier::new(description), Err(InteropError::InvalidDescription(_))));
+
+        let rejected = |change: fn(&mut AirDescription), reason: &str| {
+            let mut description = partner_description();
+            change(&mut description);
+            let err = InteropVerifier::new(description).unwrap_err();
+            assert!(err.to_string().contains(reason), "{err}");
+        };
+        rejected(|d| d.assertions.clear(), "no assertions");
+        rejected(|d| d.constraints[1].degree = 1, "constraint 1 has degree 2, declared 1");
+        rejected(|d| d.constraints[1].degree = 10, "needs a blowup factor of 16");
+        rejected(|d| d.options.num_queries = 0, "options: 0 queries");
+        rejected(|d| d.options.grinding_factor = 40, "options: grinding factor 40");
+    }
+
+    #[test]
+    fn test_rejects_overlapping_assertions() {
+        let mut description = partner_description();
+        // the last row of a 16-row trace, which the third assertion pins as well
+        description.assertions.push(AssertionDescription {
+            column: 1,
+            step: 15,
+            from_end: false,
+            value: AssertedValue::Const(BaseElement::ONE),
+        });
+        let verifier = InteropVerifier::new(description).unwrap();
+
+        let length = 16;
+        let mut columns = vec![vec![BaseElement::ONE; length], vec![BaseElement::ONE; length]];
+        for step in 1..length {
+            let (a, b) = (columns[0][step - 1], columns[1][step - 1]);
+            columns[0][step] = b + if step % 2 == 1 { a } else { BaseElement::ZERO };
+            columns[1][step] = a + b * b;
+        }
+        let result = columns[1][length - 1];
+        let options = verifier.description().options.to_proof_options();
+        let proof = prove::<PartnerAir>(TraceTable::init(columns), PartnerInputs(result), options).unwrap();
+        let err = verifier.verify(proof, vec![result], &default_acceptable_options()).unwrap_err();
+        assert!(err.to_string().contains("assertion 3 overlaps"), "{err}");
+    }
+}
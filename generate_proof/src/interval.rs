@@ -0,0 +1,421 @@
+//! Prediction intervals from committed quantile regression models.
+//!
+//! A linear model fitted with the [`pinball_loss`] at level `tau` predicts the `tau`-quantile
+//! of `y` given the features. Two of them, at a lower and an upper level, give a prediction
+//! interval: with levels of 5 and 95 percent, `y` falls into `[lower, upper]` nine times in
+//! ten. [`PredictionIntervalAir`] proves that a published interval is what the pair of models
+//! committed as [`IntervalModel::commitment`] predicts for public features, so users can
+//! check the interval rather than trust a point estimate, without seeing the parameters.
+//!
+//! Each model is a bias and one weight per feature, signed fixed-point values on a scale of
+//! the caller's choosing; predictions are `bias + sum(weight_j * x_j)`. The commitment hashes
+//! a salt, both levels and the parameters of both models, so an interval of other quantiles
+//! fails to verify. Crossing intervals (`lower > upper`, which separately fitted quantile
+//! models can produce) are rejected.
+//!
+//! The trace has one [`CYCLE_LENGTH`]-row cycle per term, the bias as a term with `x = 1`
+//! followed by the features, plus one that finishes the sponge. The sponge starts from the
+//! salt, the number of terms and the levels, and absorbs the lower and upper weight of a term
+//! into its first two lanes at the end of the term's cycle, while two accumulators add
+//! `x * weight` for either model.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::batch::field_column;
+use crate::boundary::BoundaryBuilder;
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`PredictionIntervalAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "prediction-interval/v1";
+
+/// Sponge tag of [`IntervalModel::commitment`]; tags 1 to 14 are used elsewhere
+const PARAMETERS_TAG: u64 = 15;
+
+// Trace layout: sponge state, the term's x, its lower and upper weight, then the lower and
+// upper prediction accumulated before the row
+const SPONGE: usize = 0;
+const X: usize = SPONGE + STATE_WIDTH;
+const LOWER_WEIGHT: usize = X + 1;
+const UPPER_WEIGHT: usize = LOWER_WEIGHT + 1;
+const LOWER: usize = UPPER_WEIGHT + 1;
+const UPPER: usize = LOWER + 1;
+/// Number of trace columns
+pub const TRACE_WIDTH: usize = UPPER + 1;
+
+/// Trace length for models over `num_features` features
+pub fn trace_length(num_features: usize) -> usize {
+    ((num_features + 2) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..X)
+        .with_column("x", X)
+        .with_column("lower_weight", LOWER_WEIGHT)
+        .with_column("upper_weight", UPPER_WEIGHT)
+        .with_column("lower", LOWER)
+        .with_column("upper", UPPER)
+}
+
+/// Pinball loss of `prediction` for observation `y` at level `level_percent`, the loss whose
+/// minimizer is the quantile of that level; scaled by 100
+pub fn pinball_loss(level_percent: u64, y: i128, prediction: i128) -> i128 {
+    let level = level_percent as i128;
+    if y >= prediction { level * (y - prediction) } else { (100 - level) * (prediction - y) }
+}
+
+/// Linear model fitted at one quantile level
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuantileModel {
+    /// Quantile level in percent
+    pub level: u64,
+    pub bias: i64,
+    pub weights: Vec<i64>,
+}
+
+impl QuantileModel {
+    pub fn predict(&self, features: &[i64]) -> i128 {
+        self.weights.iter().zip(features).fold(self.bias as i128, |sum, (&w, &x)| sum + w as i128 * x as i128)
+    }
+}
+
+/// Reason a pair of models cannot back an interval claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntervalError {
+    /// The levels are not `0 < lower < upper < 100`
+    InvalidLevels { lower: u64, upper: u64 },
+    /// A model's weights do not match the number of features
+    FeatureCountMismatch { weights: usize, features: usize },
+    /// The lower quantile prediction exceeds the upper one
+    Crossing { lower: i128, upper: i128 },
+}
+
+impl fmt::Display for IntervalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntervalError::InvalidLevels { lower, upper } => {
+                write!(f, "quantile levels {lower}% and {upper}% are not 0 < lower < upper < 100")
+            }
+            IntervalError::FeatureCountMismatch { weights, features } => {
+                write!(f, "{weights} weights for {features} features")
+            }
+            IntervalError::Crossing { lower, upper } => write!(f, "lower prediction {lower} exceeds upper {upper}"),
+        }
+    }
+}
+
+impl std::error::Error for IntervalError {}
+
+fn valid_levels(lower: u64, upper: u64) -> bool {
+    0 < lower && lower < upper && upper < 100
+}
+
+/// Pair of quantile models and the salt hiding them in the commitment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalModel {
+    pub lower: QuantileModel,
+    pub upper: QuantileModel,
+    pub salt: BaseElement,
+}
+
+impl IntervalModel {
+    pub fn new(lower: QuantileModel, upper: QuantileModel, salt: BaseElement) -> Result<Self, IntervalError> {
+        if !valid_levels(lower.level, upper.level) {
+            return Err(IntervalError::InvalidLevels { lower: lower.level, upper: upper.level });
+        }
+        if lower.weights.len() != upper.weights.len() {
+            let (weights, features) = (upper.weights.len(), lower.weights.len());
+            return Err(IntervalError::FeatureCountMismatch { weights, features });
+        }
+        Ok(Self { lower, upper, salt })
+    }
+
+    pub fn num_features(&self) -> usize {
+        self.lower.weights.len()
+    }
+
+    /// `(x, lower weight, upper weight)` of the bias and every feature
+    fn terms(&self, features: &[i64]) -> Vec<[BaseElement; 3]> {
+        let bias = [BaseElement::ONE, to_field(self.lower.bias as i128), to_field(self.upper.bias as i128)];
+        let weights = field_column(&self.lower.weights).into_iter().zip(field_column(&self.upper.weights));
+        let terms = weights.zip(field_column(features)).map(|((lo, hi), x)| [x, lo, hi]);
+        core::iter::once(bias).chain(terms).collect()
+    }
+
+    /// Digest published for the pair
+    pub fn commitment(&self) -> BaseElement {
+        let mut state = sponge_start(self.num_features(), self.salt, self.lower.level, self.upper.level);
+        rescue::permute(&mut state);
+        for [_, lower, upper] in self.terms(&vec![0; self.num_features()]) {
+            state[0] += lower;
+            state[1] += upper;
+            rescue::permute(&mut state);
+        }
+        state[0]
+    }
+
+    /// The interval predicted for `features`
+    pub fn predict(&self, features: &[i64]) -> Result<(i128, i128), IntervalError> {
+        if features.len() != self.num_features() {
+            return Err(IntervalError::FeatureCountMismatch { weights: self.num_features(), features: features.len() });
+        }
+        let (lower, upper) = (self.lower.predict(features), self.upper.predict(features));
+        if lower > upper {
+            return Err(IntervalError::Crossing { lower, upper });
+        }
+        Ok((lower, upper))
+    }
+}
+
+/// Sponge state before the first permutation: the salt, the number of terms, the tag and both
+/// levels
+fn sponge_start(
+    num_features: usize,
+    salt: BaseElement,
+    lower_level: u64,
+    upper_level: u64,
+) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_features + 1, PARAMETERS_TAG);
+    state[0] = salt;
+    state[3] = BaseElement::from(lower_level << 32 | upper_level);
+    state
+}
+
+/// Public inputs: the commitment, both levels, the features and the interval
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalInputs {
+    pub commitment: BaseElement,
+    pub lower_level: u64,
+    pub upper_level: u64,
+    pub features: Vec<i64>,
+    pub lower: i128,
+    pub upper: i128,
+}
+
+impl ToElements<BaseElement> for IntervalInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![
+            self.commitment,
+            BaseElement::from(self.lower_level),
+            BaseElement::from(self.upper_level),
+            BaseElement::from(self.features.len() as u64),
+        ];
+        elements.extend(field_column(&self.features));
+        elements.extend([to_field(self.lower), to_field(self.upper)]);
+        elements
+    }
+}
+
+/// AIR proving that an interval is predicted by a committed pair of quantile models
+pub struct PredictionIntervalAir {
+    context: AirContext<BaseElement>,
+    sponge: RescueGadget,
+    inputs: IntervalInputs,
+}
+
+impl PredictionIntervalAir {
+    /// The `x` of every term is read at the start of its cycle, as one sequence assertion
+    /// padded with the `x = 1` of the padding cycles
+    fn boundary(trace_length: usize, inputs: &IntervalInputs) -> BoundaryBuilder {
+        let num_terms = inputs.features.len() + 1;
+        // the salt in the first lane stays private
+        let start = sponge_start(inputs.features.len(), BaseElement::ZERO, inputs.lower_level, inputs.upper_level);
+        let mut boundary = BoundaryBuilder::new(trace_length);
+        for (i, &value) in start.iter().enumerate().skip(1) {
+            boundary.single(SPONGE + i, 0, value);
+        }
+        let xs: Vec<_> =
+            core::iter::once(BaseElement::ONE).chain(field_column(&inputs.features)).collect();
+        boundary
+            .single(SPONGE, (num_terms + 1) * CYCLE_LENGTH - 1, inputs.commitment)
+            .samples(X, 0, CYCLE_LENGTH, &xs, BaseElement::ONE)
+            .single(LOWER, 0, BaseElement::ZERO)
+            .single(UPPER, 0, BaseElement::ZERO)
+            .single(LOWER, num_terms * CYCLE_LENGTH, to_field(inputs.lower))
+            .single(UPPER, num_terms * CYCLE_LENGTH, to_field(inputs.upper));
+        boundary
+    }
+}
+
+impl Air for PredictionIntervalAir {
+    type BaseField = BaseElement;
+    type PublicInputs = IntervalInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: IntervalInputs, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert_eq!(trace_length(pub_inputs.features.len()), trace_info.length());
+        assert!(valid_levels(pub_inputs.lower_level, pub_inputs.upper_level), "Levels must be 0 < lower < upper < 100");
+        assert!(pub_inputs.lower <= pub_inputs.upper, "Prediction intervals must not cross");
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the lower and upper weight at the end of a cycle (degree 1)
+        // 3. x and both weights are constant within a cycle (degree 1)
+        // 4. Both accumulators add x * weight at the end of a cycle (degree 2)
+        let sponge = RescueGadget::new(SPONGE);
+        let mut degrees = sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 3]);
+        degrees.extend(vec![periodic(2); 2]);
+
+        let num_assertions = Self::boundary(trace_info.length(), &pub_inputs).num_assertions();
+        PredictionIntervalAir {
+            context: AirContext::new(trace_info, degrees, num_assertions, options),
+            sponge,
+            inputs: pub_inputs,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        self.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[LOWER_WEIGHT]);
+        rest[1] = last * (next[SPONGE + 1] - current[SPONGE + 1] - current[UPPER_WEIGHT]);
+        rest[2] = last * (next[SPONGE + 2] - current[SPONGE + 2]);
+        rest[3] = last * (next[SPONGE + 3] - current[SPONGE + 3]);
+        for (i, column) in [X, LOWER_WEIGHT, UPPER_WEIGHT].into_iter().enumerate() {
+            rest[4 + i] = mask * (next[column] - current[column]);
+        }
+        rest[7] = next[LOWER] - current[LOWER] - last * current[X] * current[LOWER_WEIGHT];
+        rest[8] = next[UPPER] - current[UPPER] - last * current[X] * current[UPPER_WEIGHT];
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        Self::boundary(self.trace_length(), &self.inputs).build()
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving the interval `model` predicts for `features`, with the
+/// inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_interval_trace(
+    model: &IntervalModel,
+    features: &[i64],
+) -> Result<(TraceTable<BaseElement>, IntervalInputs), IntervalError> {
+    let (lower, upper) = model.predict(features)?;
+    let terms = model.terms(features);
+    // padding terms add one and two per cycle, so the final accumulators (and with them the
+    // constraints on the exempt last row) are not zero
+    let padding = [BaseElement::ONE, BaseElement::ONE, BaseElement::new(2)];
+
+    let length = trace_length(features.len());
+    let mut columns = vec![vec![BaseElement::ZERO; length]; TRACE_WIDTH];
+    let mut row = [BaseElement::ZERO; TRACE_WIDTH];
+    let sponge = RescueGadget::new(SPONGE);
+    let mut state = sponge_start(features.len(), model.salt, model.lower.level, model.upper.level);
+    let (mut lower_sum, mut upper_sum) = (BaseElement::ZERO, BaseElement::ZERO);
+
+    for cycle in 0..length / CYCLE_LENGTH {
+        let [x, lower_weight, upper_weight] = terms.get(cycle).copied().unwrap_or(padding);
+        row[X] = x;
+        row[LOWER_WEIGHT] = lower_weight;
+        row[UPPER_WEIGHT] = upper_weight;
+        row[LOWER] = lower_sum;
+        row[UPPER] = upper_sum;
+        for step in 0..CYCLE_LENGTH {
+            sponge.write(&mut row, &state);
+            for (column, &value) in columns.iter_mut().zip(&row) {
+                column[cycle * CYCLE_LENGTH + step] = value;
+            }
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut state, step);
+            }
+        }
+        state[0] += lower_weight;
+        state[1] += upper_weight;
+        lower_sum += x * lower_weight;
+        upper_sum += x * upper_weight;
+    }
+
+    let inputs = IntervalInputs {
+        commitment: model.commitment(),
+        lower_level: model.lower.level,
+        upper_level: model.upper.level,
+        features: features.to_vec(),
+        lower,
+        upper,
+    };
+    Ok((TraceTable::init(columns), inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    fn model() -> IntervalModel {
+        let lower = QuantileModel { level: 5, bias: -40, weights: vec![3, -2, 7] };
+        let upper = QuantileModel { level: 95, bias: 60, weights: vec![4, -1, 7] };
+        IntervalModel::new(lower, upper, BaseElement::new(0xfeed)).unwrap()
+    }
+
+    #[test]
+    fn test_interval_proof() {
+        let model = model();
+        let features = [10, -5, 2];
+        let (trace, inputs) = build_interval_trace(&model, &features).unwrap();
+        assert_eq!((inputs.lower, inputs.upper), (30 + 10 + 14 - 40, 40 + 5 + 14 + 60));
+        crate::air_test!(PredictionIntervalAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<PredictionIntervalAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options())
+            .unwrap();
+        let options = default_acceptable_options();
+        let narrower = IntervalInputs { upper: inputs.upper - 1, ..inputs.clone() };
+        assert!(verify::<PredictionIntervalAir>(proof.clone(), narrower, &options).is_err());
+        let other_levels = IntervalInputs { lower_level: 10, ..inputs.clone() };
+        assert!(verify::<PredictionIntervalAir>(proof.clone(), other_levels, &options).is_err());
+        let other_model = IntervalModel { salt: BaseElement::new(1), ..model.clone() };
+        let other_commitment = IntervalInputs { commitment: other_model.commitment(), ..inputs.clone() };
+        assert!(verify::<PredictionIntervalAir>(proof.clone(), other_commitment, &options).is_err());
+        let result = verify::<PredictionIntervalAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_models() {
+        let model = model();
+        assert_eq!(model.predict(&[0, -200, 0]).unwrap_err(), IntervalError::Crossing { lower: 360, upper: 260 });
+        assert_eq!(
+            build_interval_trace(&model, &[1, 2]).unwrap_err(),
+            IntervalError::FeatureCountMismatch { weights: 3, features: 2 }
+        );
+        let swapped = IntervalModel::new(model.upper.clone(), model.lower.clone(), model.salt);
+        assert_eq!(swapped.unwrap_err(), IntervalError::InvalidLevels { lower: 95, upper: 5 });
+        assert_eq!(pinball_loss(90, 10, 4), 540);
+        assert_eq!(pinball_loss(90, 4, 10), 60);
+    }
+}
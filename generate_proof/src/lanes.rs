@@ -0,0 +1,312 @@
+//! Independent instances of one AIR proven side by side in a single trace.
+//!
+//! Tiny claims spend most of their proving time on costs that do not grow with the trace
+//! width: the FRI layers and the query openings. [`LanesAir`] proves `k` instances of an AIR
+//! in one trace, lane `i` in columns `i * w..(i + 1) * w` for an inner trace width of `w`.
+//! The constraints, assertions and periodic columns of every lane are those of its own
+//! instance with the columns shifted, so a lane says exactly what a proof of it alone would.
+//! [`LaneInputs`] lists the statement of every lane; all lanes must have the same trace
+//! length and number of transition exemptions. [`interleave_lanes`] builds the trace and
+//! [`verify_lanes`] checks the statements against the proof's trace before verifying.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    AcceptableOptions, Air, AirContext, Assertion, EvaluationFrame, Proof, ProofOptions, TraceInfo, VerifierError,
+};
+#[cfg(feature = "prover")]
+use winterfell::{Trace, TraceTable};
+
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`LanesAir`], recorded with the lane AIR's id in its layouts
+pub const AIR_ID: &str = "lanes/v1";
+
+/// Public inputs: one statement per lane, in column order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LaneInputs<P> {
+    pub lanes: Vec<P>,
+}
+
+impl<P> LaneInputs<P> {
+    pub fn new(lanes: Vec<P>) -> Result<Self, LaneError> {
+        if lanes.is_empty() {
+            return Err(LaneError::NoLanes);
+        }
+        Ok(Self { lanes })
+    }
+
+    /// Checks that a trace described by `trace_info` splits into one equal group of columns
+    /// per lane, which [`LanesAir::new`] relies on
+    pub fn check(&self, trace_info: &TraceInfo) -> Result<(), LaneError> {
+        if self.lanes.is_empty() {
+            return Err(LaneError::NoLanes);
+        }
+        if trace_info.is_multi_segment() {
+            return Err(LaneError::AuxiliarySegment);
+        }
+        if !trace_info.width().is_multiple_of(self.lanes.len()) {
+            return Err(LaneError::UnevenWidth { width: trace_info.width(), lanes: self.lanes.len() });
+        }
+        Ok(())
+    }
+}
+
+impl<P: ToElements<BaseElement>> ToElements<BaseElement> for LaneInputs<P> {
+    /// The number of lanes, then every lane's elements prefixed with their count
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![BaseElement::from(self.lanes.len() as u64)];
+        for lane in &self.lanes {
+            let lane_elements = lane.to_elements();
+            elements.push(BaseElement::from(lane_elements.len() as u64));
+            elements.extend(lane_elements);
+        }
+        elements
+    }
+}
+
+/// Reason traces cannot share one proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LaneError {
+    NoLanes,
+    WidthMismatch { lane: usize, width: usize, expected: usize },
+    LengthMismatch { lane: usize, length: usize, expected: usize },
+    /// The trace width is not a multiple of the number of lanes
+    UnevenWidth { width: usize, lanes: usize },
+    AuxiliarySegment,
+    Verifier(VerifierError),
+}
+
+impl fmt::Display for LaneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaneError::NoLanes => write!(f, "no lanes"),
+            LaneError::WidthMismatch { lane, width, expected } => {
+                write!(f, "lane {lane} has {width} columns instead of {expected}")
+            }
+            LaneError::LengthMismatch { lane, length, expected } => {
+                write!(f, "lane {lane} has {length} rows instead of {expected}")
+            }
+            LaneError::UnevenWidth { width, lanes } => write!(f, "{width} columns do not split into {lanes} lanes"),
+            LaneError::AuxiliarySegment => write!(f, "lanes do not support auxiliary segments"),
+            LaneError::Verifier(err) => write!(f, "proof verification failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for LaneError {}
+
+/// `k` instances of `A`, one per lane.
+///
+/// Each lane's frame is copied out of the full frame into one scratch frame shared by all
+/// lanes of an evaluated row. AIRs with auxiliary trace segments are not supported; the
+/// public inputs must pass [`LaneInputs::check`] for the trace.
+pub struct LanesAir<A> {
+    lanes: Vec<A>,
+    lane_width: usize,
+    /// Index of each lane's first periodic column
+    periodic_offsets: Vec<usize>,
+    context: AirContext<BaseElement>,
+}
+
+impl<A: Air<BaseField = BaseElement>> Air for LanesAir<A> {
+    type BaseField = BaseElement;
+    type PublicInputs = LaneInputs<A::PublicInputs>;
+
+    fn new(trace_info: TraceInfo, pub_inputs: Self::PublicInputs, options: ProofOptions) -> Self {
+        assert!(!trace_info.is_multi_segment(), "Lanes do not support auxiliary segments");
+        let num_lanes = pub_inputs.lanes.len();
+        assert!(num_lanes > 0, "At least one lane is required");
+        assert_eq!(trace_info.width() % num_lanes, 0, "Every lane has the same width");
+        let lane_width = trace_info.width() / num_lanes;
+
+        let lane_info = TraceInfo::new(lane_width, trace_info.length());
+        let lanes: Vec<A> =
+            pub_inputs.lanes.into_iter().map(|inputs| A::new(lane_info.clone(), inputs, options.clone())).collect();
+        let exemptions = lanes[0].context().num_transition_exemptions();
+        assert!(
+            lanes.iter().all(|lane| lane.context().num_transition_exemptions() == exemptions),
+            "Every lane has the same number of transition exemptions"
+        );
+
+        // Constraints: those of every lane, in lane order
+        let mut degrees = Vec::new();
+        let mut periodic_offsets = Vec::with_capacity(num_lanes);
+        let (mut num_periodic, mut num_assertions) = (0, 0);
+        for lane in &lanes {
+            let context = lane.context();
+            let zeros = vec![BaseElement::ZERO; context.num_transition_constraints()];
+            let constraints = lane.get_transition_constraints(&zeros);
+            degrees.extend(constraints.main_constraint_degrees().iter().cloned());
+            periodic_offsets.push(num_periodic);
+            num_periodic += lane.get_periodic_column_values().len();
+            num_assertions += context.num_assertions();
+        }
+        let context =
+            AirContext::new(trace_info, degrees, num_assertions, options).set_num_transition_exemptions(exemptions);
+
+        Self { lanes, lane_width, periodic_offsets, context }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+
+    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let (current, next) = (frame.current(), frame.next());
+        let mut lane_frame = EvaluationFrame::new(self.lane_width);
+        let mut result = result;
+        for (i, lane) in self.lanes.iter().enumerate() {
+            let columns = i * self.lane_width..(i + 1) * self.lane_width;
+            lane_frame.current_mut().copy_from_slice(&current[columns.clone()]);
+            lane_frame.next_mut().copy_from_slice(&next[columns]);
+            let periodic_end = self.periodic_offsets.get(i + 1).copied().unwrap_or(periodic_values.len());
+            let lane_periodic = &periodic_values[self.periodic_offsets[i]..periodic_end];
+
+            let (lane_result, rest) = result.split_at_mut(lane.context().num_main_transition_constraints());
+            lane.evaluate_transition(&lane_frame, lane_periodic, lane_result);
+            result = rest;
+        }
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = Vec::new();
+        for (i, lane) in self.lanes.iter().enumerate() {
+            let shift = i * self.lane_width;
+            assertions.extend(lane.get_assertions().into_iter().map(|assertion| shift_column(&assertion, shift)));
+        }
+        assertions
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        self.lanes.iter().flat_map(|lane| lane.get_periodic_column_values()).collect()
+    }
+}
+
+/// `assertion` on the column `shift` columns to the right
+fn shift_column(assertion: &Assertion<BaseElement>, shift: usize) -> Assertion<BaseElement> {
+    let (column, first_step, stride) = (assertion.column() + shift, assertion.first_step(), assertion.stride());
+    if assertion.is_single() {
+        Assertion::single(column, first_step, assertion.values()[0])
+    } else if assertion.is_periodic() {
+        Assertion::periodic(column, first_step, stride, assertion.values()[0])
+    } else {
+        Assertion::sequence(column, first_step, stride, assertion.values().to_vec())
+    }
+}
+
+/// Named columns of a [`LanesAir`] of `num_lanes` lanes of the AIR of `lane`: the lane's columns
+/// prefixed with `lane0_`, `lane1_`, ..., see [`crate::layout`]
+pub fn layout(lane: &LayoutDescriptor, num_lanes: usize) -> LayoutDescriptor {
+    let layout = LayoutDescriptor::new(&format!("{AIR_ID}({})", lane.air_id));
+    (0..num_lanes).fold(layout, |layout, i| layout.with_layout(&format!("lane{i}"), lane, i * lane.width()))
+}
+
+/// Verifies a proof of [`LanesAir`], rejecting statements that do not fit its trace instead of
+/// letting [`LanesAir::new`] panic
+pub fn verify_lanes<A: Air<BaseField = BaseElement>>(
+    proof: Proof,
+    inputs: LaneInputs<A::PublicInputs>,
+    acceptable: &AcceptableOptions,
+) -> Result<(), LaneError> {
+    inputs.check(proof.trace_info())?;
+    crate::prover::verify::<LanesAir<A>>(proof, inputs, acceptable).map_err(LaneError::Verifier)
+}
+
+/// Places `traces` side by side, lane `i` in the `i`-th group of columns
+#[cfg(feature = "prover")]
+pub fn interleave_lanes(traces: &[TraceTable<BaseElement>]) -> Result<TraceTable<BaseElement>, LaneError> {
+    let first = traces.first().ok_or(LaneError::NoLanes)?;
+    let (width, length) = (first.width(), first.length());
+    let mut columns = Vec::with_capacity(width * traces.len());
+    for (lane, trace) in traces.iter().enumerate() {
+        if trace.width() != width {
+            return Err(LaneError::WidthMismatch { lane, width: trace.width(), expected: width });
+        }
+        if trace.length() != length {
+            return Err(LaneError::LengthMismatch { lane, length: trace.length(), expected: length });
+        }
+        columns.extend((0..width).map(|column| trace.get_column(column).to_vec()));
+    }
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    type Lanes = LanesAir<LinearRegressionAir>;
+
+    fn lane(slope: u128, intercept: u128, xs: &[u128], x: u128) -> (TraceTable<BaseElement>, LinearRegressionInputs) {
+        let (slope, intercept, x) = (BaseElement::new(slope), BaseElement::new(intercept), BaseElement::new(x));
+        let xs: Vec<BaseElement> = xs.iter().map(|&x| BaseElement::new(x)).collect();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, x, TraceInfo::MIN_TRACE_LENGTH);
+        let predicted_y = slope * x + intercept;
+        let inputs = LinearRegressionInputs { x_value: x, predicted_y, sample_x_values: xs, sample_y_values: ys };
+        (trace, inputs)
+    }
+
+    #[test]
+    fn test_lanes_prove_independent_models() {
+        let (traces, statements): (Vec<_>, Vec<_>) =
+            [lane(2, 5, &[1, 3, 4], 8), lane(7, 1, &[2, 6, 9], 3), lane(1, 40, &[5, 10], 20)].into_iter().unzip();
+        let trace = interleave_lanes(&traces).unwrap();
+        let inputs = LaneInputs::new(statements).unwrap();
+        crate::air_test!(Lanes, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<Lanes>(trace, inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+        verify_lanes::<LinearRegressionAir>(proof.clone(), inputs.clone(), &default_acceptable_options()).unwrap();
+
+        // every lane's statement is checked on its own columns
+        let mut tampered = inputs.clone();
+        tampered.lanes[2].predicted_y += BaseElement::ONE;
+        assert!(verify::<Lanes>(proof.clone(), tampered, &default_acceptable_options()).is_err());
+        let mut swapped = inputs;
+        swapped.lanes.swap(0, 1);
+        assert!(verify::<Lanes>(proof.clone(), swapped.clone(), &default_acceptable_options()).is_err());
+
+        // statements that do not split the trace are errors, not panics
+        let acceptable = default_acceptable_options();
+        assert_eq!(
+            verify_lanes::<LinearRegressionAir>(proof.clone(), LaneInputs { lanes: vec![] }, &acceptable),
+            Err(LaneError::NoLanes)
+        );
+        swapped.lanes.pop();
+        assert_eq!(
+            verify_lanes::<LinearRegressionAir>(proof, swapped, &acceptable),
+            Err(LaneError::UnevenWidth { width: 21, lanes: 2 })
+        );
+        assert_eq!(LaneInputs::<LinearRegressionInputs>::new(vec![]), Err(LaneError::NoLanes));
+    }
+
+    #[test]
+    fn test_rejects_mismatched_lanes() {
+        let (short, _) = lane(2, 5, &[1, 3], 8);
+        let (long, _) = lane(2, 5, &[1, 3, 4, 5, 6, 7, 8], 8);
+        assert_eq!(interleave_lanes(&[short.clone(), long]).unwrap_err(), LaneError::LengthMismatch {
+            lane: 1,
+            length: 16,
+            expected: 8
+        });
+        let narrow = TraceTable::init(vec![vec![BaseElement::ONE; 8]]);
+        assert_eq!(interleave_lanes(&[short, narrow]).unwrap_err(), LaneError::WidthMismatch {
+            lane: 1,
+            width: 1,
+            expected: 7
+        });
+        assert_eq!(interleave_lanes(&[]).unwrap_err(), LaneError::NoLanes);
+    }
+}
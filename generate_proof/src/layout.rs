@@ -0,0 +1,330 @@
+//! Canonical, versioned descriptions of AIR trace layouts.
+//!
+//! A [`LayoutDescriptor`] names every column of an AIR's main trace: single columns such as
+//! `slope`, and groups such as a sponge state or the digits of a range check, whose columns
+//! are named by index, e.g. `sponge[2]`. Every shipped AIR describes its trace with a `layout`
+//! function next to its column constants; AIRs wrapping another AIR name its columns with a
+//! prefix, see [`LayoutDescriptor::with_layout`].
+//!
+//! The [`LayoutHash`] is computed from a canonical form that does not depend on how the code
+//! declares the columns: entries are sorted by name and hashed with their first column, width
+//! and kind, under [`LAYOUT_VERSION`] and the AIR id. Declaring the columns in another order or
+//! renaming internal constants keeps the hash; moving, resizing or renaming a column changes
+//! it. The tests pin the hash of every shipped AIR, so a layout change fails CI unless it comes
+//! with a new AIR id version and a new pinned hash. The layout hash is part of the
+//! [`ConstraintFingerprint`](crate::fingerprint::ConstraintFingerprint) and the
+//! [`ConfigCommitment`](crate::config::ConfigCommitment) of every proof, and audit logs record
+//! the [`versioned_id`](LayoutDescriptor::versioned_id).
+
+use core::fmt;
+use core::ops::Range;
+
+/// Version of the canonical form; changing it changes every layout hash
+pub const LAYOUT_VERSION: u32 = 1;
+
+const LAYOUT_DOMAIN: &[u8] = b"stark-framework/trace-layout";
+
+/// Hash of a canonical layout descriptor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayoutHash(pub [u8; 32]);
+
+impl fmt::Display for LayoutHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", crate::abi::to_hex(&self.0))
+    }
+}
+
+/// A named column, or a named group of adjacent columns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnEntry {
+    pub name: String,
+    pub columns: Range<usize>,
+    /// Whether the columns are named by index, also when the group has a single column
+    pub group: bool,
+}
+
+/// Reason a layout descriptor is not canonical
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// Names are lowercase ASCII letters, digits and underscores, starting with a letter
+    InvalidName(String),
+    DuplicateName(String),
+    EmptyGroup(String),
+    /// `column` is named by more than one entry
+    Overlap { column: usize },
+    /// `column` lies within the trace but is not named
+    Unnamed { column: usize },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::InvalidName(name) => write!(f, "column name {name:?} is not lowercase snake case"),
+            LayoutError::DuplicateName(name) => write!(f, "column name {name:?} is used twice"),
+            LayoutError::EmptyGroup(name) => write!(f, "column group {name} has no columns"),
+            LayoutError::Overlap { column } => write!(f, "column {column} has more than one name"),
+            LayoutError::Unnamed { column } => write!(f, "column {column} has no name"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Named columns of an AIR's main trace, kept in canonical order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutDescriptor {
+    pub air_id: String,
+    pub version: u32,
+    entries: Vec<ColumnEntry>,
+}
+
+impl LayoutDescriptor {
+    pub fn new(air_id: &str) -> Self {
+        Self { air_id: air_id.to_string(), version: LAYOUT_VERSION, entries: Vec::new() }
+    }
+
+    /// Names the single column `column`
+    pub fn with_column(self, name: &str, column: usize) -> Self {
+        self.with_entry(ColumnEntry { name: name.to_string(), columns: column..column + 1, group: false })
+    }
+
+    /// Names the group of `columns`, whose columns are `name[0]`, `name[1]`, ...
+    pub fn with_columns(self, name: &str, columns: Range<usize>) -> Self {
+        self.with_entry(ColumnEntry { name: name.to_string(), columns, group: true })
+    }
+
+    /// Names the columns of `inner`, shifted by `offset` columns, `{prefix}_{name}`, e.g. for
+    /// AIRs that wrap other AIRs
+    pub fn with_layout(self, prefix: &str, inner: &LayoutDescriptor, offset: usize) -> Self {
+        inner.entries.iter().fold(self, |layout, entry| {
+            layout.with_entry(ColumnEntry {
+                name: format!("{prefix}_{}", entry.name),
+                columns: entry.columns.start + offset..entry.columns.end + offset,
+                group: entry.group,
+            })
+        })
+    }
+
+    fn with_entry(mut self, entry: ColumnEntry) -> Self {
+        let position = self.entries.partition_point(|other| other.name <= entry.name);
+        self.entries.insert(position, entry);
+        self
+    }
+
+    /// Entries sorted by name
+    pub fn entries(&self) -> &[ColumnEntry] {
+        &self.entries
+    }
+
+    /// Number of columns: one past the last named column
+    pub fn width(&self) -> usize {
+        self.entries.iter().map(|entry| entry.columns.end).max().unwrap_or(0)
+    }
+
+    /// Checks the names and that every column of the trace has exactly one
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        for (i, entry) in self.entries.iter().enumerate() {
+            let mut chars = entry.name.chars();
+            let valid = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+                && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                return Err(LayoutError::InvalidName(entry.name.clone()));
+            }
+            if i > 0 && self.entries[i - 1].name == entry.name {
+                return Err(LayoutError::DuplicateName(entry.name.clone()));
+            }
+            if entry.columns.is_empty() {
+                return Err(LayoutError::EmptyGroup(entry.name.clone()));
+            }
+        }
+        let mut named = vec![false; self.width()];
+        for column in self.entries.iter().flat_map(|entry| entry.columns.clone()) {
+            if core::mem::replace(&mut named[column], true) {
+                return Err(LayoutError::Overlap { column });
+            }
+        }
+        match named.iter().position(|&named| !named) {
+            Some(column) => Err(LayoutError::Unnamed { column }),
+            None => Ok(()),
+        }
+    }
+
+    /// Name of every column in column order, e.g. for
+    /// [`TraceLayout::with_column_names`](crate::external::TraceLayout::with_column_names)
+    pub fn column_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = (0..self.width()).map(|column| format!("column {column}")).collect();
+        for entry in &self.entries {
+            for (index, column) in entry.columns.clone().enumerate() {
+                names[column] = if entry.group { format!("{}[{index}]", entry.name) } else { entry.name.clone() };
+            }
+        }
+        names
+    }
+
+    /// Layout of an external trace of `length` rows with the names of this descriptor
+    #[cfg(feature = "prover")]
+    pub fn trace_layout(&self, length: usize) -> crate::external::TraceLayout {
+        crate::external::TraceLayout::new(self.width(), length).with_column_names(self.column_names())
+    }
+
+    /// Hash of the canonical form
+    pub fn hash(&self) -> LayoutHash {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(LAYOUT_DOMAIN);
+        hasher.update(&self.version.to_le_bytes());
+        update_bytes(&mut hasher, self.air_id.as_bytes());
+        update_usize(&mut hasher, self.width());
+        update_usize(&mut hasher, self.entries.len());
+        for entry in &self.entries {
+            update_bytes(&mut hasher, entry.name.as_bytes());
+            update_usize(&mut hasher, entry.columns.start);
+            update_usize(&mut hasher, entry.columns.len());
+            hasher.update(&[entry.group as u8]);
+        }
+        LayoutHash(*hasher.finalize().as_bytes())
+    }
+
+    /// The AIR id followed by the first four bytes of the layout hash, e.g.
+    /// `chain-segment/v1@3073e8b4`
+    pub fn versioned_id(&self) -> String {
+        let hash = self.hash();
+        let prefix: String = hash.0[..4].iter().map(|byte| format!("{byte:02x}")).collect();
+        format!("{}@{prefix}", self.air_id)
+    }
+}
+
+fn update_usize(hasher: &mut blake3::Hasher, value: usize) {
+    hasher.update(&(value as u64).to_le_bytes());
+}
+
+fn update_bytes(hasher: &mut blake3::Hasher, bytes: &[u8]) {
+    update_usize(hasher, bytes.len());
+    hasher.update(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{ColumnSchema, DataSchema};
+    use crate::sparse::FeatureLayout;
+    use crate::*;
+
+    /// Layout hashes of [`shipped`] in order. A failure means a trace layout changed: bump the
+    /// AIR id's version with the hash.
+    const PINNED: [&str; 28] = [
+        "0xb1f0987d4c5da8c9e2fc83b85682f9ddef0a2883faa6e08d40c4ce7bba45ffef", // accuracy
+        "0xe818c2f5e490e4ff328b310a4bb4251026b25a066d8bc5c01a9fc92b51d6bce9", // aggregate
+        "0xbff16d1329613bf260dd7de1b2c72ddb6bf1353866396a0df7d6a524cbaa80d8", // categorical
+        "0x3073e8b42383153ea0afd02ff78ec02d195aaf2d80fa8270286264bc5b67da00", // chain
+        "0x921e481a72911ec8653c57bde87ee880f3153b01ff0976dcf89aec2fbdee970a", // compound
+        "0x02200ba9a7bdad74e7b3954083fa826700512568692d925e77dc317ec347890d", // distinct
+        "0xe9d0c7af2698a8c8f48906e072d1757267c229323e85bce7d25040eaedda36ab", // drift
+        "0x200a41873364e55ddae0eb920cf8b215c75bb5f09e7ab0582d8ddc94b6c084d3", // ema
+        "0xabb7a612c04b84896c4544abafdfb9a7e73b4f47a13fab8461db62e2328e85a2", // etl
+        "0x5b474e735b6016bc3652da475fb0734fc965e7580de6653f52a616aee853b3dc", // evaluations
+        "0xadd3b4da27afba82f36c18466917de4c92e93a89844ebc9b9f0110aebab119a6", // freshness
+        "0x0e8c69951b97f045d086ec826de6f8ff11a108268b1866276b6ef9a2b3466a35", // importance
+        "0x1e08ae86d56875c87b877e183fcc732cd23ecb7127dff2a858ac229f2952edcf", // interval
+        "0xa8ebe6f6b73ef2eb59b26a53d9dec21394097b646775651f25a86ea549b8871b", // lanes
+        "0xbfd842acbe70fb3e69ff686d8d9d545267922add4cecb1210f68f984d51a453d", // piecewise
+        "0xaab3affdbb039f6de1b5b209e1665d94513970368a45baad3be8ceee5d974988", // piecewise
+        "0x1e952e555a464eea13ff5553e1cf829e3d7c5a07a84e80408bf892b96d1808c8", // privacy
+        "0x175bb3a85103a8fecd67885b74580ee504afa4a7a72596586094bc4276b20dc6", // quantile
+        "0x8dcec572cf3d12a5ade505feca8a0c23191e7cf448d731e5757c825ecd4d7af8", // regression
+        "0xfcf059bf19f0318df30137aa302841c2e75dc238335bc8836092c034baf92ebe", // robustness
+        "0xfcd8c696edab38557b34c8c62b5187c53ecd5f4ca205105ed8aec0e8addf8578", // sensitivity
+        "0x85ed632ac39a718191d86bd1fcaefde7b0bc0dd49d4166c1fc6acf161e6d9f02", // smoothing
+        "0x1aa0e806f3e71ec2eca6e9ad00940b0779893e03e890cb679e98343380d8ec62", // sparse
+        "0xeb5fa722cc9e8b230ab966707a2b8a7d3f84184022eb71d4c1f8aa7b0b6b97cf", // state
+        "0x7c3abd28a7c39a59095b79db6c106a284e50b931f0ac69caa4f7ee238eb42e49", // validity
+        "0x06fa6a5ebc027419f5970dbb77782a8ac2d60c3e5e3ca6aaca6e190788fae05c", // variance
+        "0x3ba02a550c891784f52b43d3839107d093d9490d9ce20a0520488ed0bfb3a2b3", // weighted_fit
+        "0xe4da0dd2465aa48dccf557bd5553bfeb3e5877d120cf07a84d89952020671951", // weighted_sum
+    ];
+
+    /// Every shipped AIR at fixed parameters, with its trace width
+    fn shipped() -> Vec<(LayoutDescriptor, usize)> {
+        let schema = DataSchema::new(
+            vec![ColumnSchema::new("area", "m^2", 0, 10, 500)],
+            ColumnSchema::new("price", "", 0, 0, 100_000),
+        )
+        .unwrap();
+        let features = FeatureLayout::new(vec![0, 2, 3]).unwrap();
+        let claim = aggregate::AggregateClaim { column: 3, rows: 5, sum: Some(0), min: Some(0), max: Some(0) };
+        vec![
+            (accuracy::layout(), accuracy::trace_width()),
+            (aggregate::layout(&regression::layout(), &claim), 110),
+            (categorical::layout(3), 8),
+            (chain::layout(), 6),
+            (compound::layout(16), 69),
+            (distinct::layout(), distinct::trace_width()),
+            (drift::layout(), drift::trace_width()),
+            (ema::layout(16), ema::trace_width(16)),
+            (etl::layout(), 6),
+            (evaluations::layout(&regression::layout(), 2), 9),
+            (freshness::layout(), freshness::trace_width()),
+            (importance::layout(2), importance::trace_width(2)),
+            (interval::layout(), interval::TRACE_WIDTH),
+            (lanes::layout(&chain::layout(), 3), 18),
+            (piecewise::layout(2, true), 81),
+            (piecewise::layout(0, false), 5),
+            (privacy::layout(16, 20), privacy::trace_width(16, 20)),
+            (quantile::layout(), quantile::trace_width()),
+            (regression::layout(), regression::TRACE_WIDTH),
+            (robustness::layout(), robustness::trace_width()),
+            (sensitivity::layout(), sensitivity::trace_width()),
+            (smoothing::layout(4), smoothing::trace_width(4)),
+            (sparse::layout(&features), features.trace_width()),
+            (state::layout(), 8),
+            (validity::layout(&schema).unwrap(), validity::trace_width(&schema).unwrap()),
+            (variance::layout(10, 8), variance::trace_width(10, 8)),
+            (weighted_fit::layout(), weighted_fit::trace_width()),
+            (weighted_sum::layout(16).unwrap(), 21),
+        ]
+    }
+
+    #[test]
+    fn test_shipped_layouts_are_pinned() {
+        assert_eq!(shipped().len(), PINNED.len());
+        let mut ids = std::collections::HashSet::new();
+        for ((descriptor, width), hash) in shipped().into_iter().zip(PINNED) {
+            let air_id = descriptor.air_id.clone();
+            assert_eq!(descriptor.validate(), Ok(()), "{air_id}");
+            assert_eq!(descriptor.width(), width, "{air_id}");
+            assert_eq!(descriptor.hash().to_string(), hash, "layout of {air_id} changed");
+            assert!(ids.insert(descriptor.versioned_id()));
+        }
+        let names = regression::layout().column_names();
+        assert_eq!(names, ["slope", "intercept", "x", "y", "phases[0]", "phases[1]", "phases[2]"]);
+        assert_eq!(chain::layout().versioned_id(), "chain-segment/v1@3073e8b4");
+        let lanes = lanes::layout(&chain::layout(), 2).column_names();
+        assert_eq!(lanes[..2], ["lane0_sponge[0]", "lane0_sponge[1]"]);
+        assert!(lanes::layout(&chain::layout(), 2).versioned_id().starts_with("lanes/v1(chain-segment/v1)@"));
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let demo = |air_id: &str, x: usize, sponge: &str, y: usize| {
+            LayoutDescriptor::new(air_id).with_column("x", x).with_columns(sponge, 1..5).with_column("y", y)
+        };
+        let layout = demo("demo/v1", 0, "sponge", 5);
+        let reordered =
+            LayoutDescriptor::new("demo/v1").with_column("y", 5).with_column("x", 0).with_columns("sponge", 1..5);
+        assert_eq!(layout.hash(), reordered.hash());
+        assert_eq!(layout.column_names()[..2], ["x", "sponge[0]"]);
+
+        // moving, renaming or regrouping a column, or another AIR id, changes the hash
+        let regrouped =
+            LayoutDescriptor::new("demo/v1").with_columns("x", 0..1).with_columns("sponge", 1..5).with_column("y", 5);
+        let moved = demo("demo/v1", 5, "sponge", 0);
+        let changed = [moved, demo("demo/v1", 0, "state", 5), regrouped, demo("demo/v2", 0, "sponge", 5)];
+        assert!(changed.iter().all(|other| other.hash() != layout.hash()));
+
+        let invalid = |descriptor: LayoutDescriptor| descriptor.validate().unwrap_err();
+        assert_eq!(invalid(layout.clone().with_column("Z", 6)), LayoutError::InvalidName("Z".into()));
+        assert_eq!(invalid(layout.clone().with_column("x", 6)), LayoutError::DuplicateName("x".into()));
+        assert_eq!(invalid(layout.clone().with_columns("digits", 6..6)), LayoutError::EmptyGroup("digits".into()));
+        assert_eq!(invalid(layout.clone().with_column("z", 4)), LayoutError::Overlap { column: 4 });
+        assert_eq!(invalid(layout.with_column("z", 7)), LayoutError::Unnamed { column: 6 });
+    }
+}
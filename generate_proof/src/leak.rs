@@ -0,0 +1,191 @@
+//! Leak detection for AIR configurations: does anything public reveal the private witness?
+//!
+//! [`find_leaks`] takes the same AIR instantiated for several witnesses, each a trace with the
+//! public inputs of its claim, and the columns meant to stay private. It flags
+//!
+//! - assertions against a private column, which put the asserted cells into every proof,
+//! - public input elements that equal the same private cell in every sample while that cell
+//!   changes between samples, i.e. the public inputs carry a copy of the witness,
+//! - trace lengths that differ between samples whose public inputs have the same shape, since
+//!   the length is part of every proof and then depends on the witness alone.
+//!
+//! The checks are differential, so the samples should differ in every private value; a cell
+//! that tracks a public input only by coincidence in all of them is unlikely. They are meant
+//! for tests of new AIRs and say nothing about leakage through the STARK itself.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, ToElements},
+    Air, ProofOptions, Trace, TraceTable,
+};
+
+/// Public artifact that reveals private witness data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Leak {
+    /// An assertion pins a cell of a private column to a public value
+    AssertedPrivateCell { column: usize, step: usize, value: BaseElement },
+    /// Public input element `input` is a copy of a private cell
+    PublicInputRevealsCell { input: usize, column: usize, step: usize },
+    /// The trace length varies with the witness although the public inputs have the same shape
+    LengthDependsOnWitness { lengths: Vec<usize> },
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Leak::AssertedPrivateCell { column, step, value } => {
+                write!(f, "assertion reveals private cell ({column}, {step}) = {value}")
+            }
+            Leak::PublicInputRevealsCell { input, column, step } => {
+                write!(f, "public input {input} copies private cell ({column}, {step})")
+            }
+            Leak::LengthDependsOnWitness { lengths } => write!(f, "trace length depends on the witness: {lengths:?}"),
+        }
+    }
+}
+
+/// Checks `samples` of AIR `A` for public artifacts revealing `private_columns`.
+///
+/// # Panics
+///
+/// Panics with fewer than two samples, which cannot tell witness data from constants.
+pub fn find_leaks<A>(
+    samples: &[(TraceTable<BaseElement>, A::PublicInputs)],
+    private_columns: &[usize],
+    options: ProofOptions,
+) -> Vec<Leak>
+where
+    A: Air<BaseField = BaseElement>,
+    A::PublicInputs: Clone,
+{
+    assert!(samples.len() >= 2, "Leak detection needs at least two witnesses");
+    let mut leaks = Vec::new();
+
+    for (trace, inputs) in samples {
+        let air = A::new(trace.info().clone(), inputs.clone(), options.clone());
+        for assertion in air.get_assertions() {
+            if private_columns.contains(&assertion.column()) {
+                assertion.apply(trace.length(), |step, value| {
+                    let leak = Leak::AssertedPrivateCell { column: assertion.column(), step, value };
+                    if !leaks.contains(&leak) {
+                        leaks.push(leak);
+                    }
+                });
+            }
+        }
+    }
+
+    let public: Vec<Vec<BaseElement>> = samples.iter().map(|(_, inputs)| inputs.to_elements()).collect();
+    let shortest = samples.iter().map(|(trace, _)| trace.length()).min().expect("At least two samples");
+    let num_inputs = public.iter().map(Vec::len).min().expect("At least two samples");
+    for input in 0..num_inputs {
+        'cells: for &column in private_columns {
+            for step in 0..shortest {
+                let cells: Vec<BaseElement> = samples.iter().map(|(trace, _)| trace.get(column, step)).collect();
+                let copies = cells.iter().zip(&public).all(|(cell, elements)| *cell == elements[input]);
+                if copies && cells.iter().any(|cell| *cell != cells[0]) {
+                    leaks.push(Leak::PublicInputRevealsCell { input, column, step });
+                    // one witness cell is enough to flag the input against this column
+                    continue 'cells;
+                }
+            }
+        }
+    }
+
+    let lengths: Vec<usize> = samples.iter().map(|(trace, _)| trace.length()).collect();
+    let same_public_shape = public.iter().all(|elements| elements.len() == public[0].len());
+    if same_public_shape && lengths.iter().any(|&length| length != lengths[0]) {
+        leaks.push(Leak::LengthDependsOnWitness { lengths });
+    }
+
+    leaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+    use winterfell::{
+        math::FieldElement, AirContext, Assertion, EvaluationFrame, TraceInfo, TransitionConstraintDegree,
+    };
+
+    fn linear_sample(slope: u64, intercept: u64) -> (TraceTable<BaseElement>, LinearRegressionInputs) {
+        let (slope, intercept) = (BaseElement::from(slope), BaseElement::from(intercept));
+        let xs: Vec<BaseElement> = [2u64, 3, 5].map(BaseElement::from).to_vec();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(4);
+        let inputs = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+        (build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH), inputs)
+    }
+
+    /// Asserts its secret column against a public copy of the secret, the mistake being detected
+    struct RevealingAir {
+        context: AirContext<BaseElement>,
+        secret: BaseElement,
+    }
+
+    #[derive(Clone)]
+    struct Secret(BaseElement);
+
+    impl ToElements<BaseElement> for Secret {
+        fn to_elements(&self) -> Vec<BaseElement> {
+            vec![self.0]
+        }
+    }
+
+    impl Air for RevealingAir {
+        type BaseField = BaseElement;
+        type PublicInputs = Secret;
+
+        fn new(trace_info: TraceInfo, secret: Secret, options: ProofOptions) -> Self {
+            let degrees = vec![TransitionConstraintDegree::new(1)];
+            Self { context: AirContext::new(trace_info, degrees, 1, options), secret: secret.0 }
+        }
+
+        fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+            &self,
+            frame: &EvaluationFrame<E>,
+            _periodic_values: &[E],
+            result: &mut [E],
+        ) {
+            result[0] = frame.next()[0] - frame.current()[0];
+        }
+
+        fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+            vec![Assertion::single(0, 0, self.secret)]
+        }
+
+        fn context(&self) -> &AirContext<Self::BaseField> {
+            &self.context
+        }
+    }
+
+    #[test]
+    fn test_flags_revealed_witness() {
+        let options = ProverConfig::default().to_proof_options();
+        // slope and intercept stay private in the linear regression AIR
+        let samples = [linear_sample(3, 7), linear_sample(5, 1), linear_sample(11, 2)];
+        assert_eq!(find_leaks::<LinearRegressionAir>(&samples, &[0, 1], options.clone()), vec![]);
+
+        let revealing = |secret: u64, length: usize| {
+            (TraceTable::init(vec![vec![BaseElement::from(secret); length]]), Secret(BaseElement::from(secret)))
+        };
+        let leaks = find_leaks::<RevealingAir>(&[revealing(3, 8), revealing(9, 16)], &[0], options);
+        assert_eq!(
+            leaks,
+            vec![
+                Leak::AssertedPrivateCell { column: 0, step: 0, value: BaseElement::new(3) },
+                Leak::AssertedPrivateCell { column: 0, step: 0, value: BaseElement::new(9) },
+                Leak::PublicInputRevealsCell { input: 0, column: 0, step: 0 },
+                Leak::LengthDependsOnWitness { lengths: vec![8, 16] },
+            ]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! STARK proofs for linear regression claims, built on Winterfell.
+//!
+//! The proofs produced here are verified on-chain by the no_std contract in `verifier/`.
+//!
+//! By default the crate only verifies: it links `winter-verifier` instead of `winterfell` and
+//! leaves every trace builder out of the build. The `prover` feature adds the trace builders,
+//! the prover and proving sessions; see `Cargo.toml` for the other features. Without either,
+//! only the modules that need no Winterfell build: `abi`, `metrics`, `public_inputs`, `schema`
+//! and, with `http`, the TLS client. `scripts/check-features.sh` lints every combination.
+
+// verification-only builds reach the verifier half of Winterfell under its usual paths
+#[cfg(all(feature = "verifier", not(feature = "prover")))]
+extern crate winter_verifier as winterfell;
+
+pub mod abi;
+#[cfg(feature = "verifier")]
+pub mod accuracy;
+#[cfg(feature = "verifier")]
+pub mod aggregate;
+#[cfg(feature = "prover")]
+pub mod air_test;
+#[cfg(feature = "prover")]
+pub mod analysis;
+#[cfg(feature = "onchain")]
+pub mod anchor;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "verifier")]
+pub mod audit;
+#[cfg(feature = "verifier")]
+pub mod batch;
+#[cfg(feature = "verifier")]
+pub mod beacon;
+#[cfg(feature = "prover")]
+pub mod blocking;
+#[cfg(feature = "verifier")]
+pub mod boundary;
+#[cfg(feature = "prover")]
+pub mod budget;
+#[cfg(feature = "verifier")]
+pub mod categorical;
+#[cfg(feature = "verifier")]
+pub mod chain;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "verifier")]
+pub mod codec;
+#[cfg(feature = "verifier")]
+pub mod coin;
+#[cfg(feature = "verifier")]
+pub mod compound;
+#[cfg(feature = "verifier")]
+pub mod config;
+#[cfg(all(feature = "prover", feature = "onchain"))]
+pub mod conformance;
+#[cfg(feature = "verifier")]
+pub mod constraints;
+#[cfg(feature = "verifier")]
+pub mod dataset;
+#[cfg(feature = "verifier")]
+pub mod describe;
+#[cfg(feature = "verifier")]
+pub mod diagnostics;
+#[cfg(feature = "prover")]
+pub mod determinism;
+#[cfg(feature = "verifier")]
+pub mod disclosure;
+#[cfg(feature = "verifier")]
+pub mod distinct;
+#[cfg(feature = "verifier")]
+pub mod domain;
+#[cfg(feature = "verifier")]
+pub mod drift;
+#[cfg(feature = "verifier")]
+pub mod ema;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "verifier")]
+pub mod envelope;
+#[cfg(feature = "verifier")]
+pub mod etl;
+#[cfg(feature = "verifier")]
+pub mod evaluations;
+#[cfg(feature = "prover")]
+pub mod evaluator;
+#[cfg(feature = "verifier")]
+pub mod explain;
+#[cfg(feature = "prover")]
+pub mod external;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "prover")]
+pub mod fields;
+#[cfg(feature = "verifier")]
+pub mod fingerprint;
+#[cfg(feature = "verifier")]
+pub mod fixed_point;
+#[cfg(feature = "verifier")]
+pub mod freshness;
+#[cfg(feature = "verifier")]
+pub mod gadgets;
+#[cfg(feature = "verifier")]
+pub mod hardware;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "verifier")]
+pub mod importance;
+#[cfg(feature = "verifier")]
+pub mod integer_regression;
+#[cfg(feature = "verifier")]
+pub mod interop;
+#[cfg(feature = "verifier")]
+pub mod interval;
+#[cfg(feature = "verifier")]
+pub mod lanes;
+#[cfg(feature = "verifier")]
+pub mod layout;
+#[cfg(feature = "prover")]
+pub mod leak;
+#[cfg(feature = "verifier")]
+pub mod marketplace;
+#[cfg(feature = "prover")]
+pub mod merkle;
+pub mod metrics;
+#[cfg(feature = "verifier")]
+pub mod migrate;
+#[cfg(feature = "verifier")]
+pub mod nonce;
+#[cfg(feature = "verifier")]
+pub mod operator;
+#[cfg(feature = "onchain")]
+pub mod optimistic;
+#[cfg(feature = "verifier")]
+pub mod piecewise;
+#[cfg(feature = "prover")]
+pub mod pool;
+#[cfg(feature = "verifier")]
+pub mod privacy;
+#[cfg(feature = "verifier")]
+pub mod provenance;
+#[cfg(feature = "verifier")]
+pub mod prover;
+pub mod public_inputs;
+#[cfg(feature = "verifier")]
+pub mod quantile;
+#[cfg(feature = "verifier")]
+pub mod quantized;
+#[cfg(feature = "verifier")]
+pub mod queries;
+#[cfg(feature = "verifier")]
+pub mod regression;
+#[cfg(feature = "verifier")]
+pub mod registry;
+#[cfg(feature = "verifier")]
+pub mod resolver;
+#[cfg(feature = "prover")]
+pub mod resources;
+#[cfg(feature = "verifier")]
+pub mod robustness;
+pub mod schema;
+#[cfg(feature = "prover")]
+pub mod secret;
+#[cfg(feature = "verifier")]
+pub mod sensitivity;
+#[cfg(feature = "verifier")]
+pub mod serialization;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "verifier")]
+pub mod session;
+#[cfg(feature = "prover")]
+pub mod shares;
+#[cfg(feature = "verifier")]
+pub mod signature;
+#[cfg(feature = "prover")]
+pub mod simulation;
+#[cfg(feature = "prover")]
+pub mod sklearn;
+#[cfg(feature = "verifier")]
+pub mod smoothing;
+#[cfg(feature = "verifier")]
+pub mod sparse;
+#[cfg(feature = "verifier")]
+pub mod standardize;
+#[cfg(feature = "verifier")]
+pub mod state;
+#[cfg(feature = "server")]
+pub mod streaming;
+#[cfg(feature = "verifier")]
+pub mod storage;
+#[cfg(feature = "verifier")]
+pub mod terms;
+#[cfg(feature = "prover")]
+pub mod trace;
+#[cfg(feature = "verifier")]
+pub mod transcript;
+#[cfg(feature = "verifier")]
+pub mod validity;
+#[cfg(feature = "verifier")]
+pub mod variance;
+#[cfg(feature = "verifier")]
+pub mod weighted_fit;
+#[cfg(feature = "verifier")]
+pub mod weighted_sum;
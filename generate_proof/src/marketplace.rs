@@ -0,0 +1,289 @@
+//! Verification of envelopes from many provers, each under its own policy.
+//!
+//! A marketplace accepts claims from provers it has onboarded, and not every prover may make
+//! every claim: one is cleared for some AIRs, another only for its own models, a third must
+//! prove at a higher security level. A [`VerifierPool`] holds a [`ProverPolicy`] for each
+//! prover, keyed by the operator key the envelope is signed with (see
+//! [`operator`](crate::operator)), and a registry of the AIRs it can verify, which it tells
+//! apart by their constraint fingerprints.
+//!
+//! [`VerifierPool::verify`] keeps the reasons apart: a [`PolicyViolation`] means a possibly
+//! valid proof the prover was not allowed to make, a [`SessionError`] that the envelope or its
+//! proof did not verify.
+
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::config::{conjectured_security_bits, VerificationPolicy};
+use crate::envelope::ProofEnvelope;
+use crate::fingerprint::ConstraintFingerprint;
+use crate::operator::{verify_signer, OperatorPublicKey};
+use crate::regression::{self, LinearRegressionInputs};
+use crate::registry::ModelCommitment;
+use crate::session::{linear_fingerprint, verify_linear, SessionError};
+
+/// An AIR a [`VerifierPool`] can verify envelopes of
+#[derive(Clone, Copy, Debug)]
+pub struct AirVerifier {
+    pub air_id: &'static str,
+    /// Fingerprint the AIR has for the envelope's trace, public inputs and options, or `None` if
+    /// the public inputs are not the AIR's
+    pub fingerprint: fn(&ProofEnvelope) -> Option<ConstraintFingerprint>,
+    pub verify: fn(&ProofEnvelope, &VerificationPolicy) -> Result<(), SessionError>,
+}
+
+impl AirVerifier {
+    /// Claims of [`ProvingSession::prove_linear`](crate::session::ProvingSession::prove_linear)
+    pub fn linear() -> Self {
+        Self {
+            air_id: regression::AIR_ID,
+            fingerprint: |envelope| {
+                let proof = &envelope.proof;
+                let inputs = LinearRegressionInputs::from_elements(&envelope.public_inputs)?;
+                Some(linear_fingerprint(proof.trace_info().clone(), inputs, proof.options().clone()))
+            },
+            verify: verify_linear,
+        }
+    }
+}
+
+/// What one prover may claim; empty lists allow everything
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProverPolicy {
+    /// Ids of the AIRs the prover may make claims of
+    pub allowed_airs: Vec<String>,
+    /// Required on top of the pool's policy
+    pub min_security_bits: u32,
+    pub allowed_models: Vec<ModelCommitment>,
+}
+
+impl ProverPolicy {
+    pub fn with_airs(mut self, air_ids: &[&str]) -> Self {
+        self.allowed_airs = air_ids.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn with_min_security_bits(mut self, bits: u32) -> Self {
+        self.min_security_bits = bits;
+        self
+    }
+
+    pub fn with_models(mut self, models: Vec<ModelCommitment>) -> Self {
+        self.allowed_models = models;
+        self
+    }
+}
+
+/// Reason a prover may not make an envelope's claim, whether or not its proof holds
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The envelope carries no operator signature, so its prover is unknown
+    Unsigned,
+    /// The envelope is signed by a prover the pool has no policy for
+    UnknownProver(OperatorPublicKey),
+    /// The constraint fingerprint is not that of any AIR of the pool
+    UnknownAir,
+    AirNotAllowed { prover: OperatorPublicKey, air_id: &'static str },
+    ModelNotAllowed { prover: OperatorPublicKey, model: ModelCommitment },
+    /// The proof's options reach fewer bits of conjectured security than the prover must
+    InsufficientSecurity { prover: OperatorPublicKey, required: u32, found: u32 },
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::Unsigned => write!(f, "envelope is not signed by a prover"),
+            PolicyViolation::UnknownProver(prover) => write!(f, "no policy for prover {prover}"),
+            PolicyViolation::UnknownAir => write!(f, "constraint fingerprint matches no known AIR"),
+            PolicyViolation::AirNotAllowed { prover, air_id } => {
+                write!(f, "prover {prover} may not make {air_id} claims")
+            }
+            PolicyViolation::ModelNotAllowed { prover, model } => {
+                write!(f, "prover {prover} may not make claims for model {}", crate::abi::to_hex(&model.0))
+            }
+            PolicyViolation::InsufficientSecurity { prover, required, found } => {
+                write!(f, "prover {prover} must reach {required} bits of security, proof reaches {found}")
+            }
+        }
+    }
+}
+
+/// Reason a [`VerifierPool`] rejects an envelope
+#[derive(Debug)]
+pub enum PoolError {
+    Policy(PolicyViolation),
+    /// The signature, the envelope or the proof is invalid
+    Verification(SessionError),
+}
+
+impl PoolError {
+    pub fn is_policy_violation(&self) -> bool {
+        matches!(self, PoolError::Policy(_))
+    }
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::Policy(violation) => write!(f, "policy violation: {violation}"),
+            PoolError::Verification(err) => write!(f, "verification failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for PoolError {}
+
+/// Prover and AIR of an envelope a [`VerifierPool`] accepted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Accepted {
+    pub prover: OperatorPublicKey,
+    pub air_id: &'static str,
+}
+
+/// Verifier of envelopes from many provers under per-prover policies
+#[derive(Clone, Debug)]
+pub struct VerifierPool {
+    /// Applied to every envelope; its trusted operators are replaced by the signing prover
+    policy: VerificationPolicy,
+    airs: Vec<AirVerifier>,
+    provers: HashMap<OperatorPublicKey, ProverPolicy>,
+}
+
+impl VerifierPool {
+    /// Pool verifying linear claims under `policy`, with no provers yet
+    pub fn new(policy: VerificationPolicy) -> Self {
+        Self { policy, airs: vec![AirVerifier::linear()], provers: HashMap::new() }
+    }
+
+    pub fn with_air(mut self, air: AirVerifier) -> Self {
+        self.airs.push(air);
+        self
+    }
+
+    /// Admits `prover` under `policy`, replacing its previous policy
+    pub fn with_prover(mut self, prover: OperatorPublicKey, policy: ProverPolicy) -> Self {
+        self.provers.insert(prover, policy);
+        self
+    }
+
+    pub fn prover_policy(&self, prover: &OperatorPublicKey) -> Option<&ProverPolicy> {
+        self.provers.get(prover)
+    }
+
+    /// Checks the envelope's signature, its prover's policy and then its proof
+    pub fn verify(&self, envelope: &ProofEnvelope) -> Result<Accepted, PoolError> {
+        let violation = |violation| Err(PoolError::Policy(violation));
+        let prover = match verify_signer(envelope, &self.policy.domain) {
+            Ok(Some(prover)) => prover,
+            Ok(None) => return violation(PolicyViolation::Unsigned),
+            Err(err) => return Err(PoolError::Verification(SessionError::Signer(err))),
+        };
+        let Some(rules) = self.provers.get(&prover) else {
+            return violation(PolicyViolation::UnknownProver(prover));
+        };
+
+        let fingerprint = Some(envelope.constraint_fingerprint);
+        let Some(air) = self.airs.iter().find(|air| (air.fingerprint)(envelope) == fingerprint) else {
+            return violation(PolicyViolation::UnknownAir);
+        };
+        if !rules.allowed_airs.is_empty() && !rules.allowed_airs.iter().any(|id| id == air.air_id) {
+            return violation(PolicyViolation::AirNotAllowed { prover, air_id: air.air_id });
+        }
+        let model = envelope.model_commitment;
+        if !rules.allowed_models.is_empty() && !rules.allowed_models.contains(&model) {
+            return violation(PolicyViolation::ModelNotAllowed { prover, model });
+        }
+        let required = rules.min_security_bits;
+        let found = conjectured_security_bits(envelope.proof.options());
+        if found < required {
+            return violation(PolicyViolation::InsufficientSecurity { prover, required, found });
+        }
+
+        let policy = VerificationPolicy {
+            min_security_bits: self.policy.min_security_bits.max(required),
+            trusted_operators: vec![prover],
+            ..self.policy.clone()
+        };
+        (air.verify)(envelope, &policy).map_err(PoolError::Verification)?;
+        Ok(Accepted { prover, air_id: air.air_id })
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::to_hex;
+    use crate::config::ProverConfig;
+    use crate::operator::{OperatorKey, SignerError};
+    use crate::session::{LinearClaimRequest, ProvingSession};
+
+    fn envelope(seed: u8, x: u64) -> ProofEnvelope {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let mut session = ProvingSession::new(ProverConfig::default(), "ci");
+        if seed != 0 {
+            session = session.with_operator_key(OperatorKey::from_seed(&[seed; 32]));
+        }
+        session.prove_linear(&request).unwrap()
+    }
+
+    #[test]
+    fn test_policies_are_resolved_by_signer() {
+        let [alice, bob, carol] = [1u8, 2, 3].map(|seed| OperatorKey::from_seed(&[seed; 32]).public_key());
+        let signed = envelope(1, 4);
+        let model = signed.model_commitment;
+        let pool = VerifierPool::new(VerificationPolicy::default())
+            .with_prover(alice, ProverPolicy::default().with_airs(&[regression::AIR_ID]).with_models(vec![model]))
+            .with_prover(bob, ProverPolicy::default().with_airs(&["variance/v1"]))
+            .with_prover(carol, ProverPolicy::default().with_models(vec![ModelCommitment([0u8; 32])]));
+
+        let accepted = pool.verify(&signed).unwrap();
+        assert_eq!(accepted, Accepted { prover: alice, air_id: regression::AIR_ID });
+
+        let violation = |envelope: &ProofEnvelope| match pool.verify(envelope) {
+            Err(PoolError::Policy(violation)) => violation,
+            other => panic!("expected a policy violation, got {other:?}"),
+        };
+        assert_eq!(violation(&envelope(0, 4)), PolicyViolation::Unsigned);
+        let air_id = regression::AIR_ID;
+        assert_eq!(violation(&envelope(2, 4)), PolicyViolation::AirNotAllowed { prover: bob, air_id });
+        assert_eq!(violation(&envelope(3, 4)), PolicyViolation::ModelNotAllowed { prover: carol, model });
+        let stranger = OperatorKey::from_seed(&[4u8; 32]).public_key();
+        assert_eq!(violation(&envelope(4, 4)), PolicyViolation::UnknownProver(stranger));
+
+        let strict = pool.clone().with_prover(alice, ProverPolicy::default().with_min_security_bits(200));
+        assert!(matches!(
+            strict.verify(&signed),
+            Err(PoolError::Policy(PolicyViolation::InsufficientSecurity { required: 200, .. }))
+        ));
+        let unknown = ProofEnvelope { constraint_fingerprint: ConstraintFingerprint([0u8; 32]), ..signed };
+        assert_eq!(violation(&unknown), PolicyViolation::UnknownAir);
+    }
+
+    #[test]
+    fn test_invalid_proofs_are_not_policy_violations() {
+        let alice = OperatorKey::from_seed(&[1u8; 32]).public_key();
+        let pool = VerifierPool::new(VerificationPolicy::default()).with_prover(alice, ProverPolicy::default());
+
+        // the signature covers the claim, not the proof bytes
+        let mut swapped = envelope(1, 4);
+        swapped.proof = envelope(1, 6).proof;
+        let err = pool.verify(&swapped).unwrap_err();
+        assert!(matches!(err, PoolError::Verification(SessionError::Verifier(_))), "{err}");
+        assert!(!err.is_policy_violation());
+
+        let mut forged = envelope(1, 4);
+        forged.public_inputs[1] += winterfell::math::fields::f128::BaseElement::from(1u8);
+        let err = pool.verify(&forged).unwrap_err();
+        assert!(matches!(err, PoolError::Verification(SessionError::Signer(SignerError::BadSignature))));
+    }
+}
@@ -0,0 +1,477 @@
+//! Multi-threaded construction of the trace and constraint commitments, with an extension point
+//! for accelerators.
+//!
+//! Unless the prover is compiled with Winterfell's `concurrent` feature, Winterfell hashes a
+//! commitment's rows into leaves and builds its Merkle tree on the proving thread. Here both
+//! steps are spread out. [`MerkleOptions::hash_rows`] splits the rows across scoped threads;
+//! [`AirProver`] hashes the trace rows with it in [`PooledTraceLde`](crate::pool::PooledTraceLde)
+//! and the constraint evaluations in [`RowConstraintCommitment`]. [`HybridMerkleTree`] builds
+//! the tree level by level: a level with at least [`MerkleOptions::min_parallel_pairs`] nodes,
+//! the leaf level included, is split across scoped threads, and a level with at least
+//! [`MerkleOptions::min_device_pairs`] nodes is handed to a [`DeviceHasher`] when one is
+//! registered. A device error falls back to the threads for that level.
+//!
+//! The crate ships no GPU kernel: [`DeviceHasher`] is the extension point a backend batching
+//! Blake3 over 64-byte inputs on an accelerator implements, and [`ReferenceDevice`] is the CPU
+//! implementation such a backend must match bit for bit, which also runs the device path on
+//! machines without one.
+//!
+//! Leaves and nodes are identical to those of Winterfell's `RowMatrix::commit_to_rows` and
+//! [`MerkleTree`], which answers the openings, so proofs made by a [`HybridProver`] verify with
+//! [`verify`](crate::prover::verify) unchanged.
+
+use core::marker::PhantomData;
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+    thread,
+};
+
+use winter_air::proof::Queries;
+use winter_prover::ConstraintCommitment;
+use winter_verifier::Deserializable;
+use winterfell::{
+    crypto::{
+        BatchMerkleProof, DefaultRandomCoin, Digest as _, ElementHasher, Hasher, MerkleTree, MerkleTreeError,
+        VectorCommitment,
+    },
+    math::{fields::f128::BaseElement, FieldElement},
+    matrix::RowMatrix,
+    CompositionPoly, CompositionPolyTrace, PartitionOptions, StarkDomain, TraceTable,
+};
+
+use crate::prover::{AirProver, HashFn};
+
+type Digest = <HashFn as Hasher>::Digest;
+
+/// Columns Winterfell evaluates together when extending the composition polynomial
+const SEGMENT_WIDTH: usize = 8;
+
+/// Prover whose commitments are built by [`HybridMerkleTree`]
+pub type HybridProver<A> = AirProver<A, TraceTable<BaseElement>, DefaultRandomCoin<HashFn>, HybridMerkleTree>;
+
+static DEVICE: OnceLock<Arc<dyn DeviceHasher>> = OnceLock::new();
+
+/// Batch Blake3 hashing on an accelerator
+pub trait DeviceHasher: Send + Sync {
+    /// Writes the Blake3 hash of every 64-byte block of `input` to the matching entry of
+    /// `output`
+    fn hash_pairs(&self, input: &[u8], output: &mut [[u8; 32]]) -> Result<(), DeviceError>;
+}
+
+/// The reference [`DeviceHasher`], hashing every block with the `blake3` crate on the calling
+/// thread
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReferenceDevice;
+
+impl DeviceHasher for ReferenceDevice {
+    fn hash_pairs(&self, input: &[u8], output: &mut [[u8; 32]]) -> Result<(), DeviceError> {
+        let (blocks, rest) = input.as_chunks::<64>();
+        if !rest.is_empty() || blocks.len() != output.len() {
+            return Err(DeviceError(format!("{} input bytes for {} hashes", input.len(), output.len())));
+        }
+        for (block, hash) in blocks.iter().zip(output) {
+            *hash = *blake3::hash(block).as_bytes();
+        }
+        Ok(())
+    }
+}
+
+/// Failure reported by a [`DeviceHasher`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceError(pub String);
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "device hashing failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for DeviceError {}
+
+/// Makes `device` the default of every [`MerkleOptions`]; Winterfell builds commitments with
+/// default options, so this is how a [`HybridProver`] reaches it. Returns `device` back when one
+/// is already registered.
+pub fn register_device(device: Arc<dyn DeviceHasher>) -> Result<(), Arc<dyn DeviceHasher>> {
+    DEVICE.set(device)
+}
+
+/// How a [`HybridMerkleTree`] spreads the hashing
+#[derive(Clone)]
+pub struct MerkleOptions {
+    pub threads: usize,
+    /// Smallest level split across threads
+    pub min_parallel_pairs: usize,
+    pub device: Option<Arc<dyn DeviceHasher>>,
+    /// Smallest level handed to the device
+    pub min_device_pairs: usize,
+}
+
+impl Default for MerkleOptions {
+    /// One thread per available core and the registered device, if any
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |cores| cores.get()),
+            min_parallel_pairs: 1 << 10,
+            device: DEVICE.get().cloned(),
+            min_device_pairs: 1 << 14,
+        }
+    }
+}
+
+impl MerkleOptions {
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn with_min_parallel_pairs(mut self, pairs: usize) -> Self {
+        self.min_parallel_pairs = pairs;
+        self
+    }
+
+    pub fn with_device(mut self, device: Arc<dyn DeviceHasher>) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    pub fn with_min_device_pairs(mut self, pairs: usize) -> Self {
+        self.min_device_pairs = pairs;
+        self
+    }
+
+    /// Leaves committing to `num_rows` rows of `width` elements, row `i` being written by
+    /// `read_row(i, row)`. Each row is hashed in the partitions of `partition_options`, like
+    /// Winterfell's `RowMatrix::commit_to_rows` does; at least [`Self::min_parallel_pairs`] rows
+    /// are split across threads.
+    pub fn hash_rows<E, H>(
+        &self,
+        num_rows: usize,
+        width: usize,
+        partition_options: PartitionOptions,
+        read_row: impl Fn(usize, &mut [E]) + Sync,
+    ) -> Vec<H::Digest>
+    where
+        E: FieldElement<BaseField = BaseElement>,
+        H: ElementHasher<BaseField = BaseElement>,
+    {
+        let partition_size = partition_options.partition_size::<E>(width);
+        let hash_chunk = |first_row: usize, leaves: &mut [H::Digest]| {
+            let mut row = vec![E::ZERO; width];
+            let mut partitions = Vec::new();
+            for (i, leaf) in leaves.iter_mut().enumerate() {
+                read_row(first_row + i, &mut row);
+                *leaf = if partition_size == width {
+                    H::hash_elements(&row)
+                } else {
+                    partitions.clear();
+                    partitions.extend(row.chunks(partition_size).map(H::hash_elements));
+                    H::merge_many(&partitions)
+                };
+            }
+        };
+        let mut leaves = vec![H::Digest::default(); num_rows];
+        if self.threads > 1 && num_rows >= self.min_parallel_pairs {
+            let chunk = num_rows.div_ceil(self.threads);
+            thread::scope(|scope| {
+                for (i, leaves) in leaves.chunks_mut(chunk).enumerate() {
+                    let hash_chunk = &hash_chunk;
+                    scope.spawn(move || hash_chunk(i * chunk, leaves));
+                }
+            });
+        } else {
+            hash_chunk(0, &mut leaves);
+        }
+        leaves
+    }
+
+    /// Sets every parent to the hash of its two children, `children` being twice as long
+    fn merge_level(&self, parents: &mut [Digest], children: &[Digest]) {
+        if let Some(device) = self.device.as_ref().filter(|_| parents.len() >= self.min_device_pairs)
+            && merge_on_device(device.as_ref(), parents, children).is_ok()
+        {
+            return;
+        }
+        if self.threads > 1 && parents.len() >= self.min_parallel_pairs {
+            let chunk = parents.len().div_ceil(self.threads);
+            thread::scope(|scope| {
+                for (parents, children) in parents.chunks_mut(chunk).zip(children.chunks(2 * chunk)) {
+                    scope.spawn(move || merge(parents, children));
+                }
+            });
+        } else {
+            merge(parents, children);
+        }
+    }
+}
+
+fn merge(parents: &mut [Digest], children: &[Digest]) {
+    for (parent, pair) in parents.iter_mut().zip(children.as_chunks::<2>().0) {
+        *parent = HashFn::merge(pair);
+    }
+}
+
+fn merge_on_device(device: &dyn DeviceHasher, parents: &mut [Digest], children: &[Digest]) -> Result<(), DeviceError> {
+    let input: Vec<u8> = children.iter().flat_map(|digest| digest.as_bytes()).collect();
+    let mut output = vec![[0u8; 32]; parents.len()];
+    device.hash_pairs(&input, &mut output)?;
+    for (parent, hash) in parents.iter_mut().zip(&output) {
+        *parent = Digest::read_from_bytes(hash).expect("Blake3 digests are 32 bytes");
+    }
+    Ok(())
+}
+
+/// Merkle tree over Blake3 digests built with [`MerkleOptions`]
+pub struct HybridMerkleTree {
+    tree: MerkleTree<HashFn>,
+}
+
+impl HybridMerkleTree {
+    pub fn tree(&self) -> &MerkleTree<HashFn> {
+        &self.tree
+    }
+}
+
+impl VectorCommitment<HashFn> for HybridMerkleTree {
+    type Options = MerkleOptions;
+    type Proof = Vec<Digest>;
+    type MultiProof = BatchMerkleProof<HashFn>;
+    type Error = MerkleTreeError;
+
+    fn with_options(leaves: Vec<Digest>, options: MerkleOptions) -> Result<Self, MerkleTreeError> {
+        if leaves.len() < 2 {
+            return Err(MerkleTreeError::TooFewLeaves(2, leaves.len()));
+        }
+        if !leaves.len().is_power_of_two() {
+            return Err(MerkleTreeError::NumberOfLeavesNotPowerOfTwo(leaves.len()));
+        }
+        // the root sits at index 1 and the children of node i at 2i and 2i + 1
+        let n = leaves.len() / 2;
+        let mut nodes = vec![Digest::default(); 2 * n];
+        options.merge_level(&mut nodes[n..], &leaves);
+        let mut width = n / 2;
+        while width > 0 {
+            let (parents, children) = nodes[width..4 * width].split_at_mut(width);
+            options.merge_level(parents, children);
+            width /= 2;
+        }
+        Ok(Self { tree: MerkleTree::from_raw_parts(nodes, leaves)? })
+    }
+
+    fn commitment(&self) -> Digest {
+        self.tree.commitment()
+    }
+
+    fn domain_len(&self) -> usize {
+        self.tree.domain_len()
+    }
+
+    fn get_proof_domain_len(proof: &Self::Proof) -> usize {
+        MerkleTree::<HashFn>::get_proof_domain_len(proof)
+    }
+
+    fn get_multiproof_domain_len(proof: &Self::MultiProof) -> usize {
+        MerkleTree::<HashFn>::get_multiproof_domain_len(proof)
+    }
+
+    fn open(&self, index: usize) -> Result<(Digest, Self::Proof), MerkleTreeError> {
+        self.tree.open(index)
+    }
+
+    fn open_many(&self, indexes: &[usize]) -> Result<(Vec<Digest>, Self::MultiProof), MerkleTreeError> {
+        self.tree.open_many(indexes)
+    }
+
+    fn verify(commitment: Digest, index: usize, item: Digest, proof: &Self::Proof) -> Result<(), MerkleTreeError> {
+        <MerkleTree<HashFn> as VectorCommitment<HashFn>>::verify(commitment, index, item, proof)
+    }
+
+    fn verify_many(
+        commitment: Digest,
+        indexes: &[usize],
+        items: &[Digest],
+        proof: &Self::MultiProof,
+    ) -> Result<(), MerkleTreeError> {
+        MerkleTree::<HashFn>::verify_many(commitment, indexes, items, proof)
+    }
+}
+
+/// Commitment to the constraint evaluations whose rows are hashed by
+/// [`MerkleOptions::hash_rows`], otherwise Winterfell's `DefaultConstraintCommitment`
+pub struct RowConstraintCommitment<E, H, V>
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement>,
+    V: VectorCommitment<H>,
+{
+    evaluations: RowMatrix<E>,
+    commitment: V,
+    _h: PhantomData<H>,
+}
+
+impl<E, H, V> RowConstraintCommitment<E, H, V>
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement>,
+    V: VectorCommitment<H>,
+{
+    pub fn new(
+        composition_poly_trace: CompositionPolyTrace<E>,
+        num_constraint_composition_columns: usize,
+        domain: &StarkDomain<BaseElement>,
+        partition_options: PartitionOptions,
+    ) -> (Self, CompositionPoly<E>) {
+        let composition_poly =
+            CompositionPoly::new(composition_poly_trace, domain, num_constraint_composition_columns);
+        let evaluations = RowMatrix::evaluate_polys_over::<SEGMENT_WIDTH>(composition_poly.data(), domain);
+        let leaves = MerkleOptions::default().hash_rows::<E, H>(
+            evaluations.num_rows(),
+            evaluations.num_cols(),
+            partition_options,
+            |i, row| row.copy_from_slice(evaluations.row(i)),
+        );
+        let commitment = V::new(leaves).expect("failed to construct constraint vector commitment");
+        (Self { evaluations, commitment, _h: PhantomData }, composition_poly)
+    }
+}
+
+impl<E, H, V> ConstraintCommitment<E> for RowConstraintCommitment<E, H, V>
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement> + Sync,
+    V: VectorCommitment<H> + Sync,
+{
+    type HashFn = H;
+    type VC = V;
+
+    fn commitment(&self) -> H::Digest {
+        self.commitment.commitment()
+    }
+
+    fn query(self, positions: &[usize]) -> Queries {
+        let (_, proof) = self
+            .commitment
+            .open_many(positions)
+            .expect("failed to generate a batch opening proof for constraint queries");
+        let rows = positions.iter().map(|&position| self.evaluations.row(position).to_vec()).collect();
+        Queries::new::<H, E, V>(proof, rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use winterfell::{matrix::ColMatrix, Prover, TraceInfo};
+
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::verify;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+    use winterfell::math::FieldElement;
+
+    fn leaves(count: u64) -> Vec<Digest> {
+        (0..count).map(|i| HashFn::hash(&i.to_le_bytes())).collect()
+    }
+
+    /// Hashes with the [`ReferenceDevice`] and counts the pairs, or fails every call
+    struct FakeDevice {
+        pairs: AtomicUsize,
+        broken: bool,
+    }
+
+    impl DeviceHasher for FakeDevice {
+        fn hash_pairs(&self, input: &[u8], output: &mut [[u8; 32]]) -> Result<(), DeviceError> {
+            if self.broken {
+                return Err(DeviceError("out of memory".into()));
+            }
+            ReferenceDevice.hash_pairs(input, output)?;
+            self.pairs.fetch_add(output.len(), Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_matches_the_sequential_tree() {
+        let leaves = leaves(1 << 10);
+        let expected = MerkleTree::<HashFn>::new(leaves.clone()).unwrap();
+        for threads in [1, 3, 8] {
+            let options = MerkleOptions::default().with_threads(threads).with_min_parallel_pairs(4);
+            let tree = HybridMerkleTree::with_options(leaves.clone(), options).unwrap();
+            assert_eq!(tree.commitment(), *expected.root());
+            let (leaf, proof) = tree.open(777).unwrap();
+            assert_eq!(MerkleTree::<HashFn>::verify(tree.commitment(), 777, leaf, &proof), Ok(()));
+        }
+
+        for broken in [false, true] {
+            let device = Arc::new(FakeDevice { pairs: AtomicUsize::new(0), broken });
+            let options = MerkleOptions::default().with_device(device.clone()).with_min_device_pairs(128);
+            let tree = HybridMerkleTree::with_options(leaves.clone(), options).unwrap();
+            assert_eq!(tree.commitment(), *expected.root());
+            // the levels of 512, 256 and 128 pairs go to the device, the rest stays on the CPU
+            assert_eq!(device.pairs.load(Ordering::Relaxed), if broken { 0 } else { 512 + 256 + 128 });
+        }
+        assert!(HybridMerkleTree::new(leaves[..3].to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_reference_device_matches_the_cpu_path() {
+        for count in [2, 8, 1 << 9] {
+            let leaves = leaves(count);
+            let expected = MerkleTree::<HashFn>::new(leaves.clone()).unwrap();
+            // every level, the root's included, is hashed by the device
+            let options = MerkleOptions::default().with_device(Arc::new(ReferenceDevice)).with_min_device_pairs(1);
+            let tree = HybridMerkleTree::with_options(leaves, options).unwrap();
+            assert_eq!(tree.commitment(), *expected.root());
+            let last = count as usize - 1;
+            assert_eq!(tree.open(last).unwrap(), expected.open(last).unwrap());
+        }
+        let mut output = [[0u8; 32]; 2];
+        assert!(ReferenceDevice.hash_pairs(&[0u8; 96], &mut output).is_err());
+    }
+
+    #[test]
+    fn test_rows_hash_like_winterfell() {
+        let columns = (0..5u64)
+            .map(|c| (0..64u64).map(|r| BaseElement::from(c * 1000 + r)).collect())
+            .collect();
+        let matrix = RowMatrix::evaluate_polys::<SEGMENT_WIDTH>(&ColMatrix::new(columns), 4);
+        for partitions in [PartitionOptions::default(), PartitionOptions::new(3, 1)] {
+            let expected = matrix.commit_to_rows::<HashFn, MerkleTree<HashFn>>(partitions);
+            for threads in [1, 3] {
+                let options = MerkleOptions::default().with_threads(threads).with_min_parallel_pairs(16);
+                let leaves = options.hash_rows::<BaseElement, HashFn>(
+                    matrix.num_rows(),
+                    matrix.num_cols(),
+                    partitions,
+                    |i, row| row.copy_from_slice(matrix.row(i)),
+                );
+                assert_eq!(MerkleTree::<HashFn>::new(leaves).unwrap().root(), expected.root());
+            }
+        }
+    }
+
+    #[test]
+    fn test_hybrid_proofs_verify_unchanged() {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::ONE);
+        let xs = vec![BaseElement::new(2), BaseElement::new(4)];
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(5),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(5),
+            predicted_y: BaseElement::new(16),
+            sample_x_values: xs,
+            sample_y_values: ys,
+        };
+        let options = ProverConfig::default().to_proof_options();
+        let proof = HybridProver::<LinearRegressionAir>::new(options, inputs.clone()).prove(trace).unwrap();
+        assert!(verify::<LinearRegressionAir>(proof, inputs, &default_acceptable_options()).is_ok());
+    }
+}
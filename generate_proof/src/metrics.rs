@@ -0,0 +1,239 @@
+//! Proving health counters in the Prometheus text exposition format.
+//!
+//! A [`ProvingSession`](crate::session::ProvingSession) records the proofs it generates and how
+//! long building the trace and proving took; the server adds verifications and the number of
+//! requests waiting for the session to prove, and serves [`Metrics::render`] at `GET /metrics`. Every value is an atomic, so one
+//! [`Metrics`] can be shared between threads behind an `Arc`.
+//!
+//! Trace buffer counters show how many trace columns each job allocated and how many it
+//! reused from a [`TracePool`](crate::pool::TracePool), so the effect of pooling is visible
+//! by comparing a session with and without one.
+
+use core::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[cfg(feature = "prover")]
+use crate::pool::PoolStats;
+
+/// Upper bounds of the duration histogram buckets, in seconds
+pub const DURATION_BUCKETS: [f64; 10] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Timed phase of a proving or verification job
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Building the execution trace
+    Trace,
+    /// Generating the STARK proof from the trace
+    Prove,
+    /// Verifying an envelope
+    Verify,
+}
+
+impl Phase {
+    const ALL: [Phase; 3] = [Phase::Trace, Phase::Prove, Phase::Verify];
+
+    fn label(self) -> &'static str {
+        match self {
+            Phase::Trace => "trace",
+            Phase::Prove => "prove",
+            Phase::Verify => "verify",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative; the last one counts values above every bound
+    buckets: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS.iter().position(|&bound| seconds <= bound).unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, phase: &str) {
+        let mut cumulative = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let bound = DURATION_BUCKETS.get(i).map_or("+Inf".to_string(), |bound| bound.to_string());
+            let _ = writeln!(out, "{name}_bucket{{phase=\"{phase}\",le=\"{bound}\"}} {cumulative}");
+        }
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(out, "{name}_sum{{phase=\"{phase}\"}} {sum}");
+        let _ = writeln!(out, "{name}_count{{phase=\"{phase}\"}} {cumulative}");
+    }
+}
+
+/// Counters, gauges and phase durations of a proving service
+#[derive(Default)]
+pub struct Metrics {
+    proofs_generated: AtomicU64,
+    proof_failures: AtomicU64,
+    verifications: AtomicU64,
+    verification_failures: AtomicU64,
+    queue_depth: AtomicU64,
+    estimated_peak_memory: AtomicU64,
+    trace_buffer_allocations: AtomicU64,
+    trace_buffer_reuses: AtomicU64,
+    durations: [Histogram; 3],
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_proof(&self, succeeded: bool) {
+        let counter = if succeeded { &self.proofs_generated } else { &self.proof_failures };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_verification(&self, succeeded: bool) {
+        self.verifications.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            self.verification_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn observe(&self, phase: Phase, duration: Duration) {
+        self.durations[phase as usize].observe(duration);
+    }
+
+    /// Raises the estimated memory high-water mark to `bytes` if it is higher
+    pub fn record_estimated_memory(&self, bytes: u64) {
+        self.estimated_peak_memory.fetch_max(bytes, Ordering::Relaxed);
+    }
+
+    /// Counts the trace columns a job allocated and reused
+    #[cfg(feature = "prover")]
+    pub fn record_trace_buffers(&self, stats: PoolStats) {
+        self.trace_buffer_allocations.fetch_add(stats.allocated as u64, Ordering::Relaxed);
+        self.trace_buffer_reuses.fetch_add(stats.reused as u64, Ordering::Relaxed);
+    }
+
+    /// Marks a request as waiting for the prover until the returned guard is dropped
+    pub fn enqueue(&self) -> QueueGuard<'_> {
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+        QueueGuard(&self.queue_depth)
+    }
+
+    pub fn proofs_generated(&self) -> u64 {
+        self.proofs_generated.load(Ordering::Relaxed)
+    }
+
+    pub fn verification_failures(&self) -> u64 {
+        self.verification_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn queue_depth(&self) -> u64 {
+        self.queue_depth.load(Ordering::Relaxed)
+    }
+
+    pub fn trace_buffer_allocations(&self) -> u64 {
+        self.trace_buffer_allocations.load(Ordering::Relaxed)
+    }
+
+    pub fn trace_buffer_reuses(&self) -> u64 {
+        self.trace_buffer_reuses.load(Ordering::Relaxed)
+    }
+
+    /// All metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut scalar = |name: &str, kind: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}");
+        };
+        let load = |value: &AtomicU64| value.load(Ordering::Relaxed);
+
+        scalar("stark_proofs_generated_total", "counter", "Proofs generated.", load(&self.proofs_generated));
+        scalar("stark_proof_failures_total", "counter", "Proving jobs that failed.", load(&self.proof_failures));
+        scalar("stark_verifications_total", "counter", "Envelopes verified.", load(&self.verifications));
+        scalar(
+            "stark_verification_failures_total",
+            "counter",
+            "Envelopes that failed verification.",
+            load(&self.verification_failures),
+        );
+        scalar("stark_queue_depth", "gauge", "Requests waiting for the prover.", load(&self.queue_depth));
+        scalar(
+            "stark_estimated_peak_memory_bytes",
+            "gauge",
+            "Largest peak memory estimate of a proving job.",
+            load(&self.estimated_peak_memory),
+        );
+        scalar(
+            "stark_trace_buffer_allocations_total",
+            "counter",
+            "Trace columns allocated for proving jobs.",
+            load(&self.trace_buffer_allocations),
+        );
+        scalar(
+            "stark_trace_buffer_reuses_total",
+            "counter",
+            "Trace columns reused from earlier proving jobs.",
+            load(&self.trace_buffer_reuses),
+        );
+        if let Some(bytes) = process_memory_high_water() {
+            scalar("stark_memory_high_water_bytes", "gauge", "Peak resident memory of the process.", bytes);
+        }
+
+        let name = "stark_phase_duration_seconds";
+        let _ = writeln!(out, "# HELP {name} Duration of proving and verification phases.\n# TYPE {name} histogram");
+        for phase in Phase::ALL {
+            self.durations[phase as usize].render(&mut out, name, phase.label());
+        }
+        out
+    }
+}
+
+/// Request counted in [`Metrics::queue_depth`] until dropped
+pub struct QueueGuard<'a>(&'a AtomicU64);
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Peak resident set size of this process, where the platform reports it (`VmHWM` on Linux)
+fn process_memory_high_water() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_exposition_format() {
+        let metrics = Metrics::new();
+        metrics.record_proof(true);
+        metrics.record_proof(true);
+        metrics.record_verification(false);
+        metrics.observe(Phase::Prove, Duration::from_millis(300));
+        metrics.observe(Phase::Prove, Duration::from_secs(60));
+        {
+            let _guard = metrics.enqueue();
+            assert_eq!(metrics.queue_depth(), 1);
+        }
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE stark_proofs_generated_total counter\nstark_proofs_generated_total 2\n"));
+        assert!(text.contains("stark_verification_failures_total 1\n"));
+        assert!(text.contains("stark_queue_depth 0\n"));
+        assert!(text.contains("stark_phase_duration_seconds_bucket{phase=\"prove\",le=\"0.25\"} 0\n"));
+        assert!(text.contains("stark_phase_duration_seconds_bucket{phase=\"prove\",le=\"0.5\"} 1\n"));
+        assert!(text.contains("stark_phase_duration_seconds_bucket{phase=\"prove\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("stark_phase_duration_seconds_sum{phase=\"prove\"} 60.3\n"));
+        assert!(text.contains("stark_phase_duration_seconds_count{phase=\"trace\"} 0\n"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Compatibility of stored envelopes with this version of the framework.
+//!
+//! Both encodings carry a format version, bumped whenever the layout of an envelope or the
+//! public inputs its proof binds change. [`COMPATIBILITY`] has a row for every version ever
+//! written: the Winterfell version its proofs were produced with, what it introduced and
+//! whether it can be carried over. A layout-only change keeps the proof valid, so [`migrate`]
+//! re-encodes such envelopes in the current format. A change of the transcript (the public
+//! inputs a proof is bound to) or of Winterfell's proof serialization does not, and those
+//! envelopes are rejected with the reason, so they are proven again instead of failing
+//! verification whenever someone next looks at them. The one exception is the configuration
+//! commitment: proofs of the versions before it still verify under a policy clearing
+//! [`commit_config`](crate::config::VerificationPolicy::commit_config), see
+//! [`VersionInfo::commits_config`].
+//!
+//! Upgrading Winterfell therefore means: bump both versions if proofs of the old release no
+//! longer decode or verify, record the new release in [`WINTERFELL_VERSION`] and mark the
+//! previous rows as [`Migration::Reprove`].
+
+use core::fmt;
+
+use crate::abi::to_hex;
+use crate::envelope::{self, EnvelopeError, ProofEnvelope};
+use crate::registry::ModelId;
+use crate::serialization::{decode_on_chain_versioned, Encoding, MODEL_ID_OFFSET, ON_CHAIN_VERSION, WORD};
+
+/// Winterfell release the current envelope versions are proven and verified with
+pub const WINTERFELL_VERSION: &str = "0.12";
+
+/// What becomes of envelopes of an earlier version
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Migration {
+    /// Written by this build
+    Current,
+    /// The layout changed but the proof still verifies, without a configuration commitment
+    /// under a policy clearing `commit_config`; read by [`ProofEnvelope::from_versioned_bytes`]
+    /// or [`decode_on_chain_versioned`]
+    Reencode,
+    /// The proof no longer verifies, for the given reason
+    Reprove(&'static str),
+}
+
+/// One row of the compatibility matrix
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionInfo {
+    pub encoding: Encoding,
+    pub version: u8,
+    /// Winterfell release the version's proofs were produced with
+    pub winterfell: &'static str,
+    /// What the version introduced
+    pub change: &'static str,
+    pub migration: Migration,
+}
+
+const NO_DOMAIN_TAG: &str = "its public inputs do not start with the deployment's domain tag";
+const NO_NONCE_MARKER: &str = "its public inputs have no claim nonce marker";
+const NO_TERMS_MARKER: &str = "its public inputs have no terms hash marker";
+
+/// First version of each encoding whose proofs commit to their configuration and schema
+const COMPACT_CONFIG_VERSION: u8 = 10;
+const ON_CHAIN_CONFIG_VERSION: u8 = 6;
+
+const fn row(encoding: Encoding, version: u8, change: &'static str, migration: Migration) -> VersionInfo {
+    VersionInfo { encoding, version, winterfell: WINTERFELL_VERSION, change, migration }
+}
+
+/// Every envelope version, oldest first per encoding
+pub const COMPATIBILITY: &[VersionInfo] = &[
+    row(Encoding::Compact, 1, "model id, commitment, public inputs and proof", Migration::Reprove(NO_DOMAIN_TAG)),
+    row(Encoding::Compact, 2, "constraint fingerprint", Migration::Reprove(NO_DOMAIN_TAG)),
+    row(Encoding::Compact, 3, "data schema", Migration::Reprove(NO_NONCE_MARKER)),
+    row(Encoding::Compact, 4, "operator signature", Migration::Reprove(NO_NONCE_MARKER)),
+    row(Encoding::Compact, 5, "drand beacon round", Migration::Reprove(NO_NONCE_MARKER)),
+    row(Encoding::Compact, 6, "claim nonce", Migration::Reprove(NO_TERMS_MARKER)),
+    row(Encoding::Compact, 7, "terms hash", Migration::Reencode),
+    row(Encoding::Compact, 8, "prover provenance", Migration::Reencode),
+    row(Encoding::Compact, 9, "hardware attestation", Migration::Reencode),
+    row(Encoding::Compact, 10, "configuration and schema commitments", Migration::Current),
+    row(Encoding::OnChain, 1, "fixed-offset words with a data schema", Migration::Reprove(NO_NONCE_MARKER)),
+    row(Encoding::OnChain, 2, "operator signature", Migration::Reprove(NO_NONCE_MARKER)),
+    row(Encoding::OnChain, 3, "drand beacon round", Migration::Reprove(NO_NONCE_MARKER)),
+    row(Encoding::OnChain, 4, "claim nonce", Migration::Reprove(NO_TERMS_MARKER)),
+    row(Encoding::OnChain, 5, "terms hash", Migration::Reencode),
+    row(Encoding::OnChain, 6, "configuration and schema commitments", Migration::Current),
+];
+
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} v{} (Winterfell {}, {}): ", self.encoding, self.version, self.winterfell, self.change)?;
+        match self.migration {
+            Migration::Current => write!(f, "current"),
+            Migration::Reencode if self.commits_config() => write!(f, "can be re-encoded"),
+            Migration::Reencode => write!(f, "can be re-encoded, and verified with `commit_config` cleared"),
+            Migration::Reprove(reason) => write!(f, "must be proven again, {reason}"),
+        }
+    }
+}
+
+impl VersionInfo {
+    /// Whether the version's proofs commit to their configuration; those of earlier versions
+    /// bind no schema commitment either and verify only under a policy clearing `commit_config`
+    pub fn commits_config(&self) -> bool {
+        let first = match self.encoding {
+            Encoding::Compact => COMPACT_CONFIG_VERSION,
+            Encoding::OnChain => ON_CHAIN_CONFIG_VERSION,
+        };
+        self.version >= first
+    }
+}
+
+/// The row of `version` of `encoding`
+pub fn compatibility(encoding: Encoding, version: u8) -> Option<&'static VersionInfo> {
+    COMPATIBILITY.iter().find(|info| info.encoding == encoding && info.version == version)
+}
+
+/// Reason an envelope cannot be migrated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MigrationError {
+    NotAnEnvelope,
+    /// A version this build does not know, e.g. one written by a newer release
+    UnknownVersion { encoding: Encoding, version: u8 },
+    /// The envelope's proof no longer verifies; `model` is the model it was proven for
+    Reprove { info: &'static VersionInfo, model: ModelId },
+    Decode(EnvelopeError),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::NotAnEnvelope => write!(f, "not a proof envelope"),
+            MigrationError::UnknownVersion { encoding, version } => {
+                write!(f, "unknown {encoding} envelope version {version}, written by a newer release?")
+            }
+            MigrationError::Reprove { info, model } => write!(f, "claim of model {}: {info}", to_hex(&model.0)),
+            MigrationError::Decode(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<EnvelopeError> for MigrationError {
+    fn from(err: EnvelopeError) -> Self {
+        MigrationError::Decode(err)
+    }
+}
+
+/// Envelope re-encoded by [`migrate`]
+#[derive(Clone, Debug)]
+pub struct Migrated {
+    /// Version the envelope was read as
+    pub from: &'static VersionInfo,
+    pub envelope: ProofEnvelope,
+    /// The envelope in the current version of the target encoding
+    pub bytes: Vec<u8>,
+}
+
+/// Reads an envelope of any version and encodes it in the current version of `target`, which
+/// may differ from its own encoding
+pub fn migrate(bytes: &[u8], target: Encoding) -> Result<Migrated, MigrationError> {
+    let encoding = Encoding::detect(bytes).ok_or(MigrationError::NotAnEnvelope)?;
+    let version = *bytes.get(4).ok_or(MigrationError::NotAnEnvelope)?;
+    let info = compatibility(encoding, version).ok_or(MigrationError::UnknownVersion { encoding, version })?;
+    let envelope = match (info.migration, encoding) {
+        (Migration::Current, _) => encoding.decode(bytes)?,
+        (Migration::Reencode, Encoding::Compact) => ProofEnvelope::from_versioned_bytes(bytes)?.1,
+        (Migration::Reencode, Encoding::OnChain) => decode_on_chain_versioned(bytes)?.1,
+        (Migration::Reprove(_), _) => {
+            // the model id follows the header in every version of both encodings
+            let offset = match encoding {
+                Encoding::Compact => 5,
+                Encoding::OnChain => MODEL_ID_OFFSET,
+            };
+            let model = bytes.get(offset..offset + WORD).ok_or(EnvelopeError::Truncated)?;
+            return Err(MigrationError::Reprove { info, model: ModelId(model.try_into().expect("32-byte id")) });
+        }
+    };
+    Ok(Migrated { from: info, bytes: target.encode(&envelope), envelope })
+}
+
+/// Current version of `encoding`
+pub fn current_version(encoding: Encoding) -> u8 {
+    match encoding {
+        Encoding::Compact => envelope::VERSION,
+        Encoding::OnChain => ON_CHAIN_VERSION,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winterfell::{math::fields::f128::BaseElement, Proof};
+
+    use crate::fingerprint::ConstraintFingerprint;
+    use crate::registry::ModelCommitment;
+
+    #[test]
+    fn test_matrix_covers_every_version() {
+        for encoding in [Encoding::Compact, Encoding::OnChain] {
+            let current = current_version(encoding);
+            for version in 1..=current {
+                let info = compatibility(encoding, version).unwrap();
+                assert_eq!(info.migration == Migration::Current, version == current, "{info}");
+            }
+            assert_eq!(compatibility(encoding, current + 1), None);
+        }
+    }
+
+    #[test]
+    fn test_migrate_envelopes() {
+        let model_id = ModelId::from_name("house-prices");
+        let envelope = ProofEnvelope::new(
+            model_id,
+            ModelCommitment([9u8; 32]),
+            ConstraintFingerprint([4u8; 32]),
+            vec![BaseElement::new(8), BaseElement::new(21)],
+            Proof::new_dummy(),
+        );
+        let compact = envelope.to_bytes();
+        let migrated = migrate(&compact, Encoding::OnChain).unwrap();
+        assert_eq!(migrated.from.version, envelope::VERSION);
+        assert_eq!(migrate(&migrated.bytes, Encoding::Compact).unwrap().bytes, compact);
+
+        // version 9 had the layout of the current version
+        let mut v9 = compact.clone();
+        v9[4] = 9;
+        let reencoded = migrate(&v9, Encoding::Compact).unwrap();
+        assert_eq!((reencoded.from.version, reencoded.bytes), (9, compact.clone()));
+        assert!(reencoded.from.to_string().ends_with("verified with `commit_config` cleared"));
+        let mut v5 = migrated.bytes.clone();
+        v5[4] = 5;
+        let reencoded = migrate(&v5, Encoding::Compact).unwrap();
+        assert_eq!((reencoded.from.version, reencoded.bytes), (5, compact.clone()));
+
+        // version 8 had no attestation section after the provenance section and version 7 no
+        // provenance section after the terms hash section, neither of which changes the transcript
+        let terms_byte = 5 + 3 * 32 + 4 + 1 + 4 + 1;
+        let mut v8 = [&compact[..terms_byte + 5], &compact[terms_byte + 9..]].concat();
+        v8[4] = 8;
+        let reencoded = migrate(&v8, Encoding::Compact).unwrap();
+        assert_eq!((reencoded.from.version, reencoded.bytes), (8, compact.clone()));
+        let mut v7 = [&compact[..terms_byte + 1], &compact[terms_byte + 9..]].concat();
+        v7[4] = 7;
+        let reencoded = migrate(&v7, Encoding::Compact).unwrap();
+        assert_eq!((reencoded.from.version, reencoded.bytes), (7, compact.clone()));
+
+        // version 6 had no terms hash section after the nonce section either
+        let mut v6 = [&compact[..terms_byte], &compact[terms_byte + 9..]].concat();
+        v6[4] = 6;
+        let (version, decoded) = ProofEnvelope::from_versioned_bytes(&v6).unwrap();
+        assert_eq!((version, decoded.public_inputs), (6, envelope.public_inputs.clone()));
+        let err = migrate(&v6, Encoding::Compact).unwrap_err();
+        assert!(matches!(err, MigrationError::Reprove { model, .. } if model == model_id));
+        assert!(err.to_string().contains("no terms hash marker"), "{err}");
+
+        let mut future = compact.clone();
+        future[4] = envelope::VERSION + 1;
+        let unknown = MigrationError::UnknownVersion { encoding: Encoding::Compact, version: envelope::VERSION + 1 };
+        assert_eq!(migrate(&future, Encoding::Compact).unwrap_err(), unknown);
+        let unsupported = EnvelopeError::UnsupportedVersion(envelope::VERSION + 1);
+        assert_eq!(ProofEnvelope::from_versioned_bytes(&future).unwrap_err(), unsupported);
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_migrated_legacy_proofs_verify_without_config_commitment() {
+        use crate::config::{ProverConfig, VerificationPolicy};
+        use crate::session::{verify_linear, LinearClaimRequest, ProvingSession, SessionError};
+
+        // a prover predating the configuration commitment, whose envelopes were version 9
+        let legacy = ProverConfig { commit_config: false, ..ProverConfig::default() };
+        let request = LinearClaimRequest {
+            model: "legacy".into(),
+            salt: to_hex(&[5u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3, 7],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let mut v9 = ProvingSession::new(legacy, "ci").prove_linear(&request).unwrap().to_bytes();
+        v9[4] = 9;
+
+        let migrated = migrate(&v9, Encoding::Compact).unwrap();
+        assert!(!migrated.from.commits_config());
+        let policy = VerificationPolicy { commit_config: false, ..VerificationPolicy::default() };
+        verify_linear(&migrated.envelope, &policy).unwrap();
+        let err = verify_linear(&migrated.envelope, &VerificationPolicy::default()).unwrap_err();
+        assert!(matches!(err, SessionError::Verifier(_)), "{err}");
+    }
+}
@@ -0,0 +1,267 @@
+//! Claim nonces that keep a proof from settling twice.
+//!
+//! A [`ClaimNonce`] is a fresh 32-byte value the prover binds into a claim: it is part of the
+//! claim hash, which the operator signs, and of the public inputs of the proof (see
+//! [`TaggedInputs`](crate::domain::TaggedInputs)), so it cannot be changed without proving
+//! again. Two proofs of the same statement therefore have different claim hashes, and a
+//! settlement that records every hash it accepted rejects the second submission of a proof.
+//!
+//! [`NonceManager`] issues nonces and records which ones were settled, in memory or in an
+//! append-only file of `issued <hex>` and `consumed <hex>` lines. `verifier/ClaimSettlement.sol`
+//! does the same on-chain, keyed by the [`settlement_hash`] of the envelope, a Keccak-256 hash
+//! of its nonce and public input words that a contract can compute from the on-chain encoding.
+
+use core::fmt;
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use winterfell::math::{fields::f128::BaseElement, StarkField, ToElements};
+
+use crate::abi::{from_hex, keccak256, to_hex};
+use crate::envelope::ProofEnvelope;
+
+#[cfg(feature = "prover")]
+const NONCE_DOMAIN: &[u8] = b"stark-framework/claim-nonce/v1";
+
+/// Bytes packed into one field element, as for domain tags
+const BYTES_PER_ELEMENT: usize = 15;
+
+/// Unique value bound into one claim; never all zeros, which encodes "no nonce" on-chain
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClaimNonce(pub [u8; 32]);
+
+impl ClaimNonce {
+    /// Parses the hex encoding produced by `Display`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let bytes: [u8; 32] = from_hex(hex)?.try_into().ok()?;
+        (bytes != [0u8; 32]).then_some(Self(bytes))
+    }
+}
+
+impl fmt::Display for ClaimNonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl ToElements<BaseElement> for ClaimNonce {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        pack_bytes(&self.0)
+    }
+}
+
+/// `bytes` packed little-endian, 15 per element
+pub(crate) fn pack_bytes(bytes: &[u8]) -> Vec<BaseElement> {
+    bytes
+        .chunks(BYTES_PER_ELEMENT)
+        .map(|chunk| {
+            let mut bytes = [0u8; 16];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            BaseElement::new(u128::from_le_bytes(bytes))
+        })
+        .collect()
+}
+
+/// Keccak-256 of the envelope's nonce and public inputs as on-chain words, the key
+/// `ClaimSettlement.sol` marks as consumed; `None` for envelopes without a nonce
+pub fn settlement_hash(envelope: &ProofEnvelope) -> Option<[u8; 32]> {
+    let nonce = envelope.nonce?;
+    let mut words = Vec::with_capacity(32 * (1 + envelope.public_inputs.len()));
+    words.extend_from_slice(&nonce.0);
+    for input in &envelope.public_inputs {
+        words.extend_from_slice(&[0u8; 16]);
+        words.extend_from_slice(&input.as_int().to_be_bytes());
+    }
+    Some(keccak256(&words))
+}
+
+/// Errors returned by [`NonceManager`]
+#[derive(Debug)]
+pub enum NonceError {
+    Io(io::Error),
+    /// Line `line` (1-based) of the nonce file could not be parsed
+    Malformed { line: usize, reason: String },
+    /// The envelope carries no nonce, so it could be settled any number of times
+    Missing,
+    /// The nonce was already settled
+    Replayed(ClaimNonce),
+}
+
+impl fmt::Display for NonceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonceError::Io(err) => write!(f, "nonce file I/O error: {err}"),
+            NonceError::Malformed { line, reason } => write!(f, "malformed nonce record on line {line}: {reason}"),
+            NonceError::Missing => write!(f, "claim has no nonce"),
+            NonceError::Replayed(nonce) => write!(f, "claim with nonce {nonce} was already settled"),
+        }
+    }
+}
+
+impl std::error::Error for NonceError {}
+
+impl From<io::Error> for NonceError {
+    fn from(err: io::Error) -> Self {
+        NonceError::Io(err)
+    }
+}
+
+/// Issues claim nonces, in builds with the `prover` feature, and remembers which ones were settled
+pub struct NonceManager {
+    file: Option<File>,
+    issued: HashSet<ClaimNonce>,
+    consumed: HashSet<ClaimNonce>,
+    #[cfg(feature = "prover")]
+    seed: Option<[u8; 32]>,
+    #[cfg(feature = "prover")]
+    counter: u64,
+}
+
+impl NonceManager {
+    /// Manager whose records are lost when it is dropped
+    pub fn in_memory() -> Self {
+        Self {
+            file: None,
+            issued: HashSet::new(),
+            consumed: HashSet::new(),
+            #[cfg(feature = "prover")]
+            seed: None,
+            #[cfg(feature = "prover")]
+            counter: 0,
+        }
+    }
+
+    /// Manager recording into `path`, continuing the records already in it
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, NonceError> {
+        let path = path.as_ref();
+        let mut manager = Self::in_memory();
+        if path.exists() {
+            for (i, text) in fs::read_to_string(path)?.lines().enumerate() {
+                let malformed = |reason: &str| NonceError::Malformed { line: i + 1, reason: reason.into() };
+                let (kind, hex) = text.split_once(' ').ok_or_else(|| malformed("expected `<kind> <nonce>`"))?;
+                let nonce = ClaimNonce::from_hex(hex).ok_or_else(|| malformed("invalid nonce"))?;
+                match kind {
+                    "issued" => manager.issued.insert(nonce),
+                    "consumed" => manager.consumed.insert(nonce),
+                    _ => return Err(malformed("unknown record kind")),
+                };
+            }
+        }
+        manager.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        Ok(manager)
+    }
+
+    /// A nonce this manager has not issued or seen settled before
+    #[cfg(feature = "prover")]
+    pub fn issue(&mut self) -> Result<ClaimNonce, NonceError> {
+        let seed = *self.seed.get_or_insert_with(fresh_seed);
+        let nonce = loop {
+            self.counter += 1;
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(NONCE_DOMAIN);
+            hasher.update(&seed);
+            hasher.update(&self.counter.to_le_bytes());
+            let nonce = ClaimNonce(*hasher.finalize().as_bytes());
+            if nonce.0 != [0u8; 32] && !self.issued.contains(&nonce) && !self.consumed.contains(&nonce) {
+                break nonce;
+            }
+        };
+        self.record("issued", &nonce)?;
+        self.issued.insert(nonce);
+        Ok(nonce)
+    }
+
+    pub fn is_consumed(&self, nonce: &ClaimNonce) -> bool {
+        self.consumed.contains(nonce)
+    }
+
+    /// Marks `nonce` as settled, failing if it was settled before
+    pub fn consume(&mut self, nonce: &ClaimNonce) -> Result<(), NonceError> {
+        if self.consumed.contains(nonce) {
+            return Err(NonceError::Replayed(*nonce));
+        }
+        self.record("consumed", nonce)?;
+        self.consumed.insert(*nonce);
+        Ok(())
+    }
+
+    /// Settles a verified envelope, failing for envelopes without a nonce or settled before
+    pub fn settle(&mut self, envelope: &ProofEnvelope) -> Result<(), NonceError> {
+        self.consume(envelope.nonce.as_ref().ok_or(NonceError::Missing)?)
+    }
+
+    fn record(&mut self, kind: &str, nonce: &ClaimNonce) -> Result<(), NonceError> {
+        if let Some(file) = self.file.as_mut() {
+            writeln!(file, "{kind} {nonce}")?;
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+/// Seed from the operating system's random source, so that nonces of different managers do
+/// not repeat even across restarts
+#[cfg(feature = "prover")]
+fn fresh_seed() -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).expect("The operating system provides randomness");
+    seed
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::to_hex;
+    use crate::config::{ProverConfig, VerificationPolicy};
+    use crate::serialization::{proof_offset, Encoding, NONCE_OFFSET};
+    use crate::session::{verify_linear, LinearClaimRequest, ProvingSession};
+
+    #[test]
+    fn test_nonce_settles_once() {
+        let path = std::env::temp_dir().join(format!("stark-nonces-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let mut session =
+            ProvingSession::new(ProverConfig::default(), "ci").with_nonce_manager(NonceManager::open(&path).unwrap());
+        let first = session.prove_linear(&request).unwrap();
+        let second = session.prove_linear(&request).unwrap();
+        assert_ne!(first.nonce, second.nonce);
+        assert_ne!(first.claim_hash(&Default::default()), second.claim_hash(&Default::default()));
+
+        let policy = VerificationPolicy::default();
+        verify_linear(&first, &policy).unwrap();
+        let mut swapped = first.clone();
+        swapped.nonce = second.nonce;
+        assert!(verify_linear(&swapped, &policy).is_err());
+
+        let bytes = Encoding::OnChain.encode(&first);
+        assert_eq!(Encoding::OnChain.decode(&bytes).unwrap().nonce, first.nonce);
+        let words = &bytes[NONCE_OFFSET..proof_offset(first.public_inputs.len())];
+        assert_eq!(settlement_hash(&first), Some(keccak256(words)));
+
+        // a second manager on the same file sees the settlement of the first
+        NonceManager::open(&path).unwrap().settle(&first).unwrap();
+        let mut reopened = NonceManager::open(&path).unwrap();
+        assert!(reopened.is_consumed(first.nonce.as_ref().unwrap()));
+        assert!(matches!(reopened.settle(&first), Err(NonceError::Replayed(_))));
+        reopened.settle(&second).unwrap();
+        let mut unbound = first.clone();
+        unbound.nonce = None;
+        assert!(matches!(reopened.settle(&unbound), Err(NonceError::Missing)));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+//! Operator signatures binding a proof envelope to the prover that produced it.
+//!
+//! A STARK proof says nothing about who generated it. Operators that want to vouch for their
+//! proofs sign the envelope's signed hash with an Ed25519 [`OperatorKey`]; the signature and the
+//! operator's public key travel in the envelope, and verifiers that only accept proofs from
+//! known operators list the keys in
+//! [`VerificationPolicy::trusted_operators`](crate::config::VerificationPolicy::trusted_operators).
+//!
+//! The signed message is [`SIGNING_DOMAIN`] followed by
+//! [`ProofEnvelope::signed_hash`], which covers the claim hash, i.e. the deployment domain, the
+//! schema and the public inputs, and the provenance but not the proof bytes: the operator
+//! vouches for the claim and the build that proved it, and the proof is checked on its own.
+
+use core::fmt;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::abi::{from_hex, to_hex};
+use crate::domain::DomainTag;
+use crate::envelope::ProofEnvelope;
+
+/// Prefix of every signed message
+pub const SIGNING_DOMAIN: &[u8] = b"stark-framework/operator-signature/v1";
+
+pub const PUBLIC_KEY_BYTES: usize = 32;
+pub const SIGNATURE_BYTES: usize = 64;
+
+/// Ed25519 public key of an operator, serialized as hex
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct OperatorPublicKey(pub [u8; PUBLIC_KEY_BYTES]);
+
+impl fmt::Display for OperatorPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl TryFrom<String> for OperatorPublicKey {
+    type Error = SignerError;
+
+    fn try_from(hex: String) -> Result<Self, SignerError> {
+        let bytes = from_hex(&hex).and_then(|bytes| bytes.try_into().ok()).ok_or(SignerError::InvalidKey)?;
+        Ok(Self(bytes))
+    }
+}
+
+impl From<OperatorPublicKey> for String {
+    fn from(key: OperatorPublicKey) -> Self {
+        key.to_string()
+    }
+}
+
+/// An operator's signing key
+pub struct OperatorKey(SigningKey);
+
+impl OperatorKey {
+    /// Key derived from a 32-byte secret seed, which the operator keeps like any private key
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self(SigningKey::from_bytes(seed))
+    }
+
+    /// Key from a hex-encoded 32-byte seed
+    pub fn from_hex(hex: &str) -> Result<Self, SignerError> {
+        let seed: Option<[u8; 32]> = from_hex(hex.trim()).and_then(|bytes| bytes.try_into().ok());
+        Ok(Self::from_seed(&seed.ok_or(SignerError::InvalidKey)?))
+    }
+
+    pub fn public_key(&self) -> OperatorPublicKey {
+        OperatorPublicKey(self.0.verifying_key().to_bytes())
+    }
+
+    /// Signature over `claim_hash`
+    pub fn sign(&self, claim_hash: &[u8; 32]) -> OperatorSignature {
+        let signature = self.0.sign(&signed_message(claim_hash));
+        OperatorSignature { public_key: self.public_key(), signature: signature.to_bytes() }
+    }
+
+    /// Signs the [`signed_hash`](ProofEnvelope::signed_hash) of `envelope` under deployment
+    /// `domain` into the envelope
+    pub fn sign_envelope(&self, envelope: &mut ProofEnvelope, domain: &DomainTag) {
+        envelope.signer = Some(self.sign(&envelope.signed_hash(domain)));
+    }
+}
+
+impl fmt::Debug for OperatorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OperatorKey").field(&self.public_key()).finish()
+    }
+}
+
+/// A signature over a claim hash together with the key that made it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OperatorSignature {
+    pub public_key: OperatorPublicKey,
+    pub signature: [u8; SIGNATURE_BYTES],
+}
+
+impl OperatorSignature {
+    /// Checks the signature over `claim_hash`
+    pub fn verify(&self, claim_hash: &[u8; 32]) -> Result<(), SignerError> {
+        let key = VerifyingKey::from_bytes(&self.public_key.0).map_err(|_| SignerError::InvalidKey)?;
+        key.verify_strict(&signed_message(claim_hash), &Signature::from_bytes(&self.signature))
+            .map_err(|_| SignerError::BadSignature)
+    }
+}
+
+fn signed_message(claim_hash: &[u8; 32]) -> Vec<u8> {
+    [SIGNING_DOMAIN, claim_hash].concat()
+}
+
+/// Reason an envelope's operator signature is not accepted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignerError {
+    /// Not a 32-byte key, or not a point on the curve
+    InvalidKey,
+    BadSignature,
+    Unsigned,
+    /// Signed by a key the verifier does not trust
+    UntrustedOperator(OperatorPublicKey),
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::InvalidKey => write!(f, "invalid operator key"),
+            SignerError::BadSignature => write!(f, "operator signature does not match the claim"),
+            SignerError::Unsigned => write!(f, "envelope carries no operator signature"),
+            SignerError::UntrustedOperator(key) => write!(f, "envelope was signed by untrusted operator {key}"),
+        }
+    }
+}
+
+impl std::error::Error for SignerError {}
+
+/// The operator that signed `envelope` for deployment `domain`, or `None` if it is unsigned
+pub fn verify_signer(envelope: &ProofEnvelope, domain: &DomainTag) -> Result<Option<OperatorPublicKey>, SignerError> {
+    match &envelope.signer {
+        Some(signer) => {
+            signer.verify(&envelope.signed_hash(domain))?;
+            Ok(Some(signer.public_key))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Checks that `envelope` is validly signed by one of `trusted`
+pub fn require_signer(
+    envelope: &ProofEnvelope,
+    domain: &DomainTag,
+    trusted: &[OperatorPublicKey],
+) -> Result<OperatorPublicKey, SignerError> {
+    let signer = verify_signer(envelope, domain)?.ok_or(SignerError::Unsigned)?;
+    if trusted.contains(&signer) { Ok(signer) } else { Err(SignerError::UntrustedOperator(signer)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::ConstraintFingerprint;
+    use crate::registry::{ModelCommitment, ModelId};
+    use winterfell::{math::fields::f128::BaseElement, Proof};
+
+    #[test]
+    fn test_signed_envelope() {
+        let mut envelope = ProofEnvelope::new(
+            ModelId::from_name("house-prices"),
+            ModelCommitment([9u8; 32]),
+            ConstraintFingerprint([4u8; 32]),
+            vec![BaseElement::new(8), BaseElement::new(21)],
+            Proof::new_dummy(),
+        );
+        let domain = DomainTag::default();
+        assert_eq!(verify_signer(&envelope, &domain), Ok(None));
+
+        let key = OperatorKey::from_seed(&[7u8; 32]);
+        let other = OperatorKey::from_seed(&[8u8; 32]).public_key();
+        key.sign_envelope(&mut envelope, &domain);
+        assert_eq!(verify_signer(&envelope, &domain), Ok(Some(key.public_key())));
+        assert_eq!(require_signer(&envelope, &domain, &[other, key.public_key()]), Ok(key.public_key()));
+        assert_eq!(require_signer(&envelope, &domain, &[other]), Err(SignerError::UntrustedOperator(key.public_key())));
+
+        // the signature is bound to the claim and its deployment
+        let other_app = DomainTag::new("other-app").unwrap();
+        assert_eq!(verify_signer(&envelope, &other_app), Err(SignerError::BadSignature));
+        let mut altered = envelope.clone();
+        altered.public_inputs[1] = BaseElement::new(22);
+        assert_eq!(verify_signer(&altered, &domain), Err(SignerError::BadSignature));
+
+        let decoded = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(decoded.signer, envelope.signer);
+    }
+
+    #[test]
+    fn test_public_key_hex() {
+        let key = OperatorKey::from_hex(&to_hex(&[7u8; 32])).unwrap();
+        let json = serde_json::to_string(&key.public_key()).unwrap();
+        assert_eq!(serde_json::from_str::<OperatorPublicKey>(&json).unwrap(), key.public_key());
+        assert!(serde_json::from_str::<OperatorPublicKey>("\"0x1234\"").is_err());
+        assert_eq!(OperatorKey::from_hex("0x12").unwrap_err(), SignerError::InvalidKey);
+    }
+}
@@ -0,0 +1,340 @@
+//! Optimistic settlement of claims too large to verify in one transaction.
+//!
+//! Verifying a whole proof on-chain may exceed the block gas limit. In optimistic mode the
+//! poster posts the envelope and the root over the proof's queries with
+//! `OptimisticVerifier.postClaim` (see `verifier/OptimisticVerifier.sol`), which hashes the
+//! envelope itself and keys the claim by poster and envelope hash; the claim is final once its
+//! challenge period passes undisputed. A challenger disputes a single query with
+//! `OptimisticVerifier.challenge`, and the dispute is settled by `OptimisticVerifier.respond`,
+//! which checks just that query's [`QueryChunk`] on-chain.
+//!
+//! [`ChallengeArtifacts`] replays the proof's transcript to find its query positions and splits
+//! the proof into one chunk per query with [`queries`](crate::queries): the trace row, the
+//! constraint composition row and the FRI layer rows at that position, each with its Merkle
+//! path. The Keccak-256 hashes of the encoded chunks are Merkleized like envelopes in
+//! [`anchor`](crate::anchor). A chunk holds if its openings lead to the proof's roots, its
+//! position is the query's, and its rows are consistent with the transcript: the out-of-domain
+//! rows at `z` must satisfy the constraints, and the DEEP composition of the trace and
+//! constraint rows must be the value FRI folds, with the transcript's folding challenges, down
+//! to the proof's remainder. The artifacts depend on the envelope alone, so a challenger
+//! rebuilds them without the poster's help and [`ChallengeArtifacts::disputed_queries`] tells
+//! which queries are worth challenging.
+
+use core::fmt;
+
+use winterfell::{
+    crypto::RandomCoin,
+    math::fields::f128::BaseElement,
+    Air, FieldExtension, Proof,
+};
+
+use crate::abi::{encode_call, encode_dynamic_call, keccak256, uint_word, AbiArg};
+use crate::anchor::{envelope_hash, AnchorBatch, InclusionProof};
+use crate::coin::{CoinKind, KeccakCoin};
+use crate::config::VerificationPolicy;
+use crate::diagnostics::ood_check;
+use crate::domain::{DomainSeparatedAir, TaggedInputs};
+use crate::envelope::ProofEnvelope;
+use crate::prover::HashFn;
+use crate::queries::SplitError;
+use crate::regression::{LinearRegressionAir, LinearRegressionInputs};
+use crate::schema::DataSchema;
+use crate::session::linear_config_commitment;
+use crate::transcript::{Transcript, TranscriptError};
+
+pub use crate::queries::{MerkleOpening, ProofRoots, QueryChunk, QueryContext, QueryFailure};
+
+/// Reason the challenge artifacts of a proof cannot be built
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChallengeError {
+    /// Only proofs over the base field are split into chunks
+    UnsupportedExtension(FieldExtension),
+    /// Rows hashed in several partitions have no single Merkle leaf per query
+    Partitioned,
+    /// The envelope's public inputs do not describe a linear claim
+    InvalidInputs,
+    /// The proof was made with other options than the policy expects
+    UnexpectedOptions,
+    /// The proof does not parse, or its openings do not match its query positions
+    Malformed(String),
+}
+
+impl fmt::Display for ChallengeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChallengeError::UnsupportedExtension(extension) => {
+                write!(f, "proofs over the {extension:?} field extension are not split into chunks")
+            }
+            ChallengeError::Partitioned => write!(f, "proofs hashing rows in partitions are not split into chunks"),
+            ChallengeError::InvalidInputs => write!(f, "public inputs do not describe a linear claim"),
+            ChallengeError::UnexpectedOptions => write!(f, "proof was made with other options than the policy expects"),
+            ChallengeError::Malformed(reason) => write!(f, "malformed proof: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ChallengeError {}
+
+impl From<TranscriptError> for ChallengeError {
+    fn from(err: TranscriptError) -> Self {
+        match err {
+            TranscriptError::UnsupportedExtension(extension) => ChallengeError::UnsupportedExtension(extension),
+            TranscriptError::Malformed(reason) => ChallengeError::Malformed(reason),
+        }
+    }
+}
+
+impl From<SplitError> for ChallengeError {
+    fn from(err: SplitError) -> Self {
+        match err {
+            SplitError::Partitioned => ChallengeError::Partitioned,
+            SplitError::Malformed(reason) => ChallengeError::Malformed(reason),
+        }
+    }
+}
+
+/// A proof split into one [`QueryChunk`] per query, with the tree over the chunk hashes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChallengeArtifacts {
+    pub context: QueryContext,
+    pub chunks: Vec<QueryChunk>,
+    tree: AnchorBatch,
+}
+
+impl ChallengeArtifacts {
+    /// Artifacts of a proof for AIR `A` whose challenges were drawn from the random coin `C`
+    pub fn new<A, C>(proof: &Proof, pub_inputs: A::PublicInputs) -> Result<Self, ChallengeError>
+    where
+        A: Air<BaseField = BaseElement>,
+        C: RandomCoin<BaseField = BaseElement, Hasher = HashFn>,
+    {
+        let transcript = Transcript::<A>::replay::<C>(proof, pub_inputs)?;
+        let (context, chunks) = QueryContext::split(proof, &transcript, ood_check(&transcript).1)?;
+        let tree = AnchorBatch::from_hashes(
+            chunks.iter().map(|chunk| keccak256(&chunk.to_bytes(&context.roots))).collect(),
+        );
+        Ok(Self { context, chunks, tree })
+    }
+
+    /// Artifacts of an envelope produced by
+    /// [`ProvingSession::prove_linear`](crate::session::ProvingSession::prove_linear) for the
+    /// deployment and random coin of `policy`
+    pub fn for_linear(envelope: &ProofEnvelope, policy: &VerificationPolicy) -> Result<Self, ChallengeError> {
+        let inner =
+            LinearRegressionInputs::from_elements(&envelope.public_inputs).ok_or(ChallengeError::InvalidInputs)?;
+        let tagged = TaggedInputs {
+            config: linear_config_commitment(&envelope.proof, policy).map_err(|_| ChallengeError::UnexpectedOptions)?,
+            tag: policy.domain.clone(),
+            nonce: envelope.nonce,
+            terms_hash: envelope.terms_hash,
+            schema: envelope.schema.as_ref().map(DataSchema::commitment),
+            inner,
+        };
+        type LinearAir = DomainSeparatedAir<LinearRegressionAir>;
+        match policy.random_coin {
+            CoinKind::Default => {
+                Self::new::<LinearAir, winterfell::crypto::DefaultRandomCoin<HashFn>>(&envelope.proof, tagged)
+            }
+            CoinKind::Keccak => Self::new::<LinearAir, KeccakCoin<HashFn>>(&envelope.proof, tagged),
+        }
+    }
+
+    /// Root over the Keccak-256 hashes of the encoded chunks, in query order
+    pub fn query_root(&self) -> [u8; 32] {
+        self.tree.root()
+    }
+
+    /// Queries whose chunks do not hold
+    pub fn disputed_queries(&self) -> Vec<usize> {
+        (0..self.chunks.len()).filter(|&query| !self.chunks[query].verify(query, &self.context)).collect()
+    }
+
+    /// Data settling a dispute over the `query`-th query
+    pub fn challenge(&self, query: usize) -> Option<Challenge> {
+        let chunk = self.chunks.get(query)?.to_bytes(&self.context.roots);
+        let inclusion = self.tree.inclusion_proof(query)?;
+        Some(Challenge { query, chunk, inclusion })
+    }
+}
+
+/// A claim posted optimistically, final unless a challenge of one of its queries succeeds
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptimisticClaim {
+    /// Address the claim is posted from; claims are keyed by poster and envelope hash
+    pub poster: [u8; 20],
+    /// Keccak-256 of the on-chain encoding, which the contract computes from the posted envelope
+    pub envelope_hash: [u8; 32],
+    pub query_root: [u8; 32],
+    pub num_queries: usize,
+}
+
+impl OptimisticClaim {
+    pub fn new(envelope: &ProofEnvelope, artifacts: &ChallengeArtifacts, poster: [u8; 20]) -> Self {
+        Self {
+            poster,
+            envelope_hash: envelope_hash(envelope),
+            query_root: artifacts.query_root(),
+            num_queries: artifacts.chunks.len(),
+        }
+    }
+
+    /// Calldata for `OptimisticVerifier.postClaim(bytes envelope, bytes32 queryRoot,
+    /// uint256 numQueries)`, where `envelope` is the on-chain encoding of the claim's envelope
+    pub fn post_calldata(&self, envelope: &[u8]) -> Vec<u8> {
+        let args =
+            [AbiArg::Bytes(envelope), AbiArg::Word(self.query_root), AbiArg::Word(uint_word(self.num_queries as u128))];
+        encode_dynamic_call("postClaim(bytes,bytes32,uint256)", &args)
+    }
+
+    /// Calldata for `OptimisticVerifier.challenge(address poster, bytes32 envelopeHash,
+    /// uint256 query)`
+    pub fn challenge_calldata(&self, query: usize) -> Vec<u8> {
+        let words = [address_word(&self.poster), self.envelope_hash, uint_word(query as u128)];
+        encode_call("challenge(address,bytes32,uint256)", &words)
+    }
+}
+
+fn address_word(address: &[u8; 20]) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address);
+    word
+}
+
+/// A query's encoded chunk and its path to the posted query root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    pub query: usize,
+    pub chunk: Vec<u8>,
+    pub inclusion: InclusionProof,
+}
+
+impl Challenge {
+    /// Whether the chunk is the `query`-th leaf under `query_root`
+    pub fn verify(&self, query_root: &[u8; 32]) -> bool {
+        self.inclusion.index == self.query
+            && self.query >> self.inclusion.siblings.len() == 0
+            && self.inclusion.envelope_hash == keccak256(&self.chunk)
+            && self.inclusion.root() == *query_root
+    }
+
+    /// Calldata for `OptimisticVerifier.respond(address poster, bytes envelope, uint256 query,
+    /// bytes chunk, bytes32[] siblings)`, where `envelope` is the on-chain encoding of the
+    /// envelope `poster` posted
+    pub fn respond_calldata(&self, poster: &[u8; 20], envelope: &[u8]) -> Vec<u8> {
+        let args = [
+            AbiArg::Word(address_word(poster)),
+            AbiArg::Bytes(envelope),
+            AbiArg::Word(uint_word(self.query as u128)),
+            AbiArg::Bytes(&self.chunk),
+            AbiArg::Words(&self.inclusion.siblings),
+        ];
+        encode_dynamic_call("respond(address,bytes,uint256,bytes,bytes32[])", &args)
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::abi::{selector, to_hex};
+    use crate::config::ProverConfig;
+    use crate::serialization::Encoding;
+    use crate::session::{LinearClaimRequest, ProvingSession};
+    use winterfell::math::FieldElement;
+
+    /// Claim over enough samples for the proof to have a FRI layer besides the remainder
+    fn linear_envelope() -> ProofEnvelope {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: (1..=64).collect(),
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request).unwrap()
+    }
+
+    #[test]
+    fn test_artifacts_open_every_query() {
+        let envelope = linear_envelope();
+        let policy = VerificationPolicy::default();
+        let artifacts = ChallengeArtifacts::for_linear(&envelope, &policy).unwrap();
+        assert_eq!(artifacts.chunks.len(), envelope.proof.num_unique_queries as usize);
+        assert!(!artifacts.context.roots.fri_layers.is_empty());
+        assert!(artifacts.disputed_queries().is_empty());
+
+        let claim = OptimisticClaim::new(&envelope, &artifacts, [7u8; 20]);
+        for query in 0..artifacts.chunks.len() {
+            assert!(artifacts.challenge(query).unwrap().verify(&claim.query_root));
+        }
+        assert_eq!(artifacts.challenge(artifacts.chunks.len()), None);
+
+        let mut tampered = artifacts.clone();
+        tampered.chunks[1].trace.values[0] += BaseElement::ONE;
+        assert_eq!(tampered.disputed_queries(), vec![1]);
+        let mut moved = artifacts.challenge(0).unwrap();
+        moved.chunk = artifacts.chunks[1].to_bytes(&artifacts.context.roots);
+        assert!(!moved.verify(&claim.query_root));
+        // a chunk whose openings hold is still not another query's
+        assert!(!artifacts.chunks[1].verify(0, &artifacts.context));
+
+        // openings that hold against a transcript their rows do not fit
+        let all: Vec<usize> = (0..artifacts.chunks.len()).collect();
+        let mut ood = artifacts.clone();
+        ood.context.ood_trace[1][3] += BaseElement::ONE;
+        assert_eq!(ood.disputed_queries(), all);
+        let mut folded = artifacts.clone();
+        folded.context.fri_alphas[0] += BaseElement::ONE;
+        assert_eq!(folded.disputed_queries(), all);
+        let mut remainder = artifacts.clone();
+        remainder.context.remainder[0] += BaseElement::ONE;
+        assert_eq!(remainder.disputed_queries(), all);
+        let mut constraints = artifacts.clone();
+        constraints.context.ood_check.from_columns += BaseElement::ONE;
+        assert_eq!(constraints.disputed_queries(), all);
+
+        let other = ProofEnvelope { public_inputs: vec![BaseElement::from(1u8)], ..envelope };
+        assert_eq!(ChallengeArtifacts::for_linear(&other, &policy), Err(ChallengeError::InvalidInputs));
+    }
+
+    #[test]
+    fn test_calldata_matches_contract() {
+        let envelope = linear_envelope();
+        let policy = VerificationPolicy::default();
+        let artifacts = ChallengeArtifacts::for_linear(&envelope, &policy).unwrap();
+        let claim = OptimisticClaim::new(&envelope, &artifacts, [7u8; 20]);
+        let encoded = Encoding::OnChain.encode(&envelope);
+        assert_eq!(keccak256(&encoded), claim.envelope_hash);
+
+        let post = claim.post_calldata(&encoded);
+        assert_eq!(post[..4], selector("postClaim(bytes,bytes32,uint256)"));
+        assert_eq!(post[36..68], claim.query_root);
+        assert_eq!(post[132..132 + encoded.len()], encoded);
+        let challenge = claim.challenge_calldata(2);
+        assert_eq!(challenge[..4], selector("challenge(address,bytes32,uint256)"));
+        assert_eq!(challenge[16..36], claim.poster);
+        assert_eq!(challenge[36..68], claim.envelope_hash);
+        assert_eq!(challenge[68..], uint_word(2));
+
+        let respond = artifacts.challenge(2).unwrap().respond_calldata(&claim.poster, &encoded);
+        assert_eq!(respond[..4], selector("respond(address,bytes,uint256,bytes,bytes32[])"));
+        assert_eq!(respond[16..36], claim.poster);
+        assert_eq!(respond[36..68], uint_word(160));
+        assert_eq!(respond[196..196 + encoded.len()], encoded);
+
+        let source = include_str!("../../verifier/OptimisticVerifier.sol");
+        for signature in [
+            "function postClaim(bytes calldata envelope, bytes32 queryRoot, uint256 numQueries)",
+            "function challenge(address poster, bytes32 envelopeHash, uint256 query)",
+            "address poster,\n        bytes calldata envelope,\n        uint256 query,",
+            "function verifyQuery(bytes calldata envelope, uint256 numQueries, uint256 query, bytes calldata chunk)",
+        ] {
+            assert!(source.contains(signature), "{signature}");
+        }
+        assert!(source.contains("bytes1 internal constant LEAF_PREFIX = 0x00;"));
+        assert!(source.contains("bytes1 internal constant NODE_PREFIX = 0x01;"));
+    }
+}
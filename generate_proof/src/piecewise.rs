@@ -0,0 +1,604 @@
+//! Piecewise-linear (segmented regression) models.
+//!
+//! A model with `k` breakpoints `b_1 < .. < b_k` has `k + 1` segments, each with its own slope
+//! and intercept: segment `s` covers `b_s <= x < b_{s + 1}`, with the first segment open to the
+//! left and the last one open to the right. [`PiecewiseLinearAir`] proves that every sample and
+//! the prediction were evaluated with the segment their `x` falls into.
+//!
+//! Every row holds one evaluation point, one-hot segment flags and the model as constant
+//! columns. With `ge_j` the sum of the flags of segments `j..=k`, a row must satisfy
+//! `x >= b_j` where `ge_j = 1` and `x < b_j` where `ge_j = 0`; the margin `x - b_j` or
+//! `b_j - 1 - x` is range checked to [`VALUE_BITS`] bits, as is every breakpoint, so the
+//! comparisons cannot wrap around the field. `y` is then the flagged segment's `m * x + c`.
+//!
+//! Breakpoints are either public, asserted on the first row, or committed: the public inputs
+//! carry their Rescue digest (see [`breakpoints_digest`]), which a sponge running alongside the
+//! evaluation rows recomputes from the breakpoint columns. Slopes and intercepts stay private
+//! in both cases, as in [`LinearRegressionAir`](crate::regression::LinearRegressionAir).
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::config::padded_trace_length;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{IntegerColumn, RangeCheck};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`PiecewiseLinearAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "piecewise-linear/v1";
+
+/// Inputs and breakpoints are integers below `2^VALUE_BITS`
+pub const VALUE_BITS: usize = 32;
+
+/// Keeps the trace within Winterfell's 255 columns
+pub const MAX_BREAKPOINTS: usize = 6;
+
+/// Sponge tag of [`breakpoints_digest`]; tags 1 to 8 are used by other AIRs
+const BREAKPOINTS_TAG: u64 = 9;
+const DIGIT_BITS: usize = 2;
+
+// Trace layout: x and y, then the segment flags, slopes, intercepts and breakpoints, the
+// sponge for committed breakpoints, and the digits of the breakpoints and the margins
+const X: usize = 0;
+const Y: usize = 1;
+const SEGMENTS: usize = 2;
+
+/// Columns of a model with `num_breakpoints` breakpoints
+struct Layout {
+    num_segments: usize,
+    slopes: usize,
+    intercepts: usize,
+    breakpoints: Vec<IntegerColumn>,
+    sponge: Option<RescueGadget>,
+    margins: Vec<RangeCheck>,
+}
+
+impl Layout {
+    fn new(num_breakpoints: usize, committed: bool) -> Self {
+        let num_segments = num_breakpoints + 1;
+        let slopes = SEGMENTS + num_segments;
+        let intercepts = slopes + num_segments;
+        let first_breakpoint = intercepts + num_segments;
+        let sponge = committed.then(|| RescueGadget::new(first_breakpoint + num_breakpoints));
+        let mut next = sponge.map_or(first_breakpoint + num_breakpoints, |sponge| sponge.next_column());
+
+        let breakpoints = (0..num_breakpoints)
+            .map(|j| {
+                let column = IntegerColumn::new(first_breakpoint + j, next, VALUE_BITS, DIGIT_BITS);
+                next = column.next_column();
+                column
+            })
+            .collect();
+        let margins = (0..num_breakpoints)
+            .map(|_| {
+                let margin = RangeCheck::with_digit_bits(next, VALUE_BITS, DIGIT_BITS);
+                next = margin.next_column();
+                margin
+            })
+            .collect();
+        Self { num_segments, slopes, intercepts, breakpoints, sponge, margins }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.margins.last().map_or(self.intercepts + self.num_segments, |margin| margin.next_column())
+    }
+
+    /// Flag sum of the segments from `breakpoint + 1` on, one exactly when `x >= b_{breakpoint + 1}`
+    fn at_or_above<E: FieldElement>(&self, row: &[E], breakpoint: usize) -> E {
+        row[SEGMENTS + breakpoint + 1..SEGMENTS + self.num_segments].iter().fold(E::ZERO, |sum, &flag| sum + flag)
+    }
+}
+
+/// Rows of the sponge period: the initial permutation and one absorbing cycle per breakpoint
+fn absorb_period(num_breakpoints: usize) -> usize {
+    ((num_breakpoints + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Named columns of the trace for a model of `num_breakpoints` breakpoints, see [`crate::layout`]
+pub fn layout(num_breakpoints: usize, committed: bool) -> LayoutDescriptor {
+    let layout = Layout::new(num_breakpoints, committed);
+    let first_breakpoint = layout.intercepts + layout.num_segments;
+    let mut descriptor = LayoutDescriptor::new(AIR_ID)
+        .with_column("x", X)
+        .with_column("y", Y)
+        .with_columns("segment_flags", SEGMENTS..layout.slopes)
+        .with_columns("slopes", layout.slopes..layout.intercepts)
+        .with_columns("intercepts", layout.intercepts..first_breakpoint);
+    let mut digits = first_breakpoint + num_breakpoints;
+    if let Some(sponge) = layout.sponge {
+        descriptor = descriptor.with_columns("sponge", digits..sponge.next_column());
+        digits = sponge.next_column();
+    }
+    if let Some(last) = layout.breakpoints.last() {
+        descriptor = descriptor
+            .with_columns("breakpoints", first_breakpoint..first_breakpoint + num_breakpoints)
+            .with_columns("breakpoint_digits", digits..last.next_column())
+            .with_columns("margin_digits", last.next_column()..layout.trace_width());
+    }
+    descriptor
+}
+
+/// Breakpoint absorbed at the end of the cycle containing `row`, if any
+fn absorbed_at(row: usize, num_breakpoints: usize) -> Option<usize> {
+    let offset = row % absorb_period(num_breakpoints);
+    (offset % CYCLE_LENGTH == CYCLE_LENGTH - 1)
+        .then_some(offset / CYCLE_LENGTH)
+        .filter(|&breakpoint| breakpoint < num_breakpoints)
+}
+
+/// Trace length for `num_samples` samples and the prediction, padded to at least
+/// `min_trace_length` rows
+pub fn trace_length(num_samples: usize, num_breakpoints: usize, committed: bool, min_trace_length: usize) -> usize {
+    // Reserve at least one padding row: the last row is exempt from transition constraints
+    let rows = padded_trace_length(num_samples + 2, min_trace_length);
+    if committed {
+        rows.max(absorb_period(num_breakpoints))
+    } else {
+        rows
+    }
+}
+
+/// Digest of the breakpoints committed to by [`Breakpoints::Committed`]
+pub fn breakpoints_digest(breakpoints: &[u64]) -> BaseElement {
+    let elements: Vec<BaseElement> = breakpoints.iter().map(|&b| BaseElement::from(b)).collect();
+    rescue::hash_elements(&elements, BREAKPOINTS_TAG)
+}
+
+/// Breakpoints of a model, private except for their number and digest when committed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Breakpoints {
+    Public(Vec<u64>),
+    Committed { count: usize, digest: BaseElement },
+}
+
+impl Breakpoints {
+    /// Commits to `breakpoints` instead of revealing them
+    pub fn commit(breakpoints: &[u64]) -> Self {
+        Breakpoints::Committed { count: breakpoints.len(), digest: breakpoints_digest(breakpoints) }
+    }
+
+    pub fn count(&self) -> usize {
+        match self {
+            Breakpoints::Public(breakpoints) => breakpoints.len(),
+            Breakpoints::Committed { count, .. } => *count,
+        }
+    }
+
+    fn is_committed(&self) -> bool {
+        matches!(self, Breakpoints::Committed { .. })
+    }
+}
+
+/// A piecewise-linear model: `k` increasing breakpoints and `k + 1` segments
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PiecewiseModel {
+    pub breakpoints: Vec<u64>,
+    pub slopes: Vec<BaseElement>,
+    pub intercepts: Vec<BaseElement>,
+}
+
+impl PiecewiseModel {
+    pub fn validate(&self) -> Result<(), PiecewiseError> {
+        let count = self.breakpoints.len();
+        if !(1..=MAX_BREAKPOINTS).contains(&count) {
+            return Err(PiecewiseError::BreakpointCount(count));
+        }
+        if self.slopes.len() != count + 1 || self.intercepts.len() != count + 1 {
+            return Err(PiecewiseError::SegmentCount {
+                breakpoints: count,
+                slopes: self.slopes.len(),
+                intercepts: self.intercepts.len(),
+            });
+        }
+        if let Some(&value) = self.breakpoints.iter().find(|&&b| b >> VALUE_BITS != 0) {
+            return Err(PiecewiseError::OutOfRange(value));
+        }
+        if let Some(index) = self.breakpoints.windows(2).position(|pair| pair[0] >= pair[1]) {
+            return Err(PiecewiseError::Unordered { index: index + 1 });
+        }
+        Ok(())
+    }
+
+    /// Segment covering `x`: the number of breakpoints at or below it
+    pub fn segment(&self, x: u64) -> usize {
+        self.breakpoints.partition_point(|&b| b <= x)
+    }
+
+    pub fn predict(&self, x: u64) -> BaseElement {
+        let segment = self.segment(x);
+        self.slopes[segment] * BaseElement::from(x) + self.intercepts[segment]
+    }
+}
+
+/// Reason a piecewise-linear claim cannot be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PiecewiseError {
+    /// Models need 1 to [`MAX_BREAKPOINTS`] breakpoints
+    BreakpointCount(usize),
+    SegmentCount { breakpoints: usize, slopes: usize, intercepts: usize },
+    /// Breakpoint `index` is not above the previous one
+    Unordered { index: usize },
+    /// A breakpoint or input does not fit into [`VALUE_BITS`] bits
+    OutOfRange(u64),
+}
+
+impl fmt::Display for PiecewiseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PiecewiseError::BreakpointCount(count) => {
+                write!(f, "{count} breakpoints, models need 1 to {MAX_BREAKPOINTS}")
+            }
+            PiecewiseError::SegmentCount { breakpoints, slopes, intercepts } => write!(
+                f,
+                "{breakpoints} breakpoints need {} segments, got {slopes} slopes and {intercepts} intercepts",
+                breakpoints + 1
+            ),
+            PiecewiseError::Unordered { index } => write!(f, "breakpoint {index} is not above the previous one"),
+            PiecewiseError::OutOfRange(value) => write!(f, "value {value} does not fit into {VALUE_BITS} bits"),
+        }
+    }
+}
+
+impl std::error::Error for PiecewiseError {}
+
+/// Public inputs: the breakpoints or their digest, the prediction and the samples
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PiecewiseLinearInputs {
+    pub breakpoints: Breakpoints,
+    pub x_value: u64,
+    pub predicted_y: BaseElement,
+    pub sample_x_values: Vec<u64>,
+    pub sample_y_values: Vec<BaseElement>,
+}
+
+impl PiecewiseLinearInputs {
+    /// Inputs of `model` evaluated at the samples and `x`, revealing the breakpoints unless
+    /// `commit` is set
+    pub fn new(model: &PiecewiseModel, sample_x_values: &[u64], x: u64, commit: bool) -> Self {
+        Self {
+            breakpoints: if commit {
+                Breakpoints::commit(&model.breakpoints)
+            } else {
+                Breakpoints::Public(model.breakpoints.clone())
+            },
+            x_value: x,
+            predicted_y: model.predict(x),
+            sample_x_values: sample_x_values.to_vec(),
+            sample_y_values: sample_x_values.iter().map(|&x| model.predict(x)).collect(),
+        }
+    }
+}
+
+impl ToElements<BaseElement> for PiecewiseLinearInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![BaseElement::from(self.breakpoints.count() as u64)];
+        match &self.breakpoints {
+            Breakpoints::Public(breakpoints) => {
+                elements.push(BaseElement::ZERO);
+                elements.extend(breakpoints.iter().map(|&b| BaseElement::from(b)));
+            }
+            Breakpoints::Committed { digest, .. } => elements.extend([BaseElement::ONE, *digest]),
+        }
+        elements.extend([BaseElement::from(self.x_value), self.predicted_y]);
+        elements.extend(self.sample_x_values.iter().map(|&x| BaseElement::from(x)));
+        elements.extend(&self.sample_y_values);
+        elements
+    }
+}
+
+/// AIR for predictions of a piecewise-linear model, see the module documentation
+pub struct PiecewiseLinearAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: PiecewiseLinearInputs,
+}
+
+impl Air for PiecewiseLinearAir {
+    type BaseField = BaseElement;
+    type PublicInputs = PiecewiseLinearInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: PiecewiseLinearInputs, options: ProofOptions) -> Self {
+        let num_breakpoints = pub_inputs.breakpoints.count();
+        assert!((1..=MAX_BREAKPOINTS).contains(&num_breakpoints), "Unsupported number of breakpoints");
+        let num_samples = pub_inputs.sample_x_values.len();
+        assert_eq!(num_samples, pub_inputs.sample_y_values.len(), "Sample arrays must have equal length");
+        let xs = pub_inputs.sample_x_values.iter().chain([&pub_inputs.x_value]);
+        assert!(xs.into_iter().all(|x| x >> VALUE_BITS == 0), "Inputs must fit into VALUE_BITS bits");
+        let layout = Layout::new(num_breakpoints, pub_inputs.breakpoints.is_committed());
+        assert_eq!(layout.trace_width(), trace_info.width());
+
+        // Constraints:
+        // 1. Slopes, intercepts and breakpoints stay constant (degree 1)
+        // 2. Segment flags are binary (degree 2) and exactly one is set (degree 1)
+        // 3. y = Σ flag_s * (m_s * x + c_s) (degree 2, the model columns are constant)
+        // 4. Per breakpoint: the comparison margin matches its digits (degree 2)
+        // 5. Margin and breakpoint digits (degree 4), breakpoints match their digits (degree 1)
+        // 6. Committed breakpoints: Rescue rounds (degree 3) and absorbing a breakpoint at the
+        //    end of its cycle (degree 1 with a periodic mask)
+        let num_segments = layout.num_segments;
+        let mut degrees = vec![TransitionConstraintDegree::new(1); 2 * num_segments + num_breakpoints];
+        degrees.extend(vec![TransitionConstraintDegree::new(2); num_segments]);
+        degrees.push(TransitionConstraintDegree::new(1));
+        degrees.push(TransitionConstraintDegree::new(2));
+        for (breakpoint, margin) in layout.breakpoints.iter().zip(&layout.margins) {
+            degrees.push(TransitionConstraintDegree::new(2));
+            degrees.extend(margin.constraint_degrees());
+            degrees.extend(breakpoint.constraint_degrees());
+        }
+        if let Some(sponge) = layout.sponge {
+            degrees.extend(sponge.constraint_degrees());
+            // the absorb selectors only multiply constant breakpoint columns, which keeps them
+            // below the degree of the sponge columns
+            degrees.extend(vec![TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]); STATE_WIDTH]);
+        }
+
+        let num_assertions = 2 * (num_samples + 1)
+            + match &pub_inputs.breakpoints {
+                Breakpoints::Public(breakpoints) => breakpoints.len(),
+                Breakpoints::Committed { .. } => STATE_WIDTH + 1,
+            };
+
+        PiecewiseLinearAir {
+            context: AirContext::new(trace_info, degrees, num_assertions, options),
+            layout,
+            inputs: pub_inputs,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let num_segments = layout.num_segments;
+        let num_breakpoints = layout.breakpoints.len();
+
+        let model = layout.slopes..layout.intercepts + num_segments + num_breakpoints;
+        for (r, column) in result.iter_mut().zip(model.clone()) {
+            *r = next[column] - current[column];
+        }
+        let mut offset = model.len();
+
+        let flags = &current[SEGMENTS..SEGMENTS + num_segments];
+        for (r, &flag) in result[offset..].iter_mut().zip(flags) {
+            *r = flag * (flag - E::ONE);
+        }
+        offset += num_segments;
+        result[offset] = flags.iter().fold(E::ZERO, |sum, &flag| sum + flag) - E::ONE;
+
+        let x = current[X];
+        let evaluated = flags.iter().enumerate().fold(E::ZERO, |sum, (s, &flag)| {
+            sum + flag * (current[layout.slopes + s] * x + current[layout.intercepts + s])
+        });
+        result[offset + 1] = current[Y] - evaluated;
+        offset += 2;
+
+        for (j, (breakpoint, margin)) in layout.breakpoints.iter().zip(&layout.margins).enumerate() {
+            let ge = layout.at_or_above(current, j);
+            let difference = x - current[breakpoint.value_column()];
+            // x - b where x >= b, b - 1 - x where x < b
+            let expected = (ge + ge - E::ONE) * difference + ge - E::ONE;
+            result[offset] = expected - margin.recompose(current);
+            margin.evaluate(current, &mut result[offset + 1..]);
+            offset += 1 + margin.num_constraints();
+            breakpoint.evaluate(current, &mut result[offset..]);
+            offset += breakpoint.num_constraints();
+        }
+
+        if let Some(sponge) = layout.sponge {
+            sponge.evaluate(current, next, periodic_values, &mut result[offset..]);
+            offset += sponge.num_constraints();
+            // the first periodic column masks the last row of every cycle, the ones after the
+            // round constants select the breakpoint absorbed there
+            let last = E::ONE - periodic_values[0];
+            let absorbed = periodic_values[rescue::NUM_PERIODIC_COLUMNS..]
+                .iter()
+                .zip(&layout.breakpoints)
+                .fold(E::ZERO, |sum, (&selected, breakpoint)| sum + selected * current[breakpoint.value_column()]);
+            let state = sponge.state(current);
+            let next_state = sponge.state(next);
+            result[offset] = last * (next_state[0] - state[0] - absorbed);
+            for i in 1..STATE_WIDTH {
+                result[offset + i] = last * (next_state[i] - state[i]);
+            }
+        }
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        if self.layout.sponge.is_none() {
+            return Vec::new();
+        }
+        let num_breakpoints = self.layout.breakpoints.len();
+        let period = absorb_period(num_breakpoints);
+        let mut columns = rescue::periodic_columns();
+        for breakpoint in 0..num_breakpoints {
+            columns.push(
+                (0..period)
+                    .map(|row| BaseElement::from((absorbed_at(row, num_breakpoints) == Some(breakpoint)) as u8))
+                    .collect(),
+            );
+        }
+        columns
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = Vec::new();
+        match &self.inputs.breakpoints {
+            Breakpoints::Public(breakpoints) => {
+                for (column, &value) in self.layout.breakpoints.iter().zip(breakpoints) {
+                    assertions.push(Assertion::single(column.value_column(), 0, BaseElement::from(value)));
+                }
+            }
+            Breakpoints::Committed { count, digest } => {
+                let sponge = self.layout.sponge.expect("Committed breakpoints have a sponge");
+                let first = sponge.next_column() - STATE_WIDTH;
+                for (i, value) in rescue::sponge_iv(*count, BREAKPOINTS_TAG).into_iter().enumerate() {
+                    assertions.push(Assertion::single(first + i, 0, value));
+                }
+                assertions.push(Assertion::single(first, (count + 1) * CYCLE_LENGTH - 1, *digest));
+            }
+        }
+
+        let inputs = &self.inputs;
+        let samples = inputs.sample_x_values.iter().zip(&inputs.sample_y_values);
+        for (step, (&x, &y)) in samples.chain([(&inputs.x_value, &inputs.predicted_y)]).enumerate() {
+            assertions.push(Assertion::single(X, step, BaseElement::from(x)));
+            assertions.push(Assertion::single(Y, step, y));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Builds the trace evaluating `model` at every sample and then at `target_x`
+#[cfg(feature = "prover")]
+pub fn build_piecewise_linear_trace(
+    model: &PiecewiseModel,
+    sample_x_values: &[u64],
+    target_x: u64,
+    commit: bool,
+    min_trace_length: usize,
+) -> Result<TraceTable<BaseElement>, PiecewiseError> {
+    model.validate()?;
+    if let Some(&x) = sample_x_values.iter().chain([&target_x]).find(|&&x| x >> VALUE_BITS != 0) {
+        return Err(PiecewiseError::OutOfRange(x));
+    }
+
+    let num_breakpoints = model.breakpoints.len();
+    let layout = Layout::new(num_breakpoints, commit);
+    let trace_length = trace_length(sample_x_values.len(), num_breakpoints, commit, min_trace_length);
+    let trace_width = layout.trace_width();
+    let mut columns = vec![vec![BaseElement::ZERO; trace_length]; trace_width];
+    let mut row = vec![BaseElement::ZERO; trace_width];
+    let mut state = rescue::sponge_iv(num_breakpoints, BREAKPOINTS_TAG);
+
+    for step in 0..trace_length {
+        let exempt = step == trace_length - 1;
+        let x = sample_x_values.get(step).copied().unwrap_or(target_x);
+        let segment = model.segment(x);
+        row[X] = BaseElement::from(x);
+        row[Y] = model.predict(x);
+        for s in 0..layout.num_segments {
+            // the exempt row sets every flag to 2, breaking booleanity and the one-hot sum
+            row[SEGMENTS + s] = BaseElement::from(if exempt { 2u8 } else { (s == segment) as u8 });
+            row[layout.slopes + s] = model.slopes[s];
+            row[layout.intercepts + s] = model.intercepts[s];
+        }
+
+        for (j, (column, margin)) in layout.breakpoints.iter().zip(&layout.margins).enumerate() {
+            let b = model.breakpoints[j];
+            if exempt {
+                column.fill_exempt_row(&mut row, b);
+                margin.fill_exempt_row(&mut row);
+            } else {
+                column.fill(&mut row, b).expect("Breakpoints are validated");
+                let value = if x >= b { x - b } else { b - 1 - x };
+                margin.fill(&mut row, value as i128).expect("Margins of validated values fit");
+            }
+        }
+
+        if let Some(sponge) = layout.sponge {
+            sponge.write(&mut row, &state);
+            let round = step % CYCLE_LENGTH;
+            if round < CYCLE_LENGTH - 1 {
+                rescue::apply_round(&mut state, round);
+            } else if let Some(breakpoint) = absorbed_at(step, num_breakpoints) {
+                state[0] += BaseElement::from(model.breakpoints[breakpoint]);
+            }
+        }
+
+        for (column, &value) in columns.iter_mut().zip(&row) {
+            column[step] = value;
+        }
+    }
+
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    fn model() -> PiecewiseModel {
+        PiecewiseModel {
+            breakpoints: vec![10, 50],
+            slopes: [3u128, 1, 0].map(BaseElement::new).to_vec(),
+            intercepts: [0u128, 25, 70].map(BaseElement::new).to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_public_and_committed_breakpoints() {
+        let model = model();
+        let samples = [0, 9, 10, 49, 50, 1000];
+        assert_eq!(samples.map(|x| model.segment(x)), [0, 0, 1, 1, 2, 2]);
+        let options = ProverConfig::default().to_proof_options();
+
+        for commit in [false, true] {
+            let inputs = PiecewiseLinearInputs::new(&model, &samples, 30, commit);
+            assert_eq!(inputs.predicted_y, BaseElement::new(55));
+            let trace = build_piecewise_linear_trace(
+                &model,
+                &samples,
+                30,
+                commit,
+                TraceInfo::MIN_TRACE_LENGTH,
+            )
+            .unwrap();
+            crate::air_test!(PiecewiseLinearAir, inputs.clone())
+                .with_trace(&trace)
+                .expect_all_hold()
+                .expect_assertions_hold()
+                .expect_full_coverage();
+            let proof = prove::<PiecewiseLinearAir>(trace, inputs.clone(), options.clone()).unwrap();
+            verify::<PiecewiseLinearAir>(proof.clone(), inputs.clone(), &default_acceptable_options()).unwrap();
+
+            // the first segment's value at x = 10, the second one gives 35
+            let mut wrong_segment = inputs.clone();
+            wrong_segment.sample_y_values[2] = BaseElement::new(30);
+            assert!(verify::<PiecewiseLinearAir>(proof.clone(), wrong_segment, &default_acceptable_options()).is_err());
+
+            let mut other_breakpoints = inputs;
+            other_breakpoints.breakpoints = match commit {
+                false => Breakpoints::Public(vec![10, 51]),
+                true => Breakpoints::commit(&[10, 51]),
+            };
+            assert!(verify::<PiecewiseLinearAir>(proof, other_breakpoints, &default_acceptable_options()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_invalid_models_are_rejected() {
+        let mut model = model();
+        model.breakpoints = vec![50, 10];
+        assert_eq!(model.validate(), Err(PiecewiseError::Unordered { index: 1 }));
+        model.breakpoints = vec![10];
+        assert!(matches!(model.validate(), Err(PiecewiseError::SegmentCount { breakpoints: 1, .. })));
+        assert_eq!(
+            build_piecewise_linear_trace(
+                &self::model(),
+                &[1 << 32],
+                0,
+                false,
+                TraceInfo::MIN_TRACE_LENGTH,
+            )
+            .unwrap_err(),
+            PiecewiseError::OutOfRange(1 << 32)
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! Trace buffers reused across proving jobs.
+//!
+//! A service proving one claim after another allocates the trace columns of every job and
+//! frees them when the proof is done; with large traces these are hundreds of megabytes per
+//! job, and interleaving them with the smaller allocations of the prover fragments the heap.
+//! A [`TracePool`] keeps the column buffers of finished jobs and hands them to the next job of
+//! the same or a smaller trace length, so a steady workload stops allocating trace memory
+//! after its first job.
+//!
+//! Jobs take their columns with [`TracePool::take`] and prove a [`PooledTrace`], which gives
+//! the columns back, zeroized, when the prover drops it. A prover given the pool through
+//! [`AirProver::with_lde_pool`](crate::prover::AirProver::with_lde_pool) also extends the trace
+//! into pooled columns with [`PooledTraceLde`], the largest buffers of a proof. The trace
+//! polynomials and the constraint evaluations, which Winterfell allocates itself, are not
+//! pooled.
+//! [`PoolStats`] counts how many columns were allocated and how many reused, which the
+//! session reports through [`Metrics`](crate::metrics::Metrics).
+
+use core::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use winter_air::proof::Queries;
+use winterfell::{
+    crypto::{ElementHasher, VectorCommitment},
+    math::{fft, fields::f128::BaseElement, FieldElement},
+    matrix::{ColMatrix, RowMatrix},
+    EvaluationFrame, PartitionOptions, StarkDomain, Trace, TraceInfo, TraceLde, TracePolyTable,
+};
+
+use crate::merkle::MerkleOptions;
+use crate::secret::zeroize_elements;
+
+const ELEMENT_BYTES: usize = 16;
+/// Columns Winterfell evaluates together when extending an auxiliary segment
+const SEGMENT_WIDTH: usize = 8;
+
+/// Retained memory of [`TracePool::default`]
+pub const DEFAULT_MAX_RETAINED_BYTES: usize = 1 << 30;
+
+/// Columns handed out by one [`TracePool::take`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Columns that needed a fresh allocation
+    pub allocated: usize,
+    /// Columns served from a buffer of an earlier job
+    pub reused: usize,
+}
+
+/// Column buffers of finished jobs, kept for the next ones
+#[derive(Debug)]
+pub struct TracePool {
+    free: Mutex<Vec<Vec<BaseElement>>>,
+    max_retained_bytes: usize,
+}
+
+impl Default for TracePool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_RETAINED_BYTES)
+    }
+}
+
+impl TracePool {
+    /// Pool keeping at most `max_retained_bytes` of idle buffers
+    pub fn new(max_retained_bytes: usize) -> Self {
+        Self { free: Mutex::new(Vec::new()), max_retained_bytes }
+    }
+
+    /// `width` zeroed columns of `length` elements, reusing the smallest idle buffers that fit
+    pub fn take(&self, width: usize, length: usize) -> (Vec<Vec<BaseElement>>, PoolStats) {
+        let mut free = self.free.lock().expect("Trace pool lock is not poisoned");
+        let mut stats = PoolStats::default();
+        let columns = (0..width)
+            .map(|_| {
+                let fit = free
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, buffer)| buffer.capacity() >= length)
+                    .min_by_key(|(_, buffer)| buffer.capacity())
+                    .map(|(i, _)| i);
+                let mut column = match fit {
+                    Some(i) => {
+                        stats.reused += 1;
+                        free.swap_remove(i)
+                    }
+                    None => {
+                        stats.allocated += 1;
+                        Vec::with_capacity(length)
+                    }
+                };
+                column.clear();
+                column.resize(length, BaseElement::ZERO);
+                column
+            })
+            .collect();
+        (columns, stats)
+    }
+
+    /// Keeps `columns` for later jobs, dropping the largest idle buffers beyond the retention
+    /// limit; the witness data they hold is zeroized first
+    pub fn give_back(&self, mut columns: Vec<Vec<BaseElement>>) {
+        columns.iter_mut().for_each(|column| zeroize_elements(column));
+        let mut free = self.free.lock().expect("Trace pool lock is not poisoned");
+        free.extend(columns);
+        free.sort_unstable_by_key(|buffer| buffer.capacity());
+        while free.iter().map(|buffer| buffer.capacity() * ELEMENT_BYTES).sum::<usize>() > self.max_retained_bytes {
+            free.pop();
+        }
+    }
+
+    /// Bytes held by idle buffers
+    pub fn retained_bytes(&self) -> usize {
+        let free = self.free.lock().expect("Trace pool lock is not poisoned");
+        free.iter().map(|buffer| buffer.capacity() * ELEMENT_BYTES).sum()
+    }
+}
+
+/// Execution trace whose columns return to a [`TracePool`] when it is dropped
+pub struct PooledTrace {
+    info: TraceInfo,
+    main: Option<ColMatrix<BaseElement>>,
+    pool: Arc<TracePool>,
+}
+
+impl PooledTrace {
+    /// Trace over `columns`, usually filled after [`TracePool::take`]
+    pub fn new(columns: Vec<Vec<BaseElement>>, pool: Arc<TracePool>) -> Self {
+        let main = ColMatrix::new(columns);
+        let info = TraceInfo::new(main.num_cols(), main.num_rows());
+        Self { info, main: Some(main), pool }
+    }
+}
+
+impl Trace for PooledTrace {
+    type BaseField = BaseElement;
+
+    fn info(&self) -> &TraceInfo {
+        &self.info
+    }
+
+    fn main_segment(&self) -> &ColMatrix<BaseElement> {
+        self.main.as_ref().expect("Columns are only taken on drop")
+    }
+
+    fn read_main_frame(&self, row_idx: usize, frame: &mut EvaluationFrame<BaseElement>) {
+        let next_row_idx = (row_idx + 1) % self.info.length();
+        self.main_segment().read_row_into(row_idx, frame.current_mut());
+        self.main_segment().read_row_into(next_row_idx, frame.next_mut());
+    }
+}
+
+impl Drop for PooledTrace {
+    fn drop(&mut self) {
+        if let Some(main) = self.main.take() {
+            self.pool.give_back(main.into_columns());
+        }
+    }
+}
+
+/// Low-degree extension of the main trace segment held in columns taken from a [`TracePool`],
+/// which get them back when the prover drops it.
+///
+/// Commits to and opens the rows exactly like Winterfell's `DefaultTraceLde`, so proofs are
+/// identical whichever of the two a prover uses. Without a pool the columns are allocated and
+/// freed as usual. Auxiliary segments, which no AIR of this crate has, are not pooled.
+pub struct PooledTraceLde<E, H, V>
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement>,
+    V: VectorCommitment<H>,
+{
+    main: Vec<Vec<BaseElement>>,
+    main_oracles: V,
+    aux: Option<(RowMatrix<E>, V)>,
+    blowup: usize,
+    trace_info: TraceInfo,
+    partition_options: PartitionOptions,
+    pool: Option<Arc<TracePool>>,
+    _h: PhantomData<H>,
+}
+
+impl<E, H, V> PooledTraceLde<E, H, V>
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement>,
+    V: VectorCommitment<H>,
+{
+    /// Extends `main_trace` over the LDE domain and commits to its rows
+    pub fn new(
+        trace_info: &TraceInfo,
+        main_trace: &ColMatrix<BaseElement>,
+        domain: &StarkDomain<BaseElement>,
+        partition_options: PartitionOptions,
+        pool: Option<Arc<TracePool>>,
+    ) -> (Self, TracePolyTable<E>) {
+        let polys = main_trace.interpolate_columns();
+        let lde_size = domain.lde_domain_size();
+        let mut main = match &pool {
+            Some(pool) => pool.take(polys.num_cols(), lde_size).0,
+            None => vec![vec![BaseElement::ZERO; lde_size]; polys.num_cols()],
+        };
+        let twiddles = fft::get_twiddles::<BaseElement>(lde_size);
+        for (column, poly) in main.iter_mut().zip(polys.columns()) {
+            // p(offset * x) has the coefficients of p scaled by the powers of the offset
+            let mut power = BaseElement::ONE;
+            for (value, &coefficient) in column.iter_mut().zip(poly) {
+                *value = coefficient * power;
+                power *= domain.offset();
+            }
+            fft::evaluate_poly(column, &twiddles);
+        }
+        let main_oracles = commit_to_rows::<H, V>(&main, partition_options);
+        let lde = Self {
+            main,
+            main_oracles,
+            aux: None,
+            blowup: domain.trace_to_lde_blowup(),
+            trace_info: trace_info.clone(),
+            partition_options,
+            pool,
+            _h: PhantomData,
+        };
+        (lde, TracePolyTable::new(polys))
+    }
+}
+
+impl<E, H, V> TraceLde<E> for PooledTraceLde<E, H, V>
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement> + Sync,
+    V: VectorCommitment<H> + Sync,
+{
+    type HashFn = H;
+    type VC = V;
+
+    fn get_main_trace_commitment(&self) -> H::Digest {
+        self.main_oracles.commitment()
+    }
+
+    fn set_aux_trace(
+        &mut self,
+        aux_trace: &ColMatrix<E>,
+        domain: &StarkDomain<BaseElement>,
+    ) -> (ColMatrix<E>, H::Digest) {
+        assert!(self.aux.is_none(), "the auxiliary trace has already been added");
+        let polys = aux_trace.interpolate_columns();
+        let lde = RowMatrix::evaluate_polys_over::<SEGMENT_WIDTH>(&polys, domain);
+        let leaves = MerkleOptions::default().hash_rows::<E, H>(
+            lde.num_rows(),
+            lde.num_cols(),
+            self.partition_options,
+            |i, row| row.copy_from_slice(lde.row(i)),
+        );
+        let oracles = V::new(leaves).expect("failed to construct trace vector commitment");
+        let commitment = oracles.commitment();
+        self.aux = Some((lde, oracles));
+        (polys, commitment)
+    }
+
+    fn read_main_trace_frame_into(&self, lde_step: usize, frame: &mut EvaluationFrame<BaseElement>) {
+        let next_lde_step = (lde_step + self.blowup) % self.trace_len();
+        read_row_into(&self.main, lde_step, frame.current_mut());
+        read_row_into(&self.main, next_lde_step, frame.next_mut());
+    }
+
+    fn read_aux_trace_frame_into(&self, lde_step: usize, frame: &mut EvaluationFrame<E>) {
+        let next_lde_step = (lde_step + self.blowup) % self.trace_len();
+        let (lde, _) = self.aux.as_ref().expect("expected aux segment to be present");
+        frame.current_mut().copy_from_slice(lde.row(lde_step));
+        frame.next_mut().copy_from_slice(lde.row(next_lde_step));
+    }
+
+    fn query(&self, positions: &[usize]) -> Vec<Queries> {
+        let rows = positions
+            .iter()
+            .map(|&position| {
+                let mut row = vec![BaseElement::ZERO; self.main.len()];
+                read_row_into(&self.main, position, &mut row);
+                row
+            })
+            .collect();
+        let mut result = vec![open_rows::<BaseElement, H, V>(&self.main_oracles, positions, rows)];
+        if let Some((lde, oracles)) = &self.aux {
+            let rows = positions.iter().map(|&position| lde.row(position).to_vec()).collect();
+            result.push(open_rows::<E, H, V>(oracles, positions, rows));
+        }
+        result
+    }
+
+    fn trace_len(&self) -> usize {
+        self.main[0].len()
+    }
+
+    fn blowup(&self) -> usize {
+        self.blowup
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+}
+
+impl<E, H, V> Drop for PooledTraceLde<E, H, V>
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement>,
+    V: VectorCommitment<H>,
+{
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool {
+            pool.give_back(core::mem::take(&mut self.main));
+        }
+    }
+}
+
+fn read_row_into(columns: &[Vec<BaseElement>], row_idx: usize, row: &mut [BaseElement]) {
+    for (value, column) in row.iter_mut().zip(columns) {
+        *value = column[row_idx];
+    }
+}
+
+/// Commitment to the rows of `columns`, see [`MerkleOptions::hash_rows`]
+fn commit_to_rows<H, V>(columns: &[Vec<BaseElement>], partition_options: PartitionOptions) -> V
+where
+    H: ElementHasher<BaseField = BaseElement>,
+    V: VectorCommitment<H>,
+{
+    let leaves = MerkleOptions::default().hash_rows::<BaseElement, H>(
+        columns[0].len(),
+        columns.len(),
+        partition_options,
+        |i, row| read_row_into(columns, i, row),
+    );
+    V::new(leaves).expect("failed to construct trace vector commitment")
+}
+
+fn open_rows<E, H, V>(oracles: &V, positions: &[usize], rows: Vec<Vec<E>>) -> Queries
+where
+    E: FieldElement<BaseField = BaseElement>,
+    H: ElementHasher<BaseField = BaseElement>,
+    V: VectorCommitment<H>,
+{
+    let (_, proof) = oracles
+        .open_many(positions)
+        .expect("failed to generate a batch opening proof for trace queries");
+    Queries::new::<H, E, V>(proof, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::prover::HashFn;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+    use winterfell::{crypto::MerkleTree, Air, DefaultTraceLde};
+
+    type Lde = PooledTraceLde<BaseElement, HashFn, MerkleTree<HashFn>>;
+
+    #[test]
+    fn test_buffers_are_reused() {
+        let pool = Arc::new(TracePool::new(4 * 64 * ELEMENT_BYTES));
+        let (mut columns, stats) = pool.take(3, 64);
+        assert_eq!(stats, PoolStats { allocated: 3, reused: 0 });
+        columns[0][5] = BaseElement::ONE;
+        drop(PooledTrace::new(columns, pool.clone()));
+        assert_eq!(pool.retained_bytes(), 3 * 64 * ELEMENT_BYTES);
+
+        // a shorter trace fits into the same buffers, which come back zeroed
+        let (columns, stats) = pool.take(4, 32);
+        assert_eq!(stats, PoolStats { allocated: 1, reused: 3 });
+        assert!(columns.iter().all(|column| column.len() == 32 && column.iter().all(|&v| v == BaseElement::ZERO)));
+        pool.give_back(columns);
+        // the retention limit drops the largest buffers first
+        assert_eq!(pool.retained_bytes(), (3 * 64 + 32) * ELEMENT_BYTES);
+        let (columns, _) = pool.take(2, 256);
+        pool.give_back(columns);
+        assert_eq!(pool.retained_bytes(), (3 * 64 + 32) * ELEMENT_BYTES);
+    }
+
+    #[test]
+    fn test_lde_matches_default_and_is_pooled() {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+        let xs: Vec<BaseElement> = (1..=20u64).map(BaseElement::from).collect();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(11);
+        let inputs = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        let air = LinearRegressionAir::new(trace.info().clone(), inputs, ProverConfig::default().to_proof_options());
+        let domain = StarkDomain::new(&air);
+        let pool = Arc::new(TracePool::default());
+        let positions = [0, 5, domain.lde_domain_size() - 1];
+
+        for partitions in [PartitionOptions::default(), PartitionOptions::new(2, 1)] {
+            let (default, _) = DefaultTraceLde::<BaseElement, HashFn, MerkleTree<HashFn>>::new(
+                trace.info(),
+                trace.main_segment(),
+                &domain,
+                partitions,
+            );
+            let (pooled, _) = Lde::new(trace.info(), trace.main_segment(), &domain, partitions, Some(pool.clone()));
+            assert_eq!(pooled.get_main_trace_commitment(), default.get_main_trace_commitment());
+            assert_eq!(pooled.query(&positions), default.query(&positions));
+            let mut frames: [_; 2] = core::array::from_fn(|_| EvaluationFrame::new(trace.info().main_trace_width()));
+            for step in [0, 17, domain.lde_domain_size() - 1] {
+                pooled.read_main_trace_frame_into(step, &mut frames[0]);
+                default.read_main_trace_frame_into(step, &mut frames[1]);
+                assert_eq!(frames[0].current(), frames[1].current());
+                assert_eq!(frames[0].next(), frames[1].next());
+            }
+            drop(pooled);
+        }
+        // the second extension took the columns the first one gave back
+        let lde_columns = trace.info().main_trace_width() * domain.lde_domain_size();
+        assert_eq!(pool.retained_bytes(), lde_columns * ELEMENT_BYTES);
+    }
+}
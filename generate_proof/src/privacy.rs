@@ -0,0 +1,475 @@
+//! Noisy releases of aggregate statistics, verifiable against committed data and randomness.
+//!
+//! A curator who publishes the sum of a sensitive column adds noise to it so that no single
+//! record can be inferred from the release. [`NoisySumAir`] proves that a released value is
+//! `sum(values) + noise` where the values are the ones hashed into a public [`data_digest`],
+//! each clipped to `0..2^value_bits` so one record moves the sum by less than `2^value_bits`,
+//! and the noise is taken from [`NoiseRandomness`] committed before the data was seen.
+//!
+//! The randomness is a draw in `0..2^noise_bits` and a salt hiding it. The noise is the draw
+//! minus `2^(noise_bits - 1)`, uniform in `-2^(noise_bits - 1)..2^(noise_bits - 1)` when the
+//! draw is derived from a secret seed with [`NoiseRandomness::from_seed`]. Publishing
+//! [`NoiseRandomness::commitment`] ahead of the release keeps the curator from choosing the
+//! noise after seeing the data; the proof then shows the release used exactly that noise
+//! without revealing it. The pipeline is [`clip`], [`data_digest`], then
+//! [`build_noisy_sum_trace`] with [`NoisySumInputs::for_release`].
+//!
+//! The trace has one [`CYCLE_LENGTH`]-row cycle per value plus one that finishes the data
+//! sponge. The randomness sponge absorbs the draw and the salt at the end of every cycle; only
+//! its state after the second cycle, the commitment, is asserted.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::RangeCheck;
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`NoisySumAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "noisy-sum/v1";
+
+/// Values are clipped to at most this many bits
+pub const MAX_VALUE_BITS: usize = 32;
+/// Draws have at most this many bits
+pub const MAX_NOISE_BITS: usize = 62;
+/// Releases cover fewer than `2^MAX_VALUES_BITS` values
+pub const MAX_VALUES_BITS: usize = 32;
+
+/// Sponge tags of [`data_digest`] and [`NoiseRandomness::commitment`]; tags 1 to 10 are used
+/// by the other AIRs
+const DATA_TAG: u64 = 11;
+const NOISE_TAG: u64 = 12;
+
+const SEED_DOMAIN: &[u8] = b"stark-framework/noise-randomness/v1";
+
+// Trace layout: data sponge, value, running sum, randomness sponge, draw, salt, released sum,
+// then the digits of the value and of the draw
+const DATA_SPONGE: usize = 0;
+const VALUE: usize = DATA_SPONGE + STATE_WIDTH;
+const SUM: usize = VALUE + 1;
+const NOISE_SPONGE: usize = SUM + 1;
+const DRAW: usize = NOISE_SPONGE + STATE_WIDTH;
+const SALT: usize = DRAW + 1;
+const RELEASE: usize = SALT + 1;
+const DIGITS_START: usize = RELEASE + 1;
+
+struct Layout {
+    data: RescueGadget,
+    noise: RescueGadget,
+    value: RangeCheck,
+    draw: RangeCheck,
+}
+
+impl Layout {
+    fn new(value_bits: usize, noise_bits: usize) -> Self {
+        let value = RangeCheck::new(DIGITS_START, value_bits);
+        Self {
+            data: RescueGadget::new(DATA_SPONGE),
+            noise: RescueGadget::new(NOISE_SPONGE),
+            value,
+            draw: RangeCheck::new(value.next_column(), noise_bits),
+        }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.draw.next_column()
+    }
+}
+
+/// Number of trace columns for values of `value_bits` bits and draws of `noise_bits` bits
+pub fn trace_width(value_bits: usize, noise_bits: usize) -> usize {
+    Layout::new(value_bits, noise_bits).trace_width()
+}
+
+/// Named columns of the trace for values of `value_bits` bits and draws of `noise_bits` bits, see
+/// [`crate::layout`]
+pub fn layout(value_bits: usize, noise_bits: usize) -> LayoutDescriptor {
+    let layout = Layout::new(value_bits, noise_bits);
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("data_sponge", DATA_SPONGE..VALUE)
+        .with_column("value", VALUE)
+        .with_column("sum", SUM)
+        .with_columns("noise_sponge", NOISE_SPONGE..DRAW)
+        .with_column("draw", DRAW)
+        .with_column("salt", SALT)
+        .with_column("release", RELEASE)
+        .with_columns("value_digits", DIGITS_START..layout.value.next_column())
+        .with_columns("draw_digits", layout.value.next_column()..layout.draw.next_column())
+}
+
+/// Trace length for a release over `num_values` values
+pub fn trace_length(num_values: usize) -> usize {
+    ((num_values + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Digest the curator publishes for the clipped values
+pub fn data_digest(values: &[u64]) -> BaseElement {
+    let elements: Vec<BaseElement> = values.iter().map(|&value| BaseElement::from(value)).collect();
+    rescue::hash_elements(&elements, DATA_TAG)
+}
+
+/// Clamps every value into `0..2^value_bits`, bounding what one record adds to a sum
+pub fn clip(values: &[i64], value_bits: usize) -> Result<Vec<u64>, PrivacyError> {
+    check_bits(value_bits, MAX_NOISE_BITS)?;
+    let max = (1u64 << value_bits) - 1;
+    Ok(values.iter().map(|&value| (value.max(0) as u64).min(max)).collect())
+}
+
+fn check_bits(value_bits: usize, noise_bits: usize) -> Result<(), PrivacyError> {
+    if !(1..=MAX_VALUE_BITS).contains(&value_bits) {
+        return Err(PrivacyError::ValueBits(value_bits));
+    }
+    if !(1..=MAX_NOISE_BITS).contains(&noise_bits) {
+        return Err(PrivacyError::NoiseBits(noise_bits));
+    }
+    Ok(())
+}
+
+/// Committed randomness the noise of a release is taken from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoiseRandomness {
+    /// Noise plus `2^(noise_bits - 1)`
+    pub draw: u64,
+    pub salt: BaseElement,
+}
+
+impl NoiseRandomness {
+    /// Randomness expanded from a secret `seed`, with a uniform draw of `noise_bits` bits
+    pub fn from_seed(seed: &[u8; 32], noise_bits: usize) -> Self {
+        assert!((1..=MAX_NOISE_BITS).contains(&noise_bits), "Draws have 1..={MAX_NOISE_BITS} bits");
+        let mut reader = blake3::Hasher::new().update(SEED_DOMAIN).update(seed).finalize_xof();
+        let mut bytes = [0u8; 24];
+        reader.fill(&mut bytes);
+        let draw = u64::from_le_bytes(bytes[..8].try_into().unwrap()) >> (64 - noise_bits);
+        // 128 random bits reduced into the field, close enough to uniform to hide the draw
+        let salt = BaseElement::new(u128::from_le_bytes(bytes[8..].try_into().unwrap()));
+        Self { draw, salt }
+    }
+
+    /// The noise added to a release with draws of `noise_bits` bits
+    pub fn noise(&self, noise_bits: usize) -> i128 {
+        self.draw as i128 - (1i128 << (noise_bits - 1))
+    }
+
+    /// Digest published before the release
+    pub fn commitment(&self) -> BaseElement {
+        let mut state = rescue::sponge_iv(2, NOISE_TAG);
+        rescue::permute(&mut state);
+        state[0] += BaseElement::from(self.draw);
+        state[1] += self.salt;
+        rescue::permute(&mut state);
+        state[0]
+    }
+}
+
+/// Reason a release cannot be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrivacyError {
+    Empty,
+    TooManyValues(usize),
+    /// Values are not clipped to `1..=MAX_VALUE_BITS` bits
+    ValueBits(usize),
+    /// Draws do not have `1..=MAX_NOISE_BITS` bits
+    NoiseBits(usize),
+    /// A value was not clipped to `value_bits` bits, see [`clip`]
+    Unclipped { index: usize, value: u64, value_bits: usize },
+    DrawOutOfRange { draw: u64, noise_bits: usize },
+}
+
+impl fmt::Display for PrivacyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrivacyError::Empty => write!(f, "no values"),
+            PrivacyError::TooManyValues(count) => write!(f, "{count} values exceed 2^{MAX_VALUES_BITS}"),
+            PrivacyError::ValueBits(bits) => write!(f, "values of {bits} bits are not in 1..={MAX_VALUE_BITS}"),
+            PrivacyError::NoiseBits(bits) => write!(f, "draws of {bits} bits are not in 1..={MAX_NOISE_BITS}"),
+            PrivacyError::Unclipped { index, value, value_bits } => {
+                write!(f, "value {value} at index {index} does not fit into {value_bits} bits")
+            }
+            PrivacyError::DrawOutOfRange { draw, noise_bits } => {
+                write!(f, "draw {draw} does not fit into {noise_bits} bits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrivacyError {}
+
+/// Public inputs: both digests, the bounds and the released sum
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoisySumInputs {
+    pub data_digest: BaseElement,
+    pub randomness_commitment: BaseElement,
+    pub num_values: usize,
+    pub value_bits: usize,
+    pub noise_bits: usize,
+    pub released: i128,
+}
+
+impl NoisySumInputs {
+    /// Inputs of the release of the sum of `values` with the noise of `randomness`
+    pub fn for_release(values: &[u64], randomness: &NoiseRandomness, value_bits: usize, noise_bits: usize) -> Self {
+        let sum: i128 = values.iter().map(|&value| value as i128).sum();
+        Self {
+            data_digest: data_digest(values),
+            randomness_commitment: randomness.commitment(),
+            num_values: values.len(),
+            value_bits,
+            noise_bits,
+            released: sum + randomness.noise(noise_bits),
+        }
+    }
+}
+
+impl ToElements<BaseElement> for NoisySumInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            self.data_digest,
+            self.randomness_commitment,
+            BaseElement::from(self.num_values as u64),
+            BaseElement::from(self.value_bits as u64),
+            BaseElement::from(self.noise_bits as u64),
+            to_field(self.released),
+        ]
+    }
+}
+
+/// AIR proving that a release is the sum of committed, clipped values plus committed noise
+pub struct NoisySumAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: NoisySumInputs,
+}
+
+impl Air for NoisySumAir {
+    type BaseField = BaseElement;
+    type PublicInputs = NoisySumInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: NoisySumInputs, options: ProofOptions) -> Self {
+        assert!((1..=MAX_VALUE_BITS).contains(&pub_inputs.value_bits), "Values have 1..={MAX_VALUE_BITS} bits");
+        assert!((1..=MAX_NOISE_BITS).contains(&pub_inputs.noise_bits), "Draws have 1..={MAX_NOISE_BITS} bits");
+        let layout = Layout::new(pub_inputs.value_bits, pub_inputs.noise_bits);
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert!(pub_inputs.num_values > 0, "Releases cover at least one value");
+        assert!(pub_inputs.num_values as u64 >> MAX_VALUES_BITS == 0, "Too many values");
+        assert_eq!(trace_length(pub_inputs.num_values), trace_info.length());
+
+        let periodic = TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of both sponges (degree 3)
+        // 2. At the end of a cycle the data sponge absorbs the value and the randomness sponge
+        //    the draw and the salt (degree 1)
+        // 3. The value is constant within a cycle (degree 1)
+        // 4. The sum adds the value at the end of a cycle (degree 1)
+        // 5. Draw and salt stay constant (degree 1)
+        // 6. released = sum + draw (degree 1)
+        // 7. Value and draw match their range-checked digits
+        let mut degrees = layout.data.constraint_degrees();
+        degrees.extend(layout.noise.constraint_degrees());
+        degrees.extend(vec![periodic; 2 * STATE_WIDTH + 2]);
+        degrees.extend(vec![TransitionConstraintDegree::new(1); 5]);
+        degrees.extend(layout.value.constraint_degrees());
+        degrees.extend(layout.draw.constraint_degrees());
+
+        NoisySumAir {
+            context: AirContext::new(trace_info, degrees, 2 * STATE_WIDTH + 4, options),
+            layout,
+            inputs: pub_inputs,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(2 * STATE_WIDTH);
+        layout.data.evaluate(current, next, periodic_values, &mut sponge_result[..STATE_WIDTH]);
+        layout.noise.evaluate(current, next, periodic_values, &mut sponge_result[STATE_WIDTH..]);
+
+        let (absorb_result, rest) = rest.split_at_mut(2 * STATE_WIDTH);
+        let absorbed = [(DATA_SPONGE, [current[VALUE], E::ZERO]), (NOISE_SPONGE, [current[DRAW], current[SALT]])];
+        for (absorb, (sponge, values)) in absorb_result.chunks_mut(STATE_WIDTH).zip(absorbed) {
+            for i in 0..STATE_WIDTH {
+                let value = values.get(i).copied().unwrap_or(E::ZERO);
+                absorb[i] = last * (next[sponge + i] - current[sponge + i] - value);
+            }
+        }
+        rest[0] = mask * (next[VALUE] - current[VALUE]);
+        rest[1] = next[SUM] - current[SUM] - last * current[VALUE];
+
+        rest[2] = next[DRAW] - current[DRAW];
+        rest[3] = next[SALT] - current[SALT];
+        rest[4] = current[RELEASE] - current[SUM] - current[DRAW];
+        rest[5] = current[VALUE] - layout.value.recompose(current);
+        rest[6] = current[DRAW] - layout.draw.recompose(current);
+
+        let (value_result, draw_result) = rest[7..].split_at_mut(layout.value.num_constraints());
+        layout.value.evaluate(current, value_result);
+        layout.draw.evaluate(current, draw_result);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let inputs = &self.inputs;
+        let mut assertions = Vec::new();
+        for (sponge, len, tag) in [(DATA_SPONGE, inputs.num_values, DATA_TAG), (NOISE_SPONGE, 2, NOISE_TAG)] {
+            for (i, value) in rescue::sponge_iv(len, tag).into_iter().enumerate() {
+                assertions.push(Assertion::single(sponge + i, 0, value));
+            }
+        }
+        let digest_row = (inputs.num_values + 1) * CYCLE_LENGTH - 1;
+        assertions.push(Assertion::single(DATA_SPONGE, digest_row, inputs.data_digest));
+        assertions.push(Assertion::single(NOISE_SPONGE, 2 * CYCLE_LENGTH - 1, inputs.randomness_commitment));
+        assertions.push(Assertion::single(SUM, 0, BaseElement::ZERO));
+
+        // the release row holds the draw, which is the noise shifted to be non-negative
+        let shifted = inputs.released + (1i128 << (inputs.noise_bits - 1));
+        assertions.push(Assertion::single(RELEASE, inputs.num_values * CYCLE_LENGTH, to_field(shifted)));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace releasing the sum of `values` with the noise of `randomness`
+#[cfg(feature = "prover")]
+pub fn build_noisy_sum_trace(
+    values: &[u64],
+    randomness: &NoiseRandomness,
+    value_bits: usize,
+    noise_bits: usize,
+) -> Result<TraceTable<BaseElement>, PrivacyError> {
+    check_bits(value_bits, noise_bits)?;
+    let num_values = values.len();
+    if num_values == 0 {
+        return Err(PrivacyError::Empty);
+    }
+    if num_values as u64 >> MAX_VALUES_BITS != 0 {
+        return Err(PrivacyError::TooManyValues(num_values));
+    }
+    if let Some((index, &value)) = values.iter().enumerate().find(|(_, value)| **value >> value_bits != 0) {
+        return Err(PrivacyError::Unclipped { index, value, value_bits });
+    }
+    if randomness.draw >> noise_bits != 0 {
+        return Err(PrivacyError::DrawOutOfRange { draw: randomness.draw, noise_bits });
+    }
+
+    // padding cycles absorb a value other than the last one, which keeps the value column
+    // from being constant
+    let padding = (values[num_values - 1] == 0) as u64;
+
+    let layout = Layout::new(value_bits, noise_bits);
+    let length = trace_length(num_values);
+    let mut columns = vec![vec![BaseElement::ZERO; length]; layout.trace_width()];
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut data_state = rescue::sponge_iv(num_values, DATA_TAG);
+    let mut noise_state = rescue::sponge_iv(2, NOISE_TAG);
+    let draw = BaseElement::from(randomness.draw);
+    let mut sum = BaseElement::ZERO;
+
+    row[DRAW] = draw;
+    row[SALT] = randomness.salt;
+    layout.draw.fill(&mut row, randomness.draw as i128).expect("Draw was range checked");
+
+    for cycle in 0..length / CYCLE_LENGTH {
+        let value = values.get(cycle).copied().unwrap_or(padding);
+        row[VALUE] = BaseElement::from(value);
+        row[SUM] = sum;
+        row[RELEASE] = sum + draw;
+        layout.value.fill(&mut row, value as i128).expect("Values were range checked");
+
+        for step in 0..CYCLE_LENGTH {
+            layout.data.write(&mut row, &data_state);
+            layout.noise.write(&mut row, &noise_state);
+            for (column, &cell) in columns.iter_mut().zip(&row) {
+                column[cycle * CYCLE_LENGTH + step] = cell;
+            }
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut data_state, step);
+                rescue::apply_round(&mut noise_state, step);
+            }
+        }
+        data_state[0] += row[VALUE];
+        noise_state[0] += draw;
+        noise_state[1] += randomness.salt;
+        sum += row[VALUE];
+    }
+
+    let mut last_row: Vec<BaseElement> = columns.iter().map(|column| column[length - 1]).collect();
+    layout.value.fill_exempt_row(&mut last_row);
+    layout.draw.fill_exempt_row(&mut last_row);
+    for (column, value) in columns.iter_mut().zip(last_row) {
+        column[length - 1] = value;
+    }
+
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_noisy_sum_release() {
+        let (value_bits, noise_bits) = (8, 10);
+        let values = clip(&[120, -4, 310, 57, 200], value_bits).unwrap();
+        assert_eq!(values, [120, 0, 255, 57, 200]);
+        let randomness = NoiseRandomness::from_seed(&[7u8; 32], noise_bits);
+        let inputs = NoisySumInputs::for_release(&values, &randomness, value_bits, noise_bits);
+        assert_eq!(inputs.released, 632 + randomness.noise(noise_bits));
+
+        let trace = build_noisy_sum_trace(&values, &randomness, value_bits, noise_bits).unwrap();
+        let proof = prove::<NoisySumAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+
+        let options = default_acceptable_options();
+        let shifted = NoisySumInputs { released: inputs.released + 1, ..inputs };
+        assert!(verify::<NoisySumAir>(proof.clone(), shifted, &options).is_err());
+        let other = NoiseRandomness::from_seed(&[8u8; 32], noise_bits);
+        let resampled = NoisySumInputs { randomness_commitment: other.commitment(), ..inputs };
+        assert!(verify::<NoisySumAir>(proof.clone(), resampled, &options).is_err());
+        let other_data = NoisySumInputs { data_digest: data_digest(&[120, 0, 255, 57, 201]), ..inputs };
+        assert!(verify::<NoisySumAir>(proof.clone(), other_data, &options).is_err());
+        let result = verify::<NoisySumAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_rejects_unbounded_inputs() {
+        let randomness = NoiseRandomness { draw: 5, salt: BaseElement::ONE };
+        assert_eq!(
+            build_noisy_sum_trace(&[3, 300], &randomness, 8, 4).unwrap_err(),
+            PrivacyError::Unclipped { index: 1, value: 300, value_bits: 8 }
+        );
+        assert_eq!(build_noisy_sum_trace(&[3], &randomness, 8, 2).unwrap_err(), PrivacyError::DrawOutOfRange {
+            draw: 5,
+            noise_bits: 2
+        });
+        assert_eq!(build_noisy_sum_trace(&[], &randomness, 8, 4).unwrap_err(), PrivacyError::Empty);
+        assert_eq!(clip(&[1], 64).unwrap_err(), PrivacyError::ValueBits(64));
+        assert_eq!(build_noisy_sum_trace(&[3], &randomness, 0, 4).unwrap_err(), PrivacyError::ValueBits(0));
+        assert_eq!(build_noisy_sum_trace(&[3], &randomness, 8, 64).unwrap_err(), PrivacyError::NoiseBits(64));
+        assert_eq!(randomness.noise(4), -3);
+    }
+}
@@ -0,0 +1,230 @@
+//! Which build of the prover produced an envelope, where and when.
+//!
+//! A [`ProvingSession`](crate::session::ProvingSession) built `with_provenance` records a
+//! [`Provenance`] in every compact envelope: the crate version, the git commit it was built
+//! from (`build.rs`, or `STARK_GIT_COMMIT` for builds outside a repository), the hostname and
+//! the time of proving. When a prover release turns out to be faulty, the envelopes it produced
+//! can be found, and a [`VerificationPolicy`](crate::config::VerificationPolicy) with
+//! `min_prover_version` rejects envelopes of older releases, without provenance or unsigned.
+//!
+//! Provenance is what the prover says about itself. It is not part of the claim hash, so the
+//! proof does not cover it, but the operator signature and the hardware attestation sign it
+//! along with the claim hash, see [`ProofEnvelope::signed_hash`]. A version requirement is
+//! therefore only met by a signed envelope; it stops nobody but the holder of the key from
+//! passing off another release. The on-chain encoding does not carry provenance. With the
+//! `tracing` feature, [`Provenance::capture`] also emits it as an event.
+
+use core::fmt;
+use core::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::envelope::ProofEnvelope;
+
+/// Version of this crate
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Commit this crate was built from, `unknown` outside a git checkout
+pub const GIT_COMMIT: &str = env!("STARK_GIT_COMMIT");
+
+/// A `major.minor.patch` release of the prover; pre-release and build suffixes are ignored
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ProverVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl ProverVersion {
+    /// The version of this build
+    pub fn current() -> Self {
+        CRATE_VERSION.parse().expect("The crate version is a semantic version")
+    }
+}
+
+impl fmt::Display for ProverVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for ProverVersion {
+    type Err = InvalidVersion;
+
+    fn from_str(version: &str) -> Result<Self, InvalidVersion> {
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().map_err(|_| InvalidVersion(version.into())));
+        let mut next = || parts.next().unwrap_or_else(|| Err(InvalidVersion(version.into())));
+        let (major, minor, patch) = (next()?, next()?, next()?);
+        match parts.next() {
+            None => Ok(Self { major, minor, patch }),
+            Some(_) => Err(InvalidVersion(version.into())),
+        }
+    }
+}
+
+impl TryFrom<String> for ProverVersion {
+    type Error = InvalidVersion;
+
+    fn try_from(version: String) -> Result<Self, InvalidVersion> {
+        version.parse()
+    }
+}
+
+impl From<ProverVersion> for String {
+    fn from(version: ProverVersion) -> Self {
+        version.to_string()
+    }
+}
+
+/// A version that is not `major.minor.patch`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidVersion(pub String);
+
+impl fmt::Display for InvalidVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a major.minor.patch version", self.0)
+    }
+}
+
+impl std::error::Error for InvalidVersion {}
+
+/// The build and machine that produced an envelope
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub crate_version: ProverVersion,
+    pub git_commit: String,
+    pub hostname: String,
+    /// Unix time of proving, in seconds
+    pub timestamp: u64,
+}
+
+impl Provenance {
+    /// Provenance of a proof produced now by this build on this machine
+    pub fn capture() -> Self {
+        let provenance = Self {
+            crate_version: ProverVersion::current(),
+            git_commit: GIT_COMMIT.to_string(),
+            hostname: hostname(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            target: "stark::provenance",
+            crate_version = %provenance.crate_version,
+            git_commit = %provenance.git_commit,
+            hostname = %provenance.hostname,
+            timestamp = provenance.timestamp,
+            "captured proof provenance"
+        );
+        provenance
+    }
+
+    /// Feeds the provenance to `hasher`, every string prefixed by its length
+    pub fn absorb_into(&self, hasher: &mut blake3::Hasher) {
+        let version = self.crate_version;
+        for part in [version.major, version.minor, version.patch] {
+            hasher.update(&part.to_le_bytes());
+        }
+        for text in [&self.git_commit, &self.hostname] {
+            hasher.update(&(text.len() as u32).to_le_bytes());
+            hasher.update(text.as_bytes());
+        }
+        hasher.update(&self.timestamp.to_le_bytes());
+    }
+}
+
+/// Errors of checking provenance against a policy
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProvenanceError {
+    /// The policy requires a prover version but the envelope records no provenance
+    Missing,
+    /// The envelope records provenance but neither an operator signature nor an attestation
+    /// covers it
+    Unsigned,
+    Outdated { required: ProverVersion, found: ProverVersion },
+}
+
+impl fmt::Display for ProvenanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProvenanceError::Missing => write!(f, "envelope records no prover provenance"),
+            ProvenanceError::Unsigned => write!(f, "envelope provenance is not signed"),
+            ProvenanceError::Outdated { required, found } => {
+                write!(f, "envelope was produced by prover {found}, the policy requires {required} or later")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProvenanceError {}
+
+/// Checks that the provenance of `envelope` names a prover of at least version `required` and
+/// is signed; the signer and the attestation are verified separately
+pub fn require_version(envelope: &ProofEnvelope, required: ProverVersion) -> Result<(), ProvenanceError> {
+    let found = envelope.provenance.as_ref().ok_or(ProvenanceError::Missing)?.crate_version;
+    if envelope.signer.is_none() && envelope.attestation.is_none() {
+        return Err(ProvenanceError::Unsigned);
+    }
+    if found < required {
+        return Err(ProvenanceError::Outdated { required, found });
+    }
+    Ok(())
+}
+
+/// Name of this machine, `unknown` if the platform does not tell
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winterfell::{math::fields::f128::BaseElement, Proof};
+
+    use crate::domain::DomainTag;
+    use crate::fingerprint::ConstraintFingerprint;
+    use crate::operator::OperatorKey;
+    use crate::registry::{ModelCommitment, ModelId};
+
+    #[test]
+    fn test_versions_parse_and_order() {
+        let version: ProverVersion = "1.4.2-rc.1+build.5".parse().unwrap();
+        assert_eq!(version, ProverVersion { major: 1, minor: 4, patch: 2 });
+        assert!(version < "1.10.0".parse().unwrap());
+        assert!(version > "0.99.99".parse().unwrap());
+        for invalid in ["", "1.4", "1.4.2.0", "v1.4.2", "1.x.2"] {
+            assert!(invalid.parse::<ProverVersion>().is_err(), "{invalid}");
+        }
+        assert_eq!(ProverVersion::current().to_string(), CRATE_VERSION);
+    }
+
+    #[test]
+    fn test_require_version() {
+        let provenance = Provenance::capture();
+        assert!(!provenance.git_commit.is_empty());
+        let envelope = ProofEnvelope::new(
+            ModelId::from_name("house-prices"),
+            ModelCommitment([9u8; 32]),
+            ConstraintFingerprint([4u8; 32]),
+            vec![BaseElement::new(8)],
+            Proof::new_dummy(),
+        );
+        let current = ProverVersion::current();
+        assert_eq!(require_version(&envelope, current), Err(ProvenanceError::Missing));
+        let mut traced = envelope.with_provenance(provenance);
+        assert_eq!(require_version(&traced, current), Err(ProvenanceError::Unsigned));
+
+        OperatorKey::from_seed(&[1u8; 32]).sign_envelope(&mut traced, &DomainTag::default());
+        require_version(&traced, current).unwrap();
+        let next = ProverVersion { patch: current.patch + 1, ..current };
+        assert_eq!(require_version(&traced, next), Err(ProvenanceError::Outdated { required: next, found: current }));
+    }
+}
@@ -0,0 +1,400 @@
+#[cfg(feature = "prover")]
+use core::marker::PhantomData;
+#[cfg(feature = "prover")]
+use std::thread::{self, JoinHandle};
+
+use winterfell::{
+    math::fields::f128::BaseElement,
+    Air, Proof, VerifierError, AcceptableOptions,
+    crypto::{hashers::Blake3_256, DefaultRandomCoin, MerkleTree, RandomCoin},
+};
+#[cfg(feature = "prover")]
+use winterfell::{
+    crypto::VectorCommitment,
+    math::FieldElement,
+    ProofOptions, Prover, Trace, TraceInfo, TraceTable, ProverError,
+    matrix::ColMatrix,
+    CompositionPoly, CompositionPolyTrace,
+    StarkDomain,
+    TracePolyTable, ConstraintCompositionCoefficients,
+    AuxRandElements, PartitionOptions,
+};
+
+#[cfg(feature = "prover")]
+use std::sync::Arc;
+
+#[cfg(feature = "prover")]
+use crate::evaluator::{AirEvaluator, EvaluatorKind};
+#[cfg(feature = "prover")]
+use crate::merkle::RowConstraintCommitment;
+#[cfg(feature = "prover")]
+use crate::pool::{PooledTraceLde, TracePool};
+#[cfg(feature = "prover")]
+use crate::simulation::{DryRunError, DryRunReport};
+
+#[cfg(feature = "prover")]
+pub use crate::simulation::dry_run;
+
+/// Hash function used for all commitments produced by this crate
+pub type HashFn = Blake3_256<BaseElement>;
+/// Name of [`HashFn`] in configuration commitments, see [`crate::config::ConfigCommitment`]
+pub const HASH_FN_NAME: &str = "blake3-256";
+
+/// Prover for any AIR whose public inputs are known up front instead of being read from the trace.
+///
+/// Traces are [`TraceTable`]s unless `T` says otherwise, e.g. a
+/// [`PooledTrace`](crate::pool::PooledTrace) whose columns outlive the proof. Challenges are
+/// drawn from `C`, see [`crate::coin`]; the verifier must use the same coin. Commitments are
+/// built by `V`, e.g. a [`HybridMerkleTree`](crate::merkle::HybridMerkleTree), which must open
+/// like a [`MerkleTree`] for the proofs to verify. The low-degree extension of the trace is a
+/// [`PooledTraceLde`], which draws its columns from the pool set by [`Self::with_lde_pool`].
+#[cfg(feature = "prover")]
+pub struct AirProver<
+    A: Air<BaseField = BaseElement>,
+    T = TraceTable<BaseElement>,
+    C = DefaultRandomCoin<HashFn>,
+    V = MerkleTree<HashFn>,
+> {
+    options: ProofOptions,
+    pub_inputs: A::PublicInputs,
+    evaluator: EvaluatorKind,
+    lde_pool: Option<Arc<TracePool>>,
+    _air: PhantomData<(A, T, C, V)>,
+}
+
+#[cfg(feature = "prover")]
+impl<A, T, C, V> AirProver<A, T, C, V>
+where
+    A: Air<BaseField = BaseElement>,
+    A::PublicInputs: Clone,
+{
+    pub fn new(options: ProofOptions, pub_inputs: A::PublicInputs) -> Self {
+        Self { options, pub_inputs, evaluator: EvaluatorKind::Default, lde_pool: None, _air: PhantomData }
+    }
+
+    /// Evaluates constraints with `evaluator` instead of Winterfell's default evaluator
+    pub fn with_evaluator(mut self, evaluator: EvaluatorKind) -> Self {
+        self.evaluator = evaluator;
+        self
+    }
+
+    /// Extends traces into columns taken from `pool` instead of fresh allocations
+    pub fn with_lde_pool(mut self, pool: Arc<TracePool>) -> Self {
+        self.lde_pool = Some(pool);
+        self
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<A> AirProver<A>
+where
+    A: Air<BaseField = BaseElement>,
+    A::PublicInputs: Clone,
+{
+    /// Checks `trace` against the AIR without proving it, see [`crate::simulation`]
+    pub fn dry_run(&self, trace: &TraceTable<BaseElement>) -> Result<DryRunReport, DryRunError> {
+        dry_run::<A>(trace, self.pub_inputs.clone(), self.options.clone())
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<A, T, C, V> Prover for AirProver<A, T, C, V>
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Clone + Sync,
+    T: Trace<BaseField = BaseElement> + Send + Sync,
+    C: RandomCoin<BaseField = BaseElement, Hasher = HashFn> + Send + Sync,
+    V: VectorCommitment<HashFn> + Send + Sync,
+{
+    type BaseField = BaseElement;
+    type Air = A;
+    type Trace = T;
+    type HashFn = HashFn;
+    type VC = V;
+    type RandomCoin = C;
+    type TraceLde<E: FieldElement<BaseField = Self::BaseField>> = PooledTraceLde<E, Self::HashFn, Self::VC>;
+    type ConstraintCommitment<E: FieldElement<BaseField = Self::BaseField>> =
+        RowConstraintCommitment<E, Self::HashFn, Self::VC>;
+    type ConstraintEvaluator<'a, E: FieldElement<BaseField = Self::BaseField>> =
+        AirEvaluator<'a, Self::Air, E>;
+
+    fn get_pub_inputs(&self, _trace: &Self::Trace) -> A::PublicInputs {
+        self.pub_inputs.clone()
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn new_trace_lde<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        trace_info: &TraceInfo,
+        main_trace: &ColMatrix<Self::BaseField>,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_option: PartitionOptions,
+    ) -> (Self::TraceLde<E>, TracePolyTable<E>) {
+        PooledTraceLde::new(trace_info, main_trace, domain, partition_option, self.lde_pool.clone())
+    }
+
+    fn build_constraint_commitment<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        composition_poly_trace: CompositionPolyTrace<E>,
+        num_constraint_composition_columns: usize,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::ConstraintCommitment<E>, CompositionPoly<E>) {
+        RowConstraintCommitment::new(
+            composition_poly_trace,
+            num_constraint_composition_columns,
+            domain,
+            partition_options,
+        )
+    }
+
+    fn new_evaluator<'a, E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        air: &'a Self::Air,
+        aux_rand_elements: Option<AuxRandElements<E>>,
+        composition_coefficients: ConstraintCompositionCoefficients<E>,
+    ) -> Self::ConstraintEvaluator<'a, E> {
+        AirEvaluator::new(self.evaluator, air, aux_rand_elements, composition_coefficients)
+    }
+}
+
+/// Proves `trace` against AIR `A` for the given public inputs.
+///
+/// The proof is deterministic: with the `concurrent` feature it runs on a pool of one thread,
+/// built once per calling thread, see [`crate::determinism`]. [`spawn_prove`] with more
+/// workers is the multi-threaded path.
+#[cfg(feature = "prover")]
+pub fn prove<A>(
+    trace: TraceTable<BaseElement>,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+) -> Result<Proof, ProverError>
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Clone + Sync,
+{
+    let prove = || AirProver::<A>::new(options, pub_inputs).prove(trace);
+    #[cfg(feature = "concurrent")]
+    {
+        thread_local! {
+            static SINGLE_THREAD: rayon::ThreadPool = build_pool(1);
+        }
+        SINGLE_THREAD.with(|pool| pool.install(prove))
+    }
+    #[cfg(not(feature = "concurrent"))]
+    {
+        prove()
+    }
+}
+
+/// Proof being generated on a background thread, see [`spawn_prove`]
+#[cfg(feature = "prover")]
+pub struct ProvingHandle {
+    handle: JoinHandle<Result<Proof, ProverError>>,
+}
+
+#[cfg(feature = "prover")]
+impl ProvingHandle {
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the proof; a panic of the prover is resumed on the calling thread
+    pub fn join(self) -> Result<Proof, ProverError> {
+        self.handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+/// Proves `trace` on a background thread, leaving the caller free while the prover runs.
+///
+/// With the `concurrent` feature the proof runs in a pool of `worker_threads` threads, or in
+/// rayon's global pool for 0, which includes the grinding nonce search: Winterfell spreads the
+/// candidate nonces over the pool it is called from. Pools are built once per thread count
+/// and shared by every proof asking for that many workers. Without the feature the proof
+/// runs on the one background thread.
+///
+/// With more than one worker the nonce is whichever one a thread finds first, so the proof
+/// bytes differ from run to run; one worker keeps them reproducible, see
+/// [`ProverConfig::proving_threads`](crate::config::ProverConfig::proving_threads).
+#[cfg(feature = "prover")]
+pub fn spawn_prove<A>(
+    trace: TraceTable<BaseElement>,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+    worker_threads: usize,
+) -> ProvingHandle
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Clone + Send + Sync + 'static,
+{
+    spawn_prove_trace::<A, _, DefaultRandomCoin<HashFn>>(trace, pub_inputs, options, worker_threads, None)
+}
+
+/// [`spawn_prove`] for traces of any type, e.g. a [`PooledTrace`](crate::pool::PooledTrace),
+/// drawing challenges from the random coin `C` and extending the trace into columns of
+/// `lde_pool` when one is given
+#[cfg(feature = "prover")]
+pub fn spawn_prove_trace<A, T, C>(
+    trace: T,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+    worker_threads: usize,
+    lde_pool: Option<Arc<TracePool>>,
+) -> ProvingHandle
+where
+    A: Air<BaseField = BaseElement> + 'static,
+    A::PublicInputs: Clone + Send + Sync + 'static,
+    T: Trace<BaseField = BaseElement> + Send + Sync + 'static,
+    C: RandomCoin<BaseField = BaseElement, Hasher = HashFn> + Send + Sync + 'static,
+{
+    let prove = move || {
+        let prover = AirProver::<A, T, C>::new(options, pub_inputs);
+        match lde_pool {
+            Some(pool) => prover.with_lde_pool(pool).prove(trace),
+            None => prover.prove(trace),
+        }
+    };
+    let handle = thread::spawn(move || {
+        #[cfg(feature = "concurrent")]
+        {
+            match shared_pool(worker_threads) {
+                Some(pool) => pool.install(prove),
+                None => prove(),
+            }
+        }
+        #[cfg(not(feature = "concurrent"))]
+        {
+            let _ = worker_threads;
+            prove()
+        }
+    });
+    ProvingHandle { handle }
+}
+
+#[cfg(feature = "concurrent")]
+fn build_pool(num_threads: usize) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new().num_threads(num_threads).build().expect("Proving thread pool can be created")
+}
+
+/// The pool of `num_threads` workers, built on first use; `None` for the global pool
+#[cfg(feature = "concurrent")]
+fn shared_pool(num_threads: usize) -> Option<std::sync::Arc<rayon::ThreadPool>> {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex, OnceLock},
+    };
+
+    static POOLS: OnceLock<Mutex<HashMap<usize, Arc<rayon::ThreadPool>>>> = OnceLock::new();
+    if num_threads == 0 {
+        return None;
+    }
+    let mut pools = POOLS.get_or_init(Mutex::default).lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Some(Arc::clone(pools.entry(num_threads).or_insert_with(|| Arc::new(build_pool(num_threads)))))
+}
+
+/// Verifies a proof for AIR `A` with the hash function and commitment scheme used by [`prove`]
+pub fn verify<A>(
+    proof: Proof,
+    pub_inputs: A::PublicInputs,
+    acceptable_options: &AcceptableOptions,
+) -> Result<(), VerifierError>
+where
+    A: Air<BaseField = BaseElement>,
+{
+    verify_with_coin::<A, DefaultRandomCoin<HashFn>>(proof, pub_inputs, acceptable_options)
+}
+
+/// [`verify`] for a proof whose challenges were drawn from the random coin `C`.
+///
+/// With the `concurrent` feature and more than one thread in the current rayon pool the
+/// queries of the proof are checked in parallel, which lowers the latency of a single
+/// verification, see [`verify_concurrently`](crate::queries::verify_concurrently). On one
+/// thread, and for proofs it does not cover, `winter-verifier` checks the proof as without
+/// the feature.
+pub fn verify_with_coin<A, C>(
+    proof: Proof,
+    pub_inputs: A::PublicInputs,
+    acceptable_options: &AcceptableOptions,
+) -> Result<(), VerifierError>
+where
+    A: Air<BaseField = BaseElement>,
+    C: RandomCoin<BaseField = BaseElement, Hasher = HashFn>,
+{
+    #[cfg(feature = "concurrent")]
+    if rayon::current_num_threads() > 1 && crate::queries::splits(&proof) {
+        return crate::queries::verify_concurrently::<A, C>(&proof, pub_inputs, acceptable_options);
+    }
+    winterfell::verify::<A, HashFn, C, MerkleTree<HashFn>>(proof, pub_inputs, acceptable_options)
+}
+
+/// Verifies a batch of proofs for AIR `A`, returning one result per proof in input order.
+///
+/// With the `concurrent` feature the proofs of a batch are verified on all cores, and the
+/// queries of each proof are checked in parallel as in [`verify_with_coin`]; rayon balances
+/// both levels over the same pool.
+pub fn verify_many<A>(
+    batch: Vec<(Proof, A::PublicInputs)>,
+    acceptable_options: &AcceptableOptions,
+) -> Vec<Result<(), VerifierError>>
+where
+    A: Air<BaseField = BaseElement>,
+    A::PublicInputs: Send,
+{
+    #[cfg(feature = "concurrent")]
+    {
+        use rayon::prelude::*;
+        batch
+            .into_par_iter()
+            .map(|(proof, pub_inputs)| verify::<A>(proof, pub_inputs, acceptable_options))
+            .collect()
+    }
+    #[cfg(not(feature = "concurrent"))]
+    {
+        batch
+            .into_iter()
+            .map(|(proof, pub_inputs)| verify::<A>(proof, pub_inputs, acceptable_options))
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    #[test]
+    fn test_verify_many_keeps_order() {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(1));
+        let xs = vec![BaseElement::new(2), BaseElement::new(4)];
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let inputs = |x: u64, y: u64| LinearRegressionInputs {
+            x_value: BaseElement::from(x),
+            predicted_y: BaseElement::from(y),
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+
+        let target = BaseElement::new(5);
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        let proof = prove::<LinearRegressionAir>(trace, inputs(5, 16), ProverConfig::default().to_proof_options()).unwrap();
+
+        let batch = vec![(proof.clone(), inputs(5, 16)), (proof.clone(), inputs(5, 17)), (proof, inputs(5, 16))];
+        let results = verify_many::<LinearRegressionAir>(batch, &default_acceptable_options());
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
+    #[cfg(feature = "concurrent")]
+    #[test]
+    fn test_pools_are_built_once() {
+        assert!(shared_pool(0).is_none());
+        let pool = shared_pool(3).unwrap();
+        assert_eq!(pool.current_num_threads(), 3);
+        assert!(std::sync::Arc::ptr_eq(&pool, &shared_pool(3).unwrap()));
+    }
+}
@@ -0,0 +1,48 @@
+//! Size limits for public inputs.
+//!
+//! Every public input element travels in calldata and is absorbed into the Fiat-Shamir seed,
+//! so a claim with a very long `to_elements()` vector costs the verifier twice. Claims are
+//! therefore capped at a configured number of elements, [`DEFAULT_MAX_PUBLIC_INPUTS`] unless
+//! a deployment sets its own in [`ProverConfig`](crate::config::ProverConfig) and
+//! [`VerificationPolicy`](crate::config::VerificationPolicy). At 16 bytes per element the
+//! default keeps the inputs at 32 KiB, which leaves room for a proof within the 128 KiB
+//! transaction size most EVM nodes relay.
+
+use core::fmt;
+
+/// Public input elements accepted by default
+pub const DEFAULT_MAX_PUBLIC_INPUTS: usize = 2048;
+
+/// Error returned for claims with more public input elements than allowed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TooManyPublicInputs {
+    pub count: usize,
+    pub max: usize,
+}
+
+impl fmt::Display for TooManyPublicInputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "claim has {} public input elements, the limit is {}", self.count, self.max)
+    }
+}
+
+impl std::error::Error for TooManyPublicInputs {}
+
+/// Checks a claim with `count` public input elements against the limit `max`
+pub fn check_count(count: usize, max: usize) -> Result<(), TooManyPublicInputs> {
+    if count > max { Err(TooManyPublicInputs { count, max }) } else { Ok(()) }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_limit() {
+        assert_eq!(check_count(2048, DEFAULT_MAX_PUBLIC_INPUTS), Ok(()));
+        assert_eq!(check_count(2050, 2048), Err(TooManyPublicInputs { count: 2050, max: 2048 }));
+
+        let err = check_count(2050, 2048).unwrap_err();
+        assert_eq!(err.to_string(), "claim has 2050 public input elements, the limit is 2048");
+    }
+}
@@ -0,0 +1,417 @@
+//! Distributional quality claims over committed residuals.
+//!
+//! [`QuantileAir`] proves that at least `min_percent` percent of the residuals hashed into a
+//! public Rescue digest (see [`residuals_digest`]) are below `epsilon` in absolute value, i.e.
+//! that the `min_percent`-th percentile of `|residual|` is smaller than `epsilon`. Unlike a
+//! mean-based metric this bounds the error of most predictions, whatever a few outliers do.
+//! Residuals are signed fixed-point values, usually `y - f(x)` quantized like the model.
+//!
+//! The trace follows [`AccuracyAir`](crate::accuracy::AccuracyAir): one [`CYCLE_LENGTH`]-row
+//! cycle per residual plus one that finishes the sponge. Each cycle splits its residual into a
+//! sign and a 64-bit magnitude and holds an indicator that is 1 exactly when the magnitude is
+//! below `epsilon`: the margin `epsilon - 1 - |r|` for residuals below it, and `|r| - epsilon`
+//! for the others, is range checked. A running count adds the indicator at the end of every
+//! residual cycle; where the residual phase ends, `100 * count - min_percent * num_samples`
+//! is range checked. No sorting is needed: the percentile only depends on that count.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::batch::field_column;
+#[cfg(feature = "prover")]
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{IntegerColumn, PhaseSelector, RangeCheck};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`QuantileAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "quantile/v1";
+
+/// Claims may cover up to `2^SAMPLE_BITS` residuals
+pub const SAMPLE_BITS: usize = 32;
+
+/// Sponge tag of [`residuals_digest`]; `crate::accuracy` uses 5 and 6, `crate::distinct` 7
+pub(crate) const RESIDUALS_TAG: u64 = 8;
+/// Residual magnitudes and margins are 64-bit
+const MAGNITUDE_BITS: usize = 64;
+/// `100 * num_samples` must fit into this many bits
+const SLACK_BITS: usize = 40;
+const DIGIT_BITS: usize = 2;
+
+/// Phases of a percentile trace: one cycle per residual, then the padding cycles
+const SAMPLES: usize = 0;
+const PADDING: usize = 1;
+
+// Trace layout: sponge state, residual, its sign, the below-epsilon indicator, the phase
+// flags, running count, magnitude, then the digits of the magnitude, the margin and the slack
+const SPONGE: usize = 0;
+const RESIDUAL: usize = SPONGE + STATE_WIDTH;
+const SIGN: usize = RESIDUAL + 1;
+const BELOW: usize = SIGN + 1;
+const PHASES_START: usize = BELOW + 1;
+const COUNT: usize = PHASES_START + 2;
+const MAGNITUDE: usize = COUNT + 1;
+const DIGITS_START: usize = MAGNITUDE + 1;
+
+struct Layout {
+    phases: PhaseSelector,
+    sponge: RescueGadget,
+    magnitude: IntegerColumn,
+    margin: RangeCheck,
+    slack: RangeCheck,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let magnitude = IntegerColumn::new(MAGNITUDE, DIGITS_START, MAGNITUDE_BITS, DIGIT_BITS);
+        let margin = RangeCheck::with_digit_bits(magnitude.next_column(), MAGNITUDE_BITS, DIGIT_BITS);
+        let slack = RangeCheck::with_digit_bits(margin.next_column(), SLACK_BITS, DIGIT_BITS);
+        let phases = PhaseSelector::new(PHASES_START, 2);
+        Self { phases, sponge: RescueGadget::new(SPONGE), magnitude, margin, slack }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.slack.next_column()
+    }
+}
+
+/// Number of trace columns
+pub fn trace_width() -> usize {
+    Layout::new().trace_width()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    let layout = Layout::new();
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..RESIDUAL)
+        .with_column("residual", RESIDUAL)
+        .with_column("sign", SIGN)
+        .with_column("below", BELOW)
+        .with_columns("phases", PHASES_START..COUNT)
+        .with_column("count", COUNT)
+        .with_column("magnitude", MAGNITUDE)
+        .with_columns("magnitude_digits", DIGITS_START..layout.magnitude.next_column())
+        .with_columns("margin_digits", layout.magnitude.next_column()..layout.margin.next_column())
+        .with_columns("slack_digits", layout.margin.next_column()..layout.slack.next_column())
+}
+
+/// Trace length for `num_samples` residuals
+pub fn trace_length(num_samples: usize) -> usize {
+    ((num_samples + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// Digest of fixed-point residuals, in sample order
+pub fn residuals_digest(residuals: &[i64]) -> BaseElement {
+    rescue::hash_elements(&field_column(residuals), RESIDUALS_TAG)
+}
+
+/// Number of residuals whose magnitude is below `epsilon`
+pub fn count_below(residuals: &[i64], epsilon: u64) -> usize {
+    residuals.iter().filter(|residual| residual.unsigned_abs() < epsilon).count()
+}
+
+/// Reason a set of residuals does not satisfy a percentile claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuantileError {
+    Empty,
+    TooManySamples(usize),
+    /// The percentage is not in `1..=100`
+    InvalidPercent(u64),
+    BelowThreshold { below: usize, num_samples: usize, min_percent: u64 },
+}
+
+impl fmt::Display for QuantileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantileError::Empty => write!(f, "no residuals"),
+            QuantileError::TooManySamples(count) => write!(f, "{count} residuals exceed 2^{SAMPLE_BITS}"),
+            QuantileError::InvalidPercent(percent) => write!(f, "percentage {percent}% is not in 1..=100"),
+            QuantileError::BelowThreshold { below, num_samples, min_percent } => {
+                write!(f, "{below} of {num_samples} residuals are below epsilon, less than {min_percent}%")
+            }
+        }
+    }
+}
+
+impl std::error::Error for QuantileError {}
+
+/// Public inputs: the residuals digest, their number, the bound and the percentage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuantileInputs {
+    pub digest: BaseElement,
+    pub num_samples: usize,
+    pub epsilon: u64,
+    pub min_percent: u64,
+}
+
+impl QuantileInputs {
+    /// Inputs of the claim that `min_percent` percent of `residuals` are below `epsilon`
+    pub fn for_residuals(residuals: &[i64], epsilon: u64, min_percent: u64) -> Self {
+        Self { digest: residuals_digest(residuals), num_samples: residuals.len(), epsilon, min_percent }
+    }
+}
+
+impl ToElements<BaseElement> for QuantileInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            self.digest,
+            BaseElement::from(self.num_samples as u64),
+            BaseElement::from(self.epsilon),
+            BaseElement::from(self.min_percent),
+        ]
+    }
+}
+
+/// AIR proving that enough committed residuals are below a public bound
+pub struct QuantileAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: QuantileInputs,
+}
+
+impl Air for QuantileAir {
+    type BaseField = BaseElement;
+    type PublicInputs = QuantileInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: QuantileInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new();
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert!(pub_inputs.num_samples > 0, "Percentile claims need at least one residual");
+        assert!(pub_inputs.num_samples as u64 >> SAMPLE_BITS == 0, "Claims cover at most 2^{SAMPLE_BITS} residuals");
+        assert_eq!(trace_length(pub_inputs.num_samples), trace_info.length());
+        assert!((1..=100).contains(&pub_inputs.min_percent), "Percentage must be in 1..=100");
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. Sponge absorbs the residual at the end of a cycle (degree 1)
+        // 3. Residual is constant within a cycle (degree 1)
+        // 4. Sign is boolean and residual = (1 - 2 * sign) * magnitude (degree 2)
+        // 5. Below is boolean (degree 2)
+        // 6. Magnitude matches its 64-bit digits
+        // 7. The margin is epsilon - 1 - magnitude when below and magnitude - epsilon
+        //    otherwise (degree 2)
+        // 8. The phase flags (degree 2)
+        // 9. The count adds below at the end of residual cycles (degree 2)
+        // 10. Where the residual phase ends, 100 * count - min_percent * num_samples is the
+        //     range checked slack (degree 2)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 1]);
+        degrees.extend(vec![TransitionConstraintDegree::new(2); 3]);
+        degrees.extend(layout.magnitude.constraint_degrees());
+        degrees.push(TransitionConstraintDegree::new(2));
+        degrees.extend(layout.margin.constraint_degrees());
+        degrees.extend(layout.phases.constraint_degrees());
+        degrees.push(periodic(2));
+        degrees.push(TransitionConstraintDegree::new(2));
+        degrees.extend(layout.slack.constraint_degrees());
+
+        QuantileAir {
+            context: AirContext::new(trace_info, degrees, STATE_WIDTH + 4, options),
+            layout,
+            inputs: pub_inputs,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[RESIDUAL]);
+        for i in 1..STATE_WIDTH {
+            rest[i] = last * (next[SPONGE + i] - current[SPONGE + i]);
+        }
+        rest[4] = mask * (next[RESIDUAL] - current[RESIDUAL]);
+
+        let (sign, below, magnitude) = (current[SIGN], current[BELOW], current[MAGNITUDE]);
+        rest[5] = sign * (sign - E::ONE);
+        rest[6] = current[RESIDUAL] - (E::ONE - sign.double()) * magnitude;
+        rest[7] = below * (below - E::ONE);
+
+        let rest = &mut rest[8..];
+        layout.magnitude.evaluate(current, rest);
+        let rest = &mut rest[layout.magnitude.num_constraints()..];
+
+        let epsilon = E::from(BaseElement::from(self.inputs.epsilon));
+        let above = magnitude - epsilon;
+        let margin = above + below * (epsilon.double() - E::ONE - magnitude.double());
+        rest[0] = layout.margin.recompose(current) - margin;
+        layout.margin.evaluate(current, &mut rest[1..]);
+        let rest = &mut rest[1 + layout.margin.num_constraints()..];
+
+        let phases = &layout.phases;
+        phases.evaluate(current, next, rest);
+        let rest = &mut rest[phases.num_constraints()..];
+        // one on the last residual row, where the phases only allow the padding phase to follow
+        let edge = phases.flag(current, SAMPLES) - phases.flag(next, SAMPLES);
+        rest[0] = next[COUNT] - current[COUNT] - phases.gate(current, &[SAMPLES], last * below);
+        let required = self.inputs.min_percent * self.inputs.num_samples as u64;
+        let scaled = E::from(100u32) * next[COUNT] - E::from(BaseElement::from(required));
+        rest[1] = edge * (scaled - layout.slack.recompose(current));
+        layout.slack.evaluate(current, &mut rest[2..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_samples = self.inputs.num_samples;
+        let mut assertions: Vec<_> = rescue::sponge_iv(num_samples, RESIDUALS_TAG)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| Assertion::single(SPONGE + i, 0, value))
+            .collect();
+        assertions.push(Assertion::single(SPONGE, (num_samples + 1) * CYCLE_LENGTH - 1, self.inputs.digest));
+        let phases = &self.layout.phases;
+        assertions.push(Assertion::single(phases.column(SAMPLES), num_samples * CYCLE_LENGTH - 1, BaseElement::ONE));
+        assertions.push(Assertion::single(phases.column(PADDING), num_samples * CYCLE_LENGTH, BaseElement::ONE));
+        assertions.push(Assertion::single(COUNT, 0, BaseElement::ZERO));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving `min_percent` percent of `residuals` are below `epsilon`
+#[cfg(feature = "prover")]
+pub fn build_quantile_trace(
+    residuals: &[i64],
+    epsilon: u64,
+    min_percent: u64,
+) -> Result<TraceTable<BaseElement>, QuantileError> {
+    let num_samples = residuals.len();
+    let &last_residual = residuals.last().ok_or(QuantileError::Empty)?;
+    if num_samples as u64 >> SAMPLE_BITS != 0 {
+        return Err(QuantileError::TooManySamples(num_samples));
+    }
+    if !(1..=100).contains(&min_percent) {
+        return Err(QuantileError::InvalidPercent(min_percent));
+    }
+    let below = count_below(residuals, epsilon);
+    let slack = (100 * below as u64)
+        .checked_sub(min_percent * num_samples as u64)
+        .ok_or(QuantileError::BelowThreshold { below, num_samples, min_percent })?;
+
+    // Padding cycles repeat a residual distinct from the last one, which keeps the residual
+    // column from being constant for a single sample
+    let padding = last_residual.wrapping_add(1);
+
+    let layout = Layout::new();
+    let length = trace_length(num_samples);
+    let mut columns = vec![vec![BaseElement::ZERO; length]; layout.trace_width()];
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut sponge_state = rescue::sponge_iv(num_samples, RESIDUALS_TAG);
+    let mut count = 0u64;
+
+    layout.slack.fill(&mut row, slack as i128).expect("Slack is at most 100 * num_samples");
+
+    for cycle in 0..length / CYCLE_LENGTH {
+        let residual = residuals.get(cycle).copied().unwrap_or(padding);
+        let magnitude = residual.unsigned_abs();
+        let is_below = magnitude < epsilon;
+        let margin = if is_below { epsilon - 1 - magnitude } else { magnitude - epsilon };
+        row[RESIDUAL] = to_field(residual as i128);
+        row[SIGN] = BaseElement::from((residual < 0) as u64);
+        row[BELOW] = BaseElement::from(is_below as u64);
+        layout.phases.fill(&mut row, if cycle < num_samples { SAMPLES } else { PADDING });
+        row[COUNT] = BaseElement::from(count);
+        layout.magnitude.fill(&mut row, magnitude).expect("Magnitudes are 64-bit");
+        layout.margin.fill(&mut row, margin as i128).expect("Margins are 64-bit");
+
+        for step in 0..CYCLE_LENGTH {
+            layout.sponge.write(&mut row, &sponge_state);
+            for (column, &value) in columns.iter_mut().zip(&row) {
+                column[cycle * CYCLE_LENGTH + step] = value;
+            }
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut sponge_state, step);
+            }
+        }
+        sponge_state[0] += row[RESIDUAL];
+        if cycle < num_samples && is_below {
+            count += 1;
+        }
+    }
+
+    let mut last_row: Vec<BaseElement> = columns.iter().map(|column| column[length - 1]).collect();
+    last_row[SIGN] = BaseElement::new(2);
+    last_row[BELOW] = BaseElement::new(2);
+    layout.phases.fill_exempt_row(&mut last_row, PADDING);
+    layout.magnitude.fill_exempt_row(&mut last_row, padding.unsigned_abs());
+    layout.margin.fill_exempt_row(&mut last_row);
+    layout.slack.fill_exempt_row(&mut last_row);
+    for (column, value) in columns.iter_mut().zip(last_row) {
+        column[length - 1] = value;
+    }
+
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_quantile_proof() {
+        // 5 of 8 residuals are below 3 in absolute value
+        let residuals = [-3, 1, 0, 5, -2, 7, 2, -1];
+        let inputs = QuantileInputs::for_residuals(&residuals, 3, 60);
+
+        let trace = build_quantile_trace(&residuals, 3, 60).unwrap();
+        crate::air_test!(QuantileAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<QuantileAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+
+        let options = default_acceptable_options();
+        let stricter = QuantileInputs { min_percent: 63, ..inputs };
+        assert!(verify::<QuantileAir>(proof.clone(), stricter, &options).is_err());
+        let tighter = QuantileInputs { epsilon: 2, ..inputs };
+        assert!(verify::<QuantileAir>(proof.clone(), tighter, &options).is_err());
+        let result = verify::<QuantileAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+
+        // a single extreme residual
+        let residuals = [i64::MIN];
+        let trace = build_quantile_trace(&residuals, u64::MAX, 100).unwrap();
+        let inputs = QuantileInputs::for_residuals(&residuals, u64::MAX, 100);
+        prove::<QuantileAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_insufficient_residuals() {
+        let residuals = [4, -4, 1];
+        assert_eq!(
+            build_quantile_trace(&residuals, 4, 34).unwrap_err(),
+            QuantileError::BelowThreshold { below: 1, num_samples: 3, min_percent: 34 }
+        );
+        assert_eq!(build_quantile_trace(&residuals, 5, 100).map(|_| ()), Ok(()));
+        assert_eq!(build_quantile_trace(&[], 5, 50).unwrap_err(), QuantileError::Empty);
+        assert_eq!(build_quantile_trace(&residuals, 5, 101).unwrap_err(), QuantileError::InvalidPercent(101));
+    }
+}
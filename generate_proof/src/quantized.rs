@@ -0,0 +1,283 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+    TransitionConstraintDegree, 
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+#[cfg(feature = "prover")]
+use crate::config::padded_trace_length;
+use crate::fixed_point::{align, product_shift, rescale_product, to_field, Scale};
+use crate::gadgets::RangeCheck;
+
+// Trace layout: slope, intercept, x, y, then the bits of the rounding remainder
+const SLOPE: usize = 0;
+const INTERCEPT: usize = 1;
+const X: usize = 2;
+const Y: usize = 3;
+const BITS_START: usize = 4;
+
+/// Quantization of every value taking part in `y = m * x + b`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegressionScales {
+    pub slope: Scale,
+    pub intercept: Scale,
+    pub x: Scale,
+    pub y: Scale,
+}
+
+impl RegressionScales {
+    /// Fractional bits of the exact product `m * x`, the scale the sum is formed in
+    fn product_bits(&self) -> u32 {
+        self.slope.frac_bits + self.x.frac_bits
+    }
+
+    /// Fixed-point scale of the exact product, with no zero point
+    fn product_scale(&self) -> Scale {
+        Scale::symmetric(self.product_bits())
+    }
+
+    /// Bits dropped when `m * x + b` is stored in the y scale
+    fn shift(&self) -> u32 {
+        product_shift(&self.slope, &self.x, &self.y)
+    }
+
+    fn remainder_range(&self) -> Option<RangeCheck> {
+        let shift = self.shift() as usize;
+        (shift > 0).then(|| RangeCheck::new(BITS_START, shift))
+    }
+
+    fn trace_width(&self) -> usize {
+        assert!(
+            self.intercept.frac_bits <= self.product_bits(),
+            "Intercept scale is finer than the product it is added to"
+        );
+        self.remainder_range().map_or(BITS_START, |range| range.next_column())
+    }
+
+    /// Computes the quantized prediction and its rounding remainder for a quantized `x`
+    pub fn predict(&self, slope: i128, intercept: i128, x: i128) -> (i128, i128) {
+        let product = self.product_scale();
+        let (mx, _) = rescale_product(slope, &self.slope, x, &self.x, &product);
+        let sum = mx + align(intercept, &self.intercept, &product);
+        rescale_product(sum, &product, 1, &Scale::symmetric(0), &self.y)
+    }
+}
+
+/// Public inputs for a regression whose operands each carry their own scale and zero point
+#[derive(Clone, Debug)]
+pub struct QuantizedRegressionInputs {
+    pub scales: RegressionScales,
+    pub x_value: BaseElement,              // Quantized x of the prediction
+    pub predicted_y: BaseElement,          // The claimed quantized y
+    pub sample_x_values: Vec<BaseElement>, // Quantized sample x values
+    pub sample_y_values: Vec<BaseElement>, // Quantized sample y values
+}
+
+impl ToElements<BaseElement> for QuantizedRegressionInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let scales = self.scales;
+        let mut elements = Vec::new();
+        for scale in [scales.slope, scales.intercept, scales.x, scales.y] {
+            elements.extend(scale.to_elements());
+        }
+        elements.extend([self.x_value, self.predicted_y]);
+        elements.extend(&self.sample_x_values);
+        elements.extend(&self.sample_y_values);
+        elements
+    }
+}
+
+/// AIR proving `y = m * x + b` where `m`, `b`, `x` and `y` are quantized independently.
+///
+/// With centered values `c(v) = v - zero_point(v)`, each row satisfies
+/// `c(m) * c(x) + c(b) * 2^(fm + fx - fb) = c(y) * 2^(fm + fx - fy) + r` with
+/// `0 <= r < 2^(fm + fx - fy)`, i.e. y is the exact result rounded down into its own scale.
+pub struct QuantizedRegressionAir {
+    context: AirContext<BaseElement>,
+    scales: RegressionScales,
+    x_value: BaseElement,
+    predicted_y: BaseElement,
+    sample_x_values: Vec<BaseElement>,
+    sample_y_values: Vec<BaseElement>,
+}
+
+impl Air for QuantizedRegressionAir {
+    type BaseField = BaseElement;
+    type PublicInputs = QuantizedRegressionInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: QuantizedRegressionInputs, options: ProofOptions) -> Self {
+        let scales = pub_inputs.scales;
+        assert_eq!(scales.trace_width(), trace_info.width());
+
+        let num_samples = pub_inputs.sample_x_values.len();
+        assert_eq!(num_samples, pub_inputs.sample_y_values.len(), "Sample arrays must have equal length");
+
+        // Constraints:
+        // 1. Mixed-scale linear relationship (degree 1, slope is a constant column)
+        // 2. Slope and intercept consistency (degree 1)
+        // 3. Booleanity of every remainder bit (degree 2)
+        let mut degrees = vec![TransitionConstraintDegree::new(1); 3];
+        if let Some(range) = scales.remainder_range() {
+            degrees.extend(range.constraint_degrees());
+        }
+
+        let num_assertions = 2 * num_samples + 2;
+
+        QuantizedRegressionAir {
+            context: AirContext::new(trace_info, degrees, num_assertions, options),
+            scales,
+            x_value: pub_inputs.x_value,
+            predicted_y: pub_inputs.predicted_y,
+            sample_x_values: pub_inputs.sample_x_values,
+            sample_y_values: pub_inputs.sample_y_values,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let scales = &self.scales;
+
+        let centered = |value: E, scale: &Scale| value - E::from(to_field(scale.zero_point));
+        let power = |bits: u32| E::from(to_field(1 << bits));
+
+        let remainder = match scales.remainder_range() {
+            Some(range) => {
+                range.evaluate(current, &mut result[3..]);
+                range.recompose(current)
+            }
+            None => E::ZERO,
+        };
+
+        let product = centered(current[SLOPE], &scales.slope) * centered(current[X], &scales.x);
+        let intercept = centered(current[INTERCEPT], &scales.intercept)
+            * power(scales.product_bits() - scales.intercept.frac_bits);
+        result[0] = product + intercept - centered(current[Y], &scales.y) * power(scales.shift()) - remainder;
+        result[1] = next[SLOPE] - current[SLOPE];
+        result[2] = next[INTERCEPT] - current[INTERCEPT];
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = Vec::new();
+
+        for (i, (&x, &y)) in self.sample_x_values.iter().zip(&self.sample_y_values).enumerate() {
+            assertions.push(Assertion::single(X, i, x));
+            assertions.push(Assertion::single(Y, i, y));
+        }
+
+        let prediction_step = self.sample_x_values.len();
+        assertions.push(Assertion::single(X, prediction_step, self.x_value));
+        assertions.push(Assertion::single(Y, prediction_step, self.predicted_y));
+
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace for a mixed-scale regression over quantized samples.
+///
+/// `slope`, `intercept` and the x values are quantized with the matching entry of `scales`;
+/// y values are derived with [`RegressionScales::predict`].
+#[cfg(feature = "prover")]
+pub fn build_quantized_trace(
+    slope: i128,
+    intercept: i128,
+    scales: &RegressionScales,
+    sample_x_values: &[i128],
+    target_x: i128,
+    min_trace_length: usize,
+) -> TraceTable<BaseElement> {
+    let num_samples = sample_x_values.len();
+    // Reserve at least one padding row: the last row is exempt from transition constraints
+    let trace_length = padded_trace_length(num_samples + 2, min_trace_length);
+    let trace_width = scales.trace_width();
+    let remainder_range = scales.remainder_range();
+
+    let mut columns = vec![vec![BaseElement::ZERO; trace_length]; trace_width];
+    let mut row = vec![BaseElement::ZERO; trace_width];
+
+    for i in 0..trace_length {
+        let x = sample_x_values.get(i).copied().unwrap_or(target_x);
+        let (y, remainder) = scales.predict(slope, intercept, x);
+
+        row[SLOPE] = to_field(slope);
+        row[INTERCEPT] = to_field(intercept);
+        row[X] = to_field(x);
+        row[Y] = to_field(y);
+
+        if let Some(range) = remainder_range {
+            if i == trace_length - 1 {
+                range.fill_exempt_row(&mut row);
+            } else {
+                range.fill(&mut row, remainder).expect("Floor remainder is always within 0..2^shift");
+            }
+        }
+
+        for (column, &value) in columns.iter_mut().zip(&row) {
+            column[i] = value;
+        }
+    }
+
+    TraceTable::init(columns)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_mixed_scale_regression_proof() {
+        // House prices in the hundreds of thousands, a slope with 20 fractional bits and a
+        // y scale with a zero point: no single global scale covers all of them
+        let scales = RegressionScales {
+            slope: Scale::symmetric(20),
+            intercept: Scale::symmetric(4),
+            x: Scale::fit_range(50.0, 400.0, 16),
+            y: Scale::asymmetric(6, -1000),
+        };
+
+        let slope = scales.slope.quantize(2150.375);
+        let intercept = scales.intercept.quantize(-12_500.5);
+        let sample_x: Vec<i128> = [62.5, 120.25, 250.0, 399.0].iter().map(|&x| scales.x.quantize(x)).collect();
+        let target_x = scales.x.quantize(180.75);
+
+        let (predicted_y, _) = scales.predict(slope, intercept, target_x);
+        assert!((scales.y.dequantize(predicted_y) - (2150.375 * 180.75 - 12_500.5)).abs() < 1.0 / 64.0);
+
+        let pub_inputs = QuantizedRegressionInputs {
+            scales,
+            x_value: to_field(target_x),
+            predicted_y: to_field(predicted_y),
+            sample_x_values: sample_x.iter().map(|&x| to_field(x)).collect(),
+            sample_y_values: sample_x.iter().map(|&x| to_field(scales.predict(slope, intercept, x).0)).collect(),
+        };
+
+        let trace = build_quantized_trace(slope, intercept, &scales, &sample_x, target_x, TraceInfo::MIN_TRACE_LENGTH);
+        crate::air_test!(QuantizedRegressionAir, pub_inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let options = ProverConfig::default().to_proof_options();
+        let proof = prove::<QuantizedRegressionAir>(trace, pub_inputs.clone(), options).unwrap();
+
+        let mut forged_inputs = pub_inputs.clone();
+        forged_inputs.predicted_y += BaseElement::ONE;
+        assert!(verify::<QuantizedRegressionAir>(proof.clone(), forged_inputs, &default_acceptable_options()).is_err());
+
+        let result = verify::<QuantizedRegressionAir>(proof, pub_inputs, &default_acceptable_options());
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+}
@@ -0,0 +1,653 @@
+//! Per-query checks of a proof.
+//!
+//! [`QueryContext::split`] splits a proof, once its transcript is replayed, into one
+//! [`QueryChunk`] per query: the trace row, the constraint composition row and the FRI layer
+//! rows at the query's position, each with its Merkle path. [`QueryChunk::check`] runs the
+//! checks `winter-verifier` makes for all queries at once on that query alone: the openings,
+//! the DEEP composition of the rows and its folding down to the FRI remainder. Optimistic
+//! settlement (see [`optimistic`](crate::optimistic)) disputes single chunks on-chain, and
+//! with the `concurrent` feature [`verify_concurrently`] checks a proof's chunks in parallel.
+
+use core::fmt;
+
+use winterfell::{
+    crypto::{BatchMerkleProof, Digest, ElementHasher, MerkleTree},
+    math::{fields::f128::BaseElement, FieldElement, StarkField},
+    Air, Proof,
+};
+
+use crate::abi::uint_word;
+use crate::diagnostics::OodCheck;
+use crate::prover::HashFn;
+use crate::transcript::Transcript;
+
+type Digest32 = <HashFn as winterfell::crypto::Hasher>::Digest;
+
+/// Reason a proof cannot be split into chunks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SplitError {
+    /// Rows hashed in several partitions have no single Merkle leaf per query
+    Partitioned,
+    /// The proof's openings do not parse or do not match its query positions
+    Malformed(String),
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitError::Partitioned => write!(f, "proofs hashing rows in partitions are not split into chunks"),
+            SplitError::Malformed(reason) => write!(f, "malformed proof: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SplitError {}
+
+fn malformed(err: impl fmt::Display) -> SplitError {
+    SplitError::Malformed(err.to_string())
+}
+
+/// A row of a committed table and its Merkle path, sibling of the leaf first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleOpening {
+    pub index: usize,
+    pub values: Vec<BaseElement>,
+    pub path: Vec<[u8; 32]>,
+}
+
+impl MerkleOpening {
+    /// Whether the path leads from the row to `root`
+    pub fn verify(&self, root: &[u8; 32]) -> bool {
+        let path: Vec<Digest32> = self.path.iter().copied().map(Digest32::new).collect();
+        let leaf = HashFn::hash_elements(&self.values);
+        !path.is_empty()
+            && self.index >> path.len() == 0
+            && MerkleTree::<HashFn>::verify(Digest32::new(*root), self.index, leaf, &path).is_ok()
+    }
+
+    fn write_into(&self, root: &[u8; 32], target: &mut Vec<u8>) {
+        target.extend_from_slice(root);
+        target.extend_from_slice(&uint_word(self.index as u128));
+        target.extend_from_slice(&uint_word(self.values.len() as u128));
+        self.values.iter().for_each(|value| target.extend_from_slice(&uint_word(value.as_int())));
+        target.extend_from_slice(&uint_word(self.path.len() as u128));
+        self.path.iter().for_each(|node| target.extend_from_slice(node));
+    }
+}
+
+/// Commitments of a proof, read from its transcript
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofRoots {
+    pub trace: [u8; 32],
+    pub constraints: [u8; 32],
+    /// One root per queried FRI layer, without the remainder's
+    pub fri_layers: Vec<[u8; 32]>,
+}
+
+/// Everything needed to check the Merkle openings of one query of a proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryChunk {
+    /// Position of the query in the low-degree extension domain
+    pub position: usize,
+    pub trace: MerkleOpening,
+    pub constraints: MerkleOpening,
+    /// Row of each FRI layer holding the query's folded position
+    pub fri_layers: Vec<MerkleOpening>,
+}
+
+impl QueryChunk {
+    /// Whether this chunk is the `query`-th of the proof `context` was read from: the
+    /// out-of-domain rows and the proof of work hold, every opening leads to its root, the
+    /// position is the query's, and the DEEP composition of the rows folds down to the remainder
+    pub fn verify(&self, query: usize, context: &QueryContext) -> bool {
+        context.ood_check.holds() && context.proof_of_work && self.check(query, context).is_ok()
+    }
+
+    /// The checks of [`verify`](Self::verify) that depend on the query, in the order they run
+    pub fn check(&self, query: usize, context: &QueryContext) -> Result<(), QueryFailure> {
+        let roots = &context.roots;
+        if context.positions.get(query) != Some(&self.position)
+            || self.trace.index != self.position
+            || self.constraints.index != self.position
+        {
+            return Err(QueryFailure::Position);
+        }
+        if !self.trace.verify(&roots.trace) {
+            return Err(QueryFailure::TraceOpening);
+        }
+        if !self.constraints.verify(&roots.constraints) {
+            return Err(QueryFailure::ConstraintOpening);
+        }
+        if self.fri_layers.len() != roots.fri_layers.len() {
+            return Err(QueryFailure::FriOpening(self.fri_layers.len().min(roots.fri_layers.len())));
+        }
+        let mut layers = self.fri_layers.iter().zip(&roots.fri_layers);
+        if let Some(layer) = layers.position(|(opening, root)| !opening.verify(root)) {
+            return Err(QueryFailure::FriOpening(layer));
+        }
+        context.fold(self)
+    }
+
+    /// ABI words of the position, the number of FRI layers, then each opening as its root,
+    /// index, value count, values, path length and path
+    pub fn to_bytes(&self, roots: &ProofRoots) -> Vec<u8> {
+        let mut bytes = [uint_word(self.position as u128), uint_word(self.fri_layers.len() as u128)].concat();
+        self.trace.write_into(&roots.trace, &mut bytes);
+        self.constraints.write_into(&roots.constraints, &mut bytes);
+        for (opening, root) in self.fri_layers.iter().zip(&roots.fri_layers) {
+            opening.write_into(root, &mut bytes);
+        }
+        bytes
+    }
+}
+
+/// The first check of [`QueryChunk::check`] a chunk fails
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryFailure {
+    /// The chunk or one of its trace and constraint openings is not at the query's position
+    Position,
+    /// The trace row does not lead to the trace root
+    TraceOpening,
+    /// The constraint composition row does not lead to the constraint root
+    ConstraintOpening,
+    /// The row of this FRI layer does not lead to its root, or the layer is missing
+    FriOpening(usize),
+    /// The trace and constraint rows do not fit the out-of-domain rows
+    DeepComposition,
+    /// The row of this FRI layer does not hold the value folded into it
+    Folding(usize),
+    /// A polynomial of this degree cannot be folded at this FRI layer
+    DegreeTruncation { degree: usize, layer: usize },
+    /// The remainder has more coefficients than a polynomial of this degree
+    RemainderDegree(usize),
+    /// The last folded value is not the remainder's evaluation
+    RemainderFolding,
+}
+
+/// The part of a proof's transcript a single query is checked against
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryContext {
+    pub roots: ProofRoots,
+    /// Query positions in query order
+    pub positions: Vec<usize>,
+    /// Out-of-domain point
+    pub z: BaseElement,
+    /// Trace rows at `z` and `z * g`
+    pub ood_trace: [Vec<BaseElement>; 2],
+    /// Constraint composition rows at `z` and `z * g`
+    pub ood_constraints: [Vec<BaseElement>; 2],
+    pub deep_trace_coefficients: Vec<BaseElement>,
+    pub deep_constraint_coefficients: Vec<BaseElement>,
+    /// Folding challenge of each queried FRI layer
+    pub fri_alphas: Vec<BaseElement>,
+    /// Coefficients of the FRI remainder, highest degree first
+    pub remainder: Vec<BaseElement>,
+    /// Whether the out-of-domain rows satisfy the constraints; every query fails if not
+    pub ood_check: OodCheck,
+    /// Whether the proof-of-work nonce meets the grinding factor; every query fails if not
+    pub proof_of_work: bool,
+    pub trace_length: usize,
+    pub lde_domain_size: usize,
+    pub domain_offset: BaseElement,
+    pub folding_factor: usize,
+}
+
+impl QueryContext {
+    fn domain(&self) -> Domain {
+        let generator = BaseElement::get_root_of_unity(self.lde_domain_size.ilog2());
+        let folding_root = BaseElement::get_root_of_unity(self.folding_factor.ilog2());
+        let half = BaseElement::new(BaseElement::MODULUS / 2 + 1);
+        Domain {
+            generator,
+            generator_inv: generator.exp_vartime(self.lde_domain_size as u128 - 1),
+            trace_generator: BaseElement::get_root_of_unity(self.trace_length.ilog2()),
+            folding_root_inv: folding_root.exp_vartime(self.folding_factor as u128 - 1),
+            folding_inv: half.exp_vartime(self.folding_factor.ilog2() as u128),
+        }
+    }
+
+    /// Numerator and denominator of the DEEP composition of a query's rows, as `winterfell`'s
+    /// `DeepComposer::compose_columns`
+    fn deep_composition(&self, domain: &Domain, chunk: &QueryChunk) -> Option<(BaseElement, BaseElement)> {
+        let x = self.domain_offset * domain.generator.exp_vartime(chunk.position as u128);
+        let (den_z, den_zg) = (x - self.z, x - self.z * domain.trace_generator);
+        let columns = [
+            (&chunk.trace.values, &self.ood_trace, &self.deep_trace_coefficients),
+            (&chunk.constraints.values, &self.ood_constraints, &self.deep_constraint_coefficients),
+        ];
+        let mut numerator = BaseElement::ZERO;
+        for (values, [at_z, at_zg], coefficients) in columns {
+            if values.len() != coefficients.len() || at_z.len() != values.len() || at_zg.len() != values.len() {
+                return None;
+            }
+            for (i, &value) in values.iter().enumerate() {
+                let (t_z, t_zg) = ((value - at_z[i]) * coefficients[i], (value - at_zg[i]) * coefficients[i]);
+                numerator += t_z * den_zg + t_zg * den_z;
+            }
+        }
+        let denominator = den_z * den_zg;
+        (denominator != BaseElement::ZERO).then_some((numerator, denominator))
+    }
+
+    /// Whether the DEEP composition of `chunk` folds through its FRI layer rows to the
+    /// remainder, as `winter_fri::FriVerifier::verify` does for every query at once
+    fn fold(&self, chunk: &QueryChunk) -> Result<(), QueryFailure> {
+        let domain = self.domain();
+        let deep = self.deep_composition(&domain, chunk).ok_or(QueryFailure::DeepComposition)?;
+        self.fold_from(&domain, chunk, deep, (deep.1 * self.domain_offset).inv())
+    }
+
+    /// [`fold`](Self::fold) of the DEEP composition `numerator / denominator` of `chunk`, given
+    /// `inverse` of `denominator * domain_offset`, so that queries can share one batch inversion
+    fn fold_from(
+        &self,
+        domain: &Domain,
+        chunk: &QueryChunk,
+        (numerator, denominator): (BaseElement, BaseElement),
+        inverse: BaseElement,
+    ) -> Result<(), QueryFailure> {
+        let folding = self.folding_factor;
+        if self.fri_alphas.len() != chunk.fri_layers.len() {
+            return Err(QueryFailure::FriOpening(self.fri_alphas.len().min(chunk.fri_layers.len())));
+        }
+        let (mut evaluation, offset_inv) = (numerator * inverse * self.domain_offset, inverse * denominator);
+        let (mut generator, mut generator_inv) = (domain.generator, domain.generator_inv);
+        let (mut position, mut domain_size, mut max_degree_plus_1) =
+            (chunk.position, self.lde_domain_size, self.trace_length);
+        for (layer, (opening, &alpha)) in chunk.fri_layers.iter().zip(&self.fri_alphas).enumerate() {
+            let row_length = domain_size / folding;
+            let folded = position % row_length;
+            let opened = opening.values.get(position / row_length);
+            if opening.index != folded || opening.values.len() != folding || opened != Some(&evaluation) {
+                return Err(QueryFailure::Folding(layer));
+            }
+            let xe_inv = generator_inv.exp_vartime(folded as u128) * offset_inv;
+            evaluation = fold_coset(&opening.values, alpha * xe_inv, domain.folding_root_inv) * domain.folding_inv;
+            if max_degree_plus_1 % folding != 0 {
+                return Err(QueryFailure::DegreeTruncation { degree: max_degree_plus_1 - 1, layer });
+            }
+            generator = generator.exp(folding as u128);
+            generator_inv = generator_inv.exp(folding as u128);
+            (position, domain_size, max_degree_plus_1) = (folded, row_length, max_degree_plus_1 / folding);
+        }
+        let x = self.domain_offset * generator.exp_vartime(position as u128);
+        if self.remainder.len() > max_degree_plus_1 {
+            return Err(QueryFailure::RemainderDegree(max_degree_plus_1 - 1));
+        }
+        let remainder = self.remainder.iter().fold(BaseElement::ZERO, |acc, &coefficient| acc * x + coefficient);
+        if remainder != evaluation {
+            return Err(QueryFailure::RemainderFolding);
+        }
+        Ok(())
+    }
+
+    /// Context and chunks of a proof from its replayed `transcript` and the [`OodCheck`] of
+    /// the replay, which the caller may have needed before the proof is split
+    pub fn split<A>(
+        proof: &Proof,
+        transcript: &Transcript<A>,
+        ood_check: OodCheck,
+    ) -> Result<(Self, Vec<QueryChunk>), SplitError>
+    where
+        A: Air<BaseField = BaseElement>,
+    {
+        let (context, tables) = Self::open(proof, transcript, ood_check)?;
+        let trace = tables.trace.into_openings()?;
+        let constraints = tables.constraints.into_openings()?;
+        let layers = tables.fri_layers.into_iter().map(OpenedTable::into_openings).collect::<Result<_, _>>()?;
+        let chunks = context.chunks(trace, constraints, layers)?;
+        Ok((context, chunks))
+    }
+
+    /// Context of a proof and the rows each of its tables opens, before they are split by query
+    fn open<A>(
+        proof: &Proof,
+        transcript: &Transcript<A>,
+        ood_check: OodCheck,
+    ) -> Result<(Self, OpenedTables), SplitError>
+    where
+        A: Air<BaseField = BaseElement>,
+    {
+        let air = &transcript.air;
+        let trace_width = air.trace_info().main_trace_width();
+        let num_columns = air.context().num_constraint_composition_columns();
+        let partitions = air.options().partition_options();
+        if partitions.num_partitions::<BaseElement>(trace_width) > 1
+            || partitions.num_partitions::<BaseElement>(num_columns) > 1
+            || proof.fri_proof.num_partitions() > 1
+        {
+            return Err(SplitError::Partitioned);
+        }
+        if !transcript.matches_openings(proof) {
+            return Err(malformed("query positions do not match the transcript"));
+        }
+        let positions = &transcript.positions;
+        let lde_domain_size = air.lde_domain_size();
+        let fri_options = air.options().to_fri_options();
+        let num_fri_layers = fri_options.num_fri_layers(lde_domain_size);
+
+        let (batch, table) = proof.trace_queries[0]
+            .clone()
+            .parse::<BaseElement, HashFn, MerkleTree<HashFn>>(lde_domain_size, positions.len(), trace_width)
+            .map_err(malformed)?;
+        let rows = table.rows().map(<[_]>::to_vec).collect();
+        let trace = OpenedTable { indexes: positions.clone(), rows, batch };
+        let (batch, table) = proof
+            .constraint_queries
+            .clone()
+            .parse::<BaseElement, HashFn, MerkleTree<HashFn>>(lde_domain_size, positions.len(), num_columns)
+            .map_err(malformed)?;
+        let rows = table.rows().map(<[_]>::to_vec).collect();
+        let constraints = OpenedTable { indexes: positions.clone(), rows, batch };
+
+        let folding = fri_options.folding_factor();
+        let (layer_values, layer_proofs) = proof
+            .fri_proof
+            .clone()
+            .parse_layers::<BaseElement, HashFn, MerkleTree<HashFn>>(lde_domain_size, folding)
+            .map_err(malformed)?;
+        let (mut folded, mut domain_size) = (positions.clone(), lde_domain_size);
+        let mut fri_layers = Vec::with_capacity(layer_values.len());
+        for (values, batch) in layer_values.into_iter().zip(layer_proofs) {
+            domain_size /= folding;
+            // first occurrences in order, as `winter_fri::folding::fold_positions`
+            let mut indexes: Vec<usize> = Vec::with_capacity(folded.len());
+            for index in folded.iter().map(|position| position % domain_size) {
+                if !indexes.contains(&index) {
+                    indexes.push(index);
+                }
+            }
+            let rows = values.chunks(folding).map(<[_]>::to_vec).collect();
+            fri_layers.push(OpenedTable { indexes: indexes.clone(), rows, batch });
+            folded = indexes;
+        }
+
+        let roots = ProofRoots {
+            trace: transcript.trace_root.as_bytes(),
+            constraints: transcript.constraint_root.as_bytes(),
+            fri_layers: transcript.fri_roots[..num_fri_layers].iter().map(Digest::as_bytes).collect(),
+        };
+        let context = QueryContext {
+            roots,
+            positions: transcript.positions.clone(),
+            z: transcript.z,
+            ood_trace: [transcript.ood_trace.current().to_vec(), transcript.ood_trace.next().to_vec()],
+            ood_constraints: [transcript.ood_composition_current.clone(), transcript.ood_composition_next.clone()],
+            deep_trace_coefficients: transcript.deep_coefficients.trace.clone(),
+            deep_constraint_coefficients: transcript.deep_coefficients.constraints.clone(),
+            fri_alphas: transcript.fri_alphas[..num_fri_layers].to_vec(),
+            remainder: proof.fri_proof.parse_remainder().map_err(malformed)?,
+            ood_check,
+            proof_of_work: transcript.pow_leading_zeros >= air.options().grinding_factor(),
+            trace_length: air.trace_length(),
+            lde_domain_size,
+            domain_offset: air.domain_offset(),
+            folding_factor: folding,
+        };
+        Ok((context, OpenedTables { trace, constraints, fri_layers }))
+    }
+
+    /// One chunk per query position from the openings of each table
+    fn chunks(
+        &self,
+        trace: Vec<MerkleOpening>,
+        constraints: Vec<MerkleOpening>,
+        layers: Vec<Vec<MerkleOpening>>,
+    ) -> Result<Vec<QueryChunk>, SplitError> {
+        self.positions
+            .iter()
+            .zip(trace.into_iter().zip(constraints))
+            .map(|(&position, (trace, constraints))| {
+                let mut domain_size = self.lde_domain_size;
+                let fri_layers = layers.iter().map(|layer| {
+                    domain_size /= self.folding_factor;
+                    layer.iter().find(|opening| opening.index == position % domain_size).cloned()
+                });
+                let fri_layers = fri_layers.collect::<Option<Vec<_>>>()?;
+                Some(QueryChunk { position, trace, constraints, fri_layers })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| malformed("a FRI layer is not opened at a folded query position"))
+    }
+}
+
+/// Roots of unity of a [`QueryContext`] and their inverses, which every query's checks share;
+/// the inverses are powers of the roots, so they cost no field inversion
+struct Domain {
+    generator: BaseElement,
+    generator_inv: BaseElement,
+    trace_generator: BaseElement,
+    folding_root_inv: BaseElement,
+    /// Inverse of the folding factor, a power of one half
+    folding_inv: BaseElement,
+}
+
+/// Trace, constraint and FRI layer tables of a proof as [`QueryContext::open`] parses them
+struct OpenedTables {
+    trace: OpenedTable,
+    constraints: OpenedTable,
+    fri_layers: Vec<OpenedTable>,
+}
+
+/// Rows a table opens at its query indexes, with the batch proof of them against its root
+struct OpenedTable {
+    indexes: Vec<usize>,
+    rows: Vec<Vec<BaseElement>>,
+    batch: BatchMerkleProof<HashFn>,
+}
+
+impl OpenedTable {
+    /// Openings with a path each, so that every query can be checked on its own
+    fn into_openings(self) -> Result<Vec<MerkleOpening>, SplitError> {
+        let leaves: Vec<Digest32> = self.rows.iter().map(|row| HashFn::hash_elements(row)).collect();
+        let paths = self.batch.into_openings(&leaves, &self.indexes).map_err(malformed)?;
+        let openings = self.rows.into_iter().zip(&self.indexes).zip(paths).map(|((values, &index), (_, path))| {
+            MerkleOpening { index, values, path: path.iter().map(Digest::as_bytes).collect() }
+        });
+        Ok(openings.collect())
+    }
+
+    /// Openings without paths, for a verifier that checks the batch proof of the table instead
+    #[cfg(feature = "concurrent")]
+    fn unproven_openings(&self) -> Vec<MerkleOpening> {
+        let openings = self.rows.iter().zip(&self.indexes);
+        openings.map(|(values, &index)| MerkleOpening { index, values: values.clone(), path: vec![] }).collect()
+    }
+
+    /// Whether the batch proof opens the rows against `root`; shared nodes are hashed once, where
+    /// the paths of [`into_openings`](Self::into_openings) would hash them once per query
+    #[cfg(feature = "concurrent")]
+    fn holds(&self, root: &[u8; 32]) -> bool {
+        use rayon::prelude::*;
+        let leaves: Vec<Digest32> = self.rows.par_iter().map(|row| HashFn::hash_elements(row)).collect();
+        MerkleTree::<HashFn>::verify_batch(&Digest32::new(*root), &self.indexes, &leaves, &self.batch).is_ok()
+    }
+}
+
+/// `values.len()` times the value at `alpha` of the polynomial of degree below `values.len()`
+/// that takes `values[i]` at `xe * ω^i`, where `step` is `alpha / xe` and `root_inv` the inverse
+/// of the root of unity `ω` of that order. It is an inverse DFT of the values, so it needs no
+/// inversion per point as interpolation does
+fn fold_coset(values: &[BaseElement], step: BaseElement, root_inv: BaseElement) -> BaseElement {
+    let (mut folded, mut step_power, mut root_power) = (BaseElement::ZERO, BaseElement::ONE, BaseElement::ONE);
+    for _ in 0..values.len() {
+        // `values.len()` times the j-th coefficient of the polynomial in `x / xe`
+        let mut twiddle = BaseElement::ONE;
+        let mut coefficient = BaseElement::ZERO;
+        for &value in values {
+            coefficient += value * twiddle;
+            twiddle *= root_power;
+        }
+        folded += coefficient * step_power;
+        step_power *= step;
+        root_power *= root_inv;
+    }
+    folded
+}
+
+/// Whether [`verify_concurrently`] covers `proof`: base field rows hashed whole, without an
+/// auxiliary trace segment
+#[cfg(feature = "concurrent")]
+pub fn splits(proof: &Proof) -> bool {
+    let options = proof.options();
+    options.field_extension() == winterfell::FieldExtension::None
+        && !proof.trace_info().is_multi_segment()
+        && proof.fri_proof.num_partitions() <= 1
+        // a single partition keeps every column of a row together, however many there are
+        && options.partition_options().partition_size::<BaseElement>(usize::MAX) == usize::MAX
+}
+
+/// [`verify_with_coin`](crate::prover::verify_with_coin) of a proof that [`splits`], with its
+/// queries checked in parallel in the current rayon pool: the batch Merkle proof of each table
+/// next to the DEEP composition and FRI folding of each query. The transcript replay and the
+/// out-of-domain check stay sequential. Errors are those `winter-verifier` reports for the same
+/// failure; a commitment mismatch decides before a folding failure, and of several failing
+/// queries the first in query order does.
+#[cfg(feature = "concurrent")]
+pub fn verify_concurrently<A, C>(
+    proof: &Proof,
+    pub_inputs: A::PublicInputs,
+    acceptable_options: &winterfell::AcceptableOptions,
+) -> Result<(), winterfell::VerifierError>
+where
+    A: Air<BaseField = BaseElement>,
+    C: winterfell::crypto::RandomCoin<BaseField = BaseElement, Hasher = HashFn>,
+{
+    use rayon::prelude::*;
+    use winterfell::math::batch_inversion;
+    use winterfell::VerifierError;
+
+    let deserialization = |err: &dyn fmt::Display| VerifierError::ProofDeserializationError(err.to_string());
+    acceptable_options.validate::<HashFn>(proof)?;
+    let transcript = Transcript::<A>::replay::<C>(proof, pub_inputs).map_err(|err| deserialization(&err))?;
+    let (_, ood_check) = crate::diagnostics::ood_check(&transcript);
+    if !ood_check.holds() {
+        return Err(VerifierError::InconsistentOodConstraintEvaluations);
+    }
+    if transcript.pow_leading_zeros < transcript.air.options().grinding_factor() {
+        return Err(VerifierError::QuerySeedProofOfWorkVerificationFailed);
+    }
+    let (context, tables) =
+        QueryContext::open(proof, &transcript, ood_check).map_err(|err| deserialization(&err))?;
+
+    // each table's batch proof as a whole, next to the DEEP composition and folding of each query
+    let layers = tables.fri_layers.iter().map(OpenedTable::unproven_openings).collect();
+    let chunks = context
+        .chunks(tables.trace.unproven_openings(), tables.constraints.unproven_openings(), layers)
+        .map_err(|err| deserialization(&err))?;
+    let (roots, domain, offset) = (&context.roots, context.domain(), context.domain_offset);
+    let mut commitments = vec![
+        (&tables.trace, &roots.trace, QueryFailure::TraceOpening),
+        (&tables.constraints, &roots.constraints, QueryFailure::ConstraintOpening),
+    ];
+    let layers = tables.fri_layers.iter().zip(&roots.fri_layers).enumerate();
+    commitments.extend(layers.map(|(layer, (table, root))| (table, root, QueryFailure::FriOpening(layer))));
+    let (openings, folds): (Vec<bool>, Vec<Result<(), QueryFailure>>) = rayon::join(
+        || commitments.par_iter().map(|(table, root, _)| table.holds(root)).collect(),
+        || {
+            let deep: Vec<_> = chunks.par_iter().map(|chunk| context.deep_composition(&domain, chunk)).collect();
+            let denominators = deep.iter().map(|deep| deep.map_or(BaseElement::ONE, |(_, den)| den * offset));
+            let inverses = batch_inversion(&denominators.collect::<Vec<_>>());
+            let folds = chunks.par_iter().zip(deep).zip(inverses).map(|((chunk, deep), inverse)| {
+                let deep = deep.ok_or(QueryFailure::DeepComposition)?;
+                context.fold_from(&domain, chunk, deep, inverse)
+            });
+            folds.collect()
+        },
+    );
+    let mismatch = commitments.into_iter().zip(openings).find(|(_, holds)| !holds);
+    let result = match mismatch {
+        Some(((_, _, failure), _)) => Err(failure),
+        None => folds.into_iter().collect(),
+    };
+    result.map_err(|failure| to_verifier_error(failure, context.folding_factor))
+}
+
+/// Error `winter-verifier` reports for the failure of a query
+#[cfg(feature = "concurrent")]
+fn to_verifier_error(failure: QueryFailure, folding_factor: usize) -> winterfell::VerifierError {
+    use winter_fri::VerifierError as FriError;
+    use winterfell::VerifierError;
+
+    match failure {
+        QueryFailure::Position => {
+            VerifierError::ProofDeserializationError("query positions do not match the openings".into())
+        }
+        QueryFailure::TraceOpening => VerifierError::TraceQueryDoesNotMatchCommitment,
+        QueryFailure::ConstraintOpening => VerifierError::ConstraintQueryDoesNotMatchCommitment,
+        QueryFailure::FriOpening(_) => VerifierError::FriVerificationFailed(FriError::LayerCommitmentMismatch),
+        QueryFailure::DeepComposition => VerifierError::FriVerificationFailed(FriError::InvalidLayerFolding(0)),
+        QueryFailure::Folding(layer) => VerifierError::FriVerificationFailed(FriError::InvalidLayerFolding(layer)),
+        QueryFailure::DegreeTruncation { degree, layer } => {
+            VerifierError::FriVerificationFailed(FriError::DegreeTruncation(degree, folding_factor, layer))
+        }
+        QueryFailure::RemainderDegree(degree) => {
+            VerifierError::FriVerificationFailed(FriError::RemainderDegreeMismatch(degree))
+        }
+        QueryFailure::RemainderFolding => VerifierError::FriVerificationFailed(FriError::InvalidRemainderFolding),
+    }
+}
+
+#[cfg(all(test, feature = "concurrent"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::prove;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+    use winterfell::crypto::DefaultRandomCoin;
+    use winter_prover::Serializable;
+    use winterfell::{TraceInfo, VerifierError};
+
+    type Coin = DefaultRandomCoin<HashFn>;
+
+    fn inputs(num_samples: u64, predicted_y: u64) -> LinearRegressionInputs {
+        let xs: Vec<BaseElement> = (1..=num_samples).map(BaseElement::from).collect();
+        LinearRegressionInputs {
+            x_value: BaseElement::from(5u8),
+            predicted_y: BaseElement::from(predicted_y),
+            sample_y_values: xs.iter().map(|&x| BaseElement::from(3u8) * x + BaseElement::ONE).collect(),
+            sample_x_values: xs,
+        }
+    }
+
+    fn sequential(proof: Proof, pub_inputs: LinearRegressionInputs) -> Result<(), VerifierError> {
+        let acceptable = default_acceptable_options();
+        winterfell::verify::<LinearRegressionAir, HashFn, Coin, MerkleTree<HashFn>>(proof, pub_inputs, &acceptable)
+    }
+
+    #[test]
+    fn test_concurrent_verification_matches_winter_verifier() {
+        // enough samples for the proof to have a FRI layer besides the remainder
+        let pub_inputs = inputs(64, 16);
+        let (slope, intercept, target) = (BaseElement::from(3u8), BaseElement::ONE, pub_inputs.x_value);
+        let (xs, ys) = (&pub_inputs.sample_x_values, &pub_inputs.sample_y_values);
+        let trace = build_linear_regression_trace(slope, intercept, xs, ys, target, TraceInfo::MIN_TRACE_LENGTH);
+        let proof = prove::<LinearRegressionAir>(trace, pub_inputs.clone(), ProverConfig::default().to_proof_options())
+            .unwrap();
+        assert!(splits(&proof));
+        let acceptable = default_acceptable_options();
+        let concurrent = |proof: &Proof, pub_inputs| {
+            verify_concurrently::<LinearRegressionAir, Coin>(proof, pub_inputs, &acceptable).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(concurrent(&proof, pub_inputs.clone()), Ok(()));
+        assert_eq!(
+            concurrent(&proof, inputs(64, 17)),
+            Err(VerifierError::InconsistentOodConstraintEvaluations.to_string())
+        );
+        // both verifiers reject a flipped byte after the context, or neither; a changed
+        // context changes the AIR, which asserts its trace layout
+        let (bytes, mut parsed) = (proof.to_bytes(), 0);
+        for offset in (proof.context.to_bytes().len()..bytes.len()).step_by(41) {
+            let mut tampered = bytes.clone();
+            tampered[offset] ^= 1;
+            let Ok(tampered) = Proof::from_bytes(&tampered) else {
+                continue;
+            };
+            let expected = sequential(tampered.clone(), pub_inputs.clone()).is_ok();
+            assert_eq!(concurrent(&tampered, pub_inputs.clone()).is_ok(), expected, "byte {offset}");
+            parsed += 1;
+        }
+        assert!(parsed > 100, "{parsed} tampered proofs parsed");
+    }
+}
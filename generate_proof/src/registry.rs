@@ -0,0 +1,331 @@
+//! Local registry of model commitments, mirrored by the `ModelRegistry` companion contract.
+//!
+//! A model keeps a stable [`ModelId`] for its whole life while the commitment to its private
+//! parameters can be rotated (e.g. after retraining) or the model revoked altogether. Every
+//! state change has a matching calldata builder so the on-chain registry can be kept in sync.
+
+use core::fmt;
+use std::{fs, io, path::Path};
+
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+use crate::abi::{encode_call, from_hex, keccak256, to_hex};
+use crate::domain::DomainTag;
+use crate::envelope::ProofEnvelope;
+
+const COMMITMENT_DOMAIN: &[u8] = b"stark-framework/model-commitment/v1";
+
+/// Stable identifier of a model: `keccak256(name)`, so contracts can derive it as well
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ModelId(pub [u8; 32]);
+
+impl ModelId {
+    pub fn from_name(name: &str) -> Self {
+        ModelId(keccak256(name.as_bytes()))
+    }
+}
+
+impl fmt::Display for ModelId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+/// Hiding commitment to a model's private parameters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ModelCommitment(pub [u8; 32]);
+
+impl ModelCommitment {
+    /// Commits to `parameters` (e.g. slope and intercept) of deployment `domain` under a
+    /// secret `salt`
+    pub fn new(domain: &DomainTag, parameters: &[BaseElement], salt: &[u8; 32]) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(COMMITMENT_DOMAIN);
+        domain.absorb_into(&mut hasher);
+        hasher.update(salt);
+        for parameter in parameters {
+            hasher.update(&parameter.as_int().to_le_bytes());
+        }
+        ModelCommitment(*hasher.finalize().as_bytes())
+    }
+}
+
+impl fmt::Display for ModelCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+/// Lifecycle state of a registered model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelStatus {
+    Active,
+    Revoked,
+}
+
+/// A registered model and every commitment it has used, oldest first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub id: ModelId,
+    pub name: String,
+    pub status: ModelStatus,
+    pub commitments: Vec<ModelCommitment>,
+}
+
+impl RegistryEntry {
+    /// The commitment proofs for this model must currently use
+    pub fn current_commitment(&self) -> ModelCommitment {
+        *self.commitments.last().expect("Registry entries always hold a commitment")
+    }
+
+    /// Version of the current commitment, starting at 1 on registration
+    pub fn version(&self) -> usize {
+        self.commitments.len()
+    }
+}
+
+/// Errors returned by [`ModelRegistry`] operations
+#[derive(Debug)]
+pub enum RegistryError {
+    InvalidName(String),
+    AlreadyRegistered(ModelId),
+    UnknownModel(ModelId),
+    Revoked(ModelId),
+    CommitmentMismatch { expected: ModelCommitment, actual: ModelCommitment },
+    Malformed(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::InvalidName(name) => write!(f, "invalid model name {name:?}"),
+            RegistryError::AlreadyRegistered(id) => write!(f, "model {id} is already registered"),
+            RegistryError::UnknownModel(id) => write!(f, "model {id} is not registered"),
+            RegistryError::Revoked(id) => write!(f, "model {id} has been revoked"),
+            RegistryError::CommitmentMismatch { expected, actual } => {
+                write!(f, "model commitment {actual} does not match registered {expected}")
+            }
+            RegistryError::Malformed(line) => write!(f, "malformed registry line {line:?}"),
+            RegistryError::Io(err) => write!(f, "registry I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl From<io::Error> for RegistryError {
+    fn from(err: io::Error) -> Self {
+        RegistryError::Io(err)
+    }
+}
+
+/// Local store of model commitments.
+///
+/// Persisted as one line per model: `<id> <status> <commitment>[,<commitment>...] <name>`.
+#[derive(Clone, Debug, Default)]
+pub struct ModelRegistry {
+    entries: Vec<RegistryEntry>,
+}
+
+impl ModelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new model; names must be non-empty and free of whitespace
+    pub fn create(&mut self, name: &str, commitment: ModelCommitment) -> Result<ModelId, RegistryError> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(RegistryError::InvalidName(name.to_string()));
+        }
+        let id = ModelId::from_name(name);
+        if self.get(&id).is_some() {
+            return Err(RegistryError::AlreadyRegistered(id));
+        }
+        self.entries.push(RegistryEntry {
+            id,
+            name: name.to_string(),
+            status: ModelStatus::Active,
+            commitments: vec![commitment],
+        });
+        Ok(id)
+    }
+
+    /// Replaces the current commitment of an active model
+    pub fn rotate(&mut self, id: &ModelId, commitment: ModelCommitment) -> Result<(), RegistryError> {
+        let entry = self.active_entry_mut(id)?;
+        entry.commitments.push(commitment);
+        Ok(())
+    }
+
+    /// Permanently revokes a model; proofs referencing it are rejected afterwards
+    pub fn revoke(&mut self, id: &ModelId) -> Result<(), RegistryError> {
+        let entry = self.active_entry_mut(id)?;
+        entry.status = ModelStatus::Revoked;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &ModelId) -> Option<&RegistryEntry> {
+        self.entries.iter().find(|entry| entry.id == *id)
+    }
+
+    pub fn entries(&self) -> &[RegistryEntry] {
+        &self.entries
+    }
+
+    /// Checks that an envelope references an active model with its current commitment
+    pub fn check_envelope(&self, envelope: &ProofEnvelope) -> Result<(), RegistryError> {
+        let entry = self.get(&envelope.model_id).ok_or(RegistryError::UnknownModel(envelope.model_id))?;
+        if entry.status == ModelStatus::Revoked {
+            return Err(RegistryError::Revoked(entry.id));
+        }
+        if entry.current_commitment() != envelope.model_commitment {
+            return Err(RegistryError::CommitmentMismatch {
+                expected: entry.current_commitment(),
+                actual: envelope.model_commitment,
+            });
+        }
+        Ok(())
+    }
+
+    // CALLDATA
+    // --------------------------------------------------------------------------------------------
+
+    /// Calldata for `ModelRegistry.registerModel(bytes32 modelId, bytes32 commitment)`
+    pub fn register_calldata(&self, id: &ModelId) -> Result<Vec<u8>, RegistryError> {
+        let entry = self.get(id).ok_or(RegistryError::UnknownModel(*id))?;
+        Ok(encode_call("registerModel(bytes32,bytes32)", &[id.0, entry.commitments[0].0]))
+    }
+
+    /// Calldata for `ModelRegistry.rotateModel(bytes32 modelId, bytes32 commitment)`
+    pub fn rotate_calldata(&self, id: &ModelId) -> Result<Vec<u8>, RegistryError> {
+        let entry = self.get(id).ok_or(RegistryError::UnknownModel(*id))?;
+        Ok(encode_call("rotateModel(bytes32,bytes32)", &[id.0, entry.current_commitment().0]))
+    }
+
+    /// Calldata for `ModelRegistry.revokeModel(bytes32 modelId)`
+    pub fn revoke_calldata(&self, id: &ModelId) -> Result<Vec<u8>, RegistryError> {
+        self.get(id).ok_or(RegistryError::UnknownModel(*id))?;
+        Ok(encode_call("revokeModel(bytes32)", &[id.0]))
+    }
+
+    // PERSISTENCE
+    // --------------------------------------------------------------------------------------------
+
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        let contents = fs::read_to_string(path)?;
+        let mut registry = Self::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            registry.entries.push(parse_entry(line)?);
+        }
+        Ok(registry)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RegistryError> {
+        let mut contents = String::new();
+        for entry in &self.entries {
+            let status = match entry.status {
+                ModelStatus::Active => "active",
+                ModelStatus::Revoked => "revoked",
+            };
+            let commitments: Vec<String> = entry.commitments.iter().map(ToString::to_string).collect();
+            contents.push_str(&format!("{} {} {} {}\n", entry.id, status, commitments.join(","), entry.name));
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    fn active_entry_mut(&mut self, id: &ModelId) -> Result<&mut RegistryEntry, RegistryError> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.id == *id)
+            .ok_or(RegistryError::UnknownModel(*id))?;
+        if entry.status == ModelStatus::Revoked {
+            return Err(RegistryError::Revoked(*id));
+        }
+        Ok(entry)
+    }
+}
+
+fn parse_entry(line: &str) -> Result<RegistryEntry, RegistryError> {
+    let malformed = || RegistryError::Malformed(line.to_string());
+    let mut parts = line.split_whitespace();
+    let (Some(id), Some(status), Some(commitments), Some(name), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(malformed());
+    };
+
+    let status = match status {
+        "active" => ModelStatus::Active,
+        "revoked" => ModelStatus::Revoked,
+        _ => return Err(malformed()),
+    };
+    let commitments = commitments
+        .split(',')
+        .map(|hex| parse_bytes32(hex).map(ModelCommitment))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(malformed)?;
+    let id = parse_bytes32(id).map(ModelId).ok_or_else(malformed)?;
+    if id != ModelId::from_name(name) {
+        return Err(malformed());
+    }
+
+    Ok(RegistryEntry { id, name: name.to_string(), status, commitments })
+}
+
+fn parse_bytes32(hex: &str) -> Option<[u8; 32]> {
+    from_hex(hex)?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::selector;
+    use crate::fingerprint::ConstraintFingerprint;
+    use winterfell::Proof;
+
+    #[test]
+    fn test_model_lifecycle() {
+        let params = [BaseElement::new(3), BaseElement::new(7)];
+        let domain = DomainTag::default();
+        let first = ModelCommitment::new(&domain, &params, &[1u8; 32]);
+        let second = ModelCommitment::new(&domain, &params, &[2u8; 32]);
+        assert_ne!(first, second);
+        assert_ne!(first, ModelCommitment::new(&DomainTag::new("other-app").unwrap(), &params, &[1u8; 32]));
+
+        let mut registry = ModelRegistry::new();
+        let id = registry.create("house-prices", first).unwrap();
+        assert!(matches!(registry.create("house-prices", first), Err(RegistryError::AlreadyRegistered(_))));
+
+        let mut envelope = ProofEnvelope::new(
+            id,
+            first,
+            ConstraintFingerprint([0u8; 32]),
+            vec![BaseElement::new(6)],
+            Proof::new_dummy(),
+        );
+        registry.check_envelope(&envelope).unwrap();
+
+        registry.rotate(&id, second).unwrap();
+        assert_eq!(registry.get(&id).unwrap().version(), 2);
+        assert!(matches!(registry.check_envelope(&envelope), Err(RegistryError::CommitmentMismatch { .. })));
+        envelope.model_commitment = second;
+        registry.check_envelope(&envelope).unwrap();
+
+        let calldata = registry.rotate_calldata(&id).unwrap();
+        assert_eq!(calldata[..4], selector("rotateModel(bytes32,bytes32)"));
+        assert_eq!(calldata[4..36], id.0);
+        assert_eq!(calldata[36..], second.0);
+
+        let path = std::env::temp_dir().join(format!("model-registry-{}.txt", std::process::id()));
+        registry.revoke(&id).unwrap();
+        registry.save(&path).unwrap();
+        let loaded = ModelRegistry::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.entries(), registry.entries());
+        assert!(matches!(loaded.check_envelope(&envelope), Err(RegistryError::Revoked(_))));
+    }
+}
@@ -0,0 +1,568 @@
+use serde::{Deserialize, Serialize};
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+    TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::{
+    Prover, TraceTable, Trace,
+    crypto::{hashers::Blake3_256, DefaultRandomCoin, MerkleTree},
+    matrix::ColMatrix,
+    CompositionPoly, CompositionPolyTrace, DefaultConstraintCommitment,
+    DefaultTraceLde, DefaultConstraintEvaluator, StarkDomain,
+    TracePolyTable, ConstraintCompositionCoefficients,
+    AuxRandElements, PartitionOptions,
+};
+
+#[cfg(feature = "prover")]
+use crate::batch::{write_rows, TILE_ROWS};
+use crate::codec;
+use crate::config::padded_trace_length;
+use crate::constraints::ConstraintLabels;
+use crate::gadgets::PhaseSelector;
+use crate::layout::LayoutDescriptor;
+#[cfg(feature = "prover")]
+use crate::secret::zeroize_elements;
+
+/// Identifier of [`LinearRegressionAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "linear-regression/v2";
+
+/// Columns: slope (m), intercept (b), x_input, y_output and the three phase flags
+pub const TRACE_WIDTH: usize = 7;
+
+/// Phases of a linear regression trace: the samples, the single prediction row, then padding
+pub const SAMPLE_PHASE: usize = 0;
+pub const PREDICTION_PHASE: usize = 1;
+pub const PADDING_PHASE: usize = 2;
+
+const PHASES: PhaseSelector = PhaseSelector::new(4, 3);
+
+/// Public inputs for linear regression verification; serialized with canonical hex elements.
+///
+/// Samples are trace rows, not a set of points: an x value may repeat, also as `x_value`, and
+/// every occurrence is checked against `y = mx + b` on its own row. A repeated x with two
+/// different y values is therefore unprovable rather than ambiguous.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinearRegressionInputs {
+    #[serde(with = "codec::element")]
+    pub x_value: BaseElement,          // The x for which we want to verify y prediction
+    #[serde(with = "codec::element")]
+    pub predicted_y: BaseElement,      // The claimed y = mx + b result
+    #[serde(with = "codec::elements")]
+    pub sample_x_values: Vec<BaseElement>, // Sample x values for validation
+    #[serde(with = "codec::elements")]
+    pub sample_y_values: Vec<BaseElement>, // Sample y values for validation
+}
+
+impl LinearRegressionInputs {
+    /// Rebuilds the inputs from their `to_elements()` encoding
+    pub fn from_elements(elements: &[BaseElement]) -> Option<Self> {
+        if elements.len() < 2 || !elements.len().is_multiple_of(2) {
+            return None;
+        }
+        let num_samples = (elements.len() - 2) / 2;
+        let (samples_x, samples_y) = elements[2..].split_at(num_samples);
+        Some(Self {
+            x_value: elements[0],
+            predicted_y: elements[1],
+            sample_x_values: samples_x.to_vec(),
+            sample_y_values: samples_y.to_vec(),
+        })
+    }
+}
+
+impl ToElements<BaseElement> for LinearRegressionInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![self.x_value, self.predicted_y];
+        elements.extend(&self.sample_x_values);
+        elements.extend(&self.sample_y_values);
+        elements
+    }
+}
+
+fn transition_degrees() -> Vec<TransitionConstraintDegree> {
+    // Constraints:
+    // 1. Linear relationship: y = mx + b on sample and prediction rows (degree 2: the
+    //    relation has degree 1 since slope is a constant column, the phase flags add one)
+    // 2. Slope consistency (degree 1: next_slope - slope = 0)
+    // 3. Intercept consistency (degree 1: next_intercept - intercept = 0)
+    // 4. Phase flags: one-hot and ordered samples -> prediction -> padding
+    let mut degrees = vec![
+        PhaseSelector::gated_degree(1), // Linear constraint: y - mx - b = 0
+        TransitionConstraintDegree::new(1), // Slope consistency
+        TransitionConstraintDegree::new(1), // Intercept consistency
+    ];
+    degrees.extend(PHASES.constraint_degrees());
+    degrees
+}
+
+/// Smallest blowup factor the constraints of [`LinearRegressionAir`] can be evaluated with, for
+/// every trace length
+pub fn min_blowup() -> usize {
+    transition_degrees().iter().map(TransitionConstraintDegree::min_blowup_factor).max().unwrap_or(1)
+}
+
+/// AIR for linear regression verification
+pub struct LinearRegressionAir {
+    context: AirContext<BaseElement>,
+    x_value: BaseElement,
+    predicted_y: BaseElement,
+    sample_x_values: Vec<BaseElement>,
+    sample_y_values: Vec<BaseElement>,
+    num_samples: usize,
+}
+
+impl Air for LinearRegressionAir {
+    type BaseField = BaseElement;
+    type PublicInputs = LinearRegressionInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: LinearRegressionInputs, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        
+        let num_samples = pub_inputs.sample_x_values.len();
+        assert_eq!(num_samples, pub_inputs.sample_y_values.len(), "Sample arrays must have equal length");
+        assert!(num_samples + 1 < trace_info.length(), "The trace needs a padding row after the prediction");
+        
+        // Assertions for sample points and prediction
+        let num_assertions = 2 * num_samples + 3; // x,y pairs for samples + prediction x,y and phase
+        
+        LinearRegressionAir {
+            context: AirContext::new(trace_info, transition_degrees(), num_assertions, options),
+            x_value: pub_inputs.x_value,
+            predicted_y: pub_inputs.predicted_y,
+            sample_x_values: pub_inputs.sample_x_values,
+            sample_y_values: pub_inputs.sample_y_values,
+            num_samples,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        // Extract current state: [slope, intercept, x, y, phase flags]
+        let current = frame.current();
+        let slope = current[0];
+        let intercept = current[1];
+        let x = current[2];
+        let y = current[3];
+        
+        // Extract next state
+        let next_slope = frame.next()[0];
+        let next_intercept = frame.next()[1];
+        
+        // Constraint 1: Linear relationship y = mx + b, except on padding rows
+        // This ensures y - mx - b = 0
+        result[0] = PHASES.gate(current, &[SAMPLE_PHASE, PREDICTION_PHASE], y - slope * x - intercept);
+        
+        // Constraint 2: Slope must remain constant across all steps
+        result[1] = next_slope - slope;
+        
+        // Constraint 3: Intercept must remain constant across all steps  
+        result[2] = next_intercept - intercept;
+
+        // Constraint 4: Phase flags are one-hot and only move forward
+        PHASES.evaluate(current, frame.next(), &mut result[3..]);
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = Vec::new();
+        
+        // Assert that each sample point is correctly represented in the trace
+        for i in 0..self.num_samples {
+            // Assert x value at step i
+            assertions.push(Assertion::single(2, i, self.sample_x_values[i]));
+            // Assert y value at step i  
+            assertions.push(Assertion::single(3, i, self.sample_y_values[i]));
+        }
+        
+        // Assert the final prediction at the prediction step
+        let prediction_step = self.num_samples;
+        assertions.push(Assertion::single(2, prediction_step, self.x_value));
+        assertions.push(Assertion::single(3, prediction_step, self.predicted_y));
+
+        // Pinning the prediction phase here puts every earlier row in the sample phase or the
+        // prediction phase, so the linear relationship holds on all asserted rows
+        assertions.push(Assertion::single(PHASES.column(PREDICTION_PHASE), prediction_step, BaseElement::ONE));
+        
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+impl ConstraintLabels for LinearRegressionAir {
+    fn constraint_labels(&self) -> Vec<String> {
+        let mut labels = vec!["linear_relation".to_string(), "slope_constant".into(), "intercept_constant".into()];
+        labels.extend(PHASES.constraint_labels());
+        labels
+    }
+}
+
+/// Trace length for `num_samples` samples: one row per sample, the prediction row and at
+/// least one padding row, padded to at least `min_trace_length` rows
+pub fn trace_length(num_samples: usize, min_trace_length: usize) -> usize {
+    padded_trace_length(num_samples + 2, min_trace_length)
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    LayoutDescriptor::new(AIR_ID)
+        .with_column("slope", 0)
+        .with_column("intercept", 1)
+        .with_column("x", 2)
+        .with_column("y", 3)
+        .with_columns("phases", PHASES.column(SAMPLE_PHASE)..PHASES.next_column())
+}
+
+/// Build the execution trace for linear regression
+#[cfg(feature = "prover")]
+pub fn build_linear_regression_trace(
+    slope: BaseElement,
+    intercept: BaseElement,
+    sample_x_values: &[BaseElement],
+    sample_y_values: &[BaseElement],
+    target_x: BaseElement,
+    min_trace_length: usize,
+) -> TraceTable<BaseElement> {
+    let trace_length = trace_length(sample_x_values.len(), min_trace_length);
+    let mut columns = vec![vec![BaseElement::ZERO; trace_length]; TRACE_WIDTH];
+    fill_linear_regression_trace(&mut columns, slope, intercept, sample_x_values, sample_y_values, target_x);
+    TraceTable::init(columns)
+}
+
+/// Writes the trace of [`build_linear_regression_trace`] into `TRACE_WIDTH` columns of a
+/// [`trace_length`], e.g. buffers taken from a [`TracePool`](crate::pool::TracePool)
+#[cfg(feature = "prover")]
+pub fn fill_linear_regression_trace(
+    columns: &mut [Vec<BaseElement>],
+    slope: BaseElement,
+    intercept: BaseElement,
+    sample_x_values: &[BaseElement],
+    sample_y_values: &[BaseElement],
+    target_x: BaseElement,
+) {
+    let num_samples = sample_x_values.len();
+    let trace_length = columns[0].len();
+    assert_eq!(columns.len(), TRACE_WIDTH, "trace has {TRACE_WIDTH} columns");
+    assert_eq!(trace_length, trace_length.max(num_samples + 2).next_power_of_two(), "trace is too short");
+
+    // Fill the trace row-by-row, a block of rows at a time
+    let predicted_y = slope * target_x + intercept;
+    let mut block = vec![BaseElement::ZERO; TILE_ROWS * TRACE_WIDTH];
+    for first_row in (0..trace_length).step_by(TILE_ROWS) {
+        let num_rows = TILE_ROWS.min(trace_length - first_row);
+        for (i, row) in (first_row..).zip(block.chunks_exact_mut(TRACE_WIDTH).take(num_rows)) {
+            // Set the constant slope and intercept for every row
+            row[0] = slope;
+            row[1] = intercept;
+
+            if i < num_samples {
+                // Fill with sample data
+                row[2] = sample_x_values[i];
+                row[3] = sample_y_values[i];
+                PHASES.fill(row, SAMPLE_PHASE);
+            } else if i == num_samples {
+                // The prediction step
+                row[2] = target_x;
+                row[3] = predicted_y;
+                PHASES.fill(row, PREDICTION_PHASE);
+            } else if i < trace_length - 1 {
+                // Padding steps: repeat the prediction
+                row[2] = target_x;
+                row[3] = predicted_y;
+                PHASES.fill(row, PADDING_PHASE);
+            } else {
+                // The exempt last row breaks the relationship and the phase flags so the
+                // constraint polynomials keep their declared degrees
+                let previous_phase = if i == num_samples + 1 { PREDICTION_PHASE } else { PADDING_PHASE };
+                row[2] = target_x;
+                row[3] = predicted_y + BaseElement::ONE;
+                PHASES.fill_exempt_row(row, previous_phase);
+            }
+        }
+        write_rows(columns, first_row, &block[..num_rows * TRACE_WIDTH]);
+    }
+    // the block holds the slope and intercept
+    zeroize_elements(&mut block);
+}
+
+/// Linear Regression Prover
+#[cfg(feature = "prover")]
+pub struct LinearRegressionProver {
+    options: ProofOptions,
+}
+
+#[cfg(feature = "prover")]
+impl LinearRegressionProver {
+    pub fn new(options: ProofOptions) -> Self {
+        Self { options }
+    }
+}
+
+#[cfg(feature = "prover")]
+impl Prover for LinearRegressionProver {
+    type BaseField = BaseElement;
+    type Air = LinearRegressionAir;
+    type Trace = TraceTable<Self::BaseField>;
+    type HashFn = Blake3_256<Self::BaseField>;
+    type VC = MerkleTree<Self::HashFn>;
+    type RandomCoin = DefaultRandomCoin<Self::HashFn>;
+    type TraceLde<E: FieldElement<BaseField = Self::BaseField>> = DefaultTraceLde<E, Self::HashFn, Self::VC>;
+    type ConstraintCommitment<E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintCommitment<E, Self::HashFn, Self::VC>;
+    type ConstraintEvaluator<'a, E: FieldElement<BaseField = Self::BaseField>> =
+        DefaultConstraintEvaluator<'a, Self::Air, E>;
+
+    fn get_pub_inputs(&self, trace: &Self::Trace) -> LinearRegressionInputs {
+        // Samples are the rows of the sample phase, the prediction follows them; rows are read
+        // by position only, so repeated x values come back as often and in the order they occur
+        let mut row = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut num_samples = 0;
+        while num_samples < trace.length() {
+            trace.read_row_into(num_samples, &mut row);
+            if !PHASES.is_active(&row, SAMPLE_PHASE) {
+                break;
+            }
+            num_samples += 1;
+        }
+
+        LinearRegressionInputs {
+            x_value: trace.get(2, num_samples),
+            predicted_y: trace.get(3, num_samples),
+            sample_x_values: (0..num_samples).map(|step| trace.get(2, step)).collect(),
+            sample_y_values: (0..num_samples).map(|step| trace.get(3, step)).collect(),
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn new_trace_lde<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        trace_info: &TraceInfo,
+        main_trace: &ColMatrix<Self::BaseField>,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_option: PartitionOptions,
+    ) -> (Self::TraceLde<E>, TracePolyTable<E>) {
+        DefaultTraceLde::new(trace_info, main_trace, domain, partition_option)
+    }
+
+    fn build_constraint_commitment<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        composition_poly_trace: CompositionPolyTrace<E>,
+        num_constraint_composition_columns: usize,
+        domain: &StarkDomain<Self::BaseField>,
+        partition_options: PartitionOptions,
+    ) -> (Self::ConstraintCommitment<E>, CompositionPoly<E>) {
+        DefaultConstraintCommitment::new(
+            composition_poly_trace,
+            num_constraint_composition_columns,
+            domain,
+            partition_options,
+        )
+    }
+
+    fn new_evaluator<'a, E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        air: &'a Self::Air,
+        aux_rand_elements: Option<AuxRandElements<E>>,
+        composition_coefficients: ConstraintCompositionCoefficients<E>,
+    ) -> Self::ConstraintEvaluator<'a, E> {
+        DefaultConstraintEvaluator::new(air, aux_rand_elements, composition_coefficients)
+    }
+}
+
+/// Example usage and testing
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+
+    #[test]
+    fn test_linear_regression_proof() {
+        // Secret parameters (not revealed in proof)
+        let slope = BaseElement::new(3);        // m = 3
+        let intercept = BaseElement::new(7);    // b = 7
+        
+        // Public sample data points
+        let sample_x = vec![
+            BaseElement::new(1),   // x = 1
+            BaseElement::new(2),   // x = 2  
+            BaseElement::new(4),   // x = 4
+            BaseElement::new(5),   // x = 5
+        ];
+        
+        let sample_y = vec![
+            BaseElement::new(10),  // y = 3*1 + 7 = 10
+            BaseElement::new(13),  // y = 3*2 + 7 = 13
+            BaseElement::new(19),  // y = 3*4 + 7 = 19
+            BaseElement::new(22),  // y = 3*5 + 7 = 22
+        ];
+        
+        // Target prediction
+        let target_x = BaseElement::new(6);
+        let expected_y = slope * target_x + intercept; // 3*6 + 7 = 25
+        
+        // Build execution trace
+        let trace = build_linear_regression_trace(
+            slope, intercept, &sample_x, &sample_y, target_x
+        , TraceInfo::MIN_TRACE_LENGTH);
+        
+        // Verify trace properties
+        println!("Trace length: {}", trace.length());
+        println!("Trace width: {}", trace.width());
+        
+        // Verify the trace values manually
+        for i in 0..trace.length() - 1 {
+            let s = trace.get(0, i);
+            let b = trace.get(1, i);
+            let x = trace.get(2, i);
+            let y = trace.get(3, i);
+            println!("Step {}: slope={}, intercept={}, x={}, y={}", i, s, b, x, y);
+            
+            // Verify linear relationship
+            let expected = s * x + b;
+            assert_eq!(y, expected, "Linear relationship violated at step {}", i);
+        }
+        
+        // Define proof options
+        let options = ProverConfig::default().to_proof_options();
+        
+        // Generate proof
+        let prover = LinearRegressionProver::new(options);
+        let proof = prover.prove(trace).unwrap();
+        
+        // Verify proof
+        let pub_inputs = LinearRegressionInputs {
+            x_value: target_x,
+            predicted_y: expected_y,
+            sample_x_values: sample_x,
+            sample_y_values: sample_y,
+        };
+        
+        let min_opts = default_acceptable_options();
+        
+        let verification_result = winterfell::verify::<
+            LinearRegressionAir,
+            Blake3_256<BaseElement>,
+            DefaultRandomCoin<Blake3_256<BaseElement>>,
+            MerkleTree<Blake3_256<BaseElement>>
+        >(proof, pub_inputs, &min_opts);
+        
+        assert!(verification_result.is_ok(), "Proof verification failed: {:?}", verification_result.err());
+        println!("✅ Linear regression proof verified successfully!");
+        println!("   Predicted y = {} for x = {} (slope and intercept kept private)", expected_y, target_x);
+    }
+
+    #[test]
+    fn test_phases_cover_every_sample() {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+        let options = crate::config::ProverConfig::default().to_proof_options();
+        for num_samples in [0, 6, 7, 14] {
+            let xs: Vec<BaseElement> = (1..=num_samples as u64).map(BaseElement::from).collect();
+            let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+            let trace = build_linear_regression_trace(
+                slope,
+                intercept,
+                &xs,
+                &ys,
+                BaseElement::new(20),
+                TraceInfo::MIN_TRACE_LENGTH,
+            );
+            let prover = LinearRegressionProver::new(options.clone());
+            let inputs = prover.get_pub_inputs(&trace);
+            assert_eq!(inputs.sample_x_values, xs);
+            assert_eq!(inputs.predicted_y, BaseElement::new(67));
+            prover.prove(trace).unwrap();
+        }
+
+        // a sample moved into the padding phase escapes the relationship but not the ordering
+        let xs = [1u64, 2, 3].map(BaseElement::from).to_vec();
+        let ys = vec![BaseElement::new(10), BaseElement::new(99), BaseElement::new(16)];
+        let mut trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(20),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let mut row = vec![BaseElement::ZERO; TRACE_WIDTH];
+        trace.read_row_into(1, &mut row);
+        PHASES.fill(&mut row, PADDING_PHASE);
+        trace.update_row(1, &row);
+        let inputs = LinearRegressionInputs {
+            x_value: BaseElement::new(20),
+            predicted_y: BaseElement::new(67),
+            sample_x_values: xs,
+            sample_y_values: ys,
+        };
+        let test = crate::air_test!(LinearRegressionAir, inputs).with_trace(&trace).expect_constraint_holds(0);
+        assert!(test.evaluations()[1].iter().any(|e| *e != BaseElement::ZERO));
+    }
+
+    #[test]
+    fn test_min_blowup_matches_instances() {
+        let options = ProverConfig::default().to_proof_options();
+        for (num_samples, trace_length) in [(0, 8), (6, 8), (14, 16), (40, 64)] {
+            let xs: Vec<BaseElement> = (1..=num_samples as u64).map(BaseElement::from).collect();
+            let inputs = LinearRegressionInputs {
+                x_value: BaseElement::new(20),
+                predicted_y: BaseElement::ZERO,
+                sample_x_values: xs.clone(),
+                sample_y_values: xs,
+            };
+            let air = LinearRegressionAir::new(TraceInfo::new(TRACE_WIDTH, trace_length), inputs, options.clone());
+            assert_eq!(min_blowup(), crate::analysis::min_blowup(&air));
+        }
+    }
+
+    #[test]
+    fn test_repeated_sample_x() {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+        let xs = [2u64, 2, 5, 2, 9].map(BaseElement::from).to_vec();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        // the prediction may also repeat a sample's x
+        let target_x = BaseElement::new(5);
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target_x, TraceInfo::MIN_TRACE_LENGTH);
+        let options = ProverConfig::default().to_proof_options();
+        let prover = LinearRegressionProver::new(options);
+        let inputs = prover.get_pub_inputs(&trace);
+        assert_eq!((inputs.sample_x_values.clone(), inputs.sample_y_values.clone()), (xs.clone(), ys.clone()));
+        assert_eq!(inputs.predicted_y, BaseElement::new(22));
+        let proof = prover.prove(trace).unwrap();
+        assert!(crate::prover::verify::<LinearRegressionAir>(proof, inputs, &default_acceptable_options()).is_ok());
+
+        // every occurrence is its own row: one disagreeing y fails exactly there
+        let mut conflicting = ys.clone();
+        conflicting[3] += BaseElement::ONE;
+        let trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &conflicting,
+            target_x,
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let inputs = LinearRegressionInputs {
+            x_value: target_x,
+            predicted_y: BaseElement::new(22),
+            sample_x_values: xs,
+            sample_y_values: conflicting,
+        };
+        let test = crate::air_test!(LinearRegressionAir, inputs)
+            .with_trace(&trace)
+            .expect_constraint_fails(0, 3)
+            .expect_assertions_hold();
+        // the five sample rows and the prediction row
+        let failing: Vec<usize> = (0..=5).filter(|&row| test.evaluations()[row][0] != BaseElement::ZERO).collect();
+        assert_eq!(failing, vec![3]);
+    }
+}
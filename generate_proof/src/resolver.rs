@@ -0,0 +1,295 @@
+//! Resolution of published model commitments from a registry endpoint.
+//!
+//! The commitment a model is proven under is only meaningful if it is the one its owner
+//! published. A [`Resolver`] looks it up by [`ModelId`] at a configured [`RegistryEndpoint`]:
+//!
+//! - `http`: `GET {url}/models/{model id}` answering `{"commitment":"0x..","revoked":bool}`,
+//!   404 for unknown models; `revoked` is optional,
+//! - `chain_rpc`: an `eth_call` of `models(bytes32)` on the `ModelRegistry` contract at
+//!   `contract` through the JSON-RPC node at `url`.
+//!
+//! [`ProvingSession::with_resolver`](crate::session::ProvingSession::with_resolver) refuses to
+//! prove for a model whose published commitment differs from the request's, and a
+//! [`VerificationPolicy`](crate::config::VerificationPolicy) with a `registry` checks every
+//! envelope's commitment against the resolved one. Requests go through the TLS client of
+//! [`http`](crate::http), so endpoints are `https://` or `http://` URLs and builds without the
+//! `http` feature cannot resolve.
+
+use core::fmt;
+use std::{io, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::abi::{encode_call, from_hex, to_hex};
+use crate::envelope::ProofEnvelope;
+use crate::registry::{ModelCommitment, ModelId};
+
+/// Time allowed for connecting to, and for each read from, a registry endpoint
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest registry response read
+#[cfg(feature = "http")]
+const MAX_RESPONSE_BYTES: u64 = 1 << 16;
+
+/// Where published model commitments are looked up
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RegistryEndpoint {
+    /// HTTP service serving `GET {url}/models/{model id}`
+    Http { url: String },
+    /// JSON-RPC node and the address of the `ModelRegistry` contract
+    ChainRpc { url: String, contract: String },
+}
+
+/// Reason a model commitment could not be resolved or did not match
+#[derive(Debug)]
+pub enum ResolveError {
+    UnsupportedUrl(String),
+    /// Registry requests need a build with the `http` feature
+    Unsupported,
+    Io(io::Error),
+    Status(u16),
+    Malformed(String),
+    Unregistered(ModelId),
+    Revoked(ModelId),
+    CommitmentMismatch { published: ModelCommitment, actual: ModelCommitment },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::UnsupportedUrl(url) => write!(f, "unsupported registry URL {url:?}"),
+            ResolveError::Unsupported => write!(f, "registry requests need a build with the `http` feature"),
+            ResolveError::Io(err) => write!(f, "registry request failed: {err}"),
+            ResolveError::Status(status) => write!(f, "registry answered with status {status}"),
+            ResolveError::Malformed(reason) => write!(f, "malformed registry response: {reason}"),
+            ResolveError::Unregistered(id) => write!(f, "model {id} is not published in the registry"),
+            ResolveError::Revoked(id) => write!(f, "model {id} has been revoked"),
+            ResolveError::CommitmentMismatch { published, actual } => {
+                write!(f, "model commitment {actual} does not match published {published}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+impl From<io::Error> for ResolveError {
+    fn from(err: io::Error) -> Self {
+        ResolveError::Io(err)
+    }
+}
+
+/// Looks up model commitments at a [`RegistryEndpoint`]
+#[derive(Clone, Debug)]
+pub struct Resolver {
+    endpoint: RegistryEndpoint,
+    timeout: Duration,
+}
+
+impl Resolver {
+    pub fn new(endpoint: RegistryEndpoint) -> Self {
+        Self { endpoint, timeout: DEFAULT_TIMEOUT }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn endpoint(&self) -> &RegistryEndpoint {
+        &self.endpoint
+    }
+
+    /// The commitment currently published for `id`
+    pub fn resolve(&self, id: &ModelId) -> Result<ModelCommitment, ResolveError> {
+        match &self.endpoint {
+            RegistryEndpoint::Http { url } => {
+                let url = format!("{}/models/{id}", url.trim_end_matches('/'));
+                let (status, body) = http_request(&url, None, self.timeout)?;
+                match status {
+                    200 => {}
+                    404 => return Err(ResolveError::Unregistered(*id)),
+                    status => return Err(ResolveError::Status(status)),
+                }
+                let response: Value = serde_json::from_str(&body).map_err(|err| ResolveError::Malformed(err.to_string()))?;
+                if response["revoked"].as_bool().unwrap_or(false) {
+                    return Err(ResolveError::Revoked(*id));
+                }
+                let commitment = response["commitment"].as_str().and_then(parse_word);
+                commitment.map(ModelCommitment).ok_or_else(|| ResolveError::Malformed("missing commitment".into()))
+            }
+            RegistryEndpoint::ChainRpc { url, contract } => {
+                let call = json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "eth_call",
+                    "params": [{ "to": contract, "data": to_hex(&encode_call("models(bytes32)", &[id.0])) }, "latest"],
+                });
+                let (status, body) = http_request(url, Some(&call.to_string()), self.timeout)?;
+                if status != 200 {
+                    return Err(ResolveError::Status(status));
+                }
+                let response: Value = serde_json::from_str(&body).map_err(|err| ResolveError::Malformed(err.to_string()))?;
+                if let Some(error) = response.get("error") {
+                    return Err(ResolveError::Malformed(error.to_string()));
+                }
+                // (address owner, bytes32 commitment, uint32 version, bool revoked)
+                let words = response["result"]
+                    .as_str()
+                    .and_then(from_hex)
+                    .filter(|bytes| bytes.len() == 4 * 32)
+                    .ok_or_else(|| ResolveError::Malformed("expected four ABI words".into()))?;
+                if words[..32].iter().all(|&byte| byte == 0) {
+                    return Err(ResolveError::Unregistered(*id));
+                }
+                if words[127] != 0 {
+                    return Err(ResolveError::Revoked(*id));
+                }
+                Ok(ModelCommitment(words[32..64].try_into().expect("32-byte word")))
+            }
+        }
+    }
+
+    /// Checks that the envelope carries the commitment published for its model
+    pub fn check_envelope(&self, envelope: &ProofEnvelope) -> Result<(), ResolveError> {
+        self.check(&envelope.model_id, envelope.model_commitment)
+    }
+
+    /// Checks that `commitment` is the one published for `id`
+    pub fn check(&self, id: &ModelId, commitment: ModelCommitment) -> Result<(), ResolveError> {
+        let published = self.resolve(id)?;
+        if published != commitment {
+            return Err(ResolveError::CommitmentMismatch { published, actual: commitment });
+        }
+        Ok(())
+    }
+}
+
+fn parse_word(hex: &str) -> Option<[u8; 32]> {
+    from_hex(hex)?.try_into().ok()
+}
+
+/// Sends a GET, or a JSON POST when `body` is given, and returns the status and response body
+#[cfg(feature = "http")]
+pub fn http_request(url: &str, body: Option<&str>, timeout: Duration) -> Result<(u16, String), ResolveError> {
+    use crate::http::{self, HttpError};
+
+    let method = if body.is_some() { "POST" } else { "GET" };
+    let headers = [("Accept", "application/json"), ("Content-Type", "application/json")];
+    let (status, body) = http::send(method, url, &headers, body.unwrap_or("").as_bytes(), timeout, MAX_RESPONSE_BYTES)
+        .map_err(|err| match err {
+            HttpError::UnsupportedUrl(url) | HttpError::InsecureUrl(url) => ResolveError::UnsupportedUrl(url),
+            HttpError::TooLarge(_) => ResolveError::Malformed(err.to_string()),
+            HttpError::Io(err) => ResolveError::Io(err),
+        })?;
+    let body = String::from_utf8(body).map_err(|_| ResolveError::Malformed("response is not UTF-8".into()))?;
+    Ok((status, body))
+}
+
+/// Without the `http` feature no request can be sent
+#[cfg(not(feature = "http"))]
+pub fn http_request(_url: &str, _body: Option<&str>, _timeout: Duration) -> Result<(u16, String), ResolveError> {
+    Err(ResolveError::Unsupported)
+}
+
+#[cfg(all(test, feature = "prover", feature = "http"))]
+mod tests {
+    use super::*;
+    use crate::config::{ProverConfig, VerificationPolicy};
+    use crate::domain::DomainTag;
+    use crate::session::{verify_linear, LinearClaimRequest, ProvingSession, SessionError};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use winterfell::math::fields::f128::BaseElement;
+
+    /// Answers every connection with `respond(request line, body)` until the test ends
+    fn fake_registry(respond: fn(&str, &str) -> (u16, String)) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let (mut request_line, mut content_length) = (String::new(), 0);
+                reader.read_line(&mut request_line).unwrap();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(length) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let (status, response) = respond(&request_line, &String::from_utf8(body).unwrap());
+                let _ = write!(stream, "HTTP/1.1 {status} X\r\nContent-Length: {}\r\n\r\n{response}", response.len());
+            }
+        });
+        format!("http://{address}")
+    }
+
+    fn request(slope: u64) -> LinearClaimRequest {
+        let salt = to_hex(&[4u8; 32]);
+        LinearClaimRequest {
+            model: "demo".into(),
+            salt,
+            slope,
+            intercept: 7,
+            sample_x: vec![1, 2],
+            x: 3,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        }
+    }
+
+    /// The fake registries only know "demo", published with slope 3
+    fn demo_commitment() -> String {
+        let parameters = [BaseElement::from(3u64), BaseElement::from(7u64)];
+        ModelCommitment::new(&DomainTag::default(), &parameters, &[4u8; 32]).to_string()
+    }
+
+    #[test]
+    fn test_binds_claims_to_published_commitment() {
+        let published = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request(3)).unwrap();
+
+        let http = fake_registry(|request_line, _| {
+            if request_line.contains(&format!("/models/{}", ModelId::from_name("demo"))) {
+                (200, json!({ "commitment": demo_commitment() }).to_string())
+            } else {
+                (404, String::new())
+            }
+        });
+        let resolver = Resolver::new(RegistryEndpoint::Http { url: http.clone() });
+        resolver.check_envelope(&published).unwrap();
+        assert!(matches!(resolver.resolve(&ModelId::from_name("other")), Err(ResolveError::Unregistered(_))));
+
+        let mut session = ProvingSession::new(ProverConfig::default(), "ci").with_resolver(resolver);
+        session.prove_linear(&request(3)).unwrap();
+        let err = session.prove_linear(&request(5)).unwrap_err();
+        assert!(matches!(err, SessionError::Resolve(ResolveError::CommitmentMismatch { .. })), "{err}");
+
+        let rpc = fake_registry(|_, body| {
+            let call: Value = serde_json::from_str(body).unwrap();
+            assert_eq!(call["method"], "eth_call");
+            let owner = [[0u8; 12].as_slice(), &[0xaa; 20]].concat();
+            let result = format!("{}{}{:064x}{:064x}", to_hex(&owner), &demo_commitment()[2..], 1, 0);
+            (200, json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string())
+        });
+        let policy = VerificationPolicy {
+            registry: Some(RegistryEndpoint::ChainRpc { url: rpc, contract: to_hex(&[0x11; 20]) }),
+            ..Default::default()
+        };
+        verify_linear(&published, &policy).unwrap();
+        let rotated = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&request(5)).unwrap();
+        assert!(matches!(verify_linear(&rotated, &policy), Err(SessionError::Resolve(_))));
+
+        let ftp = Resolver::new(RegistryEndpoint::Http { url: "ftp://registry.example".into() });
+        assert!(matches!(ftp.resolve(&published.model_id), Err(ResolveError::UnsupportedUrl(_))));
+    }
+}
@@ -0,0 +1,206 @@
+//! Predicting the time and memory a proof will take before starting it.
+//!
+//! [`calibrate`] proves a few small linear regression claims on the current machine and fits
+//! `seconds = overhead + seconds_per_unit * work`, where the work of a [`ProvingPlan`] is
+//! `lde_length * log2(lde_length) * (width + 1)`: the trace and composition columns are
+//! interpolated and extended over the LDE domain with FFTs. Memory is not measured; it is
+//! predicted from the buffers Winterfell keeps alive at its peak (the extended trace, its
+//! Merkle tree and the constraint evaluations), which dominate every other allocation.
+//!
+//! The CLI and the server check an estimate against configured [`ResourceLimits`] and reject
+//! jobs that would exceed them instead of running out of memory half way through.
+
+use core::fmt;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use winterfell::{math::fields::f128::BaseElement, ProverError, TraceInfo};
+
+use crate::config::ProverConfig;
+use crate::prover::prove;
+use crate::regression::{self, build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+/// Sample counts proven by [`calibrate`]; their traces have 16 to 512 rows
+const CALIBRATION_SAMPLES: [usize; 4] = [14, 62, 254, 510];
+
+const ELEMENT_BYTES: u64 = 16;
+const DIGEST_BYTES: u64 = 32;
+
+/// Shape of a proving job
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProvingPlan {
+    pub trace_width: usize,
+    pub trace_length: usize,
+    pub blowup_factor: usize,
+}
+
+impl ProvingPlan {
+    pub fn new(trace_info: &TraceInfo, config: &ProverConfig) -> Self {
+        Self {
+            trace_width: trace_info.main_trace_width(),
+            trace_length: trace_info.length(),
+            blowup_factor: config.blowup_factor,
+        }
+    }
+
+    /// Plan of a linear regression claim over `num_samples` samples
+    pub fn linear(num_samples: usize, config: &ProverConfig) -> Self {
+        let trace_length = regression::trace_length(num_samples, config.min_trace_length);
+        Self::new(&TraceInfo::new(regression::TRACE_WIDTH, trace_length), config)
+    }
+
+    pub fn lde_length(&self) -> usize {
+        self.trace_length * self.blowup_factor
+    }
+
+    fn work(&self) -> f64 {
+        let lde_length = self.lde_length() as f64;
+        lde_length * lde_length.log2() * (self.trace_width + 1) as f64
+    }
+
+    /// Bytes of the extended trace and trace polynomials, the trace Merkle tree and the
+    /// constraint evaluations over the LDE domain
+    pub fn peak_memory_bytes(&self) -> u64 {
+        let (width, length, lde) = (self.trace_width as u64, self.trace_length as u64, self.lde_length() as u64);
+        width * (lde + length) * ELEMENT_BYTES + 2 * lde * DIGEST_BYTES + lde * ELEMENT_BYTES
+    }
+}
+
+/// Predicted cost of a [`ProvingPlan`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResourceEstimate {
+    pub seconds: f64,
+    pub memory_bytes: u64,
+}
+
+/// Timing model fitted by [`calibrate`]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub seconds_per_unit: f64,
+    pub overhead_seconds: f64,
+}
+
+impl Calibration {
+    pub fn estimate(&self, plan: &ProvingPlan) -> ResourceEstimate {
+        ResourceEstimate {
+            seconds: self.overhead_seconds + self.seconds_per_unit * plan.work(),
+            memory_bytes: plan.peak_memory_bytes(),
+        }
+    }
+}
+
+/// Proves a few small claims with `config` and fits the timing model to their wall time
+pub fn calibrate(config: &ProverConfig) -> Result<Calibration, ProverError> {
+    let mut samples = Vec::with_capacity(CALIBRATION_SAMPLES.len());
+    for num_samples in CALIBRATION_SAMPLES {
+        let (slope, intercept) = (BaseElement::new(3), BaseElement::new(7));
+        let xs: Vec<BaseElement> = (1..=num_samples as u64).map(BaseElement::from).collect();
+        let ys: Vec<BaseElement> = xs.iter().map(|&x| slope * x + intercept).collect();
+        let target = BaseElement::new(11);
+        let inputs = LinearRegressionInputs {
+            x_value: target,
+            predicted_y: slope * target + intercept,
+            sample_x_values: xs.clone(),
+            sample_y_values: ys.clone(),
+        };
+
+        let started = Instant::now();
+        let trace = build_linear_regression_trace(slope, intercept, &xs, &ys, target, config.min_trace_length);
+        prove::<LinearRegressionAir>(trace, inputs, config.to_proof_options())?;
+        samples.push((ProvingPlan::linear(num_samples, config).work(), started.elapsed().as_secs_f64()));
+    }
+    Ok(fit(&samples))
+}
+
+/// Least-squares line through `(work, seconds)` points, clamped to non-negative coefficients
+fn fit(samples: &[(f64, f64)]) -> Calibration {
+    let n = samples.len() as f64;
+    let mean_work = samples.iter().map(|s| s.0).sum::<f64>() / n;
+    let mean_seconds = samples.iter().map(|s| s.1).sum::<f64>() / n;
+    let covariance: f64 = samples.iter().map(|(w, s)| (w - mean_work) * (s - mean_seconds)).sum();
+    let variance: f64 = samples.iter().map(|(w, _)| (w - mean_work).powi(2)).sum();
+
+    let seconds_per_unit = if variance > 0.0 { (covariance / variance).max(0.0) } else { 0.0 };
+    let overhead_seconds = (mean_seconds - seconds_per_unit * mean_work).max(0.0);
+    Calibration { seconds_per_unit, overhead_seconds }
+}
+
+/// Limits a proving job must stay within; `None` means unlimited
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    pub max_seconds: Option<f64>,
+    pub max_memory_bytes: Option<u64>,
+}
+
+/// A job whose estimate exceeds its [`ResourceLimits`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LimitExceeded {
+    Time { estimated: f64, limit: f64 },
+    Memory { estimated: u64, limit: u64 },
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitExceeded::Time { estimated, limit } => {
+                write!(f, "job would take about {estimated:.1}s, the limit is {limit:.1}s")
+            }
+            LimitExceeded::Memory { estimated, limit } => {
+                write!(f, "job would need about {} MiB, the limit is {} MiB", estimated >> 20, limit >> 20)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl ResourceLimits {
+    pub fn check(&self, estimate: &ResourceEstimate) -> Result<(), LimitExceeded> {
+        if let Some(limit) = self.max_memory_bytes
+            && estimate.memory_bytes > limit
+        {
+            return Err(LimitExceeded::Memory { estimated: estimate.memory_bytes, limit });
+        }
+        if let Some(limit) = self.max_seconds
+            && estimate.seconds > limit
+        {
+            return Err(LimitExceeded::Time { estimated: estimate.seconds, limit });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_and_limits() {
+        let model = fit(&[(1.0e6, 0.2), (2.0e6, 0.3), (4.0e6, 0.5)]);
+        assert!((model.seconds_per_unit - 1.0e-7).abs() < 1e-12);
+        assert!((model.overhead_seconds - 0.1).abs() < 1e-9);
+
+        let config = ProverConfig::default();
+        let small = model.estimate(&ProvingPlan::linear(6, &config));
+        let large = model.estimate(&ProvingPlan::linear(1 << 20, &config));
+        assert!(small.seconds < large.seconds && small.memory_bytes < large.memory_bytes);
+        // 2^21 rows extended 8 times: 7 columns of LDE alone are 1.75 GiB
+        assert!(large.memory_bytes > 1 << 30);
+
+        let limits = ResourceLimits { max_seconds: Some(60.0), max_memory_bytes: Some(256 << 20) };
+        limits.check(&small).unwrap();
+        assert!(matches!(limits.check(&large), Err(LimitExceeded::Memory { .. })));
+        assert!(ResourceLimits::default().check(&large).is_ok());
+    }
+
+    #[test]
+    fn test_calibration_grows_with_trace() {
+        let config = ProverConfig::default();
+        let model = calibrate(&config).unwrap();
+        assert!(model.seconds_per_unit > 0.0);
+
+        let plan = ProvingPlan::linear(1022, &config);
+        let estimate = model.estimate(&plan);
+        assert!(estimate.seconds > model.estimate(&ProvingPlan::linear(510, &config)).seconds);
+    }
+}
@@ -0,0 +1,400 @@
+//! Robustness claims: a committed linear model changes its output by at most `epsilon` over a
+//! public, finite set of input perturbations.
+//!
+//! [`RobustnessAir`] proves `|f(x + δ) - f(x)| <= ε` for every `δ` in the set, where
+//! `f(x) = slope * x + intercept` stays private and is bound to a public
+//! [`model_commitment`]. The trace evaluates the model at `x` on its first row and at each
+//! perturbed point on the following rows, and range checks `ε + (f(x + δ) - f(x))` and
+//! `ε - (f(x + δ) - f(x))` on every perturbed row, so both must be non-negative.
+//!
+//! Next to the model columns the trace runs one Rescue permutation per
+//! [`CYCLE_LENGTH`]-row cycle, each starting from `[slope, intercept, salt + cycle, tag]`.
+//! The output of the first cycle is the commitment; later cycles only keep the sponge columns
+//! from repeating with the cycle, and every cycle ties its first lanes to the model columns.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{PhaseSelector, RangeCheck};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`RobustnessAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "robustness/v1";
+
+/// `2 * epsilon` must fit into this many bits
+pub const DIFF_BITS: usize = 32;
+
+/// Largest supported `epsilon`
+pub const MAX_EPSILON: u64 = (1 << (DIFF_BITS - 1)) - 1;
+
+/// Sponge tag of [`model_commitment`]; `crate::signature` uses 1 and 2, `crate::freshness` 3
+const MODEL_TAG: u64 = 4;
+const DIGIT_BITS: usize = 2;
+
+/// Phases of a robustness trace: the unperturbed input, one row per perturbation, padding
+const ORIGIN: usize = 0;
+const PERTURBED: usize = 1;
+#[cfg(feature = "prover")]
+const PADDING: usize = 2;
+
+// Trace layout: sponge state, model, input and output, the phase flags, then the digits of
+// `epsilon + difference` and of `epsilon - difference`
+const SPONGE: usize = 0;
+const SLOPE: usize = SPONGE + STATE_WIDTH;
+const INTERCEPT: usize = SLOPE + 1;
+const X: usize = INTERCEPT + 1;
+const Y: usize = X + 1;
+const PHASES_START: usize = Y + 1;
+const DIGITS_START: usize = PHASES_START + 3;
+
+struct Layout {
+    sponge: RescueGadget,
+    phases: PhaseSelector,
+    above: RangeCheck,
+    below: RangeCheck,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let above = RangeCheck::with_digit_bits(DIGITS_START, DIFF_BITS, DIGIT_BITS);
+        let below = RangeCheck::with_digit_bits(above.next_column(), DIFF_BITS, DIGIT_BITS);
+        Self { sponge: RescueGadget::new(SPONGE), phases: PhaseSelector::new(PHASES_START, 3), above, below }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.below.next_column()
+    }
+}
+
+/// Number of trace columns
+pub fn trace_width() -> usize {
+    Layout::new().trace_width()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    let layout = Layout::new();
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..SLOPE)
+        .with_column("slope", SLOPE)
+        .with_column("intercept", INTERCEPT)
+        .with_column("x", X)
+        .with_column("y", Y)
+        .with_columns("phases", PHASES_START..DIGITS_START)
+        .with_columns("above_digits", DIGITS_START..layout.above.next_column())
+        .with_columns("below_digits", layout.above.next_column()..layout.below.next_column())
+}
+
+/// Trace length for `num_perturbations` perturbed points, the origin and the exempt last row
+pub fn trace_length(num_perturbations: usize) -> usize {
+    (num_perturbations + 2).next_power_of_two().max(CYCLE_LENGTH)
+}
+
+/// Commitment to the model `y = slope * x + intercept`, blinded by `salt`
+pub fn model_commitment(slope: i128, intercept: i128, salt: BaseElement) -> BaseElement {
+    let mut state = sponge_start(slope, intercept, salt, 0);
+    rescue::permute(&mut state);
+    state[0]
+}
+
+fn sponge_start(slope: i128, intercept: i128, salt: BaseElement, cycle: usize) -> [BaseElement; STATE_WIDTH] {
+    [to_field(slope), to_field(intercept), salt + BaseElement::from(cycle as u64), BaseElement::from(MODEL_TAG)]
+}
+
+/// Reason a model and a perturbation set do not satisfy a robustness claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RobustnessError {
+    NoPerturbations,
+    EpsilonTooLarge(u64),
+    /// Evaluating the model overflows 128-bit integers
+    Overflow,
+    /// The output at `x + perturbations[index]` differs from `f(x)` by more than `epsilon`
+    Exceeded { index: usize, difference: i128, epsilon: u64 },
+}
+
+impl fmt::Display for RobustnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RobustnessError::NoPerturbations => write!(f, "no perturbations"),
+            RobustnessError::EpsilonTooLarge(epsilon) => write!(f, "epsilon {epsilon} is larger than {MAX_EPSILON}"),
+            RobustnessError::Overflow => write!(f, "model output overflows 128 bits"),
+            RobustnessError::Exceeded { index, difference, epsilon } => {
+                write!(f, "perturbation {index} changes the output by {difference}, more than {epsilon}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RobustnessError {}
+
+/// Public inputs: the model commitment, the input and its output, the perturbations and the bound
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RobustnessInputs {
+    pub model_commitment: BaseElement,
+    pub x: i128,
+    pub prediction: i128,
+    pub perturbations: Vec<i128>,
+    pub epsilon: u64,
+}
+
+impl ToElements<BaseElement> for RobustnessInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![
+            self.model_commitment,
+            to_field(self.x),
+            to_field(self.prediction),
+            BaseElement::from(self.epsilon),
+            BaseElement::from(self.perturbations.len() as u64),
+        ];
+        elements.extend(self.perturbations.iter().map(|&delta| to_field(delta)));
+        elements
+    }
+}
+
+/// AIR proving `|f(x + δ) - f(x)| <= ε` for a committed model `f` and every public `δ`.
+///
+/// Row 0 is asserted into the origin phase and rows 1 and `k` into the perturbed one, which
+/// puts exactly the rows `1..=k` in the perturbed phase; each of them holds an asserted
+/// perturbed input, and the model constraint applies to them and to the origin row.
+pub struct RobustnessAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: RobustnessInputs,
+}
+
+impl Air for RobustnessAir {
+    type BaseField = BaseElement;
+    type PublicInputs = RobustnessInputs;
+
+    fn new(trace_info: TraceInfo, inputs: RobustnessInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new();
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert!(!inputs.perturbations.is_empty(), "Robustness claims need at least one perturbation");
+        assert_eq!(trace_length(inputs.perturbations.len()), trace_info.length());
+        assert!(inputs.epsilon <= MAX_EPSILON, "Epsilon must be at most {MAX_EPSILON}");
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. Each cycle starts from the model columns and the tag (degree 1)
+        // 3. Slope and intercept consistency (degree 1)
+        // 4. Origin and perturbed rows evaluate the model (degree 2: slope is a constant
+        //    column, the phase flags add one)
+        // 5. Perturbed rows tie epsilon -/+ the output change to the range checked digits (degree 2)
+        // 6. Phase flags: one-hot, origin, perturbed, then padding
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); 3]);
+        degrees.extend(vec![TransitionConstraintDegree::new(1); 2]);
+        degrees.extend(vec![PhaseSelector::gated_degree(1); 3]);
+        degrees.extend(layout.above.constraint_degrees());
+        degrees.extend(layout.below.constraint_degrees());
+        degrees.extend(layout.phases.constraint_degrees());
+
+        let num_assertions = 5 + inputs.perturbations.len() + (inputs.perturbations.len() > 1) as usize;
+        RobustnessAir { context: AirContext::new(trace_info, degrees, num_assertions, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let first = periodic_values[rescue::NUM_PERIODIC_COLUMNS];
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = first * (current[SPONGE] - current[SLOPE]);
+        rest[1] = first * (current[SPONGE + 1] - current[INTERCEPT]);
+        rest[2] = first * (current[SPONGE + 3] - E::from(BaseElement::from(MODEL_TAG)));
+        rest[3] = next[SLOPE] - current[SLOPE];
+        rest[4] = next[INTERCEPT] - current[INTERCEPT];
+
+        let output = current[Y] - current[SLOPE] * current[X] - current[INTERCEPT];
+        rest[5] = layout.phases.gate(current, &[ORIGIN, PERTURBED], output);
+        let change = current[Y] - E::from(to_field(self.inputs.prediction));
+        let epsilon = E::from(BaseElement::from(self.inputs.epsilon));
+        rest[6] = layout.phases.gate(current, &[PERTURBED], epsilon + change - layout.above.recompose(current));
+        rest[7] = layout.phases.gate(current, &[PERTURBED], epsilon - change - layout.below.recompose(current));
+
+        let rest = &mut rest[8..];
+        layout.above.evaluate(current, rest);
+        let rest = &mut rest[layout.above.num_constraints()..];
+        layout.below.evaluate(current, rest);
+        layout.phases.evaluate(current, next, &mut rest[layout.below.num_constraints()..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        let mut columns = rescue::periodic_columns();
+        let mut first = vec![BaseElement::ZERO; CYCLE_LENGTH];
+        first[0] = BaseElement::ONE;
+        columns.push(first);
+        columns
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let inputs = &self.inputs;
+        let num_perturbations = inputs.perturbations.len();
+        let mut assertions = vec![
+            Assertion::single(SPONGE, CYCLE_LENGTH - 1, inputs.model_commitment),
+            Assertion::single(X, 0, to_field(inputs.x)),
+            Assertion::single(Y, 0, to_field(inputs.prediction)),
+            Assertion::single(self.layout.phases.column(ORIGIN), 0, BaseElement::ONE),
+            Assertion::single(self.layout.phases.column(PERTURBED), 1, BaseElement::ONE),
+        ];
+        if num_perturbations > 1 {
+            assertions.push(Assertion::single(self.layout.phases.column(PERTURBED), num_perturbations, BaseElement::ONE));
+        }
+        for (i, &delta) in inputs.perturbations.iter().enumerate() {
+            assertions.push(Assertion::single(X, i + 1, to_field(inputs.x) + to_field(delta)));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving that the model moves by at most `epsilon` around `x`
+#[cfg(feature = "prover")]
+pub fn build_robustness_trace(
+    slope: i128,
+    intercept: i128,
+    salt: BaseElement,
+    x: i128,
+    perturbations: &[i128],
+    epsilon: u64,
+) -> Result<TraceTable<BaseElement>, RobustnessError> {
+    if perturbations.is_empty() {
+        return Err(RobustnessError::NoPerturbations);
+    }
+    if epsilon > MAX_EPSILON {
+        return Err(RobustnessError::EpsilonTooLarge(epsilon));
+    }
+    let evaluate = |x: i128| slope.checked_mul(x).and_then(|y| y.checked_add(intercept)).ok_or(RobustnessError::Overflow);
+    let prediction = evaluate(x)?;
+    let mut points = vec![(x, prediction)];
+    for (index, &delta) in perturbations.iter().enumerate() {
+        let perturbed = x.checked_add(delta).ok_or(RobustnessError::Overflow)?;
+        let output = evaluate(perturbed)?;
+        let difference = output.checked_sub(prediction).ok_or(RobustnessError::Overflow)?;
+        if difference.unsigned_abs() > epsilon as u128 {
+            return Err(RobustnessError::Exceeded { index, difference, epsilon });
+        }
+        points.push((perturbed, output));
+    }
+
+    let layout = Layout::new();
+    let length = trace_length(perturbations.len());
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    row[SLOPE] = to_field(slope);
+    row[INTERCEPT] = to_field(intercept);
+
+    let mut sponge_state = sponge_start(slope, intercept, salt, 0);
+    for i in 0..length {
+        let step = i % CYCLE_LENGTH;
+        if step == 0 {
+            sponge_state = sponge_start(slope, intercept, salt, i / CYCLE_LENGTH);
+        }
+        layout.sponge.write(&mut row, &sponge_state);
+        if step < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut sponge_state, step);
+        }
+
+        // Padding rows repeat the last perturbed point
+        let (point, output) = points[i.min(perturbations.len())];
+        row[X] = to_field(point);
+        row[Y] = to_field(output);
+        if i == length - 1 {
+            // a distinct output keeps the model constraint from vanishing on every row
+            row[Y] += BaseElement::ONE;
+            layout.above.fill_exempt_row(&mut row);
+            layout.below.fill_exempt_row(&mut row);
+            layout.phases.fill_exempt_row(&mut row, if i == perturbations.len() + 1 { PERTURBED } else { PADDING });
+        } else if i == 0 || i > perturbations.len() {
+            layout.above.fill(&mut row, 0).expect("Zero is in range");
+            layout.below.fill(&mut row, 0).expect("Zero is in range");
+            layout.phases.fill(&mut row, if i == 0 { ORIGIN } else { PADDING });
+        } else {
+            let difference = output - prediction;
+            layout.above.fill(&mut row, epsilon as i128 + difference).expect("Difference is within epsilon");
+            layout.below.fill(&mut row, epsilon as i128 - difference).expect("Difference is within epsilon");
+            layout.phases.fill(&mut row, PERTURBED);
+        }
+        trace.update_row(i, &row);
+    }
+
+    Ok(trace)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_robustness_proof() {
+        let (slope, intercept, salt) = (3, -40, BaseElement::new(987_654_321));
+        let (x, perturbations, epsilon) = (25, vec![-2, -1, 1, 2, 0], 6);
+        let inputs = RobustnessInputs {
+            model_commitment: model_commitment(slope, intercept, salt),
+            x,
+            prediction: slope * x + intercept,
+            perturbations: perturbations.clone(),
+            epsilon,
+        };
+
+        let trace = build_robustness_trace(slope, intercept, salt, x, &perturbations, epsilon).unwrap();
+        crate::air_test!(RobustnessAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<RobustnessAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+
+        let options = default_acceptable_options();
+        let tighter = RobustnessInputs { epsilon: 5, ..inputs.clone() };
+        assert!(verify::<RobustnessAir>(proof.clone(), tighter, &options).is_err());
+        let other_model = RobustnessInputs { model_commitment: model_commitment(slope, intercept + 1, salt), ..inputs.clone() };
+        assert!(verify::<RobustnessAir>(proof.clone(), other_model, &options).is_err());
+        let result = verify::<RobustnessAir>(proof, inputs.clone(), &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+
+        // a single perturbation, and six of them, which leave no padding before the exempt row
+        for perturbations in [vec![-1], vec![-3, -2, -1, 1, 2, 3]] {
+            let trace = build_robustness_trace(slope, intercept, salt, x, &perturbations, 9).unwrap();
+            let inputs = RobustnessInputs { perturbations, epsilon: 9, ..inputs.clone() };
+            let proof = prove::<RobustnessAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+            verify::<RobustnessAir>(proof, inputs, &options).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rejects_sensitive_models() {
+        assert_eq!(
+            build_robustness_trace(3, 0, BaseElement::ONE, 10, &[1, -2], 5).unwrap_err(),
+            RobustnessError::Exceeded { index: 1, difference: -6, epsilon: 5 }
+        );
+        assert_eq!(build_robustness_trace(3, 0, BaseElement::ONE, 10, &[], 5).unwrap_err(), RobustnessError::NoPerturbations);
+        assert_eq!(
+            build_robustness_trace(3, 0, BaseElement::ONE, 10, &[1], 1 << 31).unwrap_err(),
+            RobustnessError::EpsilonTooLarge(1 << 31)
+        );
+    }
+}
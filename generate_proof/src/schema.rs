@@ -0,0 +1,279 @@
+//! Names, units and ranges of the data a claim is about.
+//!
+//! A claim such as `x = 8, y = 21` only means something once the verifier knows that `x` is a
+//! floor area in square metres quantized with 4 fractional bits. A [`DataSchema`] records that
+//! for every feature and the target, travels in the [`ProofEnvelope`](crate::envelope::ProofEnvelope)
+//! and is absorbed into its claim hash. Its [`DataSchema::commitment`] is also a public input of
+//! the proof, so a schema swapped after proving fails verification even where the claim hash
+//! is not checked, e.g. in the verifier contract, and verifiers check the claim's points against
+//! its ranges.
+//!
+//! Values are checked against the declared ranges as raw fixed-point integers, i.e. already
+//! multiplied by `2^frac_bits`.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+const SCHEMA_DOMAIN: &[u8] = b"stark-framework/schema/v1";
+const COMMITMENT_DOMAIN: &[u8] = b"stark-framework/schema-json/v1";
+
+/// One column of a dataset
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    /// Unit of the dequantized value, e.g. `"m^2"`; empty for dimensionless columns
+    #[serde(default)]
+    pub unit: String,
+    /// Fractional bits of the fixed-point encoding
+    #[serde(default)]
+    pub frac_bits: u32,
+    /// Smallest valid raw value
+    pub min: i128,
+    /// Largest valid raw value
+    pub max: i128,
+    /// Names of the categories of a categorical column, whose values are category indexes;
+    /// empty for numeric columns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+}
+
+impl ColumnSchema {
+    pub fn new(name: impl Into<String>, unit: impl Into<String>, frac_bits: u32, min: i128, max: i128) -> Self {
+        Self { name: name.into(), unit: unit.into(), frac_bits, min, max, categories: Vec::new() }
+    }
+
+    /// Column holding the index of one of `categories`
+    pub fn categorical(name: impl Into<String>, categories: Vec<String>) -> Self {
+        let max = categories.len() as i128 - 1;
+        Self { name: name.into(), unit: String::new(), frac_bits: 0, min: 0, max, categories }
+    }
+
+    pub fn is_categorical(&self) -> bool {
+        !self.categories.is_empty()
+    }
+
+    /// Index of the category named `name`
+    pub fn category_index(&self, name: &str) -> Option<usize> {
+        self.categories.iter().position(|category| category == name)
+    }
+
+    /// Checks that every value lies in `min..=max`
+    pub fn check(&self, values: impl IntoIterator<Item = i128>) -> Result<(), SchemaError> {
+        for (index, value) in values.into_iter().enumerate() {
+            if value < self.min || value > self.max {
+                return Err(SchemaError::OutOfRange { column: self.name.clone(), index, value });
+            }
+        }
+        Ok(())
+    }
+
+    fn absorb_into(&self, hasher: &mut blake3::Hasher) {
+        for text in [&self.name, &self.unit] {
+            hasher.update(&(text.len() as u32).to_le_bytes());
+            hasher.update(text.as_bytes());
+        }
+        hasher.update(&self.frac_bits.to_le_bytes());
+        hasher.update(&self.min.to_le_bytes());
+        hasher.update(&self.max.to_le_bytes());
+        // numeric columns hash as they did before categories existed
+        if self.is_categorical() {
+            hasher.update(&(self.categories.len() as u32).to_le_bytes());
+            for category in &self.categories {
+                hasher.update(&(category.len() as u32).to_le_bytes());
+                hasher.update(category.as_bytes());
+            }
+        }
+    }
+}
+
+/// Feature and target columns of the data behind a claim
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSchema {
+    pub features: Vec<ColumnSchema>,
+    pub target: ColumnSchema,
+}
+
+/// Errors returned for malformed schemas and for data outside a schema
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaError {
+    /// A column has an empty name or names another column's
+    InvalidName(String),
+    /// A column's `min` is larger than its `max`
+    EmptyRange(String),
+    /// A categorical column has an empty or repeated category, or a range other than its
+    /// category indexes
+    InvalidCategories(String),
+    /// The claim has a different number of features than the schema
+    FeatureCount { expected: usize, found: usize },
+    OutOfRange { column: String, index: usize, value: i128 },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::InvalidName(name) => write!(f, "column name {name:?} is empty or repeated"),
+            SchemaError::EmptyRange(name) => write!(f, "column {name} has min above max"),
+            SchemaError::InvalidCategories(name) => {
+                write!(f, "categories of column {name} are empty, repeated or do not match its range")
+            }
+            SchemaError::FeatureCount { expected, found } => {
+                write!(f, "schema declares {expected} features, the claim has {found}")
+            }
+            SchemaError::OutOfRange { column, index, value } => {
+                write!(f, "value {value} at index {index} is outside the range of column {column}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl DataSchema {
+    pub fn new(features: Vec<ColumnSchema>, target: ColumnSchema) -> Result<Self, SchemaError> {
+        let schema = Self { features, target };
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    /// Checks that column names are non-empty and distinct and that every range is non-empty;
+    /// schemas read from JSON should be validated before use
+    pub fn validate(&self) -> Result<(), SchemaError> {
+        let columns: Vec<&ColumnSchema> = self.features.iter().chain([&self.target]).collect();
+        for (i, column) in columns.iter().enumerate() {
+            if column.name.is_empty() || columns[..i].iter().any(|other| other.name == column.name) {
+                return Err(SchemaError::InvalidName(column.name.clone()));
+            }
+            if column.min > column.max {
+                return Err(SchemaError::EmptyRange(column.name.clone()));
+            }
+            let categories = &column.categories;
+            let repeated = categories.iter().enumerate().any(|(i, c)| c.is_empty() || categories[..i].contains(c));
+            let range = (0, categories.len() as i128 - 1);
+            if column.is_categorical() && (repeated || column.frac_bits != 0 || (column.min, column.max) != range) {
+                return Err(SchemaError::InvalidCategories(column.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `rows` of feature values followed by their `targets` against the declared ranges
+    pub fn check<'a>(
+        &self,
+        rows: impl IntoIterator<Item = &'a [i128]>,
+        targets: impl IntoIterator<Item = i128>,
+    ) -> Result<(), SchemaError> {
+        for (index, row) in rows.into_iter().enumerate() {
+            if row.len() != self.features.len() {
+                return Err(SchemaError::FeatureCount { expected: self.features.len(), found: row.len() });
+            }
+            for (column, &value) in self.features.iter().zip(row) {
+                if value < column.min || value > column.max {
+                    return Err(SchemaError::OutOfRange { column: column.name.clone(), index, value });
+                }
+            }
+        }
+        self.target.check(targets)
+    }
+
+    /// Hash of the schema absorbed into claim hashes
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(SCHEMA_DOMAIN);
+        hasher.update(&(self.features.len() as u32).to_le_bytes());
+        for column in self.features.iter().chain([&self.target]) {
+            column.absorb_into(&mut hasher);
+        }
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Hash of the schema's JSON encoding, a public input of proofs of claims with this schema;
+    /// unlike [`DataSchema::digest`] the verifier contract computes it from the schema bytes of
+    /// the on-chain encoding, see [`commitment_of_json`]
+    pub fn commitment(&self) -> [u8; 32] {
+        commitment_of_json(&serde_json::to_vec(self).expect("Schemas serialize to JSON"))
+    }
+}
+
+/// [`DataSchema::commitment`] of the schema whose canonical JSON encoding is `json`
+pub fn commitment_of_json(json: &[u8]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(COMMITMENT_DOMAIN);
+    hasher.update(json);
+    *hasher.finalize().as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn houses() -> DataSchema {
+        DataSchema::new(
+            vec![ColumnSchema::new("area", "m^2", 4, 0, 1000 << 4)],
+            ColumnSchema::new("price", "kEUR", 0, 0, 5000),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_schema_checks_ranges() {
+        let schema = houses();
+        let rows = [vec![80 << 4], vec![120 << 4]];
+        schema.check(rows.iter().map(Vec::as_slice), [300, 410]).unwrap();
+
+        assert_eq!(
+            schema.check([&[2000i128 << 4][..]], [300]),
+            Err(SchemaError::OutOfRange { column: "area".into(), index: 0, value: 2000 << 4 })
+        );
+        assert_eq!(schema.check([&[1i128, 2][..]], []), Err(SchemaError::FeatureCount { expected: 1, found: 2 }));
+        assert!(matches!(schema.check([], [-1]), Err(SchemaError::OutOfRange { index: 0, .. })));
+
+        let repeated = DataSchema::new(vec![ColumnSchema::new("price", "", 0, 0, 1)], schema.target.clone());
+        assert_eq!(repeated, Err(SchemaError::InvalidName("price".into())));
+        assert!(matches!(
+            DataSchema::new(vec![ColumnSchema::new("area", "", 0, 5, 1)], schema.target.clone()),
+            Err(SchemaError::EmptyRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_digest_covers_units_and_scale() {
+        let schema = houses();
+        let json = serde_json::to_string(&schema).unwrap();
+        assert_eq!(serde_json::from_str::<DataSchema>(&json).unwrap().digest(), schema.digest());
+
+        let mut feet = schema.clone();
+        feet.features[0].unit = "ft^2".into();
+        let mut coarse = schema.clone();
+        coarse.features[0].frac_bits = 2;
+        assert_ne!(feet.digest(), schema.digest());
+        assert_ne!(coarse.digest(), schema.digest());
+        assert_eq!(schema.commitment(), commitment_of_json(json.as_bytes()));
+        assert_ne!(feet.commitment(), schema.commitment());
+    }
+
+    #[test]
+    fn test_categorical_columns() {
+        let regions = vec!["north".to_string(), "south".into(), "east".into()];
+        let region = ColumnSchema::categorical("region", regions.clone());
+        assert_eq!((region.min, region.max), (0, 2));
+        assert_eq!(region.category_index("east"), Some(2));
+        let schema = DataSchema::new(vec![houses().features[0].clone(), region], houses().target).unwrap();
+        schema.check([&[80i128 << 4, 2][..]], [300]).unwrap();
+        assert!(matches!(schema.check([&[80i128 << 4, 3][..]], [300]), Err(SchemaError::OutOfRange { .. })));
+
+        let json = serde_json::to_string(&schema).unwrap();
+        assert_eq!(serde_json::from_str::<DataSchema>(&json).unwrap(), schema);
+        assert!(!serde_json::to_string(&houses()).unwrap().contains("categories"));
+        let mut renamed = schema.clone();
+        renamed.features[1].categories[2] = "west".into();
+        assert_ne!(renamed.digest(), schema.digest());
+
+        let repeated = ColumnSchema::categorical("region", vec!["north".into(), "north".into()]);
+        let widened = ColumnSchema { max: 5, ..ColumnSchema::categorical("region", regions) };
+        for column in [repeated, widened] {
+            let invalid = DataSchema::new(vec![column], houses().target);
+            assert_eq!(invalid, Err(SchemaError::InvalidCategories("region".into())));
+        }
+    }
+}
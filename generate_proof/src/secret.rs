@@ -0,0 +1,151 @@
+//! Zeroization of witness data.
+//!
+//! Private parameters and the traces built from them linger in freed memory unless they are
+//! overwritten first, and a later allocation, a core dump or a swapped page can reveal them.
+//! Structures of this crate holding witness data are wiped with [`zeroize`] before their
+//! memory is released:
+//!
+//! - [`SecretTrace`] and [`PooledTrace`](crate::pool::PooledTrace) hold the trace of a proving
+//!   job; the first wipes its columns on drop, the second when its columns return to the
+//!   [`TracePool`](crate::pool::TracePool), which never keeps an idle buffer with old data.
+//!   [`fill_linear_regression_trace`](crate::regression::fill_linear_regression_trace) wipes
+//!   the row buffer it fills them through.
+//! - [`LinearShare`](crate::shares::LinearShare) and
+//!   [`SharedLinearWitness`](crate::shares::SharedLinearWitness) hold shares of the parameters
+//!   and wipe them as soon as the trace is built.
+//! - [`SecretScope`] holds any other zeroizable value, e.g. parameters combined in memory, and
+//!   wipes it when it goes out of scope.
+//!
+//! Everything else is not wiped: `TraceTable`s, the sample values of public inputs, and the
+//! low-degree extension, trace polynomials and constraint evaluations that Winterfell's prover
+//! allocates internally and frees without zeroizing.
+
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    matrix::ColMatrix,
+    EvaluationFrame, Trace, TraceInfo,
+};
+use zeroize::Zeroize;
+
+/// Overwrites `elements` with zeros in a way the compiler cannot elide
+pub fn zeroize_elements(elements: &mut [BaseElement]) {
+    for element in elements.iter_mut() {
+        // SAFETY: `element` is a valid, aligned and exclusive reference
+        unsafe { core::ptr::write_volatile(element, BaseElement::ZERO) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Field elements wiped by [`Zeroize`], e.g. parameters combined from shares
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretElements(pub Vec<BaseElement>);
+
+impl SecretElements {
+    pub fn zeroed(len: usize) -> Self {
+        Self(vec![BaseElement::ZERO; len])
+    }
+}
+
+impl Zeroize for SecretElements {
+    fn zeroize(&mut self) {
+        zeroize_elements(&mut self.0);
+    }
+}
+
+impl Deref for SecretElements {
+    type Target = [BaseElement];
+
+    fn deref(&self) -> &[BaseElement] {
+        &self.0
+    }
+}
+
+impl DerefMut for SecretElements {
+    fn deref_mut(&mut self) -> &mut [BaseElement] {
+        &mut self.0
+    }
+}
+
+/// A secret value that is zeroized when it goes out of scope and never printed
+pub struct SecretScope<T: Zeroize>(T);
+
+impl<T: Zeroize> SecretScope<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize> Deref for SecretScope<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for SecretScope<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for SecretScope<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretScope(..)")
+    }
+}
+
+impl<T: Zeroize> Drop for SecretScope<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Execution trace whose columns are zeroized when the prover drops it
+pub struct SecretTrace {
+    info: TraceInfo,
+    main: ColMatrix<BaseElement>,
+}
+
+impl SecretTrace {
+    /// Trace over `columns`, e.g. filled by a `fill_*_trace` function
+    pub fn new(columns: Vec<Vec<BaseElement>>) -> Self {
+        let main = ColMatrix::new(columns);
+        let info = TraceInfo::new(main.num_cols(), main.num_rows());
+        Self { info, main }
+    }
+}
+
+impl Trace for SecretTrace {
+    type BaseField = BaseElement;
+
+    fn info(&self) -> &TraceInfo {
+        &self.info
+    }
+
+    fn main_segment(&self) -> &ColMatrix<BaseElement> {
+        &self.main
+    }
+
+    fn read_main_frame(&self, row_idx: usize, frame: &mut EvaluationFrame<BaseElement>) {
+        let next_row_idx = (row_idx + 1) % self.info.length();
+        self.main.read_row_into(row_idx, frame.current_mut());
+        self.main.read_row_into(next_row_idx, frame.next_mut());
+    }
+}
+
+impl Zeroize for SecretTrace {
+    fn zeroize(&mut self) {
+        self.main.columns_mut().for_each(zeroize_elements);
+    }
+}
+
+impl Drop for SecretTrace {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
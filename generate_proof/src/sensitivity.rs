@@ -0,0 +1,402 @@
+//! Sensitivity claims: bounds on individual private parameters of a committed model.
+//!
+//! A regulator may need to know that "the model's sensitivity to income is positive and below
+//! 0.5" without learning the coefficient itself. [`SensitivityAir`] proves that every
+//! parameter of a model committed as [`parameters_commitment`] lies within a public
+//! [`ParameterBound`], where [`ParameterBound::UNBOUNDED`] leaves a parameter unconstrained and
+//! [`ParameterBound::positive_below`] states the sentence above for fixed-point parameters.
+//!
+//! Parameters are signed 64-bit values on the scale the caller quantized them to. The trace
+//! has one [`CYCLE_LENGTH`]-row cycle per parameter plus one that finishes the sponge, which
+//! starts from the salt and the number of parameters and absorbs each parameter at the end of
+//! its cycle. Within a cycle the parameter and its asserted bounds stay constant, and
+//! `parameter - lower` and `upper - parameter` are range checked to [`GAP_BITS`] bits on every
+//! row, so both are non-negative.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::boundary::BoundaryBuilder;
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::RangeCheck;
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`SensitivityAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "sensitivity/v1";
+
+/// Width of the range checked distances to either bound, enough for any pair of `i64` values
+pub const GAP_BITS: usize = 64;
+
+/// Sponge tag of [`parameters_commitment`]; tags 1 to 15 are used elsewhere
+const PARAMETERS_TAG: u64 = 16;
+const DIGIT_BITS: usize = 2;
+
+// Trace layout: sponge state, the cycle's parameter and bounds, then the digits of
+// `parameter - lower` and of `upper - parameter`
+const SPONGE: usize = 0;
+const PARAMETER: usize = SPONGE + STATE_WIDTH;
+const LOWER: usize = PARAMETER + 1;
+const UPPER: usize = LOWER + 1;
+const DIGITS_START: usize = UPPER + 1;
+
+struct Layout {
+    sponge: RescueGadget,
+    above_lower: RangeCheck,
+    below_upper: RangeCheck,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let above_lower = RangeCheck::with_digit_bits(DIGITS_START, GAP_BITS, DIGIT_BITS);
+        let below_upper = RangeCheck::with_digit_bits(above_lower.next_column(), GAP_BITS, DIGIT_BITS);
+        Self { sponge: RescueGadget::new(SPONGE), above_lower, below_upper }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.below_upper.next_column()
+    }
+}
+
+/// Number of trace columns
+pub fn trace_width() -> usize {
+    Layout::new().trace_width()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    let layout = Layout::new();
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..PARAMETER)
+        .with_column("parameter", PARAMETER)
+        .with_column("lower", LOWER)
+        .with_column("upper", UPPER)
+        .with_columns("above_lower_digits", DIGITS_START..layout.above_lower.next_column())
+        .with_columns("below_upper_digits", layout.above_lower.next_column()..layout.below_upper.next_column())
+}
+
+/// Trace length for a model of `num_parameters` parameters
+pub fn trace_length(num_parameters: usize) -> usize {
+    ((num_parameters + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+pub(crate) fn sponge_start(num_parameters: usize, salt: BaseElement) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_parameters, PARAMETERS_TAG);
+    state[0] = salt;
+    state
+}
+
+/// Commitment to `parameters`, blinded by `salt`
+pub fn parameters_commitment(parameters: &[i64], salt: BaseElement) -> BaseElement {
+    let mut state = sponge_start(parameters.len(), salt);
+    rescue::permute(&mut state);
+    for &parameter in parameters {
+        state[0] += to_field(parameter as i128);
+        rescue::permute(&mut state);
+    }
+    state[0]
+}
+
+/// Inclusive range a parameter is claimed to lie in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParameterBound {
+    pub lower: i64,
+    pub upper: i64,
+}
+
+impl ParameterBound {
+    /// Leaves the parameter unconstrained
+    pub const UNBOUNDED: ParameterBound = ParameterBound { lower: i64::MIN, upper: i64::MAX };
+
+    pub fn between(lower: i64, upper: i64) -> Self {
+        Self { lower, upper }
+    }
+
+    /// `0 < parameter < bound`, e.g. a positive sensitivity below a fixed-point 0.5
+    pub fn positive_below(bound: i64) -> Self {
+        Self::between(1, bound - 1)
+    }
+
+    /// `0 < parameter`
+    pub fn positive() -> Self {
+        Self::between(1, i64::MAX)
+    }
+
+    /// `parameter < 0`
+    pub fn negative() -> Self {
+        Self::between(i64::MIN, -1)
+    }
+
+    /// `|parameter| < bound`
+    pub fn magnitude_below(bound: i64) -> Self {
+        Self::between(1 - bound, bound - 1)
+    }
+
+    pub fn contains(&self, parameter: i64) -> bool {
+        (self.lower..=self.upper).contains(&parameter)
+    }
+}
+
+/// Reason a model does not satisfy a sensitivity claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SensitivityError {
+    /// The claim bounds no parameter
+    NoBounds,
+    /// There is not one bound per parameter
+    BoundCount { parameters: usize, bounds: usize },
+    /// A bound whose lower end exceeds its upper end
+    EmptyBound { index: usize },
+    /// Parameter `index` lies outside its bound
+    OutOfBounds { index: usize },
+}
+
+impl fmt::Display for SensitivityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensitivityError::NoBounds => write!(f, "the claim bounds no parameter"),
+            SensitivityError::BoundCount { parameters, bounds } => {
+                write!(f, "{bounds} bounds for {parameters} parameters")
+            }
+            SensitivityError::EmptyBound { index } => write!(f, "bound of parameter {index} is empty"),
+            // the value itself stays private
+            SensitivityError::OutOfBounds { index } => write!(f, "parameter {index} lies outside its bound"),
+        }
+    }
+}
+
+impl std::error::Error for SensitivityError {}
+
+fn check_bounds(bounds: &[ParameterBound]) -> Result<(), SensitivityError> {
+    if let Some(index) = bounds.iter().position(|bound| bound.lower > bound.upper) {
+        return Err(SensitivityError::EmptyBound { index });
+    }
+    if bounds.iter().all(|&bound| bound == ParameterBound::UNBOUNDED) {
+        return Err(SensitivityError::NoBounds);
+    }
+    Ok(())
+}
+
+/// Public inputs: the parameters commitment and one bound per parameter
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SensitivityInputs {
+    pub commitment: BaseElement,
+    pub bounds: Vec<ParameterBound>,
+}
+
+impl ToElements<BaseElement> for SensitivityInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![self.commitment, BaseElement::from(self.bounds.len() as u64)];
+        for bound in &self.bounds {
+            elements.extend([to_field(bound.lower as i128), to_field(bound.upper as i128)]);
+        }
+        elements
+    }
+}
+
+/// AIR proving that every parameter of a committed model lies within its public bound
+pub struct SensitivityAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: SensitivityInputs,
+}
+
+impl SensitivityAir {
+    /// The bounds of every parameter are read at the start of its cycle, as one sequence
+    /// assertion per bound padded with the zero bounds of the padding cycles
+    fn boundary(trace_length: usize, inputs: &SensitivityInputs) -> BoundaryBuilder {
+        let bounds = &inputs.bounds;
+        // the salt in the first lane stays private
+        let start = sponge_start(bounds.len(), BaseElement::ZERO);
+        let mut boundary = BoundaryBuilder::new(trace_length);
+        for (i, &value) in start.iter().enumerate().skip(1) {
+            boundary.single(SPONGE + i, 0, value);
+        }
+        let lower: Vec<_> = bounds.iter().map(|bound| to_field(bound.lower as i128)).collect();
+        let upper: Vec<_> = bounds.iter().map(|bound| to_field(bound.upper as i128)).collect();
+        boundary
+            .single(SPONGE, (bounds.len() + 1) * CYCLE_LENGTH - 1, inputs.commitment)
+            .samples(LOWER, 0, CYCLE_LENGTH, &lower, BaseElement::ZERO)
+            .samples(UPPER, 0, CYCLE_LENGTH, &upper, BaseElement::ZERO);
+        boundary
+    }
+}
+
+impl Air for SensitivityAir {
+    type BaseField = BaseElement;
+    type PublicInputs = SensitivityInputs;
+
+    fn new(trace_info: TraceInfo, inputs: SensitivityInputs, options: ProofOptions) -> Self {
+        let layout = Layout::new();
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(trace_length(inputs.bounds.len()), trace_info.length());
+        assert_eq!(check_bounds(&inputs.bounds), Ok(()), "Sensitivity claims need non-empty bounds");
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the parameter at the end of a cycle (degree 1)
+        // 3. Parameter and bounds are constant within a cycle (degree 1)
+        // 4. Both distances to the bounds equal their range checked digits (degree 1)
+        // 5. Digits of both distances (degree 4)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 3]);
+        degrees.extend(vec![TransitionConstraintDegree::new(1); 2]);
+        degrees.extend(layout.above_lower.constraint_degrees());
+        degrees.extend(layout.below_upper.constraint_degrees());
+
+        let num_assertions = Self::boundary(trace_info.length(), &inputs).num_assertions();
+        SensitivityAir { context: AirContext::new(trace_info, degrees, num_assertions, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[PARAMETER]);
+        for lane in 1..STATE_WIDTH {
+            rest[lane] = last * (next[SPONGE + lane] - current[SPONGE + lane]);
+        }
+        for (i, column) in [PARAMETER, LOWER, UPPER].into_iter().enumerate() {
+            rest[STATE_WIDTH + i] = mask * (next[column] - current[column]);
+        }
+        rest[7] = current[PARAMETER] - current[LOWER] - layout.above_lower.recompose(current);
+        rest[8] = current[UPPER] - current[PARAMETER] - layout.below_upper.recompose(current);
+
+        let rest = &mut rest[9..];
+        layout.above_lower.evaluate(current, rest);
+        layout.below_upper.evaluate(current, &mut rest[layout.above_lower.num_constraints()..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        Self::boundary(self.trace_length(), &self.inputs).build()
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving that `parameters` lie within `bounds`, with the inputs
+/// it proves
+#[cfg(feature = "prover")]
+pub fn build_sensitivity_trace(
+    parameters: &[i64],
+    salt: BaseElement,
+    bounds: &[ParameterBound],
+) -> Result<(TraceTable<BaseElement>, SensitivityInputs), SensitivityError> {
+    if parameters.len() != bounds.len() {
+        return Err(SensitivityError::BoundCount { parameters: parameters.len(), bounds: bounds.len() });
+    }
+    check_bounds(bounds)?;
+    if let Some(index) = parameters.iter().zip(bounds).position(|(&parameter, bound)| !bound.contains(parameter)) {
+        return Err(SensitivityError::OutOfBounds { index });
+    }
+
+    let layout = Layout::new();
+    let length = trace_length(parameters.len());
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut state = sponge_start(parameters.len(), salt);
+    for i in 0..length {
+        let (cycle, step) = (i / CYCLE_LENGTH, i % CYCLE_LENGTH);
+        // cycles past the parameters hold a zero parameter within zero bounds
+        let parameter = parameters.get(cycle).copied().unwrap_or(0);
+        let bound = bounds.get(cycle).copied().unwrap_or(ParameterBound::between(0, 0));
+        row[PARAMETER] = to_field(parameter as i128);
+        row[LOWER] = to_field(bound.lower as i128);
+        row[UPPER] = to_field(bound.upper as i128);
+        layout.sponge.write(&mut row, &state);
+        if step < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, step);
+        } else {
+            state[0] += row[PARAMETER];
+        }
+        if i == length - 1 {
+            layout.above_lower.fill_exempt_row(&mut row);
+            layout.below_upper.fill_exempt_row(&mut row);
+        } else {
+            let above = parameter as i128 - bound.lower as i128;
+            layout.above_lower.fill(&mut row, above).expect("Parameters lie within their bounds");
+            layout.below_upper.fill(&mut row, bound.upper as i128 - parameter as i128).expect("Checked above");
+        }
+        trace.update_row(i, &row);
+    }
+
+    let inputs = SensitivityInputs { commitment: parameters_commitment(parameters, salt), bounds: bounds.to_vec() };
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::fixed_point::{quantize, DEFAULT_FRACTIONAL_BITS};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_sensitivity_proof() {
+        let half = quantize(0.5, DEFAULT_FRACTIONAL_BITS) as i64;
+        // intercept, income and debt coefficients
+        let parameters = [quantize(-3.25, DEFAULT_FRACTIONAL_BITS) as i64, half - 1, -half];
+        let bounds = [ParameterBound::UNBOUNDED, ParameterBound::positive_below(half), ParameterBound::negative()];
+        let salt = BaseElement::new(0xabc);
+        let (trace, inputs) = build_sensitivity_trace(&parameters, salt, &bounds).unwrap();
+        crate::air_test!(SensitivityAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<SensitivityAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options()).unwrap();
+        let options = default_acceptable_options();
+        let mut tighter = inputs.clone();
+        tighter.bounds[1] = ParameterBound::positive_below(half - 1);
+        assert!(verify::<SensitivityAir>(proof.clone(), tighter, &options).is_err());
+        let other_model = SensitivityInputs { commitment: parameters_commitment(&[0, 1, -1], salt), ..inputs.clone() };
+        assert!(verify::<SensitivityAir>(proof.clone(), other_model, &options).is_err());
+        let result = verify::<SensitivityAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_rejects_parameters_outside_bounds() {
+        let salt = BaseElement::ONE;
+        let bounds = [ParameterBound::positive_below(100), ParameterBound::magnitude_below(5)];
+        assert_eq!(
+            build_sensitivity_trace(&[99, -5], salt, &bounds).unwrap_err(),
+            SensitivityError::OutOfBounds { index: 1 }
+        );
+        let zero = build_sensitivity_trace(&[0, 4], salt, &bounds);
+        assert_eq!(zero.unwrap_err(), SensitivityError::OutOfBounds { index: 0 });
+        assert_eq!(
+            build_sensitivity_trace(&[1], salt, &[ParameterBound::UNBOUNDED]).unwrap_err(),
+            SensitivityError::NoBounds
+        );
+        assert_eq!(
+            build_sensitivity_trace(&[1, 2], salt, &[ParameterBound::between(3, 2), bounds[1]]).unwrap_err(),
+            SensitivityError::EmptyBound { index: 0 }
+        );
+        assert!(build_sensitivity_trace(&[99, -4], salt, &bounds).is_ok());
+    }
+}
@@ -0,0 +1,389 @@
+//! Byte encodings of [`ProofEnvelope`]s.
+//!
+//! [`Encoding::Compact`] is the envelope's own [`ProofEnvelope::to_bytes`] format, used for
+//! files and the APIs. [`Encoding::OnChain`] lays the envelope out in 32-byte words so that a
+//! verifier contract finds every header field and public input at a fixed offset and can read
+//! it with a single `calldataload`. `verifier/EnvelopeDecoder.sol` decodes it; the constants
+//! below and the ones in that library are compared by a test.
+//!
+//! On-chain layout, all integers big-endian and right-aligned in their word:
+//!
+//! | offset                  | word                                                   |
+//! |-------------------------|--------------------------------------------------------|
+//! | `0x00`                  | magic `STKO`, version byte, 27 zero bytes              |
+//! | `0x20`                  | model id                                               |
+//! | `0x40`                  | model commitment                                       |
+//! | `0x60`                  | constraint fingerprint                                 |
+//! | `0x80`                  | number of public inputs `n`                            |
+//! | `0xa0`                  | proof length in bytes                                  |
+//! | `0xc0`                  | schema length in bytes, 0 without a schema             |
+//! | `0xe0`                  | signer length in bytes, 96 if signed and 0 otherwise   |
+//! | `0x100`                 | beacon length in bytes, 0 without a beacon round       |
+//! | `0x120`                 | terms hash, zero without one                           |
+//! | `0x140`                 | claim nonce, zero without one                          |
+//! | `0x160 + 0x20 * i`      | public input `i`                                       |
+//! | `0x160 + 0x20 * n`      | `Proof::to_bytes()`, zero padded to whole words        |
+//! | after the proof         | canonical JSON [`DataSchema`], zero padded             |
+//! | after the schema        | operator public key, then its 64-byte signature        |
+//! | after the signer        | JSON [`BeaconRound`], zero padded to whole words       |
+//!
+//! The nonce word directly precedes the public inputs, so a contract hashes both with one
+//! `keccak256` over a calldata slice, see [`settlement_hash`](crate::nonce::settlement_hash).
+//!
+//! The on-chain encoding leaves out the envelope's [`Provenance`](crate::provenance::Provenance)
+//! and [`HardwareAttestation`](crate::hardware::HardwareAttestation), which a contract has no
+//! use for.
+//!
+//! The proof itself stays an opaque byte string in Winterfell's serialization. Decoding is
+//! strict: padding must be zero, elements canonical, the schema in the JSON `serde_json`
+//! writes and the length exact, so every envelope has a single on-chain encoding.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, StarkField},
+    Proof,
+};
+
+#[cfg(doc)]
+use crate::beacon::BeaconRound;
+use crate::envelope::{decode_beacon, EnvelopeError, ProofEnvelope};
+use crate::fingerprint::ConstraintFingerprint;
+use crate::nonce::ClaimNonce;
+use crate::operator::{OperatorPublicKey, OperatorSignature, PUBLIC_KEY_BYTES, SIGNATURE_BYTES};
+use crate::registry::{ModelCommitment, ModelId};
+use crate::schema::DataSchema;
+use crate::terms::TermsHash;
+
+/// Size of an on-chain word
+pub const WORD: usize = 32;
+pub const ON_CHAIN_MAGIC: &[u8; 4] = b"STKO";
+pub const ON_CHAIN_VERSION: u8 = 6;
+/// Oldest on-chain version [`decode_on_chain_versioned`] reads; the layout has not changed since
+const OLDEST_ON_CHAIN_LAYOUT: u8 = 5;
+
+pub const MODEL_ID_OFFSET: usize = 0x20;
+pub const MODEL_COMMITMENT_OFFSET: usize = 0x40;
+pub const FINGERPRINT_OFFSET: usize = 0x60;
+pub const NUM_INPUTS_OFFSET: usize = 0x80;
+pub const PROOF_LENGTH_OFFSET: usize = 0xa0;
+pub const SCHEMA_LENGTH_OFFSET: usize = 0xc0;
+pub const SIGNER_LENGTH_OFFSET: usize = 0xe0;
+pub const BEACON_LENGTH_OFFSET: usize = 0x100;
+pub const TERMS_HASH_OFFSET: usize = 0x120;
+pub const NONCE_OFFSET: usize = 0x140;
+/// Offset of the first public input; input `i` starts at `INPUTS_OFFSET + WORD * i`
+pub const INPUTS_OFFSET: usize = 0x160;
+/// Length of the signer section of a signed envelope
+pub const SIGNER_LENGTH: usize = PUBLIC_KEY_BYTES + SIGNATURE_BYTES;
+
+/// How an envelope is turned into bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Length-prefixed little-endian fields, see [`crate::envelope`]
+    Compact,
+    /// Fixed offsets in 32-byte words for verifier contracts
+    OnChain,
+}
+
+impl Encoding {
+    /// The encoding of `bytes`, told apart by their magic
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes.get(..4)? {
+            magic if magic == ON_CHAIN_MAGIC => Some(Encoding::OnChain),
+            b"STKE" => Some(Encoding::Compact),
+            _ => None,
+        }
+    }
+
+    pub fn encode(self, envelope: &ProofEnvelope) -> Vec<u8> {
+        match self {
+            Encoding::Compact => envelope.to_bytes(),
+            Encoding::OnChain => encode_on_chain(envelope),
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> Result<ProofEnvelope, EnvelopeError> {
+        match self {
+            Encoding::Compact => ProofEnvelope::from_bytes(bytes),
+            Encoding::OnChain => decode_on_chain(bytes),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Compact => write!(f, "compact"),
+            Encoding::OnChain => write!(f, "on-chain"),
+        }
+    }
+}
+
+/// Offset of the proof bytes in an on-chain envelope with `num_inputs` public inputs
+pub fn proof_offset(num_inputs: usize) -> usize {
+    INPUTS_OFFSET + WORD * num_inputs
+}
+
+fn padded(len: usize) -> usize {
+    len.div_ceil(WORD) * WORD
+}
+
+fn uint_word(value: u128) -> [u8; WORD] {
+    let mut word = [0u8; WORD];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+fn encode_on_chain(envelope: &ProofEnvelope) -> Vec<u8> {
+    let proof = envelope.proof.to_bytes();
+    let schema = match &envelope.schema {
+        Some(schema) => serde_json::to_vec(schema).expect("Schemas serialize to JSON"),
+        None => Vec::new(),
+    };
+    let signer = match &envelope.signer {
+        Some(signer) => [&signer.public_key.0[..], &signer.signature].concat(),
+        None => Vec::new(),
+    };
+    let beacon = match &envelope.beacon {
+        Some(beacon) => serde_json::to_vec(beacon).expect("Beacon rounds serialize to JSON"),
+        None => Vec::new(),
+    };
+    let num_inputs = envelope.public_inputs.len();
+    let capacity =
+        proof_offset(num_inputs) + padded(proof.len()) + padded(schema.len()) + signer.len() + padded(beacon.len());
+    let mut bytes = Vec::with_capacity(capacity);
+
+    let mut header = [0u8; WORD];
+    header[..4].copy_from_slice(ON_CHAIN_MAGIC);
+    header[4] = ON_CHAIN_VERSION;
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&envelope.model_id.0);
+    bytes.extend_from_slice(&envelope.model_commitment.0);
+    bytes.extend_from_slice(&envelope.constraint_fingerprint.0);
+    for len in [num_inputs, proof.len(), schema.len(), signer.len(), beacon.len()] {
+        bytes.extend_from_slice(&uint_word(len as u128));
+    }
+    bytes.extend_from_slice(&envelope.terms_hash.map_or([0u8; WORD], |terms_hash| terms_hash.0));
+    bytes.extend_from_slice(&envelope.nonce.map_or([0u8; WORD], |nonce| nonce.0));
+    for input in &envelope.public_inputs {
+        bytes.extend_from_slice(&uint_word(input.as_int()));
+    }
+    for section in [&proof, &schema, &signer, &beacon] {
+        bytes.extend_from_slice(section);
+        bytes.resize(padded(bytes.len()), 0);
+    }
+    bytes
+}
+
+/// Reads the word at `offset`
+fn word(bytes: &[u8], offset: usize) -> Result<[u8; WORD], EnvelopeError> {
+    let end = offset.checked_add(WORD).ok_or(EnvelopeError::Truncated)?;
+    Ok(bytes.get(offset..end).ok_or(EnvelopeError::Truncated)?.try_into().expect("slice has exactly WORD bytes"))
+}
+
+/// Reads the word at `offset` as an integer that must fit into 128 bits
+fn uint(bytes: &[u8], offset: usize) -> Result<u128, EnvelopeError> {
+    let word = word(bytes, offset)?;
+    if word[..16].iter().any(|&b| b != 0) {
+        return Err(EnvelopeError::InvalidPadding);
+    }
+    Ok(u128::from_be_bytes(word[16..].try_into().expect("16-byte half")))
+}
+
+/// Reads a byte length, which must leave room for its section
+fn length(bytes: &[u8], offset: usize) -> Result<usize, EnvelopeError> {
+    let len = uint(bytes, offset)?;
+    if len > bytes.len() as u128 {
+        return Err(EnvelopeError::Truncated);
+    }
+    Ok(len as usize)
+}
+
+/// The `len` bytes at `offset`, checking that the padding up to the next word is zero
+fn section(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], EnvelopeError> {
+    let padded_end = offset.checked_add(padded(len)).ok_or(EnvelopeError::Truncated)?;
+    let slot = bytes.get(offset..padded_end).ok_or(EnvelopeError::Truncated)?;
+    if slot[len..].iter().any(|&b| b != 0) {
+        return Err(EnvelopeError::InvalidPadding);
+    }
+    Ok(&slot[..len])
+}
+
+fn decode_on_chain(bytes: &[u8]) -> Result<ProofEnvelope, EnvelopeError> {
+    match decode_on_chain_versioned(bytes)? {
+        (ON_CHAIN_VERSION, envelope) => Ok(envelope),
+        (version, _) => Err(EnvelopeError::UnsupportedVersion(version)),
+    }
+}
+
+/// Decodes an on-chain envelope of any version with the current layout, together with its
+/// version
+pub fn decode_on_chain_versioned(bytes: &[u8]) -> Result<(u8, ProofEnvelope), EnvelopeError> {
+    let header = word(bytes, 0)?;
+    if &header[..4] != ON_CHAIN_MAGIC {
+        return Err(EnvelopeError::BadMagic);
+    }
+    let version = header[4];
+    if !(OLDEST_ON_CHAIN_LAYOUT..=ON_CHAIN_VERSION).contains(&version) {
+        return Err(EnvelopeError::UnsupportedVersion(version));
+    }
+    if header[5..].iter().any(|&b| b != 0) {
+        return Err(EnvelopeError::InvalidPadding);
+    }
+
+    let num_inputs = length(bytes, NUM_INPUTS_OFFSET)?;
+    let proof_len = length(bytes, PROOF_LENGTH_OFFSET)?;
+    let schema_len = length(bytes, SCHEMA_LENGTH_OFFSET)?;
+    let signer_len = length(bytes, SIGNER_LENGTH_OFFSET)?;
+    let beacon_len = length(bytes, BEACON_LENGTH_OFFSET)?;
+    if num_inputs > bytes.len() / WORD {
+        return Err(EnvelopeError::Truncated);
+    }
+
+    let mut public_inputs = Vec::with_capacity(num_inputs);
+    for i in 0..num_inputs {
+        let value = uint(bytes, INPUTS_OFFSET + WORD * i)?;
+        if value >= BaseElement::MODULUS {
+            return Err(EnvelopeError::NonCanonicalElement(value));
+        }
+        public_inputs.push(BaseElement::new(value));
+    }
+
+    let offset = proof_offset(num_inputs);
+    let proof = Proof::from_bytes(section(bytes, offset, proof_len)?)
+        .map_err(|err| EnvelopeError::InvalidProof(err.to_string()))?;
+    let offset = offset + padded(proof_len);
+    let schema = match schema_len {
+        0 => None,
+        len => {
+            let json = section(bytes, offset, len)?;
+            let schema: DataSchema =
+                serde_json::from_slice(json).map_err(|err| EnvelopeError::InvalidSchema(err.to_string()))?;
+            schema.validate().map_err(|err| EnvelopeError::InvalidSchema(err.to_string()))?;
+            // the verifier contract hashes these bytes into the schema commitment
+            if serde_json::to_vec(&schema).expect("Schemas serialize to JSON") != json {
+                return Err(EnvelopeError::InvalidSchema("schema is not in canonical JSON".into()));
+            }
+            Some(schema)
+        }
+    };
+    let offset = offset + padded(schema_len);
+    let signer = match signer_len {
+        0 => None,
+        SIGNER_LENGTH => {
+            let signer = section(bytes, offset, SIGNER_LENGTH)?;
+            let (public_key, signature) = signer.split_at(PUBLIC_KEY_BYTES);
+            Some(OperatorSignature {
+                public_key: OperatorPublicKey(public_key.try_into().expect("32-byte key")),
+                signature: signature.try_into().expect("64-byte signature"),
+            })
+        }
+        _ => return Err(EnvelopeError::InvalidSigner),
+    };
+    let offset = offset + signer_len;
+    let beacon = match beacon_len {
+        0 => None,
+        len => Some(decode_beacon(section(bytes, offset, len)?)?),
+    };
+    let end = offset + padded(beacon_len);
+    if bytes.len() != end {
+        return Err(EnvelopeError::TrailingBytes(bytes.len().saturating_sub(end)));
+    }
+
+    let envelope = ProofEnvelope {
+        model_id: ModelId(word(bytes, MODEL_ID_OFFSET)?),
+        model_commitment: ModelCommitment(word(bytes, MODEL_COMMITMENT_OFFSET)?),
+        constraint_fingerprint: ConstraintFingerprint(word(bytes, FINGERPRINT_OFFSET)?),
+        schema,
+        signer,
+        beacon,
+        nonce: match word(bytes, NONCE_OFFSET)? {
+            nonce if nonce == [0u8; WORD] => None,
+            nonce => Some(ClaimNonce(nonce)),
+        },
+        terms_hash: match word(bytes, TERMS_HASH_OFFSET)? {
+            terms_hash if terms_hash == [0u8; WORD] => None,
+            terms_hash => Some(TermsHash(terms_hash)),
+        },
+        provenance: None,
+        attestation: None,
+        public_inputs,
+        proof,
+    };
+    Ok((version, envelope))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DomainTag;
+    use crate::operator::{verify_signer, OperatorKey};
+    use crate::schema::ColumnSchema;
+
+    fn envelope() -> ProofEnvelope {
+        let area = ColumnSchema::new("area", "m^2", 0, 0, 500);
+        let schema = DataSchema::new(vec![area], ColumnSchema::new("price", "kEUR", 0, 0, 5000)).unwrap();
+        ProofEnvelope::new(
+            ModelId::from_name("house-prices"),
+            ModelCommitment([9u8; 32]),
+            ConstraintFingerprint([4u8; 32]),
+            vec![BaseElement::new(8), BaseElement::new(21), -BaseElement::new(1)],
+            Proof::new_dummy(),
+        )
+        .with_schema(schema)
+    }
+
+    #[test]
+    fn test_on_chain_round_trip_and_offsets() {
+        let envelope = envelope();
+        let bytes = Encoding::OnChain.encode(&envelope);
+        assert_eq!(bytes.len() % WORD, 0);
+        assert_eq!(Encoding::detect(&bytes), Some(Encoding::OnChain));
+        assert_eq!(Encoding::detect(&envelope.to_bytes()), Some(Encoding::Compact));
+
+        assert_eq!(bytes[MODEL_ID_OFFSET..MODEL_ID_OFFSET + WORD], envelope.model_id.0);
+        let input = |i: usize| {
+            let offset = INPUTS_OFFSET + WORD * i;
+            u128::from_be_bytes(bytes[offset + 16..offset + WORD].try_into().unwrap())
+        };
+        assert_eq!(input(1), 21);
+        assert_eq!(input(2), BaseElement::MODULUS - 1);
+
+        let decoded = Encoding::OnChain.decode(&bytes).unwrap();
+        assert_eq!(decoded.public_inputs, envelope.public_inputs);
+        assert_eq!(decoded.schema, envelope.schema);
+        assert_eq!(Encoding::OnChain.encode(&decoded), bytes);
+
+        let licensed = envelope.clone().with_terms_hash(TermsHash::of(b"CC-BY-4.0"));
+        let licensed_bytes = Encoding::OnChain.encode(&licensed);
+        assert_eq!(licensed_bytes[TERMS_HASH_OFFSET..TERMS_HASH_OFFSET + WORD], licensed.terms_hash.unwrap().0);
+        assert_eq!(Encoding::OnChain.decode(&licensed_bytes).unwrap().terms_hash, licensed.terms_hash);
+
+        let mut signed = envelope.clone();
+        let key = OperatorKey::from_seed(&[5u8; 32]);
+        key.sign_envelope(&mut signed, &DomainTag::default());
+        let signed_bytes = Encoding::OnChain.encode(&signed);
+        assert_eq!(signed_bytes.len(), bytes.len() + SIGNER_LENGTH);
+        let decoded = Encoding::OnChain.decode(&signed_bytes).unwrap();
+        assert_eq!(verify_signer(&decoded, &DomainTag::default()), Ok(Some(key.public_key())));
+
+        // the same schema in other JSON would change the schema commitment the contract computes
+        let schema_offset = proof_offset(envelope.public_inputs.len()) + padded(envelope.proof.to_bytes().len());
+        let pretty = serde_json::to_vec_pretty(envelope.schema.as_ref().unwrap()).unwrap();
+        let mut spaced = bytes[..schema_offset].to_vec();
+        spaced[SCHEMA_LENGTH_OFFSET..SCHEMA_LENGTH_OFFSET + WORD].copy_from_slice(&uint_word(pretty.len() as u128));
+        spaced.extend_from_slice(&pretty);
+        spaced.resize(padded(spaced.len()), 0);
+        assert!(matches!(Encoding::OnChain.decode(&spaced), Err(EnvelopeError::InvalidSchema(_))));
+
+        let mut padding = bytes.clone();
+        *padding.last_mut().unwrap() = 1;
+        assert_eq!(Encoding::OnChain.decode(&padding).unwrap_err(), EnvelopeError::InvalidPadding);
+        let mut large = bytes.clone();
+        large[INPUTS_OFFSET + WORD - 1] = 0xff;
+        large[INPUTS_OFFSET + 16..INPUTS_OFFSET + WORD - 1].fill(0xff);
+        assert!(matches!(Encoding::OnChain.decode(&large), Err(EnvelopeError::NonCanonicalElement(_))));
+        assert_eq!(Encoding::OnChain.decode(&bytes[..bytes.len() - WORD]).unwrap_err(), EnvelopeError::Truncated);
+    }
+}
@@ -0,0 +1,563 @@
+//! Minimal HTTP/1.1 proving service (feature `server`).
+//!
+//! | route                   | body                                | response                                   |
+//! |-------------------------|-------------------------------------|--------------------------------------------|
+//! | `GET /health`           |                                     | `{"status":"ok"}`                          |
+//! | `GET /metrics`          |                                     | Prometheus text format, see [`metrics`]    |
+//! | `POST /prove`           | JSON [`LinearClaimRequest`]         | `{"claim_hash":"0x..","envelope":"0x.."}`  |
+//! | `POST /verify`          | `{"envelope":"0x.."}`               | `{"valid":bool,"error":string or null}`    |
+//! | `POST /verify/stream`   | NDJSON `{"envelope":"0x.."}` lines  | NDJSON verdicts, see [`streaming`]         |
+//! | `POST /describe`        | `{"envelope":"0x..","locale":"de"}` | `{"description":string}`, see [`describe`] |
+//! | `GET /envelopes/{hash}` |                                     | `{"envelope":"0x.."}` from the store       |
+//!
+//! Every connection is handled on its own thread. Proofs still take turns on the session, so
+//! each is appended to the audit log in the order it was produced, while verification and the
+//! other routes run alongside them. Claims whose estimated cost exceeds the session's resource
+//! limits are rejected with 413 before any proving starts. With a [`storage`] backend on the
+//! session, `/prove` also answers the `location` of the stored envelope, and `/envelopes`
+//! serves stored envelopes by claim hash.
+//!
+//! `/verify/stream` reads its body as it goes, with `Content-Length` or chunked transfer
+//! encoding, and answers with a chunked response that carries each verdict as soon as it is
+//! known, so archives of any size can be checked in one request. It verifies on several
+//! threads and holds its connection until the stream is done.
+//!
+//! [`metrics`]: crate::metrics
+//! [`streaming`]: crate::streaming
+//! [`describe`]: crate::describe
+//! [`storage`]: crate::storage
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+    time::Instant,
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::abi::{from_hex, to_hex};
+use crate::config::VerificationPolicy;
+use crate::describe::Locale;
+use crate::envelope::ProofEnvelope;
+use crate::metrics::{Metrics, Phase};
+use crate::session::{verify_linear, LinearClaimRequest, ProvingSession, SessionError};
+use crate::storage::claim_key;
+use crate::streaming::StreamVerifier;
+
+/// Largest request body the server reads
+pub const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// Longest chunk-size or trailer line of a chunked request body
+const MAX_CHUNK_LINE_BYTES: usize = 1024;
+
+#[derive(Deserialize)]
+struct VerifyRequest {
+    envelope: String,
+}
+
+#[derive(Deserialize)]
+struct DescribeRequest {
+    envelope: String,
+    #[serde(default)]
+    locale: Option<String>,
+}
+
+fn decode_envelope(hex: &str) -> Result<ProofEnvelope, (u16, Value)> {
+    match from_hex(hex).map(|bytes| ProofEnvelope::from_bytes(&bytes)) {
+        Some(Ok(envelope)) => Ok(envelope),
+        Some(Err(err)) => Err((400, json!({ "error": err.to_string() }))),
+        None => Err((400, json!({ "error": "envelope must be hex encoded" }))),
+    }
+}
+
+/// Policy of the verification routes: the session's domain, public input limit and whether
+/// proofs commit to their configuration
+fn verification_policy(session: &ProvingSession) -> VerificationPolicy {
+    let config = session.config();
+    VerificationPolicy {
+        domain: config.domain.clone(),
+        max_public_inputs: config.max_public_inputs,
+        commit_config: config.commit_config,
+        ..Default::default()
+    }
+}
+
+/// Whether a route reads or changes the session beyond its verification policy and metrics
+fn needs_session(method: &str, path: &str) -> bool {
+    matches!((method, path), ("POST", "/prove")) || (method == "GET" && path.starts_with("/envelopes/"))
+}
+
+/// Routes one request, returning the status code and JSON response body
+pub fn handle_request(session: &mut ProvingSession, method: &str, path: &str, body: &[u8]) -> (u16, Value) {
+    if !needs_session(method, path) {
+        return handle_verification(&verification_policy(session), session.metrics(), method, path, body);
+    }
+    match (method, path) {
+        ("POST", "/prove") => {
+            let request: LinearClaimRequest = match serde_json::from_slice(body) {
+                Ok(request) => request,
+                Err(err) => return (400, json!({ "error": err.to_string() })),
+            };
+            match session.prove_linear(&request) {
+                Ok(envelope) => {
+                    let claim_hash = envelope.claim_hash(&session.config().domain);
+                    let mut response = json!({
+                        "claim_hash": to_hex(&claim_hash),
+                        "envelope": to_hex(&envelope.to_bytes()),
+                    });
+                    if let Some(store) = session.store() {
+                        response["location"] = json!(store.location(&claim_key(&claim_hash, "stke")));
+                    }
+                    (200, response)
+                }
+                Err(err @ (SessionError::ResourceLimit(_) | SessionError::PublicInputLimit(_))) => {
+                    (413, json!({ "error": err.to_string() }))
+                }
+                Err(err) => (422, json!({ "error": err.to_string() })),
+            }
+        }
+        ("GET", path) if path.starts_with("/envelopes/") => {
+            let claim_hash: [u8; 32] = match from_hex(&path["/envelopes/".len()..]).and_then(|b| b.try_into().ok()) {
+                Some(claim_hash) => claim_hash,
+                None => return (400, json!({ "error": "claim hash must be 32 hex-encoded bytes" })),
+            };
+            let Some(store) = session.store() else { return (404, json!({ "error": "no envelope store" })) };
+            match store.get(&claim_key(&claim_hash, "stke")) {
+                Ok(Some(bytes)) => (200, json!({ "envelope": to_hex(&bytes) })),
+                Ok(None) => (404, json!({ "error": "no envelope for this claim hash" })),
+                Err(err) => (500, json!({ "error": err.to_string() })),
+            }
+        }
+        _ => (404, json!({ "error": "not found" })),
+    }
+}
+
+/// Routes a request that only verifies or reads envelopes
+fn handle_verification(
+    policy: &VerificationPolicy,
+    metrics: &Metrics,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> (u16, Value) {
+    match (method, path) {
+        ("GET", "/health") => (200, json!({ "status": "ok" })),
+        ("POST", "/verify") => {
+            let request: VerifyRequest = match serde_json::from_slice(body) {
+                Ok(request) => request,
+                Err(err) => return (400, json!({ "error": err.to_string() })),
+            };
+            let envelope = match decode_envelope(&request.envelope) {
+                Ok(envelope) => envelope,
+                Err(response) => return response,
+            };
+            let started = Instant::now();
+            let result = verify_linear(&envelope, policy);
+            metrics.observe(Phase::Verify, started.elapsed());
+            metrics.record_verification(result.is_ok());
+            match result {
+                Ok(()) => (200, json!({ "valid": true, "error": null })),
+                Err(err) => (200, json!({ "valid": false, "error": err.to_string() })),
+            }
+        }
+        ("POST", "/describe") => {
+            let request: DescribeRequest = match serde_json::from_slice(body) {
+                Ok(request) => request,
+                Err(err) => return (400, json!({ "error": err.to_string() })),
+            };
+            let locale = match request.locale.as_deref().map_or(Ok(Locale::default()), str::parse) {
+                Ok(locale) => locale,
+                Err(err) => return (400, json!({ "error": err })),
+            };
+            match decode_envelope(&request.envelope) {
+                Ok(envelope) => (200, json!({ "description": envelope.describe(locale) })),
+                Err(response) => response,
+            }
+        }
+        _ => (404, json!({ "error": "not found" })),
+    }
+}
+
+/// Serves requests from `listener` until it fails, each connection on its own thread
+pub fn serve(listener: TcpListener, session: ProvingSession) -> io::Result<()> {
+    let session = Arc::new(SharedSession::new(session));
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let session = Arc::clone(&session);
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(&session, &mut stream) {
+                eprintln!("connection error: {err}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// The session shared by all connections, with what the routes that do not prove read from it
+/// copied out so that they never wait for a proof
+struct SharedSession {
+    session: Mutex<ProvingSession>,
+    policy: VerificationPolicy,
+    metrics: Arc<Metrics>,
+}
+
+impl SharedSession {
+    fn new(session: ProvingSession) -> Self {
+        let (policy, metrics) = (verification_policy(&session), session.metrics().clone());
+        Self { session: Mutex::new(session), policy, metrics }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ProvingSession> {
+        // a panicking request leaves the session as consistent as a failed one
+        self.session.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn handle_connection(shared: &SharedSession, stream: &mut TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let (mut content_length, mut chunked) = (0, false);
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.trim().eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.trim().eq_ignore_ascii_case("chunked");
+            }
+        }
+    }
+
+    let metrics = &shared.metrics;
+    if (method, path) == ("POST", "/verify/stream") {
+        let verifier = StreamVerifier::new(shared.policy.clone()).with_metrics(metrics.clone());
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nTransfer-Encoding: chunked\r\n\
+             Connection: close\r\n\r\n"
+        )?;
+        let mut response = ChunkedWriter::new(stream);
+        if chunked {
+            verifier.run(ChunkedReader::new(&mut reader), &mut response)?;
+        } else {
+            verifier.run((&mut reader).take(content_length as u64), &mut response)?;
+        }
+        return response.finish();
+    }
+    let (status, content_type, body) = if (method, path) == ("GET", "/metrics") {
+        (200, "text/plain; version=0.0.4", metrics.render())
+    } else if content_length > MAX_BODY_BYTES {
+        (413, "application/json", json!({ "error": "request body too large" }).to_string())
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        let (status, response) = if needs_session(method, path) {
+            // the queue holds the requests waiting for the proof in progress to finish
+            let queued = metrics.enqueue();
+            let mut session = shared.lock();
+            drop(queued);
+            handle_request(&mut session, method, path, &body)
+        } else {
+            handle_verification(&shared.policy, metrics, method, path, &body)
+        };
+        (status, "application/json", response.to_string())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        reason(status),
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Request body sent with chunked transfer encoding, decoded as it is read
+struct ChunkedReader<R> {
+    inner: R,
+    /// Bytes left in the current chunk
+    remaining: usize,
+    /// The CRLF closing the current chunk is still to be read
+    in_chunk: bool,
+    done: bool,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, remaining: 0, in_chunk: false, done: false }
+    }
+
+    /// Reads up to the next chunk with data, or to the end of the body
+    fn next_chunk(&mut self) -> io::Result<()> {
+        if self.in_chunk && self.read_line()? != b"\r\n" {
+            return Err(invalid_chunk("chunk data is not followed by CRLF"));
+        }
+        let line = self.read_line()?;
+        let size = std::str::from_utf8(&line).unwrap_or("").trim().split(';').next().unwrap_or("");
+        self.remaining = usize::from_str_radix(size, 16).map_err(|_| invalid_chunk("invalid chunk size"))?;
+        self.in_chunk = true;
+        if self.remaining == 0 {
+            // trailers, up to the closing empty line
+            while !self.read_line()?.trim_ascii().is_empty() {}
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// One line including its terminator, of at most [`MAX_CHUNK_LINE_BYTES`]
+    fn read_line(&mut self) -> io::Result<Vec<u8>> {
+        let mut line = Vec::new();
+        (&mut self.inner).take(MAX_CHUNK_LINE_BYTES as u64).read_until(b'\n', &mut line)?;
+        match line.last() {
+            Some(b'\n') => Ok(line),
+            None => Err(io::ErrorKind::UnexpectedEof.into()),
+            Some(_) if line.len() < MAX_CHUNK_LINE_BYTES => Err(io::ErrorKind::UnexpectedEof.into()),
+            Some(_) => Err(invalid_chunk("chunk line too long")),
+        }
+    }
+}
+
+fn invalid_chunk(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for ChunkedReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.remaining == 0 && !self.done {
+            self.next_chunk()?;
+        }
+        if self.done {
+            return Ok(&[]);
+        }
+        let available = self.inner.fill_buf()?;
+        if available.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(&available[..available.len().min(self.remaining)])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.remaining -= amount;
+        self.inner.consume(amount);
+    }
+}
+
+/// Response body sent with chunked transfer encoding, one chunk per flush
+struct ChunkedWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, buffer: Vec::new() }
+    }
+
+    /// Sends what is left and the closing empty chunk
+    fn finish(mut self) -> io::Result<()> {
+        self.flush()?;
+        self.inner.write_all(b"0\r\n\r\n")?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            write!(self.inner, "{:x}\r\n", self.buffer.len())?;
+            self.buffer.extend_from_slice(b"\r\n");
+            self.inner.write_all(&self.buffer)?;
+            self.buffer.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{verify_log, AuditLog};
+    use crate::config::ProverConfig;
+    use crate::storage::LocalStore;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_prove_and_verify_routes() {
+        let path = std::env::temp_dir().join(format!("server-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store_root = std::env::temp_dir().join(format!("server-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&store_root);
+        let mut session = ProvingSession::new(ProverConfig::default(), "server")
+            .with_audit_log(AuditLog::open(&path).unwrap())
+            .with_store(Arc::new(LocalStore::new(&store_root)));
+
+        let request = json!({
+            "model": "demo",
+            "salt": to_hex(&[5u8; 32]),
+            "slope": 3,
+            "intercept": 1,
+            "sample_x": [2, 4, 6],
+            "x": 5,
+        });
+        let (status, proved) = handle_request(&mut session, "POST", "/prove", request.to_string().as_bytes());
+        assert_eq!(status, 200);
+        assert_eq!(verify_log(&path).unwrap(), 1);
+        assert!(proved["location"].as_str().unwrap().starts_with("file://"));
+        let stored = format!("/envelopes/{}", proved["claim_hash"].as_str().unwrap());
+        let (status, fetched) = handle_request(&mut session, "GET", &stored, b"");
+        assert_eq!((status, &fetched["envelope"]), (200, &proved["envelope"]));
+        assert_eq!(handle_request(&mut session, "GET", &format!("/envelopes/0x{}", "00".repeat(32)), b"").0, 404);
+        assert_eq!(handle_request(&mut session, "GET", "/envelopes/..", b"").0, 400);
+
+        let body = json!({ "envelope": proved["envelope"] }).to_string();
+        let (status, verified) = handle_request(&mut session, "POST", "/verify", body.as_bytes());
+        assert_eq!(status, 200);
+        assert_eq!(verified["valid"], true);
+        let metrics = session.metrics();
+        assert_eq!((metrics.proofs_generated(), metrics.verification_failures()), (1, 0));
+
+        let body = json!({ "envelope": proved["envelope"], "locale": "es" }).to_string();
+        let (status, described) = handle_request(&mut session, "POST", "/describe", body.as_bytes());
+        assert_eq!(status, 200);
+        assert!(described["description"].as_str().unwrap().contains("predice 16 para la entrada 5"));
+        let body = json!({ "envelope": proved["envelope"], "locale": "xx" }).to_string();
+        assert_eq!(handle_request(&mut session, "POST", "/describe", body.as_bytes()).0, 400);
+
+        assert_eq!(handle_request(&mut session, "POST", "/prove", b"{").0, 400);
+        assert_eq!(handle_request(&mut session, "GET", "/nope", b"").0, 404);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_dir_all(&store_root).unwrap();
+    }
+
+    #[test]
+    fn test_streams_verdicts_over_chunked_http() {
+        let mut session = ProvingSession::new(ProverConfig::default(), "server");
+        let salt = to_hex(&[5u8; 32]);
+        let request = json!({ "model": "demo", "salt": salt, "slope": 3, "intercept": 1, "sample_x": [2], "x": 5 });
+        let (_, proved) = handle_request(&mut session, "POST", "/prove", request.to_string().as_bytes());
+        let line = format!("{}\n", json!({ "envelope": proved["envelope"] }));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let shared = SharedSession::new(session);
+            handle_connection(&shared, &mut stream).unwrap();
+            shared.session.into_inner().unwrap()
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        write!(client, "POST /verify/stream HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n").unwrap();
+        // the second envelope is split across two chunks
+        let (head, tail) = line.split_at(10);
+        for chunk in [line.as_str(), head, tail, "{\"envelope\":\"0x00\"}\n"] {
+            write!(client, "{:x};ext=1\r\n{chunk}\r\n", chunk.len()).unwrap();
+        }
+        write!(client, "0\r\n\r\n").unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        let session = server.join().unwrap();
+
+        let (headers, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(headers.contains("Transfer-Encoding: chunked"));
+        let mut decoded = Vec::new();
+        ChunkedReader::new(body.as_bytes()).read_to_end(&mut decoded).unwrap();
+        let verdicts: Vec<Value> = decoded.lines().map(|line| serde_json::from_str(&line.unwrap()).unwrap()).collect();
+        assert_eq!(verdicts.len(), 4);
+        assert_eq!(verdicts[3], json!({ "done": true, "total": 3, "valid": 2, "error": null }));
+        assert_eq!(session.metrics().verification_failures(), 0);
+    }
+
+    #[test]
+    fn test_rejects_malformed_chunks() {
+        let mut decoded = Vec::new();
+        let err = ChunkedReader::new(&b"3\r\nabcXY0\r\n\r\n"[..]).read_to_end(&mut decoded).unwrap_err();
+        assert_eq!(err.to_string(), "chunk data is not followed by CRLF");
+        let oversized = format!("{}1\r\na\r\n", "0".repeat(MAX_CHUNK_LINE_BYTES));
+        let err = ChunkedReader::new(oversized.as_bytes()).read_to_end(&mut decoded).unwrap_err();
+        assert_eq!(err.to_string(), "chunk line too long");
+    }
+
+    #[test]
+    fn test_queue_depth_counts_waiting_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let shared = Arc::new(SharedSession::new(ProvingSession::new(ProverConfig::default(), "server")));
+        let metrics = shared.metrics.clone();
+        let server_shared = Arc::clone(&shared);
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            handle_connection(&server_shared, &mut stream).unwrap();
+        });
+
+        // a proof in progress holds the session, so the request queues behind it
+        let proving = shared.lock();
+        let mut client = TcpStream::connect(address).unwrap();
+        write!(client, "GET /envelopes/0x{} HTTP/1.1\r\n\r\n", "00".repeat(32)).unwrap();
+        while metrics.queue_depth() == 0 {
+            std::thread::yield_now();
+        }
+        assert_eq!(metrics.queue_depth(), 1);
+        drop(proving);
+        server.join().unwrap();
+        assert_eq!(metrics.queue_depth(), 0);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_serves_connections_concurrently() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let session = ProvingSession::new(ProverConfig::default(), "server");
+        std::thread::spawn(move || serve(listener, session));
+
+        // a client that never finishes its request does not hold up the next one
+        let mut stalled = TcpStream::connect(address).unwrap();
+        write!(stalled, "POST /verify HTTP/1.1\r\n").unwrap();
+        let mut client = TcpStream::connect(address).unwrap();
+        write!(client, "GET /health HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(r#"{"status":"ok"}"#));
+    }
+}
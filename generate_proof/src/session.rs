@@ -0,0 +1,933 @@
+//! Proving session shared by the CLI and the server.
+
+use core::fmt;
+#[cfg(feature = "prover")]
+use std::sync::Arc;
+#[cfg(feature = "prover")]
+use std::time::Instant;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use winterfell::{crypto::DefaultRandomCoin, math::StarkField, Proof, ProofOptions, TraceInfo, VerifierError};
+#[cfg(feature = "prover")]
+use winterfell::{
+    math::fields::f128::BaseElement, math::FieldElement, math::ToElements, ProverError, Trace,
+};
+
+use crate::audit::AuditError;
+use crate::beacon::{BeaconError, BeaconRound};
+use crate::coin::{CoinKind, KeccakCoin};
+use crate::config::{ConfigCommitment, ConfigError, VerificationPolicy};
+use crate::domain::{DomainSeparatedAir, DomainTag, TaggedInputs};
+use crate::envelope::ProofEnvelope;
+use crate::fingerprint::ConstraintFingerprint;
+use crate::fixed_point::from_field;
+use crate::hardware::{require_attestation, verify_attestation, HardwareError};
+use crate::nonce::NonceError;
+use crate::operator::{require_signer, verify_signer, SignerError};
+use crate::provenance::{require_version, ProvenanceError};
+use crate::prover::{verify_with_coin, HashFn};
+use crate::public_inputs::{check_count, TooManyPublicInputs};
+use crate::resolver::{ResolveError, Resolver};
+use crate::regression::{self, LinearRegressionAir, LinearRegressionInputs};
+use crate::schema::{DataSchema, SchemaError};
+use crate::storage::StorageError;
+use crate::terms::TermsHash;
+#[cfg(feature = "prover")]
+use crate::{
+    abi::{from_hex, to_hex},
+    audit::{AuditEntry, AuditLog},
+    budget::{self, downgrades, BudgetError, BudgetReport, TimeBudget},
+    config::ProverConfig,
+    diagnostics::{DeepDiagnostics, DiagnosticsError},
+    hardware::{attest_envelope, HardwareSigner},
+    metrics::{Metrics, Phase},
+    nonce::{ClaimNonce, NonceManager},
+    operator::OperatorKey,
+    pool::{PoolStats, PooledTrace, TracePool},
+    provenance::Provenance,
+    prover::{spawn_prove_trace, ProvingHandle},
+    registry::{ModelCommitment, ModelId},
+    regression::{fill_linear_regression_trace, TRACE_WIDTH},
+    resources::{Calibration, LimitExceeded, ProvingPlan, ResourceLimits},
+    secret::SecretTrace,
+    storage::{store_envelope, ProofStore},
+};
+
+/// A request to prove `y = slope * x + intercept` against public sample points
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinearClaimRequest {
+    /// Registered model name; the model id is derived from it
+    pub model: String,
+    /// Hex-encoded 32-byte salt of the model commitment
+    pub salt: String,
+    pub slope: u64,
+    pub intercept: u64,
+    /// One sample row per entry in order; values may repeat and each repeat is its own sample
+    pub sample_x: Vec<u64>,
+    pub x: u64,
+    /// Names, units and ranges of `x` and `y`; samples, `x` and the predictions must respect them
+    #[serde(default)]
+    pub schema: Option<DataSchema>,
+    /// drand round bound into the claim, e.g. the one the sample points were drawn from
+    #[serde(default)]
+    pub beacon: Option<BeaconRound>,
+    /// Hash of the license or terms of use the claim is made under
+    #[serde(default)]
+    pub terms_hash: Option<TermsHash>,
+}
+
+/// Largest sample or prediction point drawn from a beacon round for a claim without a schema
+pub const BEACON_POINT_MAX: u64 = u32::MAX as u64;
+
+impl LinearClaimRequest {
+    /// Binds `beacon` into the claim and replaces the sample points and `x` with the ones drawn
+    /// from it for `domain`, keeping the number of samples; the samples must fit the schema's
+    /// feature range, or [`BEACON_POINT_MAX`] without a schema
+    pub fn with_beacon(mut self, beacon: BeaconRound, domain: &DomainTag) -> Result<Self, BeaconError> {
+        let drawn = beacon_points(&beacon, domain, self.schema.as_ref(), self.sample_x.len());
+        let (sample_x, x) = drawn.ok_or(BeaconError::PointsMismatch)?;
+        self.sample_x = sample_x;
+        self.x = x;
+        self.beacon = Some(beacon);
+        Ok(self)
+    }
+}
+
+/// Sample points and prediction point of a linear claim with `count` samples bound to `beacon`
+fn beacon_points(
+    beacon: &BeaconRound,
+    domain: &DomainTag,
+    schema: Option<&DataSchema>,
+    count: usize,
+) -> Option<(Vec<u64>, u64)> {
+    let point = |value: i128| value.clamp(0, u64::MAX as i128) as u64;
+    let (min, max) = match schema.and_then(|schema| schema.features.first()) {
+        Some(feature) => (point(feature.min), point(feature.max)),
+        None => (0, BEACON_POINT_MAX),
+    };
+    beacon.claim_points(domain, min, max, count)
+}
+
+/// Errors returned by [`ProvingSession`] and [`verify_linear`]
+#[derive(Debug)]
+pub enum SessionError {
+    InvalidRequest(String),
+    /// The session's configuration cannot prove a trace of the request's length
+    Config(ConfigError),
+    /// The envelope was produced for a different constraint system than the verifier's
+    FingerprintMismatch { expected: ConstraintFingerprint, found: ConstraintFingerprint },
+    /// The job's estimated cost exceeds the session's resource limits
+    #[cfg(feature = "prover")]
+    ResourceLimit(LimitExceeded),
+    PublicInputLimit(TooManyPublicInputs),
+    /// The request's data does not fit its schema
+    Schema(SchemaError),
+    /// The proof was ground to fewer proof-of-work bits than the policy requires
+    InsufficientGrinding { required: u32, found: u32 },
+    /// The proof was made with other options than the policy's prover configuration picks
+    OptionsMismatch,
+    /// The model commitment is not the one published in the registry
+    Resolve(ResolveError),
+    /// The operator signature is invalid, missing or from an untrusted operator
+    Signer(SignerError),
+    /// The beacon round is missing or not signed by the policy's drand network
+    Beacon(BeaconError),
+    /// No nonce could be issued for the claim
+    Nonce(NonceError),
+    /// The claim was not made under the terms the policy requires
+    TermsMismatch { required: TermsHash, found: Option<TermsHash> },
+    /// The envelope records no provenance or one of a prover older than the policy accepts
+    Provenance(ProvenanceError),
+    /// The hardware attestation is invalid, missing or from a device the policy does not list,
+    /// or the session's device could not attest
+    Hardware(HardwareError),
+    /// The envelope could not be written to the session's store
+    Storage(StorageError),
+    /// No proof reaching the minimum security was produced within the session's time budget
+    #[cfg(feature = "prover")]
+    Budget(BudgetError),
+    /// The diagnostics the session was asked to capture could not be replayed from the proof
+    #[cfg(feature = "prover")]
+    Diagnostics(DiagnosticsError),
+    #[cfg(feature = "prover")]
+    Prover(ProverError),
+    Verifier(VerifierError),
+    Audit(AuditError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::InvalidRequest(reason) => write!(f, "invalid request: {reason}"),
+            SessionError::Config(err) => write!(f, "invalid prover configuration: {err}"),
+            SessionError::FingerprintMismatch { expected, found } => {
+                write!(f, "envelope was proven for constraint system {found}, verifier expects {expected}")
+            }
+            #[cfg(feature = "prover")]
+            SessionError::ResourceLimit(err) => write!(f, "job rejected: {err}"),
+            SessionError::PublicInputLimit(err) => write!(f, "{err}"),
+            SessionError::Schema(err) => write!(f, "{err}"),
+            SessionError::InsufficientGrinding { required, found } => {
+                write!(f, "proof has {found} grinding bits, the policy requires {required}")
+            }
+            SessionError::OptionsMismatch => write!(f, "proof was made with other options than the policy expects"),
+            SessionError::Resolve(err) => write!(f, "{err}"),
+            SessionError::Signer(err) => write!(f, "{err}"),
+            SessionError::Beacon(err) => write!(f, "{err}"),
+            SessionError::Nonce(err) => write!(f, "{err}"),
+            SessionError::TermsMismatch { required, found: Some(found) } => {
+                write!(f, "claim is made under terms {found}, expected {required}")
+            }
+            SessionError::TermsMismatch { required, found: None } => {
+                write!(f, "claim names no terms, expected {required}")
+            }
+            SessionError::Provenance(err) => write!(f, "{err}"),
+            SessionError::Hardware(err) => write!(f, "{err}"),
+            SessionError::Storage(err) => write!(f, "{err}"),
+            #[cfg(feature = "prover")]
+            SessionError::Budget(err) => write!(f, "{err}"),
+            #[cfg(feature = "prover")]
+            SessionError::Diagnostics(err) => write!(f, "{err}"),
+            #[cfg(feature = "prover")]
+            SessionError::Prover(err) => write!(f, "proving failed: {err}"),
+            SessionError::Verifier(err) => write!(f, "verification failed: {err}"),
+            SessionError::Audit(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<AuditError> for SessionError {
+    fn from(err: AuditError) -> Self {
+        SessionError::Audit(err)
+    }
+}
+
+/// Proves claims with a fixed configuration, recording every proof in an optional audit log
+#[cfg(feature = "prover")]
+pub struct ProvingSession {
+    config: ProverConfig,
+    operator: String,
+    audit_log: Option<AuditLog>,
+    limits: Option<(Calibration, ResourceLimits)>,
+    resolver: Option<Resolver>,
+    operator_key: Option<OperatorKey>,
+    hardware_signer: Option<Box<dyn HardwareSigner>>,
+    metrics: Arc<Metrics>,
+    trace_pool: Option<Arc<TracePool>>,
+    nonces: Option<NonceManager>,
+    provenance: bool,
+    store: Option<Arc<dyn ProofStore>>,
+    budget: Option<(Calibration, TimeBudget)>,
+    last_budget_report: Option<BudgetReport>,
+    diagnostics: bool,
+    last_diagnostics: Option<DeepDiagnostics>,
+}
+
+#[cfg(feature = "prover")]
+impl ProvingSession {
+    pub fn new(config: ProverConfig, operator: impl Into<String>) -> Self {
+        Self {
+            config,
+            operator: operator.into(),
+            audit_log: None,
+            limits: None,
+            resolver: None,
+            operator_key: None,
+            hardware_signer: None,
+            metrics: Arc::default(),
+            trace_pool: None,
+            nonces: None,
+            provenance: false,
+            store: None,
+            budget: None,
+            last_budget_report: None,
+            diagnostics: false,
+            last_diagnostics: None,
+        }
+    }
+
+    /// Rejects jobs whose estimate under `calibration` exceeds `limits` before proving them
+    pub fn with_resource_limits(mut self, calibration: Calibration, limits: ResourceLimits) -> Self {
+        self.limits = Some((calibration, limits));
+        self
+    }
+
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Only proves for models whose commitment matches the one `resolver` finds published
+    pub fn with_resolver(mut self, resolver: Resolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Signs the claim hash of every envelope with `key`
+    pub fn with_operator_key(mut self, key: OperatorKey) -> Self {
+        self.operator_key = Some(key);
+        self
+    }
+
+    /// Has `signer` attest the claim hash of every envelope, see [`crate::hardware`]
+    pub fn with_hardware_signer(mut self, signer: Box<dyn HardwareSigner>) -> Self {
+        self.hardware_signer = Some(signer);
+        self
+    }
+
+    /// Records into `metrics` instead of the session's own, e.g. to share them between sessions
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Builds traces in column buffers from `pool`, which keeps them for the next job
+    pub fn with_trace_pool(mut self, pool: Arc<TracePool>) -> Self {
+        self.trace_pool = Some(pool);
+        self
+    }
+
+    /// Binds a fresh nonce from `nonces` into every claim, so each proof can only settle once
+    pub fn with_nonce_manager(mut self, nonces: NonceManager) -> Self {
+        self.nonces = Some(nonces);
+        self
+    }
+
+    /// Records the prover's build, hostname and time of proving in every envelope
+    pub fn with_provenance(mut self) -> Self {
+        self.provenance = true;
+        self
+    }
+
+    /// Keeps every envelope in `store` in the compact encoding, under its claim hash
+    pub fn with_store(mut self, store: Arc<dyn ProofStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Proves within `budget`, falling back to cheaper parameters reaching its minimum security
+    /// when the configured ones would take too long; see [`crate::budget`]
+    pub fn with_time_budget(mut self, calibration: Calibration, budget: TimeBudget) -> Self {
+        self.budget = Some((calibration, budget));
+        self
+    }
+
+    /// Replays every proof's transcript after proving and keeps its DEEP composition
+    /// diagnostics, see [`crate::diagnostics`]
+    pub fn with_diagnostics(mut self) -> Self {
+        self.diagnostics = true;
+        self
+    }
+
+    pub fn config(&self) -> &ProverConfig {
+        &self.config
+    }
+
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        &self.metrics
+    }
+
+    pub fn store(&self) -> Option<&Arc<dyn ProofStore>> {
+        self.store.as_ref()
+    }
+
+    /// Parameters tried and chosen for the last proof made under a time budget
+    pub fn last_budget_report(&self) -> Option<&BudgetReport> {
+        self.last_budget_report.as_ref()
+    }
+
+    /// Diagnostics of the last proof, when the session captures them
+    pub fn last_diagnostics(&self) -> Option<&DeepDiagnostics> {
+        self.last_diagnostics.as_ref()
+    }
+
+    pub fn prove_linear(&mut self, request: &LinearClaimRequest) -> Result<ProofEnvelope, SessionError> {
+        let pending = self.spawn_linear(request)?;
+        self.finish(pending)
+    }
+
+    /// Starts proving `request` on a background thread and returns without waiting for the
+    /// proof, which [`Self::finish`] turns into an envelope. Under a time budget the proof is
+    /// done by the time this returns, as the budget waits for each attempt.
+    pub fn spawn_linear(&mut self, request: &LinearClaimRequest) -> Result<PendingEnvelope, SessionError> {
+        if request.sample_x.is_empty() {
+            return Err(SessionError::InvalidRequest("at least one sample is required".into()));
+        }
+        let salt: [u8; 32] = from_hex(&request.salt)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| SessionError::InvalidRequest("salt must be 32 hex-encoded bytes".into()))?;
+        if let Some(beacon) = &request.beacon {
+            beacon.randomness_bytes().map_err(|err| SessionError::InvalidRequest(err.to_string()))?;
+            let drawn = beacon_points(beacon, &self.config.domain, request.schema.as_ref(), request.sample_x.len());
+            if drawn != Some((request.sample_x.clone(), request.x)) {
+                return Err(SessionError::Beacon(BeaconError::PointsMismatch));
+            }
+        }
+        // x, y and both coordinates of every sample
+        check_count(2 + 2 * request.sample_x.len(), self.config.max_public_inputs)
+            .map_err(SessionError::PublicInputLimit)?;
+        let plan = ProvingPlan::linear(request.sample_x.len(), &self.config);
+        self.config.validate_for_trace(plan.trace_length).map_err(SessionError::Config)?;
+        if let Some((calibration, limits)) = &self.limits {
+            limits.check(&calibration.estimate(&plan)).map_err(SessionError::ResourceLimit)?;
+        }
+        self.metrics.record_estimated_memory(plan.peak_memory_bytes());
+
+        let slope = BaseElement::from(request.slope);
+        let intercept = BaseElement::from(request.intercept);
+        let model_id = ModelId::from_name(&request.model);
+        let model_commitment = ModelCommitment::new(&self.config.domain, &[slope, intercept], &salt);
+        if let Some(resolver) = &self.resolver {
+            resolver.check(&model_id, model_commitment).map_err(SessionError::Resolve)?;
+        }
+        let sample_x: Vec<BaseElement> = request.sample_x.iter().map(|&x| BaseElement::from(x)).collect();
+        let sample_y: Vec<BaseElement> = sample_x.iter().map(|&x| slope * x + intercept).collect();
+        let target_x = BaseElement::from(request.x);
+
+        let pub_inputs = LinearRegressionInputs {
+            x_value: target_x,
+            predicted_y: slope * target_x + intercept,
+            sample_x_values: sample_x.clone(),
+            sample_y_values: sample_y.clone(),
+        };
+        let public_inputs = pub_inputs.to_elements();
+        if let Some(schema) = &request.schema {
+            check_schema(schema, &pub_inputs).map_err(SessionError::Schema)?;
+        }
+
+        let trace_info = TraceInfo::new(TRACE_WIDTH, plan.trace_length);
+        let min_blowup = regression::min_blowup();
+        let nonce = self.nonces.as_mut().map(NonceManager::issue).transpose().map_err(SessionError::Nonce)?;
+        let tagged = TaggedInputs {
+            config: None,
+            tag: self.config.domain.clone(),
+            nonce,
+            terms_hash: request.terms_hash,
+            schema: request.schema.as_ref().map(DataSchema::commitment),
+            inner: pub_inputs,
+        };
+        let workers = self.config.proving_threads();
+
+        let started = Instant::now();
+        let spawn = |config: &ProverConfig| -> Result<ProvingHandle, ConfigError> {
+            let options = config.small_claim_options(plan.trace_length, min_blowup)?;
+            let coin = config.random_coin;
+            let commitment = config.config_commitment(&regression::layout(), &options);
+            let tagged = TaggedInputs { config: commitment, ..tagged.clone() };
+            Ok(match &self.trace_pool {
+                Some(pool) => {
+                    let (mut columns, stats) = pool.take(TRACE_WIDTH, plan.trace_length);
+                    fill_linear_regression_trace(&mut columns, slope, intercept, &sample_x, &sample_y, target_x);
+                    self.metrics.record_trace_buffers(stats);
+                    self.metrics.observe(Phase::Trace, started.elapsed());
+                    let trace = PooledTrace::new(columns, pool.clone());
+                    spawn_linear(trace, tagged, options, workers, coin, Some(pool.clone()))
+                }
+                None => {
+                    let mut columns = vec![vec![BaseElement::ZERO; plan.trace_length]; TRACE_WIDTH];
+                    fill_linear_regression_trace(&mut columns, slope, intercept, &sample_x, &sample_y, target_x);
+                    let trace = SecretTrace::new(columns);
+                    self.metrics.record_trace_buffers(PoolStats { allocated: TRACE_WIDTH, reused: 0 });
+                    self.metrics.observe(Phase::Trace, started.elapsed());
+                    spawn_linear(trace, tagged, options, workers, coin, None)
+                }
+            })
+        };
+        let proving_started = Instant::now();
+        let (proving, config) = match &self.budget {
+            Some((calibration, time_budget)) => {
+                let candidates = downgrades(&self.config, plan.trace_length, min_blowup, time_budget.min_security_bits);
+                let budgeted = budget::run(&candidates, &trace_info, calibration, time_budget, |candidate| {
+                    Ok(spawn(candidate).expect("Candidates fit the trace"))
+                });
+                let (proof, config, report) = match budgeted {
+                    Ok(budgeted) => (Ok(budgeted.proof), budgeted.config, Some(budgeted.report)),
+                    Err(err) => (Err(SessionError::Budget(err)), self.config.clone(), None),
+                };
+                (Proving::Finished(Box::new(proof), report), config)
+            }
+            None => (Proving::Running(spawn(&self.config).map_err(SessionError::Config)?), self.config.clone()),
+        };
+
+        Ok(PendingEnvelope {
+            proving,
+            config,
+            started,
+            proving_started,
+            trace_info,
+            inputs: tagged.inner,
+            model_id,
+            model_commitment,
+            public_inputs,
+            schema: request.schema.clone(),
+            beacon: request.beacon.clone(),
+            nonce,
+            terms_hash: request.terms_hash,
+        })
+    }
+
+    /// Waits for `pending` and signs, stores and records its envelope like [`Self::prove_linear`]
+    pub fn finish(&mut self, pending: PendingEnvelope) -> Result<ProofEnvelope, SessionError> {
+        let (proof, report) = match pending.proving {
+            Proving::Running(handle) => (handle.join().map_err(SessionError::Prover), None),
+            Proving::Finished(proof, report) => (*proof, report),
+        };
+        let config = pending.config;
+        self.metrics.observe(Phase::Prove, pending.proving_started.elapsed());
+        self.metrics.record_proof(proof.is_ok());
+        let proof = proof?;
+        self.last_budget_report = report;
+        let fingerprint = linear_fingerprint(pending.trace_info, pending.inputs, proof.options().clone());
+        let wall_time_ms = pending.started.elapsed().as_millis() as u64;
+
+        let mut envelope =
+            ProofEnvelope::new(pending.model_id, pending.model_commitment, fingerprint, pending.public_inputs, proof);
+        envelope.schema = pending.schema;
+        envelope.beacon = pending.beacon;
+        envelope.nonce = pending.nonce;
+        envelope.terms_hash = pending.terms_hash;
+        envelope.provenance = self.provenance.then(Provenance::capture);
+        if self.diagnostics {
+            let policy = VerificationPolicy {
+                domain: self.config.domain.clone(),
+                random_coin: config.random_coin,
+                commit_config: config.commit_config,
+                ..Default::default()
+            };
+            let diagnostics = DeepDiagnostics::for_linear(&envelope, &policy).map_err(SessionError::Diagnostics)?;
+            self.last_diagnostics = Some(diagnostics);
+        }
+        if let Some(key) = &self.operator_key {
+            key.sign_envelope(&mut envelope, &self.config.domain);
+        }
+        if let Some(signer) = &self.hardware_signer {
+            attest_envelope(signer.as_ref(), &mut envelope, &self.config.domain).map_err(SessionError::Hardware)?;
+        }
+        if let Some(store) = &self.store {
+            let claim_hash = envelope.claim_hash(&self.config.domain);
+            store_envelope(store.as_ref(), &claim_hash, &envelope.to_bytes()).map_err(SessionError::Storage)?;
+        }
+
+        if let Some(audit_log) = self.audit_log.as_mut() {
+            audit_log.append(AuditEntry {
+                operator: self.operator.clone(),
+                air_id: regression::layout().versioned_id(),
+                claim_hash: to_hex(&envelope.claim_hash(&self.config.domain)),
+                config,
+                wall_time_ms,
+            })?;
+        }
+
+        Ok(envelope)
+    }
+}
+
+/// A linear claim proven on a background thread, see [`ProvingSession::spawn_linear`]
+#[cfg(feature = "prover")]
+pub struct PendingEnvelope {
+    proving: Proving,
+    config: ProverConfig,
+    started: Instant,
+    proving_started: Instant,
+    trace_info: TraceInfo,
+    inputs: LinearRegressionInputs,
+    model_id: ModelId,
+    model_commitment: ModelCommitment,
+    public_inputs: Vec<BaseElement>,
+    schema: Option<DataSchema>,
+    beacon: Option<BeaconRound>,
+    nonce: Option<ClaimNonce>,
+    terms_hash: Option<TermsHash>,
+}
+
+#[cfg(feature = "prover")]
+impl PendingEnvelope {
+    /// Whether [`ProvingSession::finish`] would return without waiting
+    pub fn is_finished(&self) -> bool {
+        match &self.proving {
+            Proving::Running(handle) => handle.is_finished(),
+            Proving::Finished(..) => true,
+        }
+    }
+}
+
+#[cfg(feature = "prover")]
+enum Proving {
+    Running(ProvingHandle),
+    /// Proofs under a time budget are finished by the time the budget picked their parameters
+    Finished(Box<Result<Proof, SessionError>>, Option<BudgetReport>),
+}
+
+type LinearAir = DomainSeparatedAir<LinearRegressionAir>;
+
+#[cfg(feature = "prover")]
+fn spawn_linear<T>(
+    trace: T,
+    inputs: TaggedInputs<LinearRegressionInputs>,
+    options: ProofOptions,
+    workers: usize,
+    coin: CoinKind,
+    lde_pool: Option<Arc<TracePool>>,
+) -> ProvingHandle
+where
+    T: Trace<BaseField = BaseElement> + Send + Sync + 'static,
+{
+    match coin {
+        CoinKind::Default => {
+            spawn_prove_trace::<LinearAir, T, DefaultRandomCoin<HashFn>>(trace, inputs, options, workers, lde_pool)
+        }
+        CoinKind::Keccak => {
+            spawn_prove_trace::<LinearAir, T, KeccakCoin<HashFn>>(trace, inputs, options, workers, lde_pool)
+        }
+    }
+}
+
+/// Checks the sample and target x values and the `y` values of a linear claim against a
+/// one-feature schema
+fn check_schema(schema: &DataSchema, inputs: &LinearRegressionInputs) -> Result<(), SchemaError> {
+    schema.validate()?;
+    let xs: Vec<[i128; 1]> =
+        inputs.sample_x_values.iter().chain([&inputs.x_value]).map(|&x| [from_field(x)]).collect();
+    let ys = [inputs.predicted_y].into_iter().chain(inputs.sample_y_values.iter().copied());
+    schema.check(xs.iter().map(|x| &x[..]), ys.map(from_field))
+}
+
+pub(crate) fn linear_fingerprint(
+    trace_info: TraceInfo,
+    pub_inputs: LinearRegressionInputs,
+    options: ProofOptions,
+) -> ConstraintFingerprint {
+    ConstraintFingerprint::for_instance::<LinearRegressionAir>(&regression::layout(), trace_info, pub_inputs, options)
+}
+
+/// Configuration commitment a linear proof must carry under `policy`, built from the options
+/// the policy expects rather than those recorded in the proof, which must be the same
+pub(crate) fn linear_config_commitment(
+    proof: &Proof,
+    policy: &VerificationPolicy,
+) -> Result<Option<ConfigCommitment>, SessionError> {
+    let trace_length = proof.trace_info().length();
+    let expected = policy
+        .expected_options(trace_length, regression::min_blowup(), proof.options())
+        .map_err(SessionError::Config)?;
+    if expected != *proof.options() {
+        return Err(SessionError::OptionsMismatch);
+    }
+    Ok(policy.config_commitment(&regression::layout(), &expected))
+}
+
+/// Verifies an envelope produced by [`ProvingSession::prove_linear`] under `policy`
+pub fn verify_linear(envelope: &ProofEnvelope, policy: &VerificationPolicy) -> Result<(), SessionError> {
+    check_count(envelope.public_inputs.len(), policy.max_public_inputs).map_err(SessionError::PublicInputLimit)?;
+    let pub_inputs = LinearRegressionInputs::from_elements(&envelope.public_inputs)
+        .ok_or_else(|| SessionError::InvalidRequest("public inputs do not describe a linear claim".into()))?;
+
+    if let Some(schema) = &envelope.schema {
+        check_schema(schema, &pub_inputs).map_err(SessionError::Schema)?;
+    }
+
+    let found = envelope.proof.options().grinding_factor();
+    if found < policy.min_grinding_bits {
+        return Err(SessionError::InsufficientGrinding { required: policy.min_grinding_bits, found });
+    }
+
+    if policy.require_fingerprint_match {
+        let proof = &envelope.proof;
+        let expected = linear_fingerprint(proof.trace_info().clone(), pub_inputs.clone(), proof.options().clone());
+        if expected != envelope.constraint_fingerprint {
+            return Err(SessionError::FingerprintMismatch { expected, found: envelope.constraint_fingerprint });
+        }
+    }
+
+    if policy.trusted_operators.is_empty() {
+        verify_signer(envelope, &policy.domain).map_err(SessionError::Signer)?;
+    } else {
+        require_signer(envelope, &policy.domain, &policy.trusted_operators).map_err(SessionError::Signer)?;
+    }
+    if policy.trusted_hardware_keys.is_empty() {
+        verify_attestation(envelope, &policy.domain).map_err(SessionError::Hardware)?;
+    } else {
+        require_attestation(envelope, &policy.domain, &policy.trusted_hardware_keys).map_err(SessionError::Hardware)?;
+    }
+
+    if let Some(beacon) = &envelope.beacon {
+        let count = pub_inputs.sample_x_values.len();
+        let drawn = beacon_points(beacon, &policy.domain, envelope.schema.as_ref(), count);
+        let matches = drawn.is_some_and(|(sample_x, x)| {
+            pub_inputs.x_value.as_int() == x as u128
+                && sample_x.into_iter().map(u128::from).eq(pub_inputs.sample_x_values.iter().map(|x| x.as_int()))
+        });
+        if !matches {
+            return Err(SessionError::Beacon(BeaconError::PointsMismatch));
+        }
+    }
+    if let Some(chain) = &policy.beacon {
+        let beacon = envelope.beacon.as_ref().ok_or(SessionError::Beacon(BeaconError::MissingRound))?;
+        beacon.verify(chain).map_err(SessionError::Beacon)?;
+        if let Some(max_age) = policy.max_beacon_age {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            let earliest = chain.earliest_round(now, max_age);
+            if beacon.round < earliest {
+                return Err(SessionError::Beacon(BeaconError::StaleRound { round: beacon.round, earliest }));
+            }
+        }
+    }
+
+    if let Some(required) = policy.required_terms_hash.filter(|&required| envelope.terms_hash != Some(required)) {
+        return Err(SessionError::TermsMismatch { required, found: envelope.terms_hash });
+    }
+
+    if let Some(required) = policy.min_prover_version {
+        require_version(envelope, required).map_err(SessionError::Provenance)?;
+    }
+
+    if let Some(endpoint) = &policy.registry {
+        Resolver::new(endpoint.clone()).check_envelope(envelope).map_err(SessionError::Resolve)?;
+    }
+
+    verify_linear_proof(envelope, pub_inputs, policy)
+}
+
+/// Verifies the proof of a linear envelope with public inputs `pub_inputs` for the deployment,
+/// configuration and random coin of `policy`, without its other requirements
+pub(crate) fn verify_linear_proof(
+    envelope: &ProofEnvelope,
+    pub_inputs: LinearRegressionInputs,
+    policy: &VerificationPolicy,
+) -> Result<(), SessionError> {
+    let tagged = TaggedInputs {
+        config: linear_config_commitment(&envelope.proof, policy)?,
+        tag: policy.domain.clone(),
+        nonce: envelope.nonce,
+        terms_hash: envelope.terms_hash,
+        schema: envelope.schema.as_ref().map(DataSchema::commitment),
+        inner: pub_inputs,
+    };
+    let (proof, options) = (envelope.proof.clone(), policy.acceptable_options());
+    match policy.random_coin {
+        CoinKind::Default => verify_with_coin::<LinearAir, DefaultRandomCoin<HashFn>>(proof, tagged, &options),
+        CoinKind::Keccak => verify_with_coin::<LinearAir, KeccakCoin<HashFn>>(proof, tagged, &options),
+    }
+    .map_err(SessionError::Verifier)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::audit::verify_log;
+    use crate::beacon::{DrandChain, DrandScheme};
+    use crate::domain::DomainTag;
+    use crate::provenance::ProverVersion;
+    use crate::resources::{Calibration, ResourceLimits};
+    use crate::schema::ColumnSchema;
+
+    #[test]
+    fn test_session_proves_and_audits() {
+        let path = std::env::temp_dir().join(format!("session-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut session = ProvingSession::new(ProverConfig::default(), "ci")
+            .with_audit_log(AuditLog::open(&path).unwrap());
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3, 7, 10],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let envelope = session.prove_linear(&request).unwrap();
+
+        assert_eq!(envelope.public_inputs[1], BaseElement::from(21u64));
+        verify_linear(&envelope, &VerificationPolicy::default()).unwrap();
+        assert_eq!(verify_log(&path).unwrap(), 1);
+
+        let mut stale = envelope.clone();
+        stale.constraint_fingerprint = ConstraintFingerprint([0u8; 32]);
+        assert!(matches!(
+            verify_linear(&stale, &VerificationPolicy::default()),
+            Err(SessionError::FingerprintMismatch { .. })
+        ));
+
+        let other_tenant = VerificationPolicy { domain: DomainTag::new("other-app").unwrap(), ..Default::default() };
+        assert!(matches!(verify_linear(&envelope, &other_tenant), Err(SessionError::Verifier(_))));
+        let strict = VerificationPolicy { max_public_inputs: 8, ..Default::default() };
+        assert!(matches!(verify_linear(&envelope, &strict), Err(SessionError::PublicInputLimit(_))));
+        let mut small = ProvingSession::new(ProverConfig { max_public_inputs: 8, ..Default::default() }, "ci");
+        assert!(matches!(small.prove_linear(&request), Err(SessionError::PublicInputLimit(_))));
+        let unfoldable = ProverConfig { fri_folding_factor: 16, fri_remainder_max_degree: 1, ..Default::default() };
+        assert!(matches!(
+            ProvingSession::new(unfoldable, "ci").prove_linear(&request),
+            Err(SessionError::Config(ConfigError::FoldingSchedule { trace_length: 8, .. }))
+        ));
+
+        let ground = VerificationPolicy { min_grinding_bits: 8, ..Default::default() };
+        assert!(matches!(
+            verify_linear(&envelope, &ground),
+            Err(SessionError::InsufficientGrinding { required: 8, found: 0 })
+        ));
+        let config = ProverConfig { grinding_factor: 8, worker_threads: 2, deterministic: false, ..Default::default() };
+        // the grinding runs in the background while the caller holds the pending envelope
+        let mut grinding = ProvingSession::new(config, "ci");
+        let pending = grinding.spawn_linear(&request).unwrap();
+        let ground_envelope = grinding.finish(pending).unwrap();
+        verify_linear(&ground_envelope, &ground).unwrap();
+
+        let terms = TermsHash::of(b"CC-BY-4.0");
+        let licensed = ProvingSession::new(ProverConfig::default(), "ci")
+            .prove_linear(&LinearClaimRequest { terms_hash: Some(terms), ..request.clone() })
+            .unwrap();
+        let licensing = VerificationPolicy { required_terms_hash: Some(terms), ..Default::default() };
+        verify_linear(&licensed, &licensing).unwrap();
+
+        assert!(matches!(
+            verify_linear(&envelope, &licensing),
+            Err(SessionError::TermsMismatch { found: None, .. })
+        ));
+        // the terms hash is part of the public inputs, so it cannot be swapped after proving
+        let relicensed = licensed.clone().with_terms_hash(TermsHash::of(b"CC-BY-NC-4.0"));
+        assert!(matches!(verify_linear(&relicensed, &VerificationPolicy::default()), Err(SessionError::Verifier(_))));
+
+        let current = VerificationPolicy { min_prover_version: Some(ProverVersion::current()), ..Default::default() };
+        assert!(matches!(
+            verify_linear(&envelope, &current),
+            Err(SessionError::Provenance(ProvenanceError::Missing))
+        ));
+        let traced =
+            ProvingSession::new(ProverConfig::default(), "ci").with_provenance().prove_linear(&request).unwrap();
+        assert_eq!(traced.provenance.as_ref().unwrap().crate_version, ProverVersion::current());
+        // an unsigned version is only the prover's word
+        assert!(matches!(
+            verify_linear(&traced, &current),
+            Err(SessionError::Provenance(ProvenanceError::Unsigned))
+        ));
+        let signed = ProvingSession::new(ProverConfig::default(), "ci")
+            .with_provenance()
+            .with_operator_key(OperatorKey::from_seed(&[1u8; 32]))
+            .prove_linear(&request)
+            .unwrap();
+        verify_linear(&signed, &current).unwrap();
+        let mut altered = signed.clone();
+        altered.provenance.as_mut().unwrap().crate_version.minor += 1;
+        assert!(matches!(verify_linear(&altered, &current), Err(SessionError::Signer(SignerError::BadSignature))));
+
+        let key = OperatorKey::from_seed(&[1u8; 32]);
+        let trusted = VerificationPolicy { trusted_operators: vec![key.public_key()], ..Default::default() };
+        assert!(matches!(verify_linear(&envelope, &trusted), Err(SessionError::Signer(SignerError::Unsigned))));
+        let signed = ProvingSession::new(ProverConfig::default(), "ci")
+            .with_operator_key(key)
+            .prove_linear(&request)
+            .unwrap();
+        verify_linear(&signed, &trusted).unwrap();
+        let mut forged = signed.clone();
+        forged.signer.as_mut().unwrap().signature[0] ^= 1;
+        assert!(matches!(
+            verify_linear(&forged, &VerificationPolicy::default()),
+            Err(SessionError::Signer(SignerError::BadSignature))
+        ));
+
+        let chain = DrandChain {
+            scheme: DrandScheme::BlsUnchainedG1,
+            public_key: "ab".repeat(96),
+            genesis_time: 0,
+            period: 3,
+        };
+        let beaconed = VerificationPolicy { beacon: Some(chain), ..Default::default() };
+        assert!(matches!(verify_linear(&envelope, &beaconed), Err(SessionError::Beacon(BeaconError::MissingRound))));
+        let round = BeaconRound { round: 5, randomness: "11".repeat(32), signature: "22".repeat(48), previous_signature: None };
+        // the points are drawn from the round, not chosen by the prover
+        let unsampled = LinearClaimRequest { beacon: Some(round.clone()), ..request.clone() };
+        assert!(matches!(
+            ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&unsampled),
+            Err(SessionError::Beacon(BeaconError::PointsMismatch))
+        ));
+        let drawn = request.clone().with_beacon(round.clone(), &DomainTag::default()).unwrap();
+        assert_eq!(drawn.sample_x.len(), request.sample_x.len());
+        let sampled = ProvingSession::new(ProverConfig::default(), "ci").prove_linear(&drawn).unwrap();
+        assert_eq!(sampled.beacon, Some(round.clone()));
+        assert_ne!(sampled.claim_hash(&DomainTag::default()), envelope.claim_hash(&DomainTag::default()));
+        verify_linear(&sampled, &VerificationPolicy::default()).unwrap();
+        assert!(matches!(
+            verify_linear(&sampled, &beaconed),
+            Err(SessionError::Beacon(BeaconError::RandomnessMismatch))
+        ));
+        let other_domain = VerificationPolicy { domain: DomainTag::new("other-app").unwrap(), ..Default::default() };
+        let other = ProvingSession::new(ProverConfig { domain: other_domain.domain.clone(), ..Default::default() }, "ci")
+            .prove_linear(&request.clone().with_beacon(round.clone(), &other_domain.domain).unwrap())
+            .unwrap();
+        verify_linear(&other, &other_domain).unwrap();
+        let mut rebound = other;
+        rebound.beacon = Some(BeaconRound { round: 6, ..round });
+        assert!(matches!(
+            verify_linear(&rebound, &other_domain),
+            Err(SessionError::Beacon(BeaconError::PointsMismatch))
+        ));
+
+        let area = ColumnSchema::new("area", "m^2", 0, 0, 10);
+        let schema = DataSchema::new(vec![area], ColumnSchema::new("price", "kEUR", 0, 0, 25)).unwrap();
+        let described = ProvingSession::new(ProverConfig::default(), "ci")
+            .prove_linear(&LinearClaimRequest { schema: Some(schema.clone()), ..request.clone() })
+            .unwrap();
+        assert_eq!(described.schema, Some(schema.clone()));
+        assert_ne!(described.claim_hash(&DomainTag::default()), envelope.claim_hash(&DomainTag::default()));
+        verify_linear(&described, &VerificationPolicy::default()).unwrap();
+        let cheap = DataSchema { target: ColumnSchema::new("price", "kEUR", 0, 0, 20), ..schema.clone() };
+        assert!(matches!(
+            session.prove_linear(&LinearClaimRequest { schema: Some(cheap.clone()), ..request.clone() }),
+            Err(SessionError::Schema(SchemaError::OutOfRange { index: 0, value: 21, .. }))
+        ));
+        // a schema swapped after proving no longer fits the proof, and one the claim's points
+        // fall outside of is rejected before the proof is checked
+        let mut swapped = described.clone();
+        swapped.schema = Some(DataSchema { target: ColumnSchema::new("price", "EUR", 0, 0, 25), ..schema });
+        assert!(matches!(verify_linear(&swapped, &VerificationPolicy::default()), Err(SessionError::Verifier(_))));
+        swapped.schema = Some(cheap);
+        assert!(matches!(
+            verify_linear(&swapped, &VerificationPolicy::default()),
+            Err(SessionError::Schema(SchemaError::OutOfRange { index: 0, value: 21, .. }))
+        ));
+
+        let calibration = Calibration { seconds_per_unit: 1e-6, overhead_seconds: 0.0 };
+        let limits = ResourceLimits { max_seconds: None, max_memory_bytes: Some(4096) };
+        let mut limited = ProvingSession::new(ProverConfig::default(), "ci")
+            .with_audit_log(AuditLog::open(&path).unwrap())
+            .with_resource_limits(calibration, limits);
+        assert!(matches!(limited.prove_linear(&request), Err(SessionError::ResourceLimit(_))));
+        assert_eq!(verify_log(&path).unwrap(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_trace_pool_reuses_buffers() {
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[3u8; 32]),
+            slope: 2,
+            intercept: 5,
+            sample_x: vec![1, 3, 7, 10],
+            x: 8,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        let mut unpooled = ProvingSession::new(ProverConfig::default(), "ci");
+        unpooled.prove_linear(&request).unwrap();
+        unpooled.prove_linear(&request).unwrap();
+        assert_eq!(unpooled.metrics().trace_buffer_allocations(), 2 * TRACE_WIDTH as u64);
+
+        let mut pooled =
+            ProvingSession::new(ProverConfig::default(), "ci").with_trace_pool(Arc::new(TracePool::default()));
+        let envelope = pooled.prove_linear(&request).unwrap();
+        pooled.prove_linear(&request).unwrap();
+        assert_eq!(pooled.metrics().trace_buffer_allocations(), TRACE_WIDTH as u64);
+        assert_eq!(pooled.metrics().trace_buffer_reuses(), TRACE_WIDTH as u64);
+        verify_linear(&envelope, &VerificationPolicy::default()).unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+//! Linear regression witnesses supplied in additive shares by several parties.
+//!
+//! When the slope and the intercept are held by different teams, no single party should ever
+//! see both. Each party hands in a [`LinearShare`]; the parameters are the sums of the shares,
+//! so any subset short of all of them is uniformly random. [`SharedLinearWitness`] combines the
+//! shares just in time, in memory, to build the trace and wipes both the shares and the
+//! combined parameters once the trace is built, see [`secret`](crate::secret). The trace
+//! itself still holds the parameters until it is dropped by the prover.
+
+use core::fmt;
+
+use winterfell::{math::fields::f128::BaseElement, TraceTable};
+
+use zeroize::Zeroize;
+
+use crate::regression::{build_linear_regression_trace, LinearRegressionInputs};
+use crate::secret::{zeroize_elements, SecretElements, SecretScope};
+
+/// One party's additive share of the slope and the intercept
+#[derive(Clone, PartialEq, Eq)]
+pub struct LinearShare {
+    pub party: usize,
+    pub slope: BaseElement,
+    pub intercept: BaseElement,
+}
+
+impl fmt::Debug for LinearShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LinearShare").field("party", &self.party).finish_non_exhaustive()
+    }
+}
+
+fn random_element() -> BaseElement {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("The operating system provides randomness");
+    BaseElement::new(u128::from_le_bytes(bytes))
+}
+
+/// Splits `slope` and `intercept` into one share for each of `parties` parties
+pub fn split_linear(slope: BaseElement, intercept: BaseElement, parties: usize) -> Vec<LinearShare> {
+    assert!(parties > 0, "At least one party holds a share");
+    let mut shares: Vec<LinearShare> = (1..parties)
+        .map(|party| LinearShare { party, slope: random_element(), intercept: random_element() })
+        .collect();
+    let last = LinearShare {
+        party: 0,
+        slope: shares.iter().fold(slope, |rest, share| rest - share.slope),
+        intercept: shares.iter().fold(intercept, |rest, share| rest - share.intercept),
+    };
+    shares.insert(0, last);
+    shares
+}
+
+impl Zeroize for LinearShare {
+    fn zeroize(&mut self) {
+        zeroize_elements(core::slice::from_mut(&mut self.slope));
+        zeroize_elements(core::slice::from_mut(&mut self.intercept));
+    }
+}
+
+/// Reason shares cannot be combined into a witness
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShareError {
+    /// The share names a party outside `0..parties`
+    UnknownParty(usize),
+    /// The party already handed in its share
+    DuplicateParty(usize),
+    /// These parties have not handed in their shares yet
+    MissingShares(Vec<usize>),
+    /// The witness was already used to build a trace and its shares are wiped
+    Consumed,
+}
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareError::UnknownParty(party) => write!(f, "party {party} holds no share of this witness"),
+            ShareError::DuplicateParty(party) => write!(f, "party {party} already handed in its share"),
+            ShareError::MissingShares(parties) => write!(f, "shares of parties {parties:?} are missing"),
+            ShareError::Consumed => write!(f, "the shares were already combined and wiped"),
+        }
+    }
+}
+
+impl std::error::Error for ShareError {}
+
+/// Shares of a linear regression witness collected from `parties` parties; shares are wiped
+/// once combined or when the witness is dropped
+pub struct SharedLinearWitness {
+    shares: Vec<Option<LinearShare>>,
+    consumed: bool,
+}
+
+impl SharedLinearWitness {
+    pub fn new(parties: usize) -> Self {
+        assert!(parties > 0, "At least one party holds a share");
+        Self { shares: vec![None; parties], consumed: false }
+    }
+
+    /// Records `share`; each party hands in exactly one
+    pub fn add(&mut self, share: LinearShare) -> Result<(), ShareError> {
+        if self.consumed {
+            return Err(ShareError::Consumed);
+        }
+        let slot = self.shares.get_mut(share.party).ok_or(ShareError::UnknownParty(share.party))?;
+        if slot.is_some() {
+            return Err(ShareError::DuplicateParty(share.party));
+        }
+        *slot = Some(share);
+        Ok(())
+    }
+
+    /// Parties whose shares are still missing
+    pub fn missing(&self) -> Vec<usize> {
+        (0..self.shares.len()).filter(|&party| self.shares[party].is_none()).collect()
+    }
+
+    /// Combines the shares and builds the trace of at least `min_trace_length` rows proving the
+    /// model's predictions at `sample_x` and `target_x`, then wipes the shares and the combined
+    /// parameters
+    pub fn build_trace(
+        &mut self,
+        sample_x: &[BaseElement],
+        target_x: BaseElement,
+        min_trace_length: usize,
+    ) -> Result<(TraceTable<BaseElement>, LinearRegressionInputs), ShareError> {
+        if self.consumed {
+            return Err(ShareError::Consumed);
+        }
+        let missing = self.missing();
+        if !missing.is_empty() {
+            return Err(ShareError::MissingShares(missing));
+        }
+
+        let mut parameters = SecretScope::new(SecretElements::zeroed(2));
+        for share in self.shares.iter().flatten() {
+            parameters[0] += share.slope;
+            parameters[1] += share.intercept;
+        }
+        let (slope, intercept) = (parameters[0], parameters[1]);
+        let sample_y: Vec<BaseElement> = sample_x.iter().map(|&x| slope * x + intercept).collect();
+        let trace = build_linear_regression_trace(slope, intercept, sample_x, &sample_y, target_x, min_trace_length);
+        let inputs = LinearRegressionInputs {
+            x_value: target_x,
+            predicted_y: slope * target_x + intercept,
+            sample_x_values: sample_x.to_vec(),
+            sample_y_values: sample_y,
+        };
+
+        self.shares.iter_mut().flatten().for_each(Zeroize::zeroize);
+        self.consumed = true;
+        Ok((trace, inputs))
+    }
+}
+
+impl Drop for SharedLinearWitness {
+    fn drop(&mut self) {
+        self.shares.iter_mut().flatten().for_each(Zeroize::zeroize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regression::LinearRegressionAir;
+    use winterfell::{math::FieldElement, Trace, TraceInfo};
+
+    #[test]
+    fn test_shares_reconstruct_the_witness() {
+        let (slope, intercept) = (BaseElement::from(3u64), BaseElement::from(7u64));
+        let sample_x = [1u64, 2, 5].map(BaseElement::from);
+        let target_x = BaseElement::from(10u64);
+        let shares = split_linear(slope, intercept, 3);
+        assert!(shares.iter().all(|share| share.slope != slope), "No single share reveals the slope");
+
+        let mut witness = SharedLinearWitness::new(3);
+        for share in shares.into_iter().rev() {
+            witness.add(share).unwrap();
+        }
+        let (trace, inputs) = witness.build_trace(&sample_x, target_x, TraceInfo::MIN_TRACE_LENGTH).unwrap();
+        assert_eq!(inputs.predicted_y, BaseElement::from(37u64));
+        let direct = build_linear_regression_trace(
+            slope,
+            intercept,
+            &sample_x,
+            &inputs.sample_y_values,
+            target_x,
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        for column in 0..trace.main_segment().num_cols() {
+            assert_eq!(trace.main_segment().get_column(column), direct.main_segment().get_column(column));
+        }
+        crate::air_test!(LinearRegressionAir, inputs).with_trace(&trace).expect_all_hold().expect_assertions_hold();
+
+        let zero = BaseElement::ZERO;
+        assert!(witness.shares.iter().flatten().all(|share| share.slope == zero && share.intercept == zero));
+        let consumed = witness.build_trace(&sample_x, target_x, TraceInfo::MIN_TRACE_LENGTH);
+        assert_eq!(consumed.unwrap_err(), ShareError::Consumed);
+    }
+
+    #[test]
+    fn test_rejects_incomplete_shares() {
+        let mut shares = split_linear(BaseElement::ONE, BaseElement::ONE, 2).into_iter();
+        let mut witness = SharedLinearWitness::new(2);
+        let first = shares.next().unwrap();
+        witness.add(first.clone()).unwrap();
+        assert_eq!(witness.add(first), Err(ShareError::DuplicateParty(0)));
+        let stranger = LinearShare { party: 2, slope: BaseElement::ONE, intercept: BaseElement::ONE };
+        assert_eq!(witness.add(stranger), Err(ShareError::UnknownParty(2)));
+        let result = witness.build_trace(&[BaseElement::ONE], BaseElement::ONE, TraceInfo::MIN_TRACE_LENGTH);
+        assert_eq!(result.unwrap_err(), ShareError::MissingShares(vec![1]));
+        assert!(format!("{:?}", shares.next().unwrap()).starts_with("LinearShare { party: 1, .. }"));
+    }
+}
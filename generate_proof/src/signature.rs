@@ -0,0 +1,344 @@
+//! Hash-based one-time signatures over the Rescue permutation, verified inside an AIR.
+//!
+//! The scheme is binary Winternitz (WOTS with `w = 2`): a message of [`MESSAGE_BITS`] bits is
+//! extended with a [`CHECKSUM_BITS`]-bit count of its zero bits, and chain `i` reveals either
+//! its secret element (bit 0) or the element's hash (bit 1). A verifier re-hashes the chains of
+//! the zero bits and compresses the results into the public key. Raising bits is trivial for
+//! a forger but lowering one needs a hash preimage, and the checksum turns every raised
+//! message bit into a lowered checksum bit.
+//!
+//! A key signs exactly one message. Data providers sign [`dataset_digest`] of their samples;
+//! [`SignatureAir`] then proves "the dataset with this digest carries a valid signature under
+//! public key PK" without revealing the signature.
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+    TransitionConstraintDegree, 
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, NUM_PERIODIC_COLUMNS, STATE_WIDTH};
+
+/// Bits of the signed message; below 128 so every message is a canonical field element
+pub const MESSAGE_BITS: usize = 120;
+/// Bits needed for the zero count of a [`MESSAGE_BITS`]-bit message
+pub const CHECKSUM_BITS: usize = 7;
+pub const NUM_CHAINS: usize = MESSAGE_BITS + CHECKSUM_BITS;
+
+const SEED_DOMAIN: &[u8] = b"stark-framework/wots/v1/secret-key";
+// Sponge tags; `crate::freshness` uses 3
+const PUBLIC_KEY_TAG: u64 = 1;
+const DATASET_TAG: u64 = 2;
+
+// Trace layout: chain hash state, public key sponge state, chain input, chain index, bit and
+// the bit accumulator. Every cycle handles one chain; the sponge absorbs that chain's public
+// element during the next cycle, so the trace has one more cycle than there are chains.
+const CHAIN: usize = 0;
+const SPONGE: usize = CHAIN + STATE_WIDTH;
+const INPUT: usize = SPONGE + STATE_WIDTH;
+const INDEX: usize = INPUT + 1;
+const BIT: usize = INDEX + 1;
+const ACC: usize = BIT + 1;
+const TRACE_WIDTH: usize = ACC + 1;
+const TRACE_LENGTH: usize = (NUM_CHAINS + 1) * CYCLE_LENGTH;
+
+/// Hash of chain element `value` at position `index`
+pub fn chain_hash(value: BaseElement, index: usize) -> BaseElement {
+    let mut state = [value, BaseElement::from(index as u64), BaseElement::ZERO, BaseElement::ZERO];
+    rescue::permute(&mut state);
+    state[0]
+}
+
+/// Message a data provider signs for `samples`: their Rescue digest truncated to [`MESSAGE_BITS`]
+pub fn dataset_digest(samples: &[BaseElement]) -> u128 {
+    rescue::hash_elements(samples, DATASET_TAG).as_int() & ((1 << MESSAGE_BITS) - 1)
+}
+
+/// The message bits (most significant first) followed by the checksum bits
+fn chain_bits(message: u128) -> Vec<bool> {
+    assert!(message >> MESSAGE_BITS == 0, "Message must fit into {MESSAGE_BITS} bits");
+    let checksum = zero_count(message);
+    let message_bits = (0..MESSAGE_BITS).rev().map(|i| (message >> i) & 1 == 1);
+    let checksum_bits = (0..CHECKSUM_BITS).rev().map(|i| (checksum >> i) & 1 == 1);
+    message_bits.chain(checksum_bits).collect()
+}
+
+fn zero_count(message: u128) -> u128 {
+    (MESSAGE_BITS - message.count_ones() as usize) as u128
+}
+
+/// Compressed public key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(pub BaseElement);
+
+/// One revealed element per chain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature(pub Vec<BaseElement>);
+
+/// One-time secret key; sign a single message with it and discard it
+pub struct SecretKey {
+    elements: Vec<BaseElement>,
+}
+
+impl SecretKey {
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let mut reader = blake3::Hasher::new_keyed(seed).update(SEED_DOMAIN).finalize_xof();
+        let mut elements = Vec::with_capacity(NUM_CHAINS);
+        while elements.len() < NUM_CHAINS {
+            let mut bytes = [0u8; 16];
+            reader.fill(&mut bytes);
+            let value = u128::from_le_bytes(bytes);
+            if value < BaseElement::MODULUS {
+                elements.push(BaseElement::new(value));
+            }
+        }
+        Self { elements }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        let chain_keys: Vec<BaseElement> =
+            self.elements.iter().enumerate().map(|(i, &sk)| chain_hash(sk, i)).collect();
+        PublicKey(rescue::hash_elements(&chain_keys, PUBLIC_KEY_TAG))
+    }
+
+    pub fn sign(&self, message: u128) -> Signature {
+        let elements = self
+            .elements
+            .iter()
+            .zip(chain_bits(message))
+            .enumerate()
+            .map(|(i, (&sk, bit))| if bit { chain_hash(sk, i) } else { sk })
+            .collect();
+        Signature(elements)
+    }
+}
+
+impl Signature {
+    /// Public key the signature verifies under for `message`
+    pub fn recover(&self, message: u128) -> PublicKey {
+        let chain_keys: Vec<BaseElement> = self
+            .0
+            .iter()
+            .zip(chain_bits(message))
+            .enumerate()
+            .map(|(i, (&element, bit))| if bit { element } else { chain_hash(element, i) })
+            .collect();
+        PublicKey(rescue::hash_elements(&chain_keys, PUBLIC_KEY_TAG))
+    }
+
+    pub fn verify(&self, public_key: &PublicKey, message: u128) -> bool {
+        self.0.len() == NUM_CHAINS && message >> MESSAGE_BITS == 0 && self.recover(message) == *public_key
+    }
+}
+
+/// Public inputs: the signer's key and the signed message
+#[derive(Clone, Copy, Debug)]
+pub struct SignatureInputs {
+    pub public_key: PublicKey,
+    pub message: u128,
+}
+
+impl ToElements<BaseElement> for SignatureInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![self.public_key.0, BaseElement::new(self.message)]
+    }
+}
+
+/// AIR proving knowledge of a valid [`Signature`] on `message` under `public_key`
+pub struct SignatureAir {
+    context: AirContext<BaseElement>,
+    inputs: SignatureInputs,
+    chain: RescueGadget,
+    sponge: RescueGadget,
+}
+
+impl Air for SignatureAir {
+    type BaseField = BaseElement;
+    type PublicInputs = SignatureInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: SignatureInputs, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        assert_eq!(TRACE_LENGTH, trace_info.length());
+        assert!(pub_inputs.message >> MESSAGE_BITS == 0, "Message must fit into {MESSAGE_BITS} bits");
+
+        let chain = RescueGadget::new(CHAIN);
+        let sponge = RescueGadget::new(SPONGE);
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the chain hash and of the public key sponge (degree 3 each)
+        // 2. Chain hash input [input, index, 0, 0] on the first row of a cycle (degree 1)
+        // 3. Sponge absorbs the chain's public element at the end of a cycle: input if the bit
+        //    is 1, its hash otherwise (degree 2 for the element, 1 for the other lanes)
+        // 4. Input is constant within a cycle (degree 1)
+        // 5. Index increments at the end of each cycle (degree 1, no periodic factor)
+        // 6. Bit is boolean at the end of a cycle (degree 2)
+        // 7. Accumulator shifts the bit in at the end of each cycle (degree 1)
+        let mut degrees = chain.constraint_degrees();
+        degrees.extend(sponge.constraint_degrees());
+        degrees.extend(vec![periodic(1); STATE_WIDTH]);
+        degrees.push(periodic(2));
+        degrees.extend(vec![periodic(1); STATE_WIDTH - 1]);
+        degrees.push(periodic(1));
+        degrees.push(TransitionConstraintDegree::new(1));
+        degrees.push(periodic(2));
+        degrees.push(periodic(1));
+
+        SignatureAir {
+            context: AirContext::new(trace_info, degrees, 9, options),
+            inputs: pub_inputs,
+            chain,
+            sponge,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let (rounds, first) = periodic_values.split_at(NUM_PERIODIC_COLUMNS);
+        let (first, last) = (first[0], E::ONE - rounds[0]);
+
+        let (chain_result, rest) = result.split_at_mut(STATE_WIDTH);
+        self.chain.evaluate(current, next, rounds, chain_result);
+        let (sponge_result, rest) = rest.split_at_mut(STATE_WIDTH);
+        self.sponge.evaluate(current, next, rounds, sponge_result);
+
+        rest[0] = first * (current[CHAIN] - current[INPUT]);
+        rest[1] = first * (current[CHAIN + 1] - current[INDEX]);
+        rest[2] = first * current[CHAIN + 2];
+        rest[3] = first * current[CHAIN + 3];
+
+        let bit = current[BIT];
+        let chain_key = bit * current[INPUT] + (E::ONE - bit) * current[CHAIN];
+        rest[4] = last * (next[SPONGE] - current[SPONGE] - chain_key);
+        for i in 1..STATE_WIDTH {
+            rest[4 + i] = last * (next[SPONGE + i] - current[SPONGE + i]);
+        }
+
+        rest[8] = rounds[0] * (next[INPUT] - current[INPUT]);
+        rest[9] = next[INDEX] - current[INDEX] - last;
+        rest[10] = last * bit * (bit - E::ONE);
+        rest[11] = next[ACC] - current[ACC] - last * (current[ACC] + bit);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        let mut columns = rescue::periodic_columns();
+        let mut first = vec![BaseElement::ZERO; CYCLE_LENGTH];
+        first[0] = BaseElement::ONE;
+        columns.push(first);
+        columns
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let message = self.inputs.message;
+        let signed_bits = (message << CHECKSUM_BITS) + zero_count(message);
+
+        let mut assertions = vec![Assertion::single(INDEX, 0, BaseElement::ZERO), Assertion::single(ACC, 0, BaseElement::ZERO)];
+        for (i, value) in rescue::sponge_iv(NUM_CHAINS, PUBLIC_KEY_TAG).into_iter().enumerate() {
+            assertions.push(Assertion::single(SPONGE + i, 0, value));
+        }
+        assertions.push(Assertion::single(ACC, MESSAGE_BITS * CYCLE_LENGTH, BaseElement::new(message)));
+        assertions.push(Assertion::single(ACC, NUM_CHAINS * CYCLE_LENGTH, BaseElement::new(signed_bits)));
+        assertions.push(Assertion::single(SPONGE, TRACE_LENGTH - 1, self.inputs.public_key.0));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace verifying `signature` on `message`
+#[cfg(feature = "prover")]
+pub fn build_signature_trace(signature: &Signature, message: u128) -> TraceTable<BaseElement> {
+    assert_eq!(signature.0.len(), NUM_CHAINS, "Signature must have one element per chain");
+    let bits = chain_bits(message);
+    let chain = RescueGadget::new(CHAIN);
+    let sponge = RescueGadget::new(SPONGE);
+
+    let mut columns = vec![vec![BaseElement::ZERO; TRACE_LENGTH]; TRACE_WIDTH];
+    let mut row = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut sponge_state = rescue::sponge_iv(NUM_CHAINS, PUBLIC_KEY_TAG);
+    let mut acc = BaseElement::ZERO;
+
+    for cycle in 0..=NUM_CHAINS {
+        // The extra final cycle only finishes the sponge; its chain lanes hash a dummy input
+        let input = signature.0.get(cycle).copied().unwrap_or(BaseElement::ZERO);
+        let bit = bits.get(cycle).copied().unwrap_or(false);
+        let mut chain_state = [input, BaseElement::from(cycle as u64), BaseElement::ZERO, BaseElement::ZERO];
+
+        for step in 0..CYCLE_LENGTH {
+            let i = cycle * CYCLE_LENGTH + step;
+            chain.write(&mut row, &chain_state);
+            sponge.write(&mut row, &sponge_state);
+            row[INPUT] = input;
+            row[INDEX] = BaseElement::from(cycle as u64);
+            row[BIT] = BaseElement::from(bit as u64);
+            row[ACC] = acc;
+            for (column, &value) in columns.iter_mut().zip(&row) {
+                column[i] = value;
+            }
+
+            if step < rescue::NUM_ROUNDS {
+                rescue::apply_round(&mut chain_state, step);
+                rescue::apply_round(&mut sponge_state, step);
+            }
+        }
+
+        let chain_key = if bit { input } else { chain_state[0] };
+        sponge_state[0] += chain_key;
+        acc = acc.double() + BaseElement::from(bit as u64);
+    }
+
+    // The bit of the exempt last row is never read; give it a non-boolean value
+    columns[BIT][TRACE_LENGTH - 1] = BaseElement::new(2);
+
+    TraceTable::init(columns)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_native_signature() {
+        let key = SecretKey::from_seed(&[7u8; 32]);
+        let message = dataset_digest(&[BaseElement::new(1), BaseElement::new(3)]);
+        let signature = key.sign(message);
+
+        assert!(signature.verify(&key.public_key(), message));
+        assert!(!signature.verify(&key.public_key(), message ^ 1));
+        assert!(!signature.verify(&SecretKey::from_seed(&[8u8; 32]).public_key(), message));
+    }
+
+    #[test]
+    fn test_signature_proof() {
+        let key = SecretKey::from_seed(&[7u8; 32]);
+        let samples = [1u128, 3, 7, 10].map(BaseElement::new);
+        let message = dataset_digest(&samples);
+        let inputs = SignatureInputs { public_key: key.public_key(), message };
+
+        let trace = build_signature_trace(&key.sign(message), message);
+        crate::air_test!(SignatureAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let proof = prove::<SignatureAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+
+        let other_message = dataset_digest(&[BaseElement::new(2)]);
+        let forged = SignatureInputs { message: other_message, ..inputs };
+        assert!(verify::<SignatureAir>(proof.clone(), forged, &default_acceptable_options()).is_err());
+        let result = verify::<SignatureAir>(proof, inputs, &default_acceptable_options());
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+}
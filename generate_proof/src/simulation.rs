@@ -0,0 +1,546 @@
+//! Simulation mode: checks a trace against an AIR without proving it.
+//!
+//! [`dry_run`] runs the checks Winterfell's prover only performs in debug builds, on the raw
+//! trace: the trace shape, every assertion, every transition constraint on every row but the
+//! last, and the degree of every transition constraint against the degree the AIR declares.
+//! No commitments are built and no FRI runs, so for the small traces used while developing an
+//! AIR a report takes milliseconds instead of the seconds of a proof.
+//!
+//! Every failing transition constraint also yields a [`Counterexample`]: the two rows of the
+//! first frame it fails on, the periodic values and public inputs it was evaluated with, and
+//! [`Counterexample::to_unit_test`] turns it into a test file that fails until the AIR or the
+//! trace builder is fixed.
+//!
+//! [`labeled_dry_run`] does the same for AIRs implementing [`ConstraintLabels`] and names
+//! every failing constraint by its label, e.g. `constraint 0 (linear_relation)`.
+//!
+//! The degree check interpolates the trace columns and evaluates the constraints over the
+//! smallest power-of-two domain that holds the largest declared degree, which is the only
+//! part whose cost grows faster than the trace.
+
+use core::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{any, fs, io};
+
+use winterfell::{
+    math::{fft, fields::f128::BaseElement, polynom, FieldElement, StarkField},
+    math::ToElements,
+    Air, EvaluationFrame, ProofOptions, Trace, TraceInfo, TraceTable,
+};
+
+use crate::constraints::ConstraintLabels;
+use crate::trace::evaluate_transitions;
+
+/// An assertion the trace does not satisfy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AssertionFailure {
+    pub column: usize,
+    pub step: usize,
+    pub expected: BaseElement,
+    pub found: BaseElement,
+}
+
+/// A transition constraint that does not evaluate to zero on every row it covers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransitionFailure {
+    pub constraint: usize,
+    /// First row where the constraint is violated
+    pub first_row: usize,
+    /// The constraint's value on that row
+    pub value: BaseElement,
+    /// Number of rows where the constraint is violated
+    pub num_rows: usize,
+}
+
+/// The smallest input reproducing a [`TransitionFailure`]: the frame of its first row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Counterexample {
+    /// Path of the AIR type, e.g. `linear_regression::regression::LinearRegressionAir`
+    pub air: &'static str,
+    pub constraint: usize,
+    /// Label of the constraint, when the AIR names its constraints
+    pub label: Option<String>,
+    pub row: usize,
+    pub trace_length: usize,
+    /// Rows `row` and `row + 1` of the trace
+    pub current: Vec<BaseElement>,
+    pub next: Vec<BaseElement>,
+    /// Periodic column values on `row`
+    pub periodic: Vec<BaseElement>,
+    /// Public inputs of the instance, as the AIR serializes them
+    pub public_inputs: Vec<BaseElement>,
+    /// The constraint's value on the frame
+    pub value: BaseElement,
+}
+
+impl Counterexample {
+    /// Name of the file [`Counterexample::write_unit_test`] writes
+    pub fn file_name(&self) -> String {
+        let air = self.air.rsplit("::").next().unwrap_or(self.air).trim_end_matches('>');
+        let mut name = String::new();
+        for (i, c) in air.chars().filter(char::is_ascii_alphanumeric).enumerate() {
+            if c.is_ascii_uppercase() && i > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        format!("{name}_constraint_{}_row_{}.rs", self.constraint, self.row)
+    }
+
+    /// Source of an integration test evaluating the constraint on the frame and expecting zero.
+    ///
+    /// `inputs` is a Rust expression building the AIR's public inputs, with full paths as the
+    /// file imports nothing else; it may read `PUBLIC_INPUTS`, the recorded public inputs,
+    /// through `elements(&PUBLIC_INPUTS)`, e.g.
+    /// `linear_regression::regression::LinearRegressionInputs::from_elements(&elements(&PUBLIC_INPUTS)).unwrap()`.
+    /// The test needs `linear_regression` with the `prover` feature and `winterfell`.
+    pub fn to_unit_test(&self, inputs: &str) -> String {
+        let constant = |values: &[BaseElement]| {
+            let values: Vec<String> = values.iter().map(|value| value.as_int().to_string()).collect();
+            format!("[u128; {}] = [{}]", values.len(), values.join(", "))
+        };
+        let (air, constraint, row) = (self.air, self.constraint, self.row);
+        let named = self.label.as_ref().map_or(String::new(), |label| format!(" (`{label}`)"));
+        format!(
+            r#"//! Constraint {constraint}{named} of `{air}` fails on the transition from row {row} to the next.
+//! Generated by `Counterexample::to_unit_test`; passes once the constraint holds.
+
+use linear_regression::config::ProverConfig;
+use winterfell::math::{{fields::f128::BaseElement, FieldElement}};
+use winterfell::{{Air, EvaluationFrame, TraceInfo}};
+
+const TRACE_LENGTH: usize = {length};
+const CURRENT: {current};
+const NEXT: {next};
+const PERIODIC: {periodic};
+const PUBLIC_INPUTS: {public_inputs};
+
+fn elements(values: &[u128]) -> Vec<BaseElement> {{
+    values.iter().map(|&value| BaseElement::new(value)).collect()
+}}
+
+#[test]
+fn constraint_{constraint}_holds_on_row_{row}() {{
+    let inputs = {inputs};
+    let trace_info = TraceInfo::new(CURRENT.len(), TRACE_LENGTH);
+    let air = <{air}>::new(trace_info, inputs, ProverConfig::default().to_proof_options());
+    let frame = EvaluationFrame::from_rows(elements(&CURRENT), elements(&NEXT));
+    let mut result = vec![BaseElement::ZERO; air.context().num_main_transition_constraints()];
+    air.evaluate_transition(&frame, &elements(&PERIODIC), &mut result);
+    assert_eq!(result[{constraint}], BaseElement::ZERO);
+}}
+"#,
+            length = self.trace_length,
+            current = constant(&self.current),
+            next = constant(&self.next),
+            periodic = constant(&self.periodic),
+            public_inputs = constant(&self.public_inputs),
+        )
+    }
+
+    /// Writes [`Counterexample::to_unit_test`] into `dir`, e.g. the crate's `tests/`
+    pub fn write_unit_test(&self, dir: impl AsRef<Path>, inputs: &str) -> io::Result<PathBuf> {
+        let path = dir.as_ref().join(self.file_name());
+        fs::write(&path, self.to_unit_test(inputs))?;
+        Ok(path)
+    }
+}
+
+/// A transition constraint whose polynomial degree differs from the declared one.
+///
+/// Degrees are those of the constraint polynomial divided by the transition divisor, as in
+/// Winterfell's debug check, so a constraint that vanishes identically has degree 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DegreeMismatch {
+    pub constraint: usize,
+    /// Degree Winterfell derives from the declared degree
+    pub declared: usize,
+    pub actual: usize,
+}
+
+/// Outcome of a [`dry_run`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DryRunReport {
+    pub trace_width: usize,
+    pub trace_length: usize,
+    pub num_assertions: usize,
+    pub num_constraints: usize,
+    pub assertion_failures: Vec<AssertionFailure>,
+    pub transition_failures: Vec<TransitionFailure>,
+    pub degree_mismatches: Vec<DegreeMismatch>,
+    /// One for every entry of `transition_failures`, in the same order
+    pub counterexamples: Vec<Counterexample>,
+    /// One label per transition constraint, or none when the AIR does not name them
+    pub constraint_labels: Vec<String>,
+    pub elapsed: Duration,
+}
+
+impl DryRunReport {
+    /// Whether proving the trace would succeed
+    pub fn is_ok(&self) -> bool {
+        self.assertion_failures.is_empty() && self.transition_failures.is_empty() && self.degree_mismatches.is_empty()
+    }
+
+    pub fn label(&self, constraint: usize) -> Option<&str> {
+        self.constraint_labels.get(constraint).map(String::as_str)
+    }
+
+    /// `constraint 3`, or `constraint 3 (slope_constant)` when the constraint is labelled
+    fn constraint_name(&self, constraint: usize) -> String {
+        match self.label(constraint) {
+            Some(label) => format!("constraint {constraint} ({label})"),
+            None => format!("constraint {constraint}"),
+        }
+    }
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "trace: {} columns x {} rows, {} assertions, {} transition constraints",
+            self.trace_width, self.trace_length, self.num_assertions, self.num_constraints
+        )?;
+        for failure in &self.assertion_failures {
+            writeln!(
+                f,
+                "assertion on column {} at step {}: expected {}, found {}",
+                failure.column, failure.step, failure.expected, failure.found
+            )?;
+        }
+        for failure in &self.transition_failures {
+            writeln!(
+                f,
+                "{} fails on {} rows, first at row {} with value {}",
+                self.constraint_name(failure.constraint),
+                failure.num_rows,
+                failure.first_row,
+                failure.value
+            )?;
+        }
+        for mismatch in &self.degree_mismatches {
+            writeln!(
+                f,
+                "{} has degree {}, declared {}",
+                self.constraint_name(mismatch.constraint),
+                mismatch.actual,
+                mismatch.declared
+            )?;
+        }
+        if self.is_ok() {
+            writeln!(f, "trace satisfies the AIR")?;
+        }
+        writeln!(f, "checked in {:.1} ms", self.elapsed.as_secs_f64() * 1e3)
+    }
+}
+
+/// Reasons a dry run cannot evaluate the AIR at all
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DryRunError {
+    /// Winterfell needs a power-of-two trace of at least 8 rows
+    InvalidLength(usize),
+    /// `Air::new` rejected the trace shape or the public inputs
+    AirRejected(String),
+}
+
+impl fmt::Display for DryRunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DryRunError::InvalidLength(length) => {
+                write!(f, "trace length {length} is not a power of two of at least {}", TraceInfo::MIN_TRACE_LENGTH)
+            }
+            DryRunError::AirRejected(reason) => write!(f, "AIR rejected the instance: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DryRunError {}
+
+/// Checks `trace` against AIR `A` for `pub_inputs` without generating a proof
+pub fn dry_run<A: Air<BaseField = BaseElement>>(
+    trace: &TraceTable<BaseElement>,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+) -> Result<DryRunReport, DryRunError> {
+    check::<A>(trace, pub_inputs, options, |_| Vec::new())
+}
+
+/// [`dry_run`] naming every constraint by its label
+pub fn labeled_dry_run<A: Air<BaseField = BaseElement> + ConstraintLabels>(
+    trace: &TraceTable<BaseElement>,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+) -> Result<DryRunReport, DryRunError> {
+    check::<A>(trace, pub_inputs, options, A::constraint_labels)
+}
+
+/// Builds AIR `A` for the shape of `trace`, reporting a shape or `Air::new` rejects as an error
+pub fn instantiate<A: Air<BaseField = BaseElement>>(
+    trace: &TraceTable<BaseElement>,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+) -> Result<A, DryRunError> {
+    let length = trace.length();
+    if !length.is_power_of_two() || length < TraceInfo::MIN_TRACE_LENGTH {
+        return Err(DryRunError::InvalidLength(length));
+    }
+    if !(1..=TraceInfo::MAX_TRACE_WIDTH).contains(&trace.width()) {
+        return Err(DryRunError::AirRejected(format!("trace width {} is not supported", trace.width())));
+    }
+    let trace_info = TraceInfo::new(trace.width(), length);
+    panic::catch_unwind(AssertUnwindSafe(|| A::new(trace_info, pub_inputs, options))).map_err(|payload| {
+        let reason = payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|reason| reason.to_string()))
+            .unwrap_or_else(|| "Air::new panicked".into());
+        DryRunError::AirRejected(reason)
+    })
+}
+
+fn check<A: Air<BaseField = BaseElement>>(
+    trace: &TraceTable<BaseElement>,
+    pub_inputs: A::PublicInputs,
+    options: ProofOptions,
+    labels: impl FnOnce(&A) -> Vec<String>,
+) -> Result<DryRunReport, DryRunError> {
+    let started = Instant::now();
+    let length = trace.length();
+    let public_inputs = pub_inputs.to_elements();
+    let air = instantiate::<A>(trace, pub_inputs, options)?;
+
+    let assertions = air.get_assertions();
+    let mut assertion_failures = Vec::new();
+    for assertion in &assertions {
+        if assertion.validate_trace_width(trace.width()).is_err() || assertion.validate_trace_length(length).is_err() {
+            return Err(DryRunError::AirRejected(format!("assertion {assertion} does not fit the trace")));
+        }
+        assertion.apply(length, |step, expected| {
+            let found = trace.get(assertion.column(), step);
+            if found != expected {
+                assertion_failures.push(AssertionFailure { column: assertion.column(), step, expected, found });
+            }
+        });
+    }
+
+    let num_constraints = air.context().num_main_transition_constraints();
+    let constraint_labels = labels(&air);
+    assert!(
+        constraint_labels.is_empty() || constraint_labels.len() == num_constraints,
+        "{} labels for {num_constraints} transition constraints",
+        constraint_labels.len()
+    );
+    let mut transition_failures: Vec<TransitionFailure> = Vec::new();
+    for (row, evaluations) in evaluate_transitions(&air, trace).into_iter().enumerate() {
+        for (constraint, value) in evaluations.into_iter().enumerate() {
+            if value == BaseElement::ZERO {
+                continue;
+            }
+            match transition_failures.iter_mut().find(|failure| failure.constraint == constraint) {
+                Some(failure) => failure.num_rows += 1,
+                None => transition_failures.push(TransitionFailure { constraint, first_row: row, value, num_rows: 1 }),
+            }
+        }
+    }
+    transition_failures.sort_by_key(|failure| failure.constraint);
+    let periodic_columns = air.get_periodic_column_values();
+    let counterexamples = transition_failures
+        .iter()
+        .map(|failure| {
+            let row = |row: usize| (0..trace.width()).map(|column| trace.get(column, row)).collect();
+            Counterexample {
+                air: any::type_name::<A>(),
+                constraint: failure.constraint,
+                label: constraint_labels.get(failure.constraint).cloned(),
+                row: failure.first_row,
+                trace_length: length,
+                current: row(failure.first_row),
+                next: row(failure.first_row + 1),
+                periodic: periodic_columns.iter().map(|column| column[failure.first_row % column.len()]).collect(),
+                public_inputs: public_inputs.clone(),
+                value: failure.value,
+            }
+        })
+        .collect();
+
+    Ok(DryRunReport {
+        trace_width: trace.width(),
+        trace_length: length,
+        num_assertions: assertions.len(),
+        num_constraints,
+        assertion_failures,
+        transition_failures,
+        degree_mismatches: degree_mismatches(&air, trace),
+        counterexamples,
+        constraint_labels,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// Compares the degree of every transition constraint polynomial with its declared degree
+fn degree_mismatches<A: Air<BaseField = BaseElement>>(air: &A, trace: &TraceTable<BaseElement>) -> Vec<DegreeMismatch> {
+    let length = trace.length();
+    let declared: Vec<usize> = air
+        .get_transition_constraints(&vec![BaseElement::ZERO; air.context().num_transition_constraints()])
+        .main_constraint_degrees()
+        .iter()
+        .map(|degree| degree.get_evaluation_degree(length))
+        .collect();
+    let domain_size = (declared.iter().copied().max().unwrap_or(0) + 1).next_power_of_two().max(length);
+    let blowup = domain_size / length;
+
+    // every column, interpolated over the trace domain and evaluated over the larger one
+    let inv_twiddles = fft::get_inv_twiddles::<BaseElement>(length);
+    let twiddles = fft::get_twiddles::<BaseElement>(domain_size);
+    let columns: Vec<Vec<BaseElement>> = (0..trace.width())
+        .map(|column| {
+            let mut values: Vec<BaseElement> = (0..length).map(|row| trace.get(column, row)).collect();
+            fft::interpolate_poly(&mut values, &inv_twiddles);
+            values.resize(domain_size, BaseElement::ZERO);
+            fft::evaluate_poly(&mut values, &twiddles);
+            values
+        })
+        .collect();
+
+    let root = BaseElement::get_root_of_unity(domain_size.ilog2());
+    let periodic_polys = air.get_periodic_column_polys();
+    let mut evaluations = vec![vec![BaseElement::ZERO; domain_size]; declared.len()];
+    let mut x = BaseElement::ONE;
+    let mut result = vec![BaseElement::ZERO; declared.len()];
+    for i in 0..domain_size {
+        let row = |offset: usize| columns.iter().map(|column| column[(i + offset) % domain_size]).collect();
+        let frame = EvaluationFrame::from_rows(row(0), row(blowup));
+        let periodic: Vec<BaseElement> = periodic_polys
+            .iter()
+            .map(|poly| polynom::eval(poly, x.exp((length / poly.len()) as u128)))
+            .collect();
+        air.evaluate_transition(&frame, &periodic, &mut result);
+        for (column, &value) in evaluations.iter_mut().zip(&result) {
+            column[i] = value;
+        }
+        x *= root;
+    }
+
+    evaluations
+        .iter()
+        .zip(&declared)
+        .enumerate()
+        .filter_map(|(constraint, (values, &declared))| {
+            let divisor_degree = length - 1;
+            let (declared, actual) = (declared - divisor_degree, fft::infer_degree(values, BaseElement::ONE));
+            let actual = actual.saturating_sub(divisor_degree);
+            (actual != declared).then_some(DegreeMismatch { constraint, declared, actual })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::prover::AirProver;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    /// Public inputs expression of the generated unit tests
+    const INPUTS: &str =
+        "linear_regression::regression::LinearRegressionInputs::from_elements(&elements(&PUBLIC_INPUTS)).unwrap()";
+
+    fn inputs(predicted_y: u64) -> LinearRegressionInputs {
+        LinearRegressionInputs {
+            x_value: BaseElement::new(8),
+            predicted_y: BaseElement::from(predicted_y),
+            sample_x_values: vec![BaseElement::new(1), BaseElement::new(3)],
+            sample_y_values: vec![BaseElement::new(7), BaseElement::new(11)],
+        }
+    }
+
+    #[test]
+    fn test_dry_run_reports_failures() {
+        let (slope, intercept) = (BaseElement::new(2), BaseElement::new(5));
+        let xs = [BaseElement::new(1), BaseElement::new(3)];
+        let ys = [BaseElement::new(7), BaseElement::new(11)];
+        let trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(8),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        let options = ProverConfig::default().to_proof_options();
+
+        let report = AirProver::<LinearRegressionAir>::new(options.clone(), inputs(21)).dry_run(&trace).unwrap();
+        assert!(report.is_ok(), "{report}");
+        assert_eq!(report.trace_length, 8);
+        assert!(report.num_assertions > 0);
+
+        let wrong = dry_run::<LinearRegressionAir>(&trace, inputs(22), options.clone()).unwrap();
+        assert!(!wrong.is_ok());
+        assert!(wrong.assertion_failures.iter().any(|failure| failure.found == BaseElement::new(21)));
+
+        let mut broken = trace.clone();
+        broken.set(3, 1, BaseElement::new(12));
+        let report = dry_run::<LinearRegressionAir>(&broken, inputs(21), options.clone()).unwrap();
+        assert!(!report.transition_failures.is_empty());
+        assert!(report.transition_failures.iter().all(|failure| failure.first_row <= 1));
+        assert!(report.to_string().contains("constraint 0 fails"), "{report}");
+        let labeled = labeled_dry_run::<LinearRegressionAir>(&broken, inputs(21), options.clone()).unwrap();
+        assert_eq!(labeled.label(1), Some("slope_constant"));
+        assert!(labeled.to_string().contains("constraint 0 (linear_relation) fails"), "{labeled}");
+
+        // a trace without the exempt-row filler collapses constraint degrees
+        let mut flat = trace.clone();
+        for column in 0..flat.width() {
+            flat.set(column, 7, flat.get(column, 6));
+        }
+        let report = dry_run::<LinearRegressionAir>(&flat, inputs(21), options).unwrap();
+        assert!(!report.degree_mismatches.is_empty(), "{report}");
+    }
+
+    #[test]
+    fn test_counterexample_reproduces_failure() {
+        let (slope, intercept) = (BaseElement::new(2), BaseElement::new(5));
+        let xs = [BaseElement::new(1), BaseElement::new(3)];
+        let ys = [BaseElement::new(7), BaseElement::new(11)];
+        let mut trace = build_linear_regression_trace(
+            slope,
+            intercept,
+            &xs,
+            &ys,
+            BaseElement::new(8),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        trace.set(3, 1, BaseElement::new(12));
+        let options = ProverConfig::default().to_proof_options();
+        let report = labeled_dry_run::<LinearRegressionAir>(&trace, inputs(21), options.clone()).unwrap();
+        assert_eq!(report.counterexamples.len(), report.transition_failures.len());
+
+        let example = &report.counterexamples[0];
+        assert_eq!(example.air, "linear_regression::regression::LinearRegressionAir");
+        assert_eq!(example.label.as_deref(), Some("linear_relation"));
+        assert_eq!(example.current[3], trace.get(3, example.row));
+        assert_eq!(example.public_inputs, inputs(21).to_elements());
+        let air = LinearRegressionAir::new(TraceInfo::new(trace.width(), 8), inputs(21), options);
+        let frame = EvaluationFrame::from_rows(example.current.clone(), example.next.clone());
+        let mut result = vec![BaseElement::ZERO; report.num_constraints];
+        air.evaluate_transition(&frame, &example.periodic, &mut result);
+        assert_eq!(result[example.constraint], example.value);
+
+        let test = example.to_unit_test(INPUTS);
+        assert!(test.contains(&format!("fn constraint_{}_holds_on_row_{}()", example.constraint, example.row)));
+        assert!(test.contains("Constraint 0 (`linear_relation`) of"));
+        let name = format!("linear_regression_air_constraint_{}_row_{}.rs", example.constraint, example.row);
+        assert_eq!(example.file_name(), name);
+        // tests/counterexample.rs compiles and runs the generated test
+    }
+
+    #[test]
+    fn test_dry_run_rejects_invalid_instances() {
+        let trace = TraceTable::new(3, 8);
+        let options = ProverConfig::default().to_proof_options();
+        let result = dry_run::<LinearRegressionAir>(&trace, inputs(21), options);
+        assert!(matches!(result, Err(DryRunError::AirRejected(_))));
+    }
+}
@@ -0,0 +1,363 @@
+//! Importing scikit-learn linear models into claims.
+//!
+//! Translating a fitted model into fixed-point witnesses by hand is where claims go wrong: a
+//! coefficient quantized at the wrong scale, an intercept missing its second shift, a scaler
+//! forgotten. [`import_sklearn`] reads the JSON export of a `LinearRegression` or binary
+//! `LogisticRegression`, optionally with the `StandardScaler` in front of it, quantizes it once
+//! and builds the witness and public inputs of the claims directly. The export is plain JSON
+//! written from the fitted estimators, no pickle is read:
+//!
+//! ```text
+//! json.dump({
+//!     "estimator": type(model).__name__,
+//!     "coef": model.coef_.tolist(),
+//!     "intercept": model.intercept_.tolist(),
+//!     "feature_names": list(model.feature_names_in_),         # optional
+//!     "scaler": {"mean": scaler.mean_.tolist(), "scale": scaler.scale_.tolist()},  # optional
+//! }, file)
+//! ```
+//!
+//! Coefficients and the intercept are quantized with `frac_bits` fractional bits. Regression
+//! AIRs multiply fixed-point coefficients by fixed-point features, so their witnesses carry the
+//! intercept with `2 * frac_bits` fractional bits; [`ImportedModel::parameters`] keeps it at the
+//! coefficients' scale for claims about the parameters themselves.
+
+use core::fmt;
+
+use serde::Deserialize;
+use winterfell::{math::fields::f128::BaseElement, TraceTable};
+
+use crate::abi::to_hex;
+use crate::fixed_point::{quantize, to_field};
+use crate::sensitivity::parameters_commitment;
+use crate::session::LinearClaimRequest;
+use crate::standardize::{
+    build_standardized_trace, predict_standardized, StandardizationParams, StandardizedRegressionInputs,
+};
+
+/// Fitted estimators the importer understands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Estimator {
+    LinearRegression,
+    /// Binary classifier; its parameters are those of the decision function
+    LogisticRegression,
+}
+
+/// `coef_` is one-dimensional for regressions and has one row per class for classifiers
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Coefficients {
+    Flat(Vec<f64>),
+    Rows(Vec<Vec<f64>>),
+}
+
+/// `intercept_` is a scalar for regressions and has one entry per class for classifiers
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Intercept {
+    Scalar(f64),
+    PerClass(Vec<f64>),
+}
+
+#[derive(Deserialize)]
+struct ScalerExport {
+    mean: Vec<f64>,
+    scale: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+struct SklearnExport {
+    estimator: String,
+    coef: Coefficients,
+    intercept: Intercept,
+    #[serde(default)]
+    feature_names: Vec<String>,
+    #[serde(default)]
+    scaler: Option<ScalerExport>,
+}
+
+/// Reason an export cannot be imported or turned into a claim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError {
+    /// The export is not valid JSON of the documented shape
+    Malformed(String),
+    UnsupportedEstimator(String),
+    /// Only binary classifiers reduce to a single linear decision function
+    MultiClass { classes: usize },
+    /// The scaler or the feature names cover a different number of features than the model
+    FeatureCount { expected: usize, found: usize },
+    /// The named value does not fit a quantized 64-bit integer
+    OutOfRange(String),
+    /// The scaler divides a feature by a scale that quantizes to zero or less
+    InvalidScale { feature: usize },
+    /// The claim only applies to a different kind of model, e.g. a regression without scaler
+    UnsupportedClaim(&'static str),
+    /// The linear claim encodes parameters and features as non-negative integers
+    Negative(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Malformed(reason) => write!(f, "malformed scikit-learn export: {reason}"),
+            ImportError::UnsupportedEstimator(name) => write!(f, "estimator {name} is not supported"),
+            ImportError::MultiClass { classes } => write!(f, "classifier has {classes} classes, only 2 are supported"),
+            ImportError::FeatureCount { expected, found } => {
+                write!(f, "model has {expected} features, the export describes {found}")
+            }
+            ImportError::OutOfRange(name) => write!(f, "{name} does not fit a quantized 64-bit integer"),
+            ImportError::InvalidScale { feature } => write!(f, "scale of feature {feature} is not positive"),
+            ImportError::UnsupportedClaim(reason) => write!(f, "{reason}"),
+            ImportError::Negative(name) => write!(f, "{name} is negative and the linear claim takes no negatives"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// A fitted model quantized for claims
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportedModel {
+    pub estimator: Estimator,
+    /// Names of the features, empty when the export has none
+    pub feature_names: Vec<String>,
+    pub frac_bits: u32,
+    pub coefficients: Vec<i64>,
+    pub intercept: i64,
+    /// Per-feature standardization applied before the model, if any
+    pub scaler: Option<Vec<StandardizationParams>>,
+}
+
+fn quantize_checked(value: f64, frac_bits: u32, name: impl Into<String>) -> Result<i64, ImportError> {
+    if !value.is_finite() {
+        return Err(ImportError::OutOfRange(name.into()));
+    }
+    i64::try_from(quantize(value, frac_bits)).map_err(|_| ImportError::OutOfRange(name.into()))
+}
+
+/// Reads a scikit-learn export and quantizes it with `frac_bits` fractional bits
+pub fn import_sklearn(json: &str, frac_bits: u32) -> Result<ImportedModel, ImportError> {
+    let export: SklearnExport = serde_json::from_str(json).map_err(|err| ImportError::Malformed(err.to_string()))?;
+    let estimator = match export.estimator.as_str() {
+        "LinearRegression" => Estimator::LinearRegression,
+        "LogisticRegression" => Estimator::LogisticRegression,
+        other => return Err(ImportError::UnsupportedEstimator(other.into())),
+    };
+    let coef = match export.coef {
+        Coefficients::Flat(coef) => coef,
+        Coefficients::Rows(mut rows) if rows.len() == 1 => rows.remove(0),
+        Coefficients::Rows(rows) => return Err(ImportError::MultiClass { classes: rows.len() }),
+    };
+    let intercept = match export.intercept {
+        Intercept::Scalar(intercept) => intercept,
+        Intercept::PerClass(intercepts) if intercepts.len() == 1 => intercepts[0],
+        Intercept::PerClass(intercepts) => return Err(ImportError::MultiClass { classes: intercepts.len() }),
+    };
+    let num_features = coef.len();
+    if !export.feature_names.is_empty() && export.feature_names.len() != num_features {
+        return Err(ImportError::FeatureCount { expected: num_features, found: export.feature_names.len() });
+    }
+
+    let coefficients = coef
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| quantize_checked(value, frac_bits, format!("coefficient {i}")))
+        .collect::<Result<_, _>>()?;
+    let scaler = export
+        .scaler
+        .map(|scaler| {
+            if scaler.mean.len() != num_features || scaler.scale.len() != num_features {
+                let found = if scaler.mean.len() != num_features { scaler.mean.len() } else { scaler.scale.len() };
+                return Err(ImportError::FeatureCount { expected: num_features, found });
+            }
+            (0..num_features)
+                .map(|i| {
+                    let mean = quantize_checked(scaler.mean[i], frac_bits, format!("mean of feature {i}"))?;
+                    let std_dev = quantize_checked(scaler.scale[i], frac_bits, format!("scale of feature {i}"))?;
+                    if std_dev <= 0 {
+                        return Err(ImportError::InvalidScale { feature: i });
+                    }
+                    Ok(StandardizationParams::new(mean as i128, std_dev as i128, frac_bits))
+                })
+                .collect()
+        })
+        .transpose()?;
+
+    Ok(ImportedModel {
+        estimator,
+        feature_names: export.feature_names,
+        frac_bits,
+        coefficients,
+        intercept: quantize_checked(intercept, frac_bits, "intercept")?,
+        scaler,
+    })
+}
+
+impl ImportedModel {
+    /// The intercept followed by the coefficients, in the order of sensitivity and importance
+    /// claims
+    pub fn parameters(&self) -> Vec<i64> {
+        [self.intercept].into_iter().chain(self.coefficients.iter().copied()).collect()
+    }
+
+    /// Commitment to [`Self::parameters`] that sensitivity and importance claims are made against
+    pub fn commitment(&self, salt: BaseElement) -> BaseElement {
+        parameters_commitment(&self.parameters(), salt)
+    }
+
+    /// Slope and intercept of a one-feature regression, the intercept widened to the scale of
+    /// `slope * x`
+    fn single_feature(&self) -> Result<(i128, i128), ImportError> {
+        if self.estimator != Estimator::LinearRegression {
+            return Err(ImportError::UnsupportedClaim("regression claims need a LinearRegression"));
+        }
+        if self.coefficients.len() != 1 {
+            return Err(ImportError::FeatureCount { expected: 1, found: self.coefficients.len() });
+        }
+        Ok((self.coefficients[0] as i128, (self.intercept as i128) << self.frac_bits))
+    }
+
+    /// Request proving the model's predictions at `sample_x` and `x` for a model registered as
+    /// `model` with `salt`; the model takes raw features and has non-negative parameters
+    pub fn linear_claim(
+        &self,
+        model: &str,
+        salt: &[u8; 32],
+        sample_x: &[f64],
+        x: f64,
+    ) -> Result<LinearClaimRequest, ImportError> {
+        if self.scaler.is_some() {
+            return Err(ImportError::UnsupportedClaim("standardized models are proven with standardized_claim"));
+        }
+        let (slope, intercept) = self.single_feature()?;
+        let unsigned = |raw: i128, name: &str| u64::try_from(raw).map_err(|_| ImportError::Negative(name.into()));
+        let quantized = |value: f64, name: String| {
+            quantize_checked(value, self.frac_bits, name.clone()).and_then(|raw| unsigned(raw as i128, &name))
+        };
+        Ok(LinearClaimRequest {
+            model: model.into(),
+            salt: to_hex(salt),
+            slope: unsigned(slope, "the slope")?,
+            intercept: unsigned(intercept, "the intercept")?,
+            sample_x: sample_x
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| quantized(value, format!("sample {i}")))
+                .collect::<Result<_, _>>()?,
+            x: quantized(x, "x".into())?,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        })
+    }
+
+    /// Trace of at least `min_trace_length` rows and public inputs proving the predictions of a
+    /// one-feature model behind a `StandardScaler` at the raw features `sample_x` and `x`
+    pub fn standardized_claim(
+        &self,
+        sample_x: &[f64],
+        x: f64,
+        min_trace_length: usize,
+    ) -> Result<(TraceTable<BaseElement>, StandardizedRegressionInputs), ImportError> {
+        let params = match self.scaler.as_deref() {
+            Some([params]) => *params,
+            _ => return Err(ImportError::UnsupportedClaim("standardized claims need a one-feature scaler")),
+        };
+        let (slope, intercept) = self.single_feature()?;
+        let (slope, intercept) = (to_field(slope), to_field(intercept));
+        let raw = |value: f64, name: String| quantize_checked(value, self.frac_bits, name).map(i128::from);
+        let sample_x: Vec<i128> =
+            sample_x.iter().enumerate().map(|(i, &value)| raw(value, format!("sample {i}"))).collect::<Result<_, _>>()?;
+        let x = raw(x, "x".into())?;
+
+        let trace = build_standardized_trace(slope, intercept, &params, &sample_x, x, min_trace_length);
+        let predict = |value| predict_standardized(slope, intercept, &params, value);
+        let inputs = StandardizedRegressionInputs {
+            params,
+            x_value: to_field(x),
+            predicted_y: predict(x),
+            sample_x_values: sample_x.iter().map(|&value| to_field(value)).collect(),
+            sample_y_values: sample_x.iter().map(|&value| predict(value)).collect(),
+        };
+        Ok((trace, inputs))
+    }
+
+    /// Whether the model is a classifier, whose claims are about its decision function
+    pub fn is_classifier(&self) -> bool {
+        self.estimator == Estimator::LogisticRegression
+    }
+
+    /// Predicted value of a regression, or the decision function of a classifier, on the
+    /// quantized raw `features`, with `2 * frac_bits` fractional bits
+    pub fn decision(&self, features: &[i128]) -> BaseElement {
+        assert_eq!(features.len(), self.coefficients.len(), "One value per feature");
+        let intercept = to_field((self.intercept as i128) << self.frac_bits);
+        self.coefficients.iter().zip(features).enumerate().fold(intercept, |sum, (i, (&coef, &x))| {
+            let x = match &self.scaler {
+                Some(scaler) => scaler[i].standardize(x).0,
+                None => x,
+            };
+            sum + to_field(coef as i128) * to_field(x)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::DEFAULT_FRACTIONAL_BITS;
+    use crate::standardize::StandardizedRegressionAir;
+    use winterfell::{math::FieldElement, TraceInfo};
+
+    #[test]
+    fn test_imports_linear_regressions() {
+        let frac_bits = DEFAULT_FRACTIONAL_BITS;
+        let json = r#"{"estimator": "LinearRegression", "coef": [1.5], "intercept": 0.25, "feature_names": ["area"]}"#;
+        let model = import_sklearn(json, frac_bits).unwrap();
+        assert_eq!(model.parameters(), vec![1 << 14, 3 << 15]);
+        assert_eq!(model.feature_names, vec!["area".to_string()]);
+        let request = model.linear_claim("houses", &[7; 32], &[1.0, 2.0], 4.0).unwrap();
+        assert_eq!((request.slope, request.intercept), (3 << 15, 1 << 30));
+        assert_eq!((request.sample_x, request.x), (vec![1 << 16, 2 << 16], 4 << 16));
+        let y = BaseElement::from(request.slope) * BaseElement::from(request.x) + BaseElement::from(request.intercept);
+        assert_eq!(model.decision(&[4 << 16]), y);
+
+        let scaled = r#"{"estimator": "LinearRegression", "coef": [1.5], "intercept": -0.25,
+            "scaler": {"mean": [4.0], "scale": [2.5]}}"#;
+        let model = import_sklearn(scaled, frac_bits).unwrap();
+        assert!(model.linear_claim("houses", &[7; 32], &[1.0], 4.0).is_err());
+        let (trace, inputs) = model.standardized_claim(
+            &[1.0, 2.5, 4.75, 9.0],
+            6.2,
+            TraceInfo::MIN_TRACE_LENGTH,
+        )
+        .unwrap();
+        assert_eq!(inputs.predicted_y, model.decision(&[quantize(6.2, frac_bits)]));
+        crate::air_test!(StandardizedRegressionAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold();
+    }
+
+    #[test]
+    fn test_imports_binary_classifiers() {
+        let json = r#"{"estimator": "LogisticRegression", "coef": [[0.5, -2.0]], "intercept": [-1.0]}"#;
+        let model = import_sklearn(json, 8).unwrap();
+        assert!(model.is_classifier());
+        assert_eq!(model.parameters(), vec![-256, 128, -512]);
+        assert_eq!(model.commitment(BaseElement::ONE), parameters_commitment(&[-256, 128, -512], BaseElement::ONE));
+        assert!(matches!(model.linear_claim("m", &[0; 32], &[], 0.0), Err(ImportError::UnsupportedClaim(_))));
+
+        let three_classes = r#"{"estimator": "LogisticRegression", "coef": [[1], [2], [3]], "intercept": [0, 0, 0]}"#;
+        assert_eq!(import_sklearn(three_classes, 8), Err(ImportError::MultiClass { classes: 3 }));
+        let scaler = r#"{"estimator": "LinearRegression", "coef": [1, 2], "intercept": 0,
+            "scaler": {"mean": [0], "scale": [1]}}"#;
+        assert_eq!(import_sklearn(scaler, 8), Err(ImportError::FeatureCount { expected: 2, found: 1 }));
+        let tree = r#"{"estimator": "DecisionTreeRegressor", "coef": [], "intercept": 0}"#;
+        assert_eq!(import_sklearn(tree, 8), Err(ImportError::UnsupportedEstimator("DecisionTreeRegressor".into())));
+        assert_eq!(
+            import_sklearn(r#"{"estimator": "LinearRegression", "coef": [1e30], "intercept": 0}"#, 8),
+            Err(ImportError::OutOfRange("coefficient 0".into()))
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Moving-average smoothing claims over a committed, private raw series.
+//!
+//! [`SmoothingAir`] proves that a published series is the `k`-period moving average of the
+//! raw series hashed into a public [`series_commitment`]: every published value is
+//! `floor((x[t - k + 1] + ... + x[t]) / k)` for `t` from `k - 1` to the end of the series. The
+//! smoothed series is public, so it can feed the regression claims as their samples, with this
+//! proof attesting the preprocessing stage in front of them.
+//!
+//! The raw series is absorbed one value per [`CYCLE_LENGTH`]-row cycle, as in
+//! [`DataValidityAir`](crate::validity::DataValidityAir). Next to the sponge the trace keeps the
+//! last `k` values in lag columns and a sliding-window accumulator: at the end of each cycle the
+//! lags shift by one and the window adds the cycle's value and drops the one `k` cycles back,
+//! so cycle `c` holds the sum of the `k` values before it. The window is divided by `k` on every
+//! row with a range-checked remainder, and the quotient of cycle `t + 1` is asserted to be the
+//! published value for `t`.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::batch::field_column;
+use crate::fixed_point::{div_floor, to_field};
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::RangeCheck;
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`SmoothingAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "moving-average/v1";
+
+/// Longest supported window; every lag takes a trace column
+pub const MAX_WINDOW: usize = 64;
+
+/// Sponge tag of [`series_commitment`]; tags 1 to 17 are used elsewhere
+const SERIES_TAG: u64 = 18;
+
+// Trace layout: sponge state, the value absorbed at the end of the cycle, the window sum of
+// the `k` values before it and its quotient by `k`, then the lags and the remainder digits
+const SPONGE: usize = 0;
+const VALUE: usize = SPONGE + STATE_WIDTH;
+const WINDOW: usize = VALUE + 1;
+const MEAN: usize = WINDOW + 1;
+const LAGS_START: usize = MEAN + 1;
+
+struct Layout {
+    window: usize,
+    sponge: RescueGadget,
+    remainder: RangeCheck,
+    slack: RangeCheck,
+}
+
+impl Layout {
+    fn new(window: usize) -> Self {
+        let remainder = RangeCheck::for_bound(LAGS_START + window, window as u128);
+        let slack = RangeCheck::new(remainder.next_column(), remainder.num_bits());
+        Self { window, sponge: RescueGadget::new(SPONGE), remainder, slack }
+    }
+
+    /// Column of the value `1 + lag` cycles back
+    fn lag(&self, lag: usize) -> usize {
+        LAGS_START + lag
+    }
+
+    fn trace_width(&self) -> usize {
+        self.slack.next_column()
+    }
+}
+
+/// Number of trace columns for a `window`-period average
+pub fn trace_width(window: usize) -> usize {
+    Layout::new(window).trace_width()
+}
+
+/// Named columns of the trace for a `window`-period average, see [`crate::layout`]
+pub fn layout(window: usize) -> LayoutDescriptor {
+    let layout = Layout::new(window);
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..VALUE)
+        .with_column("value", VALUE)
+        .with_column("window", WINDOW)
+        .with_column("mean", MEAN)
+        .with_columns("lags", layout.lag(0)..layout.lag(window))
+        .with_columns("remainder_digits", layout.lag(window)..layout.remainder.next_column())
+        .with_columns("slack_digits", layout.remainder.next_column()..layout.slack.next_column())
+}
+
+/// Trace length for `num_samples` raw values averaged over `window` periods: a cycle per value,
+/// the cycle reading the last average and `window` padding cycles that flush the lags
+pub fn trace_length(num_samples: usize, window: usize) -> usize {
+    ((num_samples + window + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+fn sponge_start(num_samples: usize, salt: BaseElement) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_samples, SERIES_TAG);
+    state[0] = salt;
+    state
+}
+
+/// Commitment to the raw `series`, blinded by `salt`
+pub fn series_commitment(series: &[i64], salt: BaseElement) -> BaseElement {
+    let mut state = sponge_start(series.len(), salt);
+    rescue::permute(&mut state);
+    for &value in series {
+        state[0] += to_field(value as i128);
+        rescue::permute(&mut state);
+    }
+    state[0]
+}
+
+/// The `window`-period moving average of `series`, rounded down, one value per full window
+pub fn moving_average(series: &[i64], window: usize) -> Vec<i64> {
+    series
+        .windows(window)
+        .map(|values| {
+            let sum: i128 = values.iter().map(|&value| value as i128).sum();
+            div_floor(sum, window as i128).0 as i64
+        })
+        .collect()
+}
+
+/// Reason a series cannot be proven smoothed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SmoothingError {
+    /// The window is not in `1..=MAX_WINDOW`
+    WindowSize(usize),
+    /// The series is shorter than one window
+    TooShort { samples: usize, window: usize },
+}
+
+impl fmt::Display for SmoothingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmoothingError::WindowSize(window) => write!(f, "window of {window} periods is not in 1..={MAX_WINDOW}"),
+            SmoothingError::TooShort { samples, window } => {
+                write!(f, "{samples} samples do not fill a window of {window} periods")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SmoothingError {}
+
+/// Public inputs: the raw series commitment, the window and the smoothed series
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmoothingInputs {
+    pub commitment: BaseElement,
+    pub window: usize,
+    pub smoothed: Vec<i64>,
+}
+
+impl SmoothingInputs {
+    /// Length of the raw series
+    pub fn num_samples(&self) -> usize {
+        self.smoothed.len() + self.window - 1
+    }
+}
+
+impl ToElements<BaseElement> for SmoothingInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![self.commitment, BaseElement::from(self.window as u64)];
+        elements.extend(field_column(&self.smoothed));
+        elements
+    }
+}
+
+/// AIR proving that a public series is the moving average of a committed raw series
+pub struct SmoothingAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: SmoothingInputs,
+}
+
+impl Air for SmoothingAir {
+    type BaseField = BaseElement;
+    type PublicInputs = SmoothingInputs;
+
+    fn new(trace_info: TraceInfo, inputs: SmoothingInputs, options: ProofOptions) -> Self {
+        assert!((1..=MAX_WINDOW).contains(&inputs.window), "Window is in 1..=MAX_WINDOW");
+        assert!(!inputs.smoothed.is_empty(), "At least one window is averaged");
+        let layout = Layout::new(inputs.window);
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(trace_length(inputs.num_samples(), inputs.window), trace_info.length());
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the value at the end of a cycle (degree 1)
+        // 3. Value, window, quotient and lags are constant within a cycle (degree 1)
+        // 4. At the end of a cycle the lags shift by one and the window slides (degree 1)
+        // 5. window = k * quotient + remainder and k - 1 - remainder = slack (degree 1)
+        // 6. Digits of the remainder and the slack (degree 2)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH]);
+        degrees.extend(vec![periodic(1); 3 + inputs.window]);
+        degrees.extend(vec![periodic(1); inputs.window + 1]);
+        degrees.extend(vec![TransitionConstraintDegree::new(1); 2]);
+        degrees.extend(layout.remainder.constraint_degrees());
+        degrees.extend(layout.slack.constraint_degrees());
+
+        let num_assertions = STATE_WIDTH + inputs.window + 1 + inputs.smoothed.len();
+        SmoothingAir { context: AirContext::new(trace_info, degrees, num_assertions, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let k = layout.window;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        let (absorb, rest) = rest.split_at_mut(STATE_WIDTH);
+        absorb[0] = last * (next[SPONGE] - current[SPONGE] - current[VALUE]);
+        for lane in 1..STATE_WIDTH {
+            absorb[lane] = last * (next[SPONGE + lane] - current[SPONGE + lane]);
+        }
+
+        let (constant, rest) = rest.split_at_mut(3 + k);
+        let columns = [VALUE, WINDOW, MEAN].into_iter().chain((0..k).map(|lag| layout.lag(lag)));
+        for (r, column) in constant.iter_mut().zip(columns) {
+            *r = mask * (next[column] - current[column]);
+        }
+
+        let (slide, rest) = rest.split_at_mut(k + 1);
+        slide[0] = last * (next[layout.lag(0)] - current[VALUE]);
+        for lag in 1..k {
+            slide[lag] = last * (next[layout.lag(lag)] - current[layout.lag(lag - 1)]);
+        }
+        slide[k] = last * (next[WINDOW] - current[WINDOW] - current[VALUE] + current[layout.lag(k - 1)]);
+
+        let window = E::from(k as u32);
+        let remainder = layout.remainder.recompose(current);
+        rest[0] = current[WINDOW] - window * current[MEAN] - remainder;
+        rest[1] = window - E::ONE - remainder - layout.slack.recompose(current);
+
+        let rest = &mut rest[2..];
+        layout.remainder.evaluate(current, rest);
+        layout.slack.evaluate(current, &mut rest[layout.remainder.num_constraints()..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_samples = self.inputs.num_samples();
+        // the salt in the first lane stays private
+        let start = sponge_start(num_samples, BaseElement::ZERO);
+        let mut assertions: Vec<_> = (1..STATE_WIDTH).map(|i| Assertion::single(SPONGE + i, 0, start[i])).collect();
+        // the series starts with empty lags and an empty window
+        let lags = (0..self.inputs.window).map(|lag| self.layout.lag(lag));
+        assertions.extend(lags.map(|column| Assertion::single(column, 0, BaseElement::ZERO)));
+        assertions.push(Assertion::single(WINDOW, 0, BaseElement::ZERO));
+        assertions.push(Assertion::single(SPONGE, (num_samples + 1) * CYCLE_LENGTH - 1, self.inputs.commitment));
+        // the average of the window ending at value `t` is read in cycle `t + 1`
+        for (i, &value) in self.inputs.smoothed.iter().enumerate() {
+            let cycle = i + self.inputs.window;
+            assertions.push(Assertion::single(MEAN, cycle * CYCLE_LENGTH, to_field(value as i128)));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving the `window`-period moving average of `series`, with the
+/// inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_smoothing_trace(
+    series: &[i64],
+    salt: BaseElement,
+    window: usize,
+) -> Result<(TraceTable<BaseElement>, SmoothingInputs), SmoothingError> {
+    if !(1..=MAX_WINDOW).contains(&window) {
+        return Err(SmoothingError::WindowSize(window));
+    }
+    if series.len() < window {
+        return Err(SmoothingError::TooShort { samples: series.len(), window });
+    }
+    let layout = Layout::new(window);
+
+    // padding cycles take fresh positive values above the series, so no column stays constant
+    let above = series.iter().copied().max().unwrap_or_default().max(0);
+    let values: Vec<i128> = (0..trace_length(series.len(), window) / CYCLE_LENGTH)
+        .map(|cycle| series.get(cycle).map_or(above as i128 + 1 + cycle as i128, |&value| value as i128))
+        .collect();
+
+    let length = trace_length(series.len(), window);
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut state = sponge_start(series.len(), salt);
+    let mut sum = 0i128;
+    for i in 0..length {
+        let (cycle, step) = (i / CYCLE_LENGTH, i % CYCLE_LENGTH);
+        let value = values[cycle];
+        let (mean, remainder) = div_floor(sum, window as i128);
+        row[VALUE] = to_field(value);
+        row[WINDOW] = to_field(sum);
+        row[MEAN] = to_field(mean);
+        for lag in 0..window {
+            let back = cycle.checked_sub(1 + lag).map_or(0, |c| values[c]);
+            row[layout.lag(lag)] = to_field(back);
+        }
+        layout.sponge.write(&mut row, &state);
+        if step < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, step);
+        } else {
+            state[0] += row[VALUE];
+            let dropped = cycle.checked_sub(window).map_or(0, |c| values[c]);
+            sum += value - dropped;
+        }
+        if i == length - 1 {
+            layout.remainder.fill_exempt_row(&mut row);
+            layout.slack.fill_exempt_row(&mut row);
+        } else {
+            layout.remainder.fill(&mut row, remainder).expect("Euclidean remainder is always within 0..window");
+            layout.slack.fill(&mut row, window as i128 - 1 - remainder).expect("Checked above");
+        }
+        trace.update_row(i, &row);
+    }
+
+    let inputs = SmoothingInputs {
+        commitment: series_commitment(series, salt),
+        window,
+        smoothed: moving_average(series, window),
+    };
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_smoothing_proof() {
+        let series = [12, 15, -3, 20, 20, 7, 0, 31];
+        let salt = BaseElement::new(0x5e7);
+        let (trace, inputs) = build_smoothing_trace(&series, salt, 3).unwrap();
+        assert_eq!(inputs.smoothed, vec![8, 10, 12, 15, 9, 12]);
+        crate::air_test!(SmoothingAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let options = ProverConfig::default().to_proof_options();
+        let proof = prove::<SmoothingAir>(trace, inputs.clone(), options).unwrap();
+        let acceptable = default_acceptable_options();
+        let mut rounded_up = inputs.clone();
+        rounded_up.smoothed[0] = 9;
+        assert!(verify::<SmoothingAir>(proof.clone(), rounded_up, &acceptable).is_err());
+        let other_series = SmoothingInputs { commitment: series_commitment(&series[1..], salt), ..inputs.clone() };
+        assert!(verify::<SmoothingAir>(proof.clone(), other_series, &acceptable).is_err());
+        let result = verify::<SmoothingAir>(proof, inputs, &acceptable);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_window_edges() {
+        let salt = BaseElement::ONE;
+        let (trace, inputs) = build_smoothing_trace(&[0, 0, 0, 0], salt, 4).unwrap();
+        assert_eq!(inputs.smoothed, vec![0]);
+        crate::air_test!(SmoothingAir, inputs).with_trace(&trace).expect_all_hold().expect_assertions_hold();
+        let (trace, inputs) = build_smoothing_trace(&[5, -5], salt, 1).unwrap();
+        assert_eq!(inputs.smoothed, vec![5, -5]);
+        crate::air_test!(SmoothingAir, inputs).with_trace(&trace).expect_all_hold().expect_assertions_hold();
+
+        let too_short = build_smoothing_trace(&[1, 2], salt, 3).unwrap_err();
+        assert_eq!(too_short, SmoothingError::TooShort { samples: 2, window: 3 });
+        assert_eq!(build_smoothing_trace(&[1], salt, 0).unwrap_err(), SmoothingError::WindowSize(0));
+    }
+}
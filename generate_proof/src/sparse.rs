@@ -0,0 +1,407 @@
+//! Predictions of a committed linear model over sparse feature vectors.
+//!
+//! A model of a few hundred features is often queried with most of them zero: one-hot
+//! groups, absent optional inputs, a product that only fills in a handful. A term whose
+//! feature is zero adds nothing to the prediction, yet a layout with one column group per
+//! feature would still allocate and constrain it. [`SparsePredictionAir`] is specialized to a
+//! [`FeatureLayout`] listing only the terms it opens: [`FeatureLayout::plan`] drops the zero
+//! features when the trace is planned, and the layout is part of [`SparseInputs`], so the
+//! verifier instantiates the same specialization from the claim and a proof of one layout
+//! does not verify under another.
+//!
+//! The model commits to each term separately: term `t` (the bias first, then one weight per
+//! feature) is committed as the first lane of a Rescue permutation of `[weight, salt_t,
+//! TERM_TAG, t]`, and [`SparseModel::commitment`] hashes all term commitments. The public
+//! inputs list every term commitment, so pruned terms stay bound to the model without
+//! entering the trace.
+//!
+//! The trace is a single [`CYCLE_LENGTH`]-row cycle with the prediction column followed by one
+//! sponge per opened term, which runs the permutation of that term's commitment. On the first
+//! row the prediction equals `sum(x_t * weight_t)` over the opened terms, with `x = 1` for the
+//! bias.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::batch::field_column;
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`SparsePredictionAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "sparse-prediction/v1";
+
+/// Sponge tag of term commitments; tags 1 to 19 are used elsewhere
+const TERM_TAG: u64 = 20;
+/// Sponge tag of [`SparseModel::commitment`] and of the term salts
+const MODEL_TAG: u64 = 21;
+
+// Trace layout: the prediction, then one sponge per opened term
+const PREDICTION: usize = 0;
+const SPONGES_START: usize = 1;
+
+/// Every proof is one cycle long
+pub const TRACE_LENGTH: usize = CYCLE_LENGTH;
+
+/// Named columns of the trace opening the terms of `features`, see [`crate::layout`]
+pub fn layout(features: &FeatureLayout) -> LayoutDescriptor {
+    let descriptor = LayoutDescriptor::new(AIR_ID).with_column("prediction", PREDICTION);
+    features.terms.iter().enumerate().fold(descriptor, |descriptor, (slot, term)| {
+        let sponge = features.sponge(slot);
+        let columns = sponge.next_column() - sponge.width()..sponge.next_column();
+        descriptor.with_columns(&format!("term_{term}_sponge"), columns)
+    })
+}
+
+/// Terms a trace opens: `0` for the bias and `j + 1` for feature `j`, in increasing order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeatureLayout {
+    terms: Vec<usize>,
+}
+
+impl FeatureLayout {
+    /// Layout opening every term of a model of `num_features` features
+    pub fn full(num_features: usize) -> Self {
+        Self { terms: (0..=num_features).collect() }
+    }
+
+    /// Layout opening the bias and the non-zero features only
+    pub fn plan(features: &[i64]) -> Self {
+        let nonzero = features.iter().enumerate().filter(|&(_, &x)| x != 0).map(|(j, _)| j + 1);
+        Self { terms: core::iter::once(0).chain(nonzero).collect() }
+    }
+
+    /// Layout opening `terms`, which must start with the bias and increase
+    pub fn new(terms: Vec<usize>) -> Result<Self, SparseError> {
+        if terms.first() != Some(&0) || terms.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(SparseError::InvalidLayout);
+        }
+        Ok(Self { terms })
+    }
+
+    pub fn terms(&self) -> &[usize] {
+        &self.terms
+    }
+
+    pub fn trace_width(&self) -> usize {
+        SPONGES_START + STATE_WIDTH * self.terms.len()
+    }
+
+    fn sponge(&self, slot: usize) -> RescueGadget {
+        RescueGadget::new(SPONGES_START + STATE_WIDTH * slot)
+    }
+
+    /// Checks that the layout fits a model of `features.len()` features and opens every
+    /// non-zero feature
+    fn check(&self, features: &[i64]) -> Result<(), SparseError> {
+        if let Some(&term) = self.terms.last().filter(|&&term| term > features.len()) {
+            return Err(SparseError::UnknownTerm(term));
+        }
+        match features.iter().enumerate().find(|&(j, &x)| x != 0 && !self.terms.contains(&(j + 1))) {
+            Some((feature, _)) => Err(SparseError::Uncovered { feature }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reason a sparse prediction cannot be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SparseError {
+    /// The model has a different number of weights than the claim has features
+    FeatureCount { expected: usize, found: usize },
+    /// The layout does not start with the bias or is not increasing
+    InvalidLayout,
+    /// The layout opens a term past the model's features
+    UnknownTerm(usize),
+    /// A non-zero feature has no columns in the layout
+    Uncovered { feature: usize },
+}
+
+impl fmt::Display for SparseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SparseError::FeatureCount { expected, found } => {
+                write!(f, "model has {expected} features, claim has {found}")
+            }
+            SparseError::InvalidLayout => write!(f, "layout must open the bias first and terms in increasing order"),
+            SparseError::UnknownTerm(term) => write!(f, "layout opens term {term} past the model's features"),
+            SparseError::Uncovered { feature } => write!(f, "feature {feature} is non-zero but not in the layout"),
+        }
+    }
+}
+
+impl std::error::Error for SparseError {}
+
+/// A linear model with one salt per term, the bias first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparseModel {
+    pub bias: i64,
+    pub weights: Vec<i64>,
+    salt: BaseElement,
+}
+
+impl SparseModel {
+    pub fn new(bias: i64, weights: Vec<i64>, salt: BaseElement) -> Self {
+        Self { bias, weights, salt }
+    }
+
+    pub fn num_features(&self) -> usize {
+        self.weights.len()
+    }
+
+    fn term(&self, term: usize) -> i64 {
+        if term == 0 { self.bias } else { self.weights[term - 1] }
+    }
+
+    fn term_salt(&self, term: usize) -> BaseElement {
+        rescue::hash_elements(&[self.salt, BaseElement::from(term as u64)], MODEL_TAG)
+    }
+
+    fn term_start(&self, term: usize) -> [BaseElement; STATE_WIDTH] {
+        let weight = to_field(self.term(term) as i128);
+        [weight, self.term_salt(term), BaseElement::from(TERM_TAG), BaseElement::from(term as u64)]
+    }
+
+    /// Commitment of every term, the bias first
+    pub fn term_commitments(&self) -> Vec<BaseElement> {
+        (0..=self.num_features())
+            .map(|term| {
+                let mut state = self.term_start(term);
+                rescue::permute(&mut state);
+                state[0]
+            })
+            .collect()
+    }
+
+    pub fn commitment(&self) -> BaseElement {
+        model_commitment(&self.term_commitments())
+    }
+
+    pub fn predict(&self, features: &[i64]) -> i128 {
+        self.weights.iter().zip(features).fold(self.bias as i128, |sum, (&w, &x)| sum + w as i128 * x as i128)
+    }
+}
+
+/// Commitment of a model given the commitments of its terms
+pub fn model_commitment(term_commitments: &[BaseElement]) -> BaseElement {
+    rescue::hash_elements(term_commitments, MODEL_TAG)
+}
+
+/// Public inputs: the term commitments, the features, the layout and the prediction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparseInputs {
+    pub term_commitments: Vec<BaseElement>,
+    pub features: Vec<i64>,
+    pub layout: FeatureLayout,
+    pub prediction: i128,
+}
+
+impl SparseInputs {
+    /// Commitment of the model the prediction is made with, see [`SparseModel::commitment`]
+    pub fn commitment(&self) -> BaseElement {
+        model_commitment(&self.term_commitments)
+    }
+}
+
+impl ToElements<BaseElement> for SparseInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![BaseElement::from(self.term_commitments.len() as u64)];
+        elements.extend(&self.term_commitments);
+        elements.push(BaseElement::from(self.features.len() as u64));
+        elements.extend(field_column(&self.features));
+        elements.push(BaseElement::from(self.layout.terms.len() as u64));
+        elements.extend(self.layout.terms.iter().map(|&term| BaseElement::from(term as u64)));
+        elements.push(to_field(self.prediction));
+        elements
+    }
+}
+
+/// AIR proving a committed linear model's prediction, with columns for the opened terms only
+pub struct SparsePredictionAir {
+    context: AirContext<BaseElement>,
+    inputs: SparseInputs,
+}
+
+impl SparsePredictionAir {
+    /// Feature value multiplying each opened term
+    fn term_values(&self) -> impl Iterator<Item = BaseElement> + '_ {
+        let features = &self.inputs.features;
+        let value = |term: usize| if term == 0 { BaseElement::ONE } else { to_field(features[term - 1] as i128) };
+        self.inputs.layout.terms.iter().map(move |&term| value(term))
+    }
+}
+
+impl Air for SparsePredictionAir {
+    type BaseField = BaseElement;
+    type PublicInputs = SparseInputs;
+
+    fn new(trace_info: TraceInfo, inputs: SparseInputs, options: ProofOptions) -> Self {
+        let layout = &inputs.layout;
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(TRACE_LENGTH, trace_info.length());
+        assert_eq!(inputs.term_commitments.len(), inputs.features.len() + 1, "One commitment per term");
+        assert_eq!(FeatureLayout::new(layout.terms.clone()).as_ref(), Ok(layout), "Invalid layout");
+        assert_eq!(layout.check(&inputs.features), Ok(()), "The layout must open every non-zero feature");
+
+        // Constraints:
+        // 1. Rescue rounds of every opened term's sponge (degree 3)
+        // 2. On the first row, the prediction is the sum of the opened weights times their
+        //    features (degree 1)
+        let mut degrees = Vec::new();
+        for slot in 0..layout.terms.len() {
+            degrees.extend(layout.sponge(slot).constraint_degrees());
+        }
+        degrees.push(TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]));
+
+        let num_assertions = 1 + 3 * layout.terms.len();
+        SparsePredictionAir { context: AirContext::new(trace_info, degrees, num_assertions, options), inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.inputs.layout;
+        let first = periodic_values[rescue::NUM_PERIODIC_COLUMNS];
+
+        let mut sum = E::ZERO;
+        for (slot, x) in self.term_values().enumerate() {
+            let sponge = layout.sponge(slot);
+            sponge.evaluate(current, next, periodic_values, &mut result[STATE_WIDTH * slot..]);
+            sum += E::from(x) * sponge.state(current)[0];
+        }
+        result[STATE_WIDTH * layout.terms.len()] = first * (current[PREDICTION] - sum);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        let mut first = vec![BaseElement::ZERO; CYCLE_LENGTH];
+        first[0] = BaseElement::ONE;
+        let mut columns = rescue::periodic_columns();
+        columns.push(first);
+        columns
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let layout = &self.inputs.layout;
+        let mut assertions = vec![Assertion::single(PREDICTION, 0, to_field(self.inputs.prediction))];
+        for (slot, &term) in layout.terms.iter().enumerate() {
+            // the weight and the salt in the first two lanes stay private
+            let column = SPONGES_START + STATE_WIDTH * slot;
+            assertions.push(Assertion::single(column + 2, 0, BaseElement::from(TERM_TAG)));
+            assertions.push(Assertion::single(column + 3, 0, BaseElement::from(term as u64)));
+            assertions.push(Assertion::single(column, CYCLE_LENGTH - 1, self.inputs.term_commitments[term]));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving `model`'s prediction for `features` with the columns of
+/// `layout`, with the inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_sparse_trace(
+    model: &SparseModel,
+    features: &[i64],
+    layout: &FeatureLayout,
+) -> Result<(TraceTable<BaseElement>, SparseInputs), SparseError> {
+    if features.len() != model.num_features() {
+        return Err(SparseError::FeatureCount { expected: model.num_features(), found: features.len() });
+    }
+    layout.check(features)?;
+
+    let prediction = model.predict(features);
+    let mut trace = TraceTable::new(layout.trace_width(), TRACE_LENGTH);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    row[PREDICTION] = to_field(prediction);
+    let mut states: Vec<_> = layout.terms.iter().map(|&term| model.term_start(term)).collect();
+    for i in 0..TRACE_LENGTH {
+        for (slot, state) in states.iter_mut().enumerate() {
+            layout.sponge(slot).write(&mut row, state);
+            if i < rescue::NUM_ROUNDS {
+                rescue::apply_round(state, i);
+            }
+        }
+        trace.update_row(i, &row);
+    }
+
+    let inputs = SparseInputs {
+        term_commitments: model.term_commitments(),
+        features: features.to_vec(),
+        layout: layout.clone(),
+        prediction,
+    };
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_pruned_layout_proof() {
+        let model = SparseModel::new(-7, vec![3, 0, -2, 5, 11, 4], BaseElement::new(0x5ca1));
+        let features = [0, 9, 4, 0, 0, 1];
+        let layout = FeatureLayout::plan(&features);
+        assert_eq!(layout.terms(), [0, 2, 3, 6]);
+        assert!(layout.trace_width() < FeatureLayout::full(features.len()).trace_width());
+
+        let (trace, inputs) = build_sparse_trace(&model, &features, &layout).unwrap();
+        assert_eq!(inputs.prediction, -7 - 8 + 4);
+        assert_eq!(inputs.commitment(), model.commitment());
+        crate::air_test!(SparsePredictionAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<SparsePredictionAir>(trace, inputs.clone(), ProverConfig::default().to_proof_options());
+        let (proof, options) = (proof.unwrap(), default_acceptable_options());
+        let wrong = SparseInputs { prediction: inputs.prediction + 1, ..inputs.clone() };
+        assert!(verify::<SparsePredictionAir>(proof.clone(), wrong, &options).is_err());
+        // pruned terms have no columns but stay bound to the model through the public inputs
+        let mut other_model = inputs.clone();
+        other_model.term_commitments[1] = SparseModel::new(-7, vec![4, 0, -2, 5, 11, 4], BaseElement::ONE)
+            .term_commitments()[1];
+        assert!(verify::<SparsePredictionAir>(proof.clone(), other_model, &options).is_err());
+        let result = verify::<SparsePredictionAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_layouts_are_part_of_the_claim() {
+        let model = SparseModel::new(1, vec![2, 3, 4], BaseElement::new(9));
+        let features = [5, 0, 6];
+        let full = FeatureLayout::full(3);
+        let (trace, inputs) = build_sparse_trace(&model, &features, &full).unwrap();
+        assert_eq!(trace.width(), full.trace_width());
+        let planned = build_sparse_trace(&model, &features, &FeatureLayout::plan(&features)).unwrap().1;
+        assert_eq!(planned.prediction, inputs.prediction);
+        assert_ne!(planned.to_elements(), inputs.to_elements());
+
+        assert_eq!(
+            build_sparse_trace(&model, &features, &FeatureLayout::new(vec![0, 1]).unwrap()).unwrap_err(),
+            SparseError::Uncovered { feature: 2 }
+        );
+        assert_eq!(FeatureLayout::new(vec![1, 3]), Err(SparseError::InvalidLayout));
+        assert_eq!(FeatureLayout::new(vec![0, 3, 3]), Err(SparseError::InvalidLayout));
+        let past = FeatureLayout::new(vec![0, 1, 3, 4]).unwrap();
+        assert_eq!(build_sparse_trace(&model, &features, &past).unwrap_err(), SparseError::UnknownTerm(4));
+        let short = build_sparse_trace(&model, &[1, 2], &full).unwrap_err();
+        assert_eq!(short, SparseError::FeatureCount { expected: 3, found: 2 });
+    }
+}
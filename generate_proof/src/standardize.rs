@@ -0,0 +1,305 @@
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+    TransitionConstraintDegree, 
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+#[cfg(feature = "prover")]
+use crate::config::padded_trace_length;
+use crate::fixed_point::{div_floor, to_field};
+use crate::gadgets::RangeCheck;
+
+// Trace layout: slope, intercept, raw x, standardized x, y, then the remainder and slack bits
+const SLOPE: usize = 0;
+const INTERCEPT: usize = 1;
+const X: usize = 2;
+const X_STD: usize = 3;
+const Y: usize = 4;
+const BITS_START: usize = 5;
+
+/// Public z-score parameters, expressed as fixed-point integers with `frac_bits` fractional bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StandardizationParams {
+    pub mean: i128,
+    pub std_dev: i128,
+    pub frac_bits: u32,
+}
+
+impl StandardizationParams {
+    pub fn new(mean: i128, std_dev: i128, frac_bits: u32) -> Self {
+        assert!(std_dev > 0, "Standard deviation must be positive");
+        Self { mean, std_dev, frac_bits }
+    }
+
+    /// Computes `floor((x - mean) * 2^frac_bits / std_dev)` and the division remainder
+    pub fn standardize(&self, x: i128) -> (i128, i128) {
+        div_floor((x - self.mean) << self.frac_bits, self.std_dev)
+    }
+
+    /// Range check for the division remainder, which must lie in `0..std_dev`
+    fn remainder_range(&self) -> RangeCheck {
+        RangeCheck::for_bound(BITS_START, self.std_dev as u128)
+    }
+
+    /// Range check for `std_dev - 1 - remainder`, which proves the remainder upper bound
+    fn slack_range(&self) -> RangeCheck {
+        let remainder = self.remainder_range();
+        RangeCheck::new(remainder.next_column(), remainder.num_bits())
+    }
+
+    fn trace_width(&self) -> usize {
+        self.slack_range().next_column()
+    }
+}
+
+/// Public inputs for a regression over a standardized feature
+#[derive(Clone, Debug)]
+pub struct StandardizedRegressionInputs {
+    pub params: StandardizationParams,
+    pub x_value: BaseElement,              // Raw (unstandardized) x of the prediction
+    pub predicted_y: BaseElement,          // The claimed y = m * x_std + b result
+    pub sample_x_values: Vec<BaseElement>, // Raw sample x values
+    pub sample_y_values: Vec<BaseElement>, // Sample y values
+}
+
+impl ToElements<BaseElement> for StandardizedRegressionInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![
+            to_field(self.params.mean),
+            to_field(self.params.std_dev),
+            BaseElement::from(self.params.frac_bits),
+            self.x_value,
+            self.predicted_y,
+        ];
+        elements.extend(&self.sample_x_values);
+        elements.extend(&self.sample_y_values);
+        elements
+    }
+}
+
+/// AIR proving `y = m * x_std + b` where `x_std = (x - mean) / std_dev` in fixed point
+pub struct StandardizedRegressionAir {
+    context: AirContext<BaseElement>,
+    params: StandardizationParams,
+    x_value: BaseElement,
+    predicted_y: BaseElement,
+    sample_x_values: Vec<BaseElement>,
+    sample_y_values: Vec<BaseElement>,
+}
+
+impl Air for StandardizedRegressionAir {
+    type BaseField = BaseElement;
+    type PublicInputs = StandardizedRegressionInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: StandardizedRegressionInputs, options: ProofOptions) -> Self {
+        let params = pub_inputs.params;
+        assert_eq!(params.trace_width(), trace_info.width());
+
+        let num_samples = pub_inputs.sample_x_values.len();
+        assert_eq!(num_samples, pub_inputs.sample_y_values.len(), "Sample arrays must have equal length");
+
+        // Constraints:
+        // 1. Linear relationship on the standardized feature: y = m * x_std + b (degree 1,
+        //    slope is a constant column)
+        // 2. Slope and intercept consistency (degree 1)
+        // 3. Division: (x - mean) * 2^f = x_std * std_dev + r (degree 1)
+        // 4. Remainder bound: std_dev - 1 - r = s (degree 1)
+        // 5. Booleanity of every bit of r and s (degree 2)
+        let mut degrees = vec![
+            TransitionConstraintDegree::new(1),
+            TransitionConstraintDegree::new(1),
+            TransitionConstraintDegree::new(1),
+            TransitionConstraintDegree::new(1),
+            TransitionConstraintDegree::new(1),
+        ];
+        degrees.extend(params.remainder_range().constraint_degrees());
+        degrees.extend(params.slack_range().constraint_degrees());
+
+        let num_assertions = 2 * num_samples + 2;
+
+        StandardizedRegressionAir {
+            context: AirContext::new(trace_info, degrees, num_assertions, options),
+            params,
+            x_value: pub_inputs.x_value,
+            predicted_y: pub_inputs.predicted_y,
+            sample_x_values: pub_inputs.sample_x_values,
+            sample_y_values: pub_inputs.sample_y_values,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+
+        let mean = E::from(to_field(self.params.mean));
+        let std_dev = E::from(to_field(self.params.std_dev));
+        let scale = E::from(to_field(1 << self.params.frac_bits));
+
+        let remainder_range = self.params.remainder_range();
+        let slack_range = self.params.slack_range();
+        let remainder = remainder_range.recompose(current);
+        let slack = slack_range.recompose(current);
+
+        result[0] = current[Y] - current[SLOPE] * current[X_STD] - current[INTERCEPT];
+        result[1] = next[SLOPE] - current[SLOPE];
+        result[2] = next[INTERCEPT] - current[INTERCEPT];
+        result[3] = (current[X] - mean) * scale - current[X_STD] * std_dev - remainder;
+        result[4] = std_dev - E::ONE - remainder - slack;
+
+        let (remainder_result, slack_result) = result[5..].split_at_mut(remainder_range.num_constraints());
+        remainder_range.evaluate(current, remainder_result);
+        slack_range.evaluate(current, slack_result);
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = Vec::new();
+
+        for (i, (&x, &y)) in self.sample_x_values.iter().zip(&self.sample_y_values).enumerate() {
+            assertions.push(Assertion::single(X, i, x));
+            assertions.push(Assertion::single(Y, i, y));
+        }
+
+        let prediction_step = self.sample_x_values.len();
+        assertions.push(Assertion::single(X, prediction_step, self.x_value));
+        assertions.push(Assertion::single(Y, prediction_step, self.predicted_y));
+
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Computes the model output `m * x_std + b` for a raw fixed-point feature
+pub fn predict_standardized(
+    slope: BaseElement,
+    intercept: BaseElement,
+    params: &StandardizationParams,
+    x: i128,
+) -> BaseElement {
+    let (x_std, _) = params.standardize(x);
+    slope * to_field(x_std) + intercept
+}
+
+/// Build the execution trace for a regression over a standardized feature.
+///
+/// Sample y values are derived from the model, so the returned trace always satisfies the AIR
+/// for inputs produced by [`predict_standardized`].
+#[cfg(feature = "prover")]
+pub fn build_standardized_trace(
+    slope: BaseElement,
+    intercept: BaseElement,
+    params: &StandardizationParams,
+    sample_x_values: &[i128],
+    target_x: i128,
+    min_trace_length: usize,
+) -> TraceTable<BaseElement> {
+    let num_samples = sample_x_values.len();
+    // Reserve at least one padding row: the last row is exempt from transition constraints
+    let trace_length = padded_trace_length(num_samples + 2, min_trace_length);
+    let trace_width = params.trace_width();
+    let remainder_range = params.remainder_range();
+    let slack_range = params.slack_range();
+
+    let mut columns = vec![vec![BaseElement::ZERO; trace_length]; trace_width];
+    let mut row = vec![BaseElement::ZERO; trace_width];
+
+    for i in 0..trace_length {
+        let x = sample_x_values.get(i).copied().unwrap_or(target_x);
+        let (x_std, remainder) = params.standardize(x);
+
+        row[SLOPE] = slope;
+        row[INTERCEPT] = intercept;
+        row[X] = to_field(x);
+        row[X_STD] = to_field(x_std);
+        row[Y] = slope * row[X_STD] + intercept;
+
+        if i == trace_length - 1 {
+            remainder_range.fill_exempt_row(&mut row);
+            slack_range.fill_exempt_row(&mut row);
+        } else {
+            remainder_range
+                .fill(&mut row, remainder)
+                .expect("Euclidean remainder is always within 0..std_dev");
+            slack_range
+                .fill(&mut row, params.std_dev - 1 - remainder)
+                .expect("Euclidean remainder is always within 0..std_dev");
+        }
+
+        for (column, &value) in columns.iter_mut().zip(&row) {
+            column[i] = value;
+        }
+    }
+
+    TraceTable::init(columns)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::fixed_point::{quantize, DEFAULT_FRACTIONAL_BITS};
+    use crate::prover::{prove, verify};
+    use crate::config::{default_acceptable_options, ProverConfig};
+
+    #[test]
+    fn test_standardized_regression_proof() {
+        let frac_bits = DEFAULT_FRACTIONAL_BITS;
+        let params = StandardizationParams::new(
+            quantize(4.0, frac_bits),
+            quantize(2.5, frac_bits),
+            frac_bits,
+        );
+
+        // Secret parameters (not revealed in proof)
+        let slope = to_field(quantize(1.5, frac_bits));
+        let intercept = to_field(quantize(-0.25, frac_bits) << frac_bits);
+
+        let sample_x: Vec<i128> = [1.0, 2.5, 4.75, 9.0].iter().map(|&x| quantize(x, frac_bits)).collect();
+        let target_x = quantize(6.2, frac_bits);
+
+        let trace = build_standardized_trace(
+            slope,
+            intercept,
+            &params,
+            &sample_x,
+            target_x,
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+
+        let pub_inputs = StandardizedRegressionInputs {
+            params,
+            x_value: to_field(target_x),
+            predicted_y: predict_standardized(slope, intercept, &params, target_x),
+            sample_x_values: sample_x.iter().map(|&x| to_field(x)).collect(),
+            sample_y_values: sample_x
+                .iter()
+                .map(|&x| predict_standardized(slope, intercept, &params, x))
+                .collect(),
+        };
+
+        crate::air_test!(StandardizedRegressionAir, pub_inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let options = ProverConfig::default().to_proof_options();
+        let proof = prove::<StandardizedRegressionAir>(trace, pub_inputs.clone(), options).unwrap();
+        let min_opts = default_acceptable_options();
+
+        let mut forged_inputs = pub_inputs.clone();
+        forged_inputs.predicted_y += BaseElement::ONE;
+        assert!(verify::<StandardizedRegressionAir>(proof.clone(), forged_inputs, &min_opts).is_err());
+
+        let result = verify::<StandardizedRegressionAir>(proof, pub_inputs, &min_opts);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+}
@@ -0,0 +1,285 @@
+//! Reads from a committed key-value map.
+//!
+//! A [`StateMap`] commits to a map from keys to field elements with the root of a sparse
+//! Merkle tree of Rescue hashes (see [`crate::gadgets::merkle`]): the leaf at position `key`
+//! holds the hash of the key and its value, and positions without a key are empty. An
+//! on-chain app that stores the root accepts a [`StateReadAir`] proof as "the value for key
+//! `k` in the map committed as `root` is `v`". AIRs that read per-key model parameters embed
+//! the [`SparseMerkleGadget`] instead and tie its value column to their own columns.
+//!
+//! Only keys present in the map can be read; an absent key has no opening.
+
+use core::fmt;
+use std::collections::{BTreeMap, HashMap};
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, 
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::gadgets::merkle::{leaf_hash, node_hash, MAX_DEPTH};
+use crate::gadgets::rescue;
+#[cfg(feature = "prover")]
+use crate::gadgets::rescue::CYCLE_LENGTH;
+use crate::gadgets::{MerkleOpening, SparseMerkleGadget};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`StateReadAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "state-read/v1";
+
+/// Reason a map cannot be updated or read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateError {
+    InvalidDepth(usize),
+    /// The key needs more bits than the tree has levels
+    KeyOutOfRange { key: u64, depth: usize },
+    MissingKey(u64),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidDepth(depth) => write!(f, "tree depth {depth} is not in 1..={MAX_DEPTH}"),
+            StateError::KeyOutOfRange { key, depth } => write!(f, "key {key} does not fit into {depth} bits"),
+            StateError::MissingKey(key) => write!(f, "key {key} is not in the map"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// Key-value map committed as a sparse Merkle tree of `depth` levels
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateMap {
+    depth: usize,
+    entries: BTreeMap<u64, BaseElement>,
+}
+
+impl StateMap {
+    pub fn new(depth: usize) -> Result<Self, StateError> {
+        if !(1..=MAX_DEPTH).contains(&depth) {
+            return Err(StateError::InvalidDepth(depth));
+        }
+        Ok(Self { depth, entries: BTreeMap::new() })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn get(&self, key: u64) -> Option<BaseElement> {
+        self.entries.get(&key).copied()
+    }
+
+    /// Sets the value of `key`, returning the previous one
+    pub fn insert(&mut self, key: u64, value: BaseElement) -> Result<Option<BaseElement>, StateError> {
+        if self.depth < u64::BITS as usize && key >> self.depth != 0 {
+            return Err(StateError::KeyOutOfRange { key, depth: self.depth });
+        }
+        Ok(self.entries.insert(key, value))
+    }
+
+    pub fn remove(&mut self, key: u64) -> Option<BaseElement> {
+        self.entries.remove(&key)
+    }
+
+    /// Root of the tree; the empty map has the root of a tree of empty leaves
+    pub fn root(&self) -> BaseElement {
+        let levels = self.levels();
+        levels[self.depth].get(&0).copied().unwrap_or_else(|| empty_roots(self.depth)[self.depth])
+    }
+
+    /// Opening of the leaf of `key`
+    pub fn open(&self, key: u64) -> Result<MerkleOpening, StateError> {
+        let value = self.get(key).ok_or(StateError::MissingKey(key))?;
+        let (levels, empty) = (self.levels(), empty_roots(self.depth));
+        let siblings = (0..self.depth)
+            .map(|level| levels[level].get(&(key >> level ^ 1)).copied().unwrap_or(empty[level]))
+            .collect();
+        Ok(MerkleOpening { key, value, siblings })
+    }
+
+    /// Non-empty nodes of every level by position, leaves first
+    fn levels(&self) -> Vec<HashMap<u64, BaseElement>> {
+        let empty = empty_roots(self.depth);
+        let mut levels = vec![self.entries.iter().map(|(&key, &value)| (key, leaf_hash(key, value))).collect()];
+        for level in 0..self.depth {
+            let children: &HashMap<u64, BaseElement> = &levels[level];
+            let mut parents = HashMap::with_capacity(children.len());
+            for &position in children.keys() {
+                let child = |position| children.get(&position).copied().unwrap_or(empty[level]);
+                let parent = position >> 1;
+                let (left, right) = (parent << 1, parent << 1 | 1);
+                parents.entry(parent).or_insert_with(|| node_hash(level + 1, child(left), child(right)));
+            }
+            levels.push(parents);
+        }
+        levels
+    }
+}
+
+/// Roots of empty subtrees, by level
+fn empty_roots(depth: usize) -> Vec<BaseElement> {
+    let mut roots = vec![BaseElement::ZERO];
+    for level in 1..=depth {
+        roots.push(node_hash(level, roots[level - 1], roots[level - 1]));
+    }
+    roots
+}
+
+/// Public inputs: the committed root, the tree depth and the read entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateReadInputs {
+    pub root: BaseElement,
+    pub depth: usize,
+    pub key: u64,
+    pub value: BaseElement,
+}
+
+impl StateReadInputs {
+    /// Parses the elements returned by `to_elements`, e.g. the public inputs of an envelope
+    pub fn from_elements(elements: &[BaseElement]) -> Option<Self> {
+        let [root, depth, key, value] = elements.try_into().ok()?;
+        let (depth, key) = (usize::try_from(depth.as_int()).ok()?, u64::try_from(key.as_int()).ok()?);
+        Some(Self { root, depth, key, value })
+    }
+}
+
+impl ToElements<BaseElement> for StateReadInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![self.root, BaseElement::from(self.depth as u64), BaseElement::from(self.key), self.value]
+    }
+}
+
+/// Trace length for a read from a tree of `depth` levels
+pub fn trace_length(depth: usize) -> usize {
+    SparseMerkleGadget::num_rows(depth).next_power_of_two()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    SparseMerkleGadget::new(0).describe("path", LayoutDescriptor::new(AIR_ID))
+}
+
+/// AIR proving that a key holds a value in a committed map
+pub struct StateReadAir {
+    context: AirContext<BaseElement>,
+    path: SparseMerkleGadget,
+    inputs: StateReadInputs,
+}
+
+impl Air for StateReadAir {
+    type BaseField = BaseElement;
+    type PublicInputs = StateReadInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: StateReadInputs, options: ProofOptions) -> Self {
+        let path = SparseMerkleGadget::new(0);
+        assert_eq!(path.width(), trace_info.width());
+        assert!((1..=MAX_DEPTH).contains(&pub_inputs.depth), "Tree depth must be in 1..={MAX_DEPTH}");
+        assert_eq!(trace_length(pub_inputs.depth), trace_info.length());
+
+        // Constraints:
+        // 1. The sparse Merkle path from the leaf of the key to the root, see SparseMerkleGadget
+        let context = AirContext::new(trace_info, path.constraint_degrees(), 8, options);
+        StateReadAir { context, path, inputs: pub_inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        self.path.evaluate(frame.current(), frame.next(), periodic_values, result);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let StateReadInputs { root, depth, key, value } = self.inputs;
+        self.path.assertions(0, depth, key, value, root)
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace reading `key` from `map`, with the inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_state_read_trace(
+    map: &StateMap,
+    key: u64,
+) -> Result<(TraceTable<BaseElement>, StateReadInputs), StateError> {
+    let opening = map.open(key)?;
+    let path = SparseMerkleGadget::new(0);
+    let length = trace_length(map.depth());
+    let mut columns = vec![vec![BaseElement::ZERO; length]; path.width()];
+    path.fill(&mut columns, 0, &opening, length / CYCLE_LENGTH);
+
+    let mut last_row: Vec<BaseElement> = columns.iter().map(|column| column[length - 1]).collect();
+    path.fill_exempt_row(&mut last_row);
+    for (column, value) in columns.iter_mut().zip(last_row) {
+        column[length - 1] = value;
+    }
+
+    let inputs = StateReadInputs { root: map.root(), depth: map.depth(), key, value: opening.value };
+    Ok((TraceTable::init(columns), inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_state_read_proof() {
+        let mut map = StateMap::new(12).unwrap();
+        for (key, value) in [(5, 50u128), (6, 60), (4095, 7), (1024, 0)] {
+            map.insert(key, BaseElement::new(value)).unwrap();
+        }
+        let (trace, inputs) = build_state_read_trace(&map, 6).unwrap();
+        assert_eq!(inputs.value, BaseElement::new(60));
+        assert_eq!(StateReadInputs::from_elements(&inputs.to_elements()), Some(inputs));
+
+        let proof = prove::<StateReadAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+        let options = default_acceptable_options();
+        let other_value = StateReadInputs { value: BaseElement::new(61), ..inputs };
+        assert!(verify::<StateReadAir>(proof.clone(), other_value, &options).is_err());
+        let other_key = StateReadInputs { key: 5, ..inputs };
+        assert!(verify::<StateReadAir>(proof.clone(), other_key, &options).is_err());
+        let mut updated = map.clone();
+        updated.insert(5, BaseElement::new(51)).unwrap();
+        let other_root = StateReadInputs { root: updated.root(), ..inputs };
+        assert!(verify::<StateReadAir>(proof.clone(), other_root, &options).is_err());
+        let result = verify::<StateReadAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+
+        // the largest key of a full-width tree
+        let mut wide = StateMap::new(MAX_DEPTH).unwrap();
+        wide.insert(u64::MAX, BaseElement::ONE).unwrap();
+        let (trace, inputs) = build_state_read_trace(&wide, u64::MAX).unwrap();
+        prove::<StateReadAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+    }
+
+    #[test]
+    fn test_map_commitment() {
+        let mut map = StateMap::new(4).unwrap();
+        let empty = map.root();
+        map.insert(3, BaseElement::new(9)).unwrap();
+        let opening = map.open(3).unwrap();
+        assert_eq!(opening.root(), map.root());
+        assert_ne!(map.root(), empty);
+        assert_eq!(map.remove(3), Some(BaseElement::new(9)));
+        assert_eq!(map.root(), empty);
+
+        assert_eq!(map.insert(16, BaseElement::ONE), Err(StateError::KeyOutOfRange { key: 16, depth: 4 }));
+        assert_eq!(map.open(2).unwrap_err(), StateError::MissingKey(2));
+        assert_eq!(StateMap::new(65).unwrap_err(), StateError::InvalidDepth(65));
+    }
+}
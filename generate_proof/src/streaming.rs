@@ -0,0 +1,269 @@
+//! Streaming verification of envelope archives (feature `server`).
+//!
+//! A [`StreamVerifier`] reads newline-delimited JSON, one `{"envelope":"0x.."}` object per
+//! line, and writes one verdict line per envelope as soon as it is verified:
+//!
+//! ```text
+//! {"index":0,"claim_hash":"0x..","valid":true,"error":null}
+//! {"index":2,"claim_hash":null,"valid":false,"error":"envelope must be hex encoded"}
+//! {"index":1,"claim_hash":"0x..","valid":false,"error":"..."}
+//! {"done":true,"total":3,"valid":1,"error":null}
+//! ```
+//!
+//! Verdicts come in the order they finish, so clients match them by `index`, the line number
+//! of the envelope among the non-empty input lines. A fixed number of worker threads verify
+//! the envelopes; the reader hands them over through a queue of bounded depth and stops
+//! reading while it is full, so at most `concurrency + queue_depth` envelopes are held in
+//! memory and a slow verifier pushes back on the sender instead of buffering the archive.
+//! Lines longer than [`MAX_LINE_BYTES`] get a failed verdict without being buffered. The
+//! closing `done` line reports the totals, or the read error that ended the stream early.
+
+use std::{
+    io::{self, BufRead, Read, Write},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Instant,
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::abi::{from_hex, to_hex};
+use crate::config::VerificationPolicy;
+use crate::envelope::ProofEnvelope;
+use crate::metrics::{Metrics, Phase};
+use crate::session::verify_linear;
+
+/// Longest input line, i.e. hex encoded envelope, a stream accepts
+pub const MAX_LINE_BYTES: usize = 1 << 20;
+
+#[derive(Deserialize)]
+struct StreamedEnvelope {
+    envelope: String,
+}
+
+/// One line of the input: its index and the line, or why it could not be read
+type Job = (usize, Result<Vec<u8>, String>);
+
+/// Totals of a finished stream
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamSummary {
+    pub total: usize,
+    pub valid: usize,
+}
+
+/// Verifies a stream of envelopes with bounded concurrency and backpressure
+pub struct StreamVerifier {
+    policy: VerificationPolicy,
+    concurrency: usize,
+    queue_depth: usize,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl StreamVerifier {
+    /// Verifier with one worker per available core and a queue twice as deep
+    pub fn new(policy: VerificationPolicy) -> Self {
+        let concurrency = thread::available_parallelism().map_or(1, |cores| cores.get());
+        Self { policy, concurrency, queue_depth: 2 * concurrency, metrics: None }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Envelopes read ahead of the workers before the reader blocks
+    pub fn with_queue_depth(mut self, queue_depth: usize) -> Self {
+        self.queue_depth = queue_depth;
+        self
+    }
+
+    /// Records the duration and outcome of every verification
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Verifies every envelope of `input`, writing and flushing a verdict line per envelope to
+    /// `output`. Fails only when `output` does; a failing `input` ends the stream with an error
+    /// in the closing line.
+    pub fn run(&self, input: impl BufRead + Send, output: &mut impl Write) -> io::Result<StreamSummary> {
+        let (jobs, queue) = mpsc::sync_channel::<Job>(self.queue_depth);
+        let (verdicts, results) = mpsc::sync_channel::<(bool, Value)>(self.queue_depth);
+        // the workers own the queue, so when writing fails and drops `results` they stop, and
+        // with them gone the reader's next send fails and it stops too
+        let queue = Arc::new(Mutex::new(queue));
+        thread::scope(|scope| {
+            let reader = scope.spawn(move || read_lines(input, &jobs));
+            for _ in 0..self.concurrency {
+                let (queue, verdicts) = (Arc::clone(&queue), verdicts.clone());
+                scope.spawn(move || {
+                    loop {
+                        // the lock is only held while waiting for the next line
+                        let Ok((index, line)) = queue.lock().expect("Stream queue poisoned").recv() else { break };
+                        if verdicts.send(self.verify_line(index, line)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop((queue, verdicts));
+
+            let mut summary = StreamSummary::default();
+            for (valid, verdict) in results {
+                summary.total += 1;
+                summary.valid += valid as usize;
+                writeln!(output, "{verdict}")?;
+                output.flush()?;
+            }
+            let error = reader.join().expect("Stream reader panicked").err().map(|err| err.to_string());
+            let done = json!({ "done": true, "total": summary.total, "valid": summary.valid, "error": error });
+            writeln!(output, "{done}")?;
+            output.flush()?;
+            Ok(summary)
+        })
+    }
+
+    fn verify_line(&self, index: usize, line: Result<Vec<u8>, String>) -> (bool, Value) {
+        let envelope = line.and_then(|line| {
+            let request: StreamedEnvelope = serde_json::from_slice(&line).map_err(|err| err.to_string())?;
+            let bytes = from_hex(&request.envelope).ok_or("envelope must be hex encoded")?;
+            ProofEnvelope::from_bytes(&bytes).map_err(|err| err.to_string())
+        });
+        let envelope = match envelope {
+            Ok(envelope) => envelope,
+            Err(error) => return (false, json!({ "index": index, "claim_hash": null, "valid": false, "error": error })),
+        };
+        let started = Instant::now();
+        let result = verify_linear(&envelope, &self.policy);
+        if let Some(metrics) = &self.metrics {
+            metrics.observe(Phase::Verify, started.elapsed());
+            metrics.record_verification(result.is_ok());
+        }
+        let claim_hash = to_hex(&envelope.claim_hash(&self.policy.domain));
+        let error = result.as_ref().err().map(ToString::to_string);
+        (result.is_ok(), json!({ "index": index, "claim_hash": claim_hash, "valid": result.is_ok(), "error": error }))
+    }
+}
+
+/// Sends every non-empty line of `input` to `jobs`, blocking while the queue is full
+fn read_lines(mut input: impl BufRead, jobs: &mpsc::SyncSender<Job>) -> io::Result<()> {
+    let mut index = 0;
+    loop {
+        let mut line = Vec::new();
+        let read = input.by_ref().take(MAX_LINE_BYTES as u64 + 1).read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        let job = if line.len() > MAX_LINE_BYTES && line.last() != Some(&b'\n') {
+            skip_line(&mut input)?;
+            Err(format!("line exceeds {MAX_LINE_BYTES} bytes"))
+        } else if line.trim_ascii().is_empty() {
+            continue;
+        } else {
+            Ok(line)
+        };
+        if jobs.send((index, job)).is_err() {
+            // the writer is gone
+            return Ok(());
+        }
+        index += 1;
+    }
+}
+
+/// Discards the rest of the current line without buffering it
+fn skip_line(input: &mut impl BufRead) -> io::Result<()> {
+    loop {
+        let buffer = input.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        match buffer.iter().position(|&byte| byte == b'\n') {
+            Some(end) => {
+                input.consume(end + 1);
+                return Ok(());
+            }
+            None => {
+                let len = buffer.len();
+                input.consume(len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::session::{LinearClaimRequest, ProvingSession};
+    use winterfell::math::{fields::f128::BaseElement, FieldElement};
+
+    fn envelope(slope: u64) -> ProofEnvelope {
+        let mut session = ProvingSession::new(ProverConfig::default(), "streaming");
+        let request = LinearClaimRequest {
+            model: "demo".into(),
+            salt: to_hex(&[7u8; 32]),
+            slope,
+            intercept: 1,
+            sample_x: vec![1, 2],
+            x: 3,
+            schema: None,
+            beacon: None,
+            terms_hash: None,
+        };
+        session.prove_linear(&request).unwrap()
+    }
+
+    fn verdicts(output: &[u8]) -> Vec<Value> {
+        output.lines().map(|line| serde_json::from_str(&line.unwrap()).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_streams_a_verdict_per_envelope() {
+        let (first, second) = (envelope(2), envelope(5));
+        let mut tampered = second.clone();
+        tampered.public_inputs[0] += BaseElement::ONE;
+        let input = [
+            json!({ "envelope": to_hex(&first.to_bytes()) }).to_string(),
+            String::new(),
+            json!({ "envelope": "zz" }).to_string(),
+            json!({ "envelope": to_hex(&second.to_bytes()) }).to_string(),
+            json!({ "envelope": to_hex(&tampered.to_bytes()) }).to_string(),
+        ]
+        .join("\n");
+
+        let metrics = Arc::new(Metrics::new());
+        let verifier = StreamVerifier::new(VerificationPolicy::default())
+            .with_concurrency(2)
+            .with_queue_depth(1)
+            .with_metrics(metrics.clone());
+        let mut output = Vec::new();
+        let summary = verifier.run(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(summary, StreamSummary { total: 4, valid: 2 });
+        assert_eq!(metrics.verification_failures(), 1);
+
+        let mut lines = verdicts(&output);
+        let done = lines.pop().unwrap();
+        assert_eq!(done, json!({ "done": true, "total": 4, "valid": 2, "error": null }));
+        lines.sort_by_key(|verdict| verdict["index"].as_u64());
+        let valid: Vec<_> = lines.iter().map(|verdict| verdict["valid"].as_bool().unwrap()).collect();
+        assert_eq!(valid, [true, false, true, false]);
+        assert_eq!(lines[1]["error"], "envelope must be hex encoded");
+        assert!(lines[3]["claim_hash"].is_string());
+    }
+
+    #[test]
+    fn test_rejects_oversized_lines_without_buffering() {
+        let mut input = vec![b'x'; MAX_LINE_BYTES + 10];
+        input.push(b'\n');
+        input.extend(json!({ "envelope": to_hex(&envelope(2).to_bytes()) }).to_string().bytes());
+
+        let verifier = StreamVerifier::new(VerificationPolicy::default()).with_concurrency(1);
+        let mut output = Vec::new();
+        let summary = verifier.run(input.as_slice(), &mut output).unwrap();
+        assert_eq!(summary, StreamSummary { total: 2, valid: 1 });
+        let lines = verdicts(&output);
+        let oversized = lines.iter().find(|verdict| verdict["index"] == 0).unwrap();
+        assert_eq!(oversized["error"], format!("line exceeds {MAX_LINE_BYTES} bytes"));
+    }
+}
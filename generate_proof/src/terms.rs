@@ -0,0 +1,75 @@
+//! Hashes of the license or terms of use a claim is made under.
+//!
+//! A [`TermsHash`] is the BLAKE3 hash of a terms document, published next to the model. The
+//! prover binds it into the claim hash and the public inputs of the proof (see
+//! [`TaggedInputs`](crate::domain::TaggedInputs)), so a claim cannot be moved to other terms
+//! without proving again, and a [`VerificationPolicy`](crate::config::VerificationPolicy) with
+//! `required_terms_hash` rejects claims made under other terms or none.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+use winterfell::math::{fields::f128::BaseElement, ToElements};
+
+use crate::abi::{from_hex, to_hex};
+use crate::nonce::pack_bytes;
+
+const TERMS_DOMAIN: &str = "stark-framework/terms/v1";
+
+/// Hash of a terms document, serialized as hex; never all zeros, which encodes "no terms"
+/// on-chain
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TermsHash(pub [u8; 32]);
+
+impl TermsHash {
+    /// Hash of the terms document `terms`
+    pub fn of(terms: &[u8]) -> Self {
+        Self(blake3::derive_key(TERMS_DOMAIN, terms))
+    }
+
+    /// Parses the hex encoding produced by `Display`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let bytes: [u8; 32] = from_hex(hex)?.try_into().ok()?;
+        (bytes != [0u8; 32]).then_some(Self(bytes))
+    }
+}
+
+impl fmt::Display for TermsHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl TryFrom<String> for TermsHash {
+    type Error = InvalidTermsHash;
+
+    fn try_from(hex: String) -> Result<Self, InvalidTermsHash> {
+        Self::from_hex(&hex).ok_or(InvalidTermsHash)
+    }
+}
+
+impl From<TermsHash> for String {
+    fn from(hash: TermsHash) -> Self {
+        hash.to_string()
+    }
+}
+
+impl ToElements<BaseElement> for TermsHash {
+    /// The bytes packed little-endian, 15 per element
+    fn to_elements(&self) -> Vec<BaseElement> {
+        pack_bytes(&self.0)
+    }
+}
+
+/// A terms hash that is not 32 hex-encoded bytes other than all zeros
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidTermsHash;
+
+impl fmt::Display for InvalidTermsHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "terms hash must be 32 hex-encoded bytes other than all zeros")
+    }
+}
+
+impl std::error::Error for InvalidTermsHash {}
@@ -0,0 +1,261 @@
+//! Trace inspection utilities: cell-level diffing and a plain-text trace format.
+//!
+//! The text format has one row per line, cells as comma-separated field elements in the canonical
+//! hex encoding of [`crate::codec`]; decimal cells from older files are still accepted.
+
+use core::fmt;
+use std::{fs, io, path::Path};
+
+use crate::codec;
+use crate::simulation::{instantiate, DryRunError};
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, EvaluationFrame, ProofOptions, Trace, TraceInfo, TraceTable,
+};
+
+/// A cell whose value differs between two traces
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellChange {
+    pub column: usize,
+    pub row: usize,
+    pub before: BaseElement,
+    pub after: BaseElement,
+}
+
+/// A transition constraint whose evaluation at `row` differs between two traces
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConstraintChange {
+    pub constraint: usize,
+    pub row: usize,
+    pub before: BaseElement,
+    pub after: BaseElement,
+}
+
+/// Differences between two traces, and optionally between their AIR evaluations
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceDiff {
+    /// `(width, length)` of both traces, set only when they differ
+    pub shape_mismatch: Option<((usize, usize), (usize, usize))>,
+    /// Changed cells within the overlapping region, in row-major order
+    pub cells: Vec<CellChange>,
+    /// Only populated by [`diff_with_air`]
+    pub constraints: Vec<ConstraintChange>,
+    /// Only populated by [`diff_with_air`]
+    pub public_inputs_differ: bool,
+}
+
+impl TraceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.shape_mismatch.is_none() && self.cells.is_empty() && self.constraints.is_empty() && !self.public_inputs_differ
+    }
+
+    /// Indices of the constraints whose evaluation changed, without duplicates
+    pub fn affected_constraints(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.constraints.iter().map(|c| c.constraint).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+impl fmt::Display for TraceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(((wa, la), (wb, lb))) = self.shape_mismatch {
+            writeln!(f, "shape: {wa}x{la} -> {wb}x{lb}")?;
+        }
+        for cell in &self.cells {
+            writeln!(f, "cell [row {}, column {}]: {} -> {}", cell.row, cell.column, cell.before, cell.after)?;
+        }
+        for c in &self.constraints {
+            writeln!(f, "constraint {} at row {}: {} -> {}", c.constraint, c.row, c.before, c.after)?;
+        }
+        if self.public_inputs_differ {
+            writeln!(f, "public inputs differ")?;
+        }
+        if self.is_empty() {
+            writeln!(f, "traces are identical")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two traces cell by cell
+pub fn diff(a: &TraceTable<BaseElement>, b: &TraceTable<BaseElement>) -> TraceDiff {
+    let shape_a = (a.width(), a.length());
+    let shape_b = (b.width(), b.length());
+
+    let mut cells = Vec::new();
+    for row in 0..shape_a.1.min(shape_b.1) {
+        for column in 0..shape_a.0.min(shape_b.0) {
+            let (before, after) = (a.get(column, row), b.get(column, row));
+            if before != after {
+                cells.push(CellChange { column, row, before, after });
+            }
+        }
+    }
+
+    TraceDiff {
+        shape_mismatch: (shape_a != shape_b).then_some((shape_a, shape_b)),
+        cells,
+        ..TraceDiff::default()
+    }
+}
+
+/// Compares two traces and the transition constraint evaluations of `A` over them.
+///
+/// Constraint evaluations are only compared when both traces have the same shape; an AIR that
+/// cannot be built for either side is reported as an error.
+pub fn diff_with_air<A: Air<BaseField = BaseElement>>(
+    a: &TraceTable<BaseElement>,
+    inputs_a: A::PublicInputs,
+    b: &TraceTable<BaseElement>,
+    inputs_b: A::PublicInputs,
+    options: ProofOptions,
+) -> Result<TraceDiff, DryRunError> {
+    let mut result = diff(a, b);
+    result.public_inputs_differ = inputs_a.to_elements() != inputs_b.to_elements();
+    if result.shape_mismatch.is_some() {
+        return Ok(result);
+    }
+
+    let air_a = instantiate::<A>(a, inputs_a, options.clone())?;
+    let air_b = instantiate::<A>(b, inputs_b, options)?;
+    let evals_a = evaluate_transitions(&air_a, a);
+    let evals_b = evaluate_transitions(&air_b, b);
+
+    for (row, (row_a, row_b)) in evals_a.iter().zip(&evals_b).enumerate() {
+        for (constraint, (&before, &after)) in row_a.iter().zip(row_b).enumerate() {
+            if before != after {
+                result.constraints.push(ConstraintChange { constraint, row, before, after });
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Evaluates every main transition constraint of `air` on every row but the last
+pub fn evaluate_transitions<A: Air<BaseField = BaseElement>>(
+    air: &A,
+    trace: &TraceTable<BaseElement>,
+) -> Vec<Vec<BaseElement>> {
+    let periodic_columns = air.get_periodic_column_values();
+    let num_constraints = air.context().num_main_transition_constraints();
+
+    (0..trace.length() - 1)
+        .map(|row| {
+            let frame = EvaluationFrame::from_rows(row_values(trace, row), row_values(trace, row + 1));
+            let periodic: Vec<BaseElement> = periodic_columns.iter().map(|c| c[row % c.len()]).collect();
+            let mut evaluations = vec![BaseElement::ZERO; num_constraints];
+            air.evaluate_transition(&frame, &periodic, &mut evaluations);
+            evaluations
+        })
+        .collect()
+}
+
+fn row_values(trace: &TraceTable<BaseElement>, row: usize) -> Vec<BaseElement> {
+    (0..trace.width()).map(|column| trace.get(column, row)).collect()
+}
+
+/// Writes `trace` in the text format described in the module docs
+pub fn write_trace(trace: &TraceTable<BaseElement>, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut text = String::new();
+    for row in 0..trace.length() {
+        let cells: Vec<String> = row_values(trace, row).into_iter().map(codec::encode).collect();
+        text.push_str(&cells.join(","));
+        text.push('\n');
+    }
+    fs::write(path, text)
+}
+
+/// Reads a trace written by [`write_trace`]
+pub fn read_trace(path: impl AsRef<Path>) -> io::Result<TraceTable<BaseElement>> {
+    let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidData, reason);
+    let text = fs::read_to_string(path)?;
+
+    let mut columns: Vec<Vec<BaseElement>> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let cells = line
+            .split(',')
+            .map(|cell| {
+                codec::parse_element(cell).map_err(|err| invalid(format!("line {}: `{}`: {err}", i + 1, cell.trim())))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        if columns.is_empty() {
+            columns = vec![Vec::new(); cells.len()];
+        } else if cells.len() != columns.len() {
+            return Err(invalid(format!("line {}: expected {} cells", i + 1, columns.len())));
+        }
+        for (column, cell) in columns.iter_mut().zip(cells) {
+            column.push(cell);
+        }
+    }
+
+    let length = columns.first().map_or(0, Vec::len);
+    if length < TraceInfo::MIN_TRACE_LENGTH || !length.is_power_of_two() {
+        return Err(invalid(format!("trace length {length} is not a power of two of at least 8")));
+    }
+    Ok(TraceTable::init(columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProverConfig;
+    use crate::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+
+    fn inputs(predicted_y: u64) -> LinearRegressionInputs {
+        LinearRegressionInputs {
+            x_value: BaseElement::new(8),
+            predicted_y: BaseElement::from(predicted_y),
+            sample_x_values: vec![BaseElement::new(1), BaseElement::new(3)],
+            sample_y_values: vec![BaseElement::new(7), BaseElement::new(11)],
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_cells_and_constraints() {
+        let xs = [BaseElement::new(1), BaseElement::new(3)];
+        let ys = [BaseElement::new(7), BaseElement::new(11)];
+        let a = build_linear_regression_trace(
+            BaseElement::new(2),
+            BaseElement::new(5),
+            &xs,
+            &ys,
+            BaseElement::new(8),
+            TraceInfo::MIN_TRACE_LENGTH,
+        );
+        assert!(diff(&a, &a).is_empty());
+
+        let mut columns: Vec<Vec<BaseElement>> = (0..a.width()).map(|c| a.get_column(c).to_vec()).collect();
+        columns[3][1] = BaseElement::new(12);
+        let b = TraceTable::init(columns);
+
+        let result = diff_with_air::<LinearRegressionAir>(
+            &a,
+            inputs(21),
+            &b,
+            inputs(22),
+            ProverConfig::default().to_proof_options(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.cells,
+            [CellChange { column: 3, row: 1, before: BaseElement::new(11), after: BaseElement::new(12) }]
+        );
+        assert_eq!(result.affected_constraints(), [0]);
+        assert_eq!(result.constraints[0].row, 1);
+        assert!(result.public_inputs_differ);
+
+        let mut unpaired = inputs(21);
+        unpaired.sample_y_values.pop();
+        let options = ProverConfig::default().to_proof_options();
+        let err = diff_with_air::<LinearRegressionAir>(&a, unpaired, &a, inputs(21), options).unwrap_err();
+        assert!(matches!(err, DryRunError::AirRejected(_)), "{err}");
+
+        let path = std::env::temp_dir().join(format!("trace-{}.csv", std::process::id()));
+        write_trace(&b, &path).unwrap();
+        assert!(diff(&b, &read_trace(&path).unwrap()).is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+//! Replay of a proof's Fiat–Shamir transcript.
+//!
+//! [`Transcript::replay`] parses a proof's commitments and out-of-domain frame and draws every
+//! challenge from them in the order `winterfell::verify` does: the constraint composition
+//! coefficients, the out-of-domain point `z`, the DEEP composition coefficients, the FRI
+//! folding challenges and the query positions. Nothing is checked on the way, so a broken
+//! proof replays as far as it parses; [`diagnostics`](crate::diagnostics) and
+//! [`optimistic`](crate::optimistic) both start from the replay.
+
+use core::fmt;
+
+use winterfell::{
+    crypto::{Hasher, RandomCoin},
+    math::{fields::f128::BaseElement, ToElements},
+    Air, ConstraintCompositionCoefficients, DeepCompositionCoefficients, EvaluationFrame, FieldExtension, Proof,
+};
+
+use crate::prover::HashFn;
+
+type Digest32 = <HashFn as Hasher>::Digest;
+
+/// Reason a proof's transcript cannot be replayed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscriptError {
+    /// Only proofs over the base field are replayed
+    UnsupportedExtension(FieldExtension),
+    /// The proof does not parse
+    Malformed(String),
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::UnsupportedExtension(extension) => {
+                write!(f, "proofs over the {extension:?} field extension are not replayed")
+            }
+            TranscriptError::Malformed(reason) => write!(f, "malformed proof: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for TranscriptError {}
+
+fn malformed(err: impl fmt::Display) -> TranscriptError {
+    TranscriptError::Malformed(err.to_string())
+}
+
+/// What a proof committed to and the challenges the verifier draws from it
+pub struct Transcript<A> {
+    pub air: A,
+    pub trace_root: Digest32,
+    pub constraint_root: Digest32,
+    /// Roots of the FRI layers, the remainder's last
+    pub fri_roots: Vec<Digest32>,
+    /// Trace rows at `z` and `z * g`
+    pub ood_trace: EvaluationFrame<BaseElement>,
+    pub ood_composition_current: Vec<BaseElement>,
+    pub ood_composition_next: Vec<BaseElement>,
+    pub constraint_coefficients: ConstraintCompositionCoefficients<BaseElement>,
+    pub z: BaseElement,
+    pub deep_coefficients: DeepCompositionCoefficients<BaseElement>,
+    /// Folding challenge drawn after each of `fri_roots`
+    pub fri_alphas: Vec<BaseElement>,
+    /// Leading zeros of the hash of the proof-of-work nonce, at least the grinding factor in a
+    /// valid proof
+    pub pow_leading_zeros: u32,
+    /// Query positions, sorted and without duplicates
+    pub positions: Vec<usize>,
+}
+
+impl<A: Air<BaseField = BaseElement>> Transcript<A> {
+    /// Transcript of a proof for AIR `A` whose challenges were drawn from the random coin `C`
+    pub fn replay<C>(proof: &Proof, pub_inputs: A::PublicInputs) -> Result<Self, TranscriptError>
+    where
+        C: RandomCoin<BaseField = BaseElement, Hasher = HashFn>,
+    {
+        let extension = proof.options().field_extension();
+        if extension != FieldExtension::None {
+            return Err(TranscriptError::UnsupportedExtension(extension));
+        }
+        if proof.trace_info().is_multi_segment() {
+            return Err(malformed("auxiliary trace segments are not supported"));
+        }
+
+        let mut seed = proof.context.to_elements();
+        seed.extend(pub_inputs.to_elements());
+        let air = A::new(proof.trace_info().clone(), pub_inputs, proof.options().clone());
+        let trace_width = air.trace_info().main_trace_width();
+        let num_columns = air.context().num_constraint_composition_columns();
+        let num_fri_layers = air.options().to_fri_options().num_fri_layers(air.lde_domain_size());
+
+        let (trace_roots, constraint_root, fri_roots) =
+            proof.commitments.clone().parse::<HashFn>(1, num_fri_layers).map_err(malformed)?;
+        let (ood_trace, ood_columns) =
+            proof.ood_frame.clone().parse::<BaseElement>(trace_width, 0, num_columns).map_err(malformed)?;
+        let mut coin = C::new(&seed);
+        coin.reseed(trace_roots[0]);
+        let constraint_coefficients =
+            air.get_constraint_composition_coefficients::<BaseElement, C>(&mut coin).map_err(malformed)?;
+        coin.reseed(constraint_root);
+        let z = coin.draw::<BaseElement>().map_err(malformed)?;
+        coin.reseed(ood_trace.hash::<HashFn>());
+        coin.reseed(ood_columns.hash::<HashFn>());
+        let deep_coefficients =
+            air.get_deep_composition_coefficients::<BaseElement, C>(&mut coin).map_err(malformed)?;
+        let mut fri_alphas = Vec::with_capacity(fri_roots.len());
+        for root in &fri_roots {
+            coin.reseed(*root);
+            fri_alphas.push(coin.draw::<BaseElement>().map_err(malformed)?);
+        }
+        let pow_leading_zeros = coin.check_leading_zeros(proof.pow_nonce);
+        let mut positions = coin
+            .draw_integers(air.options().num_queries(), air.lde_domain_size(), proof.pow_nonce)
+            .map_err(malformed)?;
+        positions.sort_unstable();
+        positions.dedup();
+
+        Ok(Self {
+            air,
+            trace_root: trace_roots[0],
+            constraint_root,
+            fri_roots,
+            ood_trace: ood_trace.main_frame(),
+            ood_composition_current: ood_columns.current_row().to_vec(),
+            ood_composition_next: ood_columns.next_row().to_vec(),
+            constraint_coefficients,
+            z,
+            deep_coefficients,
+            fri_alphas,
+            pow_leading_zeros,
+            positions,
+        })
+    }
+
+    /// Whether the proof opens as many queries as the replay drew; a proof whose transcript
+    /// diverged from the prover's has no openings at the replayed positions
+    pub fn matches_openings(&self, proof: &Proof) -> bool {
+        self.positions.len() == proof.num_unique_queries as usize
+    }
+}
+
@@ -0,0 +1,373 @@
+//! Data validity claims: every committed sample lies in the ranges of its [`DataSchema`].
+//!
+//! A model claim over private data says nothing about that data being sensible; a dataset of
+//! negative floor areas proves a regression just as well. [`DataValidityAir`] proves that every
+//! value of the rows hashed into a public [`samples_commitment`] lies within the `min..=max`
+//! its column declares in the schema, so the proof itself attests to basic data validity. The
+//! schema and the number of rows are public, the values stay private.
+//!
+//! Rows are absorbed cell by cell, the features in schema order followed by the target, one
+//! [`CYCLE_LENGTH`]-row cycle per cell plus one that finishes the sponge, which starts from the
+//! salt and the number of cells. As in [`SensitivityAir`](crate::sensitivity::SensitivityAir),
+//! a cycle's value and its asserted bounds stay constant and both distances to the bounds are
+//! range checked on every row. The range checks are as wide as the widest declared range,
+//! rounded up to whole digits; ranges wider than [`MAX_GAP_BITS`] bits cannot be proven.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::RangeCheck;
+use crate::layout::LayoutDescriptor;
+use crate::schema::{ColumnSchema, DataSchema, SchemaError};
+
+/// Identifier of [`DataValidityAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "data-validity/v1";
+
+/// Widest range a column may declare; two distances below `2^126` cannot wrap around the field
+pub const MAX_GAP_BITS: usize = 126;
+
+/// Sponge tag of [`samples_commitment`]; tags 1 to 16 are used elsewhere
+const SAMPLES_TAG: u64 = 17;
+const DIGIT_BITS: usize = 2;
+
+// Trace layout: sponge state, the cycle's value and bounds, then the digits of `value - min`
+// and of `max - value`
+const SPONGE: usize = 0;
+const VALUE: usize = SPONGE + STATE_WIDTH;
+const LOWER: usize = VALUE + 1;
+const UPPER: usize = LOWER + 1;
+const DIGITS_START: usize = UPPER + 1;
+
+struct Layout {
+    sponge: RescueGadget,
+    above_min: RangeCheck,
+    below_max: RangeCheck,
+}
+
+impl Layout {
+    fn new(gap_bits: usize) -> Self {
+        let above_min = RangeCheck::with_digit_bits(DIGITS_START, gap_bits, DIGIT_BITS);
+        let below_max = RangeCheck::with_digit_bits(above_min.next_column(), gap_bits, DIGIT_BITS);
+        Self { sponge: RescueGadget::new(SPONGE), above_min, below_max }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.below_max.next_column()
+    }
+}
+
+fn columns(schema: &DataSchema) -> Vec<&ColumnSchema> {
+    schema.features.iter().chain([&schema.target]).collect()
+}
+
+/// Bits of the range checks for `schema`: the widest declared range, in whole digits
+pub fn gap_bits(schema: &DataSchema) -> Result<usize, ValidityError> {
+    let mut bits = DIGIT_BITS;
+    for column in columns(schema) {
+        let width = column.max.abs_diff(column.min);
+        let needed = (u128::BITS - width.leading_zeros()) as usize;
+        if needed > MAX_GAP_BITS {
+            return Err(ValidityError::RangeTooWide { column: column.name.clone() });
+        }
+        bits = bits.max(needed.next_multiple_of(DIGIT_BITS));
+    }
+    Ok(bits)
+}
+
+/// Number of trace columns for `schema`
+pub fn trace_width(schema: &DataSchema) -> Result<usize, ValidityError> {
+    Ok(Layout::new(gap_bits(schema)?).trace_width())
+}
+
+/// Named columns of the trace for `schema`, see [`crate::layout`]
+pub fn layout(schema: &DataSchema) -> Result<LayoutDescriptor, ValidityError> {
+    let layout = Layout::new(gap_bits(schema)?);
+    Ok(LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..VALUE)
+        .with_column("value", VALUE)
+        .with_column("lower", LOWER)
+        .with_column("upper", UPPER)
+        .with_columns("above_min_digits", DIGITS_START..layout.above_min.next_column())
+        .with_columns("below_max_digits", layout.above_min.next_column()..layout.below_max.next_column()))
+}
+
+/// Trace length for `num_rows` rows of `num_columns` values each
+pub fn trace_length(num_rows: usize, num_columns: usize) -> usize {
+    ((num_rows * num_columns + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+fn sponge_start(num_cells: usize, salt: BaseElement) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_cells, SAMPLES_TAG);
+    state[0] = salt;
+    state
+}
+
+/// Commitment to `rows` of feature values followed by the target, blinded by `salt`
+pub fn samples_commitment(rows: &[Vec<i128>], salt: BaseElement) -> BaseElement {
+    let num_cells = rows.iter().map(Vec::len).sum();
+    let mut state = sponge_start(num_cells, salt);
+    rescue::permute(&mut state);
+    for &value in rows.iter().flatten() {
+        state[0] += to_field(value);
+        rescue::permute(&mut state);
+    }
+    state[0]
+}
+
+/// Reason a dataset cannot be proven valid
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidityError {
+    /// The claim covers no rows
+    Empty,
+    /// The schema is malformed, a row has the wrong width or a value is out of range
+    Schema(SchemaError),
+    /// A column declares a range wider than [`MAX_GAP_BITS`] bits
+    RangeTooWide { column: String },
+}
+
+impl fmt::Display for ValidityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidityError::Empty => write!(f, "the claim covers no rows"),
+            ValidityError::Schema(err) => write!(f, "{err}"),
+            ValidityError::RangeTooWide { column } => {
+                write!(f, "column {column} declares a range wider than {MAX_GAP_BITS} bits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidityError {}
+
+/// Public inputs: the samples commitment, the number of rows and the schema they respect
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataValidityInputs {
+    pub commitment: BaseElement,
+    pub num_rows: usize,
+    pub schema: DataSchema,
+}
+
+impl DataValidityInputs {
+    fn num_cells(&self) -> usize {
+        self.num_rows * (self.schema.features.len() + 1)
+    }
+}
+
+impl ToElements<BaseElement> for DataValidityInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![
+            self.commitment,
+            BaseElement::from(self.num_rows as u64),
+            BaseElement::from(self.schema.features.len() as u64),
+        ];
+        for column in columns(&self.schema) {
+            elements.extend([to_field(column.min), to_field(column.max)]);
+        }
+        elements
+    }
+}
+
+/// AIR proving that every value of a committed dataset lies within its column's declared range
+pub struct DataValidityAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: DataValidityInputs,
+}
+
+impl Air for DataValidityAir {
+    type BaseField = BaseElement;
+    type PublicInputs = DataValidityInputs;
+
+    fn new(trace_info: TraceInfo, inputs: DataValidityInputs, options: ProofOptions) -> Self {
+        assert_eq!(inputs.schema.validate(), Ok(()), "Data validity claims need a valid schema");
+        let layout = Layout::new(gap_bits(&inputs.schema).expect("Declared ranges fit the range checks"));
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(trace_length(inputs.num_rows, inputs.schema.features.len() + 1), trace_info.length());
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the value at the end of a cycle (degree 1)
+        // 3. Value and bounds are constant within a cycle (degree 1)
+        // 4. Both distances to the bounds equal their range checked digits (degree 1)
+        // 5. Digits of both distances (degree 4)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 3]);
+        degrees.extend(vec![TransitionConstraintDegree::new(1); 2]);
+        degrees.extend(layout.above_min.constraint_degrees());
+        degrees.extend(layout.below_max.constraint_degrees());
+
+        let num_assertions = STATE_WIDTH + 2 * inputs.num_cells();
+        DataValidityAir { context: AirContext::new(trace_info, degrees, num_assertions, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        rest[0] = last * (next[SPONGE] - current[SPONGE] - current[VALUE]);
+        for lane in 1..STATE_WIDTH {
+            rest[lane] = last * (next[SPONGE + lane] - current[SPONGE + lane]);
+        }
+        for (i, column) in [VALUE, LOWER, UPPER].into_iter().enumerate() {
+            rest[STATE_WIDTH + i] = mask * (next[column] - current[column]);
+        }
+        rest[7] = current[VALUE] - current[LOWER] - layout.above_min.recompose(current);
+        rest[8] = current[UPPER] - current[VALUE] - layout.below_max.recompose(current);
+
+        let rest = &mut rest[9..];
+        layout.above_min.evaluate(current, rest);
+        layout.below_max.evaluate(current, &mut rest[layout.above_min.num_constraints()..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_cells = self.inputs.num_cells();
+        // the salt in the first lane stays private
+        let start = sponge_start(num_cells, BaseElement::ZERO);
+        let mut assertions: Vec<_> = (1..STATE_WIDTH).map(|i| Assertion::single(SPONGE + i, 0, start[i])).collect();
+        assertions.push(Assertion::single(SPONGE, (num_cells + 1) * CYCLE_LENGTH - 1, self.inputs.commitment));
+        let columns = columns(&self.inputs.schema);
+        for cell in 0..num_cells {
+            let column = columns[cell % columns.len()];
+            assertions.push(Assertion::single(LOWER, cell * CYCLE_LENGTH, to_field(column.min)));
+            assertions.push(Assertion::single(UPPER, cell * CYCLE_LENGTH, to_field(column.max)));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving that `rows` of feature values followed by the target
+/// respect `schema`, with the inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_validity_trace(
+    rows: &[Vec<i128>],
+    salt: BaseElement,
+    schema: &DataSchema,
+) -> Result<(TraceTable<BaseElement>, DataValidityInputs), ValidityError> {
+    if rows.is_empty() {
+        return Err(ValidityError::Empty);
+    }
+    schema.validate().map_err(ValidityError::Schema)?;
+    let features = rows.iter().map(|row| &row[..row.len().saturating_sub(1)]);
+    let targets = rows.iter().map(|row| row.last().copied().unwrap_or_default());
+    schema.check(features, targets).map_err(ValidityError::Schema)?;
+    let layout = Layout::new(gap_bits(schema)?);
+
+    let columns = columns(schema);
+    let cells: Vec<(i128, &ColumnSchema)> =
+        rows.iter().flat_map(|row| row.iter().copied().zip(columns.iter().copied())).collect();
+    // cycles past the data hold a point just below the first column's range, bounded by itself,
+    // which keeps the bounds columns from being constant
+    let below = columns[0].min.saturating_sub(1);
+    let padding = ColumnSchema { min: below, max: below, ..columns[0].clone() };
+
+    let length = trace_length(rows.len(), columns.len());
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut state = sponge_start(cells.len(), salt);
+    for i in 0..length {
+        let (cycle, step) = (i / CYCLE_LENGTH, i % CYCLE_LENGTH);
+        let (value, column) = cells.get(cycle).copied().unwrap_or((below, &padding));
+        row[VALUE] = to_field(value);
+        row[LOWER] = to_field(column.min);
+        row[UPPER] = to_field(column.max);
+        layout.sponge.write(&mut row, &state);
+        if step < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, step);
+        } else {
+            state[0] += row[VALUE];
+        }
+        if i == length - 1 {
+            layout.above_min.fill_exempt_row(&mut row);
+            layout.below_max.fill_exempt_row(&mut row);
+        } else {
+            layout.above_min.fill(&mut row, value - column.min).expect("Values lie within their ranges");
+            layout.below_max.fill(&mut row, column.max - value).expect("Checked above");
+        }
+        trace.update_row(i, &row);
+    }
+
+    let inputs =
+        DataValidityInputs { commitment: samples_commitment(rows, salt), num_rows: rows.len(), schema: schema.clone() };
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    fn houses() -> DataSchema {
+        DataSchema::new(
+            vec![ColumnSchema::new("area", "m^2", 4, 10 << 4, 1000 << 4), ColumnSchema::new("rooms", "", 0, 1, 12)],
+            ColumnSchema::new("price", "kEUR", 0, 0, 5000),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validity_proof() {
+        let rows = vec![vec![80 << 4, 3, 310], vec![1000 << 4, 12, 5000], vec![10 << 4, 1, 0]];
+        let salt = BaseElement::new(0x5a17);
+        let (trace, inputs) = build_validity_trace(&rows, salt, &houses()).unwrap();
+        assert_eq!(gap_bits(&inputs.schema), Ok(14));
+        crate::air_test!(DataValidityAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let options = ProverConfig::default().to_proof_options();
+        let proof = prove::<DataValidityAir>(trace, inputs.clone(), options).unwrap();
+        let options = default_acceptable_options();
+        let mut narrower = inputs.clone();
+        narrower.schema.features[1].max = 11;
+        assert!(verify::<DataValidityAir>(proof.clone(), narrower, &options).is_err());
+        let other_rows = DataValidityInputs { commitment: samples_commitment(&rows[..2], salt), ..inputs.clone() };
+        assert!(verify::<DataValidityAir>(proof.clone(), other_rows, &options).is_err());
+        let result = verify::<DataValidityAir>(proof, inputs, &options);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_data() {
+        let salt = BaseElement::ONE;
+        let negative_area = build_validity_trace(&[vec![-16, 3, 310]], salt, &houses()).unwrap_err();
+        assert!(matches!(negative_area, ValidityError::Schema(SchemaError::OutOfRange { index: 0, value: -16, .. })));
+        let short_row = build_validity_trace(&[vec![80 << 4, 310]], salt, &houses()).unwrap_err();
+        assert_eq!(short_row, ValidityError::Schema(SchemaError::FeatureCount { expected: 2, found: 1 }));
+        assert_eq!(build_validity_trace(&[], salt, &houses()).unwrap_err(), ValidityError::Empty);
+
+        let mut wide = houses();
+        wide.target.max = i128::MAX;
+        assert_eq!(gap_bits(&wide), Err(ValidityError::RangeTooWide { column: "price".into() }));
+    }
+}
@@ -0,0 +1,472 @@
+//! Variance claims over a committed, private column.
+//!
+//! [`VarianceAir`] proves that the population variance of the values hashed into a public
+//! [`column_commitment`] is at most `max_variance`, a risk metric that can be published without
+//! revealing the column. Values are fixed-point integers with `frac_bits` fractional bits and
+//! the bound is read in the same scale: the proven variance is
+//! `floor((n * sum(x^2) - sum(x)^2) / (n^2 * 2^frac_bits))`.
+//!
+//! The column is absorbed one value per [`CYCLE_LENGTH`]-row cycle, as in
+//! [`DataValidityAir`](crate::validity::DataValidityAir), with the sign and range checked
+//! magnitude of the value next to it as in [`DriftAir`](crate::drift::DriftAir). Two
+//! [`Accumulator`]s add the value and its square at the end of every sample cycle. Once the
+//! padding phase starts the sums are final, and the cleared variance is divided by
+//! `n^2 * 2^frac_bits` with a remainder range checked below the divisor, as in
+//! [`SmoothingAir`](crate::smoothing::SmoothingAir); the quotient is range checked below
+//! `max_variance`.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+#[cfg(feature = "prover")]
+use crate::fixed_point::div_floor;
+use crate::fixed_point::to_field;
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{Accumulator, IntegerColumn, PhaseSelector, RangeCheck};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`VarianceAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "variance/v1";
+
+/// A column may hold up to `2^SAMPLE_BITS` values
+pub const SAMPLE_BITS: usize = 20;
+
+/// Value magnitudes must fit into this many bits, which keeps the sum of squares far from the
+/// field modulus
+pub const VALUE_BITS: usize = 32;
+
+/// Largest number of fractional bits; with it the division of the cleared variance stays below
+/// the field modulus
+pub const MAX_FRAC_BITS: u32 = 20;
+
+/// Width of the proven variance and of its distance to the bound
+const VARIANCE_BITS: usize = 64;
+const DIGIT_BITS: usize = 2;
+
+/// Sponge tag of [`column_commitment`]; tags 1 to 18 are used elsewhere
+const COLUMN_TAG: u64 = 19;
+
+/// Phases of a variance trace: one cycle per value, then the padding cycles
+const SAMPLES: usize = 0;
+const PADDING: usize = 1;
+
+// Trace layout: sponge state, the value absorbed at the end of the cycle, its sign, the phase
+// flags, the running sums of the values and their squares, the magnitude and the quotient, then
+// the digits of the magnitude, the quotient, the remainder and the two slacks
+const SPONGE: usize = 0;
+const VALUE: usize = SPONGE + STATE_WIDTH;
+const SIGN: usize = VALUE + 1;
+const PHASES_START: usize = SIGN + 1;
+const SUM: usize = PHASES_START + 2;
+const SQUARES: usize = SUM + 1;
+const MAGNITUDE: usize = SQUARES + 1;
+const QUOTIENT: usize = MAGNITUDE + 1;
+
+struct Layout {
+    phases: PhaseSelector,
+    sponge: RescueGadget,
+    sum: Accumulator,
+    squares: Accumulator,
+    magnitude: IntegerColumn,
+    quotient: IntegerColumn,
+    remainder: RangeCheck,
+    divisor_slack: RangeCheck,
+    slack: RangeCheck,
+}
+
+impl Layout {
+    fn new(divisor: u128) -> Self {
+        let magnitude = IntegerColumn::new(MAGNITUDE, QUOTIENT + 1, VALUE_BITS, DIGIT_BITS);
+        let quotient = IntegerColumn::new(QUOTIENT, magnitude.next_column(), VARIANCE_BITS, DIGIT_BITS);
+        let remainder = RangeCheck::for_bound(quotient.next_column(), divisor);
+        let divisor_slack = RangeCheck::new(remainder.next_column(), remainder.num_bits());
+        let slack = RangeCheck::with_digit_bits(divisor_slack.next_column(), VARIANCE_BITS, DIGIT_BITS);
+        Self {
+            phases: PhaseSelector::new(PHASES_START, 2),
+            sponge: RescueGadget::new(SPONGE),
+            sum: Accumulator::sum(SUM),
+            squares: Accumulator::sum(SQUARES),
+            magnitude,
+            quotient,
+            remainder,
+            divisor_slack,
+            slack,
+        }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.slack.next_column()
+    }
+}
+
+/// `n^2 * 2^frac_bits`, the denominator of the fixed-point variance
+fn divisor(num_samples: usize, frac_bits: u32) -> u128 {
+    (num_samples as u128).pow(2) << frac_bits
+}
+
+/// Number of trace columns for `num_samples` values with `frac_bits` fractional bits
+pub fn trace_width(num_samples: usize, frac_bits: u32) -> usize {
+    Layout::new(divisor(num_samples, frac_bits)).trace_width()
+}
+
+/// Named columns of the trace for `num_samples` values with `frac_bits` fractional bits, see
+/// [`crate::layout`]
+pub fn layout(num_samples: usize, frac_bits: u32) -> LayoutDescriptor {
+    let layout = Layout::new(divisor(num_samples, frac_bits));
+    LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..VALUE)
+        .with_column("value", VALUE)
+        .with_column("sign", SIGN)
+        .with_columns("phases", PHASES_START..SUM)
+        .with_column("sum", layout.sum.column())
+        .with_column("squares", layout.squares.column())
+        .with_column("magnitude", MAGNITUDE)
+        .with_column("quotient", QUOTIENT)
+        .with_columns("magnitude_digits", QUOTIENT + 1..layout.magnitude.next_column())
+        .with_columns("quotient_digits", layout.magnitude.next_column()..layout.quotient.next_column())
+        .with_columns("remainder_digits", layout.quotient.next_column()..layout.remainder.next_column())
+        .with_columns("divisor_slack_digits", layout.remainder.next_column()..layout.divisor_slack.next_column())
+        .with_columns("slack_digits", layout.divisor_slack.next_column()..layout.slack.next_column())
+}
+
+/// Trace length for `num_samples` values: a cycle per value and one reading the final sums
+pub fn trace_length(num_samples: usize) -> usize {
+    ((num_samples + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+fn sponge_start(num_samples: usize, salt: BaseElement) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_samples, COLUMN_TAG);
+    state[0] = salt;
+    state
+}
+
+/// Commitment to the column `values`, blinded by `salt`
+pub fn column_commitment(values: &[i64], salt: BaseElement) -> BaseElement {
+    let mut state = sponge_start(values.len(), salt);
+    rescue::permute(&mut state);
+    for &value in values {
+        state[0] += to_field(value as i128);
+        rescue::permute(&mut state);
+    }
+    state[0]
+}
+
+/// `n * sum(x^2) - sum(x)^2`, the population variance cleared of its denominator `n^2`
+fn cleared_variance(values: &[i64]) -> u128 {
+    let sum: i128 = values.iter().map(|&value| value as i128).sum();
+    let squares: u128 = values.iter().map(|value| (value.unsigned_abs() as u128).pow(2)).sum();
+    values.len() as u128 * squares - sum.unsigned_abs().pow(2)
+}
+
+/// Population variance of `values`, rounded down, with `frac_bits` fractional bits like the values
+pub fn variance(values: &[i64], frac_bits: u32) -> u128 {
+    cleared_variance(values) / divisor(values.len(), frac_bits)
+}
+
+/// Reason a column cannot be proven to stay within a variance bound
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VarianceError {
+    /// The column holds no value
+    Empty,
+    TooManySamples(usize),
+    /// A value does not fit into [`VALUE_BITS`] bits
+    ValueOutOfRange(i64),
+    /// More fractional bits than [`MAX_FRAC_BITS`]
+    FracBits(u32),
+    /// The variance of the column exceeds the bound
+    AboveMaximum { variance: u128, max_variance: u64 },
+}
+
+impl fmt::Display for VarianceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarianceError::Empty => write!(f, "the column holds no values"),
+            VarianceError::TooManySamples(count) => write!(f, "{count} values exceed 2^{SAMPLE_BITS}"),
+            VarianceError::ValueOutOfRange(value) => write!(f, "value {value} does not fit into {VALUE_BITS} bits"),
+            VarianceError::FracBits(bits) => write!(f, "{bits} fractional bits exceed {MAX_FRAC_BITS}"),
+            VarianceError::AboveMaximum { variance, max_variance } => {
+                write!(f, "variance {variance} exceeds the bound {max_variance}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VarianceError {}
+
+/// Public inputs: the column commitment and size, the fixed-point scale and the bound
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VarianceInputs {
+    pub commitment: BaseElement,
+    pub num_samples: usize,
+    pub frac_bits: u32,
+    /// Largest accepted variance, with `frac_bits` fractional bits
+    pub max_variance: u64,
+}
+
+impl ToElements<BaseElement> for VarianceInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![
+            self.commitment,
+            BaseElement::from(self.num_samples as u64),
+            BaseElement::from(self.frac_bits),
+            BaseElement::from(self.max_variance),
+        ]
+    }
+}
+
+/// AIR proving that the variance of a committed column is at most a public bound
+pub struct VarianceAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: VarianceInputs,
+}
+
+impl Air for VarianceAir {
+    type BaseField = BaseElement;
+    type PublicInputs = VarianceInputs;
+
+    fn new(trace_info: TraceInfo, inputs: VarianceInputs, options: ProofOptions) -> Self {
+        assert!(inputs.num_samples > 0, "Variance claims need at least one value");
+        assert!(inputs.num_samples as u64 >> SAMPLE_BITS == 0, "Columns hold at most 2^{SAMPLE_BITS} values");
+        assert!(inputs.frac_bits <= MAX_FRAC_BITS, "Values carry at most {MAX_FRAC_BITS} fractional bits");
+        let layout = Layout::new(divisor(inputs.num_samples, inputs.frac_bits));
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(trace_length(inputs.num_samples), trace_info.length());
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs the value at the end of a cycle, which keeps it constant
+        //    within one (degree 1)
+        // 3. Boolean sign with value = (1 - 2 * sign) * magnitude (degree 2), the phase flags
+        //    (degree 2), and the magnitude digits
+        // 4. The sums add the value and its square at the end of sample cycles (degree 2 and 3,
+        //    the selector reads the periodic mask)
+        // 5. In the padding phase, n * squares - sum^2 = n^2 * 2^f * quotient + remainder (degree 3)
+        // 6. n^2 * 2^f - 1 - remainder and max_variance - quotient are the range checked
+        //    slacks (degree 1), and the digits of the quotient, remainder and slacks
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 1]);
+        degrees.extend(vec![TransitionConstraintDegree::new(2); 2]);
+        degrees.extend(layout.phases.constraint_degrees());
+        degrees.extend(layout.magnitude.constraint_degrees());
+        degrees.extend([periodic(2), periodic(3), TransitionConstraintDegree::new(3)]);
+        degrees.extend(vec![TransitionConstraintDegree::new(1); 2]);
+        degrees.extend(layout.quotient.constraint_degrees());
+        degrees.extend(layout.remainder.constraint_degrees());
+        degrees.extend(layout.divisor_slack.constraint_degrees());
+        degrees.extend(layout.slack.constraint_degrees());
+
+        let num_assertions = STATE_WIDTH + 4;
+        VarianceAir { context: AirContext::new(trace_info, degrees, num_assertions, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        let (absorb, rest) = rest.split_at_mut(STATE_WIDTH + 1);
+        absorb[0] = last * (next[SPONGE] - current[SPONGE] - current[VALUE]);
+        for lane in 1..STATE_WIDTH {
+            absorb[lane] = last * (next[SPONGE + lane] - current[SPONGE + lane]);
+        }
+        absorb[STATE_WIDTH] = mask * (next[VALUE] - current[VALUE]);
+
+        let (value, sign, magnitude) = (current[VALUE], current[SIGN], current[MAGNITUDE]);
+        rest[0] = sign * (sign - E::ONE);
+        rest[1] = value - (E::ONE - sign.double()) * magnitude;
+        let phases = &layout.phases;
+        phases.evaluate(current, next, &mut rest[2..]);
+        let rest = &mut rest[2 + phases.num_constraints()..];
+        layout.magnitude.evaluate(current, rest);
+        let rest = &mut rest[layout.magnitude.num_constraints()..];
+
+        let selector = phases.gate(current, &[SAMPLES], last);
+        layout.sum.evaluate(current, next, value, selector, &mut rest[..1]);
+        layout.squares.evaluate(current, next, value * value, selector, &mut rest[1..2]);
+
+        let inputs = &self.inputs;
+        let num_samples = E::from(BaseElement::from(inputs.num_samples as u64));
+        let divisor = E::from(BaseElement::new(divisor(inputs.num_samples, inputs.frac_bits)));
+        let (sum, quotient) = (current[SUM], current[QUOTIENT]);
+        let remainder = layout.remainder.recompose(current);
+        let cleared = num_samples * current[SQUARES] - sum * sum;
+        rest[2] = phases.gate(current, &[PADDING], cleared - divisor * quotient - remainder);
+        rest[3] = divisor - E::ONE - remainder - layout.divisor_slack.recompose(current);
+        rest[4] = E::from(BaseElement::from(inputs.max_variance)) - quotient - layout.slack.recompose(current);
+
+        let rest = &mut rest[5..];
+        layout.quotient.evaluate(current, rest);
+        let rest = &mut rest[layout.quotient.num_constraints()..];
+        layout.remainder.evaluate(current, rest);
+        let rest = &mut rest[layout.remainder.num_constraints()..];
+        layout.divisor_slack.evaluate(current, rest);
+        layout.slack.evaluate(current, &mut rest[layout.divisor_slack.num_constraints()..]);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_samples = self.inputs.num_samples;
+        // the salt in the first lane stays private
+        let start = sponge_start(num_samples, BaseElement::ZERO);
+        let mut assertions: Vec<_> = (1..STATE_WIDTH).map(|i| Assertion::single(SPONGE + i, 0, start[i])).collect();
+        assertions.push(Assertion::single(SPONGE, (num_samples + 1) * CYCLE_LENGTH - 1, self.inputs.commitment));
+        let phases = &self.layout.phases;
+        assertions.push(Assertion::single(phases.column(SAMPLES), num_samples * CYCLE_LENGTH - 1, BaseElement::ONE));
+        assertions.push(Assertion::single(phases.column(PADDING), num_samples * CYCLE_LENGTH, BaseElement::ONE));
+        assertions.push(self.layout.sum.assertion(0, BaseElement::ZERO));
+        assertions.push(self.layout.squares.assertion(0, BaseElement::ZERO));
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving the variance of `values` is at most `max_variance`, with
+/// the inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_variance_trace(
+    values: &[i64],
+    salt: BaseElement,
+    frac_bits: u32,
+    max_variance: u64,
+) -> Result<(TraceTable<BaseElement>, VarianceInputs), VarianceError> {
+    if values.is_empty() {
+        return Err(VarianceError::Empty);
+    }
+    if values.len() as u64 >> SAMPLE_BITS != 0 {
+        return Err(VarianceError::TooManySamples(values.len()));
+    }
+    if let Some(&value) = values.iter().find(|value| value.unsigned_abs() >> VALUE_BITS != 0) {
+        return Err(VarianceError::ValueOutOfRange(value));
+    }
+    if frac_bits > MAX_FRAC_BITS {
+        return Err(VarianceError::FracBits(frac_bits));
+    }
+    let variance = variance(values, frac_bits);
+    if variance > max_variance as u128 {
+        return Err(VarianceError::AboveMaximum { variance, max_variance });
+    }
+
+    let divisor = divisor(values.len(), frac_bits);
+    let (quotient, remainder) = div_floor(cleared_variance(values) as i128, divisor as i128);
+    let layout = Layout::new(divisor);
+    let length = trace_length(values.len());
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut state = sponge_start(values.len(), salt);
+    let (mut sum, mut squares) = (0i128, 0i128);
+    for i in 0..length {
+        let (cycle, step) = (i / CYCLE_LENGTH, i % CYCLE_LENGTH);
+        // padding cycles alternate between 1 and -1, so value and sign never stay constant
+        let value = values.get(cycle).map_or(1 - 2 * (cycle as i128 % 2), |&value| value as i128);
+        let active = cycle < values.len();
+        row[VALUE] = to_field(value);
+        row[SIGN] = BaseElement::from((value < 0) as u8);
+        layout.phases.fill(&mut row, if active { SAMPLES } else { PADDING });
+        layout.magnitude.fill(&mut row, value.unsigned_abs() as u64).expect("Values are range checked");
+        layout.sponge.write(&mut row, &state);
+
+        let absorbs = step == CYCLE_LENGTH - 1;
+        sum = layout.sum.fill(&mut row, sum, value, active && absorbs).expect("Sums are unbounded");
+        squares = layout.squares.fill(&mut row, squares, value * value, active && absorbs).expect("Sums are unbounded");
+        if i == length - 1 {
+            row[SIGN] = BaseElement::new(2);
+            layout.phases.fill_exempt_row(&mut row, PADDING);
+            layout.magnitude.fill_exempt_row(&mut row, value.unsigned_abs() as u64);
+            layout.quotient.fill_exempt_row(&mut row, quotient as u64);
+            layout.remainder.fill_exempt_row(&mut row);
+            layout.divisor_slack.fill_exempt_row(&mut row);
+            layout.slack.fill_exempt_row(&mut row);
+        } else {
+            // the division is only checked once the sums are final
+            let (quotient, remainder) = if active { (0, 0) } else { (quotient, remainder) };
+            layout.quotient.fill(&mut row, quotient as u64).expect("Quotient is at most the bound");
+            layout.remainder.fill(&mut row, remainder).expect("Euclidean remainder is below the divisor");
+            layout.divisor_slack.fill(&mut row, divisor as i128 - 1 - remainder).expect("Checked above");
+            layout.slack.fill(&mut row, max_variance as i128 - quotient).expect("Checked above");
+        }
+
+        if step < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, step);
+        } else {
+            state[0] += row[VALUE];
+        }
+        trace.update_row(i, &row);
+    }
+
+    let inputs = VarianceInputs {
+        commitment: column_commitment(values, salt),
+        num_samples: values.len(),
+        frac_bits,
+        max_variance,
+    };
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_variance_proof() {
+        // quarters: the cleared variance is 8 * 173 - 7^2 = 1335, divided by 8^2 * 4 it is 5
+        let values = [3, -1, 4, 1, -5, 9, 2, -6];
+        assert_eq!(variance(&values, 2), 5);
+        let salt = BaseElement::new(0x7a2);
+        let (trace, inputs) = build_variance_trace(&values, salt, 2, 5).unwrap();
+        crate::air_test!(VarianceAir, inputs)
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+
+        let proof = prove::<VarianceAir>(trace, inputs, ProverConfig::default().to_proof_options()).unwrap();
+        let acceptable = default_acceptable_options();
+        let tighter = VarianceInputs { max_variance: 4, ..inputs };
+        assert!(verify::<VarianceAir>(proof.clone(), tighter, &acceptable).is_err());
+        let other_column = VarianceInputs { commitment: column_commitment(&values[1..], salt), ..inputs };
+        assert!(verify::<VarianceAir>(proof.clone(), other_column, &acceptable).is_err());
+        let result = verify::<VarianceAir>(proof, inputs, &acceptable);
+        assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_rejects_columns_above_the_bound() {
+        let salt = BaseElement::ONE;
+        let values = [3, -1, 4, 1, -5, 9, 2, -6];
+        let above = build_variance_trace(&values, salt, 0, 19).unwrap_err();
+        assert_eq!(above, VarianceError::AboveMaximum { variance: 20, max_variance: 19 });
+        // a single value has no variance
+        let (trace, inputs) = build_variance_trace(&[-7], salt, 0, 0).unwrap();
+        crate::air_test!(VarianceAir, inputs).with_trace(&trace).expect_all_hold().expect_assertions_hold();
+
+        assert_eq!(build_variance_trace(&[], salt, 0, 1).unwrap_err(), VarianceError::Empty);
+        assert_eq!(build_variance_trace(&[1 << 32], salt, 0, 1).unwrap_err(), VarianceError::ValueOutOfRange(1 << 32));
+        assert_eq!(build_variance_trace(&[1], salt, 21, 1).unwrap_err(), VarianceError::FracBits(21));
+    }
+}
@@ -0,0 +1,577 @@
+//! Weighted least-squares fits over committed samples.
+//!
+//! [`WeightedFitAir`] proves that a public line is the weighted least-squares fit of the
+//! samples hashed into a public [`samples_commitment`], where every sample carries a
+//! non-negative reliability weight. The weights are either public, listed in the inputs, or
+//! committed next to the samples ([`WeightMode`]). The fit is published exactly, as
+//! `slope / denominator` and `intercept / denominator`, so it satisfies the weighted normal
+//! equations without any rounding:
+//!
+//! ```text
+//! D = Σw * Σwx² - (Σwx)²
+//! D * Σwy  = slope * Σwx  + intercept * Σw
+//! D * Σwxy = slope * Σwx² + intercept * Σwx
+//! ```
+//!
+//! Real-valued weights are quantized with [`quantize_weights`]. Every term of the equations
+//! is quadratic in the weights, so their scale cancels and the number of fractional bits only
+//! sets their resolution; it is not part of the claim.
+//!
+//! The trace has one [`CYCLE_LENGTH`]-row cycle per sample plus one that finishes the sponge,
+//! laid out like [`VarianceAir`](crate::variance::VarianceAir): the sponge absorbs `x` and `y`
+//! (and the weight when it is committed) at the end of a cycle, next to the range checked
+//! magnitudes of `x` and `y` and the range checked weight. Five [`Accumulator`]s add `w`,
+//! `wx`, `wy`, `wx²` and `wxy` at the end of every sample cycle. Once the padding phase starts
+//! the sums are final and the three equations are checked. The sums stay far below the field
+//! modulus, so the first equation holds over the integers; with `D` non-zero the other two
+//! then pin the slope and intercept numerators.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::fixed_point::{quantize, to_field};
+use crate::gadgets::rescue::{self, RescueGadget, CYCLE_LENGTH, STATE_WIDTH};
+use crate::gadgets::{Accumulator, IntegerColumn, PhaseSelector};
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`WeightedFitAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "weighted-fit/v1";
+
+/// A fit may cover up to `2^SAMPLE_BITS` samples
+pub const SAMPLE_BITS: usize = 10;
+
+/// Magnitudes of `x` and `y` must fit into this many bits
+pub const VALUE_BITS: usize = 16;
+
+/// Quantized weights must fit into this many bits; with [`SAMPLE_BITS`] and [`VALUE_BITS`] it
+/// keeps `Σw * Σwx²` below `2^84`
+pub const WEIGHT_BITS: usize = 16;
+
+const DIGIT_BITS: usize = 2;
+
+/// Sponge tag of [`samples_commitment`]; tags 1 to 21 are used elsewhere
+const SAMPLES_TAG: u64 = 22;
+
+/// Phases of a weighted fit trace: one cycle per sample, then the padding cycles
+const SAMPLES: usize = 0;
+const PADDING: usize = 1;
+
+// Trace layout: sponge state, the sample absorbed at the end of the cycle, its weight and the
+// product `w * x`, the signs of x and y, the phase flags, the five running sums and the
+// magnitudes of x and y, then the digits of the weight and of the two magnitudes
+const SPONGE: usize = 0;
+const X: usize = SPONGE + STATE_WIDTH;
+const Y: usize = X + 1;
+const WEIGHT: usize = Y + 1;
+const WX: usize = WEIGHT + 1;
+const X_SIGN: usize = WX + 1;
+const Y_SIGN: usize = X_SIGN + 1;
+const PHASES_START: usize = Y_SIGN + 1;
+const SUM_W: usize = PHASES_START + 2;
+const SUM_WX: usize = SUM_W + 1;
+const SUM_WY: usize = SUM_WX + 1;
+const SUM_WXX: usize = SUM_WY + 1;
+const SUM_WXY: usize = SUM_WXX + 1;
+const X_MAGNITUDE: usize = SUM_WXY + 1;
+const Y_MAGNITUDE: usize = X_MAGNITUDE + 1;
+
+struct Layout {
+    phases: PhaseSelector,
+    sponge: RescueGadget,
+    sums: [Accumulator; 5],
+    weight: IntegerColumn,
+    x_magnitude: IntegerColumn,
+    y_magnitude: IntegerColumn,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let weight = IntegerColumn::new(WEIGHT, Y_MAGNITUDE + 1, WEIGHT_BITS, DIGIT_BITS);
+        let x_magnitude = IntegerColumn::new(X_MAGNITUDE, weight.next_column(), VALUE_BITS, DIGIT_BITS);
+        let y_magnitude = IntegerColumn::new(Y_MAGNITUDE, x_magnitude.next_column(), VALUE_BITS, DIGIT_BITS);
+        Self {
+            phases: PhaseSelector::new(PHASES_START, 2),
+            sponge: RescueGadget::new(SPONGE),
+            sums: [SUM_W, SUM_WX, SUM_WY, SUM_WXX, SUM_WXY].map(Accumulator::sum),
+            weight,
+            x_magnitude,
+            y_magnitude,
+        }
+    }
+
+    fn trace_width(&self) -> usize {
+        self.y_magnitude.next_column()
+    }
+}
+
+/// Number of trace columns
+pub fn trace_width() -> usize {
+    Layout::new().trace_width()
+}
+
+/// Named columns of the trace, see [`crate::layout`]
+pub fn layout() -> LayoutDescriptor {
+    let layout = Layout::new();
+    let names = ["sum_w", "sum_wx", "sum_wy", "sum_wxx", "sum_wxy"];
+    let descriptor = LayoutDescriptor::new(AIR_ID)
+        .with_columns("sponge", SPONGE..X)
+        .with_column("x", X)
+        .with_column("y", Y)
+        .with_column("weight", WEIGHT)
+        .with_column("wx", WX)
+        .with_column("x_sign", X_SIGN)
+        .with_column("y_sign", Y_SIGN)
+        .with_columns("phases", PHASES_START..SUM_W)
+        .with_column("x_magnitude", X_MAGNITUDE)
+        .with_column("y_magnitude", Y_MAGNITUDE)
+        .with_columns("weight_digits", Y_MAGNITUDE + 1..layout.weight.next_column())
+        .with_columns("x_magnitude_digits", layout.weight.next_column()..layout.x_magnitude.next_column())
+        .with_columns("y_magnitude_digits", layout.x_magnitude.next_column()..layout.y_magnitude.next_column());
+    let sums = names.iter().zip(&layout.sums);
+    sums.fold(descriptor, |descriptor, (name, sum)| descriptor.with_column(name, sum.column()))
+}
+
+/// Trace length for `num_samples` samples: a cycle per sample and one reading the final sums
+pub fn trace_length(num_samples: usize) -> usize {
+    ((num_samples + 1) * CYCLE_LENGTH).next_power_of_two()
+}
+
+/// A sample with its quantized reliability weight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeightedSample {
+    pub x: i64,
+    pub y: i64,
+    pub weight: u64,
+}
+
+impl WeightedSample {
+    pub fn new(x: i64, y: i64, weight: u64) -> Self {
+        Self { x, y, weight }
+    }
+}
+
+/// Where the verifier learns the weights from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeightMode {
+    /// The weights are listed in the public inputs and left out of the commitment
+    Public,
+    /// The weights are hashed into the commitment with the samples and stay private
+    Committed,
+}
+
+impl WeightMode {
+    fn flag(self) -> BaseElement {
+        BaseElement::from((self == WeightMode::Committed) as u8)
+    }
+}
+
+fn sponge_start(num_samples: usize, salt: BaseElement) -> [BaseElement; STATE_WIDTH] {
+    let mut state = rescue::sponge_iv(num_samples, SAMPLES_TAG);
+    state[0] = salt;
+    state
+}
+
+/// Commitment to `samples`, blinded by `salt`; the weights are part of it only in
+/// [`WeightMode::Committed`]
+pub fn samples_commitment(samples: &[WeightedSample], mode: WeightMode, salt: BaseElement) -> BaseElement {
+    let mut state = sponge_start(samples.len(), salt);
+    rescue::permute(&mut state);
+    for sample in samples {
+        state[0] += to_field(sample.x as i128);
+        state[1] += to_field(sample.y as i128);
+        state[2] += mode.flag() * BaseElement::from(sample.weight);
+        rescue::permute(&mut state);
+    }
+    state[0]
+}
+
+/// Quantizes non-negative real weights to `frac_bits` fractional bits
+pub fn quantize_weights(weights: &[f64], frac_bits: u32) -> Result<Vec<u64>, FitError> {
+    weights
+        .iter()
+        .enumerate()
+        .map(|(index, &weight)| {
+            let raw = quantize(weight, frac_bits);
+            if !weight.is_finite() || raw < 0 || raw >> WEIGHT_BITS != 0 {
+                return Err(FitError::InvalidWeight(index));
+            }
+            Ok(raw as u64)
+        })
+        .collect()
+}
+
+/// Exact weighted least-squares line `y = (slope * x + intercept) / denominator`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeightedFit {
+    pub slope: i128,
+    pub intercept: i128,
+    /// `Σw * Σwx² - (Σwx)²`, positive unless the weighted x values are all equal
+    pub denominator: i128,
+}
+
+impl WeightedFit {
+    pub fn slope(&self) -> f64 {
+        self.slope as f64 / self.denominator as f64
+    }
+
+    pub fn intercept(&self) -> f64 {
+        self.intercept as f64 / self.denominator as f64
+    }
+}
+
+/// `Σw`, `Σwx`, `Σwy`, `Σwx²` and `Σwxy`
+fn normal_sums(samples: &[WeightedSample]) -> [i128; 5] {
+    samples.iter().fold([0; 5], |[w, wx, wy, wxx, wxy], sample| {
+        let (x, y, weight) = (sample.x as i128, sample.y as i128, sample.weight as i128);
+        [w + weight, wx + weight * x, wy + weight * y, wxx + weight * x * x, wxy + weight * x * y]
+    })
+}
+
+/// Solves the weighted normal equations of `samples`
+pub fn weighted_fit(samples: &[WeightedSample]) -> Result<WeightedFit, FitError> {
+    let [w, wx, wy, wxx, wxy] = normal_sums(samples);
+    let denominator = w * wxx - wx * wx;
+    if denominator == 0 {
+        return Err(FitError::Degenerate);
+    }
+    Ok(WeightedFit { slope: w * wxy - wx * wy, intercept: wxx * wy - wx * wxy, denominator })
+}
+
+/// Reason a weighted fit cannot be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FitError {
+    /// There are no samples
+    Empty,
+    TooManySamples(usize),
+    /// A coordinate does not fit into [`VALUE_BITS`] bits
+    ValueOutOfRange(i64),
+    /// The weight at this index is negative, not finite or does not fit into [`WEIGHT_BITS`] bits
+    InvalidWeight(usize),
+    /// The weighted x values are all equal (or all weights are zero), so no line is determined
+    Degenerate,
+}
+
+impl fmt::Display for FitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FitError::Empty => write!(f, "there are no samples"),
+            FitError::TooManySamples(count) => write!(f, "{count} samples exceed 2^{SAMPLE_BITS}"),
+            FitError::ValueOutOfRange(value) => write!(f, "value {value} does not fit into {VALUE_BITS} bits"),
+            FitError::InvalidWeight(index) => write!(f, "weight {index} is not a {WEIGHT_BITS}-bit non-negative value"),
+            FitError::Degenerate => write!(f, "the weighted samples do not determine a line"),
+        }
+    }
+}
+
+impl std::error::Error for FitError {}
+
+/// Public inputs: the samples commitment and size, the public weights if any and the fit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedFitInputs {
+    pub commitment: BaseElement,
+    pub num_samples: usize,
+    /// One weight per sample in [`WeightMode::Public`], `None` when they are committed
+    pub weights: Option<Vec<u64>>,
+    pub fit: WeightedFit,
+}
+
+impl WeightedFitInputs {
+    pub fn mode(&self) -> WeightMode {
+        if self.weights.is_some() { WeightMode::Public } else { WeightMode::Committed }
+    }
+}
+
+impl ToElements<BaseElement> for WeightedFitInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![self.commitment, BaseElement::from(self.num_samples as u64), self.mode().flag()];
+        elements.extend(self.weights.iter().flatten().map(|&weight| BaseElement::from(weight)));
+        elements.extend([self.fit.slope, self.fit.intercept, self.fit.denominator].map(to_field));
+        elements
+    }
+}
+
+/// AIR proving that a public line is the weighted least-squares fit of committed samples
+pub struct WeightedFitAir {
+    context: AirContext<BaseElement>,
+    layout: Layout,
+    inputs: WeightedFitInputs,
+}
+
+impl Air for WeightedFitAir {
+    type BaseField = BaseElement;
+    type PublicInputs = WeightedFitInputs;
+
+    fn new(trace_info: TraceInfo, inputs: WeightedFitInputs, options: ProofOptions) -> Self {
+        assert!(inputs.num_samples > 0, "Weighted fits need at least one sample");
+        assert!(inputs.num_samples as u64 >> SAMPLE_BITS == 0, "Fits cover at most 2^{SAMPLE_BITS} samples");
+        if let Some(weights) = &inputs.weights {
+            assert_eq!(weights.len(), inputs.num_samples, "Public weights must match the samples");
+        }
+        assert!(inputs.fit.denominator > 0, "The fit denominator must be positive");
+        let layout = Layout::new();
+        assert_eq!(layout.trace_width(), trace_info.width());
+        assert_eq!(trace_length(inputs.num_samples), trace_info.length());
+
+        let periodic = |base| TransitionConstraintDegree::with_cycles(base, vec![CYCLE_LENGTH]);
+
+        // Constraints:
+        // 1. Rescue rounds of the sponge (degree 3)
+        // 2. The sponge absorbs x, y and, when committed, the weight at the end of a cycle,
+        //    which keeps the three constant within one (degree 1)
+        // 3. Boolean signs with x = (1 - 2 * sign) * |x| and likewise for y, and wx = w * x
+        //    (degree 2); the phase flags (degree 2); the digits of the weight and of both
+        //    magnitudes
+        // 4. The sums add w, wx, wy, wx * x and wx * y at the end of sample cycles (degree 2 and
+        //    3, the selector reads the periodic mask)
+        // 5. In the padding phase, the three normal equations over the final sums (degree 3, then 2
+        //    for the two linear in the sums)
+        let mut degrees = layout.sponge.constraint_degrees();
+        degrees.extend(vec![periodic(1); STATE_WIDTH + 3]);
+        degrees.extend(vec![TransitionConstraintDegree::new(2); 5]);
+        degrees.extend(layout.phases.constraint_degrees());
+        degrees.extend(layout.weight.constraint_degrees());
+        degrees.extend(layout.x_magnitude.constraint_degrees());
+        degrees.extend(layout.y_magnitude.constraint_degrees());
+        degrees.extend([periodic(2), periodic(2), periodic(3), periodic(3), periodic(3)]);
+        degrees.extend([3, 2, 2].map(TransitionConstraintDegree::new));
+
+        let num_weights = inputs.weights.as_ref().map_or(0, Vec::len);
+        let num_assertions = STATE_WIDTH + 7 + num_weights;
+        WeightedFitAir { context: AirContext::new(trace_info, degrees, num_assertions, options), layout, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+        let layout = &self.layout;
+        let (mask, last) = (periodic_values[0], E::ONE - periodic_values[0]);
+        let committed = E::from(self.inputs.mode().flag());
+
+        let (sponge_result, rest) = result.split_at_mut(STATE_WIDTH);
+        layout.sponge.evaluate(current, next, periodic_values, sponge_result);
+
+        let (absorb, rest) = rest.split_at_mut(STATE_WIDTH + 3);
+        let (x, y, weight) = (current[X], current[Y], current[WEIGHT]);
+        absorb[0] = last * (next[SPONGE] - current[SPONGE] - x);
+        absorb[1] = last * (next[SPONGE + 1] - current[SPONGE + 1] - y);
+        absorb[2] = last * (next[SPONGE + 2] - current[SPONGE + 2] - committed * weight);
+        absorb[3] = last * (next[SPONGE + 3] - current[SPONGE + 3]);
+        for (i, column) in [X, Y, WEIGHT].into_iter().enumerate() {
+            absorb[STATE_WIDTH + i] = mask * (next[column] - current[column]);
+        }
+
+        let (x_sign, y_sign) = (current[X_SIGN], current[Y_SIGN]);
+        rest[0] = x_sign * (x_sign - E::ONE);
+        rest[1] = x - (E::ONE - x_sign.double()) * current[X_MAGNITUDE];
+        rest[2] = y_sign * (y_sign - E::ONE);
+        rest[3] = y - (E::ONE - y_sign.double()) * current[Y_MAGNITUDE];
+        rest[4] = current[WX] - weight * x;
+        let phases = &layout.phases;
+        phases.evaluate(current, next, &mut rest[5..]);
+        let mut rest = &mut rest[5 + phases.num_constraints()..];
+        for column in [&layout.weight, &layout.x_magnitude, &layout.y_magnitude] {
+            column.evaluate(current, rest);
+            rest = &mut rest[column.num_constraints()..];
+        }
+
+        let selector = phases.gate(current, &[SAMPLES], last);
+        let wx = current[WX];
+        let terms = [weight, wx, weight * y, wx * x, wx * y];
+        for (i, (sum, term)) in layout.sums.iter().zip(terms).enumerate() {
+            sum.evaluate(current, next, term, selector, &mut rest[i..i + 1]);
+        }
+
+        let fit = &self.inputs.fit;
+        let [slope, intercept, denominator] = [fit.slope, fit.intercept, fit.denominator].map(|v| E::from(to_field(v)));
+        let [w, wx, wy, wxx, wxy] = [SUM_W, SUM_WX, SUM_WY, SUM_WXX, SUM_WXY].map(|column| current[column]);
+        rest[5] = phases.gate(current, &[PADDING], denominator - w * wxx + wx * wx);
+        rest[6] = phases.gate(current, &[PADDING], denominator * wy - slope * wx - intercept * w);
+        rest[7] = phases.gate(current, &[PADDING], denominator * wxy - slope * wxx - intercept * wx);
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
+        rescue::periodic_columns()
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let num_samples = self.inputs.num_samples;
+        // the salt in the first lane stays private
+        let start = sponge_start(num_samples, BaseElement::ZERO);
+        let mut assertions: Vec<_> = (1..STATE_WIDTH).map(|i| Assertion::single(SPONGE + i, 0, start[i])).collect();
+        assertions.push(Assertion::single(SPONGE, (num_samples + 1) * CYCLE_LENGTH - 1, self.inputs.commitment));
+        let phases = &self.layout.phases;
+        assertions.push(Assertion::single(phases.column(SAMPLES), num_samples * CYCLE_LENGTH - 1, BaseElement::ONE));
+        assertions.push(Assertion::single(phases.column(PADDING), num_samples * CYCLE_LENGTH, BaseElement::ONE));
+        assertions.extend(self.layout.sums.iter().map(|sum| sum.assertion(0, BaseElement::ZERO)));
+        for (cycle, &weight) in self.inputs.weights.iter().flatten().enumerate() {
+            assertions.push(Assertion::single(WEIGHT, cycle * CYCLE_LENGTH, BaseElement::from(weight)));
+        }
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace proving the weighted least-squares fit of `samples`, with the
+/// inputs it proves
+#[cfg(feature = "prover")]
+pub fn build_weighted_fit_trace(
+    samples: &[WeightedSample],
+    mode: WeightMode,
+    salt: BaseElement,
+) -> Result<(TraceTable<BaseElement>, WeightedFitInputs), FitError> {
+    if samples.is_empty() {
+        return Err(FitError::Empty);
+    }
+    if samples.len() as u64 >> SAMPLE_BITS != 0 {
+        return Err(FitError::TooManySamples(samples.len()));
+    }
+    let values = samples.iter().flat_map(|sample| [sample.x, sample.y]);
+    if let Some(value) = values.into_iter().find(|value| value.unsigned_abs() >> VALUE_BITS != 0) {
+        return Err(FitError::ValueOutOfRange(value));
+    }
+    if let Some(index) = samples.iter().position(|sample| sample.weight >> WEIGHT_BITS != 0) {
+        return Err(FitError::InvalidWeight(index));
+    }
+    let fit = weighted_fit(samples)?;
+
+    let layout = Layout::new();
+    let length = trace_length(samples.len());
+    let mut trace = TraceTable::new(layout.trace_width(), length);
+    let mut row = vec![BaseElement::ZERO; layout.trace_width()];
+    let mut state = sponge_start(samples.len(), salt);
+    let mut sums = [0i128; 5];
+    for i in 0..length {
+        let (cycle, step) = (i / CYCLE_LENGTH, i % CYCLE_LENGTH);
+        // padding cycles alternate between 1 and -1, so no column stays constant
+        let filler = 1 - 2 * (cycle as i64 % 2);
+        let padding = WeightedSample::new(filler, -filler, (cycle % 2) as u64);
+        let sample = samples.get(cycle).copied().unwrap_or(padding);
+        let active = cycle < samples.len();
+        let (x, y, weight) = (sample.x as i128, sample.y as i128, sample.weight as i128);
+        row[X] = to_field(x);
+        row[Y] = to_field(y);
+        row[WX] = to_field(weight * x);
+        row[X_SIGN] = BaseElement::from((x < 0) as u8);
+        row[Y_SIGN] = BaseElement::from((y < 0) as u8);
+        layout.phases.fill(&mut row, if active { SAMPLES } else { PADDING });
+        layout.sponge.write(&mut row, &state);
+
+        let absorbs = active && step == CYCLE_LENGTH - 1;
+        let terms = [weight, weight * x, weight * y, weight * x * x, weight * x * y];
+        for ((sum, accumulator), term) in sums.iter_mut().zip(&layout.sums).zip(terms) {
+            *sum = accumulator.fill(&mut row, *sum, term, absorbs).expect("Sums are unbounded");
+        }
+        if i == length - 1 {
+            row[X_SIGN] = BaseElement::new(2);
+            layout.phases.fill_exempt_row(&mut row, PADDING);
+            layout.weight.fill_exempt_row(&mut row, sample.weight);
+            layout.x_magnitude.fill_exempt_row(&mut row, x.unsigned_abs() as u64);
+            layout.y_magnitude.fill_exempt_row(&mut row, y.unsigned_abs() as u64);
+        } else {
+            layout.weight.fill(&mut row, sample.weight).expect("Weights are range checked");
+            layout.x_magnitude.fill(&mut row, x.unsigned_abs() as u64).expect("Values are range checked");
+            layout.y_magnitude.fill(&mut row, y.unsigned_abs() as u64).expect("Values are range checked");
+        }
+
+        if step < rescue::NUM_ROUNDS {
+            rescue::apply_round(&mut state, step);
+        } else {
+            state[0] += row[X];
+            state[1] += row[Y];
+            state[2] += mode.flag() * row[WEIGHT];
+        }
+        trace.update_row(i, &row);
+    }
+
+    let weights = (mode == WeightMode::Public).then(|| samples.iter().map(|sample| sample.weight).collect());
+    let inputs = WeightedFitInputs {
+        commitment: samples_commitment(samples, mode, salt),
+        num_samples: samples.len(),
+        weights,
+        fit,
+    };
+    Ok((trace, inputs))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    fn samples() -> Vec<WeightedSample> {
+        // the unreliable outlier at x = 3 barely moves the fit
+        let weights = quantize_weights(&[1.0, 1.0, 0.5, 0.0625, 1.0], 4).unwrap();
+        [(0, 1), (1, 3), (2, 5), (3, 40), (4, 9)]
+            .into_iter()
+            .zip(weights)
+            .map(|((x, y), weight)| WeightedSample::new(x, y, weight))
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_fit_proof() {
+        let samples = samples();
+        let fit = weighted_fit(&samples).unwrap();
+        assert!((fit.slope() - 2.0).abs() < 0.5 && (fit.intercept() - 1.0).abs() < 1.0, "{fit:?}");
+        let salt = BaseElement::new(0x3c1);
+        for mode in [WeightMode::Public, WeightMode::Committed] {
+            let (trace, inputs) = build_weighted_fit_trace(&samples, mode, salt).unwrap();
+            assert_eq!(inputs.mode(), mode);
+            crate::air_test!(WeightedFitAir, inputs.clone())
+                .with_trace(&trace)
+                .expect_all_hold()
+                .expect_assertions_hold()
+                .expect_full_coverage();
+
+            let options = ProverConfig::default().to_proof_options();
+            let proof = prove::<WeightedFitAir>(trace, inputs.clone(), options).unwrap();
+            let acceptable = default_acceptable_options();
+            let unweighted = WeightedFit { slope: fit.slope + 1, ..fit };
+            let other_fit = WeightedFitInputs { fit: unweighted, ..inputs.clone() };
+            assert!(verify::<WeightedFitAir>(proof.clone(), other_fit, &acceptable).is_err());
+            if let Some(mut weights) = inputs.weights.clone() {
+                // claiming the outlier with full weight breaks the weight assertions
+                weights[3] = 16;
+                let heavier = WeightedFitInputs { weights: Some(weights), ..inputs.clone() };
+                assert!(verify::<WeightedFitAir>(proof.clone(), heavier, &acceptable).is_err());
+            }
+            let result = verify::<WeightedFitAir>(proof, inputs, &acceptable);
+            assert!(result.is_ok(), "Proof verification failed: {:?}", result.err());
+        }
+    }
+
+    #[test]
+    fn test_public_weights_and_quantization() {
+        let samples = samples();
+        let salt = BaseElement::ONE;
+        let (_, inputs) = build_weighted_fit_trace(&samples, WeightMode::Public, salt).unwrap();
+        assert_eq!(inputs.weights, Some(vec![16, 16, 8, 1, 16]));
+        // public weights stay out of the commitment, committed ones are bound into it
+        let committed = samples_commitment(&samples, WeightMode::Committed, salt);
+        assert_ne!(inputs.commitment, committed);
+
+        assert_eq!(quantize_weights(&[0.5, -0.25], 4), Err(FitError::InvalidWeight(1)));
+        assert_eq!(quantize_weights(&[f64::NAN], 4), Err(FitError::InvalidWeight(0)));
+        assert_eq!(quantize_weights(&[4096.0], 4), Err(FitError::InvalidWeight(0)));
+        let flat = [WeightedSample::new(2, 1, 3), WeightedSample::new(2, 5, 1)];
+        assert_eq!(build_weighted_fit_trace(&flat, WeightMode::Public, salt).unwrap_err(), FitError::Degenerate);
+        let far = [WeightedSample::new(1 << 16, 0, 1)];
+        let error = build_weighted_fit_trace(&far, WeightMode::Public, salt).unwrap_err();
+        assert_eq!(error, FitError::ValueOutOfRange(1 << 16));
+    }
+}
@@ -0,0 +1,295 @@
+//! Weighted sums over public prices with private weights, e.g. a portfolio valuation.
+//!
+//! The claim is `V = Σ w_i * p_i` where the weights are fixed-point numbers with `frac_bits`
+//! fractional bits that sum to exactly one, i.e. `Σ w_i = 2^frac_bits`. Prices are public
+//! integers (quantized by the caller) and `V` carries the weights' `frac_bits` on top of the
+//! price scale. Only the prices and `V` are revealed; every weight is range checked to
+//! `0..=1` so a portfolio cannot offset a negative position against the others.
+
+use core::fmt;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+#[cfg(feature = "prover")]
+use winterfell::TraceTable;
+
+use crate::config::padded_trace_length;
+use crate::gadgets::RangeCheck;
+use crate::layout::LayoutDescriptor;
+
+/// Identifier of [`WeightedSumAir`] recorded in audit logs and envelopes
+pub const AIR_ID: &str = "weighted-sum/v1";
+
+// Trace layout: price, weight, running value and running weight sum before the row, then the
+// bits of the weight
+const PRICE: usize = 0;
+const WEIGHT: usize = 1;
+const VALUE: usize = 2;
+const WEIGHT_SUM: usize = 3;
+const BITS_START: usize = 4;
+
+/// Fractional bits the weights support
+pub const FRAC_BITS: core::ops::RangeInclusive<u32> = 1..=62;
+
+/// Weights above one are rejected by the range check, so it needs one integer bit
+fn weight_range(frac_bits: u32) -> Result<RangeCheck, WeightedSumError> {
+    if !FRAC_BITS.contains(&frac_bits) {
+        return Err(WeightedSumError::FracBits(frac_bits));
+    }
+    Ok(RangeCheck::new(BITS_START, frac_bits as usize + 1))
+}
+
+/// Trace length for `num_assets` assets: one row per asset and the final accumulator row,
+/// padded to at least `min_trace_length` rows
+pub fn trace_length(num_assets: usize, min_trace_length: usize) -> usize {
+    padded_trace_length(num_assets + 1, min_trace_length)
+}
+
+/// Named columns of the trace for weights of `frac_bits` fractional bits, see [`crate::layout`]
+pub fn layout(frac_bits: u32) -> Result<LayoutDescriptor, WeightedSumError> {
+    Ok(LayoutDescriptor::new(AIR_ID)
+        .with_column("price", PRICE)
+        .with_column("weight", WEIGHT)
+        .with_column("value", VALUE)
+        .with_column("weight_sum", WEIGHT_SUM)
+        .with_columns("weight_bits", BITS_START..weight_range(frac_bits)?.next_column()))
+}
+
+/// Reason a portfolio cannot be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WeightedSumError {
+    Empty,
+    LengthMismatch { weights: usize, prices: usize },
+    /// The weights do not sum to one in fixed point
+    NotNormalized { sum: u128, expected: u128 },
+    /// The weights' fractional bits are outside [`FRAC_BITS`]
+    FracBits(u32),
+}
+
+impl fmt::Display for WeightedSumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no assets"),
+            Self::LengthMismatch { weights, prices } => write!(f, "{weights} weights for {prices} prices"),
+            Self::NotNormalized { sum, expected } => write!(f, "weights sum to {sum}, expected {expected}"),
+            Self::FracBits(bits) => write!(f, "{bits} fractional bits, weights support 1..=62"),
+        }
+    }
+}
+
+impl std::error::Error for WeightedSumError {}
+
+/// Quantizes real weights summing to one; the rounding residual goes to the largest weight
+pub fn quantize_weights(weights: &[f64], frac_bits: u32) -> Result<Vec<u64>, WeightedSumError> {
+    weight_range(frac_bits)?;
+    let one = 1u64 << frac_bits;
+    let mut quantized: Vec<u64> = weights.iter().map(|&w| (w * one as f64).round().max(0.0) as u64).collect();
+    if let Some(largest) = (0..quantized.len()).max_by_key(|&i| quantized[i]) {
+        let rest: u64 = quantized.iter().enumerate().filter(|(i, _)| *i != largest).map(|(_, w)| w).sum();
+        quantized[largest] = one.saturating_sub(rest);
+    }
+    Ok(quantized)
+}
+
+/// `Σ w_i * p_i`, the value claimed for `weights` and `prices`
+pub fn weighted_value(weights: &[u64], prices: &[u64]) -> u128 {
+    weights.iter().zip(prices).map(|(&w, &p)| w as u128 * p as u128).sum()
+}
+
+/// Public inputs: the prices, the total value and the fixed-point precision of the weights
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedSumInputs {
+    pub frac_bits: u32,
+    pub prices: Vec<BaseElement>,
+    pub value: BaseElement,
+}
+
+impl WeightedSumInputs {
+    pub fn new(frac_bits: u32, prices: &[u64], value: u128) -> Result<Self, WeightedSumError> {
+        weight_range(frac_bits)?;
+        let prices = prices.iter().map(|&p| BaseElement::from(p)).collect();
+        Ok(Self { frac_bits, prices, value: BaseElement::new(value) })
+    }
+}
+
+impl ToElements<BaseElement> for WeightedSumInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![BaseElement::from(self.frac_bits), self.value];
+        elements.extend(&self.prices);
+        elements
+    }
+}
+
+/// AIR accumulating `Σ w_i * p_i` and `Σ w_i` over one asset per row.
+///
+/// Both accumulators start at zero; after the last asset the value accumulator must equal the
+/// claimed value and the weight accumulator one. Rows after the assets are not constrained by
+/// any assertion, so their weights cannot change the asserted totals.
+pub struct WeightedSumAir {
+    context: AirContext<BaseElement>,
+    range: RangeCheck,
+    inputs: WeightedSumInputs,
+}
+
+impl Air for WeightedSumAir {
+    type BaseField = BaseElement;
+    type PublicInputs = WeightedSumInputs;
+
+    fn new(trace_info: TraceInfo, inputs: WeightedSumInputs, options: ProofOptions) -> Self {
+        let range = weight_range(inputs.frac_bits).expect("WeightedSumInputs::new checks the fractional bits");
+        assert_eq!(range.next_column(), trace_info.width());
+        assert!(!inputs.prices.is_empty(), "A weighted sum needs at least one asset");
+        assert!(inputs.prices.len() < trace_info.length(), "The trace needs a row after the last asset");
+
+        // Constraints:
+        // 1. Value accumulation (degree 2: weight times price)
+        // 2. Weight accumulation (degree 1)
+        // 3. The weight equals its recomposed bits (degree 1)
+        // 4. Booleanity of every weight bit (degree 2)
+        let mut degrees = vec![
+            TransitionConstraintDegree::new(2),
+            TransitionConstraintDegree::new(1),
+            TransitionConstraintDegree::new(1),
+        ];
+        degrees.extend(range.constraint_degrees());
+
+        // Prices, both accumulators at the start and at the end
+        let num_assertions = inputs.prices.len() + 4;
+
+        WeightedSumAir { context: AirContext::new(trace_info, degrees, num_assertions, options), range, inputs }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        let current = frame.current();
+        let next = frame.next();
+
+        result[0] = next[VALUE] - current[VALUE] - current[WEIGHT] * current[PRICE];
+        result[1] = next[WEIGHT_SUM] - current[WEIGHT_SUM] - current[WEIGHT];
+        result[2] = current[WEIGHT] - self.range.recompose(current);
+        self.range.evaluate(current, &mut result[3..]);
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions: Vec<_> =
+            self.inputs.prices.iter().enumerate().map(|(i, &price)| Assertion::single(PRICE, i, price)).collect();
+
+        let last_asset = self.inputs.prices.len();
+        let one = BaseElement::new(1 << self.inputs.frac_bits);
+        assertions.push(Assertion::single(VALUE, 0, BaseElement::ZERO));
+        assertions.push(Assertion::single(WEIGHT_SUM, 0, BaseElement::ZERO));
+        assertions.push(Assertion::single(VALUE, last_asset, self.inputs.value));
+        assertions.push(Assertion::single(WEIGHT_SUM, last_asset, one));
+
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
+
+/// Build the execution trace for `Σ weights[i] * prices[i]`
+#[cfg(feature = "prover")]
+pub fn build_weighted_sum_trace(
+    weights: &[u64],
+    prices: &[u64],
+    frac_bits: u32,
+    min_trace_length: usize,
+) -> Result<TraceTable<BaseElement>, WeightedSumError> {
+    if weights.is_empty() {
+        return Err(WeightedSumError::Empty);
+    }
+    if weights.len() != prices.len() {
+        return Err(WeightedSumError::LengthMismatch { weights: weights.len(), prices: prices.len() });
+    }
+    let range = weight_range(frac_bits)?;
+    let (sum, expected) = (weights.iter().map(|&w| w as u128).sum(), 1u128 << frac_bits);
+    if sum != expected {
+        return Err(WeightedSumError::NotNormalized { sum, expected });
+    }
+
+    let trace_length = trace_length(weights.len(), min_trace_length);
+    let mut trace = TraceTable::new(range.next_column(), trace_length);
+    let mut row = vec![BaseElement::ZERO; range.next_column()];
+    let (mut value, mut weight_sum) = (BaseElement::ZERO, BaseElement::ZERO);
+
+    for i in 0..trace_length {
+        // Rows after the assets carry a zero weight so the accumulators stay at the totals
+        let weight = weights.get(i).copied().unwrap_or(0);
+        row[PRICE] = BaseElement::from(prices.get(i).copied().unwrap_or(0));
+        row[WEIGHT] = BaseElement::from(weight);
+        row[VALUE] = value;
+        row[WEIGHT_SUM] = weight_sum;
+
+        if i == trace_length - 1 {
+            // The exempt last row keeps the price column from being constant
+            row[PRICE] += BaseElement::ONE;
+            range.fill_exempt_row(&mut row);
+        } else {
+            range.fill(&mut row, weight as i128).expect("Normalized weights are at most one");
+        }
+        trace.update_row(i, &row);
+
+        value += row[WEIGHT] * row[PRICE];
+        weight_sum += row[WEIGHT];
+    }
+
+    Ok(trace)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::config::{default_acceptable_options, ProverConfig};
+    use crate::prover::{prove, verify};
+
+    #[test]
+    fn test_portfolio_valuation_proof() {
+        let frac_bits = 16;
+        // prices in cents
+        let prices = [6_512_034, 342_110, 100, 15_873];
+        let weights = quantize_weights(&[0.5, 0.25, 0.125, 0.125], frac_bits).unwrap();
+        let value = weighted_value(&weights, &prices);
+        assert_eq!(value, (6_512_034 * 4 + 342_110 * 2 + 100 + 15_873) << (frac_bits - 3));
+
+        let inputs = WeightedSumInputs::new(frac_bits, &prices, value).unwrap();
+        let trace = build_weighted_sum_trace(&weights, &prices, frac_bits, TraceInfo::MIN_TRACE_LENGTH).unwrap();
+        crate::air_test!(WeightedSumAir, inputs.clone())
+            .with_trace(&trace)
+            .expect_all_hold()
+            .expect_assertions_hold()
+            .expect_full_coverage();
+        let options = ProverConfig::default().to_proof_options();
+        let proof = prove::<WeightedSumAir>(trace, inputs.clone(), options).unwrap();
+
+        let mut other_price = inputs.clone();
+        other_price.prices[2] = BaseElement::new(101);
+        assert!(verify::<WeightedSumAir>(proof.clone(), other_price, &default_acceptable_options()).is_err());
+        verify::<WeightedSumAir>(proof, inputs, &default_acceptable_options()).unwrap();
+    }
+
+    #[test]
+    fn test_weights_must_be_normalized() {
+        let weights = [1 << 15, 1 << 14];
+        assert_eq!(
+            build_weighted_sum_trace(&weights, &[1, 2], 16, TraceInfo::MIN_TRACE_LENGTH).unwrap_err(),
+            WeightedSumError::NotNormalized { sum: 3 << 14, expected: 1 << 16 }
+        );
+        assert_eq!(quantize_weights(&[1.0 / 3.0; 3], 16).unwrap().iter().sum::<u64>(), 1 << 16);
+
+        for frac_bits in [0, 63, 64] {
+            assert_eq!(WeightedSumInputs::new(frac_bits, &[1], 1), Err(WeightedSumError::FracBits(frac_bits)));
+            assert_eq!(quantize_weights(&[1.0], frac_bits), Err(WeightedSumError::FracBits(frac_bits)));
+            let error = build_weighted_sum_trace(&[1], &[1], frac_bits, TraceInfo::MIN_TRACE_LENGTH).unwrap_err();
+            assert_eq!(error, WeightedSumError::FracBits(frac_bits));
+            assert_eq!(layout(frac_bits), Err(WeightedSumError::FracBits(frac_bits)));
+        }
+    }
+}
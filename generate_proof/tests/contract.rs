@@ -0,0 +1,193 @@
+//! The no_std verifier contract of `verifier/src`, compiled for the host and run on envelopes
+//! of the Rust prover. The contract restates the regression AIR and the tagged public inputs,
+//! so these tests fail when either drifts from the crate.
+
+extern crate alloc;
+
+#[path = "../../verifier/src/air.rs"]
+mod air;
+#[path = "../../verifier/src/envelope.rs"]
+mod envelope;
+#[path = "../../verifier/src/query.rs"]
+mod query;
+
+use linear_regression::abi::{encode_dynamic_call, from_hex, selector, to_hex, uint_word, AbiArg};
+use linear_regression::conformance::EnvelopeFixtures;
+use linear_regression::config::{ProverConfig, VerificationPolicy, DEFAULT_MIN_SECURITY_BITS};
+use linear_regression::domain::{DomainTag, TaggedInputs};
+use linear_regression::envelope::ProofEnvelope;
+use linear_regression::fingerprint::ConstraintFingerprint;
+use linear_regression::nonce::NonceManager;
+use linear_regression::optimistic::ChallengeArtifacts;
+use linear_regression::regression::{self, LinearRegressionAir, LinearRegressionInputs};
+use linear_regression::schema::{ColumnSchema, DataSchema};
+use linear_regression::serialization::{Encoding, ON_CHAIN_VERSION};
+use linear_regression::session::{LinearClaimRequest, ProvingSession};
+use linear_regression::terms::TermsHash;
+use winterfell::math::{fields::f128::BaseElement, FieldElement, ToElements};
+use winterfell::Air;
+
+use envelope::{bytes_argument, verify_envelope, Claim};
+use query::{query_arguments, verify_query};
+
+fn request(terms_hash: Option<TermsHash>) -> LinearClaimRequest {
+    LinearClaimRequest {
+        model: "demo".into(),
+        salt: to_hex(&[3u8; 32]),
+        slope: 3,
+        intercept: 7,
+        sample_x: vec![1, 2, 4, 5],
+        x: 6,
+        schema: None,
+        beacon: None,
+        terms_hash,
+    }
+}
+
+fn schema() -> DataSchema {
+    DataSchema::new(vec![ColumnSchema::new("area", "m^2", 0, 0, 10)], ColumnSchema::new("price", "kEUR", 0, 0, 50))
+        .unwrap()
+}
+
+fn prove(session: &mut ProvingSession, terms_hash: Option<TermsHash>) -> ProofEnvelope {
+    session.prove_linear(&request(terms_hash)).unwrap()
+}
+
+#[test]
+fn test_constants_match_the_crate() {
+    let layout = regression::layout();
+    assert_eq!(envelope::LAYOUT_HASH, layout.hash().0);
+    assert_eq!(envelope::LAYOUT_ID, layout.versioned_id());
+    assert_eq!(envelope::DOMAIN_TAG, DomainTag::default().as_str());
+    assert_eq!(envelope::VERSION, ON_CHAIN_VERSION);
+    assert_eq!(envelope::MIN_SECURITY_BITS, DEFAULT_MIN_SECURITY_BITS);
+    assert_eq!(envelope::expected_options(), ProverConfig::default().to_proof_options());
+    assert_eq!(envelope::VERIFY_ENVELOPE, selector("verifyEnvelope(bytes)"));
+    assert_eq!(query::VERIFY_QUERY, selector("verifyQuery(bytes,uint256,uint256,bytes)"));
+}
+
+#[test]
+fn test_contract_air_matches_the_prover() {
+    let envelope = prove(&mut ProvingSession::new(ProverConfig::default(), "contract"), None);
+    let proof = &envelope.proof;
+    let inner = LinearRegressionInputs::from_elements(&envelope.public_inputs).unwrap();
+    let prover_air = LinearRegressionAir::new(proof.trace_info().clone(), inner, proof.options().clone());
+
+    let bytes = Encoding::OnChain.encode(&envelope);
+    let claim = Claim::parse(&bytes).unwrap();
+    let inputs = claim.inputs(proof.options()).unwrap();
+    let contract_air = air::LinearRegressionAir::new(proof.trace_info().clone(), inputs, proof.options().clone());
+
+    let layout = regression::layout();
+    assert_eq!(ConstraintFingerprint::of(&layout, &contract_air), ConstraintFingerprint::of(&layout, &prover_air));
+    assert_eq!(ConstraintFingerprint::of(&layout, &contract_air), envelope.constraint_fingerprint);
+}
+
+#[test]
+fn test_claim_inputs_match_tagged_inputs() {
+    let mut session =
+        ProvingSession::new(ProverConfig::default(), "contract").with_nonce_manager(NonceManager::in_memory());
+    let config = session.config().clone();
+    let described = LinearClaimRequest { schema: Some(schema()), ..request(None) };
+    let requests = [request(None), request(Some(TermsHash::of(b"CC-BY-4.0"))), described];
+    for request in requests {
+        let envelope = session.prove_linear(&request).unwrap();
+        assert!(envelope.nonce.is_some());
+        let tagged = TaggedInputs {
+            config: config.config_commitment(&regression::layout(), envelope.proof.options()),
+            tag: config.domain.clone(),
+            nonce: envelope.nonce,
+            terms_hash: envelope.terms_hash,
+            schema: envelope.schema.as_ref().map(DataSchema::commitment),
+            inner: LinearRegressionInputs::from_elements(&envelope.public_inputs).unwrap(),
+        };
+        let bytes = Encoding::OnChain.encode(&envelope);
+        let inputs = Claim::parse(&bytes).unwrap().inputs(envelope.proof.options()).unwrap();
+        assert_eq!(inputs.to_elements(), tagged.to_elements());
+    }
+}
+
+#[test]
+fn test_verify_envelope_accepts_proofs_and_rejects_tampering() {
+    let mut session =
+        ProvingSession::new(ProverConfig::default(), "contract").with_nonce_manager(NonceManager::in_memory());
+    let plain = Encoding::OnChain.encode(&prove(&mut ProvingSession::new(ProverConfig::default(), "contract"), None));
+    let licensed = Encoding::OnChain.encode(&prove(&mut session, Some(TermsHash::of(b"CC-BY-4.0"))));
+    for bytes in [&plain, &licensed] {
+        assert!(verify_envelope(bytes));
+        let call_data = encode_dynamic_call("verifyEnvelope(bytes)", &[AbiArg::Bytes(bytes)]);
+        assert_eq!(bytes_argument(&call_data), Some(&bytes[..]));
+    }
+
+    // the predicted y, the first word after x
+    let mut tampered = plain.clone();
+    tampered[0x160 + 32 + 31] ^= 1;
+    assert!(!verify_envelope(&tampered));
+    // the terms hash and the nonce are public inputs of the proof
+    let mut relicensed = licensed.clone();
+    relicensed[0x120 + 31] ^= 1;
+    assert!(!verify_envelope(&relicensed));
+    let mut renonced = licensed.clone();
+    renonced[0x140 + 31] ^= 1;
+    assert!(!verify_envelope(&renonced));
+    assert!(!verify_envelope(&plain[..plain.len() - 32]));
+    let mut versioned = plain;
+    versioned[4] += 1;
+    assert!(!verify_envelope(&versioned));
+    // valid proofs made with other options than the contract expects, even stronger ones
+    let stronger = ProverConfig { num_queries: 40, ..ProverConfig::default() };
+    assert!(!verify_envelope(&Encoding::OnChain.encode(&prove(&mut ProvingSession::new(stronger, "contract"), None))));
+
+    // the schema commitment is a public input of the proof
+    let described = LinearClaimRequest { schema: Some(schema()), ..request(None) };
+    let mut described = ProvingSession::new(ProverConfig::default(), "contract").prove_linear(&described).unwrap();
+    assert!(verify_envelope(&Encoding::OnChain.encode(&described)));
+    described.schema.as_mut().unwrap().target.max = 60;
+    assert!(!verify_envelope(&Encoding::OnChain.encode(&described)));
+}
+
+#[test]
+fn test_verify_envelope_accepts_the_deployment_vector() {
+    // wasm-verifier's no_std path and its ink! example run against the same vector
+    let vectors: EnvelopeFixtures = serde_json::from_str(include_str!("../../test-vectors/envelopes.json")).unwrap();
+    for vector in vectors.envelopes {
+        let bytes = from_hex(&vector.on_chain).unwrap();
+        assert_eq!(verify_envelope(&bytes), vector.domain == envelope::DOMAIN_TAG, "{}", vector.name);
+    }
+}
+
+#[test]
+fn test_verify_query_matches_the_challenge_artifacts() {
+    // enough samples for the proof to have a FRI layer besides the remainder
+    let request = LinearClaimRequest { sample_x: (1..=64).collect(), ..request(None) };
+    let envelope = ProvingSession::new(ProverConfig::default(), "contract").prove_linear(&request).unwrap();
+    let artifacts = ChallengeArtifacts::for_linear(&envelope, &VerificationPolicy::default()).unwrap();
+    assert!(!artifacts.context.roots.fri_layers.is_empty());
+    let bytes = Encoding::OnChain.encode(&envelope);
+    let num_queries = artifacts.chunks.len();
+    let chunks: Vec<Vec<u8>> =
+        artifacts.chunks.iter().map(|chunk| chunk.to_bytes(&artifacts.context.roots)).collect();
+    for (query, chunk) in chunks.iter().enumerate() {
+        assert!(verify_query(&bytes, num_queries, query, chunk), "query {query}");
+    }
+
+    let args = [
+        AbiArg::Bytes(&bytes),
+        AbiArg::Word(uint_word(num_queries as u128)),
+        AbiArg::Word(uint_word(1)),
+        AbiArg::Bytes(&chunks[1]),
+    ];
+    let call_data = encode_dynamic_call("verifyQuery(bytes,uint256,uint256,bytes)", &args);
+    assert_eq!(query_arguments(&call_data), Some((&bytes[..], num_queries, 1, &chunks[1][..])));
+
+    // another query's chunk, a miscounted proof and an opening that does not lead to its root
+    assert!(!verify_query(&bytes, num_queries, 0, &chunks[1]));
+    assert!(!verify_query(&bytes, num_queries - 1, 1, &chunks[1]));
+    let mut tampered = artifacts.chunks[1].clone();
+    tampered.trace.values[0] += BaseElement::ONE;
+    assert!(!verify_query(&bytes, num_queries, 1, &tampered.to_bytes(&artifacts.context.roots)));
+    // a chunk whose openings hold for an envelope claiming another prediction
+    let mut claimed = bytes.clone();
+    claimed[0x160 + 32 + 31] ^= 1;
+    assert!(!verify_query(&claimed, num_queries, 1, &chunks[1]));
+}
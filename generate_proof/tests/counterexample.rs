@@ -0,0 +1,100 @@
+//! Compiles and runs the unit test of a counterexample against this crate. Each run builds the
+//! crate again in a scratch package, so the test is ignored by default:
+//!
+//! ```text
+//! cargo test --features prover --test counterexample -- --ignored
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use linear_regression::config::ProverConfig;
+use linear_regression::regression::{build_linear_regression_trace, LinearRegressionAir, LinearRegressionInputs};
+use linear_regression::simulation::{labeled_dry_run, Counterexample};
+use winterfell::math::fields::f128::BaseElement;
+use winterfell::TraceInfo;
+
+/// Public inputs expression of the generated unit tests
+const INPUTS: &str =
+    "linear_regression::regression::LinearRegressionInputs::from_elements(&elements(&PUBLIC_INPUTS)).unwrap()";
+
+/// Scratch package depending on this crate, removed when dropped
+struct ScratchPackage(PathBuf);
+
+impl ScratchPackage {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("counterexample-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("tests")).unwrap();
+        let manifest = format!(
+            "[package]\nname = \"counterexample\"\nversion = \"0.0.0\"\nedition = \"2024\"\n\n\
+             [dependencies]\nlinear_regression = {{ path = {manifest_dir:?}, features = [\"prover\"] }}\n\
+             winterfell = {{ version = \"0.12\", default-features = false }}\n"
+        );
+        fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+        fs::write(dir.join("src/lib.rs"), "").unwrap();
+        fs::copy(manifest_dir.join("Cargo.lock"), dir.join("Cargo.lock")).unwrap();
+        ScratchPackage(dir)
+    }
+
+    /// Runs `cargo test` on the package with `counterexample`'s unit test as its only test
+    fn run(&self, counterexample: &Counterexample) -> Output {
+        let tests = self.0.join("tests");
+        fs::remove_dir_all(&tests).unwrap();
+        fs::create_dir(&tests).unwrap();
+        counterexample.write_unit_test(&tests, INPUTS).unwrap();
+        Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+            .args(["test", "--offline", "--tests", "--manifest-path"])
+            .arg(self.0.join("Cargo.toml"))
+            .env("CARGO_TARGET_DIR", Path::new(env!("CARGO_MANIFEST_DIR")).join("target/counterexample"))
+            .output()
+            .unwrap()
+    }
+}
+
+impl Drop for ScratchPackage {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+#[ignore = "builds the crate again in a scratch package"]
+fn test_generated_unit_test_compiles() {
+    let (slope, intercept) = (BaseElement::new(2), BaseElement::new(5));
+    let xs = [BaseElement::new(1), BaseElement::new(3)];
+    let ys = [BaseElement::new(7), BaseElement::new(11)];
+    let trace =
+        build_linear_regression_trace(slope, intercept, &xs, &ys, BaseElement::new(8), TraceInfo::MIN_TRACE_LENGTH);
+    let mut broken = trace.clone();
+    broken.set(3, 1, BaseElement::new(12));
+    let inputs = LinearRegressionInputs {
+        x_value: BaseElement::new(8),
+        predicted_y: BaseElement::new(21),
+        sample_x_values: xs.to_vec(),
+        sample_y_values: ys.to_vec(),
+    };
+    let options = ProverConfig::default().to_proof_options();
+    let report = labeled_dry_run::<LinearRegressionAir>(&broken, inputs, options).unwrap();
+    let example = &report.counterexamples[0];
+    let package = ScratchPackage::new();
+
+    // the test compiles against this crate and fails on the counterexample
+    let output = package.run(example);
+    let (stdout, stderr) = (String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    assert!(!output.status.success(), "{stdout}");
+    assert!(!stderr.contains("error["), "{stderr}");
+    let name = format!("constraint_{}_holds_on_row_{}", example.constraint, example.row);
+    assert!(stdout.contains(&format!("{name} ... FAILED")), "{stdout}\n{stderr}");
+
+    // and passes once the frame satisfies the constraint
+    let row = |step: usize| (0..trace.width()).map(|column| trace.get(column, step)).collect();
+    let fixed = Counterexample { current: row(example.row), next: row(example.row + 1), ..example.clone() };
+    let output = package.run(&fixed);
+    let (stdout, stderr) = (String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(stdout.contains(&format!("{name} ... ok")), "{stdout}");
+}
@@ -0,0 +1,8 @@
+//! Integration suites that need tools from outside Cargo, built only with the `onchain-tests`
+//! feature. Install what a suite's module documentation lists, then run
+//!
+//! ```text
+//! cargo test --features onchain-tests --test it
+//! ```
+
+mod onchain;
@@ -0,0 +1,194 @@
+//! Real envelopes from the Rust prover against the contracts of `verifier/`, deployed to a
+//! local pallet-revive node.
+//!
+//! The suite does not use anvil or Foundry: the contracts run on PolkaVM, so it needs
+//! `revive-dev-node` (the pallet-revive development node of polkadot-sdk), `eth-rpc` (its
+//! Ethereum JSON-RPC adapter, from polkadot-sdk's `pallet-revive-eth-rpc`) and `resolc` (the
+//! revive Solidity compiler) on `PATH`, plus what `verifier/Makefile` needs to build the PolkaVM
+//! verifier (`polkatool` and a nightly toolchain with the `riscv64emac-unknown-none-polkavm`
+//! target). Every test compiles the Solidity contracts with
+//! resolc, builds the verifier of `verifier/src/main.rs`, starts its own node and deploys them,
+//! so proofs are checked on-chain by the contract itself: the layout checks of
+//! `EnvelopeDecoder.sol`, the STARK verification of the verifier contract, the settlement keys
+//! of `ClaimSettlement.sol` and the batch hashing of `ProofAnchor.sol` all run on the bytes the
+//! Rust side produced, so a change to either side fails here. `EnvelopeDecoder.t.sol` exposes
+//! the decoder, so every field it reads is compared with the envelope the Rust side encoded.
+
+mod node;
+
+use linear_regression::abi::{encode_call, encode_dynamic_call, keccak256, to_hex, uint_word, AbiArg};
+use linear_regression::anchor::AnchorBatch;
+use linear_regression::config::{ProverConfig, VerificationPolicy};
+use linear_regression::envelope::ProofEnvelope;
+use linear_regression::nonce::{settlement_hash, NonceManager};
+use linear_regression::operator::OperatorKey;
+use linear_regression::serialization::Encoding;
+use linear_regression::session::{verify_linear, LinearClaimRequest, ProvingSession};
+use linear_regression::terms::TermsHash;
+use winterfell::math::{fields::f128::BaseElement, FieldElement, StarkField};
+
+use node::{Artifacts, Node};
+
+/// Offset of the first public input word in the on-chain encoding, see `EnvelopeDecoder.sol`
+const INPUTS_OFFSET: usize = 0x160;
+
+fn envelopes(slopes: &[u64]) -> Vec<ProofEnvelope> {
+    prove(ProvingSession::new(ProverConfig::default(), "onchain"), slopes, None)
+}
+
+fn prove(session: ProvingSession, slopes: &[u64], terms_hash: Option<TermsHash>) -> Vec<ProofEnvelope> {
+    let mut session = session.with_nonce_manager(NonceManager::in_memory());
+    slopes
+        .iter()
+        .map(|&slope| {
+            let request = LinearClaimRequest {
+                model: "demo".into(),
+                salt: to_hex(&[3u8; 32]),
+                slope,
+                intercept: 4,
+                sample_x: vec![1, 2, 5],
+                x: 7,
+                schema: None,
+                beacon: None,
+                terms_hash,
+            };
+            session.prove_linear(&request).unwrap()
+        })
+        .collect()
+}
+
+fn address_word(address: &[u8; 20]) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address);
+    word
+}
+
+/// Offset of the proof in the on-chain encoding of `envelope`
+fn proof_offset(envelope: &ProofEnvelope) -> usize {
+    INPUTS_OFFSET + 32 * envelope.public_inputs.len()
+}
+
+/// Verdict of the verifier contract at `verifier` on the encoded envelope
+fn verify_envelope(chain: &Node, verifier: &[u8; 20], calldata: &[u8]) -> bool {
+    let call = encode_dynamic_call("verifyEnvelope(bytes)", &[AbiArg::Bytes(calldata)]);
+    chain.call(verifier, &call).unwrap()[31] == 1
+}
+
+#[test]
+fn test_settlement_accepts_real_proofs_once() {
+    let artifacts = Artifacts::build();
+    let chain = Node::spawn();
+    let verifier = chain.deploy(artifacts.verifier(), &[]);
+    let settlement = chain.deploy(&artifacts.bytecode("ClaimSettlement"), &[address_word(&verifier)]);
+
+    let envelope = envelopes(&[3]).remove(0);
+    let calldata = Encoding::OnChain.encode(&envelope);
+    assert!(verify_envelope(&chain, &verifier, &calldata));
+    let key = chain.call(&settlement, &encode_dynamic_call("settlementHash(bytes)", &[AbiArg::Bytes(&calldata)]));
+    assert_eq!(key.unwrap(), settlement_hash(&envelope).unwrap());
+
+    let settle = encode_dynamic_call("settle(bytes)", &[AbiArg::Bytes(&calldata)]);
+    chain.send(&settlement, &settle).unwrap();
+    let consumed = chain.call(&settlement, &encode_call("consumed(bytes32)", &[settlement_hash(&envelope).unwrap()]));
+    assert_eq!(consumed.unwrap()[31], 1);
+    assert!(chain.send(&settlement, &settle).unwrap_err().contains("Claim already settled"));
+
+    // a claim the proof was not made for, or a corrupted proof, is rejected by the verifier
+    // contract as by the Rust verifier
+    let mut tampered = envelope.clone();
+    tampered.public_inputs[0] += BaseElement::ONE;
+    let mut corrupted = Encoding::OnChain.encode(&envelopes(&[4]).remove(0));
+    corrupted[proof_offset(&envelope) + 64] ^= 1;
+    for calldata in [Encoding::OnChain.encode(&tampered), corrupted] {
+        let decoded = Encoding::OnChain.decode(&calldata);
+        assert!(!decoded.is_ok_and(|envelope| verify_linear(&envelope, &VerificationPolicy::default()).is_ok()));
+        assert!(!verify_envelope(&chain, &verifier, &calldata));
+        let error = chain.send(&settlement, &encode_dynamic_call("settle(bytes)", &[AbiArg::Bytes(&calldata)]));
+        assert!(error.unwrap_err().contains("Invalid proof"));
+    }
+
+    // public inputs outside the field never reach the verifier
+    let mut calldata = Encoding::OnChain.encode(&envelopes(&[5]).remove(0));
+    calldata[INPUTS_OFFSET..INPUTS_OFFSET + 32].fill(0xff);
+    assert!(Encoding::OnChain.decode(&calldata).is_err());
+    let error = chain.send(&settlement, &encode_dynamic_call("settle(bytes)", &[AbiArg::Bytes(&calldata)]));
+    assert!(error.unwrap_err().contains("Non-canonical public input"));
+}
+
+#[test]
+fn test_anchored_batches_match_rust_hashing() {
+    let artifacts = Artifacts::build();
+    let chain = Node::spawn();
+    let anchor = chain.deploy(&artifacts.bytecode("ProofAnchor"), &[]);
+
+    let batch_envelopes = envelopes(&[2, 3, 5]);
+    let batch = AnchorBatch::new(&batch_envelopes);
+    chain.send(&anchor, &batch.anchor_calldata()).unwrap();
+    assert!(chain.send(&anchor, &batch.anchor_calldata()).unwrap_err().contains("Root already anchored"));
+
+    let is_included = |calldata: &[u8], index: usize| {
+        let inclusion = batch.inclusion_proof(index).unwrap();
+        let args = [
+            AbiArg::Word(batch.root()),
+            AbiArg::Bytes(calldata),
+            AbiArg::Word(uint_word(index as u128)),
+            AbiArg::Words(&inclusion.siblings),
+        ];
+        let call = encode_dynamic_call("isIncluded(bytes32,bytes,uint256,bytes32[])", &args);
+        chain.call(&anchor, &call).unwrap()[31] == 1
+    };
+    let encoded: Vec<Vec<u8>> = batch_envelopes.iter().map(|envelope| Encoding::OnChain.encode(envelope)).collect();
+    for (index, calldata) in encoded.iter().enumerate() {
+        assert!(is_included(calldata, index));
+    }
+    let mut corrupted = encoded[1].clone();
+    corrupted[proof_offset(&batch_envelopes[1]) + 64] ^= 1;
+    assert!(!is_included(&corrupted, 1));
+    assert!(!is_included(&encoded[0], 1));
+}
+
+#[test]
+fn test_solidity_decoder_reads_rust_envelopes() {
+    let artifacts = Artifacts::build();
+    let chain = Node::spawn();
+    let decoder = chain.deploy(&artifacts.bytecode("EnvelopeDecoderTest"), &[]);
+
+    let key = OperatorKey::from_seed(&[5u8; 32]);
+    let signed = ProvingSession::new(ProverConfig::default(), "onchain").with_operator_key(key);
+    let signed = prove(signed, &[6], Some(TermsHash::of(b"CC-BY-4.0"))).remove(0);
+    assert!(signed.signer.is_some() && signed.terms_hash.is_some() && signed.nonce.is_some());
+    for envelope in [envelopes(&[3]).remove(0), signed] {
+        let calldata = Encoding::OnChain.encode(&envelope);
+        let fields = chain.call(&decoder, &encode_dynamic_call("decode(bytes)", &[AbiArg::Bytes(&calldata)]));
+        let fields = fields.unwrap();
+        let field = |i: usize| &fields[32 * i..32 * (i + 1)];
+        assert_eq!(field(0), envelope.model_id.0);
+        assert_eq!(field(1), envelope.model_commitment.0);
+        assert_eq!(field(2), envelope.constraint_fingerprint.0);
+        assert_eq!(field(3), envelope.terms_hash.map_or([0u8; 32], |terms_hash| terms_hash.0));
+        assert_eq!(field(4), envelope.nonce.map_or([0u8; 32], |nonce| nonce.0));
+        assert_eq!(field(5), uint_word(envelope.public_inputs.len() as u128));
+        assert_eq!(field(6), keccak256(&envelope.proof.to_bytes()));
+        assert_eq!(field(7), envelope.signer.as_ref().map_or([0u8; 32], |signer| signer.public_key.0));
+        for (i, input) in envelope.public_inputs.iter().enumerate() {
+            let args = [AbiArg::Bytes(&calldata), AbiArg::Word(uint_word(i as u128))];
+            let word = chain.call(&decoder, &encode_dynamic_call("publicInput(bytes,uint256)", &args));
+            assert_eq!(word.unwrap(), uint_word(input.as_int()));
+        }
+    }
+
+    // what the Rust decoder refuses, the Solidity one does too
+    let calldata = Encoding::OnChain.encode(&envelopes(&[4]).remove(0));
+    let decode =
+        |calldata: &[u8]| chain.call(&decoder, &encode_dynamic_call("decode(bytes)", &[AbiArg::Bytes(calldata)]));
+    let mut versioned = calldata.clone();
+    versioned[4] += 1;
+    assert!(decode(&versioned).unwrap_err().contains("Unsupported envelope version"));
+    assert!(decode(&calldata[..calldata.len() - 32]).unwrap_err().contains("Envelope length mismatch"));
+    let mut large = calldata.clone();
+    large[INPUTS_OFFSET..INPUTS_OFFSET + 32].fill(0xff);
+    assert!(decode(&large).unwrap_err().contains("Non-canonical public input"));
+    for bytes in [versioned, large] {
+        assert!(Encoding::OnChain.decode(&bytes).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! A local pallet-revive node behind its Ethereum JSON-RPC adapter, a client for it, and the
+//! contracts compiled for PolkaVM: the Solidity ones with resolc and the verifier of
+//! `verifier/src` with its Makefile.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use linear_regression::abi::{from_hex, to_hex};
+use serde_json::{json, Value};
+
+/// Contracts of `verifier/` the suite deploys
+const CONTRACTS: [&str; 4] = ["EnvelopeDecoder.sol", "EnvelopeDecoder.t.sol", "ClaimSettlement.sol", "ProofAnchor.sol"];
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// PolkaVM code of every contract, compiled once per test
+pub struct Artifacts {
+    output: Value,
+    verifier: Vec<u8>,
+}
+
+impl Artifacts {
+    pub fn build() -> Self {
+        let verifier_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../verifier");
+        let sources: serde_json::Map<String, Value> = CONTRACTS
+            .iter()
+            .map(|contract| {
+                let source = fs::read_to_string(verifier_dir.join(contract)).expect("Missing contract");
+                (contract.to_string(), json!({ "content": source }))
+            })
+            .collect();
+        let input = json!({
+            "language": "Solidity",
+            "sources": sources,
+            "settings": { "outputSelection": { "*": { "*": ["evm.bytecode"] } } },
+        });
+        let mut resolc = Command::new("resolc")
+            .arg("--standard-json")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("resolc must be on PATH");
+        resolc.stdin.take().unwrap().write_all(input.to_string().as_bytes()).unwrap();
+        let compiled = resolc.wait_with_output().unwrap();
+        assert!(compiled.status.success(), "resolc failed");
+        let output: Value = serde_json::from_slice(&compiled.stdout).expect("resolc printed no JSON");
+        let errors = output["errors"].as_array().into_iter().flatten();
+        let errors: Vec<&Value> = errors.filter(|error| error["severity"] == "error").collect();
+        assert!(errors.is_empty(), "resolc failed: {errors:?}");
+
+        let status = Command::new("make").arg("-C").arg(&verifier_dir).status().expect("make must be on PATH");
+        assert!(status.success(), "building the verifier contract failed");
+        let verifier = fs::read(verifier_dir.join("contract.polkavm")).expect("Missing contract.polkavm");
+        Self { output, verifier }
+    }
+
+    /// Code of `contract`, declared in any of the compiled sources
+    pub fn bytecode(&self, contract: &str) -> Vec<u8> {
+        let sources = self.output["contracts"].as_object().expect("resolc compiled no contracts");
+        let compiled = sources.values().find_map(|source| source.get(contract)).expect("Unknown contract");
+        from_hex(compiled["evm"]["bytecode"]["object"].as_str().expect("Contract without bytecode")).unwrap()
+    }
+
+    /// Code of the verifier contract built from `verifier/src/main.rs`
+    pub fn verifier(&self) -> &[u8] {
+        &self.verifier
+    }
+}
+
+/// A `revive-dev-node` and the `eth-rpc` adapter in front of it, on free local ports, killed
+/// when dropped
+pub struct Node {
+    processes: Vec<Child>,
+    port: u16,
+    account: String,
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn wait_for(port: u16, name: &str) {
+    let started = Instant::now();
+    while TcpStream::connect(("127.0.0.1", port)).is_err() {
+        assert!(started.elapsed() < STARTUP_TIMEOUT, "{name} did not start");
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+impl Node {
+    pub fn spawn() -> Self {
+        let (node_port, port) = (free_port(), free_port());
+        let node = Command::new("revive-dev-node")
+            .args(["--dev", "--rpc-port", &node_port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("revive-dev-node must be on PATH");
+        let mut chain = Self { processes: vec![node], port, account: String::new() };
+        wait_for(node_port, "revive-dev-node");
+
+        let adapter = Command::new("eth-rpc")
+            .args(["--dev", "--rpc-port", &port.to_string()])
+            .args(["--node-rpc-url", &format!("ws://127.0.0.1:{node_port}")])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("eth-rpc must be on PATH");
+        chain.processes.push(adapter);
+        wait_for(port, "eth-rpc");
+
+        let accounts = chain.rpc("eth_accounts", json!([])).expect("No accounts");
+        chain.account = accounts[0].as_str().expect("Unlocked account").to_string();
+        chain
+    }
+
+    /// Result of a JSON-RPC call, or the node's error message, e.g. a revert reason
+    pub fn rpc(&self, method: &str, params: Value) -> Result<Value, String> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).map_err(|err| err.to_string())?;
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .map_err(|err| err.to_string())?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(|err| err.to_string())?;
+
+        let split = response.windows(4).position(|window| window == b"\r\n\r\n").ok_or("Malformed response")?;
+        let head = String::from_utf8_lossy(&response[..split]).to_ascii_lowercase();
+        let mut body = response[split + 4..].to_vec();
+        if head.contains("transfer-encoding: chunked") {
+            body = dechunk(&body);
+        }
+        let mut reply: Value = serde_json::from_slice(&body).map_err(|err| err.to_string())?;
+        match reply.get("error") {
+            Some(error) => Err(error["message"].as_str().unwrap_or("unknown error").to_string()),
+            None => Ok(reply["result"].take()),
+        }
+    }
+
+    /// Deploys `code` followed by its ABI encoded constructor arguments
+    pub fn deploy(&self, code: &[u8], constructor_args: &[[u8; 32]]) -> [u8; 20] {
+        let code = [code, &constructor_args.concat()].concat();
+        let receipt = self.transact(json!({ "from": self.account, "data": to_hex(&code) })).expect("Deployment failed");
+        let address = from_hex(receipt["contractAddress"].as_str().expect("No contract address")).unwrap();
+        address.try_into().unwrap()
+    }
+
+    /// Sends a transaction calling `to` and waits for it to be included; fails with the revert
+    /// reason when it reverts
+    pub fn send(&self, to: &[u8; 20], calldata: &[u8]) -> Result<Value, String> {
+        self.transact(json!({ "from": self.account, "to": to_hex(to), "data": to_hex(calldata) }))
+    }
+
+    /// Return data of a call to `to` that is not included
+    pub fn call(&self, to: &[u8; 20], calldata: &[u8]) -> Result<Vec<u8>, String> {
+        let result = self.rpc("eth_call", json!([{ "to": to_hex(to), "data": to_hex(calldata) }, "latest"]))?;
+        Ok(from_hex(result.as_str().ok_or("Call returned no data")?).unwrap())
+    }
+
+    fn transact(&self, transaction: Value) -> Result<Value, String> {
+        // eth-rpc dry-runs every transaction and refuses to send those that would revert
+        let hash = self.rpc("eth_sendTransaction", json!([transaction]))?;
+        let started = Instant::now();
+        let receipt = loop {
+            let receipt = self.rpc("eth_getTransactionReceipt", json!([hash]))?;
+            if !receipt.is_null() {
+                break receipt;
+            }
+            assert!(started.elapsed() < RECEIPT_TIMEOUT, "transaction {hash} was not included");
+            thread::sleep(Duration::from_millis(200));
+        };
+        match receipt["status"].as_str() {
+            Some("0x1") => Ok(receipt),
+            _ => Err(format!("transaction reverted: {receipt}")),
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        for process in self.processes.iter_mut().rev() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+    }
+}
+
+/// Body of a chunked HTTP response
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(end) = body.windows(2).position(|window| window == b"\r\n") {
+        let size = usize::from_str_radix(String::from_utf8_lossy(&body[..end]).trim(), 16).unwrap_or(0);
+        if size == 0 {
+            break;
+        }
+        out.extend_from_slice(&body[end + 2..end + 2 + size]);
+        body = &body[end + 2 + size + 2..];
+    }
+    out
+}
@@ -0,0 +1,84 @@
+//! Scans freed memory for witness data. The scan replaces the global allocator, so it runs in
+//! its own test binary rather than under every unit test of the library.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+
+use linear_regression::pool::{PooledTrace, TracePool};
+use linear_regression::regression::{fill_linear_regression_trace, trace_length, TRACE_WIDTH};
+use linear_regression::secret::{SecretElements, SecretScope, SecretTrace};
+use winterfell::math::{fields::f128::BaseElement, FieldElement};
+use winterfell::TraceInfo;
+
+/// Slope planted in the traces below, unlikely to appear in memory by chance
+const MARKER: u128 = 0x5ec2_e75e_c2e7_5ec2_e75e_c2e7_5ec2_e75e;
+
+thread_local! {
+    static WATCHING: Cell<bool> = const { Cell::new(false) };
+    static LEAKED_BLOCKS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts blocks freed by the watching thread that still contain [`MARKER`]
+struct ScanningAllocator;
+
+unsafe impl GlobalAlloc for ScanningAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if WATCHING.with(Cell::get) {
+            let words = layout.size() / size_of::<u128>();
+            // SAFETY: the block is `layout.size()` bytes long and still allocated
+            let leaked = (0..words).any(|i| unsafe { ptr.cast::<u128>().add(i).read_unaligned() } == MARKER);
+            if leaked {
+                LEAKED_BLOCKS.with(|count| count.set(count.get() + 1));
+            }
+        }
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: ScanningAllocator = ScanningAllocator;
+
+/// Blocks freed while running `f` that still held [`MARKER`]
+fn leaked_blocks(f: impl FnOnce()) -> usize {
+    LEAKED_BLOCKS.with(|count| count.set(0));
+    WATCHING.with(|watching| watching.set(true));
+    f();
+    WATCHING.with(|watching| watching.set(false));
+    LEAKED_BLOCKS.with(Cell::get)
+}
+
+fn marked_columns() -> Vec<Vec<BaseElement>> {
+    let length = trace_length(2, TraceInfo::MIN_TRACE_LENGTH);
+    let mut columns = vec![vec![BaseElement::ZERO; length]; TRACE_WIDTH];
+    let samples = [2u64, 3].map(BaseElement::from);
+    let slope = BaseElement::new(MARKER);
+    let sample_y = samples.map(|x| slope * x);
+    fill_linear_regression_trace(&mut columns, slope, BaseElement::ZERO, &samples, &sample_y, samples[0]);
+    columns
+}
+
+#[test]
+fn test_secret_trace_is_wiped_on_drop() {
+    // the scan does see witness data freed without zeroization, but not the row buffer of
+    // the fill function
+    assert_eq!(leaked_blocks(|| drop(marked_columns())), 1);
+    assert_eq!(leaked_blocks(|| drop(SecretTrace::new(marked_columns()))), 0);
+
+    let pool = Arc::new(TracePool::new(0));
+    assert_eq!(leaked_blocks(|| drop(PooledTrace::new(marked_columns(), pool.clone()))), 0);
+}
+
+#[test]
+fn test_secret_scope_wipes_its_value() {
+    let leaked = leaked_blocks(|| {
+        let mut parameters = SecretScope::new(SecretElements::zeroed(2));
+        parameters[0] = BaseElement::new(MARKER);
+        assert_eq!(format!("{parameters:?}"), "SecretScope(..)");
+    });
+    assert_eq!(leaked, 0);
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+import "./EnvelopeDecoder.sol";
+
+// Proof verifier for on-chain envelopes: the PolkaVM contract built from src/main.rs exports
+// `verifyEnvelope(bytes)` and checks the proof against the claim's tagged public inputs
+interface IEnvelopeVerifier {
+    function verifyEnvelope(bytes calldata envelope) external returns (bool);
+}
+
+// Settles every claim at most once. Mirrors `NonceManager::settle` in
+// generate_proof/src/nonce.rs; the key is `nonce::settlement_hash`, the keccak256 of the nonce
+// word and the public input words, which are contiguous in the envelope.
+contract ClaimSettlement {
+    IEnvelopeVerifier public immutable verifier;
+
+    mapping(bytes32 => bool) public consumed;
+
+    event ClaimSettled(bytes32 indexed settlementHash, bytes32 indexed modelId, bytes32 nonce);
+
+    constructor(IEnvelopeVerifier verifier_) {
+        verifier = verifier_;
+    }
+
+    function settlementHash(bytes calldata envelope) public pure returns (bytes32) {
+        uint256 end = EnvelopeDecoder.INPUTS_OFFSET + EnvelopeDecoder.WORD * EnvelopeDecoder.numInputs(envelope);
+        return keccak256(envelope[EnvelopeDecoder.NONCE_OFFSET:end]);
+    }
+
+    function settle(bytes calldata envelope) external returns (bytes32 key) {
+        EnvelopeDecoder.check(envelope);
+        bytes32 nonce = EnvelopeDecoder.nonce(envelope);
+        require(nonce != bytes32(0), "Claim has no nonce");
+        key = settlementHash(envelope);
+        require(!consumed[key], "Claim already settled");
+        require(verifier.verifyEnvelope(envelope), "Invalid proof");
+        consumed[key] = true;
+        emit ClaimSettled(key, EnvelopeDecoder.modelId(envelope), nonce);
+    }
+}
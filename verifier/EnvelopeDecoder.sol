@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+// Reads proof envelopes in the on-chain encoding of generate_proof/src/serialization.rs
+// (`Encoding::OnChain`). Every field sits at a fixed offset in 32-byte words, so each read is
+// a single calldataload. The on-chain suite of generate_proof/tests/it/onchain decodes envelopes
+// the Rust side encoded through EnvelopeDecoder.t.sol and compares every field.
+library EnvelopeDecoder {
+    bytes4 internal constant MAGIC = "STKO";
+    uint8 internal constant VERSION = 6;
+    uint256 internal constant WORD = 0x20;
+
+    uint256 internal constant MODEL_ID_OFFSET = 0x20;
+    uint256 internal constant MODEL_COMMITMENT_OFFSET = 0x40;
+    uint256 internal constant FINGERPRINT_OFFSET = 0x60;
+    uint256 internal constant NUM_INPUTS_OFFSET = 0x80;
+    uint256 internal constant PROOF_LENGTH_OFFSET = 0xa0;
+    uint256 internal constant SCHEMA_LENGTH_OFFSET = 0xc0;
+    uint256 internal constant SIGNER_LENGTH_OFFSET = 0xe0;
+    uint256 internal constant BEACON_LENGTH_OFFSET = 0x100;
+    // Hash of the terms the claim is made under, zero without one
+    uint256 internal constant TERMS_HASH_OFFSET = 0x120;
+    // Claim nonce, zero without one; directly precedes the public inputs
+    uint256 internal constant NONCE_OFFSET = 0x140;
+    // Public input i starts at INPUTS_OFFSET + WORD * i
+    uint256 internal constant INPUTS_OFFSET = 0x160;
+    // Operator public key and Ed25519 signature of a signed envelope
+    uint256 internal constant SIGNER_LENGTH = 96;
+
+    // Modulus of the f128 field the public inputs live in
+    uint256 internal constant MODULUS = 0xffffffffffffffffffffd30000000001;
+
+    function word(bytes calldata envelope, uint256 offset) internal pure returns (bytes32 value) {
+        require(offset + WORD <= envelope.length, "Envelope truncated");
+        assembly {
+            value := calldataload(add(envelope.offset, offset))
+        }
+    }
+
+    function padded(uint256 len) internal pure returns (uint256) {
+        return (len + WORD - 1) / WORD * WORD;
+    }
+
+    // Checks the header, the total length and that every public input is a canonical element;
+    // padding is not inspected, the Rust decoder rejects envelopes with non-zero padding
+    function check(bytes calldata envelope) internal pure {
+        bytes32 header = word(envelope, 0);
+        require(bytes4(header) == MAGIC, "Not an on-chain envelope");
+        require(uint8(header[4]) == VERSION, "Unsupported envelope version");
+
+        uint256 n = numInputs(envelope);
+        uint256 proofLen = uint256(word(envelope, PROOF_LENGTH_OFFSET));
+        uint256 schemaLen = uint256(word(envelope, SCHEMA_LENGTH_OFFSET));
+        uint256 signerLen = uint256(word(envelope, SIGNER_LENGTH_OFFSET));
+        uint256 beaconLen = uint256(word(envelope, BEACON_LENGTH_OFFSET));
+        require(signerLen == 0 || signerLen == SIGNER_LENGTH, "Invalid signer length");
+        require(
+            envelope.length
+                == INPUTS_OFFSET + WORD * n + padded(proofLen) + padded(schemaLen) + signerLen + padded(beaconLen),
+            "Envelope length mismatch"
+        );
+        for (uint256 i = 0; i < n; i++) {
+            require(publicInput(envelope, i) < MODULUS, "Non-canonical public input");
+        }
+    }
+
+    function modelId(bytes calldata envelope) internal pure returns (bytes32) {
+        return word(envelope, MODEL_ID_OFFSET);
+    }
+
+    function modelCommitment(bytes calldata envelope) internal pure returns (bytes32) {
+        return word(envelope, MODEL_COMMITMENT_OFFSET);
+    }
+
+    function constraintFingerprint(bytes calldata envelope) internal pure returns (bytes32) {
+        return word(envelope, FINGERPRINT_OFFSET);
+    }
+
+    function nonce(bytes calldata envelope) internal pure returns (bytes32) {
+        return word(envelope, NONCE_OFFSET);
+    }
+
+    function termsHash(bytes calldata envelope) internal pure returns (bytes32) {
+        return word(envelope, TERMS_HASH_OFFSET);
+    }
+
+    function numInputs(bytes calldata envelope) internal pure returns (uint256) {
+        return uint256(word(envelope, NUM_INPUTS_OFFSET));
+    }
+
+    function publicInput(bytes calldata envelope, uint256 i) internal pure returns (uint256) {
+        return uint256(word(envelope, INPUTS_OFFSET + WORD * i));
+    }
+
+    // Winterfell's serialization of the proof, as passed to the Rust verifier contract
+    function proof(bytes calldata envelope) internal pure returns (bytes calldata) {
+        uint256 start = INPUTS_OFFSET + WORD * numInputs(envelope);
+        uint256 len = uint256(word(envelope, PROOF_LENGTH_OFFSET));
+        return envelope[start:start + len];
+    }
+
+    // Ed25519 public key of the operator that signed the claim hash, zero if unsigned; the
+    // signature itself is checked off-chain, see generate_proof/src/operator.rs
+    function signer(bytes calldata envelope) internal pure returns (bytes32) {
+        if (uint256(word(envelope, SIGNER_LENGTH_OFFSET)) == 0) {
+            return bytes32(0);
+        }
+        uint256 start = INPUTS_OFFSET + WORD * numInputs(envelope)
+            + padded(uint256(word(envelope, PROOF_LENGTH_OFFSET)))
+            + padded(uint256(word(envelope, SCHEMA_LENGTH_OFFSET)));
+        return word(envelope, start);
+    }
+}
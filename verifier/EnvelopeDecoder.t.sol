@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+import "./EnvelopeDecoder.sol";
+
+// Exposes the decoder to envelopes encoded by the Rust prover; the on-chain suite of
+// generate_proof/tests/it/onchain compares every field with the envelope it encoded, so the
+// Rust and Solidity layouts cannot drift apart.
+contract EnvelopeDecoderTest {
+    // Checks the envelope and returns its model id, model commitment, constraint fingerprint,
+    // terms hash, nonce, number of public inputs, keccak256 of the proof and signer
+    function decode(bytes calldata envelope) external pure returns (bytes32[8] memory fields) {
+        EnvelopeDecoder.check(envelope);
+        fields[0] = EnvelopeDecoder.modelId(envelope);
+        fields[1] = EnvelopeDecoder.modelCommitment(envelope);
+        fields[2] = EnvelopeDecoder.constraintFingerprint(envelope);
+        fields[3] = EnvelopeDecoder.termsHash(envelope);
+        fields[4] = EnvelopeDecoder.nonce(envelope);
+        fields[5] = bytes32(EnvelopeDecoder.numInputs(envelope));
+        fields[6] = keccak256(EnvelopeDecoder.proof(envelope));
+        fields[7] = EnvelopeDecoder.signer(envelope);
+    }
+
+    function publicInput(bytes calldata envelope, uint256 i) external pure returns (uint256) {
+        return EnvelopeDecoder.publicInput(envelope, i);
+    }
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+// Fiat-Shamir transcript of generate_proof/src/coin.rs (`KeccakCoin`), for proofs generated
+// with `random_coin = "keccak"`. Every step is one keccak256 over 32-byte words and 8-byte
+// big-endian integers; `KeccakCoinTest` checks the draws pinned by
+// `coin::tests::test_solidity_coin_matches_transcript`.
+library KeccakCoin {
+    // Modulus of the f128 field challenges are drawn from
+    uint256 internal constant MODULUS = 0xffffffffffffffffffffd30000000001;
+    uint256 internal constant MAX_DRAWS = 1000;
+
+    struct Coin {
+        bytes32 state;
+        uint64 counter;
+    }
+
+    function init(uint256[] memory seed) internal pure returns (Coin memory coin) {
+        coin.state = keccak256(abi.encodePacked(seed));
+    }
+
+    // Absorbs a commitment of the proof, e.g. a Merkle root
+    function reseed(Coin memory coin, bytes32 commitment) internal pure {
+        coin.state = keccak256(abi.encodePacked(coin.state, commitment));
+        coin.counter = 0;
+    }
+
+    function next(Coin memory coin) internal pure returns (uint256) {
+        coin.counter += 1;
+        return uint256(keccak256(abi.encodePacked(coin.state, coin.counter)));
+    }
+
+    // A base field element: the low 128 bits of the next word, redrawn until canonical
+    function draw(Coin memory coin) internal pure returns (uint256) {
+        for (uint256 i = 0; i < MAX_DRAWS; i++) {
+            uint256 value = uint128(next(coin));
+            if (value < MODULUS) {
+                return value;
+            }
+        }
+        revert("Failed to draw a field element");
+    }
+
+    // Trailing zero bits of the proof-of-work hash for `nonce`
+    function checkLeadingZeros(Coin memory coin, uint64 nonce) internal pure returns (uint256 zeros) {
+        uint64 value = uint64(uint256(keccak256(abi.encodePacked(coin.state, nonce))));
+        if (value == 0) {
+            return 64;
+        }
+        while (value & 1 == 0) {
+            value >>= 1;
+            zeros++;
+        }
+    }
+
+    // Query positions in a domain of `domainSize` points, a power of two
+    function drawIntegers(Coin memory coin, uint256 count, uint256 domainSize, uint64 nonce)
+        internal
+        pure
+        returns (uint256[] memory values)
+    {
+        require(domainSize & (domainSize - 1) == 0 && count < domainSize, "Invalid query domain");
+        coin.state = keccak256(abi.encodePacked(coin.state, nonce));
+        coin.counter = 0;
+        values = new uint256[](count);
+        for (uint256 i = 0; i < count; i++) {
+            values[i] = uint64(next(coin)) & (domainSize - 1);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+import "./KeccakCoin.sol";
+
+// Replays the transcript of `coin::tests::test_solidity_coin_matches_transcript`, which reads
+// the same constants, so the Rust and Solidity coins are pinned to identical draws.
+contract KeccakCoinTest {
+    using KeccakCoin for KeccakCoin.Coin;
+
+    bytes32 internal constant COMMITMENT = 0xd59bdccbe35361cc073a3510681f3455eece54822fe9cc8f4daf0d37523bb954;
+    uint256 internal constant DRAW_0 = 0x214bf331d691c44cb1ce340e411c18be;
+    uint256 internal constant DRAW_1 = 0x1de70c8f9897b8178fac0574380ae89d;
+    uint64 internal constant POW_NONCE = 3;
+    uint256 internal constant POW_ZEROS = 5;
+    uint64 internal constant QUERY_NONCE = 7;
+    uint256 internal constant QUERY_DOMAIN = 64;
+    uint256 internal constant POSITION_0 = 20;
+    uint256 internal constant POSITION_1 = 49;
+    uint256 internal constant POSITION_2 = 53;
+    uint256 internal constant POSITION_3 = 32;
+
+    function testTranscript() public pure {
+        uint256[] memory seed = new uint256[](3);
+        seed[0] = 1;
+        seed[1] = 2;
+        seed[2] = 3;
+        KeccakCoin.Coin memory coin = KeccakCoin.init(seed);
+        coin.reseed(COMMITMENT);
+        require(coin.draw() == DRAW_0, "draw 0");
+        require(coin.draw() == DRAW_1, "draw 1");
+        require(coin.checkLeadingZeros(POW_NONCE) == POW_ZEROS, "proof of work");
+
+        uint256[] memory positions = coin.drawIntegers(4, QUERY_DOMAIN, QUERY_NONCE);
+        require(positions[0] == POSITION_0, "position 0");
+        require(positions[1] == POSITION_1, "position 1");
+        require(positions[2] == POSITION_2, "position 2");
+        require(positions[3] == POSITION_3, "position 3");
+    }
+}
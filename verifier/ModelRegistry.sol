@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+// Mirrors the local registry in generate_proof/src/registry.rs; calldata for every function
+// is produced by `ModelRegistry::{register,rotate,revoke}_calldata`.
+contract ModelRegistry {
+    struct Model {
+        address owner;
+        bytes32 commitment;
+        uint32 version;
+        bool revoked;
+    }
+
+    mapping(bytes32 => Model) public models;
+
+    event ModelRegistered(bytes32 indexed modelId, bytes32 commitment, address owner);
+    event ModelRotated(bytes32 indexed modelId, bytes32 commitment, uint32 version);
+    event ModelRevoked(bytes32 indexed modelId);
+
+    // modelId is keccak256 of the model name
+    function registerModel(bytes32 modelId, bytes32 commitment) external {
+        require(models[modelId].owner == address(0), "Model already registered");
+        models[modelId] = Model(msg.sender, commitment, 1, false);
+        emit ModelRegistered(modelId, commitment, msg.sender);
+    }
+
+    function rotateModel(bytes32 modelId, bytes32 commitment) external {
+        Model storage model = activeModel(modelId);
+        model.commitment = commitment;
+        model.version += 1;
+        emit ModelRotated(modelId, commitment, model.version);
+    }
+
+    function revokeModel(bytes32 modelId) external {
+        Model storage model = activeModel(modelId);
+        model.revoked = true;
+        emit ModelRevoked(modelId);
+    }
+
+    // Used by verifier contracts to check the commitment carried by a proof envelope
+    function isCurrent(bytes32 modelId, bytes32 commitment) external view returns (bool) {
+        Model storage model = models[modelId];
+        return model.owner != address(0) && !model.revoked && model.commitment == commitment;
+    }
+
+    function activeModel(bytes32 modelId) internal view returns (Model storage model) {
+        model = models[modelId];
+        require(model.owner == msg.sender, "Not the model owner");
+        require(!model.revoked, "Model revoked");
+    }
+}
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+import "./EnvelopeDecoder.sol";
+
+// Checks a single query chunk of an envelope, e.g. the PolkaVM contract built from src/main.rs;
+// mirrors `QueryChunk::verify` in generate_proof/src/optimistic.rs: the openings lead to the
+// roots of the envelope's proof, the position is the `query`-th of its `numQueries` query
+// positions, and the DEEP composition of the rows folds through FRI to the proof's remainder
+interface IQueryVerifier {
+    function verifyQuery(bytes calldata envelope, uint256 numQueries, uint256 query, bytes calldata chunk)
+        external
+        returns (bool);
+}
+
+// Optimistic settlement of envelopes too large to verify in one transaction, see
+// generate_proof/src/optimistic.rs. The poster posts the envelope itself, so the claim is the
+// one the envelope commits to; claims are keyed by poster and envelope hash, so a claim posted
+// with a bogus query root does not block anyone else's. A posted claim is final once
+// `challengePeriod` blocks pass without a successful challenge. A challenge names one query; it
+// is dismissed if anyone shows within `challengePeriod` blocks that the query's chunk is in the
+// posted query root and holds, and the claim is rejected if the chunk fails or nobody responds.
+// Query roots are Merkle trees over keccak256 of the encoded chunks, hashed like ProofAnchor.sol.
+contract OptimisticVerifier {
+    bytes1 internal constant LEAF_PREFIX = 0x00;
+    bytes1 internal constant NODE_PREFIX = 0x01;
+
+    struct Claim {
+        bytes32 queryRoot;
+        uint256 numQueries;
+        uint256 postedAt;
+        // Challenged query plus one, zero while unchallenged
+        uint256 challengedQuery;
+        uint256 challengedAt;
+        bool rejected;
+    }
+
+    IQueryVerifier public immutable verifier;
+    uint256 public immutable challengePeriod;
+
+    // Keyed by `claimKey(poster, envelopeHash)`
+    mapping(bytes32 => Claim) public claims;
+
+    event ClaimPosted(address indexed poster, bytes32 indexed envelopeHash, bytes32 modelId, bytes32 queryRoot);
+    event QueryChallenged(address indexed poster, bytes32 indexed envelopeHash, uint256 query, address challenger);
+    event ChallengeDismissed(address indexed poster, bytes32 indexed envelopeHash, uint256 query);
+    event ClaimRejected(address indexed poster, bytes32 indexed envelopeHash, uint256 query);
+
+    constructor(IQueryVerifier verifier_, uint256 challengePeriod_) {
+        verifier = verifier_;
+        challengePeriod = challengePeriod_;
+    }
+
+    function claimKey(address poster, bytes32 envelopeHash) public pure returns (bytes32) {
+        return keccak256(abi.encode(poster, envelopeHash));
+    }
+
+    function postClaim(bytes calldata envelope, bytes32 queryRoot, uint256 numQueries)
+        external
+        returns (bytes32 envelopeHash)
+    {
+        EnvelopeDecoder.check(envelope);
+        require(numQueries > 0, "No queries");
+        envelopeHash = keccak256(envelope);
+        Claim storage claim = claims[claimKey(msg.sender, envelopeHash)];
+        require(claim.postedAt == 0, "Claim already posted");
+        claim.queryRoot = queryRoot;
+        claim.numQueries = numQueries;
+        claim.postedAt = block.number;
+        emit ClaimPosted(msg.sender, envelopeHash, EnvelopeDecoder.modelId(envelope), queryRoot);
+    }
+
+    function challenge(address poster, bytes32 envelopeHash, uint256 query) external {
+        Claim storage claim = claims[claimKey(poster, envelopeHash)];
+        require(claim.postedAt != 0 && !claim.rejected, "Unknown claim");
+        require(block.number <= claim.postedAt + challengePeriod, "Challenge period over");
+        require(claim.challengedQuery == 0, "Claim already challenged");
+        require(query < claim.numQueries, "Unknown query");
+        claim.challengedQuery = query + 1;
+        claim.challengedAt = block.number;
+        emit QueryChallenged(poster, envelopeHash, query, msg.sender);
+    }
+
+    function respond(
+        address poster,
+        bytes calldata envelope,
+        uint256 query,
+        bytes calldata chunk,
+        bytes32[] calldata siblings
+    ) external {
+        bytes32 envelopeHash = keccak256(envelope);
+        Claim storage claim = claims[claimKey(poster, envelopeHash)];
+        require(claim.challengedQuery == query + 1 && !claim.rejected, "Query not challenged");
+        require(isIncluded(claim.queryRoot, chunk, query, siblings), "Chunk not in query root");
+        if (verifier.verifyQuery(envelope, claim.numQueries, query, chunk)) {
+            claim.challengedQuery = 0;
+            claim.postedAt = claim.challengedAt;
+            emit ChallengeDismissed(poster, envelopeHash, query);
+        } else {
+            claim.rejected = true;
+            emit ClaimRejected(poster, envelopeHash, query);
+        }
+    }
+
+    // Rejects a claim whose challenge went unanswered
+    function timeout(address poster, bytes32 envelopeHash) external {
+        Claim storage claim = claims[claimKey(poster, envelopeHash)];
+        require(claim.challengedQuery != 0 && !claim.rejected, "Query not challenged");
+        require(block.number > claim.challengedAt + challengePeriod, "Response period not over");
+        claim.rejected = true;
+        emit ClaimRejected(poster, envelopeHash, claim.challengedQuery - 1);
+    }
+
+    function isFinal(address poster, bytes32 envelopeHash) external view returns (bool) {
+        Claim storage claim = claims[claimKey(poster, envelopeHash)];
+        return claim.postedAt != 0 && !claim.rejected && claim.challengedQuery == 0
+            && block.number > claim.postedAt + challengePeriod;
+    }
+
+    function isIncluded(bytes32 root, bytes calldata chunk, uint256 index, bytes32[] calldata siblings)
+        public
+        pure
+        returns (bool)
+    {
+        if (index >> siblings.length != 0) {
+            return false;
+        }
+        bytes32 node = keccak256(abi.encodePacked(LEAF_PREFIX, keccak256(chunk)));
+        for (uint256 level = 0; level < siblings.length; level++) {
+            node = (index >> level) & 1 == 0
+                ? keccak256(abi.encodePacked(NODE_PREFIX, node, siblings[level]))
+                : keccak256(abi.encodePacked(NODE_PREFIX, siblings[level], node));
+        }
+        return node == root;
+    }
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+// Records Merkle roots of batches of on-chain envelopes, see generate_proof/src/anchor.rs;
+// calldata for `anchorRoot` is produced by `AnchorBatch::anchor_calldata`. Leaves are
+// keccak256(LEAF_PREFIX || keccak256(envelope)) and nodes keccak256(NODE_PREFIX || left || right),
+// checked against the Rust hashing by `anchor::tests::test_contract_matches_hashing`.
+contract ProofAnchor {
+    bytes1 internal constant LEAF_PREFIX = 0x00;
+    bytes1 internal constant NODE_PREFIX = 0x01;
+
+    // Block number each root was anchored in, zero for unknown roots
+    mapping(bytes32 => uint256) public anchoredAt;
+
+    event RootAnchored(bytes32 indexed root, uint256 count, address anchorer);
+
+    function anchorRoot(bytes32 root, uint256 count) external {
+        require(count > 0, "Empty batch");
+        require(anchoredAt[root] == 0, "Root already anchored");
+        anchoredAt[root] = block.number;
+        emit RootAnchored(root, count, msg.sender);
+    }
+
+    // Whether the envelope is the index-th leaf of the batch anchored as root; siblings start
+    // next to the leaf
+    function isIncluded(bytes32 root, bytes calldata envelope, uint256 index, bytes32[] calldata siblings)
+        external
+        view
+        returns (bool)
+    {
+        if (anchoredAt[root] == 0 || index >> siblings.length != 0) {
+            return false;
+        }
+        bytes32 node = keccak256(abi.encodePacked(LEAF_PREFIX, keccak256(envelope)));
+        for (uint256 level = 0; level < siblings.length; level++) {
+            node = (index >> level) & 1 == 0
+                ? keccak256(abi.encodePacked(NODE_PREFIX, node, siblings[level]))
+                : keccak256(abi.encodePacked(NODE_PREFIX, siblings[level], node));
+        }
+        return node == root;
+    }
+}
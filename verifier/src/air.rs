@@ -0,0 +1,184 @@
+//! The regression AIR of generate_proof/src/regression.rs, as proven under
+//! `DomainSeparatedAir` (generate_proof/src/domain.rs): the same constraints over public
+//! inputs extended by the deployment's tag and the claim's commitments.
+//!
+//! The contract cannot link the standard-library crate, so the constraints are restated here.
+//! generate_proof/tests/contract.rs compares their constraint fingerprint with the prover's.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+
+#[derive(Clone, Debug)]
+pub struct LinearRegressionInputs {
+    pub x_value: BaseElement,
+    pub predicted_y: BaseElement,
+    pub sample_x_values: Vec<BaseElement>,
+    pub sample_y_values: Vec<BaseElement>,
+}
+
+impl LinearRegressionInputs {
+    /// Inverse of [`ToElements::to_elements`]; `None` unless there are as many y as x samples
+    pub fn from_elements(elements: &[BaseElement]) -> Option<Self> {
+        let num_samples = elements.len().checked_sub(2)? / 2;
+        if elements.len() != 2 + 2 * num_samples {
+            return None;
+        }
+        let (samples_x, samples_y) = elements[2..].split_at(num_samples);
+        Some(Self {
+            x_value: elements[0],
+            predicted_y: elements[1],
+            sample_x_values: samples_x.to_vec(),
+            sample_y_values: samples_y.to_vec(),
+        })
+    }
+}
+
+impl ToElements<BaseElement> for LinearRegressionInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        let mut elements = vec![self.x_value, self.predicted_y];
+        elements.extend(&self.sample_x_values);
+        elements.extend(&self.sample_y_values);
+        elements
+    }
+}
+
+/// Public inputs of a claim: every element the prover's transcript absorbed, i.e. the
+/// configuration commitment, domain tag, nonce and terms hash followed by the regression
+/// inputs, see [`crate::envelope::Claim::inputs`], and the regression inputs the AIR reads
+#[derive(Clone, Debug)]
+pub struct ClaimInputs {
+    pub elements: Vec<BaseElement>,
+    pub inner: LinearRegressionInputs,
+}
+
+impl ToElements<BaseElement> for ClaimInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        self.elements.clone()
+    }
+}
+
+// Trace columns: slope, intercept, x, y and the one-hot phase flags sample, prediction, padding
+const TRACE_WIDTH: usize = 7;
+const SAMPLE: usize = 4;
+const PREDICTION: usize = 5;
+const PADDING: usize = 6;
+
+pub struct LinearRegressionAir {
+    context: AirContext<BaseElement>,
+    x_value: BaseElement,
+    predicted_y: BaseElement,
+    sample_x_values: Vec<BaseElement>,
+    sample_y_values: Vec<BaseElement>,
+    num_samples: usize,
+}
+
+impl Air for LinearRegressionAir {
+    type BaseField = BaseElement;
+    type PublicInputs = ClaimInputs;
+
+    fn new(trace_info: TraceInfo, pub_inputs: ClaimInputs, options: ProofOptions) -> Self {
+        let pub_inputs = pub_inputs.inner;
+        assert_eq!(TRACE_WIDTH, trace_info.width());
+        
+        let num_samples = pub_inputs.sample_x_values.len();
+        assert_eq!(num_samples, pub_inputs.sample_y_values.len(), "Sample arrays must have equal length");
+        assert!(num_samples + 1 < trace_info.length(), "The trace needs a padding row after the prediction");
+        
+        // Constraints:
+        // 1. Linear relationship: y = mx + b on sample and prediction rows (degree 2: the
+        //    relation has degree 1 since slope is a constant column, the phase flags add one;
+        //    the prover declares the same, which the constraint fingerprint pins)
+        // 2. Slope consistency (degree 1: next_slope - slope = 0)
+        // 3. Intercept consistency (degree 1: next_intercept - intercept = 0)
+        // 4. Phase flags: booleanity, one-hot sum and ordering sample -> prediction -> padding
+        let mut degrees = vec![
+            TransitionConstraintDegree::new(2), // Linear constraint: (S + P) * (y - mx - b) = 0, m constant
+            TransitionConstraintDegree::new(1), // Slope consistency
+            TransitionConstraintDegree::new(1), // Intercept consistency
+        ];
+        degrees.extend([
+            TransitionConstraintDegree::new(2), // Booleanity of the three flags
+            TransitionConstraintDegree::new(2),
+            TransitionConstraintDegree::new(2),
+            TransitionConstraintDegree::new(1), // One-hot sum
+            TransitionConstraintDegree::new(2), // Ordering of the three phases
+            TransitionConstraintDegree::new(2),
+            TransitionConstraintDegree::new(2),
+        ]);
+        
+        // Assertions for sample points, prediction and the prediction phase
+        let num_assertions = 2 * num_samples + 3;
+        
+        LinearRegressionAir {
+            context: AirContext::new(trace_info, degrees, num_assertions, options),
+            x_value: pub_inputs.x_value,
+            predicted_y: pub_inputs.predicted_y,
+            sample_x_values: pub_inputs.sample_x_values,
+            sample_y_values: pub_inputs.sample_y_values,
+            num_samples,
+        }
+    }
+
+    fn evaluate_transition<E: FieldElement + From<Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic_values: &[E],
+        result: &mut [E],
+    ) {
+        // Extract current state: [slope, intercept, x, y, phase flags]
+        let current = frame.current();
+        let next = frame.next();
+        let slope = current[0];
+        let intercept = current[1];
+        let x = current[2];
+        let y = current[3];
+        let (sample, prediction, padding) = (current[SAMPLE], current[PREDICTION], current[PADDING]);
+        
+        // Constraint 1: Linear relationship y = mx + b, except on padding rows
+        result[0] = (sample + prediction) * (y - slope * x - intercept);
+        
+        // Constraint 2: Slope must remain constant across all steps
+        result[1] = next[0] - slope;
+        
+        // Constraint 3: Intercept must remain constant across all steps  
+        result[2] = next[1] - intercept;
+
+        // Constraint 4: Phase flags are one-hot and only move forward
+        result[3] = sample * (sample - E::ONE);
+        result[4] = prediction * (prediction - E::ONE);
+        result[5] = padding * (padding - E::ONE);
+        result[6] = sample + prediction + padding - E::ONE;
+        result[7] = sample * (next[SAMPLE] + next[PREDICTION] - E::ONE);
+        result[8] = prediction * (next[PREDICTION] + next[PADDING] - E::ONE);
+        result[9] = padding * (next[PADDING] - E::ONE);
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let mut assertions = Vec::new();
+        
+        // Assert that each sample point is correctly represented in the trace
+        for i in 0..self.num_samples {
+            // Assert x value at step i
+            assertions.push(Assertion::single(2, i, self.sample_x_values[i]));
+            // Assert y value at step i  
+            assertions.push(Assertion::single(3, i, self.sample_y_values[i]));
+        }
+        
+        // Assert the final prediction at the prediction step
+        let prediction_step = self.num_samples;
+        assertions.push(Assertion::single(2, prediction_step, self.x_value));
+        assertions.push(Assertion::single(3, prediction_step, self.predicted_y));
+        assertions.push(Assertion::single(PREDICTION, prediction_step, BaseElement::ONE));
+        
+        assertions
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.context
+    }
+}
@@ -0,0 +1,253 @@
+//! Claims in the on-chain envelope encoding of generate_proof/src/serialization.rs, read with
+//! the checks of `EnvelopeDecoder.check`, and the public inputs their proofs were made over.
+//!
+//! The prover extends a claim's inputs with a configuration commitment, the domain tag, the
+//! nonce, the terms hash and the schema commitment (`TaggedInputs` in
+//! generate_proof/src/domain.rs). The contract is built for one deployment: proofs for
+//! [`DOMAIN_TAG`] that commit to their configuration, over the layout [`LAYOUT_ID`] with
+//! Winterfell's default Blake3 random coin and the options of [`expected_options`].
+//! generate_proof's tests/contract.rs runs this module on envelopes of the Rust prover, so the
+//! constants cannot drift from the crate's.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use winterfell::{
+    crypto::{hashers::Blake3_256, DefaultRandomCoin, MerkleTree},
+    math::{fields::f128::BaseElement, FieldElement, StarkField, ToElements},
+    verify, AcceptableOptions, FieldExtension, Proof, ProofOptions,
+};
+// not re-exported by winter-verifier, which wasm-verifier compiles this module against
+use winter_air::BatchingMethod;
+
+use crate::air::{ClaimInputs, LinearRegressionAir, LinearRegressionInputs};
+
+pub const MAGIC: &[u8; 4] = b"STKO";
+pub const VERSION: u8 = 6;
+pub const WORD: usize = 32;
+
+const NUM_INPUTS_OFFSET: usize = 0x80;
+const PROOF_LENGTH_OFFSET: usize = 0xa0;
+const SCHEMA_LENGTH_OFFSET: usize = 0xc0;
+const SIGNER_LENGTH_OFFSET: usize = 0xe0;
+const BEACON_LENGTH_OFFSET: usize = 0x100;
+const TERMS_HASH_OFFSET: usize = 0x120;
+const NONCE_OFFSET: usize = 0x140;
+const INPUTS_OFFSET: usize = 0x160;
+const SIGNER_LENGTH: usize = 96;
+
+/// Deployment the contract accepts proofs of
+pub const DOMAIN_TAG: &str = "stark-framework";
+/// `regression::layout().versioned_id()`
+pub const LAYOUT_ID: &str = "linear-regression/v2@8dcec572";
+/// `regression::layout().hash()`
+pub const LAYOUT_HASH: [u8; 32] = [
+    0x8d, 0xce, 0xc5, 0x72, 0xcf, 0x3d, 0x12, 0xa5, 0xad, 0xe5, 0x05, 0xfe, 0xca, 0x8a, 0x0c, 0x23,
+    0x19, 0x1e, 0x7c, 0xf4, 0x48, 0xd7, 0x31, 0xe5, 0x75, 0x7c, 0x82, 0x5e, 0xcd, 0x4d, 0x7a, 0xf8,
+];
+/// Security the proof options must reach, `DEFAULT_MIN_SECURITY_BITS` of the crate
+pub const MIN_SECURITY_BITS: u32 = 95;
+/// Parameters of `ProverConfig::default()`, the options of the proofs the contract accepts
+const NUM_QUERIES: usize = 32;
+const BLOWUP_FACTOR: usize = 8;
+const FRI_FOLDING_FACTOR: usize = 8;
+const FRI_REMAINDER_MAX_DEGREE: usize = 31;
+
+const CONFIG_DOMAIN: &[u8] = b"stark-framework/prover-config/v1";
+const CONFIG_NAMES: [&str; 4] = [LAYOUT_ID, "f128", "blake3-256", "default"];
+const CONFIG_SECTION: u64 = 0x636f_6e66_6967;
+/// `DataSchema::commitment` hashes the schema's JSON under this domain
+const SCHEMA_DOMAIN: &[u8] = b"stark-framework/schema-json/v1";
+const BYTES_PER_ELEMENT: usize = 15;
+
+type HashFn = Blake3_256<BaseElement>;
+
+/// A claim read from its on-chain envelope
+pub struct Claim<'a> {
+    /// Zero without a nonce
+    pub nonce: [u8; WORD],
+    /// Zero without a terms hash
+    pub terms_hash: [u8; WORD],
+    pub public_inputs: Vec<BaseElement>,
+    /// `Proof::to_bytes()`
+    pub proof: &'a [u8],
+    /// JSON of the claim's schema, empty without one
+    pub schema: &'a [u8],
+}
+
+impl<'a> Claim<'a> {
+    /// Reads `envelope`; `None` if its header, lengths or public inputs are invalid
+    pub fn parse(envelope: &'a [u8]) -> Option<Self> {
+        let header = word(envelope, 0)?;
+        if &header[..4] != MAGIC || header[4] != VERSION || header[5..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        let num_inputs = length(envelope, NUM_INPUTS_OFFSET)?;
+        let proof_len = length(envelope, PROOF_LENGTH_OFFSET)?;
+        let schema_len = length(envelope, SCHEMA_LENGTH_OFFSET)?;
+        let signer_len = length(envelope, SIGNER_LENGTH_OFFSET)?;
+        let beacon_len = length(envelope, BEACON_LENGTH_OFFSET)?;
+        if signer_len != 0 && signer_len != SIGNER_LENGTH {
+            return None;
+        }
+        let proof_start = INPUTS_OFFSET.checked_add(num_inputs.checked_mul(WORD)?)?;
+        let end = [padded(proof_len)?, padded(schema_len)?, signer_len, padded(beacon_len)?]
+            .into_iter()
+            .try_fold(proof_start, usize::checked_add)?;
+        if envelope.len() != end {
+            return None;
+        }
+        let schema_start = proof_start + padded(proof_len)?;
+        let public_inputs = (0..num_inputs)
+            .map(|i| {
+                let value = uint(envelope, INPUTS_OFFSET + WORD * i)?;
+                (value < BaseElement::MODULUS).then(|| BaseElement::new(value))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            nonce: word(envelope, NONCE_OFFSET)?,
+            terms_hash: word(envelope, TERMS_HASH_OFFSET)?,
+            public_inputs,
+            proof: &envelope[proof_start..proof_start + proof_len],
+            schema: &envelope[schema_start..schema_start + schema_len],
+        })
+    }
+
+    /// Public inputs of a proof of this claim made with `options`; `None` unless the claim's
+    /// own inputs are those of a regression
+    pub fn inputs(&self, options: &ProofOptions) -> Option<ClaimInputs> {
+        let inner = LinearRegressionInputs::from_elements(&self.public_inputs)?;
+        let mut elements = vec![BaseElement::from(CONFIG_SECTION)];
+        elements.extend(pack_bytes(&config_commitment(options)));
+        elements.push(BaseElement::from(DOMAIN_TAG.len() as u64));
+        elements.extend(pack_bytes(DOMAIN_TAG.as_bytes()));
+        let sections = [
+            (self.nonce != [0u8; WORD]).then_some(self.nonce),
+            (self.terms_hash != [0u8; WORD]).then_some(self.terms_hash),
+            (!self.schema.is_empty()).then(|| schema_commitment(self.schema)),
+        ];
+        for section in sections {
+            match section {
+                Some(section) => {
+                    elements.push(BaseElement::ONE);
+                    elements.extend(pack_bytes(&section));
+                }
+                None => elements.push(BaseElement::ZERO),
+            }
+        }
+        elements.extend_from_slice(&self.public_inputs);
+        Some(ClaimInputs { elements, inner })
+    }
+}
+
+/// Selector of `verifyEnvelope(bytes)`, the contract's entry point
+pub const VERIFY_ENVELOPE: [u8; 4] = [0xde, 0x27, 0x85, 0xd2];
+
+/// The argument of a call to a function taking one `bytes`, e.g. `verifyEnvelope`
+pub fn bytes_argument(call_data: &[u8]) -> Option<&[u8]> {
+    let args = call_data.get(4..)?;
+    let offset = length(args, 0)?;
+    let len = length(args, offset)?;
+    let start = offset.checked_add(WORD)?;
+    args.get(start..start.checked_add(len)?)
+}
+
+/// Whether `envelope` holds a valid proof of its claim
+pub fn verify_envelope(envelope: &[u8]) -> bool {
+    let Some(claim) = Claim::parse(envelope) else {
+        return false;
+    };
+    let Ok(proof) = Proof::from_bytes(claim.proof) else {
+        return false;
+    };
+    // the configuration commitment is rebuilt from the options the contract expects, not from
+    // those the proof records
+    let options = expected_options();
+    if *proof.options() != options {
+        return false;
+    }
+    let Some(inputs) = claim.inputs(&options) else {
+        return false;
+    };
+    let acceptable = AcceptableOptions::MinConjecturedSecurity(MIN_SECURITY_BITS);
+    verify::<LinearRegressionAir, HashFn, DefaultRandomCoin<HashFn>, MerkleTree<HashFn>>(
+        proof,
+        inputs,
+        &acceptable,
+    )
+    .is_ok()
+}
+
+/// Options of the proofs the contract accepts
+pub fn expected_options() -> ProofOptions {
+    ProofOptions::new(
+        NUM_QUERIES,
+        BLOWUP_FACTOR,
+        0,
+        FieldExtension::None,
+        FRI_FOLDING_FACTOR,
+        FRI_REMAINDER_MAX_DEGREE,
+        BatchingMethod::Linear,
+        BatchingMethod::Linear,
+    )
+}
+
+/// `ConfigCommitment::new` of generate_proof/src/config.rs for this contract's deployment
+fn config_commitment(options: &ProofOptions) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(CONFIG_DOMAIN);
+    hasher.update(&[DOMAIN_TAG.len() as u8]);
+    hasher.update(DOMAIN_TAG.as_bytes());
+    hasher.update(&LAYOUT_HASH);
+    for name in CONFIG_NAMES {
+        hasher.update(&(name.len() as u64).to_le_bytes());
+        hasher.update(name.as_bytes());
+    }
+    for element in ToElements::<BaseElement>::to_elements(options) {
+        hasher.update(&element.as_int().to_le_bytes());
+    }
+    *hasher.finalize().as_bytes()
+}
+
+/// `DataSchema::commitment` of the schema encoded as `json`
+fn schema_commitment(json: &[u8]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(SCHEMA_DOMAIN);
+    hasher.update(json);
+    *hasher.finalize().as_bytes()
+}
+
+/// The bytes packed little-endian, 15 per element
+fn pack_bytes(bytes: &[u8]) -> Vec<BaseElement> {
+    bytes
+        .chunks(BYTES_PER_ELEMENT)
+        .map(|chunk| {
+            let mut bytes = [0u8; 16];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            BaseElement::new(u128::from_le_bytes(bytes))
+        })
+        .collect()
+}
+
+fn word(envelope: &[u8], offset: usize) -> Option<[u8; WORD]> {
+    envelope.get(offset..offset.checked_add(WORD)?)?.try_into().ok()
+}
+
+/// The word at `offset` as an integer; `None` unless it fits into 128 bits
+fn uint(envelope: &[u8], offset: usize) -> Option<u128> {
+    let word = word(envelope, offset)?;
+    if word[..16].iter().any(|&b| b != 0) {
+        return None;
+    }
+    Some(u128::from_be_bytes(word[16..].try_into().ok()?))
+}
+
+/// A byte length, which must leave room for its section
+fn length(envelope: &[u8], offset: usize) -> Option<usize> {
+    let len = uint(envelope, offset)?;
+    (len <= envelope.len() as u128).then_some(len as usize)
+}
+
+fn padded(len: usize) -> Option<usize> {
+    Some(len.checked_add(WORD - 1)? / WORD * WORD)
+}
@@ -0,0 +1,269 @@
+//! A single query of an envelope's proof, for `verifyQuery` of OptimisticVerifier.sol.
+//!
+//! Mirrors `QueryChunk::verify` of generate_proof/src/optimistic.rs: the proof's transcript is
+//! replayed from the envelope as `winterfell::verify` does, and the chunk holds if the
+//! out-of-domain rows satisfy the constraints, the proof of work meets the grinding factor, the
+//! chunk's openings lead to the proof's roots at the query's position, and the DEEP composition
+//! of its rows folds through its FRI layer rows to the proof's remainder. The chunk encoding is
+//! `QueryChunk::to_bytes`. generate_proof's tests/contract.rs runs this module on the chunks of
+//! `ChallengeArtifacts`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use winterfell::{
+    crypto::{hashers::Blake3_256, DefaultRandomCoin, Digest, ElementHasher, Hasher, MerkleTree, RandomCoin},
+    math::{fields::f128::BaseElement, polynom, FieldElement, StarkField, ToElements},
+    AcceptableOptions, Air, EvaluationFrame, FieldExtension, Proof,
+};
+
+use crate::air::LinearRegressionAir;
+use crate::envelope::{expected_options, Claim, MIN_SECURITY_BITS, WORD};
+
+type HashFn = Blake3_256<BaseElement>;
+type Digest32 = <HashFn as Hasher>::Digest;
+
+/// Selector of `verifyQuery(bytes,uint256,uint256,bytes)`
+pub const VERIFY_QUERY: [u8; 4] = [0xcc, 0x17, 0x43, 0x12];
+
+/// Arguments of a `verifyQuery` call: the envelope, the number of queries, the query and the
+/// chunk
+pub fn query_arguments(call_data: &[u8]) -> Option<(&[u8], usize, usize, &[u8])> {
+    let mut head = Words::new(call_data.get(4..)?);
+    let (envelope, num_queries, query, chunk) = (head.uint()?, head.uint()?, head.uint()?, head.uint()?);
+    Some((bytes_at(call_data, envelope)?, num_queries, query, bytes_at(call_data, chunk)?))
+}
+
+fn bytes_at(call_data: &[u8], offset: usize) -> Option<&[u8]> {
+    let args = call_data.get(4..)?;
+    let mut tail = Words::new(args.get(offset..)?);
+    let len = tail.uint()?;
+    let start = offset.checked_add(WORD)?;
+    args.get(start..start.checked_add(len)?)
+}
+
+/// Whether `chunk` is the `query`-th of the `num_queries` queries of the envelope's proof
+pub fn verify_query(envelope: &[u8], num_queries: usize, query: usize, chunk: &[u8]) -> bool {
+    check(envelope, num_queries, query, chunk).is_some()
+}
+
+fn check(envelope: &[u8], num_queries: usize, query: usize, chunk: &[u8]) -> Option<()> {
+    let claim = Claim::parse(envelope)?;
+    let proof = Proof::from_bytes(claim.proof).ok()?;
+    let acceptable = AcceptableOptions::MinConjecturedSecurity(MIN_SECURITY_BITS);
+    ensure(acceptable.validate::<HashFn>(&proof).is_ok())?;
+    ensure(proof.options().field_extension() == FieldExtension::None && !proof.trace_info().is_multi_segment())?;
+    let options = expected_options();
+    ensure(*proof.options() == options)?;
+    let inputs = claim.inputs(&options)?;
+
+    // the transcript, as `Transcript::replay` in generate_proof/src/transcript.rs
+    let mut seed = proof.context.to_elements();
+    seed.extend(inputs.to_elements());
+    let air = LinearRegressionAir::new(proof.trace_info().clone(), inputs, proof.options().clone());
+    let trace_width = air.trace_info().main_trace_width();
+    let num_columns = air.context().num_constraint_composition_columns();
+    let lde_domain_size = air.lde_domain_size();
+    let fri_options = air.options().to_fri_options();
+    let num_fri_layers = fri_options.num_fri_layers(lde_domain_size);
+    let partitions = air.options().partition_options();
+    ensure(
+        partitions.num_partitions::<BaseElement>(trace_width) == 1
+            && partitions.num_partitions::<BaseElement>(num_columns) == 1
+            && proof.fri_proof.num_partitions() == 1,
+    )?;
+
+    let (trace_roots, constraint_root, fri_roots) =
+        proof.commitments.clone().parse::<HashFn>(1, num_fri_layers).ok()?;
+    let (ood_trace, ood_columns) = proof.ood_frame.clone().parse::<BaseElement>(trace_width, 0, num_columns).ok()?;
+    let mut coin = DefaultRandomCoin::<HashFn>::new(&seed);
+    coin.reseed(trace_roots[0]);
+    let constraint_coefficients =
+        air.get_constraint_composition_coefficients::<BaseElement, DefaultRandomCoin<HashFn>>(&mut coin).ok()?;
+    coin.reseed(constraint_root);
+    let z = coin.draw::<BaseElement>().ok()?;
+    coin.reseed(ood_trace.hash::<HashFn>());
+    coin.reseed(ood_columns.hash::<HashFn>());
+    let deep_coefficients =
+        air.get_deep_composition_coefficients::<BaseElement, DefaultRandomCoin<HashFn>>(&mut coin).ok()?;
+    let mut fri_alphas = Vec::with_capacity(fri_roots.len());
+    for root in &fri_roots {
+        coin.reseed(*root);
+        fri_alphas.push(coin.draw::<BaseElement>().ok()?);
+    }
+    ensure(coin.check_leading_zeros(proof.pow_nonce) >= air.options().grinding_factor())?;
+    let mut positions = coin.draw_integers(air.options().num_queries(), lde_domain_size, proof.pow_nonce).ok()?;
+    positions.sort_unstable();
+    positions.dedup();
+    ensure(positions.len() == num_queries && proof.num_unique_queries as usize == num_queries)?;
+
+    // the out-of-domain rows satisfy the constraints
+    let frame = ood_trace.main_frame();
+    let trace_length = air.trace_length();
+    let from_columns = ood_columns
+        .current_row()
+        .iter()
+        .enumerate()
+        .fold(BaseElement::ZERO, |sum, (i, &value)| sum + z.exp_vartime((i * trace_length) as u128) * value);
+    ensure(evaluate_at_ood(&air, &frame, &constraint_coefficients.transition, &constraint_coefficients.boundary, z)
+        == from_columns)?;
+
+    // the chunk's openings
+    let chunk = Chunk::read(chunk)?;
+    ensure(positions.get(query) == Some(&chunk.position))?;
+    ensure(chunk.trace.index == chunk.position && chunk.constraints.index == chunk.position)?;
+    ensure(chunk.trace.verify(&trace_roots[0]) && chunk.constraints.verify(&constraint_root))?;
+    ensure(chunk.fri_layers.len() == num_fri_layers)?;
+    ensure(chunk.fri_layers.iter().zip(&fri_roots).all(|(opening, root)| opening.verify(root)))?;
+
+    // the DEEP composition of the rows, as `winterfell`'s `DeepComposer::compose_columns`
+    let lde_generator = BaseElement::get_root_of_unity(lde_domain_size.ilog2());
+    let domain_offset = air.domain_offset();
+    let x = domain_offset * lde_generator.exp(chunk.position as u128);
+    let (den_z, den_zg) = (x - z, x - z * air.trace_domain_generator());
+    let ood_rows = [[frame.current(), frame.next()], [ood_columns.current_row(), ood_columns.next_row()]];
+    let columns = [
+        (&chunk.trace.values, ood_rows[0], &deep_coefficients.trace),
+        (&chunk.constraints.values, ood_rows[1], &deep_coefficients.constraints),
+    ];
+    let mut numerator = BaseElement::ZERO;
+    for (values, [at_z, at_zg], coefficients) in columns {
+        ensure(values.len() == coefficients.len() && at_z.len() == values.len() && at_zg.len() == values.len())?;
+        for (i, &value) in values.iter().enumerate() {
+            numerator += (value - at_z[i]) * coefficients[i] * den_zg + (value - at_zg[i]) * coefficients[i] * den_z;
+        }
+    }
+    let denominator = den_z * den_zg;
+    ensure(denominator != BaseElement::ZERO)?;
+    let mut evaluation = numerator / denominator;
+
+    // FRI folding of that value, as `winter_fri::FriVerifier::verify`
+    let folding = fri_options.folding_factor();
+    let folding_roots: Vec<BaseElement> =
+        (0..folding).map(|i| lde_generator.exp((lde_domain_size / folding * i) as u128)).collect();
+    let (mut generator, mut position) = (lde_generator, chunk.position);
+    let (mut domain_size, mut max_degree_plus_1) = (lde_domain_size, trace_length);
+    for (layer, &alpha) in chunk.fri_layers.iter().zip(&fri_alphas) {
+        let row_length = domain_size / folding;
+        let folded = position % row_length;
+        ensure(layer.index == folded && layer.values.len() == folding)?;
+        ensure(layer.values[position / row_length] == evaluation && max_degree_plus_1 % folding == 0)?;
+        let xe = generator.exp(folded as u128) * domain_offset;
+        let xs: Vec<BaseElement> = folding_roots.iter().map(|&root| xe * root).collect();
+        evaluation = polynom::eval(&polynom::interpolate(&xs, &layer.values, false), alpha);
+        generator = generator.exp(folding as u128);
+        (position, domain_size, max_degree_plus_1) = (folded, row_length, max_degree_plus_1 / folding);
+    }
+    let remainder: Vec<BaseElement> = proof.fri_proof.parse_remainder().ok()?;
+    let x = domain_offset * generator.exp(position as u128);
+    ensure(remainder.len() <= max_degree_plus_1)?;
+    ensure(remainder.iter().fold(BaseElement::ZERO, |acc, &coefficient| acc * x + coefficient) == evaluation)
+}
+
+fn ensure(condition: bool) -> Option<()> {
+    condition.then_some(())
+}
+
+/// The constraint composition polynomial at `z` evaluated from the trace frame, as the
+/// verifier does
+fn evaluate_at_ood(
+    air: &LinearRegressionAir,
+    frame: &EvaluationFrame<BaseElement>,
+    transition_coefficients: &[BaseElement],
+    boundary_coefficients: &[BaseElement],
+    z: BaseElement,
+) -> BaseElement {
+    let periodic_values: Vec<BaseElement> = air
+        .get_periodic_column_polys()
+        .iter()
+        .map(|poly| polynom::eval(poly, z.exp_vartime((air.trace_length() / poly.len()) as u128)))
+        .collect();
+    let transition = air.get_transition_constraints(transition_coefficients);
+    let mut evaluations = vec![BaseElement::ZERO; transition.num_main_constraints()];
+    air.evaluate_transition(frame, &periodic_values, &mut evaluations);
+    let mut composition = transition.combine_evaluations::<BaseElement>(&evaluations, &[], z);
+    for group in air.get_boundary_constraints(None, boundary_coefficients).main_constraints() {
+        composition += group.evaluate_at(frame.current(), z);
+    }
+    composition
+}
+
+/// ABI words read in order
+struct Words<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Words<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn word(&mut self) -> Option<[u8; WORD]> {
+        let word = self.bytes.get(self.offset..self.offset.checked_add(WORD)?)?.try_into().ok()?;
+        self.offset += WORD;
+        Some(word)
+    }
+
+    /// The next word as an integer; `None` unless it fits into 128 bits
+    fn int(&mut self) -> Option<u128> {
+        let word = self.word()?;
+        if word[..16].iter().any(|&b| b != 0) {
+            return None;
+        }
+        Some(u128::from_be_bytes(word[16..].try_into().ok()?))
+    }
+
+    /// The next word as a length, offset or index
+    fn uint(&mut self) -> Option<usize> {
+        usize::try_from(self.int()?).ok()
+    }
+}
+
+/// A row of a committed table, its root and its Merkle path, sibling of the leaf first
+struct Opening {
+    root: [u8; WORD],
+    index: usize,
+    values: Vec<BaseElement>,
+    path: Vec<Digest32>,
+}
+
+impl Opening {
+    fn read(words: &mut Words) -> Option<Self> {
+        let root = words.word()?;
+        let index = words.uint()?;
+        let values = (0..words.uint()?)
+            .map(|_| words.int().filter(|&value| value < BaseElement::MODULUS).map(BaseElement::new))
+            .collect::<Option<Vec<_>>>()?;
+        let path = (0..words.uint()?).map(|_| words.word().map(Digest32::new)).collect::<Option<Vec<_>>>()?;
+        Some(Self { root, index, values, path })
+    }
+
+    /// Whether the path leads from the row to `root`, which must be the chunk's own root
+    fn verify(&self, root: &Digest32) -> bool {
+        root.as_bytes() == self.root
+            && !self.path.is_empty()
+            && self.index >> self.path.len() == 0
+            && MerkleTree::<HashFn>::verify(*root, self.index, HashFn::hash_elements(&self.values), &self.path).is_ok()
+    }
+}
+
+/// `QueryChunk::to_bytes`
+struct Chunk {
+    position: usize,
+    trace: Opening,
+    constraints: Opening,
+    fri_layers: Vec<Opening>,
+}
+
+impl Chunk {
+    fn read(bytes: &[u8]) -> Option<Self> {
+        let mut words = Words::new(bytes);
+        let position = words.uint()?;
+        let num_fri_layers = words.uint()?;
+        let trace = Opening::read(&mut words)?;
+        let constraints = Opening::read(&mut words)?;
+        let fri_layers = (0..num_fri_layers).map(|_| Opening::read(&mut words)).collect::<Option<Vec<_>>>()?;
+        (words.offset == bytes.len()).then_some(Self { position, trace, constraints, fri_layers })
+    }
+}
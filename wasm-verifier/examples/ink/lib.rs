@@ -0,0 +1,88 @@
+//! ink! contract verifying linear regression envelopes on Substrate chains with
+//! pallet-contracts. It links `stark-wasm-verifier` without `std`, so it accepts on-chain
+//! envelopes for the deployment of the PolkaVM contract in `verifier/`, and records the hash
+//! of every envelope that verified.
+//!
+//! Build with `cargo contract build --release`; the verifier adds about as much code as the
+//! `no_std` artifact checked by `scripts/check-size.sh`.
+
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod stark_verifier {
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+
+    #[ink(event)]
+    pub struct EnvelopeVerified {
+        #[ink(topic)]
+        envelope_hash: [u8; 32],
+        caller: AccountId,
+    }
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct StarkVerifier {
+        verified: Mapping<[u8; 32], ()>,
+    }
+
+    impl StarkVerifier {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Verifies the on-chain `envelope` and records it if it is valid
+        #[ink(message)]
+        pub fn verify(&mut self, envelope: Vec<u8>) -> bool {
+            let valid = stark_wasm_verifier::verify_on_chain(&envelope);
+            if valid {
+                let envelope_hash = hash(&envelope);
+                self.verified.insert(envelope_hash, &());
+                self.env().emit_event(EnvelopeVerified { envelope_hash, caller: self.env().caller() });
+            }
+            valid
+        }
+
+        /// Whether `envelope` has verified before
+        #[ink(message)]
+        pub fn is_verified(&self, envelope: Vec<u8>) -> bool {
+            self.verified.contains(hash(&envelope))
+        }
+    }
+
+    fn hash(bytes: &[u8]) -> [u8; 32] {
+        let mut output = [0u8; 32];
+        ink::env::hash_bytes::<ink::env::hash::Sha2x256>(bytes, &mut output);
+        output
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// The on-chain envelope proven for the deployment in `test-vectors/envelopes.json`
+        fn deployment_envelope() -> Vec<u8> {
+            let vectors: serde_json::Value =
+                serde_json::from_str(include_str!("../../../test-vectors/envelopes.json")).unwrap();
+            let vector = vectors["envelopes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|vector| vector["domain"] == stark_wasm_verifier::DOMAIN_TAG)
+                .unwrap();
+            let hex = vector["on_chain"].as_str().unwrap().strip_prefix("0x").unwrap();
+            (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+        }
+
+        #[ink::test]
+        fn test_records_verified_envelopes() {
+            let envelope = deployment_envelope();
+            let mut contract = StarkVerifier::new();
+            assert!(!contract.verify(envelope[1..].to_vec()));
+            assert!(!contract.is_verified(envelope.clone()));
+            assert!(contract.verify(envelope.clone()));
+            assert!(contract.is_verified(envelope));
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! NEAR contract verifying linear regression envelopes. It records the claim hash of every
+//! envelope that verified, so other contracts can look a prediction up by its claim.
+//!
+//! Build with `cargo near build` and call `verify` with the base64 of a compact or on-chain
+//! envelope, e.g. `near call <account> verify '{"envelope": "..."}'`.
+
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::store::LookupSet;
+use near_sdk::{log, near, CryptoHash, PanicOnDefault};
+use stark_wasm_verifier::VerificationPolicy;
+
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct Verifier {
+    verified: LookupSet<CryptoHash>,
+}
+
+#[near]
+impl Verifier {
+    #[init]
+    pub fn new() -> Self {
+        Self { verified: LookupSet::new(b"v") }
+    }
+
+    /// Verifies `envelope` under the default policy and records its claim if it is valid
+    pub fn verify(&mut self, envelope: Base64VecU8) -> bool {
+        let Some(claim_hash) = stark_wasm_verifier::verified_claim(&envelope.0, &VerificationPolicy::default()) else {
+            return false;
+        };
+        self.verified.insert(claim_hash);
+        log!("verified claim {}", hex(&claim_hash));
+        true
+    }
+
+    /// Whether an envelope with this claim hash has verified before
+    pub fn is_verified(&self, claim_hash: CryptoHash) -> bool {
+        self.verified.contains(&claim_hash)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,22 @@
+#!/usr/bin/env sh
+# Builds the standalone verifier with and without `std` and checks both against the size
+# budgets in tests/size_budget.rs:
+#
+#   target/wasm32-unknown-unknown/release/stark_wasm_verifier.wasm
+#   target/no-std/wasm32-unknown-unknown/release/stark_wasm_verifier.wasm
+#
+# Runs wasm-opt over the artifacts first when it is installed. Needs the wasm32 target
+# (`rustup target add wasm32-unknown-unknown`).
+set -eu
+
+cd "$(dirname "$0")/.."
+cargo rustc --release --lib --target wasm32-unknown-unknown --features standalone --crate-type cdylib
+cargo rustc --release --lib --target wasm32-unknown-unknown --no-default-features --features standalone \
+    --target-dir target/no-std --crate-type cdylib
+artifact="$PWD/target/wasm32-unknown-unknown/release/stark_wasm_verifier.wasm"
+no_std_artifact="$PWD/target/no-std/wasm32-unknown-unknown/release/stark_wasm_verifier.wasm"
+if command -v wasm-opt > /dev/null; then
+    wasm-opt -Oz --strip-debug "$artifact" -o "$artifact"
+    wasm-opt -Oz --strip-debug "$no_std_artifact" -o "$no_std_artifact"
+fi
+WASM_ARTIFACT="$artifact" WASM_NO_STD_ARTIFACT="$no_std_artifact" cargo test --test size_budget -- --ignored
@@ -0,0 +1,143 @@
+//! Verification-only build of the proof envelopes for WASM contract runtimes.
+//!
+//! With the default `std` feature the crate links `linear_regression` with
+//! `default-features = false, features = ["verifier"]`, so it carries the AIRs and the envelope
+//! checks of `generate_proof` and nothing of the prover. [`verify`] decodes a compact or
+//! on-chain envelope and runs [`verify_linear`](linear_regression::session::verify_linear) on
+//! it, the path the FFI and the CLI verify with: the STARK proof over the tagged public inputs,
+//! the domain, random coin and configuration commitment, the signer and the schema. Contracts
+//! that bring their own allocator (NEAR) depend on it as a library, see `examples/near`.
+//!
+//! With `default-features = false` the crate is `no_std` and depends only on `winter-verifier`,
+//! `blake3` and the envelope decoding of the PolkaVM contract in `verifier/src`, which it
+//! compiles in. [`verify_on_chain`], available with `std` too, then checks on-chain envelopes
+//! for the deployment that contract is built for; ink! contracts link the crate this way, see
+//! `examples/ink`.
+//!
+//! With the `standalone` feature the crate also builds into a self-contained `.wasm` exporting
+//! `stark_alloc` and `stark_verify`, see [`standalone`].
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+// the contract's modules reach the verifier half of Winterfell under its usual paths
+extern crate winter_verifier as winterfell;
+
+// shared with the PolkaVM contract, which uses more of them than this crate
+#[allow(dead_code)]
+#[path = "../../verifier/src/air.rs"]
+mod air;
+#[allow(dead_code)]
+#[path = "../../verifier/src/envelope.rs"]
+mod envelope;
+
+#[cfg(feature = "standalone")]
+pub mod standalone;
+
+#[cfg(feature = "std")]
+use linear_regression::envelope::ProofEnvelope;
+#[cfg(feature = "std")]
+use linear_regression::serialization::Encoding;
+#[cfg(feature = "std")]
+use linear_regression::session::verify_linear;
+
+#[cfg(feature = "std")]
+pub use linear_regression::config::VerificationPolicy;
+
+pub use envelope::DOMAIN_TAG;
+
+/// Whether the on-chain `envelope` verifies for the [`DOMAIN_TAG`] deployment with the default
+/// random coin and prover configuration, the checks of the PolkaVM contract
+pub fn verify_on_chain(envelope: &[u8]) -> bool {
+    envelope::verify_envelope(envelope)
+}
+
+/// Decodes a compact or on-chain envelope
+#[cfg(feature = "std")]
+pub fn decode(envelope: &[u8]) -> Option<ProofEnvelope> {
+    Encoding::detect(envelope)?.decode(envelope).ok()
+}
+
+/// Whether `envelope` decodes and verifies under the default policy
+#[cfg(feature = "std")]
+pub fn verify(envelope: &[u8]) -> bool {
+    verify_with_policy(envelope, &VerificationPolicy::default())
+}
+
+/// Whether `envelope` decodes and verifies under `policy`, e.g. for a deployment's domain
+#[cfg(feature = "std")]
+pub fn verify_with_policy(envelope: &[u8], policy: &VerificationPolicy) -> bool {
+    verified_claim(envelope, policy).is_some()
+}
+
+/// Claim hash of `envelope` under `policy`'s domain if it decodes and verifies, the key a
+/// contract records verified claims under
+#[cfg(feature = "std")]
+pub fn verified_claim(envelope: &[u8], policy: &VerificationPolicy) -> Option<[u8; 32]> {
+    let envelope = decode(envelope)?;
+    verify_linear(&envelope, policy).ok()?;
+    Some(envelope.claim_hash(&policy.domain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// The envelopes of `test-vectors/envelopes.json`
+    fn vectors() -> Vec<Value> {
+        let vectors: Value = serde_json::from_str(include_str!("../../test-vectors/envelopes.json")).unwrap();
+        vectors["envelopes"].as_array().unwrap().clone()
+    }
+
+    /// The bytes of a `0x`-prefixed hex string of the vectors
+    fn bytes(vector: &Value, encoding: &str) -> Vec<u8> {
+        let hex = vector[encoding].as_str().unwrap().strip_prefix("0x").unwrap();
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_verifies_on_chain_envelopes_of_the_deployment() {
+        for vector in vectors() {
+            let envelope = bytes(&vector, "on_chain");
+            let deployed = vector["domain"] == DOMAIN_TAG;
+            assert_eq!(verify_on_chain(&envelope), deployed, "{}", vector["name"]);
+
+            // predicted y one more than proven, the first word after x
+            let mut tampered = envelope.clone();
+            tampered[0x160 + 32 + 31] ^= 1;
+            assert!(!verify_on_chain(&tampered));
+            assert!(!verify_on_chain(&envelope[1..]));
+            assert!(!verify_on_chain(&bytes(&vector, "compact")));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verifies_prover_envelopes() {
+        use linear_regression::abi::to_hex;
+        use linear_regression::domain::DomainTag;
+
+        for vector in vectors() {
+            let policy = VerificationPolicy {
+                domain: DomainTag::new(vector["domain"].as_str().unwrap()).unwrap(),
+                random_coin: vector["coin"].as_str().unwrap().parse().unwrap(),
+                ..Default::default()
+            };
+            for encoding in ["compact", "on_chain"] {
+                let envelope = bytes(&vector, encoding);
+                let claim_hash = verified_claim(&envelope, &policy).map(|hash| to_hex(&hash));
+                assert_eq!(claim_hash.as_deref(), vector["claim_hash"].as_str(), "{encoding}");
+                // the default policy is the deployment's
+                assert_eq!(verify(&envelope), vector["domain"] == DOMAIN_TAG, "{encoding}");
+
+                // predicted y one more than proven
+                let mut tampered = decode(&envelope).unwrap();
+                tampered.public_inputs[1] += 1u64.into();
+                let tampered = Encoding::detect(&envelope).unwrap().encode(&tampered);
+                assert!(!verify_with_policy(&tampered, &policy), "{encoding}");
+                assert!(!verify_with_policy(&envelope[1..], &policy), "{encoding}");
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Runtime for a self-contained `.wasm`: a bump allocator over a static arena and two
+//! exports. Panics abort, which traps, as the release profile sets `panic = "abort"`.
+//!
+//! A host writes the envelope into a buffer from `stark_alloc`, then calls `stark_verify`,
+//! which returns 1 for an envelope that verifies under the default policy and 0 otherwise.
+//! Without `std` it takes on-chain envelopes only, checked by [`verify_on_chain`](crate::verify_on_chain),
+//! and the module also brings the panic handler.
+//! Each `stark_alloc` call starts a new verification and discards everything allocated
+//! before. Only built for `wasm32`, since it would replace the allocator of a native test
+//! binary.
+
+#![cfg(target_arch = "wasm32")]
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+
+/// Arena for the envelope buffer and all allocations of one verification
+pub const ARENA_BYTES: usize = 4 << 20;
+
+struct Bump {
+    arena: UnsafeCell<[u8; ARENA_BYTES]>,
+    next: UnsafeCell<usize>,
+}
+
+// wasm32 contracts run single-threaded
+unsafe impl Sync for Bump {}
+
+impl Bump {
+    fn reset(&self) {
+        // SAFETY: single-threaded, and no allocation outlives a verification
+        unsafe { *self.next.get() = 0 };
+    }
+}
+
+unsafe impl GlobalAlloc for Bump {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: single-threaded; the arena pointer and offset stay in bounds below
+        unsafe {
+            let base = self.arena.get() as usize;
+            let start = (base + *self.next.get() + layout.align() - 1) & !(layout.align() - 1);
+            let end = start - base + layout.size();
+            if end > ARENA_BYTES {
+                return core::ptr::null_mut();
+            }
+            *self.next.get() = end;
+            start as *mut u8
+        }
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[global_allocator]
+static ALLOCATOR: Bump = Bump { arena: UnsafeCell::new([0; ARENA_BYTES]), next: UnsafeCell::new(0) };
+
+/// Resets the arena and returns a buffer of `len` bytes for the envelope, or null
+#[no_mangle]
+pub extern "C" fn stark_alloc(len: usize) -> *mut u8 {
+    ALLOCATOR.reset();
+    // SAFETY: the layout has a non-zero alignment and is only used to reserve arena space
+    unsafe { ALLOCATOR.alloc(Layout::from_size_align_unchecked(len, 16)) }
+}
+
+/// Verifies the `len` envelope bytes at `envelope_ptr`.
+///
+/// # Safety
+///
+/// The range must be readable, normally because it lies in a buffer from `stark_alloc`.
+#[no_mangle]
+pub unsafe extern "C" fn stark_verify(envelope_ptr: *const u8, len: usize) -> i32 {
+    // SAFETY: guaranteed by the caller
+    let envelope = unsafe { core::slice::from_raw_parts(envelope_ptr, len) };
+    #[cfg(feature = "std")]
+    return crate::verify(envelope) as i32;
+    #[cfg(not(feature = "std"))]
+    return crate::verify_on_chain(envelope) as i32;
+}
+
+#[cfg(not(feature = "std"))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
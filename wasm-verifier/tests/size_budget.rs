@@ -0,0 +1,34 @@
+//! Size budgets of the standalone artifacts. NEAR stakes storage for every byte of contract
+//! code, so the release `.wasm` must stay below `SIZE_BUDGET_BYTES`, and the `no_std` build,
+//! which leaves `linear_regression` out, below `NO_STD_SIZE_BUDGET_BYTES`.
+//!
+//! Ignored by default because they need the wasm32 builds; `scripts/check-size.sh` builds the
+//! artifacts and runs them.
+
+use std::path::PathBuf;
+
+const SIZE_BUDGET_BYTES: u64 = 512 * 1024;
+const NO_STD_SIZE_BUDGET_BYTES: u64 = 256 * 1024;
+
+/// Checks the artifact in `var`, or else under `target_dir`, against `budget`
+fn check_artifact(var: &str, target_dir: &str, budget: u64) {
+    let path = std::env::var_os(var).map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join(target_dir)
+            .join("wasm32-unknown-unknown/release/stark_wasm_verifier.wasm")
+    });
+    let size = std::fs::metadata(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display())).len();
+    assert!(size <= budget, "{} is {size} bytes, over the budget of {budget}", path.display());
+}
+
+#[test]
+#[ignore = "needs the wasm32 artifact, run scripts/check-size.sh"]
+fn test_artifact_within_size_budget() {
+    check_artifact("WASM_ARTIFACT", "target", SIZE_BUDGET_BYTES);
+}
+
+#[test]
+#[ignore = "needs the wasm32 artifact, run scripts/check-size.sh"]
+fn test_no_std_artifact_within_size_budget() {
+    check_artifact("WASM_NO_STD_ARTIFACT", "target/no-std", NO_STD_SIZE_BUDGET_BYTES);
+}